@@ -0,0 +1,54 @@
+//! Carve throughput over a synthetic image, read into memory versus
+//! memory-mapped. Run with `cargo bench --bench carve`; `JFP_BENCH_BYTES`
+//! sets the image size (1 GiB by default).
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+
+use jobfileparser::{carve, Input, JobBuilder};
+
+fn main() {
+    let size: usize = std::env::var("JFP_BENCH_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1 << 30);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("image.bin");
+
+    // Mostly pseudo-random noise, with a job every 64 MiB.
+    let job = JobBuilder::new("C:\\Windows\\System32\\cmd.exe").build().unwrap().to_bytes();
+    let mut out = BufWriter::new(File::create(&path).unwrap());
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut chunk = vec![0u8; 1 << 20];
+    let mut written = 0;
+    while written < size {
+        for byte in chunk.iter_mut() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            *byte = state as u8;
+        }
+        if written % (64 << 20) == 0 {
+            chunk[..job.len()].copy_from_slice(&job);
+        }
+        let len = chunk.len().min(size - written);
+        out.write_all(&chunk[..len]).unwrap();
+        written += len;
+    }
+    out.flush().unwrap();
+    drop(out);
+
+    let mib = size as f64 / (1 << 20) as f64;
+    let start = Instant::now();
+    let found = carve(&fs::read(&path).unwrap()).len();
+    let read = start.elapsed();
+    println!("read:   {} found, {:.2?} ({:.0} MiB/s)", found, read, mib / read.as_secs_f64());
+
+    let start = Instant::now();
+    let input = Input::map(&path).unwrap();
+    assert!(input.is_mapped());
+    let found = carve(&input).len();
+    let mapped = start.elapsed();
+    println!("mapped: {} found, {:.2?} ({:.0} MiB/s)", found, mapped, mib / mapped.as_secs_f64());
+}
@@ -0,0 +1,95 @@
+//! Startup of a run that parses one job against many rules, read from their
+//! files versus from a bundle written by `compile-rules`. Run with
+//! `cargo bench --bench rules_bundle --features cli`; `JFP_BENCH_RULES`
+//! sets the number of rules (5000 by default) and `JFP_BENCH_RUNS` the
+//! number of runs timed each way (20 by default). Rules testing strings and
+//! rules with regexes are timed apart, as a bundle still compiles regexes.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use jobfileparser::JobBuilder;
+
+const BINARY: &str = env!("CARGO_BIN_EXE_jobfileparser");
+
+fn main() {
+    let var = |name: &str, default: usize| {
+        std::env::var(name).ok().and_then(|s| s.parse().ok()).unwrap_or(default)
+    };
+    let (count, runs) = (var("JFP_BENCH_RULES", 5000), var("JFP_BENCH_RUNS", 20));
+    let dir = tempfile::tempdir().unwrap();
+    let job = dir.path().join("a.job");
+    fs::write(&job, JobBuilder::new("C:\\Users\\Public\\stage7.exe").build().unwrap().to_bytes()).unwrap();
+    println!("{} rules, {} runs each", count, runs);
+
+    // Indicators as exact strings, and as patterns.
+    let strings = |rule: usize| {
+        format!("{{ field = \"name\", contains = \"\\\\stage{}.exe\" }}", rule)
+    };
+    let regexes = |rule: usize| {
+        format!("{{ field = \"name\", regex = '(?i)\\\\stage{}\\.(exe|dll)$' }}", rule)
+    };
+    let kinds: [(&str, &dyn Fn(usize) -> String); 2] =
+        [("strings", &strings), ("regexes", &regexes)];
+    for (kind, test) in kinds {
+        let rules = dir.path().join(kind);
+        write_rules(&rules, count, test);
+        let bundle = dir.path().join(format!("{}.bundle", kind));
+        let status = Command::new(BINARY)
+            .args(["compile-rules", "--rules-dir"])
+            .arg(&rules)
+            .arg("-O")
+            .arg(&bundle)
+            .stdout(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let sources = time(runs, &job, "--rules-dir", &rules);
+        let bundled = time(runs, &job, "--rules-bundle", &bundle);
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        println!(
+            "{}: --rules-dir {:.1} ms, --rules-bundle {:.1} ms per run ({:.1}x)",
+            kind,
+            ms(sources),
+            ms(bundled),
+            ms(sources) / ms(bundled)
+        );
+    }
+}
+
+/// A hundred rules per file, as an indicator feed turned into rules might
+/// be: `test` of the program and a test of the author each.
+fn write_rules(dir: &Path, count: usize, test: &dyn Fn(usize) -> String) {
+    fs::create_dir(dir).unwrap();
+    for file in 0..count.div_ceil(100) {
+        let mut text = String::new();
+        for rule in file * 100..((file + 1) * 100).min(count) {
+            text.push_str(&format!(
+                "[[rule]]\nname = \"ioc-{}\"\nscore = 50\nwhen.all = [\n    {},\n    \
+                 {{ not = {{ field = \"author\", starts_with = \"NT AUTHORITY\" }} }},\n]\n",
+                rule,
+                test(rule)
+            ));
+        }
+        fs::write(dir.join(format!("feed{:03}.toml", file)), text).unwrap();
+    }
+}
+
+/// How long parsing `job` with `option path` takes, on average.
+fn time(runs: usize, job: &Path, option: &str, path: &Path) -> Duration {
+    let start = Instant::now();
+    for _ in 0..runs {
+        let output = Command::new(BINARY)
+            .args(["parse", "-o", "jsonl", option])
+            .arg(path)
+            .arg(job)
+            .output()
+            .unwrap();
+        // The rule for stage7.exe matches, so each run exits with 3.
+        assert_eq!(output.status.code(), Some(3));
+    }
+    start.elapsed() / runs as u32
+}
@@ -0,0 +1,25 @@
+# Writes the Microsoft tasks of this machine in the format of
+# microsoft-tasks.tsv, one line per action, for merging into it. Run on a
+# clean install of each Windows version, passing the version as it should
+# appear in the windows column:
+#
+#   .\export-microsoft-tasks.ps1 -Windows 11 > windows-11.tsv
+
+param([Parameter(Mandatory)] [string] $Windows)
+
+foreach ($task in Get-ScheduledTask -TaskPath '\Microsoft\*') {
+    $uri = $task.TaskPath + $task.TaskName
+    foreach ($action in $task.Actions) {
+        if ($action.CimClass.CimClassName -eq 'MSFT_TaskComHandlerAction') {
+            $columns = @($uri, $Windows, '{' + $action.ClassId.Trim('{}').ToUpper() + '}')
+        } elseif ($action.Execute) {
+            $columns = @($uri, $Windows, $action.Execute.Trim('"'))
+            if ($action.Arguments) {
+                $columns += $action.Arguments
+            }
+        } else {
+            continue
+        }
+        $columns -join "`t"
+    }
+}
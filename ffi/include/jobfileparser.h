@@ -0,0 +1,73 @@
+#ifndef JOBFILEPARSER_H
+#define JOBFILEPARSER_H
+
+/* Generated by cbindgen from ffi/src/lib.rs. Do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * The call succeeded.
+ */
+#define JFP_OK 0
+
+/**
+ * A required pointer argument was null.
+ */
+#define JFP_ERR_NULL_ARGUMENT -1
+
+/**
+ * The input could not be parsed.
+ */
+#define JFP_ERR_PARSE -2
+
+/**
+ * The parsed artifact could not be serialized.
+ */
+#define JFP_ERR_SERIALIZE -3
+
+/**
+ * The parser panicked; the message holds the panic payload.
+ */
+#define JFP_ERR_PANIC -4
+
+/**
+ * Parses a binary `.job` file held in `data[0..len]`.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes (or be null with `len == 0`) and
+ * `json_out` must be a valid pointer to writable storage.
+ */
+int32_t jfp_parse_job(const uint8_t *data, size_t len, char **json_out);
+
+/**
+ * Parses a task XML document held in `data[0..len]`. A byte order mark
+ * selects the encoding. Input without one is decoded in the encoding its
+ * XML declaration names, or as UTF-16LE, like the files Task Scheduler
+ * writes, when it does not start with a declaration in ASCII.
+ *
+ * # Safety
+ *
+ * Same requirements as `jfp_parse_job`.
+ */
+int32_t jfp_parse_task_xml(const uint8_t *data, size_t len, char **json_out);
+
+/**
+ * Releases a string returned by this library. Passing null is a no-op.
+ *
+ * # Safety
+ *
+ * `s` must be null or a pointer obtained from this library that has not
+ * already been freed.
+ */
+void jfp_free_string(char *s);
+
+/**
+ * Returns the message for the most recent failure on the calling thread, or
+ * null if the last call succeeded. The pointer stays valid until the next
+ * call into this library on the same thread and must not be freed.
+ */
+const char *jfp_last_error_message(void);
+
+#endif  /* JOBFILEPARSER_H */
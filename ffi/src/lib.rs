@@ -0,0 +1,141 @@
+//! C ABI for embedding the job file parsers in non-Rust tooling.
+//!
+//! Every entry point returns `JFP_OK` and stores a UTF-8 JSON document in
+//! `*json_out`, or returns one of the negative `JFP_ERR_*` codes and records a
+//! message for `jfp_last_error_message`. Strings handed out by this library
+//! must be released with `jfp_free_string`. Panics never cross the boundary.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use jobfileparser::{Job, ParsedArtifact, Task};
+
+/// The call succeeded.
+pub const JFP_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const JFP_ERR_NULL_ARGUMENT: i32 = -1;
+/// The input could not be parsed.
+pub const JFP_ERR_PARSE: i32 = -2;
+/// The parsed artifact could not be serialized.
+pub const JFP_ERR_SERIALIZE: i32 = -3;
+/// The parser panicked; the message holds the panic payload.
+pub const JFP_ERR_PANIC: i32 = -4;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|m| CString::new(m.replace('\0', "")).unwrap_or_default());
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("parser panicked: {}", message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("parser panicked: {}", message)
+    } else {
+        "parser panicked".to_string()
+    }
+}
+
+fn to_json(artifact: &ParsedArtifact) -> Result<String, (i32, String)> {
+    serde_json::to_string(artifact).map_err(|e| (JFP_ERR_SERIALIZE, e.to_string()))
+}
+
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], (i32, String)> {
+    if data.is_null() {
+        if len == 0 {
+            return Ok(&[]);
+        }
+        return Err((JFP_ERR_NULL_ARGUMENT, "data is null".to_string()));
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+fn run<F>(json_out: *mut *mut c_char, parse: F) -> i32
+where
+    F: FnOnce() -> Result<String, (i32, String)>,
+{
+    if json_out.is_null() {
+        set_last_error(Some("json_out is null".to_string()));
+        return JFP_ERR_NULL_ARGUMENT;
+    }
+    // SAFETY: checked for null above; the caller guarantees it is writable.
+    unsafe { *json_out = ptr::null_mut() };
+
+    let result = match panic::catch_unwind(AssertUnwindSafe(parse)) {
+        Ok(result) => result,
+        Err(payload) => Err((JFP_ERR_PANIC, panic_message(payload.as_ref()))),
+    };
+    match result.and_then(|json| CString::new(json).map_err(|e| (JFP_ERR_SERIALIZE, e.to_string()))) {
+        Ok(json) => {
+            set_last_error(None);
+            // SAFETY: as above.
+            unsafe { *json_out = json.into_raw() };
+            JFP_OK
+        }
+        Err((code, message)) => {
+            set_last_error(Some(message));
+            code
+        }
+    }
+}
+
+/// Parses a binary `.job` file held in `data[0..len]`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes (or be null with `len == 0`) and
+/// `json_out` must be a valid pointer to writable storage.
+#[no_mangle]
+pub unsafe extern "C" fn jfp_parse_job(data: *const u8, len: usize, json_out: *mut *mut c_char) -> i32 {
+    run(json_out, || {
+        let data = input(data, len)?;
+        let job = Job::parse(data).map_err(|e| (JFP_ERR_PARSE, e.to_string()))?;
+        to_json(&ParsedArtifact::Job(job))
+    })
+}
+
+/// Parses a task XML document held in `data[0..len]`. A byte order mark
+/// selects the encoding. Input without one is decoded in the encoding its
+/// XML declaration names, or as UTF-16LE, like the files Task Scheduler
+/// writes, when it does not start with a declaration in ASCII.
+///
+/// # Safety
+///
+/// Same requirements as `jfp_parse_job`.
+#[no_mangle]
+pub unsafe extern "C" fn jfp_parse_task_xml(data: *const u8, len: usize, json_out: *mut *mut c_char) -> i32 {
+    run(json_out, || {
+        let data = input(data, len)?;
+        let task = Task::from_bytes(data).map_err(|e| (JFP_ERR_PARSE, e.to_string()))?;
+        to_json(&ParsedArtifact::Task(task))
+    })
+}
+
+/// Releases a string returned by this library. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `s` must be null or a pointer obtained from this library that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn jfp_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Returns the message for the most recent failure on the calling thread, or
+/// null if the last call succeeded. The pointer stays valid until the next
+/// call into this library on the same thread and must not be freed.
+#[no_mangle]
+pub extern "C" fn jfp_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
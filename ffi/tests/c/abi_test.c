@@ -0,0 +1,71 @@
+/* Exercises the C ABI: abi_test <job file> <task xml file> */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "jobfileparser.h"
+
+static unsigned char *read_file(const char *path, size_t *len) {
+    FILE *f = fopen(path, "rb");
+    if (!f) {
+        return NULL;
+    }
+    fseek(f, 0, SEEK_END);
+    *len = (size_t)ftell(f);
+    fseek(f, 0, SEEK_SET);
+    unsigned char *data = malloc(*len);
+    if (data && fread(data, 1, *len, f) != *len) {
+        free(data);
+        data = NULL;
+    }
+    fclose(f);
+    return data;
+}
+
+#define CHECK(cond)                                                   \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,   \
+                    __LINE__, #cond);                                 \
+            return 1;                                                 \
+        }                                                             \
+    } while (0)
+
+int main(int argc, char **argv) {
+    CHECK(argc == 3);
+
+    size_t job_len = 0, xml_len = 0;
+    unsigned char *job = read_file(argv[1], &job_len);
+    unsigned char *xml = read_file(argv[2], &xml_len);
+    CHECK(job != NULL && xml != NULL);
+
+    char *json = NULL;
+    CHECK(jfp_parse_job(job, job_len, &json) == JFP_OK);
+    CHECK(json != NULL);
+    CHECK(strstr(json, "\"format\":\"job\"") != NULL);
+    CHECK(strstr(json, "\"name\":\"a.exe\"") != NULL);
+    CHECK(jfp_last_error_message() == NULL);
+    jfp_free_string(json);
+
+    json = NULL;
+    CHECK(jfp_parse_task_xml(xml, xml_len, &json) == JFP_OK);
+    CHECK(strstr(json, "\"format\":\"task\"") != NULL);
+    CHECK(strstr(json, "\"command\":\"notepad.exe\"") != NULL);
+    jfp_free_string(json);
+
+    /* A truncated job must fail with a negative code, not crash. */
+    json = NULL;
+    CHECK(jfp_parse_job(job, 10, &json) < 0);
+    CHECK(json == NULL);
+    CHECK(jfp_last_error_message() != NULL);
+
+    CHECK(jfp_parse_task_xml((const unsigned char *)"<nope", 5, &json) == JFP_ERR_PARSE);
+    CHECK(jfp_parse_job(NULL, 4, &json) == JFP_ERR_NULL_ARGUMENT);
+    CHECK(jfp_parse_job(job, job_len, NULL) == JFP_ERR_NULL_ARGUMENT);
+    jfp_free_string(NULL);
+
+    free(job);
+    free(xml);
+    printf("ok\n");
+    return 0;
+}
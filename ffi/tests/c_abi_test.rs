@@ -0,0 +1,60 @@
+#![cfg(unix)]
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    const TASK_XML: &str = "\u{feff}<Task>\
+        <RegistrationInfo><Author>Test Author</Author></RegistrationInfo>\
+        <Triggers/>\
+        <Settings><Enabled>true</Enabled></Settings>\
+        <Actions><Exec><Command>notepad.exe</Command></Exec></Actions>\
+        </Task>";
+
+    /// Compiles tests/c/abi_test.c against the cdylib built for this test run
+    /// and runs it over a binary job and a task XML fixture.
+    #[test]
+    fn test_c_program_links_and_runs() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        // The test binary lives in target/<profile>/deps, next to the cdylib.
+        let exe = env::current_exe().unwrap();
+        let lib_dir = exe.parent().unwrap();
+        let work = tempfile::tempdir().unwrap();
+
+        let job = work.path().join("a.job");
+        let xml = work.path().join("a.xml");
+        fs::write(&job, job_bytes("a.exe")).unwrap();
+        fs::write(&xml, TASK_XML).unwrap();
+
+        let program = work.path().join("abi_test");
+        let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let status = Command::new(cc)
+            .arg("-I")
+            .arg(crate_dir.join("include"))
+            .arg(crate_dir.join("tests/c/abi_test.c"))
+            .arg("-L")
+            .arg(lib_dir)
+            .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+            .arg("-ljobfileparser_ffi")
+            .arg("-o")
+            .arg(&program)
+            .status()
+            .expect("a C compiler is required for this test");
+        assert!(status.success());
+
+        let output = Command::new(&program).arg(&job).arg(&xml).output().unwrap();
+        assert!(
+            output.status.success(),
+            "abi_test failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+
+    /// Regenerates the C header and compares it with the committed copy. Run
+    /// with `JFP_BLESS=1` to update `include/jobfileparser.h`.
+    #[test]
+    fn test_header_is_current() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+        let mut generated = Vec::new();
+        cbindgen::Builder::new()
+            .with_crate(crate_dir)
+            .with_config(config)
+            .generate()
+            .unwrap()
+            .write(&mut generated);
+
+        let header = crate_dir.join("include").join("jobfileparser.h");
+        if env::var_os("JFP_BLESS").is_some() {
+            fs::write(&header, &generated).unwrap();
+        }
+        let committed = fs::read(&header).unwrap();
+        assert!(
+            committed == generated,
+            "include/jobfileparser.h is stale; rerun this test with JFP_BLESS=1"
+        );
+    }
+}
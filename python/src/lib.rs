@@ -0,0 +1,214 @@
+//! Python bindings for the job file parsers, built with maturin.
+//!
+//! Every function returns the record `jobfileparser parse -o json` prints
+//! for the same input, converted to a dict, except that date fields become
+//! `datetime.datetime` objects (or `None` when the stored value is not a
+//! valid date).
+
+use std::ops::ControlFlow;
+use std::panic::{self, AssertUnwindSafe};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jobfileparser::date::format_unix;
+use jobfileparser::job::DEFAULT_MIN_COVERAGE;
+use jobfileparser::{Error, Job, ParsedArtifact, Record, ScanOptions, Task};
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyString};
+use serde_json::Value;
+
+create_exception!(
+    jobfileparser,
+    JobParseError,
+    PyValueError,
+    "Raised when an artifact cannot be parsed. The `path` and `offset` attributes hold the file and byte offset involved, or None."
+);
+
+/// Keys whose string values are ISO 8601 dates.
+const DATE_KEYS: &[&str] = &["run_date", "scheduled_date", "date", "start_boundary", "end_boundary"];
+
+type ScanItem = (PathBuf, Result<Record, Error>);
+
+fn to_py<'py>(
+    py: Python<'py>,
+    key: Option<&str>,
+    value: &Value,
+    datetime: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.into_pyobject(py)?.into_any()
+            } else if let Some(u) = n.as_u64() {
+                u.into_pyobject(py)?.into_any()
+            } else {
+                PyFloat::new(py, n.as_f64().unwrap_or_default()).into_any()
+            }
+        }
+        Value::String(s) if key.is_some_and(|k| DATE_KEYS.contains(&k)) => datetime
+            .call_method1("fromisoformat", (s,))
+            .unwrap_or_else(|_| py.None().into_bound(py)),
+        Value::String(s) => PyString::new(py, s).into_any(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_py(py, None, item, datetime)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (k, v) in map {
+                dict.set_item(k, to_py(py, Some(k), v, datetime)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+/// Builds the record the CLI prints for `artifact`, read from `path` as
+/// `data`, as of `now` (the current time when `None`).
+fn record(path: &Path, artifact: ParsedArtifact, data: Option<&[u8]>, now: Option<&str>) -> Record {
+    let mut record = Record::new(path, Ok(artifact));
+    if let Some(data) = data {
+        record.read_job_triggers(data);
+        record.read_user_data(data);
+        record.read_coverage(data, DEFAULT_MIN_COVERAGE);
+    }
+    let now = now.map(str::to_string).unwrap_or_else(|| {
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        format_unix(since_epoch.as_secs())
+    });
+    record.infer_history(&now);
+    record
+}
+
+fn record_to_py<'py>(py: Python<'py>, record: &Record) -> PyResult<Bound<'py, PyAny>> {
+    let value = serde_json::to_value(record).map_err(|e| JobParseError::new_err(e.to_string()))?;
+    let datetime = py.import("datetime")?.getattr("datetime")?;
+    to_py(py, None, &value, &datetime)
+}
+
+fn parse_error(py: Python<'_>, message: String, path: Option<String>, offset: Option<usize>) -> PyErr {
+    let err = JobParseError::new_err(message);
+    let value = err.value(py);
+    // Setting attributes on a fresh exception instance cannot fail.
+    let _ = value.setattr("path", path);
+    let _ = value.setattr("offset", offset);
+    err
+}
+
+/// Runs `parse`, turning both errors and panics into `JobParseError`.
+fn guarded<F>(py: Python<'_>, parse: F) -> PyResult<ParsedArtifact>
+where
+    F: FnOnce() -> Result<ParsedArtifact, Error>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(parse)) {
+        Ok(Ok(artifact)) => Ok(artifact),
+        Ok(Err(e)) => Err(parse_error(py, e.to_string(), None, e.offset())),
+        Err(_) => Err(parse_error(py, "malformed job: parser panicked".to_string(), None, None)),
+    }
+}
+
+/// Parses the bytes of a binary `.job` file into a dict. `path` is reported
+/// as the record's path, and `now` (`YYYY-MM-DDTHH:MM:SS`, like `--now`) is
+/// the time the history fields are worked out at.
+#[pyfunction]
+#[pyo3(signature = (data, path = "-", now = None))]
+fn parse_job<'py>(
+    py: Python<'py>,
+    data: &[u8],
+    path: &str,
+    now: Option<&str>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let artifact = guarded(py, || Job::parse(data).map(ParsedArtifact::Job))?;
+    record_to_py(py, &record(Path::new(path), artifact, Some(data), now))
+}
+
+/// Parses a task XML document into a dict. `path` and `now` are as for
+/// `parse_job`.
+#[pyfunction]
+#[pyo3(signature = (xml, path = "-", now = None))]
+fn parse_task_xml<'py>(
+    py: Python<'py>,
+    xml: &str,
+    path: &str,
+    now: Option<&str>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let artifact = guarded(py, || Task::from_xml(xml).map(ParsedArtifact::Task))?;
+    record_to_py(py, &record(Path::new(path), artifact, Some(xml.as_bytes()), now))
+}
+
+/// Iterates over the artifacts in a directory, yielding one dict per file.
+/// A file that fails to parse raises `JobParseError`;
+/// iteration can continue afterwards.
+#[pyfunction]
+#[pyo3(signature = (path, recursive = false))]
+fn scan_dir(path: PathBuf, recursive: bool) -> ScanIterator {
+    let (tx, rx) = mpsc::sync_channel::<ScanItem>(1);
+    let options = ScanOptions { recursive, ..ScanOptions::default() };
+    thread::spawn(move || {
+        let scan = panic::catch_unwind(AssertUnwindSafe(|| {
+            jobfileparser::scan_dir(&path, &options, |file, result| {
+                let result = result.map(|artifact| {
+                    let data = fs::read(file).ok();
+                    record(file, artifact, data.as_deref(), None)
+                });
+                // A send error means the iterator was dropped, so stop scanning.
+                match tx.send((file.to_path_buf(), result)) {
+                    Ok(()) => ControlFlow::Continue(()),
+                    Err(_) => ControlFlow::Break(()),
+                }
+            })
+        }));
+        let error = match scan {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e,
+            Err(_) => Error::Io(std::io::Error::other("parser panicked; scan aborted")),
+        };
+        let _ = tx.send((path, Err(error)));
+    });
+    ScanIterator { rx: Mutex::new(rx) }
+}
+
+#[pyclass]
+struct ScanIterator {
+    rx: Mutex<Receiver<ScanItem>>,
+}
+
+#[pymethods]
+impl ScanIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let item = py.allow_threads(|| self.rx.lock().unwrap().recv());
+        let Ok((path, result)) = item else {
+            return Ok(None);
+        };
+        let display = path.display().to_string();
+        match result {
+            Ok(record) => record_to_py(py, &record).map(Some),
+            Err(e) => Err(parse_error(py, e.to_string(), Some(display), e.offset())),
+        }
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "jobfileparser")]
+fn py_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("JobParseError", m.py().get_type::<JobParseError>())?;
+    m.add_function(wrap_pyfunction!(parse_job, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_task_xml, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_dir, m)?)?;
+    Ok(())
+}
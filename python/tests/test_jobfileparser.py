@@ -0,0 +1,150 @@
+import json
+import os
+import shutil
+import struct
+import subprocess
+from datetime import datetime
+
+import pytest
+
+import jobfileparser
+
+TASK_XML = """<?xml version="1.0" encoding="UTF-16"?>
+<Task>
+    <RegistrationInfo>
+        <Author>Test Author</Author>
+        <Date>2024-08-02T12:34:56</Date>
+        <Description>Test Task</Description>
+    </RegistrationInfo>
+    <Triggers>
+        <CalendarTrigger>
+            <StartBoundary>2024-08-02T14:00:00</StartBoundary>
+            <Enabled>true</Enabled>
+        </CalendarTrigger>
+    </Triggers>
+    <Settings>
+        <Enabled>true</Enabled>
+    </Settings>
+    <Actions>
+        <Exec>
+            <Command>notepad.exe</Command>
+            <Arguments>/A</Arguments>
+        </Exec>
+    </Actions>
+</Task>
+"""
+
+
+def job_bytes(name):
+    """Mirrors tests/common/mod.rs in the Rust crate."""
+
+    def string(value):
+        units = (value + "\0").encode("utf-16-le")
+        return struct.pack("<H", len(units) // 2) + units
+
+    data = struct.pack("<HH", 0x0601, 1) + b"\x11" * 16 + b"\0" * 12
+    data += struct.pack("<IiiiI", 0x20, 3600000, 0, 0x41300, 0)
+    data += struct.pack("<8H", 2024, 3, 1, 4, 2, 13, 0, 0)
+    data += struct.pack("<H", 0)
+    for value in (name, "", "", "", ""):
+        data += string(value)
+    return data
+
+
+def test_parse_job():
+    job = jobfileparser.parse_job(job_bytes("a.exe"))
+    assert job["format"] == "job"
+    assert job["name"] == "a.exe"
+    assert job["status"] == 0x41300
+    assert job["uuid"] == "{11111111-1111-1111-1111-111111111111}"
+    assert job["run_date"] == datetime(2024, 3, 4, 2, 13, 0)
+    assert job["parse_status"] == "ok"
+    assert job["path"] == "-"
+
+
+def test_parse_job_truncated_raises():
+    with pytest.raises(jobfileparser.JobParseError) as info:
+        jobfileparser.parse_job(b"\x01\x06")
+    assert info.value.path is None
+
+
+def test_parse_task_xml():
+    task = jobfileparser.parse_task_xml(TASK_XML)
+    assert task["format"] == "task"
+    assert task["registration_info"]["author"] == "Test Author"
+    assert task["registration_info"]["date"] == datetime(2024, 8, 2, 12, 34, 56)
+    trigger = task["triggers"]["calendar_trigger"]
+    assert trigger["start_boundary"] == datetime(2024, 8, 2, 14, 0, 0)
+    assert trigger["end_boundary"] is None
+    assert task["actions"]["exec"] == {
+        "command": "notepad.exe",
+        "arguments": "/A",
+        "working_directory": None,
+    }
+
+
+def test_parse_task_xml_invalid_raises():
+    with pytest.raises(jobfileparser.JobParseError):
+        jobfileparser.parse_task_xml("<NotATask/>")
+
+
+def test_scan_dir(tmp_path):
+    (tmp_path / "a.job").write_bytes(job_bytes("a.exe"))
+    (tmp_path / "b.xml").write_bytes(TASK_XML.encode("utf-16"))
+    (tmp_path / "c.xml").write_bytes(b"garbage")
+    (tmp_path / "notes.txt").write_text("ignored")
+
+    results = []
+    errors = []
+    it = jobfileparser.scan_dir(str(tmp_path))
+    while True:
+        try:
+            results.append(next(it))
+        except StopIteration:
+            break
+        except jobfileparser.JobParseError as e:
+            errors.append(e.path)
+
+    assert [r["format"] for r in results] == ["job", "task"]
+    assert results[0]["path"] == str(tmp_path / "a.job")
+    assert errors == [str(tmp_path / "c.xml")]
+
+
+DATE_KEYS = ("run_date", "scheduled_date", "date", "start_boundary", "end_boundary")
+
+
+def as_returned(value, key=None):
+    """Converts the dates in a record the CLI printed the way the bindings do."""
+    if isinstance(value, dict):
+        return {k: as_returned(v, k) for k, v in value.items()}
+    if isinstance(value, list):
+        return [as_returned(v) for v in value]
+    if isinstance(value, str) and key in DATE_KEYS:
+        try:
+            return datetime.fromisoformat(value)
+        except ValueError:
+            return None
+    return value
+
+
+def test_matches_cli_json(tmp_path):
+    cli = os.environ.get("JOBFILEPARSER_CLI") or shutil.which("jobfileparser")
+    if cli is None:
+        pytest.skip("jobfileparser CLI not found; set JOBFILEPARSER_CLI")
+    now = "2025-01-01T00:00:00"
+    job = tmp_path / "a.job"
+    job.write_bytes(job_bytes("a.exe"))
+    task = tmp_path / "b.xml"
+    task.write_bytes(TASK_XML.encode("utf-16"))
+
+    output = subprocess.run(
+        [cli, "parse", str(job), str(task), "-o", "json", "--now", now],
+        check=True,
+        capture_output=True,
+    ).stdout
+    expected = as_returned(json.loads(output))
+
+    assert [
+        jobfileparser.parse_job(job.read_bytes(), path=str(job), now=now),
+        jobfileparser.parse_task_xml(TASK_XML, path=str(task), now=now),
+    ] == expected
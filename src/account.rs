@@ -0,0 +1,141 @@
+//! What kind of account a job's user field names. A job keeps the name as
+//! it was when the job was created, so `Administrateur` still says the job
+//! came from the built-in administrator of a French system after the
+//! account was renamed or deleted.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::fold::fold;
+
+/// The shape of an account name, as `classify_user` sees it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum UserKind {
+    /// No account at all.
+    #[default]
+    Empty,
+    /// An account every Windows system has, such as `SYSTEM`, `NT
+    /// AUTHORITY\NETWORK SERVICE`, the built-in `Administrator` under any of
+    /// its localized names, or the SID of one.
+    WellKnown,
+    /// A bare name or `.\name`, which Windows looks up on the machine.
+    Local,
+    /// `DOMAIN\name`. A machine name in front looks the same, so local
+    /// accounts written as `HOST\name` land here too.
+    Domain,
+    /// `name@domain`.
+    Upn,
+}
+
+impl UserKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            UserKind::Empty => "empty",
+            UserKind::WellKnown => "well_known",
+            UserKind::Local => "local",
+            UserKind::Domain => "domain",
+            UserKind::Upn => "upn",
+        }
+    }
+}
+
+/// The name of the built-in administrator in each display language that
+/// renames it, lowercase, with the language. English, German, Italian, Dutch
+/// and Polish systems, among others, keep `Administrator`.
+const LOCALIZED_ADMINISTRATORS: &[(&str, &str)] = &[
+    ("administrateur", "French"),
+    ("administrador", "Spanish or Portuguese"),
+    ("administratör", "Swedish"),
+    ("järjestelmänvalvoja", "Finnish"),
+    ("rendszergazda", "Hungarian"),
+    ("администратор", "Russian"),
+    ("адміністратор", "Ukrainian"),
+    ("διαχειριστής", "Greek"),
+];
+
+/// Other account names every system has, folded.
+const WELL_KNOWN_NAMES: &[&str] = &[
+    "administrator",
+    "administrators",
+    "guest",
+    "local service",
+    "localsystem",
+    "network service",
+    "system",
+    "users",
+];
+
+/// Authorities whose accounts are all well-known, folded, including the
+/// localized names of `NT AUTHORITY`.
+const WELL_KNOWN_AUTHORITIES: &[&str] = &[
+    "autoridad nt",
+    "autorite nt",
+    "builtin",
+    "nt authority",
+    "nt service",
+    "nt-autorität",
+];
+
+/// Classifies `user`. A well-known name wins over the domain in front of
+/// it, so `CORP\Administrator` is `WellKnown`. A SID is `WellKnown` for the
+/// built-in accounts and groups and the administrator and guest (RIDs 500
+/// and 501) of any machine or domain, and `Domain` otherwise.
+pub fn classify_user(user: &str) -> UserKind {
+    let user = user.trim();
+    if user.is_empty() {
+        return UserKind::Empty;
+    }
+    if is_sid(user) {
+        return if is_well_known_sid(user) { UserKind::WellKnown } else { UserKind::Domain };
+    }
+    let (domain, name) = match user.rsplit_once('\\') {
+        Some((domain, name)) => (Some(domain), name),
+        None => (None, user),
+    };
+    let folded = fold(name);
+    let well_known_name = WELL_KNOWN_NAMES.contains(&folded.as_ref())
+        || LOCALIZED_ADMINISTRATORS.iter().any(|(admin, _)| fold(admin) == folded);
+    let well_known_domain =
+        domain.is_some_and(|domain| WELL_KNOWN_AUTHORITIES.contains(&fold(domain).as_ref()));
+    if well_known_name || well_known_domain {
+        return UserKind::WellKnown;
+    }
+    match domain {
+        Some("." | "") | None if name.contains('@') => UserKind::Upn,
+        Some("." | "") | None => UserKind::Local,
+        Some(_) => UserKind::Domain,
+    }
+}
+
+/// The language of the system `user` was created on, if it names the
+/// built-in administrator by a localized name such as `Administrateur` or
+/// `.\Администратор`, in any case.
+pub fn localized_administrator(user: &str) -> Option<&'static str> {
+    let name = user.trim().rsplit('\\').next()?;
+    let folded = fold(name);
+    LOCALIZED_ADMINISTRATORS
+        .iter()
+        .find(|(admin, _)| fold(admin) == folded)
+        .map(|(_, language)| *language)
+}
+
+fn is_sid(text: &str) -> bool {
+    let mut parts = text.split('-');
+    parts.next().is_some_and(|s| s.eq_ignore_ascii_case("S"))
+        && parts.clone().count() >= 2
+        && parts.all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn is_well_known_sid(sid: &str) -> bool {
+    let sid = sid.to_ascii_uppercase();
+    let Some(rest) = sid.strip_prefix("S-1-5-") else {
+        // Everyone, the local groups and the creator owner and group.
+        return ["S-1-1-", "S-1-2-", "S-1-3-"].iter().any(|prefix| sid.starts_with(prefix));
+    };
+    match rest.strip_prefix("21-") {
+        Some(domain) => domain.ends_with("-500") || domain.ends_with("-501"),
+        None => true,
+    }
+}
@@ -0,0 +1,129 @@
+//! Who owns a job or task file on a live Windows system, and whether
+//! accounts other than administrators may change it. A task file that
+//! `Users` can write runs whatever they put in it as the account the task
+//! runs as, so for a task running as SYSTEM it is a way to gain SYSTEM.
+//!
+//! `FileSecurity::read` asks Windows; everything else works on what it
+//! returned, so it can be checked on any platform.
+
+#[cfg(all(windows, feature = "fs"))]
+use std::io;
+#[cfg(all(windows, feature = "fs"))]
+use std::path::Path;
+
+use crate::detect::Finding;
+use crate::sddl::{is_system_account, SddlError, SecurityDescriptor};
+use crate::ParsedArtifact;
+
+/// The owner and DACL of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSecurity {
+    /// The owner's SID, such as `S-1-5-32-544`; `None` for a file without
+    /// one.
+    pub owner: Option<String>,
+    /// The DACL as SDDL, such as `D:AI(A;ID;FA;;;SY)(A;ID;0x1200a9;;;BU)`.
+    pub dacl: String,
+}
+
+impl FileSecurity {
+    /// The groups of accounts other than administrators that the DACL lets
+    /// change the file; see `SecurityDescriptor::non_admin_writers`.
+    pub fn writers(&self) -> Result<Vec<String>, SddlError> {
+        Ok(SecurityDescriptor::parse(&self.dacl)?.non_admin_writers())
+    }
+
+    /// The finding for `artifact`, read from `path`, when it runs as SYSTEM
+    /// and `writers` may change its file.
+    pub fn finding(path: &str, artifact: &ParsedArtifact, writers: &[String]) -> Option<Finding> {
+        if writers.is_empty() || !artifact.user().is_some_and(is_system_account) {
+            return None;
+        }
+        Some(Finding {
+            rule: "writable-system-task".into(),
+            description: format!(
+                "{} runs as SYSTEM, but {} can modify it",
+                path,
+                writers.join(", ")
+            ),
+            ..Finding::default()
+        })
+    }
+
+    /// Reads the owner and DACL of the file at `path`.
+    #[cfg(all(windows, feature = "fs"))]
+    pub fn read(path: &Path) -> io::Result<FileSecurity> {
+        use std::os::windows::ffi::OsStrExt;
+        use std::ptr;
+        use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+        use windows_sys::Win32::Security::Authorization::{
+            ConvertSecurityDescriptorToStringSecurityDescriptorW, ConvertSidToStringSidW,
+            GetNamedSecurityInfoW, SDDL_REVISION_1, SE_FILE_OBJECT,
+        };
+        use windows_sys::Win32::Security::{
+            DACL_SECURITY_INFORMATION, OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID,
+        };
+
+        let name: Vec<u16> =
+            crate::path::os_path(path).as_os_str().encode_wide().chain([0]).collect();
+        let mut owner_sid: PSID = ptr::null_mut();
+        let mut descriptor: PSECURITY_DESCRIPTOR = ptr::null_mut();
+        // SAFETY: `name` is NUL-terminated, and the out pointers are valid
+        // for the call. On success `descriptor` is allocated by the system
+        // and `owner_sid` points into it.
+        let status = unsafe {
+            GetNamedSecurityInfoW(
+                name.as_ptr(),
+                SE_FILE_OBJECT,
+                OWNER_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION,
+                &mut owner_sid,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut descriptor,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return Err(io::Error::from_raw_os_error(status as i32));
+        }
+        let mut owner = None;
+        let mut text = ptr::null_mut();
+        // SAFETY: `owner_sid` is null or a SID inside `descriptor`, which is
+        // freed only after both conversions.
+        if !owner_sid.is_null() && unsafe { ConvertSidToStringSidW(owner_sid, &mut text) } != 0 {
+            owner = Some(unsafe { take_wide(text) });
+        }
+        let mut text = ptr::null_mut();
+        // SAFETY: as above; the string is freed by `take_wide`.
+        let converted = unsafe {
+            ConvertSecurityDescriptorToStringSecurityDescriptorW(
+                descriptor,
+                SDDL_REVISION_1,
+                DACL_SECURITY_INFORMATION,
+                &mut text,
+                ptr::null_mut(),
+            )
+        };
+        let result = if converted != 0 {
+            Ok(FileSecurity { owner, dacl: unsafe { take_wide(text) } })
+        } else {
+            Err(io::Error::last_os_error())
+        };
+        // SAFETY: allocated by GetNamedSecurityInfoW, and no longer used.
+        unsafe { LocalFree(descriptor) };
+        result
+    }
+}
+
+/// Copies the NUL-terminated string at `text`, allocated by the system,
+/// and frees it.
+///
+/// # Safety
+///
+/// `text` must be a valid NUL-terminated UTF-16 string from `LocalAlloc`.
+#[cfg(all(windows, feature = "fs"))]
+unsafe fn take_wide(text: *mut u16) -> String {
+    let len = (0..).take_while(|&i| *text.add(i) != 0).count();
+    let copy = String::from_utf16_lossy(std::slice::from_raw_parts(text, len));
+    windows_sys::Win32::Foundation::LocalFree(text.cast());
+    copy
+}
@@ -0,0 +1,102 @@
+use futures::stream::{self, Stream};
+use std::path::{Path, PathBuf};
+use std::vec;
+
+use crate::path::os_path;
+use crate::{check_size, parse_bytes, Error, ParsedArtifact, ScanOptions};
+
+/// Async counterpart of `parse_file`. Only the read is asynchronous; parsing
+/// itself is cheap and runs inline.
+pub async fn parse_file_async<P: AsRef<Path>>(path: P) -> Result<ParsedArtifact, Error> {
+    parse_bytes(&read(path.as_ref()).await?)
+}
+
+async fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    let data = tokio::fs::read(os_path(path)).await?;
+    if data.is_empty() {
+        return Err(Error::EmptyFile);
+    }
+    Ok(data)
+}
+
+/// Async counterpart of `scan_dir`, yielding artifacts in the same order.
+/// An unreadable root directory is yielded as a single error item.
+pub fn scan_dir_stream<P: AsRef<Path>>(
+    path: P,
+    options: &ScanOptions,
+) -> impl Stream<Item = (PathBuf, Result<ParsedArtifact, Error>)> + Send {
+    let walk = Walk {
+        options: options.clone(),
+        root: Some(path.as_ref().to_path_buf()),
+        stack: Vec::new(),
+    };
+    stream::unfold(walk, |mut walk| async move {
+        let item = walk.next().await?;
+        Some((item, walk))
+    })
+}
+
+struct Walk {
+    options: ScanOptions,
+    root: Option<PathBuf>,
+    stack: Vec<vec::IntoIter<PathBuf>>,
+}
+
+impl Walk {
+    async fn next(&mut self) -> Option<(PathBuf, Result<ParsedArtifact, Error>)> {
+        if let Some(root) = self.root.take() {
+            match sorted_entries(&root).await {
+                Ok(entries) => self.stack.push(entries.into_iter()),
+                Err(e) => return Some((root, Err(e))),
+            }
+        }
+        loop {
+            let path = match self.stack.last_mut()?.next() {
+                Some(path) => path,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+            // Links to directories are not followed; see
+            // `ScanOptions::recursive`.
+            let metadata = match tokio::fs::symlink_metadata(os_path(&path)).await {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    match tokio::fs::metadata(os_path(&path)).await {
+                        Ok(metadata) if !metadata.is_dir() => metadata,
+                        _ => continue,
+                    }
+                }
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if metadata.is_dir() {
+                if self.options.recursive {
+                    match sorted_entries(&path).await {
+                        Ok(entries) => self.stack.push(entries.into_iter()),
+                        Err(e) => return Some((path, Err(e))),
+                    }
+                }
+            } else if metadata.is_file() && self.options.wants(&path) {
+                let result = match check_size(metadata.len(), self.options.max_file_size) {
+                    Ok(()) => match read(&path).await {
+                        Ok(data) => self.options.parse(&path, &data),
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                };
+                return Some((path, result));
+            }
+        }
+    }
+}
+
+async fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut entries = Vec::new();
+    let mut read_dir = tokio::fs::read_dir(os_path(dir)).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        entries.push(dir.join(entry.file_name()));
+    }
+    entries.sort();
+    Ok(entries)
+}
@@ -0,0 +1,36 @@
+//! Stopping long scans from another thread, such as a GUI's cancel button.
+//! The scanning functions that take a `CancellationToken` check it between
+//! files, or every so often while carving, and return what they had done by
+//! then with a `PartialScan` saying how far they got.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag shared by clones, set once by `cancel` and never cleared.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Asks every scan checking this token, or a clone of it, to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// How far a scan that takes a `CancellationToken` got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PartialScan {
+    /// Files handed to the callback, or for carving, bytes of the data
+    /// searched for every kind of artifact.
+    pub completed: usize,
+    /// The token stopped the scan before it was done.
+    pub cancelled: bool,
+}
@@ -0,0 +1,156 @@
+//! Recovery of jobs and task XML embedded in raw data such as disk images,
+//! memory dumps or unallocated space.
+
+#[cfg(feature = "binary")]
+use crate::consts::{self, fixed, product};
+#[cfg(feature = "binary")]
+use crate::Job;
+use crate::cancel::{CancellationToken, PartialScan};
+use crate::ParsedArtifact;
+#[cfg(feature = "xml")]
+use crate::Task;
+
+/// An artifact found in the data, with the byte range it was parsed from.
+#[derive(Debug)]
+pub struct Carved {
+    pub offset: usize,
+    pub len: usize,
+    pub artifact: ParsedArtifact,
+}
+
+/// How many bytes `carve_cancellable` searches between checks of its token.
+const CHECK_EVERY: usize = 1 << 20;
+
+/// Scans `data` for binary jobs (a known product version followed by file
+/// version 1, with plausible dates and strings) and for `<Task>` elements
+/// in UTF-8 or UTF-16LE. Results are ordered by offset; a binary job's
+/// range ends after its comment string.
+pub fn carve(data: &[u8]) -> Vec<Carved> {
+    carve_cancellable(data, &CancellationToken::new()).0
+}
+
+/// `carve`, stopping once `token` is cancelled. The data is searched a MiB
+/// at a time, the token checked before each; what was found in the part
+/// searched is returned, with the length of that part in the `PartialScan`.
+/// An artifact starting in it is found whole even if it runs past its end.
+pub fn carve_cancellable(data: &[u8], token: &CancellationToken) -> (Vec<Carved>, PartialScan) {
+    let mut found = Vec::new();
+    // Where each search resumes: binary jobs, UTF-8 and UTF-16LE tasks.
+    let mut next = [0; 3];
+    let mut partial = PartialScan::default();
+    while partial.completed < data.len() {
+        if token.is_cancelled() {
+            partial.cancelled = true;
+            break;
+        }
+        let end = data.len().min(partial.completed + CHECK_EVERY);
+        #[cfg(feature = "binary")]
+        carve_jobs(data, &mut next[0], end, &mut found);
+        #[cfg(feature = "xml")]
+        carve_tasks(data, &mut next[1..], end, &mut found);
+        partial.completed = end;
+    }
+    found.sort_by_key(|c| c.offset);
+    (found, partial)
+}
+
+/// Finds the jobs starting from `*next` up to `end`, leaving `*next` where
+/// the search is to resume.
+#[cfg(feature = "binary")]
+fn carve_jobs(data: &[u8], next: &mut usize, end: usize, found: &mut Vec<Carved>) {
+    let mut offset = *next;
+    while offset < end && offset + fixed::FILE_VERSION.end() <= data.len() {
+        let word = |at: usize| u16::from_le_bytes([data[offset + at], data[offset + at + 1]]);
+        let product = word(fixed::PRODUCT_VERSION.offset);
+        let version = word(fixed::FILE_VERSION.offset);
+        // Only headers that start with a version a real Task Scheduler
+        // build writes are considered.
+        let known = product::VERSIONS.iter().any(|(v, _)| *v == product);
+        if known && version == consts::FILE_VERSION {
+            if let Ok((job, len)) = Job::parse_prefix(&data[offset..]) {
+                if plausible(&job) {
+                    found.push(Carved {
+                        offset,
+                        len,
+                        artifact: ParsedArtifact::Job(job),
+                    });
+                    offset += len;
+                    continue;
+                }
+            }
+        }
+        offset += 1;
+    }
+    *next = offset;
+}
+
+#[cfg(feature = "binary")]
+fn plausible(job: &Job) -> bool {
+    let date = &job.run_date;
+    let date_ok = date.month <= 12
+        && date.day <= 31
+        && date.hour < 24
+        && date.minute < 60
+        && date.second < 60;
+    let strings = [
+        &job.name,
+        &job.parameters,
+        &job.working_directory,
+        &job.user,
+        &job.comment,
+    ];
+    let strings_ok = strings.iter().all(|s| {
+        !s.chars()
+            .any(|c| c == char::REPLACEMENT_CHARACTER || (c.is_control() && c != '\t'))
+    });
+    date_ok && strings_ok && !job.name.is_empty()
+}
+
+#[cfg(feature = "xml")]
+fn carve_tasks(data: &[u8], next: &mut [usize], until: usize, found: &mut Vec<Carved>) {
+    let utf16 = |s: &str| -> Vec<u8> { s.encode_utf16().flat_map(u16::to_le_bytes).collect() };
+    let searches = [
+        (b"<Task".to_vec(), b"</Task>".to_vec(), false),
+        (utf16("<Task"), utf16("</Task>"), true),
+    ];
+    for ((open, close, wide), next) in searches.into_iter().zip(next) {
+        let mut offset = *next;
+        // Only elements starting before `until` are looked at now.
+        let window = data.len().min(until + open.len() - 1);
+        let search = |offset: usize| data.get(offset..window).and_then(|rest| find(rest, &open));
+        while let Some(start) = search(offset).map(|i| i + offset) {
+            let Some(end) = find(&data[start..], &close).map(|i| start + i + close.len()) else {
+                // No element starting later has an end either.
+                offset = data.len();
+                break;
+            };
+            let bytes = &data[start..end];
+            let xml = if wide {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                String::from_utf16(&units).ok()
+            } else {
+                String::from_utf8(bytes.to_vec()).ok()
+            };
+            match xml.map(|xml| Task::from_xml(&xml)) {
+                Some(Ok(task)) => {
+                    found.push(Carved {
+                        offset: start,
+                        len: end - start,
+                        artifact: ParsedArtifact::Task(task),
+                    });
+                    offset = end;
+                }
+                _ => offset = start + open.len(),
+            }
+        }
+        *next = offset.max(until);
+    }
+}
+
+#[cfg(feature = "xml")]
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
@@ -0,0 +1,348 @@
+//! `aggregate`: the records of `-o jsonl` exports from many hosts folded
+//! into one group per distinct task, for seeing which tasks an estate runs
+//! everywhere and which only a few hosts have.
+//!
+//! Records are grouped by a content key: the command line, normalized as
+//! `CommandLine::normalized` writes it and compared as `Case` says, and
+//! the schedule. Time triggers count by how they recur and the time of day
+//! they start, not the day they were first due, so a task registered on
+//! different days on different hosts is still one task; a trigger that
+//! fires once keeps its date. Nothing else goes into the key, so the job
+//! UUID, registration dates and run history a host has of its own do not
+//! split a group. A job's triggers are only in an export written with
+//! `--embed-raw`; without them its schedule is unknown.
+//!
+//! Each group gives the hosts it was found on, named by the `hostname` of
+//! their sidecar or else by the export's file name, and the fields whose
+//! values differ between its instances. Groups whose tasks share a name
+//! with tasks that run something else are flagged as masquerading.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use flate2::read::MultiGzDecoder;
+use jobfileparser::entropy::task_name;
+use jobfileparser::schedule::{Recurrence, Schedule};
+use jobfileparser::{date, job, Case, ParsedArtifact, Record};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::commands::flatten;
+use super::terminal::{Capabilities, Paint};
+use super::{EXIT_FATAL, EXIT_SUCCESS};
+
+/// Fields that differ from host to host whatever the task, left out of the
+/// variance: the job UUID, when the task was registered, and how it last
+/// ran.
+const HOST_FIELDS: &[&str] = &[
+    "uuid",
+    "registration_info.date",
+    "run_date",
+    "scheduled_date",
+    "exit_code",
+    "status",
+    "status_name",
+];
+
+/// The schedule of a job exported without its bytes.
+const UNKNOWN_SCHEDULE: &str = "(unknown)";
+
+/// One task as found on one host.
+struct Instance {
+    host: String,
+    name: String,
+    fields: BTreeMap<String, String>,
+}
+
+/// A distinct task and every instance of it.
+struct Group {
+    command: String,
+    schedule: String,
+    instances: Vec<Instance>,
+}
+
+/// A group as `aggregate` reports it.
+#[derive(Serialize)]
+struct Rollup {
+    /// The task names its instances have, as first written.
+    names: Vec<String>,
+    command: String,
+    schedule: String,
+    host_count: usize,
+    hosts: BTreeSet<String>,
+    instances: usize,
+    /// Each field whose value differs between instances, with how many
+    /// instances have each value.
+    variance: BTreeMap<String, BTreeMap<String, usize>>,
+    /// Whether tasks of the same name run something else elsewhere.
+    masquerading: bool,
+    /// What those tasks run.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    same_name_commands: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    exports: usize,
+    records: usize,
+    /// Records of files that failed to parse, which have no task to group.
+    failed: usize,
+    groups: Vec<Rollup>,
+}
+
+/// Reads the `exports` and prints their groups as JSON or a table. Exports
+/// ending in `.gz` are decompressed.
+pub fn run(exports: &[PathBuf], json: bool, case: Case) -> u8 {
+    let mut groups: BTreeMap<(String, String), Group> = BTreeMap::new();
+    let (mut records, mut failed) = (0, 0);
+    for export in exports {
+        let lines = match open(export) {
+            Ok(lines) => lines,
+            Err(e) => {
+                eprintln!("Unable to read {}: {}", export.display(), e);
+                return EXIT_FATAL;
+            }
+        };
+        let stem = export.file_name().unwrap_or_default().to_string_lossy();
+        let host = stem.split('.').next().unwrap_or_default().to_string();
+        let mut skipped = 0;
+        for line in lines.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    eprintln!("Unable to read {}: {}", export.display(), e);
+                    return EXIT_FATAL;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            // Summaries and other lines that are not records are skipped.
+            let record = serde_json::from_str::<Value>(&line)
+                .ok()
+                .filter(|value| value.get("parse_status").is_some())
+                .and_then(|value| serde_json::from_value::<Record>(value).ok());
+            let Some(record) = record else {
+                skipped += 1;
+                continue;
+            };
+            records += 1;
+            let host = record.origin().hostname.clone().unwrap_or_else(|| host.clone());
+            match instance(&record, host) {
+                Some((command, schedule, instance)) => {
+                    let key = (case.key(&command).into_owned(), schedule.clone());
+                    groups
+                        .entry(key)
+                        .or_insert_with(|| Group { command, schedule, instances: Vec::new() })
+                        .instances
+                        .push(instance);
+                }
+                None => failed += 1,
+            }
+        }
+        if skipped > 0 {
+            let path = export.display();
+            eprintln!("Warning: {}: skipped {} lines that are not records", path, skipped);
+        }
+    }
+    let report = Report {
+        exports: exports.len(),
+        records,
+        failed,
+        groups: rollups(groups.into_values().collect(), case),
+    };
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Unable to serialize the report: {}", e),
+        }
+    } else {
+        print_table(&report, Capabilities::detect(io::stdout().is_terminal(), None));
+    }
+    EXIT_SUCCESS
+}
+
+fn open(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    let gzip = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"));
+    let reader: Box<dyn Read> = if gzip {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+/// The command line, schedule and instance of a parsed `record`; `None`
+/// for a failure.
+fn instance(record: &Record, host: String) -> Option<(String, String, Instance)> {
+    let Record::Ok { artifact, raw, .. } = record else {
+        return None;
+    };
+    let command = artifact.command().map(|command| command.normalized()).unwrap_or_default();
+    let schedule = match artifact {
+        ParsedArtifact::Job(_) => raw
+            .as_ref()
+            .and_then(|raw| base64::engine::general_purpose::STANDARD.decode(&raw.raw_content).ok())
+            .map_or_else(|| UNKNOWN_SCHEDULE.to_string(), |data| describe(&job::schedule(&data))),
+        ParsedArtifact::Task(task) => describe(&task.schedule()),
+    };
+    let mut fields = BTreeMap::new();
+    if let Ok(value) = serde_json::to_value(artifact) {
+        flatten("", &value, &mut fields);
+    }
+    fields.retain(|field, _| !HOST_FIELDS.contains(&field.as_str()));
+    let name = task_name(record.path()).to_string();
+    Some((command, schedule, Instance { host, name, fields }))
+}
+
+/// `schedule` in words, the same for every task that runs at the same times
+/// of day, whatever day its triggers started on.
+fn describe(schedule: &Schedule) -> String {
+    let mut triggers: Vec<String> = schedule
+        .time_triggers
+        .iter()
+        .map(|trigger| {
+            let mut text = match trigger.recurrence {
+                Recurrence::Once => {
+                    format!("once at {}", date::format_unix(trigger.start.max(0) as u64))
+                }
+                recurrence => {
+                    let time = trigger.start.rem_euclid(86400);
+                    format!("{:?} at {:02}:{:02}", recurrence, time / 3600, time % 3600 / 60)
+                }
+            };
+            if let Some(repetition) = trigger.repetition {
+                text.push_str(&format!(", every {}s", repetition.interval));
+                if let Some(duration) = repetition.duration {
+                    text.push_str(&format!(" for {}s", duration));
+                }
+            }
+            text
+        })
+        .collect();
+    triggers.sort();
+    if schedule.event_triggers > 0 {
+        triggers.push(format!("{} event triggers", schedule.event_triggers));
+    }
+    if triggers.is_empty() {
+        return "none".to_string();
+    }
+    triggers.join("; ")
+}
+
+/// The groups as reported, the most widespread first, with the tasks that
+/// share a name but run different commands marked.
+fn rollups(groups: Vec<Group>, case: Case) -> Vec<Rollup> {
+    let mut commands_by_name: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for group in &groups {
+        for instance in &group.instances {
+            commands_by_name
+                .entry(case.key(&instance.name).into_owned())
+                .or_default()
+                .insert(case.key(&group.command).into_owned(), group.command.clone());
+        }
+    }
+    let mut rollups: Vec<Rollup> = groups
+        .into_iter()
+        .map(|group| {
+            let own = case.key(&group.command).into_owned();
+            let mut names: BTreeMap<String, String> = BTreeMap::new();
+            let mut same_name_commands = BTreeSet::new();
+            for instance in &group.instances {
+                let name = case.key(&instance.name).into_owned();
+                for (command, written) in &commands_by_name[&name] {
+                    if *command != own {
+                        same_name_commands.insert(written.clone());
+                    }
+                }
+                names.entry(name).or_insert_with(|| instance.name.clone());
+            }
+            let hosts: BTreeSet<String> = group.instances.iter().map(|i| i.host.clone()).collect();
+            Rollup {
+                names: names.into_values().collect(),
+                host_count: hosts.len(),
+                hosts,
+                instances: group.instances.len(),
+                variance: variance(&group.instances),
+                masquerading: !same_name_commands.is_empty(),
+                same_name_commands: same_name_commands.into_iter().collect(),
+                command: group.command,
+                schedule: group.schedule,
+            }
+        })
+        .collect();
+    rollups.sort_by(|a, b| b.host_count.cmp(&a.host_count).then_with(|| a.command.cmp(&b.command)));
+    rollups
+}
+
+/// The fields whose values are not the same in every instance, with how
+/// many instances have each value; a field an instance lacks counts as
+/// `(absent)`.
+fn variance(instances: &[Instance]) -> BTreeMap<String, BTreeMap<String, usize>> {
+    let fields: BTreeSet<&String> = instances.iter().flat_map(|i| i.fields.keys()).collect();
+    let mut variance = BTreeMap::new();
+    for field in fields {
+        let mut values: BTreeMap<String, usize> = BTreeMap::new();
+        for instance in instances {
+            let value = match instance.fields.get(field) {
+                // Strings read better without their JSON quotes.
+                Some(value) => {
+                    serde_json::from_str::<String>(value).unwrap_or_else(|_| value.clone())
+                }
+                None => "(absent)".to_string(),
+            };
+            *values.entry(value).or_default() += 1;
+        }
+        if values.len() > 1 {
+            variance.insert(field.clone(), values);
+        }
+    }
+    variance
+}
+
+fn print_table(report: &Report, terminal: Capabilities) {
+    let width = report
+        .groups
+        .iter()
+        .map(|group| group.names.join(", ").chars().count())
+        .chain([4])
+        .max()
+        .unwrap_or(4);
+    let header = format!("Hosts  Instances  {:<width$}  Command", "Name");
+    println!("{}", terminal.paint(&header, Paint::Bold));
+    for group in &report.groups {
+        let command = if group.command.is_empty() { "(none)" } else { &group.command };
+        println!(
+            "{:>5}  {:>9}  {:<width$}  {}",
+            group.host_count,
+            group.instances,
+            group.names.join(", "),
+            command
+        );
+        println!("       Schedule: {}", group.schedule);
+        println!("       Hosts: {}", group.hosts.iter().cloned().collect::<Vec<_>>().join(", "));
+        for (field, values) in &group.variance {
+            let values: Vec<String> =
+                values.iter().map(|(value, count)| format!("{} ({})", value, count)).collect();
+            println!("       Varies: {}: {}", field, values.join(", "));
+        }
+        if group.masquerading {
+            let warning = format!(
+                "Masquerading: the same name runs {} elsewhere",
+                group.same_name_commands.join(", ")
+            );
+            println!("       {}", terminal.paint(&warning, Paint::Red));
+        }
+    }
+    println!(
+        "{} records from {} exports in {} groups; {} failed to parse",
+        report.records,
+        report.exports,
+        report.groups.len(),
+        report.failed
+    );
+}
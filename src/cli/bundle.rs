@@ -0,0 +1,202 @@
+//! `compile-rules` and `--rules-bundle`: the `--rules` file and the rules
+//! of a `--rules-dir` checked once and kept in one file, for pipelines that
+//! run the tool once per artifact and would otherwise read and check every
+//! rule file on each run.
+//!
+//! A bundle is gzip-compressed JSON holding the directory lists, the rules
+//! with each regex as its pattern, the version of the tool that compiled it
+//! and the SHA-256 of every file it was compiled from. Loading it skips
+//! parsing and checking the TOML; only the regexes are compiled again. A
+//! bundle is stale when another version compiled it, when a file it was
+//! compiled from has changed, or when `*.toml` files were added to or
+//! removed from its rules directory; the rules are then read from their
+//! files as `--rules` and `--rules-dir` would, with a warning. Files that
+//! are no longer there are not held against it, so a bundle can be copied
+//! to machines without the rules.
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use jobfileparser::{Case, PathRules};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::custom_rules::{Rule, RuleSet};
+use super::rules;
+use super::{EXIT_FATAL, EXIT_SUCCESS};
+
+/// The layout of the bundle, raised when it changes.
+const FORMAT: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    format: u32,
+    parser_version: String,
+    /// The `--rules` file compiled.
+    rules_file: Option<PathBuf>,
+    /// The `--rules-dir` compiled.
+    rules_dir: Option<PathBuf>,
+    /// Every file read, with the SHA-256 of its contents.
+    sources: Vec<Source>,
+    /// The `[writable_paths]` lists of the rules file.
+    flag: Vec<String>,
+    allow: Vec<String>,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Source {
+    path: PathBuf,
+    sha256: String,
+}
+
+/// The rules as `--rules`, `--rules-dir` or `--rules-bundle` give them: the
+/// directory lists of `--detect-paths` and the rules of the directory.
+pub struct Rules {
+    pub path_rules: PathRules,
+    pub rule_set: Option<RuleSet>,
+}
+
+/// Reads the `--rules` file and the `--rules-dir` as they are, printing
+/// the warnings about the rules file on stderr.
+pub fn from_sources(
+    rules_file: Option<&Path>,
+    rules_dir: Option<&Path>,
+    case: Case,
+) -> Result<Rules, String> {
+    let path_rules = match rules_file {
+        Some(path) => {
+            let (rules, warnings) = rules::from_file(path)?;
+            for warning in warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            rules
+        }
+        None => PathRules::default(),
+    };
+    let rule_set = rules_dir.map(|dir| RuleSet::from_dir(dir, case)).transpose()?;
+    Ok(Rules { path_rules, rule_set })
+}
+
+/// `compile-rules`: checks the rules file and directory and writes their
+/// bundle to `out`.
+pub fn compile(rules_file: Option<&Path>, rules_dir: Option<&Path>, out: &Path) -> u8 {
+    let rules = match from_sources(rules_file, rules_dir, Case::default()) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return EXIT_FATAL;
+        }
+    };
+    // Absolute, so that the bundle can be used from any directory.
+    let absolute = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let (rules_file, rules_dir) = (rules_file.map(absolute), rules_dir.map(absolute));
+    let sources = source_files(rules_file.as_deref(), rules_dir.as_deref());
+    let bundle = Bundle {
+        format: FORMAT,
+        parser_version: env!("CARGO_PKG_VERSION").to_string(),
+        sources: sources.into_iter().filter_map(stamp).collect(),
+        rules_file,
+        rules_dir,
+        flag: rules.path_rules.flag,
+        allow: rules.path_rules.allow,
+        rules: rules.rule_set.map(|set| set.rules().to_vec()).unwrap_or_default(),
+    };
+    if let Err(e) = write(&bundle, out) {
+        eprintln!("Unable to write {}: {}", out.display(), e);
+        return EXIT_FATAL;
+    }
+    println!(
+        "Compiled {} rules, {} flagged and {} allowed directories from {} files into {}",
+        bundle.rules.len(),
+        bundle.flag.len(),
+        bundle.allow.len(),
+        bundle.sources.len(),
+        out.display()
+    );
+    EXIT_SUCCESS
+}
+
+fn write(bundle: &Bundle, out: &Path) -> std::io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(out)?, Compression::default());
+    encoder.write_all(&serde_json::to_vec(bundle)?)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// `--rules-bundle`: the rules of the bundle at `path`, or of the files it
+/// was compiled from if it is stale.
+pub fn load(path: &Path, case: Case) -> Result<Rules, String> {
+    let bundle = read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if let Some(reason) = stale(&bundle) {
+        eprintln!(
+            "Warning: {} is out of date ({}); reading the rules from their files",
+            path.display(),
+            reason
+        );
+        return from_sources(bundle.rules_file.as_deref(), bundle.rules_dir.as_deref(), case);
+    }
+    let rule_set = bundle.rules_dir.is_some().then(|| RuleSet::from_rules(bundle.rules, case));
+    let path_rules = PathRules { flag: bundle.flag, allow: bundle.allow, case };
+    Ok(Rules { path_rules, rule_set })
+}
+
+fn read(path: &Path) -> Result<Bundle, String> {
+    let mut json = Vec::new();
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    GzDecoder::new(file)
+        .read_to_end(&mut json)
+        .map_err(|_| "not a rules bundle; compile one with compile-rules".to_string())?;
+    serde_json::from_slice(&json).map_err(|e| format!("not a usable rules bundle: {}", e))
+}
+
+/// Why `bundle` cannot be used as it is, if it cannot.
+fn stale(bundle: &Bundle) -> Option<String> {
+    if bundle.format != FORMAT || bundle.parser_version != env!("CARGO_PKG_VERSION") {
+        return Some(format!("compiled by version {}", bundle.parser_version));
+    }
+    let files = source_files(bundle.rules_file.as_deref(), bundle.rules_dir.as_deref());
+    let recorded = |path: &Path| bundle.sources.iter().any(|source| source.path == path);
+    if let Some(added) = files.iter().find(|path| !recorded(path)) {
+        return Some(format!("{} was added", added.display()));
+    }
+    for source in &bundle.sources {
+        // Gone from a directory that is still there.
+        let removed = !files.contains(&source.path);
+        if removed && source.path.parent().is_some_and(Path::exists) {
+            return Some(format!("{} was removed", source.path.display()));
+        }
+        if let Some(current) = stamp(source.path.clone()) {
+            if current.sha256 != source.sha256 {
+                return Some(format!("{} has changed", source.path.display()));
+            }
+        }
+    }
+    None
+}
+
+/// The rules file and the `*.toml` files in the rules directory, as far as
+/// the directory is there.
+fn source_files(rules_file: Option<&Path>, rules_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = rules_file.map(Path::to_path_buf).into_iter().collect();
+    if let Some(entries) = rules_dir.and_then(|dir| fs::read_dir(dir).ok()) {
+        let mut rule_files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml") && path.is_file())
+            .collect();
+        rule_files.sort();
+        files.extend(rule_files);
+    }
+    files
+}
+
+/// `path` with the SHA-256 of its contents; `None` if it cannot be read.
+fn stamp(path: PathBuf) -> Option<Source> {
+    let data = fs::read(&path).ok()?;
+    let sha256 = Sha256::digest(&data).iter().map(|b| format!("{:02x}", b)).collect();
+    Some(Source { path, sha256 })
+}
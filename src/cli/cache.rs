@@ -0,0 +1,102 @@
+//! `--cache <DIR>`: what each file parsed to, kept in DIR under the
+//! SHA-256 of its bytes, so that running again over the same evidence with
+//! other filters or heuristics skips parsing the files it has seen. Only
+//! the artifacts are kept; records are built, filtered and checked afresh
+//! on every run.
+//!
+//! An entry records the schema version and the version of the tool that
+//! wrote it, and whether the file was parsed leniently, and is ignored and
+//! rewritten when any of them differ. Files that failed to parse or came
+//! with warnings are not cached. Nothing is cached unless `enable` was
+//! called.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use jobfileparser::record::SCHEMA_VERSION;
+use jobfileparser::{ArtifactFormat, ParsedArtifact};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+static DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+static HITS: AtomicUsize = AtomicUsize::new(0);
+static STORED: AtomicUsize = AtomicUsize::new(0);
+/// Tells apart the temporary files of entries written at the same time.
+static TEMPORARY: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    schema_version: u32,
+    parser_version: String,
+    format: String,
+    lenient: bool,
+    artifacts: Vec<ParsedArtifact>,
+}
+
+/// Starts using `dir`, creating it if need be.
+pub fn enable(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("unable to create {}: {}", dir.display(), e))?;
+    *DIR.lock().unwrap_or_else(|e| e.into_inner()) = Some(dir.to_path_buf());
+    Ok(())
+}
+
+fn dir() -> Option<PathBuf> {
+    DIR.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Where the entry for `data` is kept, when caching.
+pub fn entry_path(data: &[u8]) -> Option<PathBuf> {
+    let dir = dir()?;
+    let digest: String = Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect();
+    Some(dir.join(format!("{}.json", digest)))
+}
+
+/// The artifacts cached at `path` for a file parsed as `format`, if the
+/// entry is there and was written by this version with the same options.
+pub fn lookup(path: &Path, format: ArtifactFormat, lenient: bool) -> Option<Vec<ParsedArtifact>> {
+    let text = fs::read_to_string(path).ok()?;
+    let entry: Entry = serde_json::from_str(&text).ok()?;
+    let current = entry.schema_version == SCHEMA_VERSION
+        && entry.parser_version == env!("CARGO_PKG_VERSION")
+        && entry.format == format.name()
+        && entry.lenient == lenient;
+    if !current {
+        return None;
+    }
+    HITS.fetch_add(1, Ordering::Relaxed);
+    Some(entry.artifacts)
+}
+
+/// Keeps `artifacts` at `path`. The entry is written to a temporary file
+/// and renamed into place, so that a reader never sees half of one. A
+/// failure to write only costs parsing the file again next time.
+pub fn store(path: &Path, format: ArtifactFormat, lenient: bool, artifacts: &[ParsedArtifact]) {
+    let entry = Entry {
+        schema_version: SCHEMA_VERSION,
+        parser_version: env!("CARGO_PKG_VERSION").to_string(),
+        format: format.name().to_string(),
+        lenient,
+        artifacts: artifacts.to_vec(),
+    };
+    let Ok(json) = serde_json::to_string(&entry) else {
+        return;
+    };
+    let n = TEMPORARY.fetch_add(1, Ordering::Relaxed);
+    let temporary = path.with_extension(format!("{}-{}.tmp", std::process::id(), n));
+    if fs::write(&temporary, json).and_then(|()| fs::rename(&temporary, path)).is_ok() {
+        STORED.fetch_add(1, Ordering::Relaxed);
+    } else {
+        let _ = fs::remove_file(&temporary);
+    }
+}
+
+/// Prints how many files came from the cache and how many were added to
+/// it, when caching.
+pub fn report() {
+    if dir().is_some() {
+        let (hits, stored) = (HITS.load(Ordering::Relaxed), STORED.load(Ordering::Relaxed));
+        eprintln!("Cache: {} files read from it, {} added", hits, stored);
+    }
+}
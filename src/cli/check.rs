@@ -0,0 +1,309 @@
+//! `--check`: a schedule hygiene audit, for administrators rather than
+//! investigators. Each record gets the operational `issues` it has:
+//!
+//! - `no-time-limit` and `normal-priority`, rules in the language of
+//!   `--rules-dir` kept in `data/hygiene-checks.toml`
+//! - `missing-program`: the program the task runs is not on the volume
+//!   mounted at `--check-root`, or, without one, on the running system.
+//!   Only checked on Windows unless `--check-root` is given.
+//! - `decommissioned-server`: a UNC path names a server listed in the
+//!   `--decommissioned` file
+//!
+//! When the run ends, a summary of the estate follows: how many tasks were
+//! checked and have each issue, and the programs that more than one task
+//! starts in the same minute in the week after `--now`. Those can only be
+//! told once every task is in, so they are listed there rather than on the
+//! records.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use jobfileparser::cmdline::normalize_path;
+use jobfileparser::date::{format_unix, local_seconds};
+use jobfileparser::schedule::Schedule;
+use jobfileparser::{extract_indicators, Case, Finding, ParsedArtifact, Record};
+use serde::Serialize;
+
+use super::custom_rules::RuleSet;
+
+/// How far after `--now` runs are compared for overlaps, in seconds.
+const OVERLAP_WINDOW: i64 = 7 * 86400;
+
+/// The most runs of one task compared for overlaps, for tasks that repeat
+/// every minute or so.
+const OVERLAP_RUNS: usize = 10_000;
+
+#[derive(Clone)]
+pub struct HygieneCheck {
+    rules: RuleSet,
+    /// The servers of `--decommissioned`, as `case` compares them.
+    decommissioned: BTreeSet<String>,
+    /// The volume of `--check-root`.
+    root: Option<PathBuf>,
+    case: Case,
+    /// `--now`, in seconds since 1970.
+    now: i64,
+    checked: usize,
+    with_issues: usize,
+    /// How many records have each issue.
+    issues: BTreeMap<String, usize>,
+    /// The paths of the records that start each program, as `case` compares
+    /// them, in each minute.
+    starts: BTreeMap<(String, i64), BTreeSet<String>>,
+    /// Each program as the first record to start it names it.
+    programs: BTreeMap<String, String>,
+}
+
+/// The estate summary, the last line of `-o jsonl`.
+#[derive(Serialize)]
+struct CheckSummary<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    checked: usize,
+    with_issues: usize,
+    issues: &'a BTreeMap<String, usize>,
+    overlapping_schedules: Vec<Overlap>,
+}
+
+/// A program that several tasks start in the same minute.
+#[derive(Serialize)]
+struct Overlap {
+    program: String,
+    paths: Vec<String>,
+    /// How many minutes of the week they all start it in.
+    minutes: usize,
+    first: String,
+}
+
+impl HygieneCheck {
+    /// The checks, with the servers listed one per line in
+    /// `decommissioned`, if given, and programs looked for under `root`.
+    /// Blank lines and lines starting with `#` in the list are skipped.
+    pub fn load(
+        decommissioned: Option<&Path>,
+        root: Option<&Path>,
+        now: &str,
+        case: Case,
+    ) -> Result<HygieneCheck, String> {
+        let rules = include_str!("../../data/hygiene-checks.toml");
+        let rules = RuleSet::parse("hygiene-checks.toml", rules, case)?;
+        let servers = match decommissioned {
+            Some(path) => fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?,
+            None => String::new(),
+        };
+        let decommissioned = servers
+            .lines()
+            .map(|line| line.trim().trim_start_matches('\\'))
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|server| case.key(server).into_owned())
+            .collect();
+        Ok(HygieneCheck {
+            rules,
+            decommissioned,
+            root: root.map(Path::to_path_buf),
+            case,
+            now: local_seconds(now).unwrap_or_default(),
+            checked: 0,
+            with_issues: 0,
+            issues: BTreeMap::new(),
+            starts: BTreeMap::new(),
+            programs: BTreeMap::new(),
+        })
+    }
+
+    /// Adds the issues of `record`, whose triggers a job reads from `raw`,
+    /// and notes when it starts its program. Error records are skipped.
+    pub fn check(&mut self, record: &mut Record, raw: Option<&[u8]>) {
+        let Record::Ok { artifact, .. } = &*record else {
+            return;
+        };
+        let mut found = self.rules.matches(record);
+        found.extend(self.missing_program(artifact));
+        found.extend(self.decommissioned_server(artifact));
+        self.note_starts(record.path(), artifact, raw);
+        self.checked += 1;
+        self.with_issues += usize::from(!found.is_empty());
+        record.mark_checked();
+        for issue in found {
+            *self.issues.entry(issue.rule.to_string()).or_default() += 1;
+            record.add_issue(issue);
+        }
+    }
+
+    /// A `missing-program` issue if the program `artifact` runs is not
+    /// there. Programs named without a directory are found on the `PATH`,
+    /// and ones under a variable with no known value could be anywhere, so
+    /// neither is looked for.
+    fn missing_program(&self, artifact: &ParsedArtifact) -> Option<Finding> {
+        let program = normalize_path(&artifact.command()?.executable);
+        let relative = drive_relative(&program).filter(|_| !program.contains('%'))?;
+        let file = match &self.root {
+            Some(root) => on_volume(root, relative),
+            None if cfg!(windows) => Some(PathBuf::from(&program)),
+            None => return None,
+        };
+        if file.is_some_and(|file| file.exists()) {
+            return None;
+        }
+        Some(Finding {
+            rule: "missing-program".into(),
+            description: format!("runs {}, which does not exist", program),
+            score: Some(50),
+            ..Finding::default()
+        })
+    }
+
+    /// A `decommissioned-server` issue if a UNC path in the command line or
+    /// working directory of `artifact` names a decommissioned server, by
+    /// its name or a fully qualified one.
+    fn decommissioned_server(&self, artifact: &ParsedArtifact) -> Option<Finding> {
+        if self.decommissioned.is_empty() {
+            return None;
+        }
+        let working_directory = match artifact {
+            ParsedArtifact::Job(job) => Some(job.working_directory.as_str()),
+            ParsedArtifact::Task(task) => {
+                task.actions.exec.as_ref().and_then(|exec| exec.working_directory.as_deref())
+            }
+        };
+        let command_line = artifact.command_line();
+        let texts = command_line.as_deref().into_iter().chain(working_directory);
+        let servers: BTreeSet<String> = extract_indicators(texts)
+            .into_iter()
+            .filter_map(|indicator| {
+                let host = indicator.value.trim_start_matches('\\').split(['\\', '/']).next()?;
+                let name = host.split('.').next().unwrap_or(host);
+                let listed = [host, name]
+                    .iter()
+                    .any(|server| self.decommissioned.contains(self.case.key(server).as_ref()));
+                listed.then(|| host.to_string())
+            })
+            .collect();
+        if servers.is_empty() {
+            return None;
+        }
+        let servers: Vec<String> = servers.into_iter().collect();
+        Some(Finding {
+            rule: "decommissioned-server".into(),
+            description: format!("refers to decommissioned {}", servers.join(", ")),
+            score: Some(40),
+            ..Finding::default()
+        })
+    }
+
+    /// Notes the minutes of the week after `now` in which the task at
+    /// `path` starts its program.
+    fn note_starts(&mut self, path: &str, artifact: &ParsedArtifact, raw: Option<&[u8]>) {
+        let Some(command) = artifact.command() else {
+            return;
+        };
+        let schedule: Schedule = match artifact {
+            ParsedArtifact::Job(_) => match raw {
+                Some(raw) => jobfileparser::job::schedule(raw),
+                None => return,
+            },
+            ParsedArtifact::Task(task) => task.schedule(),
+        };
+        let program = normalize_path(&command.executable);
+        let key = self.case.key(&program).into_owned();
+        let runs = schedule.runs_between(self.now, self.now + OVERLAP_WINDOW, OVERLAP_RUNS);
+        if runs.is_empty() {
+            return;
+        }
+        self.programs.entry(key.clone()).or_insert(program);
+        for minute in runs.into_iter().map(|run| run.div_euclid(60)) {
+            self.starts.entry((key.clone(), minute)).or_default().insert(path.to_string());
+        }
+    }
+
+    /// The programs started by more than one task in the same minute, one
+    /// entry per program and set of tasks.
+    fn overlaps(&self) -> Vec<Overlap> {
+        let mut groups: BTreeMap<(&str, Vec<&str>), (usize, i64)> = BTreeMap::new();
+        for ((key, minute), paths) in &self.starts {
+            if paths.len() < 2 {
+                continue;
+            }
+            let paths = paths.iter().map(String::as_str).collect();
+            let group = groups.entry((key, paths)).or_insert((0, *minute));
+            group.0 += 1;
+        }
+        groups
+            .into_iter()
+            .map(|((key, paths), (minutes, first))| Overlap {
+                program: self.programs.get(key).cloned().unwrap_or_default(),
+                paths: paths.into_iter().map(str::to_string).collect(),
+                minutes,
+                first: format_unix((first * 60).max(0) as u64),
+            })
+            .collect()
+    }
+
+    /// The estate summary as a JSON line, for `-o jsonl`.
+    pub fn summary_json(&self) -> Option<String> {
+        let summary = CheckSummary {
+            kind: "check_summary",
+            checked: self.checked,
+            with_issues: self.with_issues,
+            issues: &self.issues,
+            overlapping_schedules: self.overlaps(),
+        };
+        serde_json::to_string(&summary).ok()
+    }
+
+    /// The estate summary as text.
+    pub fn summary_text(&self) -> String {
+        let mut text = format!("{} of {} tasks checked have issues\n", self.with_issues, self.checked);
+        for (rule, count) in &self.issues {
+            text.push_str(&format!("  {}: {}\n", rule, count));
+        }
+        let overlaps = self.overlaps();
+        if !overlaps.is_empty() {
+            text.push_str("Programs started by several tasks in the same minute:\n");
+        }
+        for overlap in overlaps {
+            text.push_str(&format!(
+                "  {}: {}, in {} minutes from {}\n",
+                overlap.program,
+                overlap.paths.join(", "),
+                overlap.minutes,
+                overlap.first
+            ));
+        }
+        text
+    }
+}
+
+/// `path` without its drive, as `Windows\notepad.exe` for
+/// `C:\Windows\notepad.exe`; `None` for a path that does not start with a
+/// drive.
+fn drive_relative(path: &str) -> Option<&str> {
+    let bytes = path.as_bytes();
+    let drive = bytes.len() > 3 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == b":\\";
+    drive.then(|| &path[3..])
+}
+
+/// Where `relative`, a path below the root of a Windows volume, is on the
+/// volume mounted at `root`. Names are matched without regard to case, as
+/// Windows does, preferring an exact match. `None` if a directory on the
+/// way is missing.
+fn on_volume(root: &Path, relative: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for name in relative.split('\\').filter(|name| !name.is_empty()) {
+        let exact = path.join(name);
+        if exact.exists() {
+            path = exact;
+            continue;
+        }
+        let mut matches: Vec<PathBuf> = fs::read_dir(&path)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(name))
+            .map(|entry| entry.path())
+            .collect();
+        matches.sort();
+        path = matches.into_iter().next().unwrap_or(exact);
+    }
+    Some(path)
+}
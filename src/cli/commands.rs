@@ -0,0 +1,1265 @@
+//! The work behind each subcommand. Every function returns the process exit
+//! code.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use jobfileparser::consts;
+use jobfileparser::convert::{job_to_task, job_to_task_losses, task_to_job, task_to_job_losses};
+use jobfileparser::fold::fold;
+use jobfileparser::sniff::{sniff, Sniff, SNIFF_LENGTH};
+use jobfileparser::{
+    carve_cancellable, list_dir, parse_file, read_file_limited, ArtifactFormat, Case,
+    DateStyle, DetectOptions, Error, Input, Job, JobBuilder, ParsedArtifact, PathRules, ScanOptions,
+    Task, UuidFormat, Warning, WarningCode, UUID,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::cache;
+use super::check::HygieneCheck;
+use super::csv::CsvStyle;
+use super::custom_rules::RuleSet;
+use super::output::{Format, Output};
+use super::relative;
+use super::rollup::Rollup;
+use super::schedlog::SchedLogCheck;
+use super::sidecar::Sidecars;
+use super::sink::Destination;
+use super::source;
+use super::terminal::Capabilities;
+use super::timezone::Zone;
+use super::inline;
+use super::interrupt;
+use super::live;
+use super::timings::{self, Stage};
+use super::{EXIT_FATAL, EXIT_INTERRUPTED, EXIT_PARSE_FAILURE, EXIT_SUCCESS};
+
+/// Output options after flags, environment and config file are merged.
+pub struct Settings {
+    pub format: Format,
+    pub detect: bool,
+    pub fail_on_findings: bool,
+    /// Treat records with warnings as failures for the exit code.
+    pub fail_on_warnings: bool,
+    pub exit_zero_always: bool,
+    /// Embed each file's bytes in its record (JSON formats only).
+    pub embed_raw: bool,
+    /// Report unrecognized task XML elements on stderr.
+    pub verbose: bool,
+    /// How the text format prints dates.
+    pub date_style: DateStyle,
+    /// How the text and JSON formats write a job's UUID.
+    pub uuid_format: UuidFormat,
+    /// `YYYY-MM-DDTHH:MM:SS` to judge whether a job was due.
+    pub now: String,
+    /// Add each file's `absolute_path` to JSON records.
+    pub absolute_path: bool,
+    /// Name files that took longer than this on stderr.
+    pub slow_threshold: Option<Duration>,
+    /// Only write jobs that were due but never ran, and failures.
+    pub never_ran: bool,
+    /// Only write tasks whose effective state is this, and failures.
+    pub enabled_filter: Option<bool>,
+    /// Only write artifacts known to have no triggers, and failures.
+    pub no_triggers: bool,
+    /// Only write artifacts with more than one action, and failures.
+    pub multi_action: bool,
+    /// Only write records with findings, and count the rest.
+    pub only_findings: bool,
+    /// Only write jobs with this UUID, and failures.
+    pub find_uuid: Option<UUID>,
+    /// Skip artifacts identical to one already written.
+    pub dedupe: bool,
+    /// Time each stage and print the totals on stderr.
+    pub timings: bool,
+    /// Print the most common indicators on stderr at the end.
+    pub stats: bool,
+    /// Count files done and failures on stderr as the run goes.
+    pub progress: bool,
+    /// Limits for the heuristics run by `detect`.
+    pub detect_options: DetectOptions,
+    /// The `coverage` below which a job gets a `LowCoverage` warning.
+    pub min_coverage: f64,
+    /// The directory lists for `--detect-paths`; `None` without it.
+    pub path_rules: Option<PathRules>,
+    /// The rules of `--rules-dir`; `None` without it.
+    pub custom_rules: Option<RuleSet>,
+    /// How accounts, task names and paths are matched.
+    pub case: Case,
+    /// The log for `--schedlog`; `None` without it.
+    pub schedlog: Option<SchedLogCheck>,
+    /// The checks of `--check`; `None` without it.
+    pub check: Option<HygieneCheck>,
+    /// Classify tasks against the built-in knowledge base, and leave out
+    /// unmodified Microsoft defaults without findings.
+    pub classify: bool,
+    /// Read each file's owner and DACL, for `--acl`. Only on Windows.
+    pub acl: bool,
+    /// Delimiter and quoting for `-o csv`.
+    pub csv: CsvStyle,
+    /// Where `-o csv` writes a row per trigger; `None` without
+    /// `--csv-triggers`.
+    pub csv_triggers: Option<PathBuf>,
+    /// Where `-o csv` writes a row per action; `None` without
+    /// `--csv-actions`.
+    pub csv_actions: Option<PathBuf>,
+    /// The zone of the local times inside artifacts, for the timeline
+    /// formats; `None` for the others.
+    pub timezone: Option<Zone>,
+    /// Drop the decoration around text records.
+    pub no_banner: bool,
+    /// Drop the CSV and group table header rows.
+    pub no_header: bool,
+    /// Color, width and box drawing for the text format and the browser.
+    pub terminal: Capabilities,
+    /// How many characters of a string from the file are printed; `None`
+    /// for all of them.
+    pub max_string_length: Option<usize>,
+    /// The `-w` file; stdout if `None`.
+    pub write: Option<Destination>,
+    /// Where `--manifest` writes the manifest of the run.
+    pub manifest: Option<PathBuf>,
+    /// Where `--cache` keeps parsed artifacts.
+    pub cache: Option<PathBuf>,
+    /// Where `-o taskxml-bundle` writes a file per task instead of the
+    /// bundle.
+    pub split_out_dir: Option<PathBuf>,
+}
+
+impl Settings {
+    /// Finishes `output`, opening the browser for `Format::Collect`, and
+    /// returns the exit code.
+    fn finish(&self, mut output: Output, fatal: bool) -> u8 {
+        output.finish();
+        if output.interrupted() {
+            return EXIT_INTERRUPTED;
+        }
+        let code = if self.exit_zero_always {
+            EXIT_SUCCESS
+        } else if fatal {
+            EXIT_FATAL
+        } else {
+            output.exit_code(self.fail_on_findings, self.fail_on_warnings)
+        };
+        #[cfg(feature = "tui")]
+        if self.format == Format::Collect {
+            if let Err(e) = super::tui::run(output.into_records(), self.terminal) {
+                eprintln!("Error: {}", e);
+                return EXIT_FATAL;
+            }
+        }
+        code
+    }
+}
+
+/// Reads a `--files-from` list: one path per line, from `source` or stdin
+/// for `-`. Blank lines and lines starting with `#` are skipped, as is a
+/// leading byte-order mark. Relative paths are resolved against `base_dir`.
+pub fn read_path_list(source: &Path, base_dir: Option<&Path>) -> io::Result<Vec<PathBuf>> {
+    let mut data = Vec::new();
+    if source == Path::new("-") {
+        io::stdin().read_to_end(&mut data)?;
+    } else {
+        data = fs::read(source)?;
+    }
+    let text = String::from_utf8_lossy(&data);
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(&text);
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            // Lists produced on Windows may use either separator.
+            #[cfg(windows)]
+            let line = line.replace('/', "\\");
+            let path = PathBuf::from(line);
+            match base_dir {
+                Some(base) if path.is_relative() => base.join(path),
+                _ => path,
+            }
+        })
+        .collect())
+}
+
+/// A file read by `load`: its bytes and what they parsed to.
+pub struct Loaded {
+    /// The file read, which records may show under another name, such as
+    /// its Windows path for `scan --image-root`.
+    pub source: PathBuf,
+    pub data: Input,
+    /// What the file was parsed as.
+    pub format: ArtifactFormat,
+    /// One result for a binary job, one per `<Task>` element for task XML.
+    pub results: Vec<Result<ParsedArtifact, Error>>,
+    /// The extension names the other format than the content holds.
+    pub extension_mismatch: bool,
+    /// What reading the file ran into, for every record from it: the
+    /// mismatch, content skipped after the last task, and what a lenient
+    /// parse read past. Each is also printed on stderr.
+    pub warnings: Vec<Warning>,
+    /// How long reading and parsing took, measured on the thread that did
+    /// it.
+    pub elapsed: Duration,
+}
+
+/// Reads and parses `path`, skipping empty and oversized files like
+/// `parse_file_limited`. Malformed content after the tasks of a task XML
+/// file is reported on stderr and otherwise ignored.
+///
+/// The file is parsed as its content says unless `options.strict_extension`
+/// is set. A file whose extension says otherwise is warned about on stderr,
+/// since renaming a task is a way to slip it past extension-based triage.
+pub fn load(path: &Path, options: &ScanOptions) -> Result<Loaded, Error> {
+    let start = Instant::now();
+    let (data, read) =
+        timings::time(Stage::Read, || read_file_limited(path, options.max_file_size));
+    let mut loaded = load_data(path, data?, Some(ArtifactFormat::for_path(path)), read, options)?;
+    loaded.elapsed = start.elapsed();
+    Ok(loaded)
+}
+
+/// Parses `data`, given on the command line by `--hex` or `--b64`, like
+/// `load` parses a file, and reports it as `inline::SOURCE`. Having no
+/// name, it is always parsed as its content says.
+pub fn load_inline(data: Vec<u8>, options: &ScanOptions) -> Result<Loaded, Error> {
+    if data.is_empty() {
+        return Err(Error::EmptyFile);
+    }
+    let start = Instant::now();
+    let path = Path::new(inline::SOURCE);
+    let mut loaded = load_data(path, Input::Buffered(data), None, Duration::ZERO, options)?;
+    loaded.elapsed = start.elapsed();
+    Ok(loaded)
+}
+
+/// Parses `data`, read from `path` in `read`. `named` is the format the
+/// file name says, `None` for input without a name.
+fn load_data(
+    path: &Path,
+    data: Input,
+    named: Option<ArtifactFormat>,
+    read: Duration,
+    options: &ScanOptions,
+) -> Result<Loaded, Error> {
+    let format = match named {
+        Some(named) if options.strict_extension => named,
+        _ => ArtifactFormat::sniff(&data),
+    };
+    let extension_mismatch = named.is_some_and(|named| named != format);
+    let mut warnings = Vec::new();
+    if let Some(named) = named.filter(|_| extension_mismatch) {
+        let message = format!(
+            "named like {} but holds {}; parsing it as {}",
+            format_description(named),
+            format_description(format),
+            format_description(format)
+        );
+        eprintln!("Warning: {} is {}", path.display(), message);
+        warnings.push(Warning::new(WarningCode::ExtensionMismatch, message));
+    }
+    let cached = cache::entry_path(&data);
+    let hit = cached.as_deref().and_then(|entry| cache::lookup(entry, format, options.lenient));
+    if let Some(artifacts) = hit {
+        return Ok(Loaded {
+            source: path.to_path_buf(),
+            data,
+            format,
+            results: artifacts.into_iter().map(Ok).collect(),
+            extension_mismatch,
+            warnings,
+            elapsed: Duration::ZERO,
+        });
+    }
+    // What parsing warns about is not cached, so neither is the file.
+    let unwarned = warnings.len();
+    let stage = match format {
+        ArtifactFormat::Xml => Stage::Xml,
+        ArtifactFormat::Binary => Stage::Binary,
+    };
+    let (results, parse) = timings::time(stage, || match format {
+        ArtifactFormat::Xml => match Task::all_from_bytes(&data) {
+            Ok(list) => {
+                if let Some(e) = list.trailing_error {
+                    eprintln!(
+                        "Warning: ignoring the rest of {} after task {}: {}",
+                        path.display(),
+                        list.tasks.len(),
+                        e
+                    );
+                    let message =
+                        format!("ignoring the rest after task {}: {}", list.tasks.len(), e);
+                    let mut warning = Warning::new(WarningCode::TrailingContent, message);
+                    warning.offset = e.offset();
+                    warnings.push(warning);
+                }
+                list.tasks
+                    .into_iter()
+                    .map(|task| task.map(ParsedArtifact::Task))
+                    .collect()
+            }
+            Err(e) => vec![Err(e)],
+        },
+        ArtifactFormat::Binary => {
+            let (result, recovered) = options.parse_with_warnings(path, &data);
+            for e in recovered {
+                eprintln!("Warning: {}: {}; reading what there is", path.display(), e);
+                warnings.push(Warning::recovered(&e));
+            }
+            vec![result]
+        }
+    });
+    timings::trace(path, data.len(), stage, read, parse);
+    if let Some(entry) = cached.filter(|_| warnings.len() == unwarned) {
+        let artifacts: Result<Vec<ParsedArtifact>, &Error> =
+            results.iter().map(|result| result.as_ref().cloned()).collect();
+        if let Ok(artifacts) = artifacts {
+            cache::store(&entry, format, options.lenient, &artifacts);
+        }
+    }
+    Ok(Loaded {
+        source: path.to_path_buf(),
+        data,
+        format,
+        results,
+        extension_mismatch,
+        warnings,
+        elapsed: Duration::ZERO,
+    })
+}
+
+fn format_description(format: ArtifactFormat) -> &'static str {
+    match format {
+        ArtifactFormat::Binary => "a binary job",
+        ArtifactFormat::Xml => "task XML",
+    }
+}
+
+/// Parses `paths`, then the `inline` bytes of `--hex` or `--b64`, and
+/// writes their records. With `show_source`, the files are printed after
+/// the records, once the output is finished; see `source::print_source`.
+/// With `relative_paths`, records name the files relative to the deepest
+/// directory they share.
+pub fn parse(
+    paths: &[PathBuf],
+    inline: Option<Vec<u8>>,
+    options: &ScanOptions,
+    settings: &Settings,
+    show_source: bool,
+    relative_paths: bool,
+) -> u8 {
+    interrupt::install();
+    let mut output = match Output::new(settings) {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return EXIT_FATAL;
+        }
+    };
+    output.set_total(paths.len() + usize::from(inline.is_some()));
+    let mut sources = Vec::new();
+    let root = relative_paths.then(|| relative::common_ancestor(paths)).flatten();
+    let inputs = paths.iter().map(|path| (path.as_path(), None));
+    for (path, data) in inputs.chain(inline.map(|data| (Path::new(inline::SOURCE), Some(data)))) {
+        if interrupt::interrupted() {
+            output.interrupt();
+            break;
+        }
+        output.set_source(data.is_none().then_some(path));
+        let loaded = match data {
+            Some(data) => load_inline(data, options),
+            None => load(path, options),
+        };
+        if let (true, Ok(loaded)) = (show_source, &loaded) {
+            sources.push((loaded.format, loaded.data.to_vec()));
+        }
+        let shown = root.as_deref().and_then(|root| relative::relative_to(root, path));
+        output.write_loaded(shown.as_deref().map_or(path, Path::new), loaded, None);
+    }
+    let code = settings.finish(output, false);
+    for (format, data) in sources {
+        source::print_source(format, &data);
+    }
+    code
+}
+
+/// `parse --fragment`: prints the section of the report for each of
+/// `paths`, then the `inline` bytes, read as a fragment of task XML, with the
+/// warnings about its schedule.
+pub fn parse_fragments(
+    paths: &[PathBuf],
+    inline: Option<Vec<u8>>,
+    max_file_size: u64,
+    settings: &Settings,
+) -> u8 {
+    let mut code = EXIT_SUCCESS;
+    let total = paths.len() + usize::from(inline.is_some());
+    let inputs = paths.iter().map(|path| (path.as_path(), None));
+    let inline = inline.map(|data| (Path::new(inline::SOURCE), Some(data)));
+    for (i, (path, data)) in inputs.chain(inline).enumerate() {
+        let data = match data {
+            Some(data) => Ok(data),
+            None => read_file_limited(path, max_file_size).map(|input| input.to_vec()),
+        };
+        let fragment = match data.and_then(|data| Task::fragment_from_bytes(&data)) {
+            Ok(fragment) => fragment,
+            Err(e) => {
+                eprintln!("Error: {}: {}", path.display(), e);
+                code = EXIT_PARSE_FAILURE;
+                continue;
+            }
+        };
+        if total > 1 {
+            if i > 0 {
+                println!();
+            }
+            println!("# {}", path.display());
+        }
+        print!("{}", fragment.format_with(settings.date_style));
+        for warning in fragment.task.schedule_warnings() {
+            println!("Warning [{}]: {}", warning.code.name(), warning.message);
+        }
+    }
+    code
+}
+
+pub struct ScanControls {
+    pub recursive: bool,
+    pub max_file_size: u64,
+    /// Parse files as their extension says rather than their content.
+    pub strict_extension: bool,
+    /// Cut job strings that run past the end of the file instead of failing.
+    pub lenient: bool,
+    #[cfg(feature = "watch")]
+    pub watch: bool,
+    /// Read and parse files on this many threads.
+    #[cfg(feature = "parallel")]
+    pub threads: usize,
+    /// Stop after this many records.
+    pub limit: Option<usize>,
+    /// Report what would be parsed, as which format, without parsing.
+    pub list_only: bool,
+    /// Print a per-user rollup instead of the records.
+    pub by_user: bool,
+    /// Group `DOMAIN\user` and `user@domain` under `user`.
+    pub strip_domain: bool,
+    /// Group records by this many leading directories under the scan root.
+    pub group_by_dir: Option<usize>,
+    /// The scan root is a mounted Windows volume: scan its task directories
+    /// and report paths as they are on Windows.
+    pub image_root: bool,
+    /// Report files relative to the scan root.
+    pub relative_paths: bool,
+    /// Scan the task directories of the running system.
+    pub live: bool,
+    /// Scan the running system's task directories when given, for
+    /// `--i-know-this-is-live`.
+    pub live_acknowledged: bool,
+    /// A SOFTWARE hive whose TaskCache the tasks found are checked against.
+    #[cfg(feature = "hive")]
+    pub hive: Option<PathBuf>,
+}
+
+/// Where Task Scheduler 2.0 keeps task XML on Windows.
+const XML_TASK_DIR: &str = "C:\\Windows\\System32\\Tasks";
+
+/// The task directories Task Scheduler keeps on the system drive, below
+/// `Windows`, with their path on Windows.
+const IMAGE_TASK_DIRS: [(&[&str], &str); 2] =
+    [(&["Tasks"], "C:\\Windows\\Tasks"), (&["System32", "Tasks"], XML_TASK_DIR)];
+
+/// A directory to scan, and the Windows path its files are reported under
+/// for `--image-root`.
+struct ScanRoot {
+    dir: PathBuf,
+    windows: Option<&'static str>,
+    /// Report files relative to `dir`, for `--relative-paths`.
+    relative: bool,
+}
+
+impl ScanRoot {
+    /// The path to report for `path`, a file below `dir`.
+    fn display(&self, path: &Path) -> PathBuf {
+        let Some(windows) = self.windows else {
+            let relative = self.relative.then(|| relative::relative_to(&self.dir, path));
+            return relative.flatten().map_or_else(|| path.to_path_buf(), PathBuf::from);
+        };
+        let relative = path.strip_prefix(&self.dir).unwrap_or(path);
+        let mut shown = windows.to_string();
+        for component in relative.components() {
+            shown.push('\\');
+            shown.push_str(&component.as_os_str().to_string_lossy());
+        }
+        PathBuf::from(shown)
+    }
+}
+
+/// The task directories of the Windows volume mounted at `root`. Names are
+/// matched without regard to case, preferring an exact match, since a
+/// volume mounted from an image keeps whatever case Windows wrote.
+fn image_roots(root: &Path) -> Result<Vec<ScanRoot>, String> {
+    let windows = child_dir(root, "Windows")
+        .ok_or_else(|| format!("{} has no Windows directory", root.display()))?;
+    let roots: Vec<ScanRoot> = IMAGE_TASK_DIRS
+        .iter()
+        .filter_map(|(names, shown)| {
+            let dir = names.iter().try_fold(windows.clone(), |dir, name| child_dir(&dir, name))?;
+            Some(ScanRoot {
+                dir,
+                windows: Some(shown),
+                relative: false,
+            })
+        })
+        .collect();
+    if roots.is_empty() {
+        return Err(format!("{} has no task directories", windows.display()));
+    }
+    Ok(roots)
+}
+
+fn child_dir(dir: &Path, name: &str) -> Option<PathBuf> {
+    let exact = dir.join(name);
+    if exact.is_dir() {
+        return Some(exact);
+    }
+    let mut matches: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(name))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    matches.sort();
+    matches.into_iter().next()
+}
+
+/// Scans `dirs`, or with `--image-root` the task directories of the image
+/// mounted at the single one given.
+pub fn scan(dirs: &[PathBuf], controls: &ScanControls, settings: &Settings) -> u8 {
+    let mut options = ScanOptions {
+        // Task Scheduler 2.0 keeps tasks in folders.
+        recursive: controls.recursive || controls.image_root || controls.live,
+        max_file_size: controls.max_file_size,
+        strict_extension: controls.strict_extension,
+        lenient: controls.lenient,
+        ..ScanOptions::default()
+    };
+    // Tasks under System32\Tasks are named without an extension, and a
+    // directory checked against a hive, or whose tasks are classified, is
+    // taken to be a copy of it.
+    #[cfg_attr(not(feature = "hive"), allow(unused_mut))]
+    let mut task_dirs = controls.image_root || controls.live || settings.classify;
+    #[cfg(feature = "hive")]
+    {
+        task_dirs |= controls.hive.is_some();
+    }
+    if task_dirs {
+        options.extensions.push(String::new());
+    }
+    let system_root = live::system_root(|name| env::var(name).ok());
+    let roots = if controls.image_root {
+        match image_roots(&dirs[0]) {
+            Ok(roots) => roots,
+            Err(e) => {
+                eprintln!("{}", e);
+                return EXIT_FATAL;
+            }
+        }
+    } else if controls.live {
+        let Some(system_root) = &system_root else {
+            eprintln!("Error: --live needs %SystemRoot%, which only Windows sets");
+            return EXIT_FATAL;
+        };
+        live::TASK_DIRS
+            .iter()
+            .map(|parts| parts.iter().fold(system_root.clone(), |dir, part| dir.join(part)))
+            .filter(|dir| dir.is_dir())
+            .map(|dir| ScanRoot {
+                dir,
+                windows: None,
+                relative: controls.relative_paths,
+            })
+            .collect()
+    } else {
+        distinct_roots(dirs, options.recursive)
+            .into_iter()
+            .map(|dir| ScanRoot {
+                dir,
+                windows: None,
+                relative: controls.relative_paths,
+            })
+            .collect()
+    };
+    if !controls.live && !check_live(&roots, options.recursive, system_root.as_deref(), controls) {
+        return EXIT_FATAL;
+    }
+    // The paths of an image's or the system's directories already tell them
+    // apart.
+    let tally_roots = !controls.image_root && !controls.live && roots.len() > 1;
+    #[cfg(feature = "watch")]
+    if controls.watch && roots.len() > 1 {
+        eprintln!("Error: --watch takes a single directory");
+        return EXIT_FATAL;
+    }
+    #[cfg(feature = "hive")]
+    let task_cache = match &controls.hive {
+        Some(hive) => {
+            // Task paths are relative to System32\Tasks, or to the scanned
+            // directory, taken to be a copy of it.
+            let task_dirs = roots
+                .iter()
+                .filter(|root| root.windows.is_none_or(|windows| windows == XML_TASK_DIR))
+                .map(|root| {
+                    root.windows.map_or_else(|| root.dir.display().to_string(), str::to_string)
+                })
+                .collect();
+            match super::hive::TaskCacheCheck::load(hive, task_dirs, settings.case) {
+                Ok(check) => Some(check),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return EXIT_FATAL;
+                }
+            }
+        }
+        None => None,
+    };
+    interrupt::install();
+    let mut output = match Output::new(settings) {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return EXIT_FATAL;
+        }
+    };
+    #[cfg(feature = "hive")]
+    if let Some(check) = task_cache {
+        output.check_task_cache(check);
+    }
+    #[cfg(feature = "watch")]
+    if controls.watch {
+        let ok = super::watch::watch(&roots[0].dir, &options, &mut output);
+        return settings.finish(output, !ok);
+    }
+    if controls.by_user {
+        return scan_by_user(&roots, &options, controls, settings);
+    }
+    if output.shows_progress() && !controls.list_only {
+        // A walk of its own, since the files are read as they are listed.
+        let mut total = 0;
+        for root in &roots {
+            let _ = list_dir(&root.dir, &options, |_, result| {
+                total += usize::from(result.is_ok());
+                ControlFlow::Continue(())
+            });
+        }
+        output.set_total(total);
+    }
+    let limit = controls.limit.unwrap_or(usize::MAX);
+    let mut written = 0;
+    let mut next = |records: usize| {
+        written += records;
+        if written < limit && !interrupt::interrupted() {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    };
+    let mut fatal = false;
+    for root in &roots {
+        if limit == 0 || next(0).is_break() {
+            break;
+        }
+        if tally_roots {
+            output.start_root(&root.dir.display().to_string());
+        }
+        let mut sidecars = Sidecars::new(&root.dir);
+        let result = if let Some(depth) = controls.group_by_dir {
+            let list_only = controls.list_only;
+            let sidecars = &mut sidecars;
+            scan_grouped(root, depth, &options, list_only, sidecars, &mut output, &mut next)
+        } else if controls.list_only {
+            list_dir(&root.dir, &options, |path, result| {
+                let format = result.map(|()| ArtifactFormat::for_path(path));
+                output.write_listing(&root.display(path), format);
+                next(1)
+            })
+        } else {
+            scan_root(root, &options, controls, &mut sidecars, &mut output, &mut next)
+        };
+        if let Err(e) = result {
+            eprintln!("Unable to read directory {}: {}", root.dir.display(), e);
+            fatal = true;
+        }
+    }
+    if interrupt::interrupted() {
+        output.interrupt();
+    }
+    settings.finish(output, fatal)
+}
+
+/// Whether scanning `roots` may go ahead: not if it would read a task
+/// directory of the running system, unless `--i-know-this-is-live` was
+/// given. Either way, each such directory is named on stderr.
+fn check_live(
+    roots: &[ScanRoot],
+    recursive: bool,
+    system_root: Option<&Path>,
+    controls: &ScanControls,
+) -> bool {
+    for root in roots {
+        let Some(tasks) = live::live_task_dir(&root.dir, recursive, system_root, &live::Disk) else {
+            continue;
+        };
+        if controls.live_acknowledged {
+            eprintln!(
+                "Warning: scanning {}, a task directory of this system, as \
+                 --i-know-this-is-live was given",
+                tasks.display()
+            );
+            continue;
+        }
+        eprintln!(
+            "WARNING: {} is a task directory of the system running this tool.\n\
+             Its tasks are this machine's, not the evidence's, and would be mixed into \
+             the output.\n\
+             Give --i-know-this-is-live to scan it anyway, or --live to scan this \
+             system on purpose.",
+            tasks.display()
+        );
+        return false;
+    }
+    true
+}
+
+/// `dirs` without those given twice, or, if `recursive`, inside another;
+/// each one skipped is named on stderr. Directories are compared by their
+/// canonical path, so a link to one scanned is skipped too.
+fn distinct_roots(dirs: &[PathBuf], recursive: bool) -> Vec<PathBuf> {
+    let resolved: Vec<PathBuf> = dirs
+        .iter()
+        .map(|dir| fs::canonicalize(dir).unwrap_or_else(|_| relative::absolute(dir)))
+        .collect();
+    let mut kept = Vec::new();
+    for (i, dir) in dirs.iter().enumerate() {
+        let covering = resolved.iter().enumerate().find_map(|(j, other)| {
+            let inside = relative::relative_to(other, &resolved[i])?;
+            let covers = if inside.is_empty() { j < i } else { recursive };
+            covers.then_some((j, inside.is_empty()))
+        });
+        match covering {
+            Some((_, true)) => {
+                eprintln!("Warning: {} is given more than once; scanning it once", dir.display());
+            }
+            Some((j, false)) => eprintln!(
+                "Warning: {} is inside {}, which is scanned too; skipping it",
+                dir.display(),
+                dirs[j].display()
+            ),
+            None => kept.push(dir.clone()),
+        }
+    }
+    kept
+}
+
+/// Reads and writes the files below `root` as they are listed, or as they
+/// finish on the worker threads with `--threads`.
+fn scan_root(
+    root: &ScanRoot,
+    options: &ScanOptions,
+    controls: &ScanControls,
+    sidecars: &mut Sidecars,
+    output: &mut Output,
+    mut next: impl FnMut(usize) -> ControlFlow<()>,
+) -> Result<(), Error> {
+    let mut write = |path: &Path, loaded| {
+        output.set_origin(sidecars.origin(path));
+        output.set_source(Some(path));
+        next(output.write_loaded(&root.display(path), loaded, None))
+    };
+    #[cfg(feature = "parallel")]
+    if controls.threads > 1 {
+        return super::parallel::load_dir(&root.dir, options, controls.threads, write);
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = controls;
+    list_dir(&root.dir, options, |path, result| {
+        write(path, result.and_then(|()| load(path, options)))
+    })
+}
+
+/// Lists `dir` first, then writes its files group by group, the groups in
+/// alphabetical order and the files within a group in walk order.
+fn scan_grouped(
+    root: &ScanRoot,
+    depth: usize,
+    options: &ScanOptions,
+    list_only: bool,
+    sidecars: &mut Sidecars,
+    output: &mut Output,
+    mut next: impl FnMut(usize) -> ControlFlow<()>,
+) -> Result<(), Error> {
+    let mut entries = Vec::new();
+    list_dir(&root.dir, options, |path, result| {
+        // A failed entry is a directory that could not be read, so all of
+        // its components count.
+        let group = group_key(&root.dir, path, depth, result.is_err());
+        entries.push((group, path.to_path_buf(), result));
+        ControlFlow::Continue(())
+    })?;
+    entries.sort_by(|a, b| a.0.to_lowercase().cmp(&b.0.to_lowercase()).then(a.0.cmp(&b.0)));
+    for (group, path, result) in entries {
+        output.start_group(&group);
+        let written = if list_only {
+            let format = result.map(|()| ArtifactFormat::for_path(&path));
+            output.write_listing(&root.display(&path), format);
+            1
+        } else {
+            let loaded = result.and_then(|()| load(&path, options));
+            output.set_origin(sidecars.origin(&path));
+            output.set_source(Some(&path));
+            output.write_loaded(&root.display(&path), loaded, None)
+        };
+        if next(written).is_break() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The first `depth` directories of `path` under `root`, joined with `/`,
+/// or `.` for files directly in `root`.
+fn group_key(root: &Path, path: &Path, depth: usize, is_dir: bool) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let mut dirs: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if !is_dir {
+        dirs.pop();
+    }
+    dirs.truncate(depth);
+    if dirs.is_empty() {
+        ".".to_string()
+    } else {
+        dirs.join("/")
+    }
+}
+
+fn scan_by_user(
+    roots: &[ScanRoot],
+    options: &ScanOptions,
+    controls: &ScanControls,
+    settings: &Settings,
+) -> u8 {
+    let mut rollup = Rollup::new(controls.strip_domain, settings.case);
+    let mut failed = 0;
+    let mut seen = 0;
+    let limit = controls.limit.unwrap_or(usize::MAX);
+    for root in roots {
+        // A limit of 0 still reads a file, as the walk stops after one.
+        if seen >= limit.max(1) || interrupt::interrupted() {
+            break;
+        }
+        let result = list_dir(&root.dir, options, |path, result| {
+            let results = match result.and_then(|()| load(path, options)) {
+                Ok(loaded) => loaded.results,
+                Err(e) => vec![Err(e)],
+            };
+            for result in results {
+                match result {
+                    Ok(artifact) => rollup.add(&artifact),
+                    Err(e) => {
+                        eprintln!("Unable to process file {}: {}", path.display(), e);
+                        failed += 1;
+                    }
+                }
+            }
+            seen += 1;
+            if seen < limit && !interrupt::interrupted() {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("Unable to read directory {}: {}", root.dir.display(), e);
+            return if settings.exit_zero_always {
+                EXIT_SUCCESS
+            } else {
+                EXIT_FATAL
+            };
+        }
+    }
+    rollup.print(settings.format);
+    if interrupt::interrupted() {
+        eprintln!("Interrupted after {} files; the rollup covers only those", seen);
+        return EXIT_INTERRUPTED;
+    }
+    if failed > 0 && !settings.exit_zero_always {
+        EXIT_PARSE_FAILURE
+    } else {
+        EXIT_SUCCESS
+    }
+}
+
+/// Reports each carved artifact under `<image>@<offset>`. The image is
+/// memory-mapped, or read from stdin for `-`.
+pub fn carve(image: &Path, settings: &Settings) -> u8 {
+    let data = if image == Path::new("-") {
+        Input::read(io::stdin().lock())
+    } else {
+        Input::map(image)
+    };
+    let data = match data {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Unable to read {}: {}", image.display(), e);
+            return if settings.exit_zero_always {
+                EXIT_SUCCESS
+            } else {
+                EXIT_FATAL
+            };
+        }
+    };
+    interrupt::install();
+    let mut output = match Output::new(settings) {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return EXIT_FATAL;
+        }
+    };
+    output.add_input(image, &data);
+    // Ctrl-C stops the search; what it found so far is still written.
+    let (found, partial) = carve_cancellable(&data, interrupt::token());
+    for carved in found {
+        if interrupt::interrupted() && !partial.cancelled {
+            break;
+        }
+        let location = PathBuf::from(format!("{}@{:#x}", image.display(), carved.offset));
+        let raw = &data[carved.offset..carved.offset + carved.len];
+        output.write_raw(&location, Ok(carved.artifact), Some(raw));
+    }
+    if partial.cancelled {
+        eprintln!("Searched {} of {} bytes before Ctrl-C", partial.completed, data.len());
+    }
+    if interrupt::interrupted() {
+        output.interrupt();
+    }
+    settings.finish(output, false)
+}
+
+/// Writes `data` to `path`, refusing to replace an existing file unless
+/// `force` is set.
+fn write_new(path: &Path, data: &[u8], force: bool) -> u8 {
+    if !force && path.exists() {
+        eprintln!(
+            "Refusing to overwrite {}; pass --force to replace it",
+            path.display()
+        );
+        return EXIT_FATAL;
+    }
+    match fs::write(path, data) {
+        Ok(()) => EXIT_SUCCESS,
+        Err(e) => {
+            eprintln!("Unable to write {}: {}", path.display(), e);
+            EXIT_FATAL
+        }
+    }
+}
+
+fn encode(artifact: &ParsedArtifact) -> Vec<u8> {
+    match artifact {
+        ParsedArtifact::Job(job) => job.to_bytes(),
+        ParsedArtifact::Task(task) => task.to_utf16_bytes(),
+    }
+}
+
+pub fn convert(input: &Path, to_xml: bool, out_file: Option<PathBuf>, force: bool) -> u8 {
+    let artifact = match parse_file(input) {
+        Ok(artifact) => artifact,
+        Err(e) => {
+            eprintln!("Unable to process file {}: {}", input.display(), e);
+            return EXIT_PARSE_FAILURE;
+        }
+    };
+    let converted = match (artifact, to_xml) {
+        (ParsedArtifact::Job(job), true) => ParsedArtifact::Task(job_to_task(&job)),
+        (ParsedArtifact::Task(task), false) => match task_to_job(&task) {
+            Ok(job) => ParsedArtifact::Job(job),
+            Err(e) => {
+                eprintln!("Unable to convert {}: {}", input.display(), e);
+                return EXIT_PARSE_FAILURE;
+            }
+        },
+        (artifact, _) => artifact,
+    };
+    let out_file =
+        out_file.unwrap_or_else(|| input.with_extension(if to_xml { "xml" } else { "job" }));
+    write_new(&out_file, &encode(&converted), force)
+}
+
+/// Converts every job under `input` to task XML (or, without `to_xml`,
+/// every task XML file to a job) into `out_dir`, keeping each file's path
+/// below `input`, and prints what each conversion left behind. Outputs whose
+/// names would collide, as those of `a.job` and `A.JOB` do, get `-2`, `-3`
+/// and so on after their stem, in path order.
+pub fn convert_tree(input: &Path, to_xml: bool, out_dir: &Path, force: bool) -> u8 {
+    let (from, to) = if to_xml { ("job", "xml") } else { ("xml", "job") };
+    let options = ScanOptions {
+        recursive: true,
+        extensions: vec![from.to_string()],
+        ..ScanOptions::default()
+    };
+    let mut sources = Vec::new();
+    let mut failed = 0;
+    // Everything is listed before anything is written, so an output
+    // directory inside `input` is not converted again.
+    if input.is_dir() {
+        let listed = list_dir(input, &options, |path, result| {
+            match result {
+                Ok(()) => sources.push(path.to_path_buf()),
+                Err(e) => {
+                    eprintln!("Unable to read directory {}: {}", path.display(), e);
+                    failed += 1;
+                }
+            }
+            ControlFlow::Continue(())
+        });
+        if let Err(e) = listed {
+            eprintln!("Unable to read directory {}: {}", input.display(), e);
+            return EXIT_FATAL;
+        }
+    } else {
+        sources.push(input.to_path_buf());
+    }
+
+    let mut taken = BTreeSet::new();
+    let (mut converted, mut warnings, mut unwritten) = (0, 0, false);
+    for source in &sources {
+        let relative = match source.strip_prefix(input) {
+            Ok(relative) if !relative.as_os_str().is_empty() => relative,
+            _ => Path::new(source.file_name().unwrap_or_default()),
+        };
+        let target = unique_target(out_dir.join(relative).with_extension(to), &mut taken);
+        let result = read_file_limited(source, options.max_file_size).and_then(|data| {
+            let artifact = options.parse(source, &data)?;
+            Ok(match artifact {
+                ParsedArtifact::Job(job) if to_xml => {
+                    let losses = job_to_task_losses(&job, &data);
+                    (ParsedArtifact::Task(job_to_task(&job)), losses)
+                }
+                ParsedArtifact::Task(task) if !to_xml => {
+                    let losses = task_to_job_losses(&task);
+                    (ParsedArtifact::Job(task_to_job(&task)?), losses)
+                }
+                artifact => (artifact, vec![format!("already {}, written unchanged", to)]),
+            })
+        });
+        let (artifact, losses) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Unable to process file {}: {}", source.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        if let Some(parent) = target.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("Unable to create {}: {}", parent.display(), e);
+                unwritten = true;
+                continue;
+            }
+        }
+        if write_new(&target, &encode(&artifact), force) != EXIT_SUCCESS {
+            unwritten = true;
+            continue;
+        }
+        converted += 1;
+        println!("{} -> {}", source.display(), target.display());
+        for loss in &losses {
+            println!("  warning: {}", loss);
+        }
+        warnings += losses.len();
+    }
+    println!(
+        "Converted {} of {} files, {} warnings",
+        converted,
+        sources.len(),
+        warnings
+    );
+    if unwritten {
+        EXIT_FATAL
+    } else if failed > 0 {
+        EXIT_PARSE_FAILURE
+    } else {
+        EXIT_SUCCESS
+    }
+}
+
+/// `path`, or if another output already took it (ignoring case, as Windows
+/// does), the first free `stem-N.ext` beside it.
+fn unique_target(path: PathBuf, taken: &mut BTreeSet<String>) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    let extension = path.extension().unwrap_or_default().to_string_lossy().into_owned();
+    let mut target = path.clone();
+    let mut n = 1;
+    while !taken.insert(fold(&target.to_string_lossy()).into_owned()) {
+        n += 1;
+        target = path.with_file_name(format!("{}-{}.{}", stem, n, extension));
+    }
+    target
+}
+
+pub struct CreateOptions {
+    pub application: String,
+    pub parameters: String,
+    pub working_directory: String,
+    pub author: String,
+    pub comment: String,
+    pub disabled: bool,
+    pub hidden: bool,
+    pub xml: bool,
+    pub out_file: PathBuf,
+    pub force: bool,
+}
+
+pub fn create(options: &CreateOptions) -> u8 {
+    let mut flags = 0;
+    if options.disabled {
+        flags |= consts::flags::DISABLED;
+    }
+    if options.hidden {
+        flags |= consts::flags::HIDDEN;
+    }
+    let job: Job = match JobBuilder::new(&options.application)
+        .parameters(&options.parameters)
+        .working_directory(&options.working_directory)
+        .author(&options.author)
+        .comment(&options.comment)
+        .flags(flags)
+        .build()
+    {
+        Ok(job) => job,
+        Err(e) => {
+            eprintln!("Unable to create {}: {}", options.out_file.display(), e);
+            return EXIT_FATAL;
+        }
+    };
+    let artifact = if options.xml {
+        ParsedArtifact::Task(job_to_task(&job))
+    } else {
+        ParsedArtifact::Job(job)
+    };
+    write_new(&options.out_file, &encode(&artifact), options.force)
+}
+
+/// Flattens nested JSON into `a.b.c` keys with scalar values.
+pub fn flatten(prefix: &str, value: &Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, fields);
+            }
+        }
+        Value::Array(items) => {
+            for (i, value) in items.iter().enumerate() {
+                flatten(&format!("{}[{}]", prefix, i), value, fields);
+            }
+        }
+        value => {
+            fields.insert(prefix.to_string(), value.to_string());
+        }
+    }
+}
+
+fn fields(path: &Path) -> Option<BTreeMap<String, String>> {
+    let artifact = match parse_file(path) {
+        Ok(artifact) => artifact,
+        Err(e) => {
+            eprintln!("Unable to process file {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let value = serde_json::to_value(&artifact).ok()?;
+    let mut fields = BTreeMap::new();
+    flatten("", &value, &mut fields);
+    Some(fields)
+}
+
+/// A `detect` line.
+#[derive(Serialize)]
+struct Detected<'a> {
+    path: String,
+    #[serde(flatten)]
+    sniff: Option<Sniff>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+/// Prints a JSON line per file with what `sniff` makes of its first
+/// `SNIFF_LENGTH` bytes, or the error that kept it from being read.
+pub fn detect(paths: &[PathBuf]) -> u8 {
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    let mut code = EXIT_SUCCESS;
+    for path in paths {
+        let read = fs::File::open(path).and_then(|file| {
+            let size = file.metadata()?.len();
+            let mut head = Vec::with_capacity(SNIFF_LENGTH);
+            file.take(SNIFF_LENGTH as u64).read_to_end(&mut head)?;
+            Ok(sniff(&head, size))
+        });
+        let error = read.as_ref().err().map(|e| e.to_string());
+        if error.is_some() {
+            code = EXIT_PARSE_FAILURE;
+        }
+        let line = Detected {
+            path: path.display().to_string(),
+            sniff: read.ok(),
+            error: error.as_deref(),
+        };
+        if let Ok(json) = serde_json::to_string(&line) {
+            let _ = writeln!(out, "{}", json);
+        }
+    }
+    let _ = out.flush();
+    code
+}
+
+/// Prints one line per field whose value differs, `(absent)` standing in
+/// for a field only one side has.
+pub fn diff(a: &Path, b: &Path) -> u8 {
+    let (Some(left), Some(right)) = (fields(a), fields(b)) else {
+        return EXIT_FATAL;
+    };
+    let mut differ = false;
+    let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    for key in keys {
+        let (l, r) = (left.get(key), right.get(key));
+        if l != r {
+            differ = true;
+            let absent = "(absent)".to_string();
+            println!(
+                "{}: {} -> {}",
+                key,
+                l.unwrap_or(&absent),
+                r.unwrap_or(&absent)
+            );
+        }
+    }
+    if differ {
+        EXIT_PARSE_FAILURE
+    } else {
+        EXIT_SUCCESS
+    }
+}
@@ -0,0 +1,90 @@
+//! Shell completion scripts.
+
+use clap::Command;
+use clap_complete::Shell;
+use std::io::{self, Write};
+
+/// The line of clap's PowerShell script after which value completion is
+/// spliced in.
+const POWERSHELL_ANCHOR: &str = "    $commandElements = $commandAst.CommandElements\n";
+
+/// Writes the completion script for `shell`. clap's PowerShell script only
+/// completes flag names, so for PowerShell the enumerated values of every
+/// option (e.g. `--output-format json`) are added on top.
+pub fn generate(shell: Shell, cmd: &mut Command, out: &mut dyn Write) -> io::Result<()> {
+    let name = cmd.get_name().to_string();
+    if shell != Shell::PowerShell {
+        clap_complete::generate(shell, cmd, name, out);
+        return Ok(());
+    }
+
+    let mut script = Vec::new();
+    clap_complete::generate(shell, cmd, name, &mut script);
+    let script = String::from_utf8_lossy(&script);
+    match script.find(POWERSHELL_ANCHOR) {
+        Some(at) => {
+            let at = at + POWERSHELL_ANCHOR.len();
+            out.write_all(script[..at].as_bytes())?;
+            out.write_all(powershell_values(cmd).as_bytes())?;
+            out.write_all(script[at..].as_bytes())
+        }
+        None => out.write_all(script.as_bytes()),
+    }
+}
+
+fn powershell_values(cmd: &Command) -> String {
+    let mut entries = Vec::new();
+    collect_values(cmd, &mut entries);
+    let mut script = String::from("    $values = @{\n");
+    for (flag, values) in entries {
+        let values: Vec<String> = values.iter().map(|v| format!("'{}'", v)).collect();
+        script.push_str(&format!("        '{}' = @({})\n", flag, values.join(", ")));
+    }
+    script.push_str(
+        "    }
+    $previous = $commandElements | Where-Object { $_.Extent.EndOffset -lt $cursorPosition } | Select-Object -Last 1
+    if ($previous -and $values.ContainsKey($previous.ToString())) {
+        return $values[$previous.ToString()] | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {
+            [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_)
+        }
+    }
+",
+    );
+    script
+}
+
+/// Collects `(flag, possible values)` for every option of `cmd` and its
+/// subcommands, once per spelling (`-o` and `--output-format`).
+fn collect_values(cmd: &Command, entries: &mut Vec<(String, Vec<String>)>) {
+    for arg in cmd.get_arguments() {
+        let values: Vec<String> = arg
+            .get_possible_values()
+            .iter()
+            .filter(|v| !v.is_hide_set())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        if values.is_empty() || arg.is_positional() {
+            continue;
+        }
+        let mut flags: Vec<String> = arg
+            .get_short_and_visible_aliases()
+            .unwrap_or_default()
+            .iter()
+            .map(|c| format!("-{}", c))
+            .collect();
+        flags.extend(
+            arg.get_long_and_visible_aliases()
+                .unwrap_or_default()
+                .iter()
+                .map(|l| format!("--{}", l)),
+        );
+        for flag in flags {
+            if !entries.iter().any(|(f, _)| *f == flag) {
+                entries.push((flag, values.clone()));
+            }
+        }
+    }
+    for sub in cmd.get_subcommands() {
+        collect_values(sub, entries);
+    }
+}
@@ -0,0 +1,119 @@
+//! Defaults for command-line options, read from a TOML config file and from
+//! `JOBPARSER_`-prefixed environment variables. Precedence, highest first:
+//! command-line flag, environment variable, config file, built-in default.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Option values that may be supplied as defaults. `None` means "not set
+/// at this level".
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Defaults {
+    pub output_format: Option<String>,
+    pub detect: Option<bool>,
+    pub fail_on_findings: Option<bool>,
+    pub exit_zero_always: Option<bool>,
+    /// The `--timezone` of the timeline formats, as given.
+    pub timezone: Option<String>,
+    /// The `--fields` of the JSON formats, by name.
+    pub fields: Option<Vec<String>>,
+}
+
+impl Defaults {
+    /// Fills every unset value from `fallback`.
+    pub fn or(self, fallback: Defaults) -> Defaults {
+        Defaults {
+            output_format: self.output_format.or(fallback.output_format),
+            detect: self.detect.or(fallback.detect),
+            fail_on_findings: self.fail_on_findings.or(fallback.fail_on_findings),
+            exit_zero_always: self.exit_zero_always.or(fallback.exit_zero_always),
+            timezone: self.timezone.or(fallback.timezone),
+            fields: self.fields.or(fallback.fields),
+        }
+    }
+}
+
+/// The config file used when `--config` is not given:
+/// `$XDG_CONFIG_HOME/jobfileparser/config.toml` (or `~/.config/...`), and on
+/// Windows `config.toml` next to the executable.
+pub fn default_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        let exe = env::current_exe().ok()?;
+        return Some(exe.with_file_name("config.toml"));
+    }
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("jobfileparser").join("config.toml"))
+}
+
+/// Reads a config file. Problems with individual keys are returned as
+/// warnings; an unreadable or malformed file is an error.
+pub fn from_file(path: &Path) -> Result<(Defaults, Vec<String>), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let table: toml::Table = text
+        .parse()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let mut defaults = Defaults::default();
+    let mut warnings = Vec::new();
+    for (key, value) in &table {
+        let ok = match key.as_str() {
+            "output_format" => value
+                .as_str()
+                .map(|v| defaults.output_format = Some(v.to_string())),
+            "detect" => value.as_bool().map(|v| defaults.detect = Some(v)),
+            "fail_on_findings" => value.as_bool().map(|v| defaults.fail_on_findings = Some(v)),
+            "exit_zero_always" => value.as_bool().map(|v| defaults.exit_zero_always = Some(v)),
+            "timezone" => value.as_str().map(|v| defaults.timezone = Some(v.to_string())),
+            "fields" => value
+                .as_array()
+                .and_then(|items| items.iter().map(|v| v.as_str().map(str::to_string)).collect())
+                .map(|v| defaults.fields = Some(v)),
+            _ => {
+                warnings.push(format!("{}: unknown config key `{}`", path.display(), key));
+                continue;
+            }
+        };
+        if ok.is_none() {
+            warnings.push(format!(
+                "{}: config key `{}` has the wrong type",
+                path.display(),
+                key
+            ));
+        }
+    }
+    Ok((defaults, warnings))
+}
+
+/// Reads `JOBPARSER_OUTPUT_FORMAT`, `JOBPARSER_DETECT`,
+/// `JOBPARSER_FAIL_ON_FINDINGS`, `JOBPARSER_EXIT_ZERO_ALWAYS`,
+/// `JOBPARSER_TIMEZONE` and `JOBPARSER_FIELDS`. Booleans accept `1`/`0`,
+/// `true`/`false` and `yes`/`no`; fields are separated by commas.
+pub fn from_env() -> (Defaults, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut flag = |name: &str| {
+        let value = env::var(name).ok()?;
+        match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" => Some(true),
+            "0" | "false" | "no" => Some(false),
+            _ => {
+                warnings.push(format!("{}: expected a boolean, got `{}`", name, value));
+                None
+            }
+        }
+    };
+    let defaults = Defaults {
+        output_format: env::var("JOBPARSER_OUTPUT_FORMAT").ok(),
+        detect: flag("JOBPARSER_DETECT"),
+        fail_on_findings: flag("JOBPARSER_FAIL_ON_FINDINGS"),
+        exit_zero_always: flag("JOBPARSER_EXIT_ZERO_ALWAYS"),
+        timezone: env::var("JOBPARSER_TIMEZONE").ok(),
+        fields: env::var("JOBPARSER_FIELDS")
+            .ok()
+            .map(|list| list.split(',').map(|v| v.trim().to_string()).collect()),
+    };
+    (defaults, warnings)
+}
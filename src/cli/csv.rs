@@ -0,0 +1,80 @@
+//! The `-o csv` rows.
+
+/// When a field is put in quotes, as the `csv` crate's writer offers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteStyle {
+    Always,
+    /// Only fields holding the delimiter, a quote or a line break.
+    Necessary,
+    /// Never, even when a field holds the delimiter.
+    Never,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CsvStyle {
+    pub delimiter: char,
+    pub quote_style: QuoteStyle,
+}
+
+impl Default for CsvStyle {
+    fn default() -> CsvStyle {
+        CsvStyle {
+            delimiter: ',',
+            quote_style: QuoteStyle::Necessary,
+        }
+    }
+}
+
+/// The column names of the header row.
+pub const COLUMNS: [&str; 15] = [
+    "path", "format", "user", "author", "command", "last_run", "findings", "warnings", "error",
+    "issues", "record_index", "trigger_count", "action_count", "has_exec", "has_comhandler",
+];
+
+/// The columns of the `--csv-triggers` file: a row per trigger, joined to
+/// its record's row by `path` and `record_index`.
+pub const TRIGGER_COLUMNS: [&str; 10] = [
+    "path", "record_index", "trigger_index", "type", "start", "end", "enabled", "schedule",
+    "interval", "duration",
+];
+
+/// The columns of the `--csv-actions` file, joined like the triggers.
+pub const ACTION_COLUMNS: [&str; 7] =
+    ["path", "record_index", "action_index", "type", "command", "arguments", "class_id"];
+
+/// Checks a `--delimiter`: one ASCII character that neither starts a quote
+/// nor ends a row.
+pub fn parse_delimiter(value: &str) -> Result<char, String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some('"'), None) => Err("the delimiter cannot be the quote character `\"`".to_string()),
+        (Some('\r' | '\n'), None) => Err("the delimiter cannot be a line break".to_string()),
+        (Some(c), None) if c.is_ascii() => Ok(c),
+        _ => Err(format!("the delimiter must be a single ASCII character, not `{}`", value)),
+    }
+}
+
+impl CsvStyle {
+    /// Joins `fields` into one row, without the line break.
+    pub fn row(&self, fields: &[&str]) -> String {
+        let mut row = String::new();
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                row.push(self.delimiter);
+            }
+            let quote = match self.quote_style {
+                QuoteStyle::Always => true,
+                QuoteStyle::Necessary => field.contains([self.delimiter, '"', '\r', '\n']),
+                QuoteStyle::Never => false,
+            };
+            if quote {
+                row.push('"');
+                row.push_str(&field.replace('"', "\"\""));
+                row.push('"');
+            } else {
+                row.push_str(field);
+            }
+        }
+        row
+    }
+}
@@ -0,0 +1,389 @@
+//! `--rules-dir`: rules shipped as data rather than code. Every `*.toml`
+//! file in the directory holds `[[rule]]` tables, each with a name, the
+//! score and, optionally, the ATT&CK technique its findings carry, and a
+//! condition over the fields of the record as `-o json` writes them:
+//!
+//! ```toml
+//! [[rule]]
+//! name = "mshta-command"
+//! attack = "T1218.005"
+//! score = 70
+//! description = "runs mshta.exe"
+//! when = { field = "effective_command", contains = "mshta" }
+//!
+//! [[rule]]
+//! name = "busy-hidden-task"
+//! attack = "T1564"
+//! score = 40
+//! when.all = [
+//!     { field = "settings.hidden", equals = true },
+//!     { field = "trigger_count", ge = 3 },
+//! ]
+//! ```
+//!
+//! A condition is `all`, `any` or `not` over other conditions, or a
+//! `field` with one test: `equals` (a string, number or boolean),
+//! `contains`, `starts_with`, `regex`, or the comparisons `gt`, `ge`, `lt`
+//! and `le`. A field is a dotted path through the record, and passes if any
+//! value it leads to passes, so `findings.rule` looks at every finding. A
+//! field the record does not have fails every test. Strings are compared
+//! without regard to case unless `--case-sensitive`; a `regex` is matched
+//! as written, so `(?i)` makes it ignore case.
+//!
+//! Every file is checked when the run starts, and all the problems found
+//! are reported with the file and line they are on.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use jobfileparser::{Case, Finding, Record};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use toml::Spanned;
+
+/// The rules of a `--rules-dir`, in file and then file order.
+#[derive(Clone)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    case: Case,
+}
+
+/// A rule checked and ready to run, as `--rules-bundle` keeps it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Rule {
+    name: String,
+    attack: Option<String>,
+    score: u32,
+    description: String,
+    when: Condition,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    #[serde(default)]
+    rule: Vec<RuleSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    name: Spanned<String>,
+    attack: Option<String>,
+    score: u32,
+    /// What a finding says [default: "matches <name>"].
+    description: Option<String>,
+    when: Condition,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "ConditionSpec", into = "ConditionSpec")]
+enum Condition {
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+    Field(String, Test),
+}
+
+#[derive(Clone)]
+enum Test {
+    Equals(Scalar),
+    Contains(String),
+    StartsWith(String),
+    Regex(Regex),
+    Gt(f64),
+    Ge(f64),
+    Lt(f64),
+    Le(f64),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Scalar {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+/// A condition as written, before it is checked to name exactly one thing.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConditionSpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    all: Option<Vec<Condition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    any: Option<Vec<Condition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    not: Option<Box<Condition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    equals: Option<Scalar>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contains: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    starts_with: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    regex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gt: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ge: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lt: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    le: Option<f64>,
+}
+
+/// A condition written back as it would be in a rules file, with a regex
+/// as its pattern.
+impl From<Condition> for ConditionSpec {
+    fn from(condition: Condition) -> ConditionSpec {
+        let mut spec = ConditionSpec::default();
+        match condition {
+            Condition::All(all) => spec.all = Some(all),
+            Condition::Any(any) => spec.any = Some(any),
+            Condition::Not(not) => spec.not = Some(not),
+            Condition::Field(field, test) => {
+                spec.field = Some(field);
+                match test {
+                    Test::Equals(scalar) => spec.equals = Some(scalar),
+                    Test::Contains(s) => spec.contains = Some(s),
+                    Test::StartsWith(s) => spec.starts_with = Some(s),
+                    Test::Regex(regex) => spec.regex = Some(regex.as_str().to_string()),
+                    Test::Gt(n) => spec.gt = Some(n),
+                    Test::Ge(n) => spec.ge = Some(n),
+                    Test::Lt(n) => spec.lt = Some(n),
+                    Test::Le(n) => spec.le = Some(n),
+                }
+            }
+        }
+        spec
+    }
+}
+
+impl TryFrom<ConditionSpec> for Condition {
+    type Error = String;
+
+    fn try_from(spec: ConditionSpec) -> Result<Condition, String> {
+        let regex = spec
+            .regex
+            .map(|regex| Regex::new(&regex).map_err(|e| format!("invalid regex: {}", e)))
+            .transpose()?;
+        let tests: Vec<Test> = [
+            spec.equals.map(Test::Equals),
+            spec.contains.map(Test::Contains),
+            spec.starts_with.map(Test::StartsWith),
+            regex.map(Test::Regex),
+            spec.gt.map(Test::Gt),
+            spec.ge.map(Test::Ge),
+            spec.lt.map(Test::Lt),
+            spec.le.map(Test::Le),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let groups: Vec<Condition> = [
+            spec.all.map(|all| non_empty("all", all).map(Condition::All)),
+            spec.any.map(|any| non_empty("any", any).map(Condition::Any)),
+            spec.not.map(|not| Ok(Condition::Not(not))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Result<_, _>>()?;
+        match (spec.field, tests.len(), groups.len()) {
+            (Some(field), 1, 0) => Ok(Condition::Field(field, tests.into_iter().next().unwrap())),
+            (Some(_), _, 0) => Err("`field` takes exactly one of `equals`, `contains`, \
+                                    `starts_with`, `regex`, `gt`, `ge`, `lt` and `le`"
+                .to_string()),
+            (None, 0, 1) => Ok(groups.into_iter().next().unwrap()),
+            _ => Err("a condition is a `field` with a test, or one of `all`, `any` and `not`"
+                .to_string()),
+        }
+    }
+}
+
+fn non_empty(key: &str, conditions: Vec<Condition>) -> Result<Vec<Condition>, String> {
+    if conditions.is_empty() {
+        Err(format!("`{}` needs at least one condition", key))
+    } else {
+        Ok(conditions)
+    }
+}
+
+impl RuleSet {
+    /// Reads every `*.toml` file in `dir`. Any problem with a file, or a
+    /// rule name used twice, is an error; all of them are listed, one per
+    /// line, as `<file>:<line>: <problem>`.
+    pub fn from_dir(dir: &Path, case: Case) -> Result<RuleSet, String> {
+        let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml") && path.is_file())
+            .collect();
+        paths.sort();
+        let mut rule_set = RuleSet { rules: Vec::new(), case };
+        let mut problems = Vec::new();
+        let mut names = HashSet::new();
+        for path in paths {
+            match fs::read_to_string(&path) {
+                Ok(text) => {
+                    let source = path.display().to_string();
+                    rule_set.add_file(&source, &text, &mut names, &mut problems);
+                }
+                Err(e) => problems.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        if problems.is_empty() {
+            Ok(rule_set)
+        } else {
+            Err(problems.join("\n"))
+        }
+    }
+
+    /// The rules of a `--rules-bundle`, already checked.
+    pub fn from_rules(rules: Vec<Rule>, case: Case) -> RuleSet {
+        RuleSet { rules, case }
+    }
+
+    /// The rules, to keep in a `--rules-bundle`.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Reads the rules in `text`, a rules file named `source` in problems.
+    pub fn parse(source: &str, text: &str, case: Case) -> Result<RuleSet, String> {
+        let mut rule_set = RuleSet { rules: Vec::new(), case };
+        let mut problems = Vec::new();
+        rule_set.add_file(source, text, &mut HashSet::new(), &mut problems);
+        if problems.is_empty() {
+            Ok(rule_set)
+        } else {
+            Err(problems.join("\n"))
+        }
+    }
+
+    /// Adds the rules of the file `source` holding `text`, noting any
+    /// problem and any name already in `names`.
+    fn add_file(
+        &mut self,
+        source: &str,
+        text: &str,
+        names: &mut HashSet<String>,
+        problems: &mut Vec<String>,
+    ) {
+        let file: RuleFile = match toml::from_str(text) {
+            Ok(file) => file,
+            Err(e) => {
+                let line = e.span().map_or(1, |span| line_of(text, span.start));
+                problems.push(format!("{}:{}: {}", source, line, e.message().trim()));
+                return;
+            }
+        };
+        for spec in file.rule {
+            let line = line_of(text, spec.name.span().start);
+            let name = spec.name.into_inner();
+            if !names.insert(name.clone()) {
+                let problem = format!("rule {} is defined twice", name);
+                problems.push(format!("{}:{}: {}", source, line, problem));
+                continue;
+            }
+            self.rules.push(Rule {
+                description: spec.description.unwrap_or_else(|| format!("matches {}", name)),
+                name,
+                attack: spec.attack,
+                score: spec.score,
+                when: spec.when,
+            });
+        }
+    }
+
+    /// Adds a finding for every rule `record` matches. Error records are
+    /// left untouched.
+    pub fn apply(&self, record: &mut Record) {
+        for finding in self.matches(record) {
+            record.add_finding(finding);
+        }
+    }
+
+    /// A finding for every rule `record` matches; none for error records.
+    pub fn matches(&self, record: &Record) -> Vec<Finding> {
+        if !matches!(record, Record::Ok { .. }) {
+            return Vec::new();
+        }
+        let Ok(value) = serde_json::to_value(record) else {
+            return Vec::new();
+        };
+        self.rules
+            .iter()
+            .filter(|rule| rule.when.matches(&value, self.case))
+            .map(|rule| Finding {
+                rule: rule.name.clone().into(),
+                description: rule.description.clone(),
+                technique: rule.attack.clone(),
+                score: Some(rule.score),
+            })
+            .collect()
+    }
+}
+
+impl Condition {
+    fn matches(&self, record: &Value, case: Case) -> bool {
+        match self {
+            Condition::All(all) => all.iter().all(|c| c.matches(record, case)),
+            Condition::Any(any) => any.iter().any(|c| c.matches(record, case)),
+            Condition::Not(not) => !not.matches(record, case),
+            Condition::Field(field, test) => {
+                let path: Vec<&str> = field.split('.').collect();
+                let mut values = Vec::new();
+                lookup(record, &path, &mut values);
+                values.into_iter().any(|value| test.passes(value, case))
+            }
+        }
+    }
+}
+
+impl Test {
+    fn passes(&self, value: &Value, case: Case) -> bool {
+        let number = value.as_f64();
+        let text = value.as_str().map(|text| case.key(text));
+        match self {
+            Test::Equals(Scalar::Bool(b)) => value.as_bool() == Some(*b),
+            Test::Equals(Scalar::Number(n)) => number == Some(*n),
+            Test::Equals(Scalar::String(s)) => text.is_some_and(|text| text == case.key(s)),
+            Test::Contains(s) => text.is_some_and(|text| text.contains(case.key(s).as_ref())),
+            Test::StartsWith(s) => text.is_some_and(|text| text.starts_with(case.key(s).as_ref())),
+            Test::Regex(regex) => value.as_str().is_some_and(|text| regex.is_match(text)),
+            Test::Gt(n) => number.is_some_and(|number| number > *n),
+            Test::Ge(n) => number.is_some_and(|number| number >= *n),
+            Test::Lt(n) => number.is_some_and(|number| number < *n),
+            Test::Le(n) => number.is_some_and(|number| number <= *n),
+        }
+    }
+}
+
+/// Collects the values `path` leads to from `value`, going into every
+/// element of the arrays on the way.
+fn lookup<'a>(value: &'a Value, path: &[&str], values: &mut Vec<&'a Value>) {
+    match (value, path.split_first()) {
+        (Value::Array(elements), _) => {
+            for element in elements {
+                lookup(element, path, values);
+            }
+        }
+        (_, None) => values.push(value),
+        (Value::Object(fields), Some((key, rest))) => {
+            if let Some(value) = fields.get(*key) {
+                lookup(value, rest, values);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The 1-based line of byte `offset` in `text`.
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
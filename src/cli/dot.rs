@@ -0,0 +1,140 @@
+//! `-o dot`: the records as a Graphviz digraph. Each task is a box labeled
+//! with its name and a summary of its schedule, inside a cluster for the
+//! account it runs as, with an edge to the program it starts. Programs
+//! started by several tasks get a single node. Tasks with findings are
+//! filled red.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use jobfileparser::entropy::task_name;
+use jobfileparser::fold::fold;
+use jobfileparser::{ParsedArtifact, Record};
+
+/// The cluster for artifacts that name no account.
+const NO_USER: &str = "(none)";
+
+struct TaskNode {
+    label: String,
+    flagged: bool,
+    /// The index of the program in `Graph::programs`.
+    program: Option<usize>,
+}
+
+#[derive(Default)]
+pub struct Graph {
+    /// Tasks by the account they run as.
+    users: BTreeMap<String, Vec<TaskNode>>,
+    /// The programs in the order they were first seen, as first written.
+    programs: Vec<String>,
+    /// Indexes into `programs` by normalized path.
+    program_index: BTreeMap<String, usize>,
+}
+
+impl Graph {
+    /// Adds the task `record` describes. Failed files have no node.
+    pub fn add(&mut self, record: &Record) {
+        let Record::Ok { artifact, .. } = record else {
+            return;
+        };
+        let program = artifact.command().map(|command| {
+            let key = fold(&command.executable.replace('/', "\\")).into_owned();
+            *self.program_index.entry(key).or_insert_with(|| {
+                self.programs.push(command.executable.clone());
+                self.programs.len() - 1
+            })
+        });
+        let user = artifact.user().unwrap_or(NO_USER).to_string();
+        self.users.entry(user).or_default().push(TaskNode {
+            label: format!("{}\n{}", task_name(record.path()), schedule(artifact)),
+            flagged: !record.findings().is_empty(),
+            program,
+        });
+    }
+
+    /// The Graphviz source for the graph.
+    pub fn render(&self) -> String {
+        let mut dot = String::from("digraph tasks {\n    rankdir=LR;\n");
+        let mut edges = Vec::new();
+        let mut id = 0;
+        for (cluster, (user, tasks)) in self.users.iter().enumerate() {
+            let _ = writeln!(dot, "    subgraph cluster_{} {{", cluster);
+            let _ = writeln!(dot, "        label={};", quote(user));
+            for task in tasks {
+                let _ = write!(dot, "        t{} [shape=box, label={}", id, quote(&task.label));
+                if task.flagged {
+                    dot.push_str(", style=filled, fillcolor=red");
+                }
+                dot.push_str("];\n");
+                if let Some(program) = task.program {
+                    edges.push(format!("    t{} -> p{};\n", id, program));
+                }
+                id += 1;
+            }
+            dot.push_str("    }\n");
+        }
+        for (index, program) in self.programs.iter().enumerate() {
+            let _ = writeln!(dot, "    p{} [shape=ellipse, label={}];", index, quote(program));
+        }
+        dot.extend(edges);
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// When the task runs: a job's next scheduled run, or the kinds of trigger
+/// a task has, then the last run if there was one.
+fn schedule(artifact: &ParsedArtifact) -> String {
+    let mut summary = match artifact {
+        ParsedArtifact::Job(job) if job.scheduled_date.year != 0 => {
+            format!("scheduled {}", job.scheduled_date.format_iso())
+        }
+        ParsedArtifact::Job(_) => "not scheduled".to_string(),
+        ParsedArtifact::Task(task) => {
+            let mut triggers = Vec::new();
+            if let Some(calendar) = &task.triggers.calendar_trigger {
+                triggers.push(format!("calendar from {}", calendar.start_boundary));
+            }
+            // Other triggers are only kept as unknown elements, such as
+            // `Triggers/LogonTrigger[2]/Enabled`.
+            for path in task.unknown_elements.keys() {
+                let Some(rest) = path.strip_prefix("Triggers/") else {
+                    continue;
+                };
+                let element = rest.split(['/', '[']).next().unwrap_or(rest);
+                let kind = element.strip_suffix("Trigger").unwrap_or(element).to_lowercase();
+                if !triggers.iter().any(|t| t.split(' ').next() == Some(kind.as_str())) {
+                    triggers.push(kind);
+                }
+            }
+            if triggers.is_empty() {
+                "no triggers".to_string()
+            } else {
+                triggers.join(", ")
+            }
+        }
+    };
+    if let Some(run) = artifact.last_run() {
+        let _ = write!(summary, "; last run {}", run);
+    }
+    summary
+}
+
+/// `text` as a quoted DOT string. Backslashes and quotes are escaped so
+/// that Windows paths come through as written, and line breaks become the
+/// `\n` escape Graphviz centers lines with.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
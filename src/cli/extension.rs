@@ -0,0 +1,228 @@
+//! What the name of the `-w` file says about the format written to it.
+//! Without `-o`, `-w report.csv` writes CSV; with an `-o` its extension does
+//! not stand for, the run is refused unless `--force-format` is given. A
+//! file that is already there and clearly holds something else than what
+//! would be written over it, such as a JSON export about to be replaced by
+//! CSV, is only overwritten with `--force`.
+//!
+//! The extension is read from before a final `.gz`, the one `--gzip` adds,
+//! so `-w report.jsonl.gz` and `-w report.jsonl --gzip` both write JSON
+//! lines.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Extensions that stand for an output format, lowercase, with the
+/// `--output-format` names that write such files; without `-o`, the first
+/// is written.
+pub const EXTENSIONS: &[(&str, &[&str])] = &[
+    ("csv", &["csv"]),
+    ("json", &["json"]),
+    ("jsonl", &["jsonl", "plaso"]),
+    ("ndjson", &["jsonl", "plaso"]),
+    ("dot", &["dot"]),
+    ("gv", &["dot"]),
+    ("ioc", &["openioc"]),
+    ("xml", &["taskxml-bundle", "openioc"]),
+    ("body", &["bodyfile"]),
+    ("tln", &["tln"]),
+    ("txt", &["text", "minimal", "bodyfile", "tln"]),
+];
+
+/// How many bytes of an existing file `check_overwrite` looks at.
+const HEAD_LENGTH: usize = 512;
+
+/// The formats that write files named like `path`; `None` for an extension
+/// that stands for none, or no extension.
+pub fn formats_for(path: &Path) -> Option<&'static [&'static str]> {
+    let (extension, _) = extensions(path);
+    let extension = extension?;
+    EXTENSIONS.iter().find(|(known, _)| *known == extension).map(|(_, formats)| *formats)
+}
+
+/// `path` with `.gz` added, unless it already ends in it.
+pub fn gzip_path(path: &Path) -> PathBuf {
+    if extensions(path).1 {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// The extension of the file name of `path`, lowercase, from before a final
+/// `.gz` if there is one, and whether there is.
+fn extensions(path: &Path) -> (Option<String>, bool) {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase());
+    let name = name.unwrap_or_default();
+    let (stem, gzip) = match name.strip_suffix(".gz") {
+        Some(stem) => (stem, true),
+        None => (name.as_str(), false),
+    };
+    let extension = stem.rsplit_once('.').filter(|(before, _)| !before.is_empty());
+    (extension.map(|(_, extension)| extension.to_string()), gzip)
+}
+
+/// What the first bytes of a file say it holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Content {
+    Gzip,
+    /// What `-o json` writes.
+    JsonArray,
+    /// What `-o jsonl` and `-o plaso` write.
+    JsonLines,
+    Dot,
+    Xml,
+    /// Any other text, as the remaining formats write.
+    Text,
+    Binary,
+}
+
+impl Content {
+    /// What `-o format` writes, compressed with `--gzip` or not.
+    pub fn written(format: &str, gzip: bool) -> Content {
+        match format {
+            _ if gzip => Content::Gzip,
+            "json" => Content::JsonArray,
+            "jsonl" | "plaso" => Content::JsonLines,
+            "dot" => Content::Dot,
+            "openioc" | "taskxml-bundle" => Content::Xml,
+            _ => Content::Text,
+        }
+    }
+
+    /// What `head`, the start of a file, holds; `None` if it is empty or
+    /// only whitespace, which anything may replace.
+    pub fn sniff(head: &[u8]) -> Option<Content> {
+        if head.starts_with(&[0x1F, 0x8B]) {
+            return Some(Content::Gzip);
+        }
+        let text = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+        let start = text.iter().position(|b| !b.is_ascii_whitespace())?;
+        let text = &text[start..];
+        // A multi-byte character may be cut at the end of the head.
+        let valid = match std::str::from_utf8(text) {
+            Ok(_) => true,
+            Err(e) => e.error_len().is_none(),
+        };
+        if !valid || text.contains(&0) {
+            return Some(Content::Binary);
+        }
+        let graph = text.starts_with(b"digraph") || text.starts_with(b"strict digraph");
+        let content = match text[0] {
+            b'[' => Content::JsonArray,
+            b'{' => Content::JsonLines,
+            b'<' => Content::Xml,
+            _ if graph => Content::Dot,
+            _ => Content::Text,
+        };
+        Some(content)
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Content::Gzip => "gzip data",
+            Content::JsonArray => "a JSON array",
+            Content::JsonLines => "JSON lines",
+            Content::Dot => "a Graphviz graph",
+            Content::Xml => "XML",
+            Content::Text => "text",
+            Content::Binary => "binary data",
+        }
+    }
+}
+
+/// An error if `path` exists and clearly holds something else than `-o
+/// format`, compressed with `--gzip` or not, would write over it.
+pub fn check_overwrite(path: &Path, format: &str, gzip: bool) -> Result<(), String> {
+    let Ok(file) = File::open(path) else {
+        return Ok(());
+    };
+    let mut head = Vec::with_capacity(HEAD_LENGTH);
+    if file.take(HEAD_LENGTH as u64).read_to_end(&mut head).is_err() {
+        return Ok(());
+    }
+    let written = Content::written(format, gzip);
+    match Content::sniff(&head) {
+        Some(found) if found != written => Err(format!(
+            "{} holds {}, not {}; pass --force to overwrite it",
+            path.display(),
+            found.describe(),
+            written.describe()
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_for() {
+        let formats = |path: &str| formats_for(Path::new(path)).map(|formats| formats[0]);
+        assert_eq!(formats("report.csv"), Some("csv"));
+        assert_eq!(formats("out/REPORT.JSON"), Some("json"));
+        assert_eq!(formats("a.b.jsonl"), Some("jsonl"));
+        assert_eq!(formats("events.ndjson"), Some("jsonl"));
+        assert_eq!(formats("graph.gv"), Some("dot"));
+        assert_eq!(formats("iocs.ioc"), Some("openioc"));
+        assert_eq!(formats("estate.xml"), Some("taskxml-bundle"));
+        assert_eq!(formats("report.txt"), Some("text"));
+        // The extension before the one --gzip adds counts.
+        assert_eq!(formats("report.jsonl.gz"), Some("jsonl"));
+        assert_eq!(formats("report.gz"), None);
+        assert_eq!(formats("report.html"), None);
+        assert_eq!(formats("report"), None);
+        assert_eq!(formats(".csv"), None);
+        assert_eq!(formats_for(Path::new("t.jsonl")), Some(&["jsonl", "plaso"][..]));
+        assert_eq!(formats_for(Path::new("t.xml")), Some(&["taskxml-bundle", "openioc"][..]));
+        for (extension, formats) in EXTENSIONS {
+            assert_eq!(extension.to_lowercase(), *extension);
+            assert!(!formats.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_gzip_path() {
+        assert_eq!(gzip_path(Path::new("out.jsonl")), Path::new("out.jsonl.gz"));
+        assert_eq!(gzip_path(Path::new("out.jsonl.gz")), Path::new("out.jsonl.gz"));
+        assert_eq!(gzip_path(Path::new("OUT.GZ")), Path::new("OUT.GZ"));
+        assert_eq!(gzip_path(Path::new("out")), Path::new("out.gz"));
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(Content::sniff(b""), None);
+        assert_eq!(Content::sniff(b" \n"), None);
+        assert_eq!(Content::sniff(b"\x1f\x8b\x08\x00"), Some(Content::Gzip));
+        assert_eq!(Content::sniff(b"[\n  {\"path\": \"a.job\"}"), Some(Content::JsonArray));
+        assert_eq!(Content::sniff(b"\xEF\xBB\xBF{\"path\":1}\n"), Some(Content::JsonLines));
+        assert_eq!(Content::sniff(b"digraph tasks {"), Some(Content::Dot));
+        assert_eq!(Content::sniff(b"<?xml version=\"1.0\"?>"), Some(Content::Xml));
+        assert_eq!(Content::sniff(b"path,user,command\n"), Some(Content::Text));
+        assert_eq!(Content::sniff("Größe \u{e9}".as_bytes()), Some(Content::Text));
+        // Cut inside a character by the length of the head.
+        assert_eq!(Content::sniff(&"path é".as_bytes()[..6]), Some(Content::Text));
+        assert_eq!(Content::sniff(b"\x01\x06\x01\x00\x11\x11"), Some(Content::Binary));
+        assert_eq!(Content::sniff(b"ab\xff\xfecd"), Some(Content::Binary));
+    }
+
+    #[test]
+    fn test_check_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.csv");
+        assert_eq!(check_overwrite(&path, "csv", false), Ok(()));
+        std::fs::write(&path, "[\n]\n").unwrap();
+        let refused = format!(
+            "{} holds a JSON array, not text; pass --force to overwrite it",
+            path.display()
+        );
+        assert_eq!(check_overwrite(&path, "csv", false), Err(refused));
+        assert_eq!(check_overwrite(&path, "json", false), Ok(()));
+        assert!(check_overwrite(&path, "json", true).is_err());
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(check_overwrite(&path, "dot", false), Ok(()));
+    }
+}
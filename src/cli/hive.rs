@@ -0,0 +1,99 @@
+//! `scan --hive`: cross-checks the task XML found on disk against the
+//! TaskCache of a SOFTWARE hive. A task file the registry does not list gets
+//! a `not-in-task-cache` finding; registered tasks no file matched are
+//! listed on stderr when the scan ends.
+
+use std::fs;
+use std::path::Path;
+
+use jobfileparser::{read_task_cache, Case, CachedTask, Finding};
+
+pub struct TaskCacheCheck {
+    tasks: Vec<CachedTask>,
+    /// Whether a file matched each of `tasks`.
+    matched: Vec<bool>,
+    /// The directories, as records show them, that task paths start from.
+    roots: Vec<String>,
+    case: Case,
+}
+
+impl TaskCacheCheck {
+    /// Reads the hive at `path`. `roots` are the scanned task directories;
+    /// task paths are matched as `case` says.
+    pub fn load(path: &Path, roots: Vec<String>, case: Case) -> Result<TaskCacheCheck, String> {
+        let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let tasks = read_task_cache(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(TaskCacheCheck {
+            matched: vec![false; tasks.len()],
+            tasks,
+            roots,
+            case,
+        })
+    }
+
+    /// The task path, `\Folder\Name`, of a file shown as `path`.
+    fn task_path(&self, path: &Path) -> Option<String> {
+        let shown = path.to_string_lossy();
+        self.roots.iter().find_map(|root| {
+            let rest = shown.strip_prefix(root.as_str())?;
+            let rest = rest.strip_prefix(['\\', '/'])?;
+            Some(format!("\\{}", rest.replace('/', "\\")))
+        })
+    }
+
+    /// Matches the task read from the file shown as `path` against the
+    /// registry, returning a finding if no registered task has its path.
+    pub fn check(&mut self, path: &Path) -> Option<Finding> {
+        let task_path = self.task_path(path)?;
+        let found = self.tasks.iter().position(|task| self.case.eq(&task.path, &task_path));
+        match found {
+            Some(index) => {
+                self.matched[index] = true;
+                None
+            }
+            None => Some(Finding {
+                rule: "not-in-task-cache".into(),
+                description: format!(
+                    "{} is not registered in the TaskCache; the task may have been planted \
+                     or its registry entries deleted",
+                    task_path
+                ),
+                ..Finding::default()
+            }),
+        }
+    }
+
+    /// The registered tasks that no file matched.
+    pub fn unmatched(&self) -> Vec<&CachedTask> {
+        self.tasks
+            .iter()
+            .zip(&self.matched)
+            .filter(|(_, &matched)| !matched)
+            .map(|(task, _)| task)
+            .collect()
+    }
+
+    /// Prints the registered tasks that no file matched.
+    pub fn report(&self) {
+        let missing = self.unmatched();
+        if missing.is_empty() {
+            return;
+        }
+        eprintln!("{} TaskCache tasks have no file on disk:", missing.len());
+        for task in &missing {
+            let listed = match (task.in_tasks, task.in_tree) {
+                (true, true) => "Tasks and Tree",
+                (true, false) => "Tasks only",
+                _ => "Tree only",
+            };
+            let last_run = task.dynamic_info.as_ref().and_then(|info| info.last_run.as_deref());
+            eprintln!(
+                "  {} {} ({}), last run {}",
+                task.path,
+                task.id,
+                listed,
+                last_run.unwrap_or("never")
+            );
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! `parse --hex` and `--b64`: a job or task given on the command line as
+//! text, such as a job body copied out of a network capture or a registry
+//! value, instead of in a file.
+//!
+//! Whitespace is ignored, so text wrapped over several lines, as `xxd -p`
+//! and `base64` write it, can be pasted as it is. Hex may also have `0x` in
+//! front of each run of digits. Positions in errors count the characters of
+//! the argument from 1.
+
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::{DecodeError, Engine};
+
+/// The name records of inline input are reported under.
+pub const SOURCE: &str = "<inline>";
+
+/// The bytes `text` spells in hex digits, two per byte, in either case.
+pub fn decode_hex(text: &str) -> Result<Vec<u8>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut digits = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let starts_run = i == 0 || chars[i - 1].is_whitespace();
+        if c.is_whitespace() {
+            i += 1;
+        } else if starts_run && c == '0' && matches!(chars.get(i + 1), Some('x' | 'X')) {
+            i += 2;
+        } else {
+            let digit = c.to_digit(16);
+            let digit = digit.ok_or_else(|| invalid("hex digit", c, i))?;
+            digits.push(digit as u8);
+            i += 1;
+        }
+    }
+    if digits.is_empty() {
+        return Err("no hex digits".to_string());
+    }
+    if !digits.len().is_multiple_of(2) {
+        let count = digits.len();
+        return Err(format!("{} hex digits, an odd number: the last byte is cut short", count));
+    }
+    Ok(digits.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect())
+}
+
+/// The bytes `text` encodes in base64, in the standard or the URL-safe
+/// alphabet, with or without padding.
+pub fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    // The symbols in the standard alphabet, and where each was.
+    let mut symbols = String::new();
+    let mut positions = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        let symbol = match c {
+            c if c.is_whitespace() => continue,
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '+' | '/' | '=' => c,
+            '-' => '+',
+            '_' => '/',
+            c => return Err(invalid("base64 character", c, i)),
+        };
+        symbols.push(symbol);
+        positions.push(i);
+    }
+    if symbols.is_empty() {
+        return Err("no base64 characters".to_string());
+    }
+    let config =
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    let engine = GeneralPurpose::new(&alphabet::STANDARD, config);
+    let char_at = |offset: usize| text.chars().nth(positions[offset]).unwrap_or_default();
+    engine.decode(&symbols).map_err(|e| match e {
+        DecodeError::InvalidByte(offset, _) => {
+            format!("unexpected {:?} at position {}", char_at(offset), positions[offset] + 1)
+        }
+        DecodeError::InvalidLastSymbol(offset, _) => format!(
+            "{:?} at position {} leaves bits over at the end",
+            char_at(offset),
+            positions[offset] + 1
+        ),
+        DecodeError::InvalidLength(_) => {
+            format!("{} base64 characters do not make whole bytes", symbols.len())
+        }
+        DecodeError::InvalidPadding => "the padding at the end is wrong".to_string(),
+    })
+}
+
+fn invalid(what: &str, c: char, index: usize) -> String {
+    format!("{:?} at position {} is not a {}", c, index + 1, what)
+}
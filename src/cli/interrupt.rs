@@ -0,0 +1,38 @@
+//! Ctrl-C for long runs. The first Ctrl-C cancels the token commands check
+//! between files, and that carving checks as it goes, so that the output
+//! stays whole and the summary is printed; a second one exits at once.
+
+use std::process;
+use std::sync::{Once, OnceLock};
+
+use jobfileparser::CancellationToken;
+
+use super::EXIT_INTERRUPTED;
+
+static INSTALL: Once = Once::new();
+
+/// Installs the handler, once per process.
+pub fn install() {
+    INSTALL.call_once(|| {
+        let handler = || {
+            if token().is_cancelled() {
+                process::exit(EXIT_INTERRUPTED.into());
+            }
+            token().cancel();
+        };
+        if let Err(e) = ctrlc::set_handler(handler) {
+            eprintln!("Unable to install the Ctrl-C handler: {}", e);
+        }
+    });
+}
+
+/// The token Ctrl-C cancels, for the library's cancellable scans.
+pub fn token() -> &'static CancellationToken {
+    static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+    TOKEN.get_or_init(CancellationToken::new)
+}
+
+/// Whether Ctrl-C has been pressed since `install`.
+pub fn interrupted() -> bool {
+    token().is_cancelled()
+}
@@ -0,0 +1,199 @@
+//! The guard against scanning the machine the tool runs on by mistake. On an
+//! analysis workstation `C:\Windows\System32\Tasks` is one mistyped path
+//! away, and its tasks look like any others once they are in the output.
+//!
+//! A directory counts as the running system's when it is, is inside, or
+//! with `-r` holds one of the task directories of `%SystemRoot%`, on the
+//! same volume, and `%SystemRoot%` holds the files of a Windows
+//! installation. A mounted image or a copied tree has a path of its own, or
+//! sits on another volume.
+
+use std::path::{Path, PathBuf};
+
+use super::relative;
+
+/// The task directories of a Windows installation, below its Windows
+/// directory.
+pub const TASK_DIRS: [&[&str]; 2] = [&["Tasks"], &["System32", "Tasks"]];
+
+/// Files every Windows installation has, below its Windows directory. A
+/// `%SystemRoot%` without them is not where the running system lives.
+const MARKERS: [&[&str]; 2] = [&["System32", "config", "SYSTEM"], &["System32", "ntoskrnl.exe"]];
+
+/// What the guard needs to know about the file systems of the machine.
+pub trait Probe {
+    /// The serial number of the volume holding `path`.
+    fn volume(&self, path: &Path) -> Option<u64>;
+    fn exists(&self, path: &Path) -> bool;
+}
+
+/// The file systems of the machine the tool runs on.
+pub struct Disk;
+
+impl Probe for Disk {
+    #[cfg(unix)]
+    fn volume(&self, path: &Path) -> Option<u64> {
+        use std::os::unix::fs::MetadataExt;
+        path.metadata().ok().map(|metadata| metadata.dev())
+    }
+
+    #[cfg(windows)]
+    fn volume(&self, path: &Path) -> Option<u64> {
+        use std::os::windows::ffi::OsStrExt;
+        use std::ptr;
+        use windows_sys::Win32::Storage::FileSystem::{GetVolumeInformationW, GetVolumePathNameW};
+
+        let name: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+        let mut root = [0u16; 261];
+        // SAFETY: `name` is NUL-terminated, and `root` is as long as the
+        // length passed.
+        if unsafe { GetVolumePathNameW(name.as_ptr(), root.as_mut_ptr(), root.len() as u32) } == 0 {
+            return None;
+        }
+        let mut serial = 0;
+        // SAFETY: `root` was NUL-terminated by GetVolumePathNameW, and the
+        // buffers not asked for are null with a length of 0.
+        let read = unsafe {
+            GetVolumeInformationW(
+                root.as_ptr(),
+                ptr::null_mut(),
+                0,
+                &mut serial,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+            )
+        };
+        (read != 0).then_some(u64::from(serial))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn volume(&self, _path: &Path) -> Option<u64> {
+        None
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+}
+
+/// `%SystemRoot%`, the Windows directory of the running system, read
+/// through `var`; `None` where there is none, as on other systems.
+pub fn system_root(var: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    let dir = var("SystemRoot").or_else(|| var("windir"))?;
+    (!dir.is_empty()).then(|| PathBuf::from(dir))
+}
+
+/// The task directory of the running system, whose Windows directory is
+/// `system_root`, that scanning `dir` would read; `None` if it reads none.
+pub fn live_task_dir(
+    dir: &Path,
+    recursive: bool,
+    system_root: Option<&Path>,
+    probe: &impl Probe,
+) -> Option<PathBuf> {
+    let system_root = system_root?;
+    let below = |parts: &[&str]| parts.iter().fold(system_root.to_path_buf(), |p, c| p.join(c));
+    if !MARKERS.iter().any(|marker| probe.exists(&below(marker))) {
+        return None;
+    }
+    let volume = probe.volume(system_root)?;
+    if probe.volume(dir) != Some(volume) {
+        return None;
+    }
+    TASK_DIRS.iter().map(|parts| below(parts)).find(|tasks| {
+        relative::relative_to(tasks, dir).is_some()
+            || (recursive && relative::relative_to(dir, tasks).is_some())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A machine whose files are `files`, each on the volume of the first
+    /// entry of `volumes` whose directory holds it.
+    struct Fake {
+        files: Vec<PathBuf>,
+        volumes: Vec<(PathBuf, u64)>,
+    }
+
+    impl Probe for Fake {
+        fn volume(&self, path: &Path) -> Option<u64> {
+            let path = relative::absolute(path);
+            let mut volumes = self.volumes.iter();
+            let found = volumes.find(|(dir, _)| path.starts_with(relative::absolute(dir)));
+            found.map(|(_, volume)| *volume)
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.files.iter().any(|file| file == path)
+        }
+    }
+
+    fn workstation() -> Fake {
+        Fake {
+            files: vec![PathBuf::from("/c/Windows/System32/config/SYSTEM")],
+            volumes: vec![(PathBuf::from("/c"), 1)],
+        }
+    }
+
+    fn live(dir: &str, recursive: bool, root: Option<&str>, probe: &Fake) -> Option<PathBuf> {
+        live_task_dir(Path::new(dir), recursive, root.map(Path::new), probe)
+    }
+
+    #[test]
+    fn test_live_task_dirs() {
+        let probe = workstation();
+        let root = Some("/c/Windows");
+        let tasks = Some(PathBuf::from("/c/Windows/System32/Tasks"));
+        assert_eq!(live("/c/Windows/System32/Tasks", false, root, &probe), tasks);
+        assert_eq!(live("/c/Windows/System32/Tasks/", false, root, &probe), tasks);
+        assert_eq!(live("/c/Windows/System32/Tasks/Microsoft", false, root, &probe), tasks);
+        assert_eq!(
+            live("/c/Windows/Tasks", false, root, &probe),
+            Some(PathBuf::from("/c/Windows/Tasks"))
+        );
+        // A directory holding a task directory only reads it with -r.
+        assert_eq!(live("/c/Windows/System32", true, root, &probe), tasks);
+        assert_eq!(live("/c/Windows/System32", false, root, &probe), None);
+        assert_eq!(live("/c/Windows/System32/drivers", true, root, &probe), None);
+    }
+
+    #[test]
+    fn test_images_and_copies() {
+        let probe = workstation();
+        let root = Some("/c/Windows");
+        // A copied tree has a path of its own.
+        assert_eq!(live("/c/cases/17/Windows/System32/Tasks", true, root, &probe), None);
+        // A volume mounted below the task directory is not the system's.
+        let mounted = "/c/Windows/System32/Tasks/mnt";
+        assert!(live(mounted, false, root, &probe).is_some());
+        let probe = Fake {
+            volumes: vec![(PathBuf::from(mounted), 2), (PathBuf::from("/c"), 1)],
+            ..workstation()
+        };
+        assert_eq!(live(mounted, false, root, &probe), None);
+        // No %SystemRoot%, or one without a Windows installation in it.
+        assert_eq!(live("/c/Windows/System32/Tasks", false, None, &probe), None);
+        let probe = Fake {
+            files: Vec::new(),
+            ..workstation()
+        };
+        assert_eq!(live("/c/Windows/System32/Tasks", false, root, &probe), None);
+    }
+
+    #[test]
+    fn test_system_root() {
+        let var = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(system_root(var(&[("SystemRoot", r"C:\WINDOWS")])), Some(r"C:\WINDOWS".into()));
+        assert_eq!(system_root(var(&[("windir", r"D:\Windows")])), Some(r"D:\Windows".into()));
+        assert_eq!(system_root(var(&[("SystemRoot", "")])), None);
+        assert_eq!(system_root(var(&[])), None);
+    }
+}
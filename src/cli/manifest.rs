@@ -0,0 +1,162 @@
+//! `--manifest`: a chain-of-custody record of a run, listing every input
+//! with its size, hashes and parse status, the tool version and command
+//! line, when the run started and ended, and the hashes of the `-w` file.
+//! `verify-manifest` hashes the files again and reports any that changed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jobfileparser::date::format_unix;
+use jobfileparser::Error;
+use serde::{Deserialize, Serialize};
+use sha1_smol::Sha1;
+use sha2::{Digest, Sha256};
+
+use super::{EXIT_FATAL, EXIT_PARSE_FAILURE, EXIT_SUCCESS};
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub tool: String,
+    pub version: String,
+    pub command_line: Vec<String>,
+    /// UTC, as `YYYY-MM-DDTHH:MM:SSZ`.
+    pub started: String,
+    pub finished: String,
+    /// Stopped early by Ctrl-C; the inputs are those handled until then.
+    pub interrupted: bool,
+    pub inputs: Vec<FileEntry>,
+    /// The `-w` file; `None` if the records went to stdout.
+    pub output: Option<FileEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FileEntry {
+    pub path: String,
+    /// `None`, like the hashes, if the file could not be read.
+    pub size: Option<u64>,
+    pub sha1: Option<String>,
+    pub sha256: Option<String>,
+    /// For inputs: `parsed`, or `failed` with the `Error::kind` in `error`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FileEntry {
+    fn new(path: &Path, data: Option<&[u8]>) -> FileEntry {
+        // Absolute, so that the manifest can be checked from anywhere.
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        FileEntry {
+            path: path.display().to_string(),
+            size: data.map(|data| data.len() as u64),
+            sha1: data.map(|data| Sha1::from(data).digest().to_string()),
+            sha256: data.map(sha256),
+            status: None,
+            error: None,
+        }
+    }
+}
+
+/// The manifest of the run in progress, written by `finish`.
+pub struct ManifestWriter {
+    path: PathBuf,
+    manifest: Manifest,
+}
+
+impl ManifestWriter {
+    /// Starts the manifest to be written to `path`.
+    pub fn start(path: &Path) -> ManifestWriter {
+        ManifestWriter {
+            path: path.to_path_buf(),
+            manifest: Manifest {
+                tool: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                command_line: std::env::args().collect(),
+                started: now(),
+                finished: String::new(),
+                interrupted: false,
+                inputs: Vec::new(),
+                output: None,
+            },
+        }
+    }
+
+    /// Lists an input read as `data`, or that could not be read, and the
+    /// first error parsing it.
+    pub fn add_input(&mut self, path: &Path, data: Option<&[u8]>, error: Option<&Error>) {
+        let mut entry = FileEntry::new(path, data);
+        entry.status = Some(if error.is_some() { "failed" } else { "parsed" }.to_string());
+        entry.error = error.map(|e| e.kind().to_string());
+        self.manifest.inputs.push(entry);
+    }
+
+    /// Hashes `output`, the finished `-w` file, and writes the manifest.
+    /// Errors are reported on stderr.
+    pub fn finish(mut self, output: Option<&Path>, interrupted: bool) {
+        self.manifest.finished = now();
+        self.manifest.interrupted = interrupted;
+        self.manifest.output =
+            output.map(|path| FileEntry::new(path, fs::read(path).ok().as_deref()));
+        let written = serde_json::to_string_pretty(&self.manifest)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&self.path, json + "\n").map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            eprintln!("Unable to write the manifest {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Hashes the files `manifest` lists again and prints those whose size or
+/// hashes changed, or that are gone. Returns 1 if any did.
+pub fn verify(manifest: &Path) -> u8 {
+    let manifest: Manifest = match fs::read(manifest)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_slice(&data).map_err(|e| e.to_string()))
+    {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("Unable to read the manifest {}: {}", manifest.display(), e);
+            return EXIT_FATAL;
+        }
+    };
+    let mut checked = 0;
+    let mut changed = 0;
+    for entry in manifest.inputs.iter().chain(&manifest.output) {
+        // Unreadable when the manifest was written: nothing to compare.
+        if entry.size.is_none() {
+            continue;
+        }
+        checked += 1;
+        let path = Path::new(&entry.path);
+        let data = fs::read(path).ok();
+        let now = FileEntry::new(path, data.as_deref());
+        let problem = if data.is_none() {
+            "missing".to_string()
+        } else if now.size != entry.size {
+            format!("size {} -> {}", entry.size.unwrap_or(0), now.size.unwrap_or(0))
+        } else if now.sha256 != entry.sha256 || now.sha1 != entry.sha1 {
+            "hash mismatch".to_string()
+        } else {
+            continue;
+        };
+        changed += 1;
+        println!("{}: {}", entry.path, problem);
+    }
+    eprintln!("{} of {} files match the manifest", checked - changed, checked);
+    if changed > 0 {
+        EXIT_PARSE_FAILURE
+    } else {
+        EXIT_SUCCESS
+    }
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn now() -> String {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{}Z", format_unix(since_epoch.as_secs()))
+}
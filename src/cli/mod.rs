@@ -0,0 +1,56 @@
+//! Pieces of the command-line tool that are not part of the library API.
+
+pub mod aggregate;
+pub mod bundle;
+pub mod cache;
+pub mod check;
+pub mod commands;
+pub mod completions;
+pub mod config;
+pub mod csv;
+pub mod custom_rules;
+pub mod dot;
+pub mod extension;
+#[cfg(feature = "hive")]
+pub mod hive;
+pub mod inline;
+pub mod interrupt;
+pub mod live;
+pub mod manifest;
+pub mod openioc;
+pub mod output;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod progress;
+pub mod relative;
+pub mod rollup;
+pub mod rules;
+pub mod schedlog;
+pub mod selftest;
+pub mod sidecar;
+#[cfg(feature = "http")]
+pub mod serve;
+pub mod sink;
+pub mod source;
+pub mod taskxml;
+pub mod terminal;
+pub mod timeline;
+pub mod timezone;
+pub mod timings;
+pub mod truncate;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+/// Every file parsed and, with `--detect`, nothing was flagged.
+pub const EXIT_SUCCESS: u8 = 0;
+/// At least one file failed to parse (or had findings, with
+/// `--fail-on-findings`); for `diff`, the artifacts differ.
+pub const EXIT_PARSE_FAILURE: u8 = 1;
+/// Bad arguments or an unreadable scan root; nothing useful was produced.
+pub const EXIT_FATAL: u8 = 2;
+/// `--detect` flagged at least one file.
+pub const EXIT_FINDINGS: u8 = 3;
+/// Stopped by Ctrl-C; the output covers the files handled until then.
+pub const EXIT_INTERRUPTED: u8 = 130;
@@ -0,0 +1,199 @@
+//! `-o openioc`: the indicators of the records with findings as an OpenIOC
+//! 1.1 document, printed once every record is in. Each record becomes an
+//! `Indicator` that ORs together its terms:
+//!
+//! - `TaskItem/Name`: the task's name
+//! - `TaskItem/ActionList/Action/ExecProgramPath` and `ExecArguments`: the
+//!   program it runs, and its arguments
+//! - `FileItem/FullPath`: the program behind a wrapper such as `cmd /c`, and
+//!   the script and UNC paths among its indicators
+//! - `UrlHistoryItem/URL`: the URLs among them
+//! - `TaskItem/JobUUID`: a job's UUID. The standard terms have none, so this
+//!   one is the tool's own, with a context type of `jobfileparser`.
+//!
+//! The path of each record and the rules it matched go in a `comment`
+//! parameter of its indicator. Ids are derived from the content, so scanning
+//! the same files with the same `--now` gives the same document.
+
+use std::fmt::Write;
+
+use jobfileparser::entropy::task_name;
+use jobfileparser::fold::fold;
+use jobfileparser::{IndicatorKind, ParsedArtifact, Record, UuidFormat};
+use sha2::{Digest, Sha256};
+
+const AUTHOR: &str = concat!("jobfileparser ", env!("CARGO_PKG_VERSION"));
+
+/// One `IndicatorItem`.
+struct Term {
+    search: &'static str,
+    condition: &'static str,
+    value: String,
+}
+
+/// A record with findings and its terms.
+struct Entry {
+    comment: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Default)]
+pub struct Ioc {
+    entries: Vec<Entry>,
+}
+
+impl Ioc {
+    /// Adds the terms of `record`, if it has findings. A job's UUID is
+    /// written in `uuid_format`.
+    pub fn add(&mut self, record: &Record, uuid_format: UuidFormat) {
+        let Record::Ok { artifact, .. } = record else {
+            return;
+        };
+        if record.findings().is_empty() {
+            return;
+        }
+        let mut terms = Vec::new();
+        let mut push = |search, condition, value: &str| {
+            let value = value.trim();
+            let duplicate = terms.iter().any(|term: &Term| {
+                term.search == search && fold(&term.value) == fold(value)
+            });
+            if !value.is_empty() && !duplicate {
+                terms.push(Term { search, condition, value: value.to_string() });
+            }
+        };
+        push("TaskItem/Name", "is", task_name(record.path()));
+        if let Some(command) = artifact.command() {
+            push("TaskItem/ActionList/Action/ExecProgramPath", "is", &command.executable);
+            if let Some(program) = &command.effective_executable {
+                push("FileItem/FullPath", "contains", program);
+            }
+        }
+        if let Some(arguments) = artifact.arguments() {
+            push("TaskItem/ActionList/Action/ExecArguments", "is", arguments);
+        }
+        for indicator in record.indicators() {
+            match indicator.kind {
+                IndicatorKind::Url => push("UrlHistoryItem/URL", "is", &indicator.value),
+                IndicatorKind::Script | IndicatorKind::Unc => {
+                    push("FileItem/FullPath", "contains", &indicator.value)
+                }
+            }
+        }
+        if let ParsedArtifact::Job(job) = artifact {
+            push("TaskItem/JobUUID", "is", &job.uuid.format(uuid_format));
+        }
+        let rules: Vec<&str> = record.findings().iter().map(|f| f.rule.as_ref()).collect();
+        self.entries.push(Entry {
+            comment: format!("{}: {}", record.path(), rules.join(", ")),
+            terms,
+        });
+    }
+
+    /// The document, authored at `now`, a `YYYY-MM-DDTHH:MM:SS` timestamp.
+    pub fn render(&self, now: &str) -> String {
+        let mut seed = Sha256::new();
+        seed.update(now);
+        for entry in &self.entries {
+            seed.update(&entry.comment);
+            for term in &entry.terms {
+                seed.update([0]);
+                seed.update(&term.value);
+            }
+        }
+        let seed = seed.finalize();
+        let mut next_id = 0;
+        let mut id = || {
+            next_id += 1;
+            derived_id(&seed, next_id)
+        };
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<OpenIOC xmlns=\"http://openioc.org/schemas/OpenIOC_1.1\" id=\"{}\" \
+             last-modified=\"{}\">",
+            derived_id(&seed, 0),
+            escape(now)
+        );
+        xml.push_str("  <metadata>\n");
+        let description = format!("Indicators of {} scheduled tasks with findings", self.entries.len());
+        let _ = writeln!(xml, "    <short_description>{}</short_description>", AUTHOR);
+        let _ = writeln!(xml, "    <description>{}</description>", description);
+        xml.push_str("    <keywords/>\n");
+        let _ = writeln!(xml, "    <authored_by>{}</authored_by>", AUTHOR);
+        let _ = writeln!(xml, "    <authored_date>{}</authored_date>", escape(now));
+        xml.push_str("    <links/>\n  </metadata>\n  <criteria>\n");
+        let _ = writeln!(xml, "    <Indicator id=\"{}\" operator=\"OR\">", id());
+        let mut parameters = Vec::new();
+        for entry in &self.entries {
+            let indicator = id();
+            let _ = writeln!(xml, "      <Indicator id=\"{}\" operator=\"OR\">", indicator);
+            for term in &entry.terms {
+                let (document, kind) = match term.search.split_once('/') {
+                    Some((document, "JobUUID")) => (document, "jobfileparser"),
+                    Some((document, _)) => (document, "mir"),
+                    None => (term.search, "mir"),
+                };
+                let _ = writeln!(
+                    xml,
+                    "        <IndicatorItem id=\"{}\" condition=\"{}\">\n          \
+                     <Context document=\"{}\" search=\"{}\" type=\"{}\"/>\n          \
+                     <Content type=\"string\">{}</Content>\n        </IndicatorItem>",
+                    id(),
+                    term.condition,
+                    document,
+                    term.search,
+                    kind,
+                    escape(&term.value)
+                );
+            }
+            xml.push_str("      </Indicator>\n");
+            parameters.push((indicator, &entry.comment));
+        }
+        xml.push_str("    </Indicator>\n  </criteria>\n  <parameters>\n");
+        for (indicator, comment) in parameters {
+            let _ = writeln!(
+                xml,
+                "    <param id=\"{}\" ref-id=\"{}\" name=\"comment\">\n      \
+                 <value type=\"string\">{}</value>\n    </param>",
+                id(),
+                indicator,
+                escape(comment)
+            );
+        }
+        xml.push_str("  </parameters>\n</OpenIOC>\n");
+        xml
+    }
+}
+
+/// The `n`th id of a document, a UUID made from `seed` as RFC 9562
+/// version 8 allows.
+fn derived_id(seed: &[u8], n: u64) -> String {
+    let mut hash = Sha256::new();
+    hash.update(seed);
+    hash.update(n.to_le_bytes());
+    let mut bytes: [u8; 16] = hash.finalize()[..16].try_into().unwrap_or_default();
+    bytes[6] = (bytes[6] & 0x0F) | 0x80;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// `text` as XML character data or an attribute value. Characters XML 1.0
+/// does not allow at all, such as most control characters, become U+FFFD.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}' => escaped.push('\u{FFFD}'),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
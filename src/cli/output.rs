@@ -0,0 +1,1461 @@
+//! Rendering of records on stdout or into the `-w` file.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use jobfileparser::record::{Origin, SCHEMA_VERSION};
+use jobfileparser::task::ActionTarget;
+use jobfileparser::{
+    enabled, job, ArtifactFormat, Case, Classification, DateStyle, DecodedArtifact, DetectOptions,
+    Enablement, Error, FileSecurity, Finding, Indicator, IndicatorKind, KnowledgeBase,
+    ParsedArtifact, PathRules, Record, UuidFormat, Warning, WarningCode, UUID,
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::cache;
+use super::check::HygieneCheck;
+use super::commands::{Loaded, Settings};
+use super::csv::{self, CsvStyle};
+use super::custom_rules::RuleSet;
+use super::dot::Graph;
+use super::manifest::ManifestWriter;
+use super::openioc::Ioc;
+use super::progress::Progress;
+use super::relative;
+use super::schedlog::SchedLogCheck;
+use super::sink::Sink;
+use super::taskxml::TaskBundle;
+use super::terminal::{Capabilities, Paint};
+use super::timeline::{self, FileTimes};
+use super::timezone::Zone;
+#[cfg(feature = "hive")]
+use super::hive::TaskCacheCheck;
+use super::timings::{self, SlowFiles, Stage};
+use super::truncate;
+use super::{EXIT_FINDINGS, EXIT_PARSE_FAILURE, EXIT_SUCCESS};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    JsonLines,
+    /// One tab-separated line per record: path, user, command line and last
+    /// run. The columns are fixed so that scripts can rely on them.
+    Minimal,
+    /// Comma-separated values under a header row, with the delimiter and
+    /// quoting of `Settings::csv`.
+    Csv,
+    /// A Graphviz digraph of the tasks and the programs they run, printed
+    /// once every record is in.
+    Dot,
+    /// An OpenIOC 1.1 document of the indicators of records with findings,
+    /// printed once every record is in.
+    OpenIoc,
+    /// Every task as task XML in one document, printed once every record
+    /// is in, or with `Settings::split_out_dir` written to a file each.
+    TaskXmlBundle,
+    /// Keep records in memory for the interactive browser instead of
+    /// printing them.
+    #[cfg(feature = "tui")]
+    Collect,
+    /// `--validate-only`: a line with the path, code and message of each
+    /// warning, and nothing for records without any.
+    Validate,
+    /// The times of each record as timeline events, in UTC.
+    Timeline(timeline::Style),
+}
+
+impl Format {
+    /// The `--output-format` names, one for each format but `Collect` and
+    /// `Validate`.
+    pub const NAMES: [&'static str; 11] = [
+        "text",
+        "json",
+        "jsonl",
+        "minimal",
+        "csv",
+        "dot",
+        "openioc",
+        "taskxml-bundle",
+        "bodyfile",
+        "tln",
+        "plaso",
+    ];
+
+    /// Looks up a format by its `--output-format` name.
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "jsonl" => Some(Format::JsonLines),
+            "minimal" => Some(Format::Minimal),
+            "csv" => Some(Format::Csv),
+            "dot" => Some(Format::Dot),
+            "openioc" => Some(Format::OpenIoc),
+            "taskxml-bundle" => Some(Format::TaskXmlBundle),
+            "bodyfile" => Some(Format::Timeline(timeline::Style::Bodyfile)),
+            "tln" => Some(Format::Timeline(timeline::Style::Tln)),
+            "plaso" => Some(Format::Timeline(timeline::Style::Plaso)),
+            _ => None,
+        }
+    }
+}
+
+/// A `scan --list-only` entry.
+#[derive(Serialize)]
+struct Listing<'a> {
+    path: String,
+    format: &'static str,
+    #[serde(flatten)]
+    context: Context<'a>,
+}
+
+/// What `scan --watch` writes for a removed file.
+#[derive(Serialize)]
+struct Tombstone<'a> {
+    path: String,
+    #[serde(flatten)]
+    context: Context<'a>,
+}
+
+/// A record as `-o json` and `-o jsonl` write it: the keys of the `Record`,
+/// then those the options add.
+#[derive(Serialize, JsonSchema)]
+struct JsonRecord<'a> {
+    #[serde(flatten)]
+    record: &'a Record,
+    /// The absolute path of the file the record was read from, with
+    /// `--fields absolute_path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    absolute_path: Option<&'a str>,
+    /// How long reading and parsing the file and building the record took,
+    /// in milliseconds, with `--verbose`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<f64>,
+    #[serde(flatten)]
+    context: Context<'a>,
+}
+
+/// Where in a scan, and why, a JSON object was written: the last keys of
+/// records, listings and tombstones alike.
+#[derive(Serialize, JsonSchema)]
+struct Context<'a> {
+    /// The `scan --watch` event: `initial`, `created`, `modified` or
+    /// `removed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event: Option<&'a str>,
+    /// The directory given to `scan` the file was found in, when a scan
+    /// covers several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root: Option<&'a str>,
+    /// The directory the file was grouped under with `scan --group-by-dir`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<&'a str>,
+}
+
+/// A JSON Schema (draft 2020-12) describing the records of `-o json` and
+/// `-o jsonl`, for `--print-schema`. The top-level `version` is the record
+/// `SCHEMA_VERSION`.
+pub fn record_schema() -> serde_json::Value {
+    let mut schema = schemars::generate::SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<JsonRecord>()
+        .to_value();
+    if let Some(object) = schema.as_object_mut() {
+        object.insert("version".to_string(), SCHEMA_VERSION.into());
+    }
+    schema
+}
+
+/// The last line of `-o jsonl` with `--only-findings`.
+#[derive(Serialize)]
+struct Summary {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// Every record, written or not.
+    records: usize,
+    with_findings: usize,
+    failed: usize,
+}
+
+/// Writes records to stdout, or the `-w` file, in the selected format and
+/// keeps the tallies the exit code is derived from. Failures are always reported on stderr as
+/// well; the JSON formats also emit an error record.
+pub struct Output {
+    out: Sink,
+    format: Format,
+    detect: bool,
+    detect_options: DetectOptions,
+    min_coverage: f64,
+    path_rules: Option<PathRules>,
+    custom_rules: Option<RuleSet>,
+    /// How `--stats` tells indicators apart.
+    case: Case,
+    /// Embed the bytes of each artifact in its record.
+    embed_raw: bool,
+    /// Name unrecognized task XML elements on stderr.
+    verbose: bool,
+    date_style: DateStyle,
+    uuid_format: UuidFormat,
+    csv: CsvStyle,
+    /// How many rows `-o csv` has written, the `record_index` of the last.
+    csv_rows: usize,
+    /// The `--csv-triggers` file.
+    csv_triggers: Option<BufWriter<File>>,
+    /// The `--csv-actions` file.
+    csv_actions: Option<BufWriter<File>>,
+    /// Print `# <path>` instead of the banner around jobs, and no group
+    /// headers.
+    no_banner: bool,
+    /// Leave out the CSV header and the group table header.
+    no_header: bool,
+    /// Color and wrapping of the text format.
+    terminal: Capabilities,
+    /// `--max-string-length`; `None` with `--no-truncate`.
+    max_string_length: Option<usize>,
+    /// Strings cut to `max_string_length`.
+    truncated: usize,
+    now: String,
+    /// Drop parsed records that `never_ran_but_scheduled` does not flag.
+    never_ran: bool,
+    /// Drop parsed records whose `effective_enabled` differs, for
+    /// `--enabled-only` and `--disabled-only`.
+    enabled_filter: Option<bool>,
+    /// Drop parsed records whose `trigger_count` is not known to be 0.
+    no_triggers: bool,
+    /// Drop parsed records with fewer than two actions.
+    multi_action: bool,
+    /// Drop parsed records other than jobs with this UUID.
+    find_uuid: Option<UUID>,
+    /// Drop records without findings, for `--only-findings`, and sum up at
+    /// the end.
+    only_findings: bool,
+    /// Records dropped by `--only-findings`.
+    unflagged: usize,
+    /// The artifacts written so far, for `--dedupe`; `None` without it.
+    seen: Option<HashSet<ParsedArtifact>>,
+    /// Records dropped by `--dedupe`.
+    duplicates: usize,
+    /// How many records name each indicator, for `--stats`, by the
+    /// indicator as first seen; `None` without it.
+    indicators: Option<HashMap<(IndicatorKind, String), (Indicator, usize)>>,
+    count: usize,
+    failed: usize,
+    /// Failures by `Error::kind`, for the summary.
+    failures: BTreeMap<&'static str, usize>,
+    flagged: usize,
+    /// Records with at least one warning.
+    warned: usize,
+    /// Records with `DuplicateTrigger` and `ConflictingTrigger` warnings,
+    /// for `--stats`.
+    duplicate_triggers: usize,
+    conflicting_triggers: usize,
+    /// Jobs by `COVERAGE_BUCKETS`, for `--stats`.
+    coverage: [usize; COVERAGE_BUCKETS.len()],
+    /// Print the indicator and trigger counts at the end.
+    stats: bool,
+    /// For `--progress`.
+    progress: Option<Progress>,
+    /// Where the following records' files came from; see `set_origin`.
+    origin: Origin,
+    /// Records for `At<n>.job` files.
+    at_jobs: usize,
+    /// Stopped early by Ctrl-C.
+    interrupted: bool,
+    /// Tallies for `scan --group-by-dir`, in the order the groups were
+    /// started. The last one is the current group.
+    groups: Vec<Group>,
+    /// Tallies for a scan of several directories, one per directory in the
+    /// order they were scanned. The last one is the current root.
+    roots: Vec<Group>,
+    /// For `scan --hive`.
+    #[cfg(feature = "hive")]
+    task_cache: Option<TaskCacheCheck>,
+    /// For `--schedlog`.
+    schedlog: Option<SchedLogCheck>,
+    /// For `--check`.
+    check: Option<HygieneCheck>,
+    /// Classify tasks against the built-in knowledge base, for
+    /// `--classify`.
+    classify: bool,
+    /// Read the owner and DACL of each file, for `--acl`.
+    acl: bool,
+    /// Those of the file whose records are being written.
+    security: Option<FileSecurity>,
+    /// The zone of the local times in artifacts, for `Format::Timeline`.
+    timezone: Option<Zone>,
+    /// The file system times of the file whose records are being written,
+    /// for `Format::Timeline`.
+    file_times: Option<FileTimes>,
+    /// How long reading and parsing the file whose records are being
+    /// written took.
+    loaded_in: Duration,
+    /// For `--slow-threshold` and the slowest files at the end.
+    slow: SlowFiles,
+    /// Add `absolute_path` to JSON records, for `--fields absolute_path`.
+    absolute_path: bool,
+    /// The absolute path of the file whose records are being written; see
+    /// `set_source`.
+    source: Option<String>,
+    /// Records by classification, for the summary.
+    classified: HashMap<Classification, usize>,
+    /// Unmodified Microsoft defaults without findings, left out by
+    /// `--classify`.
+    collapsed: usize,
+    /// For `--manifest`; taken when it is written.
+    manifest: Option<ManifestWriter>,
+    /// The tasks kept for `Format::Dot`.
+    graph: Graph,
+    /// The indicators kept for `Format::OpenIoc`.
+    ioc: Ioc,
+    /// The tasks kept for `Format::TaskXmlBundle`.
+    tasks: TaskBundle,
+    /// `--split-out-dir`.
+    split_out_dir: Option<PathBuf>,
+    /// Tasks `--split-out-dir` could not write.
+    unwritten: usize,
+    #[cfg(feature = "tui")]
+    records: Vec<Record>,
+}
+
+impl Output {
+    /// Fails if the `-w` file cannot be created.
+    pub fn new(settings: &Settings) -> Result<Output, String> {
+        let header = match settings.format {
+            Format::Json => Some("[\n".to_string()),
+            Format::Csv if !settings.no_header => {
+                Some(format!("{}\n", settings.csv.row(&csv::COLUMNS)))
+            }
+            _ => None,
+        };
+        let out = Sink::open(settings.write.as_ref(), header)?;
+        let side_file = |path: &Option<PathBuf>, columns: &[&str]| {
+            let Some(path) = path else {
+                return Ok(None);
+            };
+            let file = File::create(path)
+                .map_err(|e| format!("Unable to create {}: {}", path.display(), e))?;
+            let mut file = BufWriter::new(file);
+            if !settings.no_header {
+                let _ = writeln!(file, "{}", settings.csv.row(columns));
+            }
+            Ok::<_, String>(Some(file))
+        };
+        let csv_triggers = side_file(&settings.csv_triggers, &csv::TRIGGER_COLUMNS)?;
+        let csv_actions = side_file(&settings.csv_actions, &csv::ACTION_COLUMNS)?;
+        if settings.timings {
+            timings::enable(settings.verbose);
+        }
+        if let Some(dir) = &settings.cache {
+            cache::enable(dir)?;
+        }
+        Ok(Output {
+            out,
+            format: settings.format,
+            detect: settings.detect,
+            detect_options: settings.detect_options,
+            min_coverage: settings.min_coverage,
+            path_rules: settings.path_rules.clone(),
+            custom_rules: settings.custom_rules.clone(),
+            case: settings.case,
+            embed_raw: settings.embed_raw,
+            verbose: settings.verbose,
+            date_style: settings.date_style,
+            uuid_format: settings.uuid_format,
+            csv: settings.csv,
+            csv_rows: 0,
+            csv_triggers,
+            csv_actions,
+            no_banner: settings.no_banner,
+            no_header: settings.no_header,
+            terminal: settings.terminal,
+            max_string_length: settings.max_string_length,
+            truncated: 0,
+            now: settings.now.clone(),
+            never_ran: settings.never_ran,
+            enabled_filter: settings.enabled_filter,
+            no_triggers: settings.no_triggers,
+            multi_action: settings.multi_action,
+            find_uuid: settings.find_uuid.clone(),
+            only_findings: settings.only_findings,
+            unflagged: 0,
+            seen: settings.dedupe.then(HashSet::new),
+            duplicates: 0,
+            indicators: settings.stats.then(HashMap::new),
+            count: 0,
+            failed: 0,
+            failures: BTreeMap::new(),
+            flagged: 0,
+            warned: 0,
+            duplicate_triggers: 0,
+            conflicting_triggers: 0,
+            coverage: [0; COVERAGE_BUCKETS.len()],
+            stats: settings.stats,
+            progress: settings.progress.then(Progress::new),
+            origin: Origin::default(),
+            at_jobs: 0,
+            interrupted: false,
+            groups: Vec::new(),
+            roots: Vec::new(),
+            #[cfg(feature = "hive")]
+            task_cache: None,
+            schedlog: settings.schedlog.clone(),
+            check: settings.check.clone(),
+            classify: settings.classify,
+            acl: settings.acl,
+            security: None,
+            timezone: settings.timezone.clone(),
+            file_times: None,
+            loaded_in: Duration::ZERO,
+            slow: SlowFiles::new(settings.slow_threshold, settings.timings),
+            absolute_path: settings.absolute_path,
+            source: None,
+            classified: HashMap::new(),
+            collapsed: 0,
+            manifest: settings.manifest.as_deref().map(ManifestWriter::start),
+            graph: Graph::default(),
+            ioc: Ioc::default(),
+            tasks: TaskBundle::default(),
+            split_out_dir: settings.split_out_dir.clone(),
+            unwritten: 0,
+            #[cfg(feature = "tui")]
+            records: Vec::new(),
+        })
+    }
+
+    /// Checks the tasks written from now on against `check`, and lists
+    /// the registered tasks none of them matched when finishing.
+    #[cfg(feature = "hive")]
+    pub fn check_task_cache(&mut self, check: TaskCacheCheck) {
+        self.task_cache = Some(check);
+    }
+
+    /// Starts the group the following records belong to. Text output gets a
+    /// header; JSON records get a `group` key.
+    pub fn start_group(&mut self, name: &str) {
+        if self.groups.last().is_some_and(|group| group.name == name) {
+            return;
+        }
+        if self.format == Format::Text && !self.no_banner {
+            if !self.groups.is_empty() {
+                let _ = writeln!(self.out);
+            }
+            let _ = writeln!(self.out, "== {} ==", name);
+        }
+        self.groups.push(Group {
+            name: name.to_string(),
+            ..Group::default()
+        });
+    }
+
+    /// Starts the scanned directory the following records were found in,
+    /// when a scan covers several. JSON records get a `root` key, and the
+    /// directories are tallied at the end.
+    pub fn start_root(&mut self, name: &str) {
+        self.roots.push(Group {
+            name: name.to_string(),
+            ..Group::default()
+        });
+    }
+
+    /// Counts a written record, in the current group and root too if there
+    /// are, and flushes it so that it survives the process being killed.
+    fn counted(&mut self, failed: bool, findings: usize) {
+        self.out.end_record();
+        self.count += 1;
+        for tally in [self.groups.last_mut(), self.roots.last_mut()].into_iter().flatten() {
+            tally.records += 1;
+            tally.failed += usize::from(failed);
+            tally.flagged += usize::from(findings > 0);
+            tally.findings += findings;
+        }
+    }
+
+    pub fn write(&mut self, path: &Path, result: Result<ParsedArtifact, Error>) {
+        self.write_event(path, Some(result), None, None);
+    }
+
+    /// Writes the records for a file read by `commands::load` and returns
+    /// how many were written. A file holding several tasks gets a record per
+    /// task, at `<path>#<n>`.
+    pub fn write_loaded(
+        &mut self,
+        path: &Path,
+        loaded: Result<Loaded, Error>,
+        event: Option<&str>,
+    ) -> usize {
+        if let Some(manifest) = &mut self.manifest {
+            match &loaded {
+                Ok(loaded) => {
+                    let error = loaded.results.iter().find_map(|result| result.as_ref().err());
+                    manifest.add_input(&loaded.source, Some(&loaded.data), error);
+                }
+                Err(e) => manifest.add_input(path, None, Some(e)),
+            }
+        }
+        if let Some(progress) = &mut self.progress {
+            let failed = loaded.as_ref().map_or(true, |loaded| {
+                loaded.results.iter().any(|result| result.is_err())
+            });
+            progress.file_done(failed);
+        }
+        let loaded = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.write_event(path, Some(Err(e)), None, event);
+                return 1;
+            }
+        };
+        self.security = if self.acl { read_security(&loaded.source) } else { None };
+        if matches!(self.format, Format::Timeline(_)) {
+            self.file_times = FileTimes::read(&loaded.source);
+        }
+        let start = Instant::now();
+        self.loaded_in = loaded.elapsed;
+        let count = loaded.results.len();
+        for (i, result) in loaded.results.into_iter().enumerate() {
+            let numbered;
+            let path = if count > 1 {
+                numbered = PathBuf::from(format!("{}#{}", path.display(), i + 1));
+                &numbered
+            } else {
+                path
+            };
+            let (mismatch, warnings) = (loaded.extension_mismatch, &loaded.warnings);
+            self.write_record(path, result, Some(&loaded.data), mismatch, warnings, event);
+        }
+        self.security = None;
+        self.file_times = None;
+        self.loaded_in = Duration::ZERO;
+        self.slow.add(path, loaded.elapsed + start.elapsed());
+        count
+    }
+
+    /// Like `write`, with the bytes `result` was parsed from. They are
+    /// embedded in the record if `--embed-raw` was given.
+    pub fn write_raw(
+        &mut self,
+        path: &Path,
+        result: Result<ParsedArtifact, Error>,
+        raw: Option<&[u8]>,
+    ) {
+        self.write_event(path, Some(result), raw, None);
+    }
+
+    /// Sets where the files of the following records came from, as read
+    /// from the sidecars of a scanned directory.
+    pub fn set_origin(&mut self, origin: Origin) {
+        self.origin = origin;
+    }
+
+    /// Sets the file the following records were read from, whatever path
+    /// they are reported under; `None` for inline input.
+    pub fn set_source(&mut self, source: Option<&Path>) {
+        self.source = source
+            .filter(|_| self.absolute_path)
+            .map(|path| relative::absolute(path).to_string_lossy().into_owned());
+    }
+
+    /// Sets how many files `--progress` counts towards.
+    pub fn set_total(&mut self, total: usize) {
+        if let Some(progress) = &mut self.progress {
+            progress.set_total(total);
+        }
+    }
+
+    /// Whether `--progress` was given.
+    pub fn shows_progress(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    /// Lists `path`, read as `data`, in the `--manifest`, for inputs not
+    /// read through `write_loaded`.
+    pub fn add_input(&mut self, path: &Path, data: &[u8]) {
+        if let Some(manifest) = &mut self.manifest {
+            manifest.add_input(path, Some(data), None);
+        }
+    }
+
+    /// Writes a record tagged with a watch event such as `created`. A
+    /// `None` result is a tombstone for a removed file: only the event and
+    /// path are written.
+    pub fn write_event(
+        &mut self,
+        path: &Path,
+        result: Option<Result<ParsedArtifact, Error>>,
+        raw: Option<&[u8]>,
+        event: Option<&str>,
+    ) {
+        let Some(result) = result else {
+            match self.format {
+                Format::Text => {
+                    let _ = writeln!(self.out, "[{}] {}", event.unwrap_or("removed"), path.display());
+                }
+                // A removed file has no fields to print.
+                Format::Minimal
+                | Format::Dot
+                | Format::OpenIoc
+                | Format::TaskXmlBundle
+                | Format::Validate
+                | Format::Timeline(_) => {}
+                Format::Csv => self.print_csv(&[&path.display().to_string()]),
+                _ => {
+                    let tombstone = Tombstone {
+                        path: path.display().to_string(),
+                        context: self.context(event),
+                    };
+                    match serde_json::to_string(&tombstone) {
+                        Ok(json) => self.emit_json(json),
+                        Err(e) => eprintln!("Unable to serialize {}: {}", path.display(), e),
+                    }
+                }
+            }
+            self.counted(false, 0);
+            return;
+        };
+        self.write_record(path, result, raw, false, &[], event);
+    }
+
+    /// Writes the record for `result`, flagged if it was read from a file
+    /// whose extension names the other format, with `warnings` about how
+    /// the file was read.
+    fn write_record(
+        &mut self,
+        path: &Path,
+        result: Result<ParsedArtifact, Error>,
+        raw: Option<&[u8]>,
+        extension_mismatch: bool,
+        warnings: &[Warning],
+        event: Option<&str>,
+    ) {
+        timings::time(Stage::Output, || {
+            self.write_record_untimed(path, result, raw, extension_mismatch, warnings, event)
+        });
+    }
+
+    fn write_record_untimed(
+        &mut self,
+        path: &Path,
+        result: Result<ParsedArtifact, Error>,
+        raw: Option<&[u8]>,
+        extension_mismatch: bool,
+        warnings: &[Warning],
+        event: Option<&str>,
+    ) {
+        let start = Instant::now();
+        if self.never_ran {
+            if let Ok(artifact) = &result {
+                if !artifact.never_ran_but_scheduled(&self.now) {
+                    return;
+                }
+            }
+        }
+        if let (Some(wanted), Ok(artifact)) = (self.enabled_filter, &result) {
+            if artifact.effective_enabled(&self.now).enabled != wanted {
+                return;
+            }
+        }
+        if let (Some(seen), Ok(artifact)) = (&mut self.seen, &result) {
+            if seen.contains(artifact) {
+                self.duplicates += 1;
+                return;
+            }
+            seen.insert(artifact.clone());
+        }
+        if let Err(e) = &result {
+            eprintln!("Unable to process file {}: {}", path.display(), e);
+            self.failed += 1;
+            *self.failures.entry(e.kind()).or_default() += 1;
+        }
+        if let (true, Ok(ParsedArtifact::Task(task))) = (self.verbose, &result) {
+            if !task.unknown_elements.is_empty() {
+                let names: Vec<&str> = task.unknown_elements.keys().map(String::as_str).collect();
+                eprintln!("{}: unrecognized elements: {}", path.display(), names.join(", "));
+            }
+        }
+        let mut record = Record::new(path, result);
+        record.set_origin(self.origin.clone());
+        if let Some(raw) = raw {
+            record.read_job_triggers(raw);
+            record.read_user_data(raw);
+            record.read_coverage(raw, self.min_coverage);
+            if let Some(coverage) = record.coverage() {
+                self.coverage[coverage_bucket(coverage)] += 1;
+            }
+        }
+        if let Record::Ok { trigger_count, action_count, artifact, .. } = &record {
+            let dropped = (self.no_triggers && *trigger_count != Some(0))
+                || (self.multi_action && *action_count < 2)
+                || self.find_uuid.as_ref().is_some_and(|uuid| !has_uuid(artifact, uuid));
+            if dropped {
+                return;
+            }
+        }
+        record.infer_history(&self.now);
+        if extension_mismatch {
+            record.flag_extension_mismatch();
+        }
+        record.add_warnings(warnings.iter().cloned());
+        if !record.warnings().is_empty() {
+            self.warned += 1;
+        }
+        for (code, tasks) in [
+            (WarningCode::DuplicateTrigger, &mut self.duplicate_triggers),
+            (WarningCode::ConflictingTrigger, &mut self.conflicting_triggers),
+        ] {
+            *tasks += usize::from(record.warnings().iter().any(|w| w.code == code));
+        }
+        if let Some(raw) = raw.filter(|_| self.embed_raw) {
+            record.embed_raw(raw);
+        }
+        if record.at_job_index().is_some() {
+            self.at_jobs += 1;
+        }
+        if self.detect {
+            record.detect_with(&self.detect_options);
+        }
+        if let Some(rules) = &self.path_rules {
+            record.detect_paths(rules);
+        }
+        #[cfg(feature = "hive")]
+        if let (Some(check), Record::Ok { artifact: ParsedArtifact::Task(_), .. }) =
+            (&mut self.task_cache, &record)
+        {
+            if let Some(finding) = check.check(path) {
+                record.add_finding(finding);
+            }
+        }
+        if let Some(check) = &mut self.schedlog {
+            check.check(&mut record);
+        }
+        // After --schedlog, which gives tasks their last start.
+        if self.detect {
+            record.detect_schedule(raw, &self.detect_options);
+        }
+        if let Some(security) = &self.security {
+            record.apply_file_security(security);
+        }
+        if self.classify {
+            record.classify(KnowledgeBase::builtin());
+            if let Some(class) = record.classification() {
+                *self.classified.entry(class).or_default() += 1;
+            }
+        }
+        // Last, so that rules can look at the findings of the others.
+        if let Some(rules) = &self.custom_rules {
+            rules.apply(&mut record);
+        }
+        if let Some(check) = &mut self.check {
+            check.check(&mut record, raw);
+        }
+        if let Some(counts) = &mut self.indicators {
+            for indicator in record.indicators() {
+                // Paths in any letter case count as one, like in a record,
+                // unless --case-sensitive.
+                let value = match indicator.kind {
+                    IndicatorKind::Url => indicator.value.clone(),
+                    _ => self.case.key(&indicator.value).into_owned(),
+                };
+                counts.entry((indicator.kind, value)).or_insert((indicator.clone(), 0)).1 += 1;
+            }
+        }
+        let failed = matches!(record, Record::Error { .. });
+        let findings = record.findings().len();
+        if findings > 0 {
+            self.flagged += 1;
+        } else if self.only_findings {
+            self.unflagged += 1;
+            return;
+        } else if record.classification() == Some(Classification::MicrosoftDefault) {
+            self.collapsed += 1;
+            return;
+        }
+        // Indicators of compromise only match whole values, and tasks to be
+        // imported need theirs.
+        let whole = matches!(self.format, Format::OpenIoc | Format::TaskXmlBundle);
+        let max = self.max_string_length.filter(|_| !whole);
+        if let (Some(max), Record::Ok { artifact, findings, decoded_comment_artifacts, .. }) =
+            (max, &mut record)
+        {
+            let findings = findings.as_deref_mut().unwrap_or_default();
+            let decoded = decoded_comment_artifacts;
+            self.truncated += truncate::truncate_record(artifact, findings, decoded, max);
+        }
+        match self.format {
+            #[cfg(feature = "tui")]
+            Format::Collect => self.records.push(record),
+            Format::Text => {
+                if let Some(event) = event {
+                    let _ = writeln!(self.out, "[{}] {}", event, path.display());
+                }
+                let findings = record.findings().to_vec();
+                let issues = record.issues().to_vec();
+                let warnings = record.warnings().len();
+                let classification = record.classification();
+                if let Record::Ok {
+                    artifact,
+                    interpretations,
+                    decoded_comment_artifacts,
+                    ..
+                } = record
+                {
+                    let (style, no_banner) = (self.date_style, self.no_banner);
+                    let state = artifact.effective_enabled(&self.now);
+                    let notes = Notes {
+                        state,
+                        warnings,
+                        classification,
+                        findings: &findings,
+                        issues: &issues,
+                        interpretations: &interpretations,
+                        decoded: &decoded_comment_artifacts,
+                    };
+                    let uuid = self.uuid_format;
+                    let mut text = Vec::new();
+                    print_artifact(&mut text, path, artifact, notes, style, uuid, no_banner);
+                    let text = self.terminal.render(&String::from_utf8_lossy(&text));
+                    let _ = write!(self.out, "{}", text);
+                }
+            }
+            Format::Csv => self.print_csv_record(path, &record, raw),
+            Format::Dot => self.graph.add(&record),
+            Format::OpenIoc => self.ioc.add(&record, self.uuid_format),
+            Format::TaskXmlBundle => self.tasks.add(&record),
+            Format::Validate => {
+                for warning in record.warnings() {
+                    let (code, message) = (warning.code.name(), &warning.message);
+                    let _ = writeln!(self.out, "{}: {}: {}", path.display(), code, message);
+                }
+            }
+            // Failures are only reported on stderr.
+            Format::Timeline(style) => {
+                let zone = self.timezone.as_ref().expect("settings require a zone");
+                let file = self.file_times.as_ref();
+                let _ = timeline::write(&mut self.out, style, &record, file, zone);
+            }
+            Format::Minimal => {
+                if let Record::Ok { artifact, .. } = &record {
+                    print_minimal(&mut self.out, path, artifact);
+                }
+            }
+            Format::Json | Format::JsonLines => {
+                // The file's reading and parsing, and this record so far.
+                let duration = self.loaded_in + start.elapsed();
+                let json = JsonRecord {
+                    record: &record,
+                    absolute_path: self.source.as_deref(),
+                    duration_ms: self.verbose.then(|| timings::millis_value(duration)),
+                    context: self.context(event),
+                };
+                match self.uuid_format.scope(|| serde_json::to_string(&json)) {
+                    Ok(json) => self.emit_json(json),
+                    Err(e) => {
+                        eprintln!("Unable to serialize {}: {}", path.display(), e);
+                        return;
+                    }
+                }
+            }
+        }
+        self.counted(failed, findings);
+    }
+
+    /// Writes a `scan --list-only` entry: the format a file would be parsed
+    /// as, or the error that kept a directory from being listed.
+    pub fn write_listing(&mut self, path: &Path, result: Result<ArtifactFormat, Error>) {
+        let format = match result {
+            Ok(format) => format,
+            Err(e) => return self.write(path, Err(e)),
+        };
+        match self.format {
+            Format::Text
+            | Format::Minimal
+            | Format::Dot
+            | Format::OpenIoc
+            | Format::TaskXmlBundle
+            | Format::Validate
+            | Format::Timeline(_) => {
+                let _ = writeln!(self.out, "{}\t{}", format.name(), path.display());
+            }
+            Format::Csv => self.print_csv(&[&path.display().to_string(), format.name()]),
+            _ => {
+                let listing = Listing {
+                    path: path.display().to_string(),
+                    format: format.name(),
+                    context: self.context(None),
+                };
+                match serde_json::to_string(&listing) {
+                    Ok(json) => self.emit_json(json),
+                    Err(e) => {
+                        eprintln!("Unable to serialize {}: {}", path.display(), e);
+                        return;
+                    }
+                }
+            }
+        }
+        self.counted(false, 0);
+    }
+
+    /// Prints the CSV row of `record`, and its triggers and actions into the
+    /// `--csv-triggers` and `--csv-actions` files. A job's triggers are read
+    /// from `raw`, its bytes.
+    fn print_csv_record(&mut self, path: &Path, record: &Record, raw: Option<&[u8]>) {
+        let path = path.display().to_string();
+        let findings: Vec<&str> = record.findings().iter().map(|f| f.rule.as_ref()).collect();
+        let warnings: Vec<&str> = record.warnings().iter().map(|w| w.code.name()).collect();
+        let issues: Vec<&str> = record.issues().iter().map(|i| i.rule.as_ref()).collect();
+        self.csv_rows += 1;
+        let index = self.csv_rows.to_string();
+        match record {
+            Record::Ok { artifact, trigger_count, action_count, has_exec, has_comhandler, .. } => {
+                let format = match artifact {
+                    ParsedArtifact::Job(_) => ArtifactFormat::Binary,
+                    ParsedArtifact::Task(_) => ArtifactFormat::Xml,
+                };
+                self.print_csv(&[
+                    &path,
+                    format.name(),
+                    artifact.user().unwrap_or(""),
+                    artifact.author().unwrap_or(""),
+                    &artifact.command_line().unwrap_or_default(),
+                    &artifact.last_run().unwrap_or_default(),
+                    &findings.join(" "),
+                    &warnings.join(" "),
+                    "",
+                    &issues.join(" "),
+                    &index,
+                    &trigger_count.map(|count| count.to_string()).unwrap_or_default(),
+                    &action_count.to_string(),
+                    &has_exec.to_string(),
+                    &has_comhandler.to_string(),
+                ]);
+                self.print_csv_triggers(&path, &index, artifact, raw);
+                self.print_csv_actions(&path, &index, artifact);
+            }
+            Record::Error { detail, .. } => {
+                let (findings, warnings) = (findings.join(" "), warnings.join(" "));
+                let row = [&path, "", "", "", "", "", &findings, &warnings, detail, "", &index];
+                self.print_csv(&row);
+            }
+        }
+    }
+
+    fn print_csv_triggers(
+        &mut self,
+        path: &str,
+        index: &str,
+        artifact: &ParsedArtifact,
+        raw: Option<&[u8]>,
+    ) {
+        let Some(file) = &mut self.csv_triggers else {
+            return;
+        };
+        let triggers = match artifact {
+            ParsedArtifact::Job(_) => raw.map(job::trigger_details).unwrap_or_default(),
+            ParsedArtifact::Task(task) => task.trigger_details(),
+        };
+        for (i, trigger) in triggers.iter().enumerate() {
+            let row = self.csv.row(&[
+                path,
+                index,
+                &(i + 1).to_string(),
+                &trigger.kind,
+                trigger.start.as_deref().unwrap_or(""),
+                trigger.end.as_deref().unwrap_or(""),
+                &trigger.enabled.to_string(),
+                &trigger.summary,
+                trigger.interval.as_deref().unwrap_or(""),
+                trigger.duration.as_deref().unwrap_or(""),
+            ]);
+            let _ = writeln!(file, "{}", row);
+        }
+    }
+
+    fn print_csv_actions(&mut self, path: &str, index: &str, artifact: &ParsedArtifact) {
+        let Some(file) = &mut self.csv_actions else {
+            return;
+        };
+        let actions = match artifact {
+            ParsedArtifact::Job(job) => vec![ActionTarget::Exec {
+                command: job.name.clone(),
+                arguments: Some(job.parameters.clone()).filter(|p| !p.is_empty()),
+            }],
+            ParsedArtifact::Task(task) => task.action_targets(),
+        };
+        for (i, action) in actions.iter().enumerate() {
+            let (kind, command, arguments, class_id) = match action {
+                ActionTarget::Exec { command, arguments } => {
+                    ("Exec", command.as_str(), arguments.as_deref().unwrap_or(""), "")
+                }
+                ActionTarget::ComHandler { class_id } => ("ComHandler", "", "", class_id.as_str()),
+                ActionTarget::Other(kind) => (kind.as_str(), "", "", ""),
+            };
+            let row = self.csv.row(&[
+                path,
+                index,
+                &(i + 1).to_string(),
+                kind,
+                command,
+                arguments,
+                class_id,
+            ]);
+            let _ = writeln!(file, "{}", row);
+        }
+    }
+
+    /// Prints a CSV row, padding `fields` with empty ones to the width of
+    /// the header.
+    fn print_csv(&mut self, fields: &[&str]) {
+        let mut row = [""; csv::COLUMNS.len()];
+        row[..fields.len()].copy_from_slice(fields);
+        let row = self.csv.row(&row);
+        let _ = writeln!(self.out, "{}", row);
+    }
+
+    /// The `event`, `root` and `group` keys of the next JSON object.
+    fn context<'a>(&'a self, event: Option<&'a str>) -> Context<'a> {
+        Context {
+            event,
+            root: self.roots.last().map(|root| root.name.as_str()),
+            group: self.groups.last().map(|group| group.name.as_str()),
+        }
+    }
+
+    /// Prints one serialized JSON object, as an element of the array of
+    /// `-o json` or a line of `-o jsonl`.
+    fn emit_json(&mut self, json: String) {
+        if self.format == Format::Json && self.count > 0 {
+            let _ = writeln!(self.out, ",");
+        }
+        if self.format == Format::Json {
+            let _ = write!(self.out, "{}", json);
+        } else {
+            let _ = writeln!(self.out, "{}", json);
+        }
+    }
+
+    /// Closes the JSON array, prints the graph of `-o dot` or the document
+    /// of `-o openioc`, prints the per-group and per-root tables after text
+    /// output (the latter on stderr for the other formats) and summarizes
+    /// failures and `at.exe` jobs on stderr.
+    pub fn finish(&mut self) {
+        // The JSON array only holds records; consumers of the lines get the
+        // counts the records were picked from.
+        if self.only_findings && self.format == Format::JsonLines {
+            let summary = Summary {
+                kind: "summary",
+                records: self.count + self.unflagged + self.collapsed,
+                with_findings: self.flagged,
+                failed: self.failed,
+            };
+            if let Ok(json) = serde_json::to_string(&summary) {
+                let _ = writeln!(self.out, "{}", json);
+            }
+        }
+        if self.format == Format::Json {
+            if self.count > 0 {
+                let _ = writeln!(self.out);
+            }
+            let _ = writeln!(self.out, "]");
+        }
+        if self.format == Format::Dot {
+            let _ = write!(self.out, "{}", self.graph.render());
+        }
+        if self.format == Format::OpenIoc {
+            let _ = write!(self.out, "{}", self.ioc.render(&self.now));
+        }
+        if self.format == Format::TaskXmlBundle {
+            match &self.split_out_dir {
+                Some(dir) => self.unwritten = self.tasks.write_split(dir, &mut self.out),
+                None => {
+                    let _ = write!(self.out, "{}", self.tasks.render());
+                }
+            }
+        }
+        if self.format == Format::Text && !self.groups.is_empty() {
+            print_groups(&mut self.out, "Group", &self.groups, self.no_header, self.terminal);
+        }
+        if !self.roots.is_empty() {
+            let mut roots = mem::take(&mut self.roots);
+            roots.push(Group {
+                name: "Total".to_string(),
+                ..roots.iter().fold(Group::default(), Group::add)
+            });
+            if self.format == Format::Text {
+                print_groups(&mut self.out, "Root", &roots, self.no_header, self.terminal);
+            } else {
+                let plain = Capabilities {
+                    color: false,
+                    width: None,
+                    unicode: false,
+                };
+                print_groups(&mut io::stderr(), "Root", &roots, self.no_header, plain);
+            }
+        }
+        // Only where it cannot break what the format promises to hold.
+        if let Some(check) = &self.check {
+            match self.format {
+                Format::JsonLines => {
+                    if let Some(json) = check.summary_json() {
+                        let _ = writeln!(self.out, "{}", json);
+                    }
+                }
+                Format::Text => {
+                    let _ = write!(self.out, "{}", self.terminal.render(&check.summary_text()));
+                }
+                _ => eprint!("{}", check.summary_text()),
+            }
+        }
+        self.out.finish();
+        for file in [&mut self.csv_triggers, &mut self.csv_actions].into_iter().flatten() {
+            let _ = file.flush();
+        }
+        if let Some(progress) = &mut self.progress {
+            progress.finish();
+        }
+        // Written last, and after Ctrl-C too, so that it covers the whole
+        // output.
+        if let Some(manifest) = self.manifest.take() {
+            manifest.finish(self.out.path(), self.interrupted);
+        }
+        if self.at_jobs > 0 {
+            eprintln!("{} of {} files are At jobs created by at.exe", self.at_jobs, self.count);
+        }
+        timings::report();
+        self.slow.report();
+        cache::report();
+        #[cfg(feature = "hive")]
+        if let Some(check) = &self.task_cache {
+            check.report();
+        }
+        if let Some(check) = &self.schedlog {
+            check.report();
+        }
+        if self.duplicates > 0 {
+            eprintln!("{} duplicate records skipped", self.duplicates);
+        }
+        if let (Some(max), 1..) = (self.max_string_length, self.truncated) {
+            eprintln!("{} strings longer than {} characters truncated", self.truncated, max);
+        }
+        if let Some(counts) = &self.indicators {
+            print_indicator_stats(counts);
+        }
+        if self.stats {
+            for (count, what) in [
+                (self.duplicate_triggers, "duplicate"),
+                (self.conflicting_triggers, "conflicting"),
+            ] {
+                if count > 0 {
+                    eprintln!("{} of {} records have {} triggers", count, self.count, what);
+                }
+            }
+            let jobs: usize = self.coverage.iter().sum();
+            if jobs > 0 {
+                let buckets: Vec<String> = COVERAGE_BUCKETS
+                    .iter()
+                    .zip(self.coverage)
+                    .map(|((_, name), count)| format!("{}: {}", name, count))
+                    .collect();
+                eprintln!("Coverage of {} jobs: {}", jobs, buckets.join(", "));
+            }
+            let modified = self.classified.get(&Classification::ModifiedDefault).copied();
+            if let Some(modified) = modified {
+                eprintln!("{} of {} records are modified Microsoft defaults", modified, self.count);
+            }
+        }
+        if self.classify {
+            let count = |class| self.classified.get(&class).copied().unwrap_or(0);
+            eprintln!(
+                "Classified {} records: {} Microsoft defaults ({} not shown), {} modified, {} \
+                 non-default",
+                self.classified.values().sum::<usize>(),
+                count(Classification::MicrosoftDefault),
+                self.collapsed,
+                count(Classification::ModifiedDefault),
+                count(Classification::NonDefault)
+            );
+        }
+        if self.only_findings {
+            let records = self.count + self.unflagged + self.collapsed;
+            eprintln!("Scanned {} records, {} with findings", records, self.flagged);
+        }
+        if self.interrupted {
+            eprintln!("Interrupted after {} records; the output is incomplete", self.count);
+        }
+        if self.failed > 0 {
+            let kinds: Vec<String> = self
+                .failures
+                .iter()
+                .map(|(kind, count)| format!("{} {}", count, kind))
+                .collect();
+            eprintln!(
+                "{} of {} files failed: {}",
+                self.failed,
+                self.count,
+                kinds.join(", ")
+            );
+        }
+    }
+
+    /// Records that the run was cut short by Ctrl-C.
+    pub fn interrupt(&mut self) {
+        self.interrupted = true;
+    }
+
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// The records kept by `Format::Collect`.
+    #[cfg(feature = "tui")]
+    pub fn into_records(self) -> Vec<Record> {
+        self.records
+    }
+
+    /// The exit code for the run; `fail_on_warnings` treats a record with
+    /// warnings like a failed one.
+    pub fn exit_code(&self, fail_on_findings: bool, fail_on_warnings: bool) -> u8 {
+        #[cfg_attr(not(feature = "hive"), allow(unused_mut))]
+        let mut flagged = self.flagged;
+        // A registered task with no file is as suspicious as a flagged one.
+        #[cfg(feature = "hive")]
+        if let Some(check) = &self.task_cache {
+            flagged += check.unmatched().len();
+        }
+        let warned = fail_on_warnings && self.warned > 0;
+        let failed = self.failed + self.unwritten;
+        if failed > 0 || (fail_on_findings && flagged > 0) || warned {
+            EXIT_PARSE_FAILURE
+        } else if flagged > 0 {
+            EXIT_FINDINGS
+        } else {
+            EXIT_SUCCESS
+        }
+    }
+}
+
+/// The owner and DACL of `path`, for `--acl`; `None`, with a message, if
+/// they cannot be read.
+#[cfg(windows)]
+fn read_security(path: &Path) -> Option<FileSecurity> {
+    FileSecurity::read(path)
+        .map_err(|e| eprintln!("Unable to read the owner and ACL of {}: {}", path.display(), e))
+        .ok()
+}
+
+// `settings` refuses --acl elsewhere.
+#[cfg(not(windows))]
+fn read_security(_path: &Path) -> Option<FileSecurity> {
+    None
+}
+
+/// The coverage ranges `--stats` counts jobs in, by lowest percentage.
+const COVERAGE_BUCKETS: [(f64, &str); 5] = [
+    (100.0, "100%"),
+    (90.0, "90-99%"),
+    (75.0, "75-89%"),
+    (50.0, "50-74%"),
+    (0.0, "below 50%"),
+];
+
+/// The index in `COVERAGE_BUCKETS` of the range `coverage` falls in.
+fn coverage_bucket(coverage: f64) -> usize {
+    let last = COVERAGE_BUCKETS.len() - 1;
+    COVERAGE_BUCKETS.iter().position(|(min, _)| coverage >= *min).unwrap_or(last)
+}
+
+/// Per-group tallies for `scan --group-by-dir`.
+#[derive(Default)]
+struct Group {
+    name: String,
+    records: usize,
+    failed: usize,
+    /// Records with at least one finding.
+    flagged: usize,
+    findings: usize,
+}
+
+impl Group {
+    /// The tallies of `self` and `other` added up, under no name.
+    fn add(self, other: &Group) -> Group {
+        Group {
+            name: String::new(),
+            records: self.records + other.records,
+            failed: self.failed + other.failed,
+            flagged: self.flagged + other.flagged,
+            findings: self.findings + other.findings,
+        }
+    }
+}
+
+/// Prints a table of `groups`, whose first column is headed `title`.
+fn print_groups(
+    out: &mut impl Write,
+    title: &str,
+    groups: &[Group],
+    no_header: bool,
+    terminal: Capabilities,
+) {
+    let width = groups.iter().map(|g| g.name.len()).chain([title.len()]).max().unwrap_or(5);
+    let _ = writeln!(out);
+    if !no_header {
+        let header = format!("{:<width$}  Records  Failed  Flagged  Findings", title);
+        let _ = writeln!(out, "{}", terminal.paint(&header, Paint::Bold));
+    }
+    for g in groups {
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>7}  {:>6}  {:>7}  {:>8}",
+            g.name, g.records, g.failed, g.flagged, g.findings
+        );
+    }
+}
+
+/// How many indicators `--stats` lists.
+const TOP_INDICATORS: usize = 10;
+
+/// Prints the indicators named by the most records on stderr, most common
+/// first.
+fn print_indicator_stats(counts: &HashMap<(IndicatorKind, String), (Indicator, usize)>) {
+    if counts.is_empty() {
+        return;
+    }
+    let mut top: Vec<(&Indicator, usize)> = counts.values().map(|(i, n)| (i, *n)).collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    eprintln!("Most common indicators:");
+    for (indicator, count) in top.into_iter().take(TOP_INDICATORS) {
+        eprintln!("{:>7}  {:<6}  {}", count, indicator.kind.name(), indicator.value);
+    }
+}
+
+/// Prints the `-o minimal` line for `artifact`. Tabs and line breaks in the
+/// fields become spaces so that every record stays on one line.
+fn print_minimal(out: &mut impl Write, path: &Path, artifact: &ParsedArtifact) {
+    let field = |value: &str| value.replace(['\t', '\r', '\n'], " ");
+    let _ = writeln!(
+        out,
+        "{}\t{}\t{}\t{}",
+        field(&path.display().to_string()),
+        field(artifact.user().unwrap_or("")),
+        field(&artifact.command_line().unwrap_or_default()),
+        artifact.last_run().unwrap_or_default()
+    );
+}
+
+/// What the text output says about an artifact besides its fields.
+struct Notes<'a> {
+    state: Enablement,
+    /// How many warnings the record has.
+    warnings: usize,
+    /// For `--classify`.
+    classification: Option<Classification>,
+    findings: &'a [Finding],
+    /// For `--check`.
+    issues: &'a [Finding],
+    /// What notable combinations of a job's flags mean.
+    interpretations: &'a [String],
+    /// Payloads decoded from the comment and user data.
+    decoded: &'a [DecodedArtifact],
+}
+
+/// Whether `artifact` is a job with `uuid`.
+fn has_uuid(artifact: &ParsedArtifact, uuid: &UUID) -> bool {
+    matches!(artifact, ParsedArtifact::Job(job) if job.uuid == *uuid)
+}
+
+/// Prints `artifact` as text, with its effective state and the number of
+/// warnings, if any, at the top.
+fn print_artifact(
+    out: &mut impl Write,
+    path: &Path,
+    artifact: ParsedArtifact,
+    notes: Notes,
+    style: DateStyle,
+    uuid_format: UuidFormat,
+    no_banner: bool,
+) {
+    let mut state = match notes.state {
+        Enablement { enabled: true, reason: enabled::ENABLED } => "Effective State: Enabled".to_string(),
+        Enablement { enabled, reason } => {
+            let word = if enabled { "Enabled" } else { "Disabled" };
+            format!("Effective State: {} ({})", word, reason)
+        }
+    };
+    if notes.warnings > 0 {
+        state.push_str(&format!("\nWarnings: {}", notes.warnings));
+    }
+    if let Some(classification) = notes.classification {
+        state.push_str(&format!("\nClassification: {}", classification.describe()));
+    }
+    match artifact {
+        ParsedArtifact::Task(task) => {
+            let _ = writeln!(out, "{}", state);
+            let _ = write!(out, "{}", task.format_task_with(style));
+        }
+        ParsedArtifact::Job(job) if no_banner => {
+            let _ = writeln!(out, "# {}", path.display());
+            let _ = writeln!(out, "{}", state);
+            let _ = write!(out, "{}", job.format_job_as(style, uuid_format));
+            let _ = writeln!(out, "{}", interpretation(notes.interpretations));
+        }
+        ParsedArtifact::Job(job) => {
+            let _ = writeln!(out, "************************************************************************");
+            let _ = writeln!(out, "File: {}", path.display());
+            let _ = writeln!(out, "{}", state);
+            let _ = write!(out, "{}", job.format_job_as(style, uuid_format));
+            let _ = writeln!(out, "{}", interpretation(notes.interpretations));
+            let _ = writeln!(out, "************************************************************************");
+        }
+    }
+    for finding in notes.findings {
+        let _ = writeln!(out, "{}", finding_line(finding));
+    }
+    for issue in notes.issues {
+        let _ = writeln!(out, "Issue [{}]: {}", issue.rule, issue.description);
+    }
+    for payload in notes.decoded {
+        let chain: Vec<&str> = payload.encoding.iter().map(|encoding| encoding.name()).collect();
+        let _ = writeln!(
+            out,
+            "Decoded [{}] from {} at {}: {:?}",
+            chain.join(", "),
+            payload.source,
+            payload.offset,
+            payload.decoded
+        );
+    }
+}
+
+/// The interpretation section of a job in the text format, a line per
+/// sentence; empty if there is nothing to interpret.
+fn interpretation(interpretations: &[String]) -> String {
+    let mut section = String::new();
+    if !interpretations.is_empty() {
+        section.push_str("Interpretation:\n");
+        for sentence in interpretations {
+            section.push_str(&format!("  - {}\n", sentence));
+        }
+    }
+    section
+}
+
+/// `finding` as the text format and the browser print it, with the
+/// technique and score of a `--rules-dir` rule at the end.
+pub fn finding_line(finding: &Finding) -> String {
+    let mut line = format!("Finding [{}]: {}", finding.rule, finding.description);
+    let tags: Vec<String> = finding
+        .technique
+        .iter()
+        .cloned()
+        .chain(finding.score.map(|score| format!("score {}", score)))
+        .collect();
+    if !tags.is_empty() {
+        line.push_str(&format!(" ({})", tags.join(", ")));
+    }
+    line
+}
@@ -0,0 +1,75 @@
+//! `scan --threads`: files are read and parsed on a pool of worker threads
+//! while the calling thread walks the directory and writes the records.
+//! The workers hand each file over a channel and never write themselves,
+//! so `Output` stays the only writer and every record comes out whole,
+//! whatever the format. Records come in the order their files finish.
+
+use std::io;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+
+use jobfileparser::{list_dir, Error, ScanOptions};
+use rayon::ThreadPoolBuilder;
+
+use super::commands::{load, Loaded};
+
+/// How many parsed files each worker may have waiting for the writer
+/// before it blocks, which bounds the memory held when writing is slow.
+const QUEUED_PER_THREAD: usize = 4;
+
+/// Loads the files below `dir` on `threads` workers and passes each to
+/// `write` on the calling thread, until `write` breaks. Files already being
+/// parsed then are dropped unwritten.
+pub fn load_dir<F>(
+    dir: &Path,
+    options: &ScanOptions,
+    threads: usize,
+    mut write: F,
+) -> Result<(), Error>
+where
+    F: FnMut(&Path, Result<Loaded, Error>) -> ControlFlow<()>,
+{
+    let pool = ThreadPoolBuilder::new().num_threads(threads).build().map_err(io::Error::other)?;
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::sync_channel::<(PathBuf, Result<Loaded, Error>)>(
+        threads.saturating_mul(QUEUED_PER_THREAD),
+    );
+    let mut write_one = |(path, loaded): (PathBuf, Result<Loaded, Error>)| {
+        if !stop.load(Ordering::Relaxed) && write(&path, loaded).is_break() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    };
+    // The calling thread is not one of the workers, so it keeps draining
+    // the channel while they block on a full one.
+    pool.in_place_scope(|scope| {
+        let listed = list_dir(dir, options, |path, result| {
+            let sender = sender.clone();
+            let path = path.to_path_buf();
+            let stop = &stop;
+            scope.spawn(move |_| {
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                let loaded = result.and_then(|()| load(&path, options));
+                let _ = sender.send((path, loaded));
+            });
+            while let Ok(done) = receiver.try_recv() {
+                write_one(done);
+            }
+            if stop.load(Ordering::Relaxed) {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        // Once every worker has sent its file, or given up, the channel
+        // has no senders left and the loop ends.
+        drop(sender);
+        for done in receiver.iter() {
+            write_one(done);
+        }
+        listed
+    })
+}
@@ -0,0 +1,72 @@
+//! `--progress`: how many files are done, out of how many, and how many
+//! failed, on stderr while records go to stdout. On a terminal it is a
+//! single line rewritten in place; otherwise a line is printed every few
+//! seconds, so that logs stay readable.
+
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// How often the line is rewritten on a terminal.
+const TERMINAL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often a line is printed when stderr is not a terminal.
+const PLAIN_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct Progress {
+    /// The number of files to read, once known.
+    total: Option<usize>,
+    done: usize,
+    failed: usize,
+    terminal: bool,
+    /// When the line was last printed.
+    shown: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new() -> Progress {
+        Progress {
+            total: None,
+            done: 0,
+            failed: 0,
+            terminal: io::stderr().is_terminal(),
+            shown: None,
+        }
+    }
+
+    pub fn set_total(&mut self, total: usize) {
+        self.total = Some(total);
+    }
+
+    /// Counts a file read, and prints the line if it is due.
+    pub fn file_done(&mut self, failed: bool) {
+        self.done += 1;
+        self.failed += usize::from(failed);
+        let interval = if self.terminal { TERMINAL_INTERVAL } else { PLAIN_INTERVAL };
+        if self.shown.is_none_or(|shown| shown.elapsed() >= interval) {
+            self.show();
+        }
+    }
+
+    /// Prints the final count, ending the line on a terminal.
+    pub fn finish(&mut self) {
+        self.show();
+        if self.terminal {
+            eprintln!();
+        }
+    }
+
+    fn show(&mut self) {
+        let line = match self.total {
+            Some(total) => format!("{}/{} files, {} failed", self.done, total, self.failed),
+            None => format!("{} files, {} failed", self.done, self.failed),
+        };
+        let mut stderr = io::stderr().lock();
+        if self.terminal {
+            // Back to the start of the line, then clear what is left of it.
+            let _ = write!(stderr, "\r\x1b[KProgress: {}", line);
+            let _ = stderr.flush();
+        } else {
+            let _ = writeln!(stderr, "Progress: {}", line);
+        }
+        self.shown = Some(Instant::now());
+    }
+}
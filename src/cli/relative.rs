@@ -0,0 +1,59 @@
+//! `--relative-paths`: records name their files relative to the scan root,
+//! or to the deepest directory all the files given to `parse` share, so
+//! that results archived beside the evidence do not carry the layout of the
+//! machine they were made on.
+//!
+//! Paths are compared component by component after being made absolute, so
+//! a root written with or without a trailing separator, or with `.` in it,
+//! is the same root. On Windows the comparison ignores letter case, as the
+//! file system does. Relative paths are written with `/` between their
+//! components on every system.
+
+use std::ffi::OsStr;
+use std::path::{self, Component, Path, PathBuf};
+
+/// `path` made absolute against the working directory, without resolving
+/// links; as it is if that fails.
+pub fn absolute(path: &Path) -> PathBuf {
+    path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// `path` relative to `root`, its components joined with `/`; `None` if it
+/// is not below `root`.
+pub fn relative_to(root: &Path, path: &Path) -> Option<String> {
+    let (root, path) = (absolute(root), absolute(path));
+    let mut components = path.components();
+    for expected in root.components() {
+        if !same(expected, components.next()?) {
+            return None;
+        }
+    }
+    let parts: Vec<String> =
+        components.map(|part| part.as_os_str().to_string_lossy().into_owned()).collect();
+    Some(parts.join("/"))
+}
+
+/// The deepest directory holding all of `files`; `None` if they have none
+/// in common, as for files on different drives.
+pub fn common_ancestor(files: &[PathBuf]) -> Option<PathBuf> {
+    let mut dirs = files.iter().map(|file| {
+        let file = absolute(file);
+        file.parent().map(Path::to_path_buf).unwrap_or(file)
+    });
+    let first = dirs.next()?;
+    let mut shared: Vec<Component> = first.components().collect();
+    for dir in dirs {
+        let count = shared.iter().zip(dir.components()).take_while(|(a, b)| same(**a, *b)).count();
+        shared.truncate(count);
+    }
+    (!shared.is_empty()).then(|| shared.iter().collect())
+}
+
+fn same(a: Component, b: Component) -> bool {
+    let (a, b): (&OsStr, &OsStr) = (a.as_os_str(), b.as_os_str());
+    if cfg!(windows) {
+        a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+    } else {
+        a == b
+    }
+}
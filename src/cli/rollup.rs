@@ -0,0 +1,122 @@
+//! `scan --by-user`: parsed artifacts grouped by the account they belong to.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use jobfileparser::{Case, ParsedArtifact};
+use serde::Serialize;
+
+use super::output::Format;
+
+/// The group for artifacts that name no account.
+const NO_USER: &str = "(none)";
+
+#[derive(Default, Serialize)]
+struct UserSummary {
+    user: String,
+    count: usize,
+    commands: BTreeSet<String>,
+    earliest_run: Option<String>,
+    latest_run: Option<String>,
+}
+
+pub struct Rollup {
+    strip_domain: bool,
+    /// How accounts are told apart; a group is named as its first artifact
+    /// writes the account.
+    case: Case,
+    users: BTreeMap<String, UserSummary>,
+}
+
+impl Rollup {
+    pub fn new(strip_domain: bool, case: Case) -> Rollup {
+        Rollup {
+            strip_domain,
+            case,
+            users: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, artifact: &ParsedArtifact) {
+        let user = match artifact.user() {
+            Some(user) => strip(user, self.strip_domain),
+            None => NO_USER,
+        };
+        let summary = self
+            .users
+            .entry(self.case.key(user).into_owned())
+            .or_insert_with(|| UserSummary {
+                user: user.to_string(),
+                ..UserSummary::default()
+            });
+        summary.count += 1;
+        if let Some(command) = artifact.command() {
+            summary.commands.insert(command.normalized());
+        }
+        if let Some(run) = artifact.last_run() {
+            // ISO 8601 timestamps order correctly as strings.
+            if summary
+                .earliest_run
+                .as_ref()
+                .is_none_or(|earliest| run < *earliest)
+            {
+                summary.earliest_run = Some(run.clone());
+            }
+            if summary
+                .latest_run
+                .as_ref()
+                .is_none_or(|latest| run > *latest)
+            {
+                summary.latest_run = Some(run);
+            }
+        }
+    }
+
+    pub fn print(&self, format: Format) {
+        match format {
+            Format::Json => {
+                match serde_json::to_string_pretty(&self.users.values().collect::<Vec<_>>()) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => eprintln!("Unable to serialize the report: {}", e),
+                }
+            }
+            Format::JsonLines => {
+                for summary in self.users.values() {
+                    match serde_json::to_string(summary) {
+                        Ok(json) => println!("{}", json),
+                        Err(e) => eprintln!("Unable to serialize the report: {}", e),
+                    }
+                }
+            }
+            _ => {
+                for summary in self.users.values() {
+                    println!("User: {}", summary.user);
+                    println!("  Tasks: {}", summary.count);
+                    if let Some(earliest) = &summary.earliest_run {
+                        println!("  Earliest run: {}", earliest);
+                    }
+                    if let Some(latest) = &summary.latest_run {
+                        println!("  Latest run: {}", latest);
+                    }
+                    println!("  Commands:");
+                    for command in &summary.commands {
+                        println!("    {}", command);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `user`, without a `DOMAIN\` prefix or `@domain` suffix if asked.
+fn strip(user: &str, strip_domain: bool) -> &str {
+    let mut user = user;
+    if strip_domain {
+        if let Some((_, name)) = user.rsplit_once('\\') {
+            user = name;
+        }
+        if let Some((name, _)) = user.split_once('@') {
+            user = name;
+        }
+    }
+    user
+}
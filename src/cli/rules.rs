@@ -0,0 +1,56 @@
+//! The `--rules` file: TOML that tunes the heuristics. Its
+//! `[writable_paths]` table extends the directory lists of `--detect-paths`:
+//!
+//! ```toml
+//! [writable_paths]
+//! flag = ["D:\\Shares\\Drop"]
+//! allow = ["%ProgramData%\\Contoso\\Agent"]
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use jobfileparser::PathRules;
+
+/// Reads a rules file. Unknown tables and keys are returned as warnings; an
+/// unreadable or malformed file, or a list of the wrong type, is an error.
+pub fn from_file(path: &Path) -> Result<(PathRules, Vec<String>), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let table: toml::Table = text
+        .parse()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let mut rules = PathRules::default();
+    let mut warnings = Vec::new();
+    for (name, value) in &table {
+        let Some(section) = value.as_table().filter(|_| name == "writable_paths") else {
+            warnings.push(format!("{}: unknown rules table `{}`", path.display(), name));
+            continue;
+        };
+        for (key, value) in section {
+            let list = match key.as_str() {
+                "flag" => &mut rules.flag,
+                "allow" => &mut rules.allow,
+                _ => {
+                    warnings.push(format!("{}: unknown key `{}.{}`", path.display(), name, key));
+                    continue;
+                }
+            };
+            *list = strings(value).ok_or(format!(
+                "{}: `{}.{}` must be a list of directories",
+                path.display(),
+                name,
+                key
+            ))?;
+        }
+    }
+    Ok((rules, warnings))
+}
+
+fn strings(value: &toml::Value) -> Option<Vec<String>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|item| item.as_str().map(str::to_string))
+        .collect()
+}
@@ -0,0 +1,77 @@
+//! `--schedlog`: sets the execution history from a scheduler log beside
+//! each record, and lists on stderr, when the run ends, the artifacts the
+//! log never mentions and the logged tasks no artifact defines. Either can
+//! mean a deleted task or a tampered log.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use jobfileparser::{Case, ExecutionLog, Record};
+
+#[derive(Clone)]
+pub struct SchedLogCheck {
+    log: ExecutionLog,
+    /// The `ExecutionLog::key`s of the tasks an artifact matched.
+    matched: BTreeSet<String>,
+    /// The paths of the artifacts with no log entries.
+    unlogged: Vec<String>,
+}
+
+impl SchedLogCheck {
+    /// Reads `SchedLgU.txt` or an operational log CSV export, whose task
+    /// names are matched as `case` says.
+    pub fn load(path: &Path, case: Case) -> Result<SchedLogCheck, String> {
+        let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let log = ExecutionLog::parse_with(&data, case);
+        if log.is_empty() {
+            eprintln!("Warning: {}: no task entries found", path.display());
+        }
+        Ok(SchedLogCheck {
+            log,
+            matched: BTreeSet::new(),
+            unlogged: Vec::new(),
+        })
+    }
+
+    /// Attaches the history of the task `record` defines, or notes that the
+    /// log has none. Error records are skipped.
+    pub fn check(&mut self, record: &mut Record) {
+        if matches!(record, Record::Error { .. }) {
+            return;
+        }
+        match self.log.history(record.path()) {
+            Some(history) => {
+                self.matched.insert(self.log.key(record.path()));
+                record.set_execution_history(history.clone());
+            }
+            None => self.unlogged.push(record.path().to_string()),
+        }
+    }
+
+    /// Prints the mismatches in both directions.
+    pub fn report(&self) {
+        if !self.unlogged.is_empty() {
+            eprintln!("{} artifacts have no scheduler log entries:", self.unlogged.len());
+            for path in &self.unlogged {
+                eprintln!("  {}", path);
+            }
+        }
+        let orphans: Vec<_> = self
+            .log
+            .tasks()
+            .filter(|(name, _)| !self.matched.contains(&self.log.key(name)))
+            .collect();
+        if !orphans.is_empty() {
+            eprintln!("{} logged tasks have no artifact:", orphans.len());
+            for (name, history) in orphans {
+                eprintln!(
+                    "  {}: {} runs, last seen {}",
+                    name,
+                    history.runs,
+                    history.last_seen.as_deref().unwrap_or("unknown")
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! `selftest`: writes a few synthetic artifacts to a temporary directory,
+//! runs this executable's `parse` over them in every output format and
+//! checks the results, so that a build can be verified without samples.
+//! Every name in `Format::NAMES` is run, and each format must have a check.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use jobfileparser::consts::trigger::{self, TriggerType};
+use jobfileparser::consts::{fixed, Field};
+use jobfileparser::{JobBuilder, JobDate};
+use serde_json::Value;
+
+use super::output::Format;
+use super::timeline::Style;
+use super::{EXIT_FATAL, EXIT_PARSE_FAILURE, EXIT_SUCCESS};
+
+/// A task with a modeled calendar trigger and two trigger types the model
+/// keeps as unknown elements.
+const TASK_XML: &str = r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Author>CORP\admin</Author>
+  </RegistrationInfo>
+  <Triggers>
+    <CalendarTrigger>
+      <StartBoundary>2024-03-01T09:00:00</StartBoundary>
+      <Enabled>true</Enabled>
+    </CalendarTrigger>
+    <LogonTrigger>
+      <Enabled>true</Enabled>
+    </LogonTrigger>
+    <BootTrigger />
+  </Triggers>
+  <Settings>
+    <Enabled>true</Enabled>
+  </Settings>
+  <Principals>
+    <Principal id="Author">
+      <UserId>S-1-5-18</UserId>
+    </Principal>
+  </Principals>
+  <Actions Context="Author">
+    <Exec>
+      <Command>C:\Tools\agent.exe</Command>
+      <Arguments>--check</Arguments>
+    </Exec>
+  </Actions>
+</Task>
+"#;
+
+/// The file names of the artifacts, in the order they are parsed.
+const FILES: [&str; 3] = ["ran.job", "never.job", "task.xml"];
+
+/// The configuration variables that would change what `parse` prints.
+const CONFIG_VARIABLES: [&str; 6] = [
+    "JOBPARSER_OUTPUT_FORMAT",
+    "JOBPARSER_DETECT",
+    "JOBPARSER_FAIL_ON_FINDINGS",
+    "JOBPARSER_EXIT_ZERO_ALWAYS",
+    "JOBPARSER_TIMEZONE",
+    "JOBPARSER_FIELDS",
+];
+
+/// The last run of `ran.job`, 2024-03-04T02:13:00, as seconds since the
+/// Unix epoch when read as UTC.
+const RAN_EPOCH: &str = "1709518380";
+
+type Check = Result<(), String>;
+
+/// Verifies what `parse` printed in one format.
+type Verify = fn(&str) -> Check;
+
+pub fn selftest() -> u8 {
+    let dir = env::temp_dir().join(format!("jobfileparser-selftest-{}", process::id()));
+    let written = write_artifacts(&dir);
+    report("write sample artifacts", &written);
+    if written.is_err() {
+        let _ = fs::remove_dir_all(&dir);
+        return EXIT_FATAL;
+    }
+
+    let mut failed = 0;
+    for name in Format::NAMES {
+        let result = match Format::from_name(name) {
+            Some(format) => {
+                run_parse(&dir, name, format).and_then(|stdout| verifier(format)(&stdout))
+            }
+            None => Err("a format of that name".to_string()),
+        };
+        report(&format!("parse -o {}", name), &result);
+        failed += usize::from(result.is_err());
+    }
+    let _ = fs::remove_dir_all(&dir);
+
+    // Writing the artifacts was the first check.
+    let total = Format::NAMES.len() + 1;
+    if failed > 0 {
+        println!("{} of {} checks failed", failed, total);
+        EXIT_PARSE_FAILURE
+    } else {
+        println!("All {} checks passed", total);
+        EXIT_SUCCESS
+    }
+}
+
+fn report(name: &str, result: &Check) {
+    match result {
+        Ok(()) => println!("PASS {}", name),
+        Err(reason) => println!("FAIL {}: {}", name, reason),
+    }
+}
+
+fn write_artifacts(dir: &Path) -> Check {
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let ran = JobBuilder::new("C:\\Windows\\System32\\backup.exe")
+        .parameters("/full")
+        .author("CORP\\operator")
+        .status(0x41300)
+        .run_date(JobDate {
+            year: 2024,
+            month: 3,
+            weekday: Some(1),
+            day: 4,
+            hour: 2,
+            minute: 13,
+            second: 0,
+        })
+        .build()
+        .map_err(|e| e.to_string())?;
+    let ran = with_trigger(
+        ran.to_bytes(),
+        &[
+            (trigger::TRIGGER_TYPE, TriggerType::Daily as u32),
+            (trigger::TRIGGER_SPECIFIC0, 1),
+            (trigger::START_HOUR, 2),
+            (trigger::START_MINUTE, 13),
+        ],
+    );
+    let never = JobBuilder::new("C:\\Tools\\sync.exe").build().map_err(|e| e.to_string())?;
+    let never = with_trigger(
+        never.to_bytes(),
+        &[(trigger::TRIGGER_TYPE, TriggerType::Once as u32), (trigger::START_HOUR, 9)],
+    );
+    let mut task = vec![0xFF, 0xFE];
+    for unit in TASK_XML.encode_utf16() {
+        task.extend_from_slice(&unit.to_le_bytes());
+    }
+    for (name, data) in FILES.iter().zip([ran, never, task]) {
+        let path = dir.join(name);
+        fs::write(&path, data).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// `job`, as `Job::to_bytes` writes it, with one trigger starting on
+/// 2024-03-01 in place of its empty trigger section, with `fields` set.
+fn with_trigger(mut job: Vec<u8>, fields: &[(Field, u32)]) -> Vec<u8> {
+    let offset = &job[fixed::TRIGGER_OFFSET.offset..][..2];
+    let at = usize::from(u16::from_le_bytes([offset[0], offset[1]]));
+    job[at..at + 2].copy_from_slice(&1u16.to_le_bytes());
+    let mut record = vec![0; trigger::LENGTH];
+    let start = [
+        (trigger::TRIGGER_SIZE, trigger::LENGTH as u32),
+        (trigger::BEGIN_YEAR, 2024),
+        (trigger::BEGIN_MONTH, 3),
+        (trigger::BEGIN_DAY, 1),
+    ];
+    for (field, value) in start.iter().chain(fields) {
+        record[field.offset..][..field.size].copy_from_slice(&value.to_le_bytes()[..field.size]);
+    }
+    job.extend(record);
+    job
+}
+
+/// The check of what `parse` prints in `format`. There is no catch-all arm,
+/// so a new format does not build until it has a check here.
+fn verifier(format: Format) -> Verify {
+    match format {
+        Format::Text => check_text,
+        Format::Json => check_json,
+        Format::JsonLines => check_jsonl,
+        Format::Minimal => check_minimal,
+        Format::Csv => check_csv,
+        Format::Dot => check_dot,
+        Format::OpenIoc => check_openioc,
+        Format::TaskXmlBundle => check_taskxml_bundle,
+        Format::Timeline(Style::Bodyfile) => check_bodyfile,
+        Format::Timeline(Style::Tln) => check_tln,
+        Format::Timeline(Style::Plaso) => check_plaso,
+        #[cfg(feature = "tui")]
+        Format::Collect => unreachable!("`Collect` has no --output-format name"),
+        Format::Validate => unreachable!("`Validate` has no --output-format name"),
+    }
+}
+
+/// Runs `parse -o <name>` over the artifacts with an empty configuration
+/// and returns what it printed. The timeline formats read the artifacts'
+/// local times as UTC.
+fn run_parse(dir: &Path, name: &str, format: Format) -> Result<String, String> {
+    let exe = env::current_exe().map_err(|e| format!("unable to find this executable: {}", e))?;
+    let config = dir.join("config.toml");
+    fs::write(&config, "").map_err(|e| format!("{}: {}", config.display(), e))?;
+    let paths: Vec<PathBuf> = FILES.iter().map(|name| dir.join(name)).collect();
+    let mut command = Command::new(exe);
+    command.arg("--config").arg(&config).arg("parse").args(&paths).args(["-o", name]);
+    if let Format::Timeline(_) = format {
+        command.arg("--assume-utc");
+    }
+    for variable in CONFIG_VARIABLES {
+        command.env_remove(variable);
+    }
+    let output = command.output().map_err(|e| format!("unable to run parse: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "parse exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|_| "the output is not UTF-8".to_string())
+}
+
+fn expect(condition: bool, what: &str) -> Check {
+    if condition {
+        Ok(())
+    } else {
+        Err(format!("expected {}", what))
+    }
+}
+
+fn check_text(stdout: &str) -> Check {
+    for line in [
+        "Application: C:\\Windows\\System32\\backup.exe",
+        "Status: Task is ready to run",
+        "Date Run: Monday Mar 4 02:13:00 2024",
+        "Scheduled Date: Mar 1 02:13:00 2024",
+        "User: CORP\\operator",
+        "Application: C:\\Tools\\sync.exe",
+        "Status: Task has not run",
+        "Scheduled Date: Mar 1 09:00:00 2024",
+        "StartBoundary: 2024-03-01T09:00:00",
+        "UserId: Some(\"S-1-5-18 (SYSTEM)\")",
+        "Command: C:\\Tools\\agent.exe",
+    ] {
+        expect(stdout.lines().any(|l| l == line), &format!("the line `{}`", line))?;
+    }
+    Ok(())
+}
+
+fn check_json(stdout: &str) -> Check {
+    match serde_json::from_str(stdout) {
+        Ok(Value::Array(records)) => check_records(&records),
+        _ => Err("a JSON array".to_string()),
+    }
+}
+
+fn check_jsonl(stdout: &str) -> Check {
+    let records: Result<Vec<Value>, _> = stdout.lines().map(serde_json::from_str).collect();
+    check_records(&records.map_err(|e| format!("a JSON object per line: {}", e))?)
+}
+
+/// The fields both JSON formats must carry.
+fn check_records(records: &[Value]) -> Check {
+    expect(records.len() == 3, "3 records")?;
+    let (ran, never, task) = (&records[0], &records[1], &records[2]);
+    expect(records.iter().all(|r| r["parse_status"] == "ok"), "every record to parse")?;
+    expect(ran["name"] == "C:\\Windows\\System32\\backup.exe", "the job's application")?;
+    expect(ran["run_date"] == "2024-03-04T02:13:00", "the job's run date")?;
+    expect(ran["user"] == "CORP\\operator", "the job's user")?;
+    expect(ran["trigger_count"] == 1, "the job's trigger")?;
+    expect(never["status_name"] == "has_not_run", "the never-run job's status")?;
+    expect(never["run_date"] == "0000-00-00T00:00:00", "the never-run job's empty run date")?;
+    expect(never["scheduled_date"] == "2024-03-01T09:00:00", "the never-run job's trigger")?;
+    expect(never["never_ran_but_scheduled"] == true, "the never-run job to be overdue")?;
+    expect(task["format"] == "task", "task XML to be read as a task")?;
+    let calendar = &task["triggers"]["calendar_trigger"];
+    expect(calendar["start_boundary"] == "2024-03-01T09:00:00", "the calendar trigger")?;
+    let unknown = &task["unknown_elements"];
+    expect(
+        unknown.get("Triggers/LogonTrigger").is_some() && unknown.get("Triggers/BootTrigger").is_some(),
+        "the logon and boot triggers among the unknown elements",
+    )?;
+    expect(task["actions"]["exec"]["arguments"] == "--check", "the task's arguments")
+}
+
+fn check_minimal(stdout: &str) -> Check {
+    let lines: Vec<Vec<&str>> = stdout.lines().map(|l| l.split('\t').skip(1).collect()).collect();
+    expect(
+        lines
+            == [
+                vec!["CORP\\operator", "C:\\Windows\\System32\\backup.exe /full", "2024-03-04T02:13:00"],
+                vec!["", "C:\\Tools\\sync.exe", ""],
+                vec!["S-1-5-18", "C:\\Tools\\agent.exe --check", ""],
+            ],
+        "3 lines of user, command line and last run",
+    )
+}
+
+fn check_csv(stdout: &str) -> Check {
+    let lines: Vec<&str> = stdout.lines().collect();
+    expect(lines.len() == 4, "a header and 3 rows")?;
+    expect(
+        lines[0]
+            == "path,format,user,author,command,last_run,findings,warnings,error,issues,\
+                record_index,trigger_count,action_count,has_exec,has_comhandler",
+        "the header row",
+    )?;
+    let formats: Vec<&str> = lines[1..].iter().filter_map(|l| l.split(',').nth(1)).collect();
+    expect(formats == ["binary", "binary", "xml"], "the formats binary, binary and xml")?;
+    let triggers: Vec<&str> = lines[1..].iter().filter_map(|l| l.split(',').nth(11)).collect();
+    expect(triggers == ["1", "1", "3"], "the trigger counts 1, 1 and 3")?;
+    expect(lines[3].contains(",S-1-5-18,CORP\\admin,C:\\Tools\\agent.exe --check,"), "the task's row")
+}
+
+fn check_dot(stdout: &str) -> Check {
+    expect(stdout.starts_with("digraph tasks {"), "a digraph")?;
+    for line in [
+        "t1 [shape=box, label=\"ran\\nscheduled 2024-03-01T02:13:00; \
+         last run 2024-03-04T02:13:00\"];",
+        "t0 [shape=box, label=\"never\\nscheduled 2024-03-01T09:00:00\"];",
+        "p2 [shape=ellipse, label=\"C:\\\\Tools\\\\agent.exe\"];",
+        "t2 -> p2;",
+    ] {
+        expect(stdout.lines().any(|l| l.trim() == line), &format!("the line `{}`", line))?;
+    }
+    expect(stdout.trim_end().ends_with('}'), "the digraph to be closed")
+}
+
+fn check_openioc(stdout: &str) -> Check {
+    expect(
+        stdout.contains("<OpenIOC xmlns=\"http://openioc.org/schemas/OpenIOC_1.1\""),
+        "an OpenIOC 1.1 document",
+    )?;
+    // Nothing is detected without --detect.
+    expect(
+        stdout.contains("<description>Indicators of 0 scheduled tasks with findings</description>"),
+        "no tasks with findings",
+    )?;
+    expect(stdout.trim_end().ends_with("</OpenIOC>"), "the document to be closed")
+}
+
+fn check_taskxml_bundle(stdout: &str) -> Check {
+    expect(stdout.contains("<TaskBundle count=\"3\">"), "a bundle of 3 tasks")?;
+    for name in FILES {
+        expect(
+            stdout.lines().any(|l| l.contains("<BundledTask path=") && l.contains(name)),
+            &format!("{} in the bundle", name),
+        )?;
+    }
+    for line in [
+        "<Command>C:\\Windows\\System32\\backup.exe</Command>",
+        "<Arguments>/full</Arguments>",
+        "<StartBoundary>2024-03-01T09:00:00</StartBoundary>",
+        "<UserId>S-1-5-18</UserId>",
+    ] {
+        expect(stdout.lines().any(|l| l.trim() == line), &format!("the line `{}`", line))?;
+    }
+    expect(stdout.trim_end().ends_with("</TaskBundle>"), "the bundle to be closed")
+}
+
+fn check_bodyfile(stdout: &str) -> Check {
+    let lines: Vec<Vec<&str>> = stdout.lines().map(|l| l.split('|').collect()).collect();
+    expect(lines.iter().all(|l| l.len() == 11), "11 fields on every line")?;
+    // Each artifact has a line of its file times, and ran.job its last run.
+    expect(lines.len() == 4, "4 lines")?;
+    expect(
+        lines.iter().any(|l| l[1].contains("ran.job (Last Time Executed)") && l[8] == RAN_EPOCH),
+        "the last run of ran.job",
+    )
+}
+
+fn check_tln(stdout: &str) -> Check {
+    let lines: Vec<Vec<&str>> = stdout.lines().map(|l| l.split('|').collect()).collect();
+    expect(lines.iter().all(|l| l.len() == 5), "5 fields on every line")?;
+    // Three file times of each artifact, and the last run of ran.job.
+    expect(lines.len() == 10, "10 lines")?;
+    expect(
+        lines.iter().any(|l| {
+            l[..4] == [RAN_EPOCH, "JOB", "", "CORP\\operator"]
+                && l[4].contains("ran.job: Last Time Executed")
+        }),
+        "the last run of ran.job",
+    )
+}
+
+fn check_plaso(stdout: &str) -> Check {
+    let events: Result<Vec<Value>, _> = stdout.lines().map(serde_json::from_str).collect();
+    let events = events.map_err(|e| format!("a JSON object per line: {}", e))?;
+    expect(events.len() == 10, "10 events")?;
+    let runs: Vec<&Value> =
+        events.iter().filter(|e| e["data_type"] == "windows:tasks:job").collect();
+    expect(runs.len() == 1, "one run")?;
+    expect(runs[0]["datetime"] == "2024-03-04T02:13:00+00:00", "the last run of ran.job")?;
+    expect(runs[0]["username"] == "CORP\\operator", "the user of ran.job")
+}
@@ -0,0 +1,190 @@
+//! `serve`: a small HTTP API that parses artifacts posted to it.
+//!
+//! `POST /parse` takes the raw file as the body and answers with the same
+//! JSON record `parse -o json` prints; `GET /healthz` answers `ok`.
+
+use std::io::{self, Read};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use jobfileparser::{job, parse_bytes, DetectOptions, Record};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// How many parses may run at once for each worker: its current one, and
+/// one it gave up on that has not finished yet.
+const PARSES_PER_WORKER: usize = 2;
+
+pub struct ServeOptions {
+    /// Largest request body accepted, in bytes.
+    pub max_body_size: u64,
+    /// How long a single parse may run before the request is abandoned.
+    pub timeout: Duration,
+    /// Number of requests handled at once.
+    pub workers: usize,
+    pub detect: bool,
+}
+
+/// Binds `addr` and serves requests until the process is killed. Only
+/// returns if the address cannot be bound.
+pub fn serve(addr: &str, options: ServeOptions) -> io::Result<()> {
+    let server = Server::http(addr).map_err(io::Error::other)?;
+    if let Some(addr) = server.server_addr().to_ip() {
+        eprintln!("Listening on http://{}", addr);
+    }
+    let server = Arc::new(server);
+    let options = Arc::new(options);
+    let parses = Arc::new(AtomicUsize::new(0));
+    let workers: Vec<_> = (0..options.workers.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let options = Arc::clone(&options);
+            let parses = Arc::clone(&parses);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(request, &options, &parses);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        // A worker only exits if the server shuts down.
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+/// A parse counted against the limit until it is dropped, when its thread
+/// finishes, even if it panics.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    /// Counts one more running parse, or returns `None` if `limit` are
+    /// running already.
+    fn acquire(parses: &Arc<AtomicUsize>, limit: usize) -> Option<Slot> {
+        parses
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| (n < limit).then_some(n + 1))
+            .ok()
+            .map(|_| Slot(Arc::clone(parses)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn handle(mut request: Request, options: &ServeOptions, parses: &Arc<AtomicUsize>) {
+    let peer = request.remote_addr().copied();
+    let (status, body) = match (request.method(), request.url()) {
+        (Method::Get, "/healthz") => (200, "ok\n".to_string()),
+        (Method::Post, "/parse") => parse(&mut request, options, parses),
+        (_, "/healthz") | (_, "/parse") => (405, error_body("method not allowed")),
+        _ => (404, error_body("not found")),
+    };
+    let content_type = if body.starts_with('{') {
+        "application/json"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", content_type).unwrap());
+    if let Err(e) = request.respond(response) {
+        eprintln!("Unable to respond to {}: {}", describe(peer), e);
+    }
+}
+
+fn parse(
+    request: &mut Request,
+    options: &ServeOptions,
+    parses: &Arc<AtomicUsize>,
+) -> (u16, String) {
+    let too_large = || (413, error_body("request body is too large"));
+    if request
+        .body_length()
+        .is_some_and(|len| len as u64 > options.max_body_size)
+    {
+        return too_large();
+    }
+    let mut data = Vec::new();
+    let reader = request.as_reader().take(options.max_body_size + 1);
+    if let Err(e) = { reader }.read_to_end(&mut data) {
+        return (
+            400,
+            error_body(&format!("unable to read request body: {}", e)),
+        );
+    }
+    if data.len() as u64 > options.max_body_size {
+        return too_large();
+    }
+
+    // The parsers are bounded, but a worker should never be held hostage by
+    // one pathological input: give up on it and let the thread finish alone.
+    // Threads given up on still count, so a stream of such inputs is
+    // refused rather than piling them up.
+    let limit = options.workers.max(1) * PARSES_PER_WORKER;
+    let Some(slot) = Slot::acquire(parses, limit) else {
+        return (503, error_body("too many parses running; try again later"));
+    };
+    let (tx, rx) = mpsc::channel();
+    let detect = options.detect;
+    thread::spawn(move || {
+        let _slot = slot;
+        let mut record = Record::new(Path::new("-"), parse_bytes(&data));
+        record.read_job_triggers(&data);
+        record.read_user_data(&data);
+        record.read_coverage(&data, job::DEFAULT_MIN_COVERAGE);
+        if detect {
+            record.detect();
+            record.detect_schedule(Some(&data), &DetectOptions::default());
+        }
+        let _ = tx.send(record);
+    });
+    let record = match rx.recv_timeout(options.timeout) {
+        Ok(record) => record,
+        Err(_) => return (504, error_body("parsing timed out")),
+    };
+    let status = match record {
+        Record::Ok { .. } => 200,
+        Record::Error { .. } => 422,
+    };
+    match serde_json::to_string(&record) {
+        Ok(json) => (status, json),
+        Err(e) => (
+            500,
+            error_body(&format!("unable to serialize record: {}", e)),
+        ),
+    }
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn describe(peer: Option<SocketAddr>) -> String {
+    peer.map_or_else(|| "client".to_string(), |peer| peer.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_are_limited_until_parses_finish() {
+        let parses = Arc::new(AtomicUsize::new(0));
+        let first = Slot::acquire(&parses, 2).unwrap();
+        let second = Slot::acquire(&parses, 2).unwrap();
+        assert!(Slot::acquire(&parses, 2).is_none());
+        drop(first);
+        let third = Slot::acquire(&parses, 2).unwrap();
+        assert_eq!(parses.load(Ordering::Acquire), 2);
+        drop((second, third));
+        assert_eq!(parses.load(Ordering::Acquire), 0);
+    }
+}
@@ -0,0 +1,95 @@
+//! `jobparser.meta.toml` files, which say where the files of a scanned
+//! directory came from. Records for the files below such a directory get
+//! its `hostname`, `timezone` and `evidence_id`, and a sidecar further down
+//! overrides the values it sets:
+//!
+//! ```toml
+//! hostname = "WS042"
+//! timezone = "Europe/Berlin"
+//! evidence_id = "CASE-17/E03"
+//! ```
+//!
+//! A sidecar that cannot be read, is not valid TOML, or has other keys or
+//! values that are not strings is ignored with a warning on stderr.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use jobfileparser::record::Origin;
+
+pub const SIDECAR_NAME: &str = "jobparser.meta.toml";
+
+/// The sidecars below a scan root, read once per directory.
+pub struct Sidecars {
+    root: PathBuf,
+    /// What each directory seen so far passes on, its own sidecar and
+    /// those above it combined.
+    resolved: HashMap<PathBuf, Origin>,
+}
+
+impl Sidecars {
+    pub fn new(root: &Path) -> Sidecars {
+        Sidecars {
+            root: root.to_path_buf(),
+            resolved: HashMap::new(),
+        }
+    }
+
+    /// Where `file`, below the root, came from.
+    pub fn origin(&mut self, file: &Path) -> Origin {
+        match file.parent() {
+            Some(dir) => self.resolve(dir).clone(),
+            None => Origin::default(),
+        }
+    }
+
+    fn resolve(&mut self, dir: &Path) -> &Origin {
+        if !self.resolved.contains_key(dir) {
+            let inherited = match dir.parent() {
+                Some(parent) if dir != self.root && dir.starts_with(&self.root) => {
+                    self.resolve(parent).clone()
+                }
+                _ => Origin::default(),
+            };
+            let origin = match read(&dir.join(SIDECAR_NAME)) {
+                Some(own) => inherited.overridden_by(&own),
+                None => inherited,
+            };
+            self.resolved.insert(dir.to_path_buf(), origin);
+        }
+        &self.resolved[dir]
+    }
+}
+
+/// The sidecar at `path`, or `None` if there is none or it is ignored.
+fn read(path: &Path) -> Option<Origin> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => return ignored(path, &e.to_string()),
+    };
+    let table: toml::Table = match text.parse() {
+        Ok(table) => table,
+        Err(e) => return ignored(path, e.message()),
+    };
+    let mut origin = Origin::default();
+    for (key, value) in &table {
+        let field = match key.as_str() {
+            "hostname" => &mut origin.hostname,
+            "timezone" => &mut origin.timezone,
+            "evidence_id" => &mut origin.evidence_id,
+            _ => return ignored(path, &format!("unknown key {}", key)),
+        };
+        match value.as_str() {
+            Some(value) => *field = Some(value.to_string()),
+            None => return ignored(path, &format!("{} is not a string", key)),
+        }
+    }
+    Some(origin)
+}
+
+fn ignored(path: &Path, problem: &str) -> Option<Origin> {
+    eprintln!("Warning: ignoring {}: {}", path.display(), problem.trim_end());
+    None
+}
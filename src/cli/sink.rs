@@ -0,0 +1,328 @@
+//! Where `Output` writes records: stdout, or the file given with `-w`,
+//! which `--rotate-size` starts afresh once it grows past a limit. The
+//! full file is renamed to `<file>.<YYYYMMDDTHHMMSS>`, with `-<n>` added
+//! after the first rotation in a second, and `--rotate-count` removes the oldest of those
+//! beyond the number kept. Files are only rotated between records, so a
+//! record is never split across two of them.
+//!
+//! With `--gzip` the file is gzip-compressed. Flushing the compressor after
+//! every record would cost most of the compression, so it is flushed every
+//! `GZIP_FLUSH_RECORDS` records or `GZIP_FLUSH_BYTES` bytes of output
+//! instead: a run that dies leaves a file that decompresses up to the last
+//! flush, missing only the gzip trailer.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use jobfileparser::date::format_unix;
+
+/// How many records `--gzip` writes between flushes at most.
+pub const GZIP_FLUSH_RECORDS: usize = 100;
+/// How many uncompressed bytes `--gzip` writes between flushes at most.
+pub const GZIP_FLUSH_BYTES: u64 = 1024 * 1024;
+
+/// The `-w` file and its rotation limits.
+#[derive(Clone)]
+pub struct Destination {
+    pub path: PathBuf,
+    /// Rotate once the file holds at least this many bytes.
+    pub rotate_size: Option<u64>,
+    /// How many rotated files to keep; all of them if `None`.
+    pub rotate_count: Option<usize>,
+    /// Compress the file with gzip. `rotate_size` still counts the bytes
+    /// before compression.
+    pub gzip: bool,
+}
+
+pub enum Sink {
+    Stdout(io::Stdout),
+    File(RotatingFile),
+}
+
+impl Sink {
+    /// Opens `destination`, or stdout without one, and writes `header` at
+    /// the top of every file.
+    pub fn open(destination: Option<&Destination>, header: Option<String>) -> Result<Sink, String> {
+        let mut sink = match destination {
+            None => Sink::Stdout(io::stdout()),
+            Some(destination) => {
+                Sink::File(RotatingFile::create(destination.clone(), header.clone())?)
+            }
+        };
+        if let (Sink::Stdout(_), Some(header)) = (&sink, header) {
+            let _ = sink.write_all(header.as_bytes());
+        }
+        Ok(sink)
+    }
+
+    /// Flushes the record just written, so that it survives the process
+    /// being killed, and rotates the file if it is full.
+    pub fn end_record(&mut self) {
+        match self {
+            Sink::Stdout(stdout) => {
+                let _ = stdout.flush();
+            }
+            Sink::File(file) => file.end_record(),
+        }
+    }
+
+    /// The `-w` file, or `None` for stdout.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Sink::Stdout(_) => None,
+            Sink::File(file) => Some(&file.destination.path),
+        }
+    }
+
+    /// Flushes what is left and reports the first write error, if any.
+    pub fn finish(&mut self) {
+        if let Sink::File(file) = self {
+            file.end_record();
+            file.close();
+            if let Some(e) = &file.error {
+                eprintln!("Unable to write {}: {}", file.destination.path.display(), e);
+            }
+        }
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Stdout(stdout) => stdout.write(buf),
+            Sink::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Stdout(stdout) => stdout.flush(),
+            Sink::File(file) => file.flush(),
+        }
+    }
+}
+
+/// The open `-w` file, compressed or not.
+enum Writer {
+    Plain(BufWriter<File>),
+    /// Boxed, as the compressor's state is large.
+    Gzip(Box<GzEncoder<BufWriter<File>>>),
+}
+
+impl Writer {
+    /// Writes what is buffered, and for gzip the trailer, and closes the
+    /// file.
+    fn close(self) -> io::Result<()> {
+        match self {
+            Writer::Plain(mut file) => file.flush(),
+            Writer::Gzip(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Writer::Plain(file) => file.write(buf),
+            Writer::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Writer::Plain(file) => file.flush(),
+            Writer::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+pub struct RotatingFile {
+    destination: Destination,
+    header: Option<String>,
+    /// `None` while the file is being rotated, or if rotating failed.
+    file: Option<Writer>,
+    /// Bytes written to the current file.
+    written: u64,
+    /// Records and bytes written since the last flush.
+    unflushed: (usize, u64),
+    /// The timestamp and number of the last rotated file.
+    last: Option<(String, u32)>,
+    /// The first error writing or rotating; later records are dropped.
+    error: Option<io::Error>,
+}
+
+impl RotatingFile {
+    fn create(destination: Destination, header: Option<String>) -> Result<RotatingFile, String> {
+        let file = open(&destination)
+            .map_err(|e| format!("unable to create {}: {}", destination.path.display(), e))?;
+        let mut file = RotatingFile {
+            destination,
+            header,
+            file: Some(file),
+            written: 0,
+            unflushed: (0, 0),
+            last: None,
+            error: None,
+        };
+        file.write_header();
+        Ok(file)
+    }
+
+    fn write_header(&mut self) {
+        if let Some(header) = self.header.take() {
+            let _ = self.write_all(header.as_bytes());
+            self.header = Some(header);
+        }
+    }
+
+    fn end_record(&mut self) {
+        self.unflushed.0 += 1;
+        let (records, bytes) = self.unflushed;
+        let due = !self.destination.gzip
+            || records >= GZIP_FLUSH_RECORDS
+            || bytes >= GZIP_FLUSH_BYTES;
+        if due {
+            if let Err(e) = self.flush() {
+                self.error.get_or_insert(e);
+            }
+            self.unflushed = (0, 0);
+        }
+        let full = self.destination.rotate_size.is_some_and(|size| self.written >= size);
+        if full && self.error.is_none() {
+            if let Err(e) = self.rotate() {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let path = self.destination.path.clone();
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        // Colons are not allowed in file names on Windows.
+        let stamp = format_unix(since_epoch.as_secs()).replace(['-', ':'], "");
+        // Number past every file from the same second, including ones
+        // removed since, so that names keep sorting oldest first.
+        let taken = rotated_files(&path)?
+            .into_iter()
+            .filter(|(other, _, _)| *other == stamp)
+            .map(|(_, n, _)| n)
+            .max();
+        let last = self.last.as_ref().filter(|(other, _)| *other == stamp).map(|(_, n)| *n);
+        let n = taken.max(last).map_or(0, |n| n + 1);
+        let rotated = match n {
+            0 => suffixed(&path, &stamp),
+            n => suffixed(&path, &format!("{}-{}", stamp, n)),
+        };
+        // Close the file first: Windows cannot rename an open file.
+        if let Some(file) = self.file.take() {
+            file.close()?;
+        }
+        fs::rename(&path, &rotated)?;
+        self.last = Some((stamp, n));
+        self.file = Some(open(&self.destination)?);
+        self.written = 0;
+        self.unflushed = (0, 0);
+        self.write_header();
+        if let Some(keep) = self.destination.rotate_count {
+            let mut old = rotated_files(&path)?;
+            old.sort();
+            let excess = old.len().saturating_sub(keep);
+            for (_, _, file) in old.into_iter().take(excess) {
+                fs::remove_file(file)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RotatingFile {
+    /// Closes the file, for good.
+    fn close(&mut self) {
+        if let Some(file) = self.file.take() {
+            if let Err(e) = file.close() {
+                self.error.get_or_insert(e);
+            }
+        }
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(file) = self.file.as_mut().filter(|_| self.error.is_none()) else {
+            return Ok(buf.len());
+        };
+        match file.write(buf) {
+            Ok(n) => {
+                self.written += n as u64;
+                self.unflushed.1 += n as u64;
+                Ok(n)
+            }
+            Err(e) => {
+                self.error = Some(e);
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+fn open(destination: &Destination) -> io::Result<Writer> {
+    let file = BufWriter::new(File::create(&destination.path)?);
+    Ok(if destination.gzip {
+        Writer::Gzip(Box::new(GzEncoder::new(file, Compression::default())))
+    } else {
+        Writer::Plain(file)
+    })
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// The files an earlier rotation of `path` left, with the timestamp and
+/// number their names end in, so that they sort oldest first.
+fn rotated_files(path: &Path) -> io::Result<Vec<(String, u32, PathBuf)>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Some(prefix) = path.file_name().map(|name| format!("{}.", name.to_string_lossy())) else {
+        return Ok(Vec::new());
+    };
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(suffix) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+        let (stamp, n) = match suffix.split_once('-') {
+            Some((stamp, n)) => match n.parse() {
+                Ok(n) => (stamp, n),
+                Err(_) => continue,
+            },
+            None => (suffix, 0),
+        };
+        let is_stamp = stamp.len() == 15
+            && stamp
+                .char_indices()
+                .all(|(i, c)| if i == 8 { c == 'T' } else { c.is_ascii_digit() });
+        if is_stamp {
+            files.push((stamp.to_string(), n, entry.path()));
+        }
+    }
+    Ok(files)
+}
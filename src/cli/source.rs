@@ -0,0 +1,67 @@
+//! `parse --show-source`: the file behind a record, printed after it, as
+//! indented XML or as a hex dump of a binary job with its sections marked.
+
+use std::fmt::Write;
+
+use jobfileparser::{job, task, ArtifactFormat};
+
+/// Bytes per hex dump line.
+const LINE: usize = 16;
+
+/// Prints `data`, read as `format`, on stdout. XML that is not well formed
+/// is dumped as hex instead, with a warning on stderr.
+pub fn print_source(format: ArtifactFormat, data: &[u8]) {
+    let source = match format {
+        ArtifactFormat::Xml => match task::indent_xml(data) {
+            Ok(xml) => xml,
+            Err(e) => {
+                eprintln!("Warning: unable to indent the XML ({}); showing it as hex", e);
+                hex_dump(data, &[])
+            }
+        },
+        ArtifactFormat::Binary => hex_dump(data, &job::sections(data)),
+    };
+    print!("\nSource ({} bytes):\n{}", data.len(), source);
+}
+
+/// `data` as 16 bytes per line, offset, hex and ASCII, under a line naming
+/// each of `sections` and each run of bytes between or after them. Each
+/// section starts a line of its own.
+pub fn hex_dump(data: &[u8], sections: &[job::Section]) -> String {
+    let mut parts = Vec::new();
+    let mut end = 0;
+    for section in sections {
+        if section.offset > end {
+            parts.push(("not in any section", end, section.offset));
+        }
+        parts.push((section.name, section.offset, section.end()));
+        end = section.end();
+    }
+    if data.len() > end {
+        parts.push(("not in any section", end, data.len()));
+    }
+    let mut dump = String::new();
+    for (name, start, end) in parts {
+        let _ = writeln!(dump, "-- {}: {} bytes at {:#x}", name, end - start, start);
+        for (i, line) in data[start..end].chunks(LINE).enumerate() {
+            let _ = write!(dump, "{:08x}  ", start + i * LINE);
+            for column in 0..LINE {
+                if column == LINE / 2 {
+                    dump.push(' ');
+                }
+                match line.get(column) {
+                    Some(byte) => {
+                        let _ = write!(dump, "{:02x} ", byte);
+                    }
+                    None => dump.push_str("   "),
+                }
+            }
+            let ascii: String = line
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            let _ = writeln!(dump, " |{}|", ascii);
+        }
+    }
+    dump
+}
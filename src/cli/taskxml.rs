@@ -0,0 +1,201 @@
+//! `-o taskxml-bundle`: every task as task XML in one UTF-8 document,
+//! printed once every record is in, for importing an estate's tasks into
+//! lab machines. Task XML is written again from what was parsed, and jobs
+//! are converted as `convert --to xml` converts them, so only what the
+//! model keeps carries over. Each task is wrapped in an element naming the
+//! file it came from:
+//!
+//! ```xml
+//! <?xml version="1.0" encoding="UTF-8"?>
+//! <TaskBundle count="1">
+//!   <BundledTask path="C:\Windows\Tasks\At1.job" source="job">
+//! <Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+//!   ...
+//! </Task>
+//!   </BundledTask>
+//! </TaskBundle>
+//! ```
+//!
+//! Tasks come in the order of their paths, so the same files give the same
+//! document whatever order they were read in. The task XML is not indented
+//! any further, which would change the text of elements running over
+//! several lines. Failed files have no entry.
+//!
+//! With `--split-out-dir`, each task is written to a file of its own in
+//! that directory instead, UTF-16 as Task Scheduler writes it, named after
+//! the file it came from with `.xml` for its extension. Names that would
+//! collide get `-2`, `-3` and so on after their stem, in path order.
+
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use jobfileparser::convert::job_to_task;
+use jobfileparser::fold::fold;
+use jobfileparser::{ParsedArtifact, Record, Task};
+
+use super::openioc::escape;
+
+/// A task and the file it came from.
+struct Entry {
+    path: String,
+    /// `job` or `xml`, the format of the file.
+    source: &'static str,
+    task: Task,
+}
+
+#[derive(Default)]
+pub struct TaskBundle {
+    entries: Vec<Entry>,
+}
+
+impl TaskBundle {
+    /// Adds the task `record` holds, converted if it is a job.
+    pub fn add(&mut self, record: &Record) {
+        let Record::Ok { artifact, .. } = record else {
+            return;
+        };
+        let (source, task) = match artifact {
+            ParsedArtifact::Job(job) => ("job", job_to_task(job)),
+            ParsedArtifact::Task(task) => ("xml", task.clone()),
+        };
+        self.entries.push(Entry { path: record.path().to_string(), source, task });
+    }
+
+    /// The entries in the order of their paths.
+    fn sorted(&self) -> Vec<&Entry> {
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries
+    }
+
+    /// The document.
+    pub fn render(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!("<TaskBundle count=\"{}\">\n", self.entries.len()));
+        for entry in self.sorted() {
+            xml.push_str(&format!(
+                "  <BundledTask path=\"{}\" source=\"{}\">\n",
+                escape(&entry.path),
+                entry.source
+            ));
+            xml.push_str(without_declaration(&entry.task.to_xml()));
+            xml.push_str("  </BundledTask>\n");
+        }
+        xml.push_str("</TaskBundle>\n");
+        xml
+    }
+
+    /// Writes each task to a new file in `dir`, printing a `<path> ->
+    /// <file>` line for each on `out`, and returns how many could not be
+    /// written. Files already there are not replaced.
+    pub fn write_split(&self, dir: &Path, out: &mut impl Write) -> usize {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Unable to create {}: {}", dir.display(), e);
+            return self.entries.len();
+        }
+        let mut taken = BTreeSet::new();
+        let mut unwritten = 0;
+        for entry in self.sorted() {
+            let target = unique_target(dir, &file_stem(&entry.path), &mut taken);
+            let written = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&target)
+                .and_then(|mut file| file.write_all(&entry.task.to_utf16_bytes()));
+            match written {
+                Ok(()) => {
+                    let _ = writeln!(out, "{} -> {}", entry.path, target.display());
+                }
+                Err(e) => {
+                    eprintln!("Unable to write {}: {}", target.display(), e);
+                    unwritten += 1;
+                }
+            }
+        }
+        unwritten
+    }
+}
+
+/// `xml` without the `<?xml ...?>` line `Task::to_xml` starts with, which
+/// names an encoding the bundle is not in.
+fn without_declaration(xml: &str) -> &str {
+    match xml.split_once('\n') {
+        Some((first, rest)) if first.starts_with("<?xml") => rest,
+        _ => xml,
+    }
+}
+
+/// The name of the file at `path` without a `.job` or `.xml` extension,
+/// with the characters Windows does not allow in file names replaced by
+/// `_`. A record from a registry hive or a carved image names its file
+/// after what it was found in, so its path is not always one a file can
+/// have.
+fn file_stem(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or_default();
+    let known = |extension: &str| ["job", "xml"].iter().any(|e| e.eq_ignore_ascii_case(extension));
+    let stem = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && known(extension) => stem,
+        _ => name,
+    };
+    let stem: String = stem
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' | '\u{0}'..='\u{1F}' => '_',
+            c => c,
+        })
+        .collect();
+    if stem.trim_matches(['.', ' ']).is_empty() {
+        "task".to_string()
+    } else {
+        stem
+    }
+}
+
+/// `dir/<stem>.xml`, or with `-2`, `-3` and so on after the stem if a name
+/// in `taken` is the same but for case.
+fn unique_target(dir: &Path, stem: &str, taken: &mut BTreeSet<String>) -> PathBuf {
+    let mut name = format!("{}.xml", stem);
+    let mut n = 1;
+    while !taken.insert(fold(&name).into_owned()) {
+        n += 1;
+        name = format!("{}-{}.xml", stem, n);
+    }
+    dir.join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(file_stem(r"C:\Windows\Tasks\At1.job"), "At1");
+        assert_eq!(file_stem("tasks/Microsoft/Windows/Defrag/ScheduledDefrag"), "ScheduledDefrag");
+        assert_eq!(file_stem("a.b.xml"), "a.b");
+        assert_eq!(file_stem(".job"), ".job");
+        assert_eq!(file_stem("Backup.JOB"), "Backup");
+        assert_eq!(file_stem("GoogleUpdateTaskMachineUA.1.3"), "GoogleUpdateTaskMachineUA.1.3");
+        assert_eq!(file_stem("image.raw@0x3e8"), "image.raw@0x3e8");
+        assert_eq!(file_stem("SOFTWARE:Tasks|x?"), "SOFTWARE_Tasks_x_");
+        assert_eq!(file_stem("dir/"), "task");
+    }
+
+    #[test]
+    fn test_unique_target() {
+        let mut taken = BTreeSet::new();
+        let dir = Path::new("out");
+        assert_eq!(unique_target(dir, "At1", &mut taken), dir.join("At1.xml"));
+        assert_eq!(unique_target(dir, "AT1", &mut taken), dir.join("AT1-2.xml"));
+        assert_eq!(unique_target(dir, "at1", &mut taken), dir.join("at1-3.xml"));
+        assert_eq!(unique_target(dir, "At2", &mut taken), dir.join("At2.xml"));
+    }
+
+    #[test]
+    fn test_without_declaration() {
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-16\"?>\n<Task>\n</Task>\n";
+        assert_eq!(without_declaration(xml), "<Task>\n</Task>\n");
+        assert_eq!(without_declaration("<Task/>\n"), "<Task/>\n");
+    }
+}
@@ -0,0 +1,367 @@
+//! What the terminal the human-readable output goes to can do, found once
+//! and used by the text format, the tables and the browser:
+//!
+//! - color, which `NO_COLOR` turns off, `CLICOLOR_FORCE` turns on even
+//!   when output is not a terminal, and `CLICOLOR=0` turns off; otherwise
+//!   only a terminal other than `TERM=dumb` gets it
+//! - the width to wrap long lines at: `--width`, or the terminal's when
+//!   output goes to one; `--width 0` never wraps
+//! - whether box drawing can use Unicode, which needs a UTF-8 locale in
+//!   `LC_ALL`, `LC_CTYPE` or `LANG`; Windows consoles always can
+
+use std::env;
+
+/// The narrowest width lines are wrapped at, whatever `--width` says.
+pub const MIN_WIDTH: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    pub color: bool,
+    /// The width to wrap at, in characters; `None` not to wrap.
+    pub width: Option<usize>,
+    /// Box drawing characters are shown, rather than `+`, `-` and `|`.
+    pub unicode: bool,
+}
+
+/// The colors the text output uses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paint {
+    Bold,
+    Red,
+    Yellow,
+}
+
+impl Capabilities {
+    /// The capabilities of stdout, which is a terminal if `terminal`, with
+    /// the `--width` of `width`.
+    pub fn detect(terminal: bool, width: Option<usize>) -> Capabilities {
+        let terminal_width = if terminal { terminal_width() } else { None };
+        Capabilities::from_env(|name| env::var(name).ok(), terminal, width, terminal_width)
+    }
+
+    /// `detect`, with the environment read through `var` and the size of
+    /// the terminal given.
+    pub fn from_env(
+        var: impl Fn(&str) -> Option<String>,
+        terminal: bool,
+        width: Option<usize>,
+        terminal_width: Option<usize>,
+    ) -> Capabilities {
+        let set = |name: &str| var(name).is_some_and(|value| !value.is_empty());
+        let color = if set("NO_COLOR") {
+            false
+        } else if set("CLICOLOR_FORCE") && var("CLICOLOR_FORCE").as_deref() != Some("0") {
+            true
+        } else if var("CLICOLOR").as_deref() == Some("0") {
+            false
+        } else {
+            terminal && var("TERM").as_deref() != Some("dumb")
+        };
+        let width = match width {
+            Some(0) => None,
+            Some(width) => Some(width),
+            None => terminal_width.filter(|_| terminal).or_else(|| {
+                let columns = var("COLUMNS").filter(|_| terminal)?;
+                columns.trim().parse().ok().filter(|&columns: &usize| columns > 0)
+            }),
+        };
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|name| var(name).filter(|value| !value.is_empty()));
+        let unicode = match locale {
+            Some(locale) => {
+                let locale = locale.to_ascii_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            }
+            None => cfg!(windows),
+        };
+        Capabilities { color, width: width.map(|width| width.max(MIN_WIDTH)), unicode }
+    }
+
+    /// `text` in `paint`, if color is on.
+    pub fn paint(&self, text: &str, paint: Paint) -> String {
+        if !self.color {
+            return text.to_string();
+        }
+        let code = match paint {
+            Paint::Bold => "1",
+            Paint::Red => "1;31",
+            Paint::Yellow => "33",
+        };
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    }
+
+    /// `text`, lines of the text format, wrapped at the width and with the
+    /// label of each finding, issue and warning count painted.
+    pub fn render(&self, text: &str) -> String {
+        let mut rendered = String::with_capacity(text.len());
+        for line in text.split_inclusive('\n') {
+            let (line, end) = match line.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (line, ""),
+            };
+            let lines = match self.width {
+                Some(width) => wrap(line, width),
+                None => vec![line.to_string()],
+            };
+            for (i, part) in lines.iter().enumerate() {
+                if i > 0 {
+                    rendered.push('\n');
+                }
+                rendered.push_str(&self.label(part, i == 0));
+            }
+            rendered.push_str(end);
+        }
+        rendered
+    }
+
+    /// `line` with its label painted, if it is the first line of a finding,
+    /// an issue or a warning count.
+    fn label(&self, line: &str, first: bool) -> String {
+        let labels = [
+            ("Finding [", Paint::Red),
+            ("Issue [", Paint::Yellow),
+            ("Warnings:", Paint::Yellow),
+        ];
+        let paint = labels.into_iter().find(|(label, _)| first && line.starts_with(label));
+        let Some((label, paint)) = paint else {
+            return line.to_string();
+        };
+        let end = match label.strip_suffix('[') {
+            Some(_) => line.find("]:").map_or(line.len(), |end| end + 2),
+            None => label.len(),
+        };
+        format!("{}{}", self.paint(&line[..end], paint), &line[end..])
+    }
+
+    /// The set of lines the browser draws borders with.
+    #[cfg(feature = "tui")]
+    pub fn border_set(&self) -> ratatui::symbols::border::Set {
+        use ratatui::symbols::border;
+        if self.unicode {
+            return border::PLAIN;
+        }
+        border::Set {
+            top_left: "+",
+            top_right: "+",
+            bottom_left: "+",
+            bottom_right: "+",
+            vertical_left: "|",
+            vertical_right: "|",
+            horizontal_top: "-",
+            horizontal_bottom: "-",
+        }
+    }
+}
+
+/// The width of the terminal, in characters.
+#[cfg(feature = "tui")]
+fn terminal_width() -> Option<usize> {
+    crossterm::terminal::size().ok().map(|(columns, _)| usize::from(columns)).filter(|&c| c > 0)
+}
+
+/// The width of the terminal, in characters. Without the browser's
+/// terminal library there is no asking it, so `COLUMNS` is all there is.
+#[cfg(not(feature = "tui"))]
+fn terminal_width() -> Option<usize> {
+    None
+}
+
+/// `line` broken into lines of at most `width` characters, at spaces where
+/// it can be. Lines after the first are indented to where the value of a
+/// `Label: value` line or the text of a `- ` item starts, so that they read
+/// as part of it. A word longer than a line, such as a long path, is broken
+/// after the last `\` or `/` that fits, or else where the line ends.
+/// Characters are counted as one column each.
+pub fn wrap(line: &str, width: usize) -> Vec<String> {
+    let width = width.max(MIN_WIDTH);
+    if line.chars().count() <= width {
+        return vec![line.to_string()];
+    }
+    let text = line.trim_start_matches(' ');
+    let lead = (line.len() - text.len()).min(width / 2);
+    let indent = hanging_indent(text, lead).min(width / 2);
+    let mut lines = Vec::new();
+    let mut current = " ".repeat(lead);
+    let mut length = lead;
+    let mut fresh = true;
+    for word in text.split(' ') {
+        let mut word = word;
+        let mut word_length = word.chars().count();
+        let space = usize::from(!fresh);
+        if length + space + word_length <= width {
+            if !fresh {
+                current.push(' ');
+            }
+            current.push_str(word);
+            length += space + word_length;
+            fresh = false;
+            continue;
+        }
+        // A word that fits on a line of its own starts one; a longer one
+        // starts where it is, if there is a fair amount of room left.
+        if !fresh && (word_length <= width - indent || width - length < MIN_WIDTH / 2) {
+            lines.push(std::mem::take(&mut current));
+            current = " ".repeat(indent);
+            length = indent;
+            fresh = true;
+        }
+        while length + usize::from(!fresh) + word_length > width {
+            if !fresh {
+                current.push(' ');
+                length += 1;
+            }
+            let (piece, rest) = split_word(word, width - length);
+            current.push_str(piece);
+            lines.push(std::mem::take(&mut current));
+            current = " ".repeat(indent);
+            length = indent;
+            fresh = true;
+            word = rest;
+            word_length = word.chars().count();
+        }
+        if !fresh {
+            current.push(' ');
+            length += 1;
+        }
+        current.push_str(word);
+        length += word_length;
+        fresh = word.is_empty() && fresh;
+    }
+    lines.push(current);
+    lines
+}
+
+/// How far lines after the first of `text`, which follows `lead` spaces,
+/// are indented: past the first `: ` or a leading `- `.
+fn hanging_indent(text: &str, lead: usize) -> usize {
+    if text.starts_with("- ") {
+        return lead + 2;
+    }
+    match text.find(": ") {
+        Some(end) => lead + text[..end].chars().count() + 2,
+        None => lead,
+    }
+}
+
+/// `word` split after at most `room` characters, preferably after a path
+/// separator in the second half of them. At least one character goes
+/// first, so a line always takes something.
+fn split_word(word: &str, room: usize) -> (&str, &str) {
+    let room = room.max(1);
+    let end = word.char_indices().nth(room).map_or(word.len(), |(end, _)| end);
+    let separator = word[..end]
+        .char_indices()
+        .rev()
+        .find(|&(i, c)| (c == '\\' || c == '/') && i + 1 >= end / 2)
+        .map(|(i, _)| i + 1);
+    word.split_at(separator.unwrap_or(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+    }
+
+    fn capabilities(vars: &[(&str, &str)], terminal: bool) -> Capabilities {
+        Capabilities::from_env(env(vars), terminal, None, Some(100))
+    }
+
+    fn unwrap(lines: &[String]) -> String {
+        lines.iter().map(|line| line.trim_start()).collect()
+    }
+
+    #[test]
+    fn test_color() {
+        assert!(capabilities(&[], true).color);
+        assert!(!capabilities(&[], false).color);
+        assert!(!capabilities(&[("NO_COLOR", "1")], true).color);
+        // An empty NO_COLOR does not count.
+        assert!(capabilities(&[("NO_COLOR", "")], true).color);
+        assert!(capabilities(&[("CLICOLOR_FORCE", "1")], false).color);
+        assert!(!capabilities(&[("CLICOLOR_FORCE", "0")], false).color);
+        assert!(!capabilities(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")], true).color);
+        assert!(!capabilities(&[("CLICOLOR", "0")], true).color);
+        assert!(!capabilities(&[("TERM", "dumb")], true).color);
+    }
+
+    #[test]
+    fn test_width_and_locale() {
+        assert_eq!(capabilities(&[], true).width, Some(100));
+        assert_eq!(capabilities(&[], false).width, None);
+        assert_eq!(Capabilities::from_env(env(&[]), false, Some(60), None).width, Some(60));
+        assert_eq!(Capabilities::from_env(env(&[]), true, Some(0), Some(100)).width, None);
+        assert_eq!(Capabilities::from_env(env(&[]), true, Some(5), None).width, Some(MIN_WIDTH));
+        let columns = [("COLUMNS", "132")];
+        assert_eq!(Capabilities::from_env(env(&columns), true, None, None).width, Some(132));
+        assert_eq!(Capabilities::from_env(env(&columns), false, None, None).width, None);
+
+        assert!(capabilities(&[("LANG", "en_US.UTF-8")], true).unicode);
+        assert!(capabilities(&[("LC_ALL", "de_DE.utf8"), ("LANG", "C")], true).unicode);
+        assert!(!capabilities(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")], true).unicode);
+        assert!(!capabilities(&[("LANG", "en_US.ISO-8859-1")], true).unicode);
+    }
+
+    #[test]
+    fn test_wrap_words_with_hanging_indent() {
+        let line = "Arguments: /c copy C:\\Users\\Public\\a.txt \\\\srv\\share\\b.txt && exit";
+        let lines = wrap(line, 40);
+        assert_eq!(
+            lines,
+            [
+                "Arguments: /c copy C:\\Users\\Public\\a.txt",
+                "           \\\\srv\\share\\b.txt && exit",
+            ]
+        );
+        assert_eq!(wrap("  - short enough", 40), ["  - short enough"]);
+        let item = wrap("  - TASK_FLAG_HIDDEN and TASK_FLAG_INTERACTIVE are both set", 30);
+        let indented = |line: &String| line.starts_with("    ") && !line.starts_with("     ");
+        assert!(item[1..].iter().all(indented), "{:?}", item);
+    }
+
+    #[test]
+    fn test_wrap_single_token_path() {
+        // A 1000-character path: broken after separators, never past the
+        // width, and nothing lost.
+        let path = format!("C:\\{}a.exe", "ProgramData\\Vendor\\".repeat(53));
+        assert!(path.len() >= 1000);
+        let line = format!("Command: {}", path);
+        let lines = wrap(&line, 80);
+        assert!(lines.iter().all(|line| line.chars().count() <= 80), "{:?}", lines);
+        assert!(lines[1..].iter().all(|line| line.starts_with("         ")));
+        assert!(lines[..lines.len() - 1].iter().all(|line| line.ends_with('\\')), "{:?}", lines);
+        assert_eq!(unwrap(&lines), line);
+
+        // Without any separator it is cut where each line ends.
+        let token = "x".repeat(1000);
+        let lines = wrap(&token, 80);
+        assert_eq!(lines.len(), 13);
+        assert!(lines.iter().all(|line| line.chars().count() <= 80));
+        assert_eq!(unwrap(&lines), token);
+
+        // Multibyte characters are counted once and never split.
+        let token = "é".repeat(1000);
+        let lines = wrap(&format!("Description: {}", token), MIN_WIDTH);
+        assert!(lines.iter().all(|line| line.chars().count() <= MIN_WIDTH));
+        // The token starts a line of its own, so the space before it goes.
+        assert_eq!(unwrap(&lines), format!("Description:{}", token));
+    }
+
+    #[test]
+    fn test_render() {
+        let text = "Command: a.exe\nFinding [hidden]: the task is hidden\n";
+        let plain = Capabilities { color: false, width: None, unicode: true };
+        assert_eq!(plain.render(text), text);
+        let color = Capabilities { color: true, ..plain };
+        assert_eq!(
+            color.render(text),
+            "Command: a.exe\n\x1b[1;31mFinding [hidden]:\x1b[0m the task is hidden\n"
+        );
+        let narrow = Capabilities { width: Some(20), ..plain };
+        let wrapped = narrow.render("Command: a.exe b.exe c.exe");
+        assert_eq!(wrapped, "Command: a.exe b.exe\n         c.exe");
+    }
+}
@@ -0,0 +1,232 @@
+//! `-o bodyfile`, `-o tln` and `-o plaso`: the times of each record as
+//! events for a super timeline, in UTC.
+//!
+//! Times inside artifacts, such as when a job last ran, are in the local
+//! time of the machine, and are converted with `--timezone`; the times the
+//! file system keeps are in UTC already. Each event says which it was:
+//! `local-converted`, with the zone and the time as written, or
+//! `utc-native`. A task registration date written with a zone of its own is
+//! converted by that and counts as `utc-native`.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jobfileparser::{date, ParsedArtifact, Record};
+
+use super::timezone::{self, Zone};
+
+/// How the events are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// The body file of Sleuth Kit's `mactime`.
+    Bodyfile,
+    /// The five fields of the TLN format.
+    Tln,
+    /// JSON lines laid out as Plaso's `json_line` output.
+    Plaso,
+}
+
+/// What an event's time was before it was converted to UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Basis {
+    /// Local time in the zone, as written in the artifact.
+    Local { zone: String, written: String },
+    UtcNative,
+}
+
+impl Basis {
+    fn name(&self) -> &'static str {
+        match self {
+            Basis::Local { .. } => "local-converted",
+            Basis::UtcNative => "utc-native",
+        }
+    }
+
+    /// The label written beside an event.
+    fn label(&self) -> String {
+        match self {
+            Basis::Local { zone, written } => {
+                format!("local-converted from {} {}", written, zone)
+            }
+            Basis::UtcNative => "utc-native".to_string(),
+        }
+    }
+}
+
+/// A time inside an artifact.
+struct Event {
+    /// Seconds since 1970, in UTC.
+    utc: i64,
+    /// As Plaso names such times.
+    description: &'static str,
+    basis: Basis,
+}
+
+/// The times the file system keeps for a file, in seconds since 1970.
+#[derive(Debug, Clone, Default)]
+pub struct FileTimes {
+    size: u64,
+    modified: Option<i64>,
+    accessed: Option<i64>,
+    created: Option<i64>,
+}
+
+impl FileTimes {
+    /// Those of `path`; `None` if it cannot be read, as for an entry of an
+    /// archive or inline input.
+    pub fn read(path: &Path) -> Option<FileTimes> {
+        let metadata = fs::metadata(path).ok()?;
+        let seconds = |time: std::io::Result<SystemTime>| {
+            let time = time.ok()?;
+            Some(match time.duration_since(UNIX_EPOCH) {
+                Ok(after) => after.as_secs() as i64,
+                Err(before) => -(before.duration().as_secs() as i64),
+            })
+        };
+        Some(FileTimes {
+            size: metadata.len(),
+            modified: seconds(metadata.modified()),
+            accessed: seconds(metadata.accessed()),
+            created: seconds(metadata.created()),
+        })
+    }
+
+    fn events(&self) -> Vec<Event> {
+        [
+            (self.modified, "Content Modification Time"),
+            (self.accessed, "Last Access Time"),
+            (self.created, "Creation Time"),
+        ]
+        .into_iter()
+        .filter_map(|(time, description)| {
+            time.map(|utc| Event { utc, description, basis: Basis::UtcNative })
+        })
+        .collect()
+    }
+}
+
+/// The times inside `artifact`: when a job last ran, or when a task was
+/// registered.
+fn artifact_events(artifact: &ParsedArtifact, zone: &Zone) -> Vec<Event> {
+    let (written, description) = match artifact {
+        ParsedArtifact::Job(_) => (artifact.last_run(), "Last Time Executed"),
+        ParsedArtifact::Task(task) => (task.registration_info.date.clone(), "Registration Time"),
+    };
+    let Some(written) = written else {
+        return Vec::new();
+    };
+    let Some(local) = date::local_seconds(&written) else {
+        return Vec::new();
+    };
+    // Anything after the seconds and their fraction is a zone.
+    let suffix = written.get(19..).unwrap_or_default();
+    let suffix = suffix.trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let (utc, basis) = match suffix {
+        "" => {
+            let basis = Basis::Local { zone: zone.name.clone(), written };
+            (zone.to_utc(local), basis)
+        }
+        "Z" => (local, Basis::UtcNative),
+        offset => match timezone::parse_offset(offset) {
+            Some(offset) => (local - offset, Basis::UtcNative),
+            None => return Vec::new(),
+        },
+    };
+    vec![Event { utc, description, basis }]
+}
+
+/// Writes the events of `record`, whose file has the times of `file`, in
+/// `style`. Failed files have none.
+pub fn write(
+    out: &mut impl Write,
+    style: Style,
+    record: &Record,
+    file: Option<&FileTimes>,
+    zone: &Zone,
+) -> std::io::Result<()> {
+    let Record::Ok { artifact, .. } = record else {
+        return Ok(());
+    };
+    let path = record.path();
+    let events = artifact_events(artifact, zone);
+    let (source, data_type) = match artifact {
+        ParsedArtifact::Job(_) => ("JOB", "windows:tasks:job"),
+        ParsedArtifact::Task(_) => ("TASK", "windows:tasks:task_xml"),
+    };
+    let host = record.origin().hostname.as_deref().unwrap_or_default();
+    let user = artifact.user().unwrap_or_default();
+    match style {
+        Style::Bodyfile => {
+            // The file times on one line, as mactime expects them.
+            if let Some(file) = file {
+                let time = |time: Option<i64>| time.unwrap_or_default();
+                writeln!(
+                    out,
+                    "0|{} [utc-native]|0|0|0|0|{}|{}|{}|0|{}",
+                    field(path),
+                    file.size,
+                    time(file.accessed),
+                    time(file.modified),
+                    time(file.created)
+                )?;
+            }
+            for event in &events {
+                let name = format!("{} ({}) [{}]", path, event.description, event.basis.label());
+                writeln!(out, "0|{}|0|0|0|0|0|0|{}|0|0", field(&name), event.utc)?;
+            }
+        }
+        Style::Tln => {
+            let file_events = file.map(FileTimes::events).unwrap_or_default();
+            let all = events
+                .iter()
+                .map(|e| (source, e))
+                .chain(file_events.iter().map(|e| ("FILE", e)));
+            for (source, event) in all {
+                let description =
+                    format!("{}: {} [{}]", path, event.description, event.basis.label());
+                let fields = [source, host, user, description.as_str()].map(field);
+                writeln!(out, "{}|{}", event.utc, fields.join("|"))?;
+            }
+        }
+        Style::Plaso => {
+            let file_events = file.map(FileTimes::events).unwrap_or_default();
+            let all = events
+                .iter()
+                .map(|e| (data_type, e))
+                .chain(file_events.iter().map(|e| ("fs:stat", e)));
+            for (data_type, event) in all {
+                let mut line = serde_json::json!({
+                    "timestamp": event.utc * 1_000_000,
+                    "timestamp_desc": event.description,
+                    "datetime": format!("{}+00:00", format_utc(event.utc)),
+                    "data_type": data_type,
+                    "parser": "jobfileparser",
+                    "filename": path,
+                    "display_name": path,
+                    "hostname": host,
+                    "username": user,
+                    "message": format!("{}: {}", path, event.description),
+                    "time_basis": event.basis.name(),
+                });
+                if let Basis::Local { zone, written } = &event.basis {
+                    line["timezone"] = zone.as_str().into();
+                    line["original_time"] = written.as_str().into();
+                }
+                writeln!(out, "{}", line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `text` with the separators of the line formats and line breaks made
+/// spaces.
+fn field(text: &str) -> String {
+    text.replace(['|', '\n', '\r'], " ")
+}
+
+fn format_utc(seconds: i64) -> String {
+    date::format_unix(seconds.max(0) as u64)
+}
@@ -0,0 +1,396 @@
+//! `--timezone`: the zone the clock of the machine an artifact came from
+//! was set to, for turning the local times inside it into UTC.
+//!
+//! A zone is `UTC`, an offset such as `+05:30`, a POSIX `TZ` rule such as
+//! `CET-1CEST,M3.5.0,M10.5.0/3`, or a name: an IANA name or Windows zone
+//! name from a built-in list, or on Unix any IANA name in the system's
+//! `zoneinfo` database, by the rule at the end of its file. The rule of a
+//! zone as it is now is applied to every year, so dates from before a zone
+//! last changed its daylight saving time dates, as the United States did
+//! in 2007, can be an hour off.
+//!
+//! A local time in the hour skipped when clocks go forward is taken as if
+//! they had not gone forward yet. One in the hour repeated when they go
+//! back is ambiguous and is taken as the first of the two, in daylight
+//! saving time.
+
+use jobfileparser::date;
+
+/// Zones known by name on every platform, by their rule.
+const NAMES: &[(&str, &str)] = &[
+    ("UTC", "UTC0"),
+    ("Etc/UTC", "UTC0"),
+    ("GMT", "GMT0"),
+    ("Europe/London", "GMT0BST,M3.5.0/1,M10.5.0"),
+    ("Europe/Dublin", "GMT0IST,M3.5.0/1,M10.5.0"),
+    ("Europe/Lisbon", "WET0WEST,M3.5.0/1,M10.5.0"),
+    ("Europe/Amsterdam", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Berlin", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Brussels", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Madrid", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Paris", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Rome", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Stockholm", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Vienna", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Warsaw", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Zurich", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Europe/Athens", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Helsinki", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Kyiv", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Europe/Istanbul", "<+03>-3"),
+    ("Europe/Moscow", "MSK-3"),
+    ("America/New_York", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Chicago", "CST6CDT,M3.2.0,M11.1.0"),
+    ("America/Denver", "MST7MDT,M3.2.0,M11.1.0"),
+    ("America/Phoenix", "MST7"),
+    ("America/Los_Angeles", "PST8PDT,M3.2.0,M11.1.0"),
+    ("America/Anchorage", "AKST9AKDT,M3.2.0,M11.1.0"),
+    ("America/Toronto", "EST5EDT,M3.2.0,M11.1.0"),
+    ("America/Mexico_City", "CST6"),
+    ("America/Sao_Paulo", "<-03>3"),
+    ("Pacific/Honolulu", "HST10"),
+    ("Asia/Dubai", "<+04>-4"),
+    ("Asia/Kolkata", "IST-5:30"),
+    ("Asia/Shanghai", "CST-8"),
+    ("Asia/Singapore", "<+08>-8"),
+    ("Asia/Tokyo", "JST-9"),
+    ("Asia/Seoul", "KST-9"),
+    ("Australia/Perth", "AWST-8"),
+    ("Australia/Sydney", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+    ("Pacific/Auckland", "NZST-12NZDT,M9.5.0,M4.1.0/3"),
+    // As the TimeZoneKeyName value of the SYSTEM hive names them.
+    ("GMT Standard Time", "GMT0BST,M3.5.0/1,M10.5.0"),
+    ("W. Europe Standard Time", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Romance Standard Time", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("Central Europe Standard Time", "CET-1CEST,M3.5.0,M10.5.0/3"),
+    ("FLE Standard Time", "EET-2EEST,M3.5.0/3,M10.5.0/4"),
+    ("Eastern Standard Time", "EST5EDT,M3.2.0,M11.1.0"),
+    ("Central Standard Time", "CST6CDT,M3.2.0,M11.1.0"),
+    ("Mountain Standard Time", "MST7MDT,M3.2.0,M11.1.0"),
+    ("Pacific Standard Time", "PST8PDT,M3.2.0,M11.1.0"),
+    ("India Standard Time", "IST-5:30"),
+    ("China Standard Time", "CST-8"),
+    ("Tokyo Standard Time", "JST-9"),
+    ("AUS Eastern Standard Time", "AEST-10AEDT,M10.1.0,M4.1.0/3"),
+];
+
+/// A time zone: its offset from UTC in standard time, and when daylight
+/// saving time is kept, if it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zone {
+    /// As given to `--timezone`.
+    pub name: String,
+    /// Seconds east of UTC.
+    standard: i64,
+    dst: Option<Dst>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Dst {
+    /// Seconds east of UTC.
+    offset: i64,
+    /// When it starts, in standard time, and ends, in daylight saving time.
+    start: Transition,
+    end: Transition,
+}
+
+/// `Mm.w.d/time`: day `weekday` of week `week` of `month`, week 5 being
+/// the last, at `time` seconds after midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Transition {
+    month: i64,
+    week: i64,
+    /// 0 for Sunday.
+    weekday: i64,
+    time: i64,
+}
+
+impl Zone {
+    /// Reads a `--timezone` value.
+    pub fn parse(text: &str) -> Result<Zone, String> {
+        let text = text.trim();
+        let rule = match NAMES.iter().find(|(name, _)| name.eq_ignore_ascii_case(text)) {
+            Some((_, rule)) => rule.to_string(),
+            None if text.eq_ignore_ascii_case("Z") => "UTC0".to_string(),
+            None if text.starts_with(['+', '-']) => {
+                let offset = parse_offset(text).ok_or_else(|| invalid(text))?;
+                return Ok(Zone { name: text.to_string(), standard: offset, dst: None });
+            }
+            None if text.contains('/') && !text.contains(',') => system_rule(text)
+                .ok_or_else(|| format!("unknown time zone {}; give a POSIX TZ rule", text))?,
+            None => text.to_string(),
+        };
+        let mut zone = parse_rule(&rule).ok_or_else(|| invalid(text))?;
+        zone.name = text.to_string();
+        Ok(zone)
+    }
+
+    /// The UTC time of `local`, seconds since 1970 as a wall clock in this
+    /// zone showed them.
+    pub fn to_utc(&self, local: i64) -> i64 {
+        let Some(dst) = &self.dst else {
+            return local - self.standard;
+        };
+        let year = year_of(local);
+        // When daylight saving time starts and ends that year, in UTC.
+        let start = transition_day(year, dst.start) + dst.start.time - self.standard;
+        let end = transition_day(year, dst.end) + dst.end.time - dst.offset;
+        let in_dst = |utc: i64| {
+            if start < end {
+                (start..end).contains(&utc)
+            } else {
+                !(end..start).contains(&utc)
+            }
+        };
+        // The earlier reading first, for the hour that happens twice.
+        if in_dst(local - dst.offset) {
+            local - dst.offset
+        } else {
+            local - self.standard
+        }
+    }
+
+    /// UTC itself, for `--assume-utc`.
+    pub fn utc() -> Zone {
+        Zone { name: "UTC".to_string(), standard: 0, dst: None }
+    }
+}
+
+fn invalid(text: &str) -> String {
+    format!("invalid time zone {}: expected a name, an offset such as +05:30 or a TZ rule", text)
+}
+
+/// `+HH:MM`, `+HHMM` or `+HH` as seconds east of UTC.
+pub fn parse_offset(text: &str) -> Option<i64> {
+    let (sign, digits) = text.split_at(1);
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() == 4 => digits.split_at(2),
+        None => (digits, "0"),
+    };
+    let (hours, minutes): (i64, i64) = (hours.parse().ok()?, minutes.parse().ok()?);
+    if hours > 14 || minutes > 59 || !digits.bytes().all(|b| b.is_ascii_digit() || b == b':') {
+        return None;
+    }
+    let seconds = (hours * 60 + minutes) * 60;
+    Some(if sign == "-" { -seconds } else { seconds })
+}
+
+/// A POSIX `TZ` rule: `std offset [dst [offset] [,start[/time],end[/time]]]`,
+/// with offsets west of UTC and transitions as `Mm.w.d`. A zone with
+/// daylight saving time but no transitions gets those of the United States.
+fn parse_rule(rule: &str) -> Option<Zone> {
+    let mut rest = rule;
+    let _standard_name = take_name(&mut rest)?;
+    let standard = -take_time(&mut rest)?;
+    if rest.is_empty() {
+        return Some(Zone { name: String::new(), standard, dst: None });
+    }
+    let _dst_name = take_name(&mut rest)?;
+    let offset = match rest.starts_with(|c: char| c == '+' || c == '-' || c.is_ascii_digit()) {
+        true => -take_time(&mut rest)?,
+        false => standard + 3600,
+    };
+    let rules = if rest.is_empty() { ",M3.2.0,M11.1.0" } else { rest };
+    let mut transitions = rules.strip_prefix(',')?.split(',');
+    let start = parse_transition(transitions.next()?)?;
+    let end = parse_transition(transitions.next()?)?;
+    if transitions.next().is_some() {
+        return None;
+    }
+    Some(Zone { name: String::new(), standard, dst: Some(Dst { offset, start, end }) })
+}
+
+/// Takes a zone abbreviation, such as `CET` or `<+0330>`, off the front.
+fn take_name<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let (name, after) = match rest.strip_prefix('<') {
+        Some(quoted) => {
+            let end = quoted.find('>')?;
+            (&quoted[..end], &quoted[end + 1..])
+        }
+        None => {
+            let end = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+            rest.split_at(end)
+        }
+    };
+    *rest = after;
+    (name.len() >= 3 || name.starts_with(['+', '-'])).then_some(name)
+}
+
+/// Takes `[+-]hh[:mm[:ss]]` off the front, as seconds.
+fn take_time(rest: &mut &str) -> Option<i64> {
+    let sign = if rest.starts_with('-') { -1 } else { 1 };
+    let digits = rest.trim_start_matches(['+', '-']);
+    let end = digits.find(|c: char| !c.is_ascii_digit() && c != ':').unwrap_or(digits.len());
+    let mut seconds = 0;
+    let mut parts = 0;
+    for (i, part) in digits[..end].split(':').enumerate() {
+        let value: i64 = part.parse().ok()?;
+        if i > 2 || (i > 0 && value > 59) {
+            return None;
+        }
+        seconds += value * [3600, 60, 1][i];
+        parts += 1;
+    }
+    *rest = &digits[end..];
+    (parts > 0 && seconds <= 25 * 3600).then_some(sign * seconds)
+}
+
+/// `Mm.w.d[/time]`; the time defaults to 02:00.
+fn parse_transition(text: &str) -> Option<Transition> {
+    let (date, mut time) = match text.split_once('/') {
+        Some((date, time)) => (date, time),
+        None => (text, "2"),
+    };
+    let mut fields = date.strip_prefix('M')?.split('.').map(|field| field.parse::<i64>().ok());
+    let (month, week, weekday) = (fields.next()??, fields.next()??, fields.next()??);
+    let valid = (1..=12).contains(&month) && (1..=5).contains(&week) && (0..=6).contains(&weekday);
+    if fields.next().is_some() || !valid {
+        return None;
+    }
+    let time = take_time(&mut time).filter(|_| time.is_empty())?;
+    Some(Transition { month, week, weekday, time })
+}
+
+/// The rule at the end of the zone's file in the system's `zoneinfo`
+/// database, which has been there since version 2 of the format.
+#[cfg(unix)]
+fn system_rule(name: &str) -> Option<String> {
+    if name.split('/').any(|part| part.is_empty() || part == "..") {
+        return None;
+    }
+    let data = std::fs::read(std::path::Path::new("/usr/share/zoneinfo").join(name)).ok()?;
+    if !data.starts_with(b"TZif") || data.get(4).is_none_or(|&version| version < b'2') {
+        return None;
+    }
+    let text = std::str::from_utf8(data.strip_suffix(b"\n")?.rsplit(|&b| b == b'\n').next()?);
+    text.ok().filter(|rule| !rule.is_empty()).map(str::to_string)
+}
+
+#[cfg(not(unix))]
+fn system_rule(_name: &str) -> Option<String> {
+    None
+}
+
+/// The year the day of `seconds` since 1970 falls in.
+fn year_of(seconds: i64) -> i64 {
+    let days = seconds.div_euclid(86400);
+    // 400 years are 146097 days; the estimate is off by at most one.
+    let mut year = 1970 + days * 400 / 146097;
+    while days_before(year) > days {
+        year -= 1;
+    }
+    while days_before(year + 1) <= days {
+        year += 1;
+    }
+    year
+}
+
+/// Days from 1970 to the first of January of `year`.
+fn days_before(year: i64) -> i64 {
+    days_of(year, 1, 1)
+}
+
+fn days_of(year: i64, month: i64, day: i64) -> i64 {
+    let text = format!("{:04}-{:02}-{:02}T00:00:00", year, month, day);
+    date::local_seconds(&text).unwrap_or_default().div_euclid(86400)
+}
+
+/// The seconds since 1970 of the midnight starting the day `transition`
+/// names in `year`.
+fn transition_day(year: i64, transition: Transition) -> i64 {
+    let first = days_of(year, transition.month, 1);
+    // 1970-01-01 was a Thursday.
+    let first_weekday = (first + 4).rem_euclid(7);
+    let mut day = first + (transition.weekday - first_weekday).rem_euclid(7);
+    day += (transition.week - 1) * 7;
+    let next_month = match transition.month {
+        12 => days_before(year + 1),
+        month => days_of(year, month + 1, 1),
+    };
+    while day >= next_month {
+        day -= 7;
+    }
+    day * 86400
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The UTC time of `local` in the zone `--timezone` reads from `zone`.
+    fn utc(zone: &str, local: &str) -> String {
+        let zone = Zone::parse(zone).unwrap();
+        date::format_unix(zone.to_utc(date::local_seconds(local).unwrap()) as u64)
+    }
+
+    #[test]
+    fn test_southern_hemisphere() {
+        // Daylight saving time runs from October into April, over the end
+        // of the year.
+        let sydney = "Australia/Sydney";
+        assert_eq!(utc(sydney, "2024-01-15T12:00:00"), "2024-01-15T01:00:00");
+        assert_eq!(utc(sydney, "2024-07-15T12:00:00"), "2024-07-15T02:00:00");
+        assert_eq!(utc(sydney, "2024-12-31T23:30:00"), "2024-12-31T12:30:00");
+        assert_eq!(utc(sydney, "2025-01-01T00:30:00"), "2024-12-31T13:30:00");
+        assert_eq!(utc(sydney, "2024-04-07T03:30:00"), "2024-04-06T17:30:00");
+        assert_eq!(utc(sydney, "2024-10-06T03:30:00"), "2024-10-05T16:30:00");
+        let rule = "AEST-10AEDT,M10.1.0,M4.1.0/3";
+        assert_eq!(utc(rule, "2024-01-15T12:00:00"), "2024-01-15T01:00:00");
+    }
+
+    #[test]
+    fn test_skipped_and_repeated_hours() {
+        // Clocks went forward at 02:00 and back at 03:00 in Berlin, and the
+        // other way around in Sydney.
+        assert_eq!(utc("Europe/Berlin", "2024-03-31T01:59:00"), "2024-03-31T00:59:00");
+        assert_eq!(utc("Europe/Berlin", "2024-03-31T02:30:00"), "2024-03-31T01:30:00");
+        assert_eq!(utc("Europe/Berlin", "2024-03-31T03:00:00"), "2024-03-31T01:00:00");
+        assert_eq!(utc("Europe/Berlin", "2024-10-27T02:30:00"), "2024-10-27T00:30:00");
+        assert_eq!(utc("Europe/Berlin", "2024-10-27T03:00:00"), "2024-10-27T02:00:00");
+        assert_eq!(utc("Australia/Sydney", "2024-10-06T02:30:00"), "2024-10-05T16:30:00");
+        assert_eq!(utc("Australia/Sydney", "2024-04-07T02:30:00"), "2024-04-06T15:30:00");
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(parse_offset("+05:30"), Some(19800));
+        assert_eq!(parse_offset("-0330"), Some(-12600));
+        assert_eq!(parse_offset("+05"), Some(18000));
+        assert_eq!(parse_offset("-00:00"), Some(0));
+        for text in ["+15:00", "+05:60", "+5a", "+", "-05:3x"] {
+            assert_eq!(parse_offset(text), None, "{}", text);
+        }
+        assert_eq!(utc("+05:30", "2024-01-15T12:00:00"), "2024-01-15T06:30:00");
+        assert_eq!(utc("-08:00", "2024-01-15T20:00:00"), "2024-01-16T04:00:00");
+        // Rules count west of UTC, so their signs are the other way around.
+        assert_eq!(utc("IST-5:30", "2024-01-15T12:00:00"), "2024-01-15T06:30:00");
+        assert_eq!(utc("<+0545>-5:45", "2024-01-15T12:00:00"), "2024-01-15T06:15:00");
+        assert_eq!(utc("<-03>3", "2024-01-15T12:00:00"), "2024-01-15T15:00:00");
+        assert_eq!(utc("Z", "2024-01-15T12:00:00"), "2024-01-15T12:00:00");
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        for rule in [
+            "",
+            "CET",
+            "CE-1",
+            "CET-1CEST,M3.5.0",
+            "CET-1CEST,M3.5.0,M10.5",
+            "CET-1CEST,M3.5.0,M10.5.0/3,M1.1.0",
+            "CET-1CEST,M13.1.0,M10.5.0",
+            "CET-1CEST,M3.6.0,M10.5.0",
+            "CET-1CEST,M3.5.7,M10.5.0",
+            "CET-1CEST,M3.5.0/2x,M10.5.0",
+            "CET-1CEST;M3.5.0,M10.5.0",
+            "CET-26",
+            "<+03-3",
+        ] {
+            let error = Zone::parse(rule).unwrap_err();
+            assert!(error.starts_with("invalid time zone"), "{:?}: {}", rule, error);
+        }
+        let error = Zone::parse("Europe/Nowhere").unwrap_err();
+        assert_eq!(error, "unknown time zone Europe/Nowhere; give a POSIX TZ rule");
+    }
+}
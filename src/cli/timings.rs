@@ -0,0 +1,205 @@
+//! `--timings`: how long reading, parsing and writing took, summed over the
+//! run and printed on stderr at the end. With `-v` each file also gets a
+//! line as it is loaded. Nothing is measured unless `enable` was called.
+//!
+//! `SlowFiles` keeps the time of each file for `--slow-threshold` and the
+//! list of the slowest, which is measured either way.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage {
+    /// Reading or mapping the file.
+    Read,
+    /// Parsing a binary job.
+    Binary,
+    /// Decoding and parsing task XML.
+    Xml,
+    /// Building the record, running the heuristics and printing it.
+    Output,
+}
+
+impl Stage {
+    const ALL: [Stage; 4] = [Stage::Read, Stage::Binary, Stage::Xml, Stage::Output];
+
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Read => "read",
+            Stage::Binary => "binary",
+            Stage::Xml => "xml",
+            Stage::Output => "output",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Tally {
+    count: u32,
+    total: Duration,
+    max: Duration,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TRACE: AtomicBool = AtomicBool::new(false);
+static TALLIES: Mutex<[Tally; 4]> = Mutex::new(
+    [Tally {
+        count: 0,
+        total: Duration::ZERO,
+        max: Duration::ZERO,
+    }; 4],
+);
+
+/// Starts measuring; `trace` adds a line per file.
+pub fn enable(trace: bool) {
+    ENABLED.store(true, Ordering::Relaxed);
+    TRACE.store(trace, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Runs `f`, adding the time it took to `stage` when measuring. Also
+/// returns that time, which is zero when not measuring.
+pub fn time<T>(stage: Stage, f: impl FnOnce() -> T) -> (T, Duration) {
+    if !enabled() {
+        return (f(), Duration::ZERO);
+    }
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let mut tallies = TALLIES.lock().unwrap_or_else(|e| e.into_inner());
+    let tally = &mut tallies[stage as usize];
+    tally.count += 1;
+    tally.total += elapsed;
+    tally.max = tally.max.max(elapsed);
+    (result, elapsed)
+}
+
+/// Prints the line for a loaded file when tracing: its size, the format it
+/// was parsed as and how long each stage took.
+pub fn trace(path: &Path, size: usize, stage: Stage, read: Duration, parse: Duration) {
+    if TRACE.load(Ordering::Relaxed) {
+        eprintln!(
+            "{}: {} bytes, {}, read {}, parse {}",
+            path.display(),
+            size,
+            stage.name(),
+            millis(read),
+            millis(parse)
+        );
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Prints the totals when measuring: one row per stage that ran, with the
+/// number of times it ran and the total, average and longest time.
+pub fn report() {
+    if !enabled() {
+        return;
+    }
+    let tallies = *TALLIES.lock().unwrap_or_else(|e| e.into_inner());
+    let rows: Vec<[String; 5]> = Stage::ALL
+        .iter()
+        .map(|&stage| (stage, tallies[stage as usize]))
+        .filter(|(_, tally)| tally.count > 0)
+        .map(|(stage, tally)| {
+            [
+                stage.name().to_string(),
+                tally.count.to_string(),
+                millis(tally.total),
+                millis(tally.total / tally.count),
+                millis(tally.max),
+            ]
+        })
+        .collect();
+    let header = ["Stage", "Count", "Total", "Average", "Max"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let line = |cells: [&str; 5]| {
+        let mut line = format!("{:<width$}", cells[0], width = widths[0]);
+        for (cell, width) in cells.iter().zip(widths).skip(1) {
+            line.push_str(&format!("  {:>width$}", cell, width = width));
+        }
+        line
+    };
+    eprintln!("Timings:");
+    eprintln!("{}", line(header));
+    for row in &rows {
+        eprintln!("{}", line(row.each_ref().map(String::as_str)));
+    }
+}
+
+/// How many files the list of the slowest holds.
+const SLOWEST: usize = 10;
+
+/// The time each file took, for `--slow-threshold` and the list of the
+/// slowest files at the end: reading and parsing it, timed on the thread
+/// that did so, plus building and writing its records. Time spent waiting
+/// for a worker thread or for the writer is not counted.
+#[derive(Debug, Default)]
+pub struct SlowFiles {
+    /// Files that took longer are named on stderr as they are written.
+    threshold: Option<Duration>,
+    /// Print the slowest files at the end.
+    report: bool,
+    /// The slowest files so far, slowest first.
+    slowest: Vec<(Duration, PathBuf)>,
+}
+
+impl SlowFiles {
+    /// The slowest files are listed at the end with `--timings` or a
+    /// `threshold`.
+    pub fn new(threshold: Option<Duration>, timings: bool) -> SlowFiles {
+        SlowFiles {
+            threshold,
+            report: timings || threshold.is_some(),
+            slowest: Vec::new(),
+        }
+    }
+
+    /// Notes that `path` took `elapsed`.
+    pub fn add(&mut self, path: &Path, elapsed: Duration) {
+        if let Some(threshold) = self.threshold.filter(|&threshold| elapsed > threshold) {
+            eprintln!(
+                "Slow: {} took {}, over the threshold of {}",
+                path.display(),
+                millis(elapsed),
+                millis(threshold)
+            );
+        }
+        let at = self.slowest.partition_point(|(slower, _)| *slower >= elapsed);
+        if at < SLOWEST {
+            self.slowest.insert(at, (elapsed, path.to_path_buf()));
+            self.slowest.truncate(SLOWEST);
+        }
+    }
+
+    /// Prints the slowest files, if asked to.
+    pub fn report(&self) {
+        if !self.report || self.slowest.is_empty() {
+            return;
+        }
+        eprintln!("Slowest files:");
+        let times: Vec<String> = self.slowest.iter().map(|(time, _)| millis(*time)).collect();
+        let width = times.iter().map(String::len).max().unwrap_or_default();
+        for (time, (_, path)) in times.iter().zip(&self.slowest) {
+            eprintln!("  {:>width$}  {}", time, path.display(), width = width);
+        }
+    }
+}
+
+/// `duration` in milliseconds to the microsecond, for JSON records.
+pub fn millis_value(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
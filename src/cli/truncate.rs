@@ -0,0 +1,68 @@
+//! `--max-string-length`: values from the file cut short before they are
+//! printed, so that a comment of a few megabytes does not swamp a table.
+//! What is cut is replaced by a marker with the length and SHA-256 of the
+//! whole value, so the value can still be told apart and looked up.
+
+use jobfileparser::task::{Principal, RegistrationInfo};
+use jobfileparser::{DecodedArtifact, Finding, ParsedArtifact};
+use sha2::{Digest, Sha256};
+
+/// The `--max-string-length` unless one is given.
+pub const DEFAULT_MAX_STRING_LENGTH: usize = 4096;
+
+/// Cuts `value` to its first `max` characters and the marker, if it is
+/// longer. Returns whether it was.
+pub fn truncate(value: &mut String, max: usize) -> bool {
+    let Some((end, _)) = value.char_indices().nth(max) else {
+        return false;
+    };
+    let total = value.chars().count();
+    let hash: String =
+        Sha256::digest(value.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+    value.truncate(end);
+    value.push_str(&format!("...[truncated, {} chars total, sha256={}]", total, hash));
+    true
+}
+
+/// Truncates every string of `artifact` read from the file, the payloads
+/// `decoded` from them and the descriptions of `findings`, which may quote
+/// them. Returns how many were truncated.
+pub fn truncate_record(
+    artifact: &mut ParsedArtifact,
+    findings: &mut [Finding],
+    decoded: &mut [DecodedArtifact],
+    max: usize,
+) -> usize {
+    let mut values: Vec<&mut String> = Vec::new();
+    match artifact {
+        ParsedArtifact::Job(job) => values.extend([
+            &mut job.name,
+            &mut job.parameters,
+            &mut job.working_directory,
+            &mut job.user,
+            &mut job.comment,
+        ]),
+        ParsedArtifact::Task(task) => {
+            let RegistrationInfo { author, date, description, security_descriptor } =
+                &mut task.registration_info;
+            values.extend([author, date, description, security_descriptor].into_iter().flatten());
+            if let Some(trigger) = &mut task.triggers.calendar_trigger {
+                values.push(&mut trigger.start_boundary);
+                values.extend(&mut trigger.end_boundary);
+            }
+            if let Some(exec) = &mut task.actions.exec {
+                values.push(&mut exec.command);
+                let optional = [&mut exec.arguments, &mut exec.working_directory];
+                values.extend(optional.into_iter().flatten());
+            }
+            if let Some(principal) = task.principals.as_mut().and_then(|p| p.principal.as_mut()) {
+                let Principal { user_id, group_id, run_level } = principal;
+                values.extend([user_id, group_id, run_level].into_iter().flatten());
+            }
+            values.extend(task.unknown_elements.values_mut());
+        }
+    }
+    values.extend(decoded.iter_mut().map(|payload| &mut payload.decoded));
+    values.extend(findings.iter_mut().map(|finding| &mut finding.description));
+    values.into_iter().map(|value| truncate(value, max) as usize).sum()
+}
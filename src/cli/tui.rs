@@ -0,0 +1,421 @@
+//! Interactive browser over records that have already been parsed.
+//!
+//! Keys: arrows or `j`/`k` move, `PgUp`/`PgDn`/`g`/`G` jump, `/` searches
+//! every field incrementally, `h` toggles hidden tasks only, `e` exports the
+//! records currently listed as JSON, `q` quits.
+
+use std::fs;
+use std::io::{self, Stdout};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use jobfileparser::fold::fold;
+use jobfileparser::{ParsedArtifact, Record};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{Frame, Terminal};
+
+use super::output::finding_line;
+use super::terminal::Capabilities;
+
+const DEFAULT_EXPORT: &str = "jobfileparser-export.json";
+
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Browse,
+    Search,
+    Export(String),
+}
+
+/// One line of the list, precomputed from a record.
+struct Entry {
+    record: Record,
+    columns: [String; 5],
+    hidden: bool,
+    /// The record as lowercased JSON, searched by `/`.
+    haystack: String,
+}
+
+impl Entry {
+    fn new(record: Record) -> Entry {
+        let haystack = fold(&serde_json::to_string(&record).unwrap_or_default()).into_owned();
+        let (columns, hidden) = match &record {
+            Record::Ok {
+                path,
+                artifact,
+                findings,
+                ..
+            } => (
+                [
+                    file_name(path),
+                    artifact.author().unwrap_or("-").to_string(),
+                    artifact.command_line().unwrap_or_default(),
+                    last_run(artifact),
+                    findings
+                        .as_ref()
+                        .map_or("-".to_string(), |f| f.len().to_string()),
+                ],
+                artifact.is_hidden(),
+            ),
+            Record::Error { path, detail, .. } => (
+                [
+                    file_name(path),
+                    "-".to_string(),
+                    format!("error: {}", detail),
+                    "-".to_string(),
+                    "-".to_string(),
+                ],
+                false,
+            ),
+        };
+        Entry {
+            record,
+            columns,
+            hidden,
+            haystack,
+        }
+    }
+}
+
+fn file_name(path: &str) -> String {
+    path.rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+}
+
+fn last_run(artifact: &ParsedArtifact) -> String {
+    match artifact {
+        ParsedArtifact::Job(job) if job.run_date.year == 0 => "never".to_string(),
+        ParsedArtifact::Job(job) => job.run_date.format_iso(),
+        ParsedArtifact::Task(_) => "-".to_string(),
+    }
+}
+
+pub struct App {
+    entries: Vec<Entry>,
+    /// Indices into `entries` that pass the search and hidden filters.
+    visible: Vec<usize>,
+    table: TableState,
+    query: String,
+    hidden_only: bool,
+    mode: Mode,
+    status: String,
+    terminal: Capabilities,
+}
+
+impl App {
+    pub fn new(records: Vec<Record>, terminal: Capabilities) -> App {
+        let mut app = App {
+            entries: records.into_iter().map(Entry::new).collect(),
+            visible: Vec::new(),
+            table: TableState::default(),
+            query: String::new(),
+            hidden_only: false,
+            mode: Mode::Browse,
+            status: String::new(),
+            terminal,
+        };
+        app.refilter();
+        app
+    }
+
+    fn refilter(&mut self) {
+        let query = fold(&self.query).into_owned();
+        self.visible = (0..self.entries.len())
+            .filter(|&i| {
+                let entry = &self.entries[i];
+                (!self.hidden_only || entry.hidden) && entry.haystack.contains(&query)
+            })
+            .collect();
+        let selected = self.table.selected().unwrap_or(0);
+        self.table.select(if self.visible.is_empty() {
+            None
+        } else {
+            Some(selected.min(self.visible.len() - 1))
+        });
+    }
+
+    fn selected(&self) -> Option<&Entry> {
+        self.table
+            .selected()
+            .map(|i| &self.entries[self.visible[i]])
+    }
+
+    fn step(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let last = self.visible.len() as isize - 1;
+        let current = self.table.selected().unwrap_or(0) as isize;
+        self.table
+            .select(Some((current + delta).clamp(0, last) as usize));
+    }
+
+    /// Writes the records currently listed to `path` as a JSON array.
+    fn export(&self, path: &str) -> io::Result<usize> {
+        let records: Vec<&Record> = self
+            .visible
+            .iter()
+            .map(|&i| &self.entries[i].record)
+            .collect();
+        fs::write(path, serde_json::to_string_pretty(&records)?)?;
+        Ok(records.len())
+    }
+
+    /// Applies a key press; returns false when the browser should close.
+    fn handle(&mut self, key: KeyCode) -> bool {
+        match &mut self.mode {
+            Mode::Browse => match key {
+                KeyCode::Char('q') | KeyCode::Esc => return false,
+                KeyCode::Down | KeyCode::Char('j') => self.step(1),
+                KeyCode::Up | KeyCode::Char('k') => self.step(-1),
+                KeyCode::PageDown => self.step(20),
+                KeyCode::PageUp => self.step(-20),
+                KeyCode::Home | KeyCode::Char('g') => self.step(isize::MIN / 2),
+                KeyCode::End | KeyCode::Char('G') => self.step(isize::MAX / 2),
+                KeyCode::Char('/') => self.mode = Mode::Search,
+                KeyCode::Char('h') => {
+                    self.hidden_only = !self.hidden_only;
+                    self.refilter();
+                }
+                KeyCode::Char('e') => self.mode = Mode::Export(DEFAULT_EXPORT.to_string()),
+                _ => {}
+            },
+            Mode::Search => {
+                match key {
+                    KeyCode::Enter => self.mode = Mode::Browse,
+                    KeyCode::Esc => {
+                        self.query.clear();
+                        self.mode = Mode::Browse;
+                    }
+                    KeyCode::Backspace => {
+                        self.query.pop();
+                    }
+                    KeyCode::Char(c) => self.query.push(c),
+                    _ => return true,
+                }
+                self.refilter();
+            }
+            Mode::Export(path) => match key {
+                KeyCode::Enter => {
+                    let path = path.clone();
+                    self.status = match self.export(&path) {
+                        Ok(count) => format!("exported {} records to {}", count, path),
+                        Err(e) => format!("export failed: {}", e),
+                    };
+                    self.mode = Mode::Browse;
+                }
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    path.pop();
+                }
+                KeyCode::Char(c) => path.push(c),
+                _ => {}
+            },
+        }
+        true
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [list, detail] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(main);
+
+        let rows = self
+            .visible
+            .iter()
+            .map(|&i| Row::new(self.entries[i].columns.iter().map(String::as_str)));
+        let widths = [
+            Constraint::Percentage(20),
+            Constraint::Percentage(15),
+            Constraint::Percentage(40),
+            Constraint::Length(19),
+            Constraint::Length(5),
+        ];
+        let title = format!(
+            "{} of {} records{}",
+            self.visible.len(),
+            self.entries.len(),
+            if self.hidden_only {
+                " (hidden only)"
+            } else {
+                ""
+            }
+        );
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(["Name", "User", "Command", "Last run", "Score"])
+                    .style(Style::new().add_modifier(Modifier::BOLD)),
+            )
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .block(Block::bordered().border_set(self.terminal.border_set()).title(title));
+        frame.render_stateful_widget(table, list, &mut self.table);
+
+        let text = match self.selected().map(|e| &e.record) {
+            Some(Record::Ok {
+                artifact, findings, ..
+            }) => {
+                let mut text = artifact.format();
+                for finding in findings.iter().flatten() {
+                    text.push_str(&format!("{}\n", finding_line(finding)));
+                }
+                text
+            }
+            Some(Record::Error { detail, .. }) => detail.clone(),
+            None => String::new(),
+        };
+        let title = self
+            .selected()
+            .map_or(String::new(), |e| e.columns[0].clone());
+        // Wrapped as the text format is, inside the borders; ratatui only
+        // wraps what is still too wide.
+        let wrapping = Capabilities {
+            color: false,
+            width: Some(usize::from(detail.width.saturating_sub(2))),
+            ..self.terminal
+        };
+        let finding = Style::new().fg(Color::Red);
+        let lines: Vec<Line> = wrapping
+            .render(&text)
+            .lines()
+            .map(|line| {
+                if self.terminal.color && line.starts_with("Finding [") {
+                    Line::styled(line.to_string(), finding)
+                } else {
+                    Line::from(line.to_string())
+                }
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().border_set(self.terminal.border_set()).title(title)),
+            detail,
+        );
+
+        let line = match &self.mode {
+            Mode::Search => format!("/{}", self.query),
+            Mode::Export(path) => format!("export to: {}", path),
+            Mode::Browse if !self.status.is_empty() => self.status.clone(),
+            Mode::Browse => "q quit  / search  h hidden only  e export".to_string(),
+        };
+        frame.render_widget(Line::from(line), status);
+    }
+}
+
+/// Restores the terminal when dropped, including on early return.
+struct TerminalGuard(Terminal<CrosstermBackend<Stdout>>);
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.0.backend_mut(), LeaveAlternateScreen);
+        let _ = self.0.show_cursor();
+    }
+}
+
+/// Runs the browser until the user quits.
+pub fn run(records: Vec<Record>, terminal: Capabilities) -> io::Result<()> {
+    let mut app = App::new(records, terminal);
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = TerminalGuard(Terminal::new(CrosstermBackend::new(stdout))?);
+    loop {
+        terminal.0.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !app.handle(key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jobfileparser::JobBuilder;
+    use std::path::Path;
+
+    fn record(file: &str, application: &str, flags: u32) -> Record {
+        let job = JobBuilder::new(application)
+            .author("EXAMPLE\\ops")
+            .flags(flags)
+            .build().unwrap();
+        let mut record = Record::new(Path::new(file), Ok(ParsedArtifact::Job(job)));
+        record.detect();
+        record
+    }
+
+    fn app() -> App {
+        let terminal = Capabilities { color: false, width: None, unicode: true };
+        let records = vec![
+            record("a.job", "C:\\Tools\\backup.exe", 0),
+            record("b.job", "cmd.exe", 0x200),
+            record("c.job", "C:\\Tools\\sync.exe", 0),
+        ];
+        App::new(records, terminal)
+    }
+
+    fn visible_names(app: &App) -> Vec<&str> {
+        app.visible
+            .iter()
+            .map(|&i| app.entries[i].columns[0].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_columns() {
+        let app = app();
+        assert_eq!(
+            app.entries[1].columns,
+            ["b.job", "EXAMPLE\\ops", "cmd.exe", "never", "2"]
+        );
+    }
+
+    #[test]
+    fn test_incremental_search() {
+        let mut app = app();
+        app.handle(KeyCode::Char('/'));
+        for c in "tools".chars() {
+            app.handle(KeyCode::Char(c));
+        }
+        assert_eq!(visible_names(&app), ["a.job", "c.job"]);
+        app.handle(KeyCode::Backspace);
+        app.handle(KeyCode::Esc);
+        assert_eq!(app.visible.len(), 3);
+        assert_eq!(app.mode, Mode::Browse);
+    }
+
+    #[test]
+    fn test_hidden_only_and_selection() {
+        let mut app = app();
+        app.handle(KeyCode::End);
+        assert_eq!(app.table.selected(), Some(2));
+        app.handle(KeyCode::Char('h'));
+        assert_eq!(visible_names(&app), ["b.job"]);
+        assert_eq!(app.table.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_export_writes_visible_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.json");
+        let mut app = app();
+        app.handle(KeyCode::Char('h'));
+        app.mode = Mode::Export(path.to_str().unwrap().to_string());
+        app.handle(KeyCode::Enter);
+
+        let exported: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(exported.as_array().unwrap().len(), 1);
+        assert_eq!(exported[0]["path"], "b.job");
+        assert!(app.status.starts_with("exported 1 records"));
+    }
+}
@@ -0,0 +1,176 @@
+//! `scan --watch`: an initial scan followed by re-parsing files as they are
+//! created, modified or removed, until Ctrl-C.
+
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use jobfileparser::{list_dir, Error, ScanOptions};
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecursiveMode, Watcher};
+
+use super::commands::load;
+use super::interrupt;
+use super::output::Output;
+
+/// How long a path must stay quiet before its pending event is reported, so
+/// that a burst of writes to one file yields a single record.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+const POLL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Change {
+    Created,
+    Modified,
+    Removed,
+}
+
+impl Change {
+    fn name(self) -> &'static str {
+        match self {
+            Change::Created => "created",
+            Change::Modified => "modified",
+            Change::Removed => "removed",
+        }
+    }
+
+    fn from_kind(kind: &EventKind) -> Option<Change> {
+        match kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                Some(Change::Created)
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                Some(Change::Removed)
+            }
+            EventKind::Modify(ModifyKind::Metadata(_)) => None,
+            EventKind::Modify(_) => Some(Change::Modified),
+            _ => None,
+        }
+    }
+
+    /// Folds a new change into one still waiting out the debounce period.
+    fn merge(self, next: Change) -> Change {
+        match (self, next) {
+            (_, Change::Removed) => Change::Removed,
+            (Change::Created, _) => Change::Created,
+            (Change::Removed, Change::Created) => Change::Modified,
+            (_, next) => next,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    created: usize,
+    modified: usize,
+    removed: usize,
+}
+
+/// Runs until Ctrl-C, then prints a summary to stderr. Returns false if the
+/// root directory could not be read or watched.
+pub fn watch(dir: &Path, options: &ScanOptions, output: &mut Output) -> bool {
+    // Start watching before the initial scan so nothing written during it
+    // is missed.
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("Unable to watch {}: {}", dir.display(), e);
+            return false;
+        }
+    };
+    let mode = if options.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    if let Err(e) = watcher.watch(dir, mode) {
+        eprintln!("Unable to watch {}: {}", dir.display(), e);
+        return false;
+    }
+
+    let initial = list_dir(dir, options, |path, result| {
+        let loaded = result.and_then(|()| load(path, options));
+        output.write_loaded(path, loaded, Some("initial"));
+        ControlFlow::Continue(())
+    });
+    if let Err(e) = initial {
+        eprintln!("Unable to read directory {}: {}", dir.display(), e);
+        return false;
+    }
+
+    let mut pending: HashMap<PathBuf, (Change, Instant)> = HashMap::new();
+    let mut summary = Summary::default();
+    while !interrupt::interrupted() {
+        match rx.recv_timeout(POLL) {
+            Ok(Ok(event)) => {
+                let Some(change) = Change::from_kind(&event.kind) else {
+                    continue;
+                };
+                for path in event.paths {
+                    if !options.wants(&path) {
+                        continue;
+                    }
+                    let merged = match pending.get(&path) {
+                        Some((previous, _)) => previous.merge(change),
+                        None => change,
+                    };
+                    pending.insert(path, (merged, Instant::now()));
+                }
+            }
+            Ok(Err(e)) => eprintln!("Watch error: {}", e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        let mut ready: Vec<(PathBuf, Change)> = pending
+            .iter()
+            .filter(|(_, (_, at))| now.duration_since(*at) >= DEBOUNCE)
+            .map(|(path, (change, _))| (path.clone(), *change))
+            .collect();
+        ready.sort_by(|a, b| a.0.cmp(&b.0));
+        for (path, change) in ready {
+            pending.remove(&path);
+            report(&path, change, options, output, &mut summary);
+        }
+    }
+
+    eprintln!(
+        "Watch stopped: {} created, {} modified, {} removed",
+        summary.created, summary.modified, summary.removed
+    );
+    true
+}
+
+fn report(
+    path: &Path,
+    change: Change,
+    options: &ScanOptions,
+    output: &mut Output,
+    summary: &mut Summary,
+) {
+    let loaded = match change {
+        Change::Removed => None,
+        _ => match load(path, options) {
+            // Gone again before it could be read: report the removal.
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                return report(path, Change::Removed, options, output, summary);
+            }
+            loaded => Some(loaded),
+        },
+    };
+    match change {
+        Change::Created => summary.created += 1,
+        Change::Modified => summary.modified += 1,
+        Change::Removed => summary.removed += 1,
+    }
+    match loaded {
+        Some(loaded) => {
+            output.write_loaded(path, loaded, Some(change.name()));
+        }
+        None => output.write_event(path, None, None, Some(change.name())),
+    }
+}
@@ -0,0 +1,443 @@
+//! Windows command lines: splitting them into arguments the way programs
+//! see them, and looking through interpreters such as `cmd /c` to the
+//! command they were started to run.
+
+/// Unwrapping gives up after this many nested interpreters.
+const MAX_DEPTH: usize = 8;
+
+/// Extensions that end the program in an unquoted command line, which
+/// Windows otherwise reads up to the first space.
+const PROGRAM_EXTENSIONS: &[&str] = &[".exe", ".com", ".bat", ".cmd"];
+
+/// PowerShell parameters that take a value, which is not the command.
+const POWERSHELL_VALUE_PARAMETERS: &[&str] = &[
+    "configurationname",
+    "custompipename",
+    "executionpolicy",
+    "inputformat",
+    "outputformat",
+    "psconsolefile",
+    "settingsfile",
+    "version",
+    "windowstyle",
+    "workingdirectory",
+];
+
+/// Short forms of `POWERSHELL_VALUE_PARAMETERS` too short to go by prefix.
+const POWERSHELL_VALUE_ALIASES: &[&str] = &["ep", "ex", "if", "of", "v", "w", "wd"];
+
+/// A command line split into the program and its arguments, with the
+/// interpreters it goes through looked past.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandLine {
+    /// The program, without quotes.
+    pub executable: String,
+    pub arguments: Vec<String>,
+    /// The interpreters between the task and what it really runs, outermost
+    /// first, by lowercase file name: `cmd.exe` for `cmd /c`,
+    /// `powershell.exe` for `powershell -Command` or `-File`, `wscript.exe`
+    /// or `cscript.exe` for a script, and `explorer.exe` for what it opens.
+    pub wrappers: Vec<String>,
+    /// What the innermost wrapper runs: the command after `cmd /c`, the
+    /// PowerShell script text (decoded for `-EncodedCommand`), or a script
+    /// and its arguments. `None` without wrappers.
+    pub effective_command: Option<String>,
+    /// The program or script `effective_command` starts; `None` when it is
+    /// PowerShell script text.
+    pub effective_executable: Option<String>,
+}
+
+/// What an interpreter was asked to run.
+enum Payload {
+    /// A command line of its own, which may wrap another.
+    Command(String),
+    /// PowerShell script text.
+    Script(String),
+}
+
+struct Token {
+    value: String,
+    /// Byte offsets of the token in the text it was read from.
+    start: usize,
+    end: usize,
+    quoted: bool,
+}
+
+impl CommandLine {
+    /// Splits a whole command line, program included. Like `CreateProcess`,
+    /// an unquoted program path with spaces runs up to the first word ending
+    /// in `.exe`, `.com`, `.bat` or `.cmd`. `None` for a blank line.
+    pub fn parse(line: &str) -> Option<CommandLine> {
+        CommandLine::parse_nested(line, 0)
+    }
+
+    /// Builds the command line from a program and its arguments stored
+    /// apart, as jobs and task `Exec` actions do. Quotes around the program
+    /// are dropped.
+    pub fn from_parts(program: &str, arguments: &str) -> CommandLine {
+        let executable = program.trim().trim_matches('"').to_string();
+        CommandLine::build(executable, arguments, 0)
+    }
+
+    /// The command line rebuilt with canonical quoting, so spellings that
+    /// run the same thing compare equal.
+    pub fn normalized(&self) -> String {
+        // The program is read up to the next quote, without escapes.
+        let mut line = if self.executable.is_empty() || self.executable.contains([' ', '\t']) {
+            format!("\"{}\"", self.executable)
+        } else {
+            self.executable.clone()
+        };
+        for argument in &self.arguments {
+            line.push(' ');
+            line.push_str(&quote(argument));
+        }
+        line
+    }
+
+    fn parse_nested(line: &str, depth: usize) -> Option<CommandLine> {
+        let tokens = tokenize(line, true);
+        let first = tokens.first()?;
+        let (executable, end) = if first.quoted || has_program_extension(&first.value) {
+            (first.value.clone(), first.end)
+        } else {
+            spaced_program(line, first)
+        };
+        Some(CommandLine::build(executable, &line[end..], depth))
+    }
+
+    fn build(executable: String, arguments: &str, depth: usize) -> CommandLine {
+        let tokens = tokenize(arguments, false);
+        let mut command = CommandLine {
+            executable,
+            arguments: tokens.iter().map(|t| t.value.clone()).collect(),
+            wrappers: Vec::new(),
+            effective_command: None,
+            effective_executable: None,
+        };
+        if depth >= MAX_DEPTH {
+            return command;
+        }
+        let wrapper = file_name(&command.executable);
+        match unwrap(&wrapper, arguments, &tokens) {
+            Some(Payload::Command(text)) => {
+                if let Some(inner) = CommandLine::parse_nested(&text, depth + 1) {
+                    command.wrappers.push(wrapper);
+                    if inner.wrappers.is_empty() {
+                        command.effective_command = Some(text);
+                        command.effective_executable = Some(inner.executable);
+                    } else {
+                        command.wrappers.extend(inner.wrappers);
+                        command.effective_command = inner.effective_command;
+                        command.effective_executable = inner.effective_executable;
+                    }
+                }
+            }
+            Some(Payload::Script(text)) => {
+                command.wrappers.push(wrapper);
+                command.effective_command = Some(text);
+            }
+            None => {}
+        }
+        command
+    }
+}
+
+/// Splits `line` into arguments by the rules of `CommandLineToArgvW` and the
+/// Microsoft C runtime. The first argument is the program, which ends at the
+/// first space outside quotes and takes backslashes literally.
+pub fn split(line: &str) -> Vec<String> {
+    tokenize(line, true).into_iter().map(|t| t.value).collect()
+}
+
+fn tokenize(text: &str, program_first: bool) -> Vec<Token> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let is_space = |c: char| c == ' ' || c == '\t';
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    loop {
+        while i < chars.len() && is_space(chars[i].1) {
+            i += 1;
+        }
+        let Some(&(start, _)) = chars.get(i) else {
+            break;
+        };
+        let program = program_first && tokens.is_empty();
+        let mut value = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        while let Some(&(_, c)) = chars.get(i) {
+            if c == '\\' && !program {
+                // Backslashes only escape a quote that follows them.
+                let run = chars[i..].iter().take_while(|&&(_, c)| c == '\\').count();
+                i += run;
+                if chars.get(i).is_some_and(|&(_, c)| c == '"') {
+                    value.extend(std::iter::repeat_n('\\', run / 2));
+                    if run % 2 == 1 {
+                        value.push('"');
+                        i += 1;
+                    }
+                } else {
+                    value.extend(std::iter::repeat_n('\\', run));
+                }
+                continue;
+            }
+            if c == '"' {
+                quoted = true;
+                // `""` inside quotes is a literal quote.
+                if in_quotes && !program && chars.get(i + 1).is_some_and(|&(_, c)| c == '"') {
+                    value.push('"');
+                    i += 2;
+                    continue;
+                }
+                in_quotes = !in_quotes;
+            } else if is_space(c) && !in_quotes {
+                break;
+            } else {
+                value.push(c);
+            }
+            i += 1;
+        }
+        let end = chars.get(i).map_or(text.len(), |&(offset, _)| offset);
+        tokens.push(Token {
+            value,
+            start,
+            end,
+            quoted,
+        });
+    }
+    tokens
+}
+
+/// The program of an unquoted command line whose first word, `first`, has
+/// no program extension: the shortest run of words from it that ends in
+/// one, else `first` alone. Returns the program and where it ends.
+fn spaced_program(line: &str, first: &Token) -> (String, usize) {
+    let rest = &line[first.start..];
+    let word_ends = rest
+        .char_indices()
+        .filter(|&(_, c)| c == ' ' || c == '\t')
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(rest.len()));
+    for end in word_ends {
+        let candidate = &rest[..end];
+        if candidate.contains('"') {
+            break;
+        }
+        if has_program_extension(candidate) {
+            return (candidate.to_string(), first.start + end);
+        }
+    }
+    (first.value.clone(), first.end)
+}
+
+fn has_program_extension(path: &str) -> bool {
+    let path = path.to_ascii_lowercase();
+    PROGRAM_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
+
+/// The lowercase file name of a program path.
+pub(crate) fn file_name(path: &str) -> String {
+    let path = path.trim();
+    path.rsplit(['\\', '/']).next().unwrap_or(path).to_lowercase()
+}
+
+/// What `program` (a lowercase file name) runs when given `arguments`, if it
+/// is an interpreter this module looks through.
+fn unwrap(program: &str, arguments: &str, tokens: &[Token]) -> Option<Payload> {
+    let stem = program.strip_suffix(".exe").unwrap_or(program);
+    let payload = match stem {
+        "cmd" => cmd_payload(arguments, tokens),
+        "powershell" => powershell_payload(arguments, tokens, false),
+        "pwsh" => powershell_payload(arguments, tokens, true),
+        "wscript" | "cscript" => tokens
+            .iter()
+            .find(|t| !t.value.starts_with("//"))
+            .map(|t| Payload::Command(arguments[t.start..].trim_end().to_string())),
+        "explorer" => tokens
+            .first()
+            .map(|t| Payload::Command(arguments[t.start..].trim_end().to_string())),
+        _ => None,
+    };
+    payload.filter(|payload| match payload {
+        Payload::Command(text) | Payload::Script(text) => !text.trim().is_empty(),
+    })
+}
+
+/// The command after `cmd /c`, `/k` or `/r`, with its quotes treated as
+/// `cmd /?` describes: kept when they are the only two and enclose a program
+/// path with spaces and no special characters, otherwise the first and the
+/// last are removed.
+fn cmd_payload(arguments: &str, tokens: &[Token]) -> Option<Payload> {
+    let mut strip_quotes = false;
+    for token in tokens {
+        let switch = token.value.to_ascii_lowercase();
+        if !switch.starts_with('/') {
+            return None;
+        }
+        if switch == "/s" {
+            strip_quotes = true;
+        }
+        if !matches!(switch.as_str(), "/c" | "/k" | "/r") {
+            continue;
+        }
+        let rest = arguments[token.end..].trim();
+        let Some(inner) = rest.strip_prefix('"') else {
+            return Some(Payload::Command(rest.to_string()));
+        };
+        let keep = !strip_quotes
+            && rest.matches('"').count() == 2
+            && rest.ends_with('"')
+            && inner.contains(' ')
+            && has_program_extension(&inner[..inner.len() - 1])
+            && !inner.contains(['&', '<', '>', '(', ')', '@', '^', '|']);
+        if keep {
+            return Some(Payload::Command(rest.to_string()));
+        }
+        let text = match inner.rfind('"') {
+            Some(last) => format!("{}{}", &inner[..last], &inner[last + 1..]),
+            None => inner.to_string(),
+        };
+        return Some(Payload::Command(text));
+    }
+    None
+}
+
+/// What `powershell` or `pwsh` runs: `-Command` text, a decoded
+/// `-EncodedCommand`, or a `-File` script. The first positional argument is
+/// a command for Windows PowerShell and a script file for `pwsh`.
+fn powershell_payload(arguments: &str, tokens: &[Token], pwsh: bool) -> Option<Payload> {
+    let mut iter = tokens.iter().enumerate();
+    while let Some((i, token)) = iter.next() {
+        let Some(name) = token.value.strip_prefix(['-', '/']) else {
+            let rest = arguments[token.start..].trim_end().to_string();
+            return Some(if pwsh { Payload::Command(rest) } else { Payload::Script(rest) });
+        };
+        let name = name.to_ascii_lowercase();
+        let abbreviates = |full: &str, min: usize| name.len() >= min && full.starts_with(&name);
+        if name == "c" || abbreviates("command", 3) {
+            // A command given as one quoted argument loses its quotes.
+            let text = match &tokens[i + 1..] {
+                [only] => only.value.clone(),
+                _ => arguments[token.end..].trim().to_string(),
+            };
+            return Some(Payload::Script(text));
+        }
+        if name == "e" || name == "ec" || (name.starts_with("en") && abbreviates("encodedcommand", 2))
+        {
+            let blob = tokens.get(i + 1)?.value.as_str();
+            let text = decode_encoded_command(blob).unwrap_or_else(|| blob.to_string());
+            return Some(Payload::Script(text));
+        }
+        if name == "f" || abbreviates("file", 2) {
+            let script = tokens.get(i + 1)?;
+            return Some(Payload::Command(arguments[script.start..].trim_end().to_string()));
+        }
+        let takes_value = POWERSHELL_VALUE_ALIASES.contains(&name.as_str())
+            || POWERSHELL_VALUE_PARAMETERS.iter().any(|full| abbreviates(full, 3));
+        if takes_value {
+            iter.next();
+        }
+    }
+    None
+}
+
+/// Decodes a PowerShell `-EncodedCommand`: base64 of UTF-16LE text.
+fn decode_encoded_command(blob: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in blob.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    String::from_utf16(&units).ok()
+}
+
+/// Quotes `argument` so `split` reads it back unchanged.
+fn quote(argument: &str) -> String {
+    if !argument.is_empty() && !argument.contains([' ', '\t', '"']) {
+        return argument.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in argument.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // Backslashes before a quote are doubled, and the quote escaped.
+            '"' => quoted.extend(std::iter::repeat_n('\\', backslashes + 1)),
+            _ => backslashes = 0,
+        }
+        if c == '"' {
+            backslashes = 0;
+        }
+        quoted.push(c);
+    }
+    // So are those before the closing quote.
+    quoted.extend(std::iter::repeat_n('\\', backslashes));
+    quoted.push('"');
+    quoted
+}
+
+/// Where the environment variables common in task paths point on a default
+/// Windows install. Per-user folders keep `%USERNAME%`, which stands for
+/// whichever account runs the task.
+const ENVIRONMENT: &[(&str, &str)] = &[
+    ("allusersprofile", "C:\\ProgramData"),
+    ("appdata", "C:\\Users\\%USERNAME%\\AppData\\Roaming"),
+    ("commonprogramfiles", "C:\\Program Files\\Common Files"),
+    ("commonprogramfiles(x86)", "C:\\Program Files (x86)\\Common Files"),
+    ("homedrive", "C:"),
+    ("localappdata", "C:\\Users\\%USERNAME%\\AppData\\Local"),
+    ("programdata", "C:\\ProgramData"),
+    ("programfiles", "C:\\Program Files"),
+    ("programfiles(x86)", "C:\\Program Files (x86)"),
+    ("public", "C:\\Users\\Public"),
+    ("systemdrive", "C:"),
+    ("systemroot", "C:\\Windows"),
+    ("temp", "C:\\Users\\%USERNAME%\\AppData\\Local\\Temp"),
+    ("tmp", "C:\\Users\\%USERNAME%\\AppData\\Local\\Temp"),
+    ("userprofile", "C:\\Users\\%USERNAME%"),
+    ("windir", "C:\\Windows"),
+];
+
+/// `path` with the environment variables in `ENVIRONMENT` expanded, without
+/// quotes, and with `/` as `\`. Other variables are left as they are, and the
+/// host's own environment is never consulted.
+pub fn normalize_path(path: &str) -> String {
+    let path = path.trim().trim_matches('"').replace('/', "\\");
+    let mut out = String::with_capacity(path.len());
+    let mut rest = path.as_str();
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = after[..end].to_ascii_lowercase();
+        match ENVIRONMENT.iter().find(|(var, _)| *var == name) {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,136 @@
+//! The layout of the binary `.job` format and the values of its coded
+//! fields, as described in [MS-TSCH] section 2.4 and read by `Job::parse`.
+//! Offsets are in bytes from the start of the file, or of the structure
+//! their module names; every integer is little-endian.
+//!
+//! The status codes, flag bits, priority classes and product versions are
+//! stable. The offset tables, `Field` and the `fixed`, `variable`,
+//! `systemtime`, `trigger` and `signature` modules, are public only with
+//! the `unstable` feature.
+
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+mod layout;
+
+#[cfg(feature = "unstable")]
+pub use layout::{fixed, signature, systemtime, trigger, variable, Field};
+#[cfg(not(feature = "unstable"))]
+#[cfg_attr(not(feature = "binary"), allow(unused_imports))]
+pub(crate) use layout::{fixed, signature, systemtime, trigger, variable, Field};
+
+/// The `SCHED_S_TASK_*` codes of the status field.
+pub mod status {
+    pub const READY: i32 = 0x41300;
+    pub const RUNNING: i32 = 0x41301;
+    pub const DISABLED: i32 = 0x41302;
+    pub const HAS_NOT_RUN: i32 = 0x41303;
+    pub const NO_MORE_RUNS: i32 = 0x41304;
+    pub const NOT_SCHEDULED: i32 = 0x41305;
+    pub const TERMINATED: i32 = 0x41306;
+    pub const NO_VALID_TRIGGERS: i32 = 0x41307;
+    pub const EVENT_TRIGGER: i32 = 0x41308;
+}
+
+/// The `TASK_FLAG_*` bits of the flags field, as laid out in [MS-TSCH]
+/// section 2.4.1. They say how a task behaves and are set by whoever
+/// creates it; bits 0x8, 0x4000 to 0x800000 and those above 0x1000000 are
+/// reserved, and `state::APPLICATION_NAME` is not a flag but a state bit.
+pub mod flags {
+    /// Runs in the interactive session of the logged-on user, where it can
+    /// show windows.
+    pub const INTERACTIVE: u32 = 0x1;
+    /// Deleted once it has no more runs scheduled.
+    pub const DELETE_WHEN_DONE: u32 = 0x2;
+    /// Not run by its triggers.
+    pub const DISABLED: u32 = 0x4;
+    /// Only started when the computer has been idle for the idle wait of
+    /// the idle time field.
+    pub const START_ONLY_IF_IDLE: u32 = 0x10;
+    /// Stopped when the computer stops being idle.
+    pub const KILL_ON_IDLE_END: u32 = 0x20;
+    /// Not started while the computer runs on batteries.
+    pub const DONT_START_IF_ON_BATTERIES: u32 = 0x40;
+    /// Stopped when the computer switches to batteries.
+    pub const KILL_IF_GOING_ON_BATTERIES: u32 = 0x80;
+    /// Only run while the computer is docked.
+    pub const RUN_ONLY_IF_DOCKED: u32 = 0x100;
+    /// Left out of the Scheduled Tasks folder.
+    pub const HIDDEN: u32 = 0x200;
+    /// Only run while the computer is connected to the Internet.
+    pub const RUN_IF_CONNECTED_TO_INTERNET: u32 = 0x400;
+    /// Started again when the computer is idle again, after it was stopped
+    /// by `KILL_ON_IDLE_END`.
+    pub const RESTART_ON_IDLE_RESUME: u32 = 0x800;
+    /// Wakes the computer to run, and keeps it from sleeping until done.
+    pub const SYSTEM_REQUIRED: u32 = 0x1000;
+    /// Only run while its user is logged on.
+    pub const RUN_ONLY_IF_LOGGED_ON: u32 = 0x2000;
+}
+
+/// The bits Task Scheduler keeps about a job in the high byte of its flags
+/// field, which say what state the job is in rather than how it behaves.
+pub mod state {
+    /// The job has an application name; Task Scheduler sets it when one is
+    /// stored, and a job without it cannot run.
+    pub const APPLICATION_NAME: u32 = 0x1000000;
+}
+
+/// The process priority classes of the priority field, as laid out in
+/// [MS-TSCH] section 2.4.1; the other bits are reserved.
+pub mod priority {
+    pub const NORMAL: u32 = 0x20;
+    pub const IDLE: u32 = 0x40;
+    pub const HIGH: u32 = 0x80;
+    pub const REALTIME: u32 = 0x100;
+}
+
+/// The product version words Task Scheduler writes, one per Windows
+/// release.
+pub mod product {
+    pub const WINDOWS_NT_4_0: u16 = 0x400;
+    pub const WINDOWS_2000: u16 = 0x500;
+    pub const WINDOWS_XP: u16 = 0x501;
+    pub const WINDOWS_VISTA: u16 = 0x600;
+    pub const WINDOWS_7: u16 = 0x601;
+    pub const WINDOWS_8: u16 = 0x602;
+    pub const WINDOWS_8_1: u16 = 0x603;
+    pub const WINDOWS_10: u16 = 0xa00;
+
+    /// Every version with the release's name.
+    pub const VERSIONS: [(u16, &str); 8] = [
+        (WINDOWS_NT_4_0, "Windows NT 4.0"),
+        (WINDOWS_2000, "Windows 2000"),
+        (WINDOWS_XP, "Windows XP"),
+        (WINDOWS_VISTA, "Windows Vista"),
+        (WINDOWS_7, "Windows 7"),
+        (WINDOWS_8, "Windows 8"),
+        (WINDOWS_8_1, "Windows 8.1"),
+        (WINDOWS_10, "Windows 10"),
+    ];
+
+    /// The product and file versions Task Scheduler writes together. Every
+    /// release writes `FILE_VERSION`; a job with any other pair, or a
+    /// product word not listed, was damaged or made by something else.
+    pub const FILE_VERSIONS: [(u16, u16); 8] = [
+        (WINDOWS_NT_4_0, super::FILE_VERSION),
+        (WINDOWS_2000, super::FILE_VERSION),
+        (WINDOWS_XP, super::FILE_VERSION),
+        (WINDOWS_VISTA, super::FILE_VERSION),
+        (WINDOWS_7, super::FILE_VERSION),
+        (WINDOWS_8, super::FILE_VERSION),
+        (WINDOWS_8_1, super::FILE_VERSION),
+        (WINDOWS_10, super::FILE_VERSION),
+    ];
+
+    /// The release's name for `version`, if it is one of `VERSIONS`.
+    pub fn name(version: u16) -> Option<&'static str> {
+        VERSIONS.iter().find(|(known, _)| *known == version).map(|(_, name)| *name)
+    }
+
+    /// Whether `FILE_VERSIONS` has the pair.
+    pub fn is_plausible(version: u16, file_version: u16) -> bool {
+        FILE_VERSIONS.contains(&(version, file_version))
+    }
+}
+
+/// The only file version Task Scheduler writes.
+pub const FILE_VERSION: u16 = 1;
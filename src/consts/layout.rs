@@ -0,0 +1,215 @@
+//! The offsets and sizes of the binary `.job` format's structures. They
+//! describe the file rather than what it means, so they are public only
+//! with the `unstable` feature; see the crate documentation.
+
+/// Where a field lives and how many bytes it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl Field {
+    /// The offset just past the field.
+    pub const fn end(self) -> usize {
+        self.offset + self.size
+    }
+}
+
+/// The fixed-length section every job starts with.
+pub mod fixed {
+    use super::Field;
+
+    pub const PRODUCT_VERSION: Field = Field { offset: 0, size: 2 };
+    pub const FILE_VERSION: Field = Field { offset: 2, size: 2 };
+    pub const UUID: Field = Field { offset: 4, size: 16 };
+    /// Offset of the application name's length; `Job::parse` does not
+    /// follow it and reads the strings from `variable::APPLICATION_NAME`.
+    pub const APP_NAME_LEN_OFFSET: Field = Field { offset: 20, size: 2 };
+    pub const TRIGGER_OFFSET: Field = Field { offset: 22, size: 2 };
+    pub const ERROR_RETRY_COUNT: Field = Field { offset: 24, size: 2 };
+    pub const ERROR_RETRY_INTERVAL: Field = Field { offset: 26, size: 2 };
+    pub const IDLE_DEADLINE: Field = Field { offset: 28, size: 2 };
+    pub const IDLE_WAIT: Field = Field { offset: 30, size: 2 };
+    pub const PRIORITY: Field = Field { offset: 32, size: 4 };
+    /// In milliseconds.
+    pub const MAXIMUM_RUN_TIME: Field = Field { offset: 36, size: 4 };
+    pub const EXIT_CODE: Field = Field { offset: 40, size: 4 };
+    /// One of the `status` codes.
+    pub const STATUS: Field = Field { offset: 44, size: 4 };
+    /// A combination of the `flags` bits.
+    pub const FLAGS: Field = Field { offset: 48, size: 4 };
+    /// A `systemtime`, all zero if the job has never run.
+    pub const LAST_RUN_TIME: Field = Field { offset: 52, size: 16 };
+
+    /// The length of the section.
+    pub const LENGTH: usize = 68;
+
+    /// Every field of the section with its [MS-TSCH] name, in order.
+    pub const FIELDS: [(&str, Field); 15] = [
+        ("Product Version", PRODUCT_VERSION),
+        ("File Version", FILE_VERSION),
+        ("Job uuid", UUID),
+        ("App Name Len Offset", APP_NAME_LEN_OFFSET),
+        ("Trigger Offset", TRIGGER_OFFSET),
+        ("Error Retry Count", ERROR_RETRY_COUNT),
+        ("Error Retry Interval", ERROR_RETRY_INTERVAL),
+        ("Idle Deadline", IDLE_DEADLINE),
+        ("Idle Wait", IDLE_WAIT),
+        ("Priority", PRIORITY),
+        ("Maximum Run Time", MAXIMUM_RUN_TIME),
+        ("Exit Code", EXIT_CODE),
+        ("Status", STATUS),
+        ("Flags", FLAGS),
+        ("Last Run Time", LAST_RUN_TIME),
+    ];
+}
+
+/// The start of the variable-length section that follows.
+pub mod variable {
+    use super::Field;
+
+    pub const RUNNING_INSTANCE_COUNT: Field = Field { offset: 68, size: 2 };
+    /// The first of the five strings: application name, parameters, working
+    /// directory, author and comment. Each is a count of UTF-16 code units,
+    /// including the terminating NUL, followed by the units. An empty string
+    /// is either a count of 0 and no units, or a count of 1 and a lone NUL,
+    /// depending on the writer.
+    pub const APPLICATION_NAME: usize = 70;
+    /// The size of each string's code unit count.
+    pub const STRING_LENGTH_SIZE: usize = 2;
+}
+
+/// A `SYSTEMTIME`: eight 16-bit words.
+pub mod systemtime {
+    use super::Field;
+
+    pub const YEAR: Field = Field { offset: 0, size: 2 };
+    pub const MONTH: Field = Field { offset: 2, size: 2 };
+    pub const DAY_OF_WEEK: Field = Field { offset: 4, size: 2 };
+    pub const DAY: Field = Field { offset: 6, size: 2 };
+    pub const HOUR: Field = Field { offset: 8, size: 2 };
+    pub const MINUTE: Field = Field { offset: 10, size: 2 };
+    pub const SECOND: Field = Field { offset: 12, size: 2 };
+    pub const MILLISECONDS: Field = Field { offset: 14, size: 2 };
+
+    pub const LENGTH: usize = 16;
+
+    pub const FIELDS: [(&str, Field); 8] = [
+        ("Year", YEAR),
+        ("Month", MONTH),
+        ("Day Of Week", DAY_OF_WEEK),
+        ("Day", DAY),
+        ("Hour", HOUR),
+        ("Minute", MINUTE),
+        ("Second", SECOND),
+        ("Milliseconds", MILLISECONDS),
+    ];
+}
+
+/// A trigger in the triggers section, which is a 16-bit count followed by
+/// the triggers. The parser does not decode triggers yet.
+pub mod trigger {
+    use super::Field;
+
+    pub const TRIGGER_SIZE: Field = Field { offset: 0, size: 2 };
+    pub const RESERVED1: Field = Field { offset: 2, size: 2 };
+    pub const BEGIN_YEAR: Field = Field { offset: 4, size: 2 };
+    pub const BEGIN_MONTH: Field = Field { offset: 6, size: 2 };
+    pub const BEGIN_DAY: Field = Field { offset: 8, size: 2 };
+    pub const END_YEAR: Field = Field { offset: 10, size: 2 };
+    pub const END_MONTH: Field = Field { offset: 12, size: 2 };
+    pub const END_DAY: Field = Field { offset: 14, size: 2 };
+    pub const START_HOUR: Field = Field { offset: 16, size: 2 };
+    pub const START_MINUTE: Field = Field { offset: 18, size: 2 };
+    pub const MINUTES_DURATION: Field = Field { offset: 20, size: 4 };
+    pub const MINUTES_INTERVAL: Field = Field { offset: 24, size: 4 };
+    /// A combination of `FLAG_*`.
+    pub const FLAGS: Field = Field { offset: 28, size: 4 };
+    /// A `TriggerType`.
+    pub const TRIGGER_TYPE: Field = Field { offset: 32, size: 4 };
+    pub const TRIGGER_SPECIFIC0: Field = Field { offset: 36, size: 2 };
+    pub const TRIGGER_SPECIFIC1: Field = Field { offset: 38, size: 2 };
+    pub const TRIGGER_SPECIFIC2: Field = Field { offset: 40, size: 2 };
+    pub const PADDING: Field = Field { offset: 42, size: 2 };
+    pub const RESERVED2: Field = Field { offset: 44, size: 2 };
+    pub const RESERVED3: Field = Field { offset: 46, size: 2 };
+
+    pub const LENGTH: usize = 48;
+
+    pub const FIELDS: [(&str, Field); 20] = [
+        ("Trigger Size", TRIGGER_SIZE),
+        ("Reserved1", RESERVED1),
+        ("Begin Year", BEGIN_YEAR),
+        ("Begin Month", BEGIN_MONTH),
+        ("Begin Day", BEGIN_DAY),
+        ("End Year", END_YEAR),
+        ("End Month", END_MONTH),
+        ("End Day", END_DAY),
+        ("Start Hour", START_HOUR),
+        ("Start Minute", START_MINUTE),
+        ("Minutes Duration", MINUTES_DURATION),
+        ("Minutes Interval", MINUTES_INTERVAL),
+        ("Flags", FLAGS),
+        ("Trigger Type", TRIGGER_TYPE),
+        ("TriggerSpecific0", TRIGGER_SPECIFIC0),
+        ("TriggerSpecific1", TRIGGER_SPECIFIC1),
+        ("TriggerSpecific2", TRIGGER_SPECIFIC2),
+        ("Padding", PADDING),
+        ("Reserved2", RESERVED2),
+        ("Reserved3", RESERVED3),
+    ];
+
+    pub const FLAG_HAS_END_DATE: u32 = 0x1;
+    pub const FLAG_KILL_AT_DURATION_END: u32 = 0x2;
+    pub const FLAG_DISABLED: u32 = 0x4;
+
+    /// The `TASK_TRIGGER_TYPE` values.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u32)]
+    pub enum TriggerType {
+        Once = 0,
+        Daily = 1,
+        Weekly = 2,
+        MonthlyDate = 3,
+        MonthlyDow = 4,
+        OnIdle = 5,
+        AtSystemStart = 6,
+        AtLogon = 7,
+    }
+
+    impl TriggerType {
+        pub fn from_value(value: u32) -> Option<TriggerType> {
+            let trigger_type = match value {
+                0 => TriggerType::Once,
+                1 => TriggerType::Daily,
+                2 => TriggerType::Weekly,
+                3 => TriggerType::MonthlyDate,
+                4 => TriggerType::MonthlyDow,
+                5 => TriggerType::OnIdle,
+                6 => TriggerType::AtSystemStart,
+                7 => TriggerType::AtLogon,
+                _ => return None,
+            };
+            Some(trigger_type)
+        }
+    }
+}
+
+/// The job signature that may follow the triggers.
+pub mod signature {
+    use super::Field;
+
+    /// 1 for the only version there is.
+    pub const SIGNATURE_VERSION: Field = Field { offset: 0, size: 2 };
+    pub const MIN_CLIENT_VERSION: Field = Field { offset: 2, size: 2 };
+    pub const SIGNATURE: Field = Field { offset: 4, size: 64 };
+
+    pub const LENGTH: usize = 68;
+
+    pub const FIELDS: [(&str, Field); 3] = [
+        ("Signature Version", SIGNATURE_VERSION),
+        ("Min Client Version", MIN_CLIENT_VERSION),
+        ("Signature", SIGNATURE),
+    ];
+}
@@ -0,0 +1,146 @@
+//! Conversion between binary jobs and task XML. Only the fields both formats
+//! share survive: the action, author, comment, and the enabled and hidden state.
+//! Binary triggers are not parsed yet, so converted XML has none.
+//! `job_to_task_losses` and `task_to_job_losses` say what a conversion
+//! leaves behind.
+
+use std::collections::BTreeMap;
+
+use crate::consts;
+use crate::job::{JobBuilder, TaskFlag};
+use crate::task::{Actions, Exec, RegistrationInfo, Settings, Triggers};
+use crate::{Error, Job, Task};
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Builds the XML task equivalent to a binary job.
+pub fn job_to_task(job: &Job) -> Task {
+    Task {
+        registration_info: RegistrationInfo {
+            author: non_empty(&job.user),
+            date: None,
+            description: non_empty(&job.comment),
+            security_descriptor: None,
+        },
+        triggers: Triggers {
+            calendar_trigger: None,
+        },
+        settings: Settings {
+            enabled: Some(job.flags & consts::flags::DISABLED == 0),
+            allow_start_if_on_batteries: Some(
+                job.flags & consts::flags::DONT_START_IF_ON_BATTERIES == 0,
+            ),
+            hidden: Some(job.flags & consts::flags::HIDDEN != 0),
+        },
+        actions: Actions {
+            exec: Some(Exec {
+                command: job.name.clone(),
+                arguments: non_empty(&job.parameters),
+                working_directory: non_empty(&job.working_directory),
+            }),
+        },
+        principals: None,
+        unknown_elements: BTreeMap::new(),
+    }
+}
+
+/// Builds the binary job equivalent to an XML task. A task without an `Exec`
+/// action yields a job with an empty application name. Fails with
+/// `Error::StringTooLong` when a string of the task does not fit in a job.
+pub fn task_to_job(task: &Task) -> Result<Job, Error> {
+    let exec = task.actions.exec.as_ref();
+    let mut flags = 0;
+    if task.settings.enabled == Some(false) {
+        flags |= consts::flags::DISABLED;
+    }
+    if task.settings.allow_start_if_on_batteries == Some(false) {
+        flags |= consts::flags::DONT_START_IF_ON_BATTERIES;
+    }
+    if task.settings.hidden == Some(true) {
+        flags |= consts::flags::HIDDEN;
+    }
+    let info = &task.registration_info;
+    JobBuilder::new(exec.map_or("", |e| e.command.as_str()))
+        .parameters(exec.and_then(|e| e.arguments.as_deref()).unwrap_or(""))
+        .working_directory(
+            exec.and_then(|e| e.working_directory.as_deref())
+                .unwrap_or(""),
+        )
+        .author(info.author.as_deref().unwrap_or(""))
+        .comment(info.description.as_deref().unwrap_or(""))
+        .flags(flags)
+        .build()
+}
+
+pub use crate::job::trigger_count as job_trigger_count;
+
+/// What `job_to_task` leaves out of `job`, read from `data`, one line
+/// each: its triggers, and the fields and flags that differ from their
+/// defaults. The UUID never carries over and is not listed.
+pub fn job_to_task_losses(job: &Job, data: &[u8]) -> Vec<String> {
+    let mut losses = Vec::new();
+    match job_trigger_count(data) {
+        Some(0) => {}
+        Some(1) => losses.push("1 trigger not converted".to_string()),
+        Some(count) => losses.push(format!("{} triggers not converted", count)),
+        None => losses.push("trigger section unreadable".to_string()),
+    }
+    let back = match task_to_job(&job_to_task(job)) {
+        Ok(back) => back,
+        Err(e) => {
+            losses.push(e.to_string());
+            return losses;
+        }
+    };
+    let fields = [
+        ("priority", job.priority != back.priority),
+        ("maximum run time", job.max_run_time != back.max_run_time),
+        ("exit code", job.exit_code != back.exit_code),
+        ("status", job.status != back.status),
+        ("last run time", job.run_date != back.run_date),
+    ];
+    for (field, lost) in fields {
+        if lost {
+            losses.push(format!("{} dropped", field));
+        }
+    }
+    for flag in TaskFlag::from_bits(job.flags & !back.flags) {
+        losses.push(format!("flag {} dropped", flag.constant_name()));
+    }
+    losses
+}
+
+/// What `task_to_job` leaves out of `task`, one line each: its triggers,
+/// principal, registration date and security descriptor, and any element
+/// the model keeps as unknown.
+pub fn task_to_job_losses(task: &Task) -> Vec<String> {
+    let mut losses = Vec::new();
+    match task.trigger_count() {
+        0 => {}
+        1 => losses.push("1 trigger not converted".to_string()),
+        count => losses.push(format!("{} triggers not converted", count)),
+    }
+    let info = &task.registration_info;
+    let fields = [
+        ("principal", task.principals.as_ref().and_then(|p| p.principal.as_ref()).is_some()),
+        ("registration date", info.date.is_some()),
+        ("security descriptor", info.security_descriptor.is_some()),
+    ];
+    for (field, lost) in fields {
+        if lost {
+            losses.push(format!("{} dropped", field));
+        }
+    }
+    for path in task.unknown_elements.keys() {
+        if !path.starts_with("Triggers/") {
+            losses.push(format!("{} dropped", path));
+        }
+    }
+    losses
+}
@@ -0,0 +1,388 @@
+//! Display styles for the dates in the human-readable output. The structured
+//! formats always use ISO 8601.
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+/// How `format_job_with` and `format_task_with` print dates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateStyle {
+    /// `Friday Mar 1 02:13:00 2024`; task trigger boundaries as written.
+    #[default]
+    Classic,
+    /// `2024-03-01T02:13:00`.
+    Iso,
+    /// Month and weekday names in the given language, e.g.
+    /// `Freitag, 1. März 2024 02:13:00`.
+    Locale(Locale),
+}
+
+/// The languages `DateStyle::Locale` has names for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    /// English, in the layout of `DateStyle::Classic`.
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+impl Locale {
+    /// The locale for a POSIX locale name such as `de_DE.UTF-8` or a tag
+    /// such as `fr-CA`; `None` for languages without tables.
+    pub fn from_name(name: &str) -> Option<Locale> {
+        let language = name.split(['_', '-', '.', '@']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            "fr" => Some(Locale::Fr),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+
+    /// The locale named by `LC_ALL`, `LC_TIME` or `LANG`, in that order,
+    /// falling back to English.
+    pub fn from_env() -> Locale {
+        ["LC_ALL", "LC_TIME", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+            .next()
+            .and_then(|name| Locale::from_name(&name))
+            .unwrap_or(Locale::En)
+    }
+
+    fn weekdays(self) -> [&'static str; 7] {
+        match self {
+            Locale::En => [
+                "Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
+            ],
+            Locale::De => [
+                "Sonntag", "Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag",
+            ],
+            Locale::Fr => ["dimanche", "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi"],
+            Locale::Es => ["domingo", "lunes", "martes", "miércoles", "jueves", "viernes", "sábado"],
+        }
+    }
+
+    fn months(self) -> [&'static str; 12] {
+        match self {
+            Locale::En => [
+                "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+            ],
+            Locale::De => [
+                "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August",
+                "September", "Oktober", "November", "Dezember",
+            ],
+            Locale::Fr => [
+                "janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août",
+                "septembre", "octobre", "novembre", "décembre",
+            ],
+            Locale::Es => [
+                "enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto",
+                "septiembre", "octubre", "noviembre", "diciembre",
+            ],
+        }
+    }
+}
+
+/// The calendar fields of a date, shared by job dates and task XML
+/// timestamps.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Fields {
+    pub year: u16,
+    pub month: u16,
+    /// 0 for Sunday.
+    pub weekday: Option<u16>,
+    pub day: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+}
+
+impl Fields {
+    pub fn format_iso(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+
+    /// Formats the fields in `style`. A month or weekday outside the valid
+    /// range falls back to ISO.
+    pub fn format(&self, style: DateStyle) -> String {
+        let locale = match style {
+            DateStyle::Iso => return self.format_iso(),
+            DateStyle::Classic | DateStyle::Locale(Locale::En) => Locale::En,
+            DateStyle::Locale(locale) => locale,
+        };
+        let month = (self.month as usize).checked_sub(1).and_then(|m| locale.months().get(m).copied());
+        let Some(month) = month else {
+            return self.format_iso();
+        };
+        let weekday = match self.weekday {
+            Some(weekday) => match locale.weekdays().get(weekday as usize) {
+                Some(name) => Some(*name),
+                None => return self.format_iso(),
+            },
+            None => None,
+        };
+        let time = format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second);
+        let (day, year) = (self.day, self.year);
+        let date = match locale {
+            Locale::En => {
+                let date = format!("{} {} {} {}", month, day, time, year);
+                return match weekday {
+                    Some(weekday) => format!("{} {}", weekday, date),
+                    None => date,
+                };
+            }
+            Locale::De => format!("{}. {} {}", day, month, year),
+            Locale::Fr => format!("{} {} {}", day, month, year),
+            Locale::Es => format!("{} de {} de {}", day, month, year),
+        };
+        match (weekday, locale) {
+            (Some(weekday), Locale::Fr) => format!("{} {} {}", weekday, date, time),
+            (Some(weekday), _) => format!("{}, {} {}", weekday, date, time),
+            (None, _) => format!("{} {}", date, time),
+        }
+    }
+}
+
+/// Formats a task XML timestamp such as a trigger's `StartBoundary`
+/// (`2024-03-01T02:13:00`, optionally with fractional seconds and a zone) in
+/// `style`. Only the locale style changes it: the others keep the text as
+/// written, as does every style when the text is not such a timestamp.
+pub fn format_timestamp(text: &str, style: DateStyle) -> String {
+    let DateStyle::Locale(_) = style else {
+        return text.to_string();
+    };
+    let Some((fields, rest)) = parse_timestamp(text) else {
+        return text.to_string();
+    };
+    // Fractional seconds add nothing to a localized date; a zone does.
+    let zone = rest.trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    if zone.is_empty() {
+        fields.format(style)
+    } else {
+        format!("{} {}", fields.format(style), zone)
+    }
+}
+
+/// Whether `text` is a task XML timestamp as `format_timestamp` reads it,
+/// with a valid month and day.
+pub fn is_timestamp(text: &str) -> bool {
+    parse_timestamp(text.trim()).is_some()
+}
+
+/// How the task XML timestamps `a` and `b` are ordered in time. Fractions
+/// of a second count, and so do zones (`Z`, `+02:00`) when both have one;
+/// `None` when either is not a timestamp, or only one names a zone.
+pub fn compare_timestamps(a: &str, b: &str) -> Option<Ordering> {
+    let (a, a_fraction, a_zone) = instant(a.trim())?;
+    let (b, b_fraction, b_zone) = instant(b.trim())?;
+    let (a, b) = match (a_zone, b_zone) {
+        (Some(a_zone), Some(b_zone)) => (a - a_zone * 60, b - b_zone * 60),
+        (None, None) => (a, b),
+        _ => return None,
+    };
+    // Fractions compare as written once padded to the same length.
+    let width = a_fraction.len().max(b_fraction.len());
+    let fractions = (format!("{:0<width$}", a_fraction), format!("{:0<width$}", b_fraction));
+    Some(a.cmp(&b).then(fractions.0.cmp(&fractions.1)))
+}
+
+/// The seconds since 1970 of a task XML timestamp, or of a date as
+/// `JobDate::format_iso` writes it, taking the time as written: fractions
+/// of a second are dropped and a zone is ignored. `None` for anything else.
+pub fn local_seconds(text: &str) -> Option<i64> {
+    instant(text.trim()).map(|(seconds, _, _)| seconds)
+}
+
+/// The seconds since 1970 of a timestamp as written, ignoring its zone,
+/// with its fraction digits and its zone's offset from UTC in minutes.
+fn instant(text: &str) -> Option<(i64, &str, Option<i64>)> {
+    let (fields, rest) = parse_timestamp(text)?;
+    let (fraction, zone) = match rest.strip_prefix('.') {
+        Some(rest) => rest.split_at(rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len())),
+        None => ("", rest),
+    };
+    let zone = match zone.as_bytes() {
+        [] => None,
+        [b'Z'] => Some(0),
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let digits = [h1, h2, m1, m2];
+            if !digits.iter().all(|d| d.is_ascii_digit()) {
+                return None;
+            }
+            let [h1, h2, m1, m2] = digits.map(|d| i64::from(d - b'0'));
+            let minutes = (h1 * 10 + h2) * 60 + m1 * 10 + m2;
+            Some(if *sign == b'-' { -minutes } else { minutes })
+        }
+        _ => return None,
+    };
+    let days = days_from_civil(fields.year.into(), fields.month.into(), fields.day.into());
+    let hours = days * 24 + i64::from(fields.hour);
+    let seconds = (hours * 60 + i64::from(fields.minute)) * 60 + i64::from(fields.second);
+    Some((seconds, fraction, zone))
+}
+
+fn parse_timestamp(text: &str) -> Option<(Fields, &str)> {
+    let bytes = text.as_bytes();
+    let shape = b"dddd-dd-ddTdd:dd:dd";
+    if bytes.len() < shape.len() {
+        return None;
+    }
+    for (&b, &want) in bytes.iter().zip(shape) {
+        let ok = if want == b'd' { b.is_ascii_digit() } else { b == want };
+        if !ok {
+            return None;
+        }
+    }
+    let number = |range: std::ops::Range<usize>| text[range].parse::<u16>().ok();
+    let mut fields = Fields {
+        year: number(0..4)?,
+        month: number(5..7)?,
+        weekday: None,
+        day: number(8..10)?,
+        hour: number(11..13)?,
+        minute: number(14..16)?,
+        second: number(17..19)?,
+    };
+    fields.weekday = Some(day_of_week(fields.year, fields.month, fields.day)?);
+    Some((fields, &text[shape.len()..]))
+}
+
+/// Reads back what `Fields::format_iso` wrote, which need not be a valid
+/// date: `0000-00-00T00:00:00` stands for a job that never ran. The weekday
+/// is worked out when the date exists.
+#[cfg(feature = "serde")]
+pub(crate) fn parse_iso(text: &str) -> Option<Fields> {
+    let numbers: Vec<u16> =
+        text.split(['-', 'T', ':']).map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    let [year, month, day, hour, minute, second] = numbers[..] else {
+        return None;
+    };
+    Some(Fields {
+        year,
+        month,
+        weekday: day_of_week(year, month, day),
+        day,
+        hour,
+        minute,
+        second,
+    })
+}
+
+/// The day of the week, 0 for Sunday, that a Gregorian date falls on;
+/// `None` if there is no such date, such as 30 February or month 13.
+pub fn day_of_week(year: u16, month: u16, day: u16) -> Option<u16> {
+    let valid = (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day);
+    valid.then(|| weekday(year, month, day))
+}
+
+/// The English name of a day of the week, 0 for Sunday; `None` past 6.
+pub fn weekday_name(weekday: u16) -> Option<&'static str> {
+    Locale::En.weekdays().get(usize::from(weekday)).copied()
+}
+
+fn days_in_month(year: u16, month: u16) -> u16 {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The day of the week, 0 for Sunday, of a Gregorian date (Sakamoto's
+/// method).
+fn weekday(year: u16, month: u16, day: u16) -> u16 {
+    const OFFSETS: [i32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let year = i32::from(year) - i32::from(month < 3);
+    let days = year + year.div_euclid(4) - year.div_euclid(100) + year.div_euclid(400);
+    (days + OFFSETS[usize::from(month) - 1] + i32::from(day)).rem_euclid(7) as u16
+}
+
+/// The length of an `xs:duration` such as a repetition's `PT15M` or a
+/// time limit's `P3D`, taking a year as 365 days and a month as 30. Only
+/// seconds may have a fraction; `None` for anything else, including a
+/// negative duration.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    const DAY: f64 = 86400.0;
+    const DATE_UNITS: &[(char, f64)] = &[('Y', 365.0 * DAY), ('M', 30.0 * DAY), ('D', DAY)];
+    const TIME_UNITS: &[(char, f64)] = &[('H', 3600.0), ('M', 60.0), ('S', 1.0)];
+    let rest = text.trim().strip_prefix('P')?;
+    let (date, time) = match rest.split_once('T') {
+        Some((_, "")) => return None,
+        Some((date, time)) => (date, time),
+        None => (rest, ""),
+    };
+    if date.is_empty() && time.is_empty() {
+        return None;
+    }
+    let mut seconds = 0.0;
+    for (mut part, units) in [(date, DATE_UNITS), (time, TIME_UNITS)] {
+        // Units come in order, each at most once.
+        let mut units = units.iter();
+        while !part.is_empty() {
+            let end = part.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+            let (number, unit) = (&part[..end], part[end..].chars().next()?);
+            let (_, scale) = units.find(|(name, _)| *name == unit)?;
+            if number.is_empty() || (number.contains('.') && unit != 'S') {
+                return None;
+            }
+            seconds += number.parse::<f64>().ok()? * scale;
+            part = &part[end + unit.len_utf8()..];
+        }
+    }
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// The days from 1970-01-01 to a Gregorian date, the inverse of
+/// `civil_from_days`.
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The year, month and day of the Gregorian date `days` after 1970-01-01
+/// (Howard Hinnant's days-to-civil conversion).
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats a Unix timestamp as ISO 8601 in UTC, like `JobDate::format_iso`.
+pub fn format_unix(seconds: u64) -> String {
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time = seconds % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Whether `text` is a timestamp in the `YYYY-MM-DDTHH:MM:SS` form that
+/// `JobDate::format_iso` produces, of a date that exists.
+pub fn is_iso_timestamp(text: &str) -> bool {
+    text.len() == 19 && parse_timestamp(text).is_some()
+}
@@ -0,0 +1,256 @@
+//! Microsoft's own scheduled tasks, which make up most of a `System32\Tasks`
+//! folder on every machine, told apart from the rest by what each of them
+//! runs. The built-in knowledge base is `data/microsoft-tasks.tsv`, embedded
+//! at build time; `data/export-microsoft-tasks.ps1` regenerates it from a
+//! clean install.
+//!
+//! A task is known by its URI, its path below the Tasks folder. It is a
+//! Microsoft default when every action it has is one the knowledge base
+//! lists for that URI, on any Windows version: programs are compared after
+//! `normalize_path` and without regard to case, with `SysWOW64` and
+//! `Sysnative` taken as `System32`, and arguments with runs of whitespace
+//! taken as one space. A task with a known URI and any other action has
+//! been modified, which is what `classify` reports a finding for.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::cmdline::normalize_path;
+use crate::detect::Finding;
+use crate::fold::fold;
+#[cfg(feature = "xml")]
+use crate::task::{ActionTarget, Task};
+use crate::ParsedArtifact;
+
+/// How a task relates to the ones Windows ships with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Classification {
+    /// A Microsoft task that runs what it ships with.
+    MicrosoftDefault,
+    /// A Microsoft task that runs something else.
+    ModifiedDefault,
+    /// Any other task, and every job.
+    NonDefault,
+}
+
+impl Classification {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Classification::MicrosoftDefault => "Microsoft default (matches expectations)",
+            Classification::ModifiedDefault => "Microsoft default (MODIFIED: command differs)",
+            Classification::NonDefault => "non-default",
+        }
+    }
+}
+
+/// What the Microsoft tasks run, by URI.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(not(feature = "xml"), allow(dead_code))]
+pub struct KnowledgeBase {
+    /// Keyed by the folded URI.
+    tasks: HashMap<String, Vec<Expected>>,
+}
+
+/// One action a Microsoft task may have.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "xml"), allow(dead_code))]
+struct Expected {
+    /// The program and arguments as the knowledge base writes them.
+    written: String,
+    /// The program or `{CLSID}`, compared by `program_key`.
+    program: String,
+    /// The arguments, compared by `arguments_key`; `None` for any.
+    arguments: Option<String>,
+}
+
+impl KnowledgeBase {
+    /// The knowledge base built into the crate.
+    pub fn builtin() -> &'static KnowledgeBase {
+        static BUILTIN: OnceLock<KnowledgeBase> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            KnowledgeBase::parse(include_str!("../data/microsoft-tasks.tsv"))
+                .expect("the built-in knowledge base parses")
+        })
+    }
+
+    /// Reads a knowledge base written as `data/microsoft-tasks.tsv` is: a
+    /// line per action with the URI, the Windows versions, the program and
+    /// optionally the arguments, separated by tabs. Blank lines and lines
+    /// starting with `#` are skipped. A line without a URI and a program
+    /// fails, naming its line number.
+    pub fn parse(text: &str) -> Result<KnowledgeBase, String> {
+        let mut tasks: HashMap<String, Vec<Expected>> = HashMap::new();
+        for (n, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let columns: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let (uri, program) = match columns.as_slice() {
+                [uri, _, program, ..] if uri.starts_with('\\') && !program.is_empty() => {
+                    (*uri, *program)
+                }
+                _ => {
+                    let problem = "expected <uri>, <windows> and <program>, separated by tabs";
+                    return Err(format!("line {}: {}", n + 1, problem));
+                }
+            };
+            let arguments = columns.get(3).copied().filter(|a| !a.is_empty() && *a != "*");
+            let written = match arguments {
+                Some(arguments) => format!("{} {}", program, arguments),
+                None => program.to_string(),
+            };
+            tasks.entry(fold(uri).into_owned()).or_default().push(Expected {
+                written,
+                program: program_key(program),
+                arguments: arguments.map(arguments_key),
+            });
+        }
+        Ok(KnowledgeBase { tasks })
+    }
+
+    /// How many tasks the knowledge base knows.
+    pub fn task_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Where `artifact`, read from `path`, stands, and the finding for a
+    /// modified default, which names the first action that does not match.
+    /// Jobs are never Microsoft defaults.
+    #[cfg_attr(not(feature = "xml"), allow(unused_variables))]
+    pub fn classify(
+        &self,
+        path: &str,
+        artifact: &ParsedArtifact,
+    ) -> (Classification, Option<Finding>) {
+        match artifact {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(_) => (Classification::NonDefault, None),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => self.classify_task(path, task),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    #[cfg(feature = "xml")]
+    fn classify_task(&self, path: &str, task: &Task) -> (Classification, Option<Finding>) {
+        let Some(uri) = task_uri(path, task) else {
+            return (Classification::NonDefault, None);
+        };
+        let Some(expected) = self.tasks.get(fold(&uri).as_ref()) else {
+            return (Classification::NonDefault, None);
+        };
+        let targets = task.action_targets();
+        let differs = if targets.is_empty() {
+            Some("nothing".to_string())
+        } else {
+            targets
+                .iter()
+                .find(|target| !expected.iter().any(|expected| expected.matches(target)))
+                .map(describe_target)
+        };
+        let Some(runs) = differs else {
+            return (Classification::MicrosoftDefault, None);
+        };
+        let finding = Finding {
+            rule: "modified-default-task".into(),
+            description: format!(
+                "{} is a Microsoft default task, but runs {} instead of {}",
+                uri,
+                runs,
+                expected[0].written
+            ),
+            ..Finding::default()
+        };
+        (Classification::ModifiedDefault, Some(finding))
+    }
+}
+
+#[cfg(feature = "xml")]
+impl Expected {
+    fn matches(&self, target: &ActionTarget) -> bool {
+        match target {
+            ActionTarget::Exec { command, arguments } => {
+                let arguments = arguments_key(arguments.as_deref().unwrap_or(""));
+                !self.program.starts_with('{')
+                    && glob(&self.program, &program_key(command))
+                    && self.arguments.as_ref().is_none_or(|pattern| glob(pattern, &arguments))
+            }
+            ActionTarget::ComHandler { class_id } => self.program == program_key(class_id),
+            ActionTarget::Other(_) => false,
+        }
+    }
+}
+
+#[cfg(feature = "xml")]
+fn describe_target(target: &ActionTarget) -> String {
+    match target {
+        ActionTarget::Exec { command, arguments } => match arguments.as_deref() {
+            Some(arguments) if !arguments.trim().is_empty() => {
+                format!("{} {}", command.trim(), arguments.trim())
+            }
+            _ => command.trim().to_string(),
+        },
+        ActionTarget::ComHandler { class_id } => class_id.trim().to_string(),
+        ActionTarget::Other(kind) => format!("a {} action", kind),
+    }
+}
+
+/// The task's path below the Tasks folder, from the file's path when it is
+/// under `System32\Tasks`, and otherwise from its `RegistrationInfo/URI`.
+#[cfg(feature = "xml")]
+fn task_uri(path: &str, task: &Task) -> Option<String> {
+    const TASKS: &str = "\\system32\\tasks\\";
+    let path = path.replace('/', "\\");
+    if let Some(start) = path.to_ascii_lowercase().rfind(TASKS) {
+        let uri = &path[start + TASKS.len() - 1..];
+        let uri = match uri.len().checked_sub(4) {
+            Some(end) if uri.is_char_boundary(end) && uri[end..].eq_ignore_ascii_case(".xml") => {
+                &uri[..end]
+            }
+            _ => uri,
+        };
+        return Some(uri.to_string());
+    }
+    let uri = task.unknown_elements.get("RegistrationInfo/URI")?.trim();
+    Some(uri.to_string()).filter(|uri| uri.starts_with('\\'))
+}
+
+/// A program or `{CLSID}` as it is compared.
+fn program_key(program: &str) -> String {
+    fold(&normalize_path(program))
+        .replace("\\syswow64\\", "\\system32\\")
+        .replace("\\sysnative\\", "\\system32\\")
+}
+
+/// Arguments as they are compared.
+fn arguments_key(arguments: &str) -> String {
+    fold(arguments).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Whether `text` matches `pattern`, in which `*` matches any run of
+/// characters.
+#[cfg_attr(not(feature = "xml"), allow(dead_code))]
+fn glob(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
@@ -0,0 +1,372 @@
+//! Built-in heuristics that flag artifacts worth a closer look.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::cmdline::{file_name, normalize_path};
+use crate::date;
+use crate::fold::{fold, Case};
+use crate::schedule::Schedule;
+#[cfg(feature = "xml")]
+use crate::sddl;
+use crate::ParsedArtifact;
+
+#[cfg(feature = "binary")]
+use crate::{account, consts, warning};
+
+/// Programs that run arbitrary script or command text passed to them.
+const SCRIPT_HOSTS: &[&str] = &[
+    "cmd.exe",
+    "cscript.exe",
+    "mshta.exe",
+    "powershell.exe",
+    "pwsh.exe",
+    "regsvr32.exe",
+    "rundll32.exe",
+    "wscript.exe",
+];
+
+/// A single heuristic match. `rule` is a stable identifier for automation.
+/// Findings sort by rule, then description.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Finding {
+    pub rule: Cow<'static, str>,
+    pub description: String,
+    /// The MITRE ATT&CK technique, such as `T1218.005`, for rules that
+    /// name one.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub technique: Option<String>,
+    /// How much the match weighs, for rules that say.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub score: Option<u32>,
+}
+
+/// Names longer than this are not flagged as random, however they score:
+/// long random-looking names are mostly vendors appending a GUID or SID.
+pub const RANDOM_NAME_MAX_LENGTH: usize = 40;
+
+/// Tunable limits for the heuristics that `Record::detect_with` runs on top
+/// of `detect`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectOptions {
+    /// The `name_entropy` score from which a task name counts as random.
+    pub name_entropy_threshold: f64,
+    /// How far from the nearest scheduled run a task's last run may be
+    /// before `detect_off_schedule` flags it.
+    pub schedule_tolerance: Duration,
+}
+
+impl Default for DetectOptions {
+    fn default() -> DetectOptions {
+        DetectOptions {
+            name_entropy_threshold: 0.8,
+            schedule_tolerance: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Vendor folders under `ProgramData` that `detect_writable_path` leaves
+/// alone, lowercase.
+const PROGRAMDATA_VENDORS: &[&str] = &[
+    "c:\\programdata\\microsoft\\windows defender\\",
+    "c:\\programdata\\microsoft\\windows defender advanced threat protection\\",
+    "c:\\programdata\\package cache\\",
+];
+
+/// Accounts whose tasks `detect_writable_path` looks at: SYSTEM and the
+/// local Administrators group, by SID or by name, lowercase.
+const PRIVILEGED_ACCOUNTS: &[&str] = &[
+    "s-1-5-18",
+    "system",
+    "nt authority\\system",
+    "s-1-5-32-544",
+    "administrators",
+    "builtin\\administrators",
+];
+
+/// Directory lists for `detect_writable_path` on top of the built-in ones,
+/// as read from the `[writable_paths]` table of a `--rules` file. Entries
+/// may use environment variables such as `%ProgramData%`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathRules {
+    /// More directories whose programs are flagged.
+    pub flag: Vec<String>,
+    /// Directories whose programs are never flagged, such as a legitimate
+    /// agent under `ProgramData`.
+    pub allow: Vec<String>,
+    /// How programs are matched against `flag` and `allow`.
+    pub case: Case,
+}
+
+/// Runs every built-in heuristic over `artifact`.
+#[cfg_attr(not(any(feature = "binary", feature = "xml")), allow(unreachable_code, unused))]
+pub fn detect(artifact: &ParsedArtifact) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    match artifact {
+        #[cfg(feature = "binary")]
+        ParsedArtifact::Job(job) => {
+            if job.flags & consts::flags::HIDDEN != 0 {
+                findings.push(Finding {
+                    rule: "hidden-task".into(),
+                    description: "task is hidden from the Task Scheduler UI".to_string(),
+                    ..Finding::default()
+                });
+            }
+            if !consts::product::is_plausible(job.product_info, job.file_version) {
+                findings.push(Finding {
+                    rule: "implausible-version".into(),
+                    description: format!(
+                        "{} with file version {} was not written by Task Scheduler; the job \
+                         may be hand-crafted or damaged",
+                        warning::describe_product(job.product_info),
+                        job.file_version
+                    ),
+                    // Damage explains it as well as forgery does.
+                    score: Some(10),
+                    ..Finding::default()
+                });
+            }
+            if let Some((stored, actual)) = job.run_date.weekday_mismatch() {
+                findings.push(Finding {
+                    rule: "weekday-mismatch".into(),
+                    description: format!(
+                        "the last run time's weekday field says {}, but {} was a {}; the \
+                         date may have been edited",
+                        warning::describe_weekday(stored),
+                        job.run_date.format_iso(),
+                        warning::describe_weekday(actual)
+                    ),
+                    // Tools writing jobs by themselves get it wrong by mistake too.
+                    score: Some(10),
+                    ..Finding::default()
+                });
+            }
+            if let Some(language) = account::localized_administrator(&job.user) {
+                findings.push(Finding {
+                    rule: "localized-admin".into(),
+                    description: format!(
+                        "created by the built-in administrator of a {} system ({})",
+                        language, job.user
+                    ),
+                    ..Finding::default()
+                });
+            }
+        }
+        #[cfg(feature = "xml")]
+        ParsedArtifact::Task(task) => {
+            let runs_as_system = task
+                .principal()
+                .and_then(|p| p.user_id.as_deref())
+                .is_some_and(sddl::is_system_account);
+            let author = task.registration_info.author.as_deref();
+            if runs_as_system && !author.is_some_and(is_microsoft_author) {
+                findings.push(Finding {
+                    rule: "system-third-party".into(),
+                    description: match author {
+                        Some(author) => format!("runs as SYSTEM but was registered by {}", author),
+                        None => "runs as SYSTEM but names no author".to_string(),
+                    },
+                    ..Finding::default()
+                });
+            }
+        }
+        #[cfg(not(any(feature = "binary", feature = "xml")))]
+        _ => unreachable!(),
+    }
+    if let Some(command) = artifact.command() {
+        // A script host behind a launcher such as explorer.exe counts too.
+        let program = std::iter::once(&command.executable)
+            .chain(&command.effective_executable)
+            .map(|path| file_name(path))
+            .find(|name| SCRIPT_HOSTS.contains(&name.as_str()));
+        if let Some(program) = program {
+            findings.push(Finding {
+                rule: "script-host".into(),
+                description: format!("runs the script host {}", program),
+                ..Finding::default()
+            });
+        }
+    }
+    findings
+}
+
+/// The number in the name of a job created by `at.exe`, which names them
+/// `At1.job`, `At2.job` and so on. The match ignores case.
+pub fn at_job_index(path: &Path) -> Option<u32> {
+    // Split by hand so Windows paths read on other systems work too.
+    let name = path.to_str()?.rsplit(['\\', '/']).next()?.to_ascii_lowercase();
+    let digits = name.strip_prefix("at")?.strip_suffix(".job")?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// The finding for a job named like those `at.exe` creates. `at.exe` jobs
+/// are a hallmark of old lateral-movement tooling, and one that runs a
+/// program from a network share gets its own, stronger rule.
+pub fn detect_at_job(index: u32, artifact: &ParsedArtifact) -> Finding {
+    let remote = artifact.command().is_some_and(|command| {
+        std::iter::once(&command.executable)
+            .chain(&command.effective_executable)
+            .any(|path| is_unc_path(path))
+    });
+    if remote {
+        Finding {
+            rule: "remote-at-job".into(),
+            description: format!(
+                "At{}.job, created by at.exe, runs a program from a network share",
+                index
+            ),
+            ..Finding::default()
+        }
+    } else {
+        Finding {
+            rule: "at-job".into(),
+            description: format!("At{}.job was created by at.exe", index),
+            ..Finding::default()
+        }
+    }
+}
+
+/// The finding for a task whose name scored `score` by `name_entropy`, if
+/// the score reaches the threshold in `options` and the name is at most
+/// `RANDOM_NAME_MAX_LENGTH` characters long.
+pub fn detect_random_name(name: &str, score: f64, options: &DetectOptions) -> Option<Finding> {
+    if score < options.name_entropy_threshold || name.chars().count() > RANDOM_NAME_MAX_LENGTH {
+        return None;
+    }
+    Some(Finding {
+        rule: "random-name".into(),
+        description: format!("task name \"{}\" looks random (score {:.2})", name, score),
+        ..Finding::default()
+    })
+}
+
+/// The finding for a task whose last run, `last_run`, is further than
+/// `options.schedule_tolerance` from every run its triggers could have
+/// started: one started by hand, such as with "Run now", or a last-run time
+/// or trigger changed after the fact. `last_run` is local time, as
+/// `date::local_seconds` reads it. Nothing is found for a task without
+/// time triggers, one that an event can start as well, or one that starts
+/// missed runs late.
+pub fn detect_off_schedule(
+    schedule: &Schedule,
+    last_run: &str,
+    options: &DetectOptions,
+) -> Option<Finding> {
+    if schedule.event_triggers > 0 || schedule.catches_up {
+        return None;
+    }
+    let expected = schedule.nearest_run(date::local_seconds(last_run)?)?;
+    let tolerance = i64::try_from(options.schedule_tolerance.as_secs()).unwrap_or(i64::MAX);
+    if expected.distance <= tolerance {
+        return None;
+    }
+    Some(Finding {
+        rule: "off-schedule-run".into(),
+        description: format!(
+            "last run inconsistent with schedule: ran {}, nearest expected run {}",
+            last_run.trim(),
+            date::format_unix(expected.time.max(0) as u64)
+        ),
+        ..Finding::default()
+    })
+}
+
+/// The finding for a task that runs as SYSTEM or the Administrators group
+/// from a directory ordinary users can write to, or from somewhere else a
+/// privileged program has no business living. The program and, for a
+/// wrapped command, the program it starts are checked after
+/// `normalize_path`. The rule names the directory that matched:
+///
+/// - `writable-path-recycle-bin`: under `$Recycle.Bin`
+/// - `writable-path-tasks`: in `Windows\Tasks` itself
+/// - `writable-path-temp`: under a `Temp` or `Tmp` directory
+/// - `writable-path-users`: under `Users`
+/// - `writable-path-programdata`: under `ProgramData`, outside a few known
+///   vendor folders
+/// - `writable-path-non-system-drive`: on a drive other than `C:`
+/// - `writable-path-listed`: under a directory in `PathRules::flag`
+///
+/// Nothing under a directory in `PathRules::allow` is flagged.
+pub fn detect_writable_path(artifact: &ParsedArtifact, rules: &PathRules) -> Option<Finding> {
+    let account = artifact.user()?;
+    if !PRIVILEGED_ACCOUNTS.contains(&fold(account.trim()).as_ref()) {
+        return None;
+    }
+    let command = artifact.command()?;
+    let prefix = |dir: &String| directory_prefix(dir, rules.case);
+    let allowed: Vec<String> = rules.allow.iter().map(prefix).collect();
+    let flagged: Vec<String> = rules.flag.iter().map(prefix).collect();
+    std::iter::once(&command.executable)
+        .chain(&command.effective_executable)
+        .find_map(|program| {
+            let path = normalize_path(program);
+            let listed = rules.case.key(&path);
+            if allowed.iter().any(|dir| listed.starts_with(dir.as_str())) {
+                return None;
+            }
+            let listed = flagged.iter().any(|dir| listed.starts_with(dir.as_str()));
+            let rule = writable_path_rule(&fold(&path), listed)?;
+            Some(Finding {
+                rule: rule.into(),
+                description: format!("{} runs {}", account, path),
+                ..Finding::default()
+            })
+        })
+}
+
+/// The `detect_writable_path` rule for a folded, normalized path, `listed`
+/// if it is under a `PathRules::flag` directory.
+fn writable_path_rule(path: &str, listed: bool) -> Option<&'static str> {
+    let rule = if path.contains("\\$recycle.bin\\") {
+        "writable-path-recycle-bin"
+    } else if path.contains("\\windows\\tasks\\") {
+        "writable-path-tasks"
+    } else if path.contains("\\temp\\") || path.contains("\\tmp\\") {
+        "writable-path-temp"
+    } else if path.contains("\\users\\") {
+        "writable-path-users"
+    } else if path.contains(":\\programdata\\") {
+        let vendor = PROGRAMDATA_VENDORS.iter().any(|dir| path.starts_with(dir));
+        if vendor {
+            return None;
+        }
+        "writable-path-programdata"
+    } else if listed {
+        "writable-path-listed"
+    } else if path.as_bytes().get(1) == Some(&b':') && !path.starts_with("c:") {
+        "writable-path-non-system-drive"
+    } else {
+        return None;
+    };
+    Some(rule)
+}
+
+/// A directory from `PathRules`, normalized and keyed by `case`, with a
+/// trailing `\` so it only matches whole folder names.
+fn directory_prefix(dir: &str, case: Case) -> String {
+    let mut dir = case.key(&normalize_path(dir)).into_owned();
+    if !dir.ends_with('\\') {
+        dir.push('\\');
+    }
+    dir
+}
+
+fn is_unc_path(command: &str) -> bool {
+    command.starts_with("\\\\") || command.starts_with("//")
+}
+
+/// Whether a task author is Microsoft: built-in tasks name the company or
+/// point at a string resource in a system DLL (`$(@%SystemRoot%\...)`).
+#[cfg(feature = "xml")]
+fn is_microsoft_author(author: &str) -> bool {
+    let author = fold(author.trim());
+    author.starts_with("microsoft") || author.starts_with("$(@%systemroot%")
+}
@@ -0,0 +1,93 @@
+//! Whether a task is live, that is set to start on its own, from the
+//! switches both formats keep at several levels. `judge` combines them in
+//! this order, and the first that applies decides:
+//!
+//! 1. The task's own switch: `TASK_FLAG_DISABLED` in a job's flags, or a
+//!    `SCHED_S_TASK_DISABLED` status, or `Settings/Enabled` set to false in
+//!    task XML. Nothing a trigger says turns such a task back on.
+//! 2. A task with no triggers is enabled: it still runs when started by
+//!    hand or by another program.
+//! 3. A task whose triggers are all disabled is disabled, even when its
+//!    settings say enabled; Task Scheduler would never start it.
+//! 4. A task whose enabled triggers have all passed their `EndBoundary` is
+//!    disabled as expired.
+//! 5. Anything else is enabled.
+//!
+//! Binary jobs keep their triggers in a section this crate does not read,
+//! so only the first rule applies to them.
+
+// The reasons `judge` gives.
+pub const ENABLED: &str = "enabled";
+pub const NO_TRIGGERS: &str = "no triggers";
+pub const FLAG_DISABLED: &str = "flag TASK_FLAG_DISABLED";
+pub const STATUS_DISABLED: &str = "status SCHED_S_TASK_DISABLED";
+pub const SETTINGS_DISABLED: &str = "disabled via Settings";
+pub const TRIGGERS_DISABLED: &str = "all triggers disabled";
+pub const TRIGGERS_EXPIRED: &str = "all triggers expired";
+pub const TRIGGERS_DISABLED_OR_EXPIRED: &str = "all triggers disabled or expired";
+
+/// Whether a task is live, and the rule that decided it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Enablement {
+    pub enabled: bool,
+    /// One of the reasons above, such as `TRIGGERS_EXPIRED`.
+    pub reason: &'static str,
+}
+
+/// What `judge` needs of a trigger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerState {
+    pub enabled: bool,
+    /// As written, `YYYY-MM-DDTHH:MM:SS` with any fraction or time zone.
+    pub end_boundary: Option<String>,
+}
+
+impl TriggerState {
+    /// Whether the end boundary had passed by `now` (`YYYY-MM-DDTHH:MM:SS`).
+    /// The time zone is not taken into account, and a boundary that is not
+    /// a timestamp never passes.
+    pub fn expired(&self, now: &str) -> bool {
+        let Some(end) = self.end_boundary.as_deref().and_then(|end| end.trim().get(..19)) else {
+            return false;
+        };
+        let timestamp = end.bytes().enumerate().all(|(i, b)| match i {
+            4 | 7 => b == b'-',
+            10 => b == b'T',
+            13 | 16 => b == b':',
+            _ => b.is_ascii_digit(),
+        });
+        timestamp && end < now
+    }
+}
+
+/// Applies the rules above to the reason the task itself is disabled, if it
+/// is, and its triggers, or `None` when they are not known.
+pub fn judge(
+    disabled: Option<&'static str>,
+    triggers: Option<&[TriggerState]>,
+    now: &str,
+) -> Enablement {
+    let decide = |enabled, reason| Enablement { enabled, reason };
+    if let Some(reason) = disabled {
+        return decide(false, reason);
+    }
+    let Some(triggers) = triggers else {
+        return decide(true, ENABLED);
+    };
+    if triggers.is_empty() {
+        return decide(true, NO_TRIGGERS);
+    }
+    let enabled: Vec<&TriggerState> = triggers.iter().filter(|t| t.enabled).collect();
+    if enabled.is_empty() {
+        return decide(false, TRIGGERS_DISABLED);
+    }
+    if enabled.iter().all(|t| t.expired(now)) {
+        let reason = if enabled.len() == triggers.len() {
+            TRIGGERS_EXPIRED
+        } else {
+            TRIGGERS_DISABLED_OR_EXPIRED
+        };
+        return decide(false, reason);
+    }
+    decide(true, ENABLED)
+}
@@ -0,0 +1,157 @@
+//! Scores how random a task name looks. Malware often registers tasks under
+//! names like `GxkQpd` or a bare GUID, where vendors pick words such as
+//! `GoogleUpdateTaskMachineCore` or `Adobe Acrobat Update Task`.
+
+/// Names with fewer letters and digits than this score 0: there is too
+/// little to go on, and `At1`-style names are common.
+const MIN_LENGTH: usize = 5;
+
+/// Words common in the names of legitimate tasks, lowercase. A name made of
+/// them scores low however its letters are spread.
+const WORDS: &[&str] = &[
+    "account", "acrobat", "adobe", "agent", "amd", "antivirus", "app", "apple", "application",
+    "appraiser", "apps", "auto", "automatic", "avast", "backup", "battery", "bits", "boot",
+    "browser", "cache", "ceip", "center", "cert", "certificate", "check", "chrome", "cisco",
+    "citrix", "clean", "cleanup", "client", "clock", "cloud", "collector", "compatibility",
+    "config", "configuration", "consolidator", "core", "customer", "daily", "data", "defender",
+    "defrag", "dell", "diagnostic", "diagnostics", "disk", "dll", "dog", "download", "driver",
+    "dropbox", "edge", "error", "event", "events", "experience", "explorer", "family", "feature",
+    "features", "file", "files", "firefox", "firmware", "folder", "folders", "full", "game",
+    "google", "group", "health", "helper", "history", "host", "hourly", "idle", "improvement",
+    "index", "install", "installer", "intel", "inventory", "java", "job", "kernel", "launch",
+    "launcher", "license", "live", "location", "log", "login", "logon", "logs", "machine",
+    "maintenance", "manager", "media", "memory", "microsoft", "monitor", "monthly", "mozilla",
+    "net", "network", "notification", "nvidia", "office", "onedrive", "oracle", "policy", "power",
+    "print", "printer", "profile", "program", "proxy", "quick", "reader", "realtek", "recovery",
+    "refresh", "register", "registration", "reminder", "renew", "report", "reporter", "reporting",
+    "restore", "run", "runner", "safety", "scan", "scheduled", "sdk", "search", "security",
+    "server", "service", "session", "setup", "shell", "shutdown", "silent", "skip", "skype",
+    "slack", "sleep", "software", "sophos", "space", "standalone", "startup", "steam", "storage",
+    "store", "sync", "system", "task", "tasks", "teams", "telemetry", "temp", "time", "tool",
+    "tools", "update", "updater", "upload", "user", "validation", "verify", "vmware", "wake",
+    "watch", "web", "weekly", "windows", "worker", "xbox", "zoom",
+];
+
+/// Letter pairs common in English words. Pairs outside it, like the `xk`
+/// and `qp` of `GxkQpd`, are what keeps a made-up name from reading as one.
+const COMMON_BIGRAMS: &str = "\
+    ab ac ad af ag ai al am an ap ar as at au av aw ay ba be bi bl bo br bu by \
+    ca ce ch ci ck cl co cr ct cu cy da de di do dr du dy ea ec ed ee ef eg ei \
+    el em en ep er es et eu ev ew ex ey fa fe fi fl fo fr ft fu ga ge gh gi gl \
+    gn go gr gu ha he hi ho hu hy ia ib ic id ie if ig il im in io ip ir is it \
+    iv iz ja je jo ju ka ke ki kn ko ks la ld le li ll lo ls lt lu ly ma me mi \
+    mm mo mp mu my na nc nd ne ng ni nk no ns nt nu ny oa ob oc od of og oi ok \
+    ol om on oo op or os ot ou ov ow ox oy pa pe ph pi pl po pp pr ps pt pu qu \
+    ra rc rd re rg ri rk rl rm rn ro rp rs rt ru ry sa sc se sh si sk sl sm sn \
+    so sp ss st su sw sy ta tc te th ti tl to tr ts tt tu tw ty ua ub uc ud ue \
+    ug ui ul um un up ur us ut va ve vi vo wa we wh wi wo wr xe xi xp xt ya ye \
+    yo ys za ze zi zo";
+
+/// Scores how random `name` looks, from 0 (made of words, or too short to
+/// judge) to 1 (every character different and none part of a word).
+///
+/// Only ASCII letters and digits count, compared without case. The score is
+/// their Shannon entropy, divided by the most a string of that length could
+/// reach over its character class (hex digits or all alphanumerics), times
+/// the share of them not covered by a word from a built-in list of words
+/// common in vendor task names. Unless the name is all hex digits, that is
+/// further scaled by the share of adjacent pairs in what is left that are
+/// not common in English, so a plain word such as `script` scores low too.
+pub fn name_entropy(name: &str) -> f64 {
+    let chars: Vec<u8> = name
+        .bytes()
+        .filter(u8::is_ascii_alphanumeric)
+        .map(|b| b.to_ascii_lowercase())
+        .collect();
+    if chars.len() < MIN_LENGTH {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for &c in &chars {
+        counts[c as usize] += 1;
+    }
+    let len = chars.len() as f64;
+    let entropy: f64 = counts
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    let hex = chars.iter().all(u8::is_ascii_hexdigit);
+    let alphabet = if hex { 16 } else { 36 };
+    let spread = entropy / (chars.len().min(alphabet) as f64).log2();
+
+    let lowercase = name.to_ascii_lowercase();
+    let rest: Vec<&str> = lowercase
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(outside_words)
+        .collect();
+    let uncovered = rest.iter().map(|run| run.len()).sum::<usize>() as f64 / len;
+    let unusual = if hex { 1.0 } else { unusual_pairs(&rest) };
+    (spread * uncovered * unusual * 100.0).round() / 100.0
+}
+
+/// The runs of `text` outside words from `WORDS`, matching the longest word
+/// at each position from the left.
+fn outside_words(text: &str) -> Vec<&str> {
+    let mut runs = Vec::new();
+    let mut run_start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let word = WORDS
+            .iter()
+            .filter(|w| text[i..].starts_with(*w))
+            .max_by_key(|w| w.len());
+        match word {
+            Some(word) => {
+                if run_start < i {
+                    runs.push(&text[run_start..i]);
+                }
+                i += word.len();
+                run_start = i;
+            }
+            None => i += 1,
+        }
+    }
+    if run_start < text.len() {
+        runs.push(&text[run_start..]);
+    }
+    runs
+}
+
+/// The share of adjacent pairs within `runs` that are not in
+/// `COMMON_BIGRAMS`; pairs with a digit never are. 1 when there are no
+/// pairs to judge.
+fn unusual_pairs(runs: &[&str]) -> f64 {
+    let pairs: Vec<&str> = runs
+        .iter()
+        .flat_map(|run| (0..run.len().saturating_sub(1)).map(move |i| &run[i..i + 2]))
+        .collect();
+    if pairs.is_empty() {
+        return 1.0;
+    }
+    let common = pairs
+        .iter()
+        .filter(|pair| COMMON_BIGRAMS.split_ascii_whitespace().any(|common| common == **pair))
+        .count();
+    1.0 - common as f64 / pairs.len() as f64
+}
+
+/// The name of the task stored at `path`: its file name without a `.job`
+/// or `.xml` extension. Task XML under `System32\Tasks` has no extension
+/// and is named after the task.
+pub fn task_name(path: &str) -> &str {
+    // Split by hand so Windows paths read on other systems work too.
+    let name = path.rsplit(['\\', '/']).next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext))
+            if !stem.is_empty()
+                && (ext.eq_ignore_ascii_case("job") || ext.eq_ignore_ascii_case("xml")) =>
+        {
+            stem
+        }
+        _ => name,
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    #[cfg(feature = "xml")]
+    Xml(quick_xml::DeError),
+    /// The artifact's format was disabled at compile time; holds the feature name.
+    Unsupported(&'static str),
+    /// A binary job ended before the `len` bytes of a field at `offset`.
+    TruncatedJob { offset: usize, len: usize },
+    /// A binary job's `field` string declares `declared` UTF-16 code units,
+    /// but only `available` are left in the file.
+    StringLengthOutOfBounds { field: &'static str, declared: usize, available: usize },
+    /// A job's `field` string is `len` UTF-16 code units, too many for the
+    /// strings to end within the reach of a binary job's trigger offset.
+    StringTooLong { field: &'static str, len: usize },
+    /// The file has no content at all, as left behind by some collectors.
+    EmptyFile,
+    /// The file is `size` bytes, over the `limit` it was read with; it was
+    /// not read.
+    TooLarge { size: u64, limit: u64 },
+    /// A registry hive is malformed at `offset`.
+    InvalidHive { offset: usize, reason: &'static str },
+    /// Task XML given as a fragment has this root element, which is not
+    /// one `Task::from_fragment` reads; empty if it has no element at all.
+    UnknownFragment(String),
+}
+
+impl Error {
+    /// The variant name, for machine-readable error reports.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "Io",
+            #[cfg(feature = "xml")]
+            Error::Xml(_) => "Xml",
+            Error::Unsupported(_) => "Unsupported",
+            Error::TruncatedJob { .. } => "TruncatedJob",
+            Error::StringLengthOutOfBounds { .. } => "StringLengthOutOfBounds",
+            Error::StringTooLong { .. } => "StringTooLong",
+            Error::EmptyFile => "EmptyFile",
+            Error::TooLarge { .. } => "TooLarge",
+            Error::InvalidHive { .. } => "InvalidHive",
+            Error::UnknownFragment(_) => "UnknownFragment",
+        }
+    }
+
+    /// The byte offset in the artifact the error refers to, when known.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::TruncatedJob { offset, .. } | Error::InvalidHive { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            #[cfg(feature = "xml")]
+            Error::Xml(e) => write!(f, "{}", e),
+            Error::Unsupported(feature) => {
+                write!(f, "support for this format requires the \"{}\" feature", feature)
+            }
+            Error::TruncatedJob { offset, len } => write!(
+                f,
+                "job file is truncated: expected {} bytes at offset {}",
+                len, offset
+            ),
+            Error::StringLengthOutOfBounds { field, declared, available } => write!(
+                f,
+                "job {} is {} characters long, but only {} are left in the file",
+                field, declared, available
+            ),
+            Error::StringTooLong { field, len } => write!(
+                f,
+                "job {} is {} characters long, more than a job file holds",
+                field, len
+            ),
+            Error::EmptyFile => write!(f, "empty file"),
+            Error::TooLarge { size, limit } => write!(
+                f,
+                "file is too large ({} bytes, limit {}); skipped",
+                size, limit
+            ),
+            Error::InvalidHive { offset, reason } => {
+                write!(f, "invalid registry hive: {} at offset {}", reason, offset)
+            }
+            Error::UnknownFragment(root) => {
+                if root.is_empty() {
+                    write!(f, "the fragment has no element")?;
+                } else {
+                    write!(f, "<{}> is not a fragment of task XML", root)?;
+                }
+                write!(
+                    f,
+                    "; expected Triggers, Actions, Settings, a trigger (BootTrigger, \
+                     CalendarTrigger, EventTrigger, IdleTrigger, LogonTrigger, \
+                     RegistrationTrigger, SessionStateChangeTrigger, TimeTrigger) or an \
+                     action (ComHandler, Exec, SendEmail, ShowMessage)"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            #[cfg(feature = "xml")]
+            Error::Xml(e) => Some(e),
+            Error::Unsupported(_)
+            | Error::TruncatedJob { .. }
+            | Error::StringLengthOutOfBounds { .. }
+            | Error::StringTooLong { .. }
+            | Error::EmptyFile
+            | Error::TooLarge { .. }
+            | Error::InvalidHive { .. }
+            | Error::UnknownFragment(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+#[cfg(feature = "xml")]
+impl From<quick_xml::DeError> for Error {
+    fn from(e: quick_xml::DeError) -> Error {
+        Error::Xml(e)
+    }
+}
@@ -0,0 +1,47 @@
+//! Case-insensitive matching of paths, task names and accounts, which
+//! Windows compares without regard to case. Every filter and list that
+//! matches such names goes through `Case`; what is printed keeps the case
+//! it was written in.
+
+use std::borrow::Cow;
+
+/// How names are compared: without regard to case unless asked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Case {
+    #[default]
+    Insensitive,
+    Sensitive,
+}
+
+impl Case {
+    /// What `text` is compared by: itself, or `fold(text)`.
+    pub fn key(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Case::Insensitive => fold(text),
+            Case::Sensitive => Cow::Borrowed(text),
+        }
+    }
+
+    pub fn eq(self, a: &str, b: &str) -> bool {
+        self.key(a) == self.key(b)
+    }
+}
+
+/// `text` with its case folded, so that names differing only in case fold
+/// alike. Each character is uppercased and then lowercased, which folds
+/// `ß` and `SS`, or `ς` and `Σ`, as Unicode case folding does. The Latin
+/// and Turkish `I`, `i`, `İ` and `ı` all fold to `i`, whichever language
+/// wrote the name.
+pub fn fold(text: &str) -> Cow<'_, str> {
+    if !text.bytes().any(|b| b.is_ascii_uppercase() || !b.is_ascii()) {
+        return Cow::Borrowed(text);
+    }
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'İ' | 'ı' => folded.push('i'),
+            _ => folded.extend(c.to_uppercase().flat_map(char::to_lowercase)),
+        }
+    }
+    Cow::Owned(folded)
+}
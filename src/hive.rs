@@ -0,0 +1,304 @@
+//! A minimal reader for offline registry hives (the `regf` format), enough
+//! to walk keys and read values. Transaction logs are not replayed, so a
+//! hive copied from a running system may lack its most recent changes.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::Error;
+
+/// Where the hive bins, and so all cell offsets, start.
+const BINS_OFFSET: usize = 0x1000;
+/// The offset of the root key's cell in the base block.
+const ROOT_CELL: usize = 0x24;
+/// Set on a key or value whose name is stored as Latin-1 rather than UTF-16LE.
+const KEY_COMP_NAME: u16 = 0x0020;
+const VALUE_COMP_NAME: u16 = 0x0001;
+/// Set on a value's data size when the data is kept in the offset field.
+const DATA_IN_OFFSET: u32 = 0x8000_0000;
+/// Values larger than this are split into `db` segments.
+const BIG_DATA_THRESHOLD: usize = 16344;
+/// How deep `ri` index lists may nest before the hive is taken as corrupt.
+const MAX_INDEX_DEPTH: usize = 8;
+/// How many subkeys or values one key may have before the hive is taken as
+/// corrupt, well above what a real key holds.
+const MAX_ENTRIES: usize = 1 << 20;
+
+pub const REG_SZ: u32 = 1;
+pub const REG_EXPAND_SZ: u32 = 2;
+pub const REG_BINARY: u32 = 3;
+pub const REG_DWORD: u32 = 4;
+
+/// A hive held in memory.
+#[derive(Debug, Clone, Copy)]
+pub struct Hive<'a> {
+    data: &'a [u8],
+}
+
+/// A key node (`nk` cell).
+#[derive(Debug, Clone, Copy)]
+pub struct Key<'a> {
+    hive: Hive<'a>,
+    offset: u32,
+    cell: &'a [u8],
+}
+
+/// A value (`vk` cell).
+#[derive(Debug, Clone)]
+pub struct Value<'a> {
+    pub name: String,
+    /// The registry type, such as `REG_SZ`.
+    pub kind: u32,
+    pub data: Cow<'a, [u8]>,
+}
+
+fn invalid(offset: usize, reason: &'static str) -> Error {
+    Error::InvalidHive { offset, reason }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let b = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let b = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// A key or value name, Latin-1 when `compressed` and UTF-16LE otherwise.
+fn name(raw: &[u8], compressed: bool) -> String {
+    if compressed {
+        raw.iter().map(|&b| char::from(b)).collect()
+    } else {
+        utf16(raw)
+    }
+}
+
+fn utf16(raw: &[u8]) -> String {
+    let units: Vec<u16> = raw
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+impl<'a> Hive<'a> {
+    /// Checks the `regf` signature. Cells are only read, and checked, as
+    /// keys and values are looked up.
+    pub fn new(data: &'a [u8]) -> Result<Hive<'a>, Error> {
+        if !data.starts_with(b"regf") {
+            return Err(invalid(0, "missing regf signature"));
+        }
+        if data.len() < BINS_OFFSET {
+            return Err(invalid(data.len(), "truncated base block"));
+        }
+        Ok(Hive { data })
+    }
+
+    pub fn root(&self) -> Result<Key<'a>, Error> {
+        let offset = read_u32(self.data, ROOT_CELL);
+        self.key(offset.ok_or(invalid(ROOT_CELL, "truncated base block"))?)
+    }
+
+    /// The data of the allocated cell at `offset`, relative to the bins.
+    fn cell(&self, offset: u32) -> Result<&'a [u8], Error> {
+        let start = BINS_OFFSET + offset as usize;
+        let size = read_u32(self.data, start).ok_or(invalid(start, "cell out of range"))? as i32;
+        // Allocated cells have a negative size, which includes the size field.
+        if size >= 0 {
+            return Err(invalid(start, "cell is not allocated"));
+        }
+        let len = size.unsigned_abs() as usize;
+        self.data
+            .get(start + 4..start + len.max(4))
+            .ok_or(invalid(start, "cell out of range"))
+    }
+
+    fn key(&self, offset: u32) -> Result<Key<'a>, Error> {
+        let cell = self.cell(offset)?;
+        if !cell.starts_with(b"nk") || cell.len() < 0x4C {
+            return Err(invalid(BINS_OFFSET + offset as usize, "expected a key node"));
+        }
+        Ok(Key { hive: *self, offset, cell })
+    }
+
+    /// Appends the key offsets of the subkey list at `offset`. `visited`
+    /// holds the lists already read, as an `ri` list naming one twice would
+    /// otherwise be read over and over.
+    fn subkey_offsets(
+        &self,
+        offset: u32,
+        depth: usize,
+        visited: &mut HashSet<u32>,
+        out: &mut Vec<u32>,
+    ) -> Result<(), Error> {
+        let at = BINS_OFFSET + offset as usize;
+        if depth > MAX_INDEX_DEPTH {
+            return Err(invalid(at, "subkey index nested too deep"));
+        }
+        if !visited.insert(offset) {
+            return Err(invalid(at, "subkey list referenced twice"));
+        }
+        let cell = self.cell(offset)?;
+        let count = read_u16(cell, 2).ok_or(invalid(at, "truncated subkey list"))? as usize;
+        let (stride, nested) = match cell.get(..2) {
+            Some(b"lf" | b"lh") => (8, false),
+            Some(b"li") => (4, false),
+            Some(b"ri") => (4, true),
+            _ => return Err(invalid(at, "unknown subkey list")),
+        };
+        for i in 0..count {
+            let entry = read_u32(cell, 4 + i * stride).ok_or(invalid(at, "truncated subkey list"))?;
+            if nested {
+                self.subkey_offsets(entry, depth + 1, visited, out)?;
+            } else if out.len() == MAX_ENTRIES {
+                return Err(invalid(at, "too many subkeys"));
+            } else {
+                out.push(entry);
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&self, offset: u32) -> Result<Value<'a>, Error> {
+        let at = BINS_OFFSET + offset as usize;
+        let cell = self.cell(offset)?;
+        if !cell.starts_with(b"vk") || cell.len() < 0x14 {
+            return Err(invalid(at, "expected a value"));
+        }
+        let truncated = || invalid(at, "truncated value");
+        let name_len = read_u16(cell, 2).ok_or_else(truncated)? as usize;
+        let size = read_u32(cell, 4).ok_or_else(truncated)?;
+        let data_offset = read_u32(cell, 8).ok_or_else(truncated)?;
+        let kind = read_u32(cell, 12).ok_or_else(truncated)?;
+        let flags = read_u16(cell, 16).ok_or_else(truncated)?;
+        let raw_name = cell.get(0x14..0x14 + name_len).ok_or_else(truncated)?;
+        let data = if size & DATA_IN_OFFSET != 0 {
+            let len = ((size & !DATA_IN_OFFSET) as usize).min(4);
+            Cow::Owned(cell[8..8 + len].to_vec())
+        } else if size as usize > BIG_DATA_THRESHOLD {
+            Cow::Owned(self.big_data(data_offset, size as usize)?)
+        } else {
+            let data = self.cell(data_offset)?;
+            Cow::Borrowed(data.get(..size as usize).ok_or_else(truncated)?)
+        };
+        Ok(Value {
+            name: name(raw_name, flags & VALUE_COMP_NAME != 0),
+            kind,
+            data,
+        })
+    }
+
+    /// Joins the segments of a `db` cell.
+    fn big_data(&self, offset: u32, size: usize) -> Result<Vec<u8>, Error> {
+        let at = BINS_OFFSET + offset as usize;
+        let cell = self.cell(offset)?;
+        if !cell.starts_with(b"db") {
+            return Err(invalid(at, "expected big data"));
+        }
+        let count = read_u16(cell, 2).ok_or(invalid(at, "truncated big data"))? as usize;
+        let list = self.cell(read_u32(cell, 4).ok_or(invalid(at, "truncated big data"))?)?;
+        // `size` is only as good as the cell it came from.
+        let mut data = Vec::with_capacity(size.min(count * BIG_DATA_THRESHOLD));
+        for i in 0..count {
+            let segment = read_u32(list, i * 4).ok_or(invalid(at, "truncated big data"))?;
+            let segment = self.cell(segment)?;
+            let wanted = (size - data.len()).min(BIG_DATA_THRESHOLD).min(segment.len());
+            data.extend_from_slice(&segment[..wanted]);
+        }
+        if data.len() < size {
+            return Err(invalid(at, "truncated big data"));
+        }
+        Ok(data)
+    }
+}
+
+impl<'a> Key<'a> {
+    /// The offset of the key's cell, relative to the bins, which tells keys
+    /// apart.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    pub fn name(&self) -> String {
+        let len = read_u16(self.cell, 0x48).unwrap_or(0) as usize;
+        let flags = read_u16(self.cell, 0x02).unwrap_or(0);
+        let raw = self.cell.get(0x4C..0x4C + len).unwrap_or_default();
+        name(raw, flags & KEY_COMP_NAME != 0)
+    }
+
+    /// The last write time, as a FILETIME.
+    pub fn timestamp(&self) -> u64 {
+        let b = &self.cell[4..12];
+        u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+    }
+
+    pub fn subkeys(&self) -> Result<Vec<Key<'a>>, Error> {
+        let count = read_u32(self.cell, 0x14).unwrap_or(0);
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let mut offsets = Vec::new();
+        let list = read_u32(self.cell, 0x1C).unwrap_or(0);
+        self.hive.subkey_offsets(list, 0, &mut HashSet::new(), &mut offsets)?;
+        offsets.into_iter().map(|offset| self.hive.key(offset)).collect()
+    }
+
+    /// The subkey called `name`, compared case-insensitively as Windows does.
+    pub fn subkey(&self, name: &str) -> Result<Option<Key<'a>>, Error> {
+        Ok(self.subkeys()?.into_iter().find(|key| key.name().eq_ignore_ascii_case(name)))
+    }
+
+    /// Follows `path`, a `\`-separated list of subkey names.
+    pub fn descend(&self, path: &str) -> Result<Option<Key<'a>>, Error> {
+        let mut key = *self;
+        for name in path.split('\\').filter(|name| !name.is_empty()) {
+            match key.subkey(name)? {
+                Some(subkey) => key = subkey,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(key))
+    }
+
+    pub fn values(&self) -> Result<Vec<Value<'a>>, Error> {
+        let count = read_u32(self.cell, 0x24).unwrap_or(0) as usize;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let list_offset = read_u32(self.cell, 0x28).unwrap_or(0);
+        if count > MAX_ENTRIES {
+            return Err(invalid(BINS_OFFSET + list_offset as usize, "too many values"));
+        }
+        let list = self.hive.cell(list_offset)?;
+        (0..count)
+            .map(|i| {
+                let offset = read_u32(list, i * 4)
+                    .ok_or(invalid(BINS_OFFSET + list_offset as usize, "truncated value list"))?;
+                self.hive.value(offset)
+            })
+            .collect()
+    }
+
+    /// The value called `name`, compared case-insensitively; the default
+    /// value is called `""`.
+    pub fn value(&self, name: &str) -> Result<Option<Value<'a>>, Error> {
+        Ok(self.values()?.into_iter().find(|value| value.name.eq_ignore_ascii_case(name)))
+    }
+}
+
+impl Value<'_> {
+    /// The data of a `REG_SZ` or `REG_EXPAND_SZ` value, up to its first NUL.
+    pub fn string(&self) -> Option<String> {
+        if self.kind != REG_SZ && self.kind != REG_EXPAND_SZ {
+            return None;
+        }
+        let text = utf16(&self.data);
+        Some(text.split('\0').next().unwrap_or_default().to_string())
+    }
+
+    pub fn dword(&self) -> Option<u32> {
+        Some(self.kind).filter(|&kind| kind == REG_DWORD).and_then(|_| read_u32(&self.data, 0))
+    }
+}
@@ -0,0 +1,246 @@
+//! Secondary payloads named in a task's arguments: script files, URLs and
+//! UNC paths. A task that runs `powershell -File \\host\share\a.ps1` or
+//! downloads from a URL points at what it really runs, and these are the
+//! values to look for elsewhere.
+//!
+//! Arguments are split at quotes, so `"C:\My Scripts\a.ps1"` is one path,
+//! then at whitespace. Trailing punctuation is dropped from URLs unless it
+//! closes a bracket the URL opened. Paths written with doubled backslashes
+//! (`C:\\Scripts\\a.ps1`), as in escaped strings, are read with single ones.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::fold::fold;
+
+/// Extensions of files that Windows hands to a script host.
+pub const SCRIPT_EXTENSIONS: &[&str] = &[
+    "bat", "cmd", "hta", "js", "jse", "ps1", "psd1", "psm1", "vbe", "vbs", "wsf", "wsh",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum IndicatorKind {
+    /// A local or relative path to a file with a `SCRIPT_EXTENSIONS`
+    /// extension.
+    Script,
+    /// An `http` or `https` URL.
+    Url,
+    /// A path on a network share, `\\server\share\...`.
+    Unc,
+}
+
+impl IndicatorKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            IndicatorKind::Script => "script",
+            IndicatorKind::Url => "url",
+            IndicatorKind::Unc => "unc",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Indicator {
+    pub kind: IndicatorKind,
+    pub value: String,
+}
+
+/// The indicators in `texts`, in the order they first appear. Paths that
+/// differ only in letter case are listed once.
+pub fn extract_indicators<'a>(texts: impl IntoIterator<Item = &'a str>) -> Vec<Indicator> {
+    let mut found = Vec::new();
+    for text in texts {
+        scan(text, 0, &mut found);
+    }
+    found
+}
+
+/// Adds the indicators in `text` to `found`. Quotes inside quotes, as in
+/// `-Command "& 'C:\My Scripts\a.ps1'"`, are looked into a level or two.
+fn scan(text: &str, depth: usize, found: &mut Vec<Indicator>) {
+    for (segment, quoted) in segments(text) {
+        if quoted {
+            if let Some(indicator) = classify_quoted(segment) {
+                add(found, indicator);
+                continue;
+            }
+            if depth < 2 && segment.contains(['"', '\'']) {
+                scan(segment, depth + 1, found);
+                continue;
+            }
+        }
+        for indicator in segment.split_whitespace().filter_map(classify) {
+            add(found, indicator);
+        }
+    }
+}
+
+fn add(found: &mut Vec<Indicator>, indicator: Indicator) {
+    if !found.iter().any(|seen| same(seen, &indicator)) {
+        found.push(indicator);
+    }
+}
+
+fn same(a: &Indicator, b: &Indicator) -> bool {
+    a.kind == b.kind
+        && match a.kind {
+            IndicatorKind::Url => a.value == b.value,
+            _ => fold(&a.value) == fold(&b.value),
+        }
+}
+
+/// `text` split at double and single quotes, with whether each piece was
+/// quoted. A backslash before a quote, as in `\"`, escapes the quote for
+/// the program and is dropped.
+fn segments(text: &str) -> Vec<(&str, bool)> {
+    let mut segments = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        let closes = quote == Some(c);
+        if closes || (quote.is_none() && (c == '"' || c == '\'')) {
+            let piece = &text[start..i];
+            let piece = if piece.ends_with("\\\\") { piece } else { piece.trim_end_matches('\\') };
+            segments.push((piece, closes));
+            quote = if closes { None } else { Some(c) };
+            start = i + 1;
+        }
+    }
+    segments.push((&text[start..], quote.is_some()));
+    segments
+}
+
+/// A quoted string that is a path as a whole, spaces and all.
+fn classify_quoted(segment: &str) -> Option<Indicator> {
+    let segment = segment.trim();
+    if !segment.contains(char::is_whitespace) {
+        return classify(segment);
+    }
+    // URLs hold no spaces, and a path after other words is only taken up
+    // to the next space.
+    if find_url(segment).is_some() || path_part(segment)? != segment {
+        return None;
+    }
+    classify_path(segment)
+}
+
+fn classify(word: &str) -> Option<Indicator> {
+    let word = word.trim_start_matches(['(', '[', '{', '&', '@', ',', ';']);
+    if let Some(start) = find_url(word) {
+        return Some(Indicator {
+            kind: IndicatorKind::Url,
+            value: trim_url(&word[start..]).to_string(),
+        });
+    }
+    let word = word.trim_end_matches([')', ']', '}', ',', ';']);
+    classify_path(path_part(word)?)
+}
+
+fn classify_path(path: &str) -> Option<Indicator> {
+    let path = unescape(path);
+    let kind = if is_unc(&path) {
+        IndicatorKind::Unc
+    } else if has_script_extension(&path) {
+        IndicatorKind::Script
+    } else {
+        return None;
+    };
+    Some(Indicator { kind, value: path })
+}
+
+fn find_url(word: &str) -> Option<usize> {
+    let lower = word.to_ascii_lowercase();
+    ["https://", "http://"]
+        .iter()
+        .filter_map(|scheme| lower.find(scheme).map(|start| (start, start + scheme.len())))
+        .filter(|&(_, host)| host < word.len())
+        .map(|(start, _)| start)
+        .min()
+}
+
+/// `url` without the punctuation that ends the sentence or expression it
+/// sits in. A closing bracket stays if the URL has a matching opening one.
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let Some(last) = url.chars().last() else {
+            return url;
+        };
+        let opening = match last {
+            ')' => '(',
+            ']' => '[',
+            '}' => '{',
+            '.' | ',' | ';' | ':' | '!' | '?' | '>' | '`' => {
+                url = &url[..url.len() - 1];
+                continue;
+            }
+            _ => return url,
+        };
+        if url.matches(opening).count() >= url.matches(last).count() {
+            return url;
+        }
+        url = &url[..url.len() - 1];
+    }
+}
+
+/// The path in `word`, which may follow a `-File:` or `name=` prefix.
+fn path_part(word: &str) -> Option<&str> {
+    let word = match word.rfind('=') {
+        Some(i) => &word[i + 1..],
+        None => word,
+    };
+    let bytes = word.as_bytes();
+    let drive = (1..bytes.len().saturating_sub(1)).find(|&i| {
+        bytes[i] == b':' && bytes[i - 1].is_ascii_alphabetic() && matches!(bytes[i + 1], b'\\' | b'/')
+    });
+    let path = match (drive, word.find("\\\\")) {
+        (Some(d), Some(u)) if u < d => &word[u..],
+        (Some(d), _) => &word[d - 1..],
+        (None, Some(u)) => &word[u..],
+        (None, None) if word.starts_with(['-', '/']) => match word.split_once(':') {
+            Some((_, rest)) => rest,
+            None => word,
+        },
+        (None, None) => word,
+    };
+    Some(path).filter(|path| !path.is_empty())
+}
+
+/// `path` with doubled backslashes halved, if it was written escaped: that
+/// is, if any separator after a leading `\\` is doubled.
+fn unescape(path: &str) -> String {
+    let body = path.trim_start_matches('\\');
+    if body.contains("\\\\") {
+        path.replace("\\\\", "\\")
+    } else {
+        path.to_string()
+    }
+}
+
+fn is_unc(path: &str) -> bool {
+    let Some(rest) = path.strip_prefix("\\\\") else {
+        return false;
+    };
+    let mut parts = rest.split('\\');
+    let server = parts.next().unwrap_or("");
+    let share = parts.next().unwrap_or("");
+    let valid = |part: &str| {
+        !part.is_empty() && !part.contains(['/', ':', '*', '?', '"', '<', '>', '|'])
+    };
+    valid(server) && valid(share) && server != "." && server != "?"
+}
+
+fn has_script_extension(path: &str) -> bool {
+    let name = path.rsplit(['\\', '/']).next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) => {
+            !stem.is_empty()
+                && SCRIPT_EXTENSIONS.iter().any(|script| ext.eq_ignore_ascii_case(script))
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,72 @@
+//! File contents for the parsers, either read into memory or memory-mapped.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::ops::Deref;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::path::os_path;
+
+/// Files at least this large are mapped by `Input::open` rather than read.
+pub const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+/// The bytes of an input, dereferencing to `[u8]`.
+pub enum Input {
+    Buffered(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Input {
+    /// Reads `path`, mapping it instead if it is a regular file of at least
+    /// `MMAP_THRESHOLD` bytes.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Input> {
+        Input::open_with_threshold(path.as_ref(), MMAP_THRESHOLD)
+    }
+
+    /// Maps `path` whatever its size, for callers such as carving that scan
+    /// large images. Falls back to reading sources that cannot be mapped,
+    /// such as pipes.
+    pub fn map<P: AsRef<Path>>(path: P) -> io::Result<Input> {
+        Input::open_with_threshold(path.as_ref(), 0)
+    }
+
+    /// Reads everything from `reader`, for stdin and other streams.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Input> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(Input::Buffered(data))
+    }
+
+    fn open_with_threshold(path: &Path, threshold: u64) -> io::Result<Input> {
+        let file = File::open(os_path(path))?;
+        let metadata = file.metadata()?;
+        // Mapping a zero-length file fails on some platforms, and there is
+        // nothing to gain from it anyway.
+        if metadata.is_file() && metadata.len() > 0 && metadata.len() >= threshold {
+            // SAFETY: the map is only read. If another process truncates the
+            // file while it is mapped, reads past the new end fault; that is
+            // accepted for evidence files, which are not expected to change.
+            if let Ok(map) = unsafe { Mmap::map(&file) } {
+                return Ok(Input::Mapped(map));
+            }
+        }
+        Input::read(file)
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, Input::Mapped(_))
+    }
+}
+
+impl Deref for Input {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Input::Buffered(data) => data,
+            Input::Mapped(map) => map,
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! What notable combinations of a job's flags mean for an analyst, beyond
+//! the flag names themselves. Each entry of `INTERPRETATIONS` is a test and
+//! the sentence to give when it holds; adding one is adding a line there.
+
+use crate::date;
+use crate::job::{Job, TaskFlag, TaskStatus};
+
+/// How many days before `now` a run counts as recent.
+pub const RECENT_RUN_DAYS: i64 = 30;
+
+/// A combination of flags worth spelling out.
+pub struct Interpretation {
+    /// Whether it applies to a job, as of `now` (`YYYY-MM-DDTHH:MM:SS`).
+    pub applies: fn(&Job, &str) -> bool,
+    /// What it means for that job.
+    pub sentence: fn(&Job) -> String,
+}
+
+/// The combinations, in the order their sentences are given.
+pub const INTERPRETATIONS: &[Interpretation] = &[
+    Interpretation {
+        applies: |job, _| has(job, TaskFlag::DeleteWhenDone) && !no_more_runs(job),
+        sentence: |_| {
+            "TASK_FLAG_DELETE_WHEN_DONE is set: Task Scheduler deletes this job after its last \
+             scheduled run, so the file may be gone when the system is next examined"
+                .into()
+        },
+    },
+    Interpretation {
+        applies: |job, _| has(job, TaskFlag::DeleteWhenDone) && no_more_runs(job),
+        sentence: |_| {
+            "TASK_FLAG_DELETE_WHEN_DONE is set and no more runs are scheduled: Task Scheduler \
+             was due to delete this job, so finding it at all is worth noting"
+                .into()
+        },
+    },
+    Interpretation {
+        applies: |job, now| has(job, TaskFlag::Disabled) && ran_recently(job, now),
+        sentence: |job| {
+            format!(
+                "TASK_FLAG_DISABLED is set, yet the job last ran at {}: it was disabled since, \
+                 or the flag was set to make it look inactive",
+                job.run_date.format_iso()
+            )
+        },
+    },
+    Interpretation {
+        applies: |job, _| has(job, TaskFlag::Hidden) && has(job, TaskFlag::Interactive),
+        sentence: |_| {
+            "TASK_FLAG_HIDDEN and TASK_FLAG_INTERACTIVE are both set: the job is left out of \
+             the Scheduled Tasks folder, but runs in view of the logged-on user"
+                .into()
+        },
+    },
+];
+
+/// The sentences of the `INTERPRETATIONS` that apply to `job` as of `now`.
+pub fn interpret(job: &Job, now: &str) -> Vec<String> {
+    INTERPRETATIONS
+        .iter()
+        .filter(|interpretation| (interpretation.applies)(job, now))
+        .map(|interpretation| (interpretation.sentence)(job))
+        .collect()
+}
+
+fn has(job: &Job, flag: TaskFlag) -> bool {
+    job.flag_names().contains(&flag)
+}
+
+fn no_more_runs(job: &Job) -> bool {
+    job.status_name() == Some(TaskStatus::NoMoreRuns)
+}
+
+/// Whether `job` last ran within `RECENT_RUN_DAYS` before `now`.
+fn ran_recently(job: &Job, now: &str) -> bool {
+    if job.run_date.year == 0 {
+        return false;
+    }
+    let run = date::local_seconds(&job.run_date.format_iso());
+    match (run, date::local_seconds(now)) {
+        (Some(run), Some(now)) => (0..=RECENT_RUN_DAYS * 86400).contains(&(now - run)),
+        _ => false,
+    }
+}
@@ -0,0 +1,1376 @@
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "schema")]
+use std::borrow::Cow;
+#[cfg(feature = "serde")]
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use crate::consts::{self, fixed, flags, state, status, systemtime, trigger, variable, Field};
+use crate::consts::trigger::TriggerType;
+use crate::date::{self, days_from_civil, DateStyle, Fields};
+use crate::enabled::{self, Enablement};
+use crate::schedule::{Recurrence, Repetition, Schedule, TimeTrigger, TriggerDetail};
+use crate::warning::TriggerSchedule;
+use crate::Error;
+
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+mod sections;
+
+#[cfg(feature = "unstable")]
+pub use sections::{sections, Section};
+#[cfg(not(feature = "unstable"))]
+pub(crate) use sections::sections;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JobDate {
+    pub year: u16,
+    pub month: u16,
+    pub weekday: Option<u16>,
+    pub day: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+}
+
+impl JobDate {
+    pub fn new(data: &[u8], scheduled: bool) -> JobDate {
+        let word = |field: Field| u16::from_le_bytes([data[field.offset], data[field.offset + 1]]);
+        let year = word(systemtime::YEAR);
+        let month = word(systemtime::MONTH);
+        let weekday = if !scheduled {
+            Some(word(systemtime::DAY_OF_WEEK))
+        } else {
+            None
+        };
+        let day = word(systemtime::DAY);
+        let hour = word(systemtime::HOUR);
+        let minute = word(systemtime::MINUTE);
+        let second = word(systemtime::SECOND);
+        JobDate {
+            year,
+            month,
+            weekday,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    /// Formats the date like `Friday Mar 1 02:13:00 2024`. A month or weekday
+    /// outside the valid range falls back to `format_iso`.
+    pub fn format_date(&self) -> String {
+        self.format_with(DateStyle::Classic)
+    }
+
+    /// Formats the date in `style`, falling back to `format_iso` like
+    /// `format_date`.
+    pub fn format_with(&self, style: DateStyle) -> String {
+        self.fields().format(style)
+    }
+
+    /// Formats the date as ISO 8601 without a zone, e.g. `2024-03-04T02:13:00`.
+    pub fn format_iso(&self) -> String {
+        self.fields().format_iso()
+    }
+
+    /// The day of the week, 0 for Sunday, the date falls on, worked out from
+    /// its year, month and day. `weekday` is only what the writing system
+    /// stored, and is what the formatted date shows. `None` for a date that
+    /// does not exist, such as 30 February or the zeros of `never`.
+    pub fn calendar_weekday(&self) -> Option<u16> {
+        date::day_of_week(self.year, self.month, self.day)
+    }
+
+    /// The stored weekday and the one the date falls on, if they differ.
+    /// Task Scheduler writes the two together, so a difference means the
+    /// date was written by something else, such as a hand edit.
+    pub fn weekday_mismatch(&self) -> Option<(u16, u16)> {
+        let (stored, actual) = (self.weekday?, self.calendar_weekday()?);
+        (stored != actual).then_some((stored, actual))
+    }
+
+    fn fields(&self) -> Fields {
+        Fields {
+            year: self.year,
+            month: self.month,
+            weekday: self.weekday,
+            day: self.day,
+            hour: self.hour,
+            minute: self.minute,
+            second: self.second,
+        }
+    }
+}
+
+impl JobDate {
+    /// The all-zero date Task Scheduler stores for a job that has never run.
+    pub fn never(scheduled: bool) -> JobDate {
+        JobDate {
+            year: 0,
+            month: 0,
+            weekday: if scheduled { None } else { Some(0) },
+            day: 0,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        }
+    }
+
+    /// Encodes the date as a 16-byte SYSTEMTIME; the milliseconds are zero.
+    fn to_bytes(&self) -> [u8; 16] {
+        let words = [
+            self.year,
+            self.month,
+            self.weekday.unwrap_or(0),
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            0,
+        ];
+        let mut out = [0; 16];
+        for (chunk, word) in out.chunks_exact_mut(2).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for JobDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.format_iso())
+    }
+}
+
+/// Reads the ISO form back. The weekday is not serialized, so it is worked
+/// out from the date, or `None` for an invalid one; `Job` fixes it up for
+/// `scheduled_date` and never-run dates.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for JobDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let fields = crate::date::parse_iso(&text)
+            .ok_or_else(|| de::Error::custom(format!("invalid job date {:?}", text)))?;
+        Ok(JobDate {
+            year: fields.year,
+            month: fields.month,
+            weekday: fields.weekday,
+            day: fields.day,
+            hour: fields.hour,
+            minute: fields.minute,
+            second: fields.second,
+        })
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for JobDate {
+    fn schema_name() -> Cow<'static, str> {
+        "JobDate".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "An ISO 8601 timestamp without a zone; all zeros if the job has never run."
+        })
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UUID {
+    pub uuid0: u32,
+    pub uuid1: u16,
+    pub uuid2: u16,
+    pub uuid3: u16,
+    pub uuid4: u16,
+    pub uuid5: u16,
+    pub uuid6: u16,
+}
+
+impl UUID {
+    pub fn new(data: &[u8]) -> UUID {
+        UUID {
+            uuid0: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            uuid1: u16::from_le_bytes([data[4], data[5]]),
+            uuid2: u16::from_le_bytes([data[6], data[7]]),
+            uuid3: u16::from_be_bytes([data[8], data[9]]),
+            uuid4: u16::from_be_bytes([data[10], data[11]]),
+            uuid5: u16::from_be_bytes([data[12], data[13]]),
+            uuid6: u16::from_be_bytes([data[14], data[15]]),
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; 16] {
+        let mut out = [0; 16];
+        out[0..4].copy_from_slice(&self.uuid0.to_le_bytes());
+        out[4..6].copy_from_slice(&self.uuid1.to_le_bytes());
+        out[6..8].copy_from_slice(&self.uuid2.to_le_bytes());
+        out[8..10].copy_from_slice(&self.uuid3.to_be_bytes());
+        out[10..12].copy_from_slice(&self.uuid4.to_be_bytes());
+        out[12..14].copy_from_slice(&self.uuid5.to_be_bytes());
+        out[14..16].copy_from_slice(&self.uuid6.to_be_bytes());
+        out
+    }
+
+    /// Reads the `{XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX}` form written by
+    /// `format_uuid`; the braces are optional and case does not matter.
+    pub fn parse_uuid(text: &str) -> Option<UUID> {
+        let text = text.strip_prefix('{').unwrap_or(text);
+        let text = text.strip_suffix('}').unwrap_or(text);
+        let groups: Vec<&str> = text.split('-').collect();
+        let [a, b, c, d, e] = groups[..] else {
+            return None;
+        };
+        let hex = |group: &str, len: usize| {
+            group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit())
+        };
+        if !(hex(a, 8) && hex(b, 4) && hex(c, 4) && hex(d, 4) && hex(e, 12)) {
+            return None;
+        }
+        let word = |group: &str| u16::from_str_radix(group, 16).ok();
+        Some(UUID {
+            uuid0: u32::from_str_radix(a, 16).ok()?,
+            uuid1: word(b)?,
+            uuid2: word(c)?,
+            uuid3: word(d)?,
+            uuid4: word(&e[0..4])?,
+            uuid5: word(&e[4..8])?,
+            uuid6: word(&e[8..12])?,
+        })
+    }
+
+    pub fn format_uuid(&self) -> String {
+        self.format(UuidFormat::BracedUpper)
+    }
+
+    /// The GUID in `format`, such as `{0A1B2C3D-...}` or `0a1b2c3d-...`.
+    pub fn format(&self, format: UuidFormat) -> String {
+        let plain = format!(
+            "{:08X}-{:04X}-{:04X}-{:04X}-{:04X}{:04X}{:04X}",
+            self.uuid0, self.uuid1, self.uuid2, self.uuid3, self.uuid4, self.uuid5, self.uuid6
+        );
+        match format {
+            UuidFormat::BracedUpper => format!("{{{}}}", plain),
+            UuidFormat::BracedLower => format!("{{{}}}", plain.to_ascii_lowercase()),
+            UuidFormat::PlainUpper => plain,
+            UuidFormat::PlainLower => plain.to_ascii_lowercase(),
+        }
+    }
+}
+
+/// How `UUID::format` writes a GUID: in braces as Microsoft tools show it,
+/// or bare, in either case. Records serialize the braced uppercase form
+/// outside `UuidFormat::scope`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UuidFormat {
+    #[default]
+    BracedUpper,
+    BracedLower,
+    PlainUpper,
+    PlainLower,
+}
+
+#[cfg(feature = "serde")]
+thread_local! {
+    /// The format `UUID` serializes in on this thread.
+    static SERIALIZED: Cell<UuidFormat> = const { Cell::new(UuidFormat::BracedUpper) };
+}
+
+#[cfg(feature = "serde")]
+impl UuidFormat {
+    /// Runs `f` with every `UUID` it serializes on this thread written in
+    /// this format, such as a record's `uuid` in `serde_json::to_string`.
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        /// Puts the previous format back, even if `f` panics.
+        struct Restore(UuidFormat);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SERIALIZED.with(|format| format.set(self.0));
+            }
+        }
+        let _restore = Restore(SERIALIZED.with(|format| format.replace(self)));
+        f()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for UUID {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.format(SERIALIZED.with(Cell::get)))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for UUID {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        UUID::parse_uuid(&text).ok_or_else(|| de::Error::custom(format!("invalid UUID {:?}", text)))
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for UUID {
+    fn schema_name() -> Cow<'static, str> {
+        "Uuid".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "The job's GUID in braces, as `format_uuid` writes it."
+        })
+    }
+}
+
+/// The `SCHED_S_TASK_*` status codes Task Scheduler records in a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum TaskStatus {
+    Ready,
+    Running,
+    Disabled,
+    HasNotRun,
+    NoMoreRuns,
+    NotScheduled,
+    Terminated,
+    NoValidTriggers,
+    EventTrigger,
+}
+
+impl TaskStatus {
+    /// Maps a `SCHED_S_TASK_*` code to its status.
+    pub fn from_code(code: i32) -> Option<TaskStatus> {
+        let status = match code {
+            status::READY => TaskStatus::Ready,
+            status::RUNNING => TaskStatus::Running,
+            status::DISABLED => TaskStatus::Disabled,
+            status::HAS_NOT_RUN => TaskStatus::HasNotRun,
+            status::NO_MORE_RUNS => TaskStatus::NoMoreRuns,
+            status::NOT_SCHEDULED => TaskStatus::NotScheduled,
+            status::TERMINATED => TaskStatus::Terminated,
+            status::NO_VALID_TRIGGERS => TaskStatus::NoValidTriggers,
+            status::EVENT_TRIGGER => TaskStatus::EventTrigger,
+            _ => return None,
+        };
+        Some(status)
+    }
+}
+
+/// The `TASK_FLAG_*` bits of a job's flags field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum TaskFlag {
+    Interactive,
+    DeleteWhenDone,
+    Disabled,
+    StartOnlyIfIdle,
+    KillOnIdleEnd,
+    DontStartIfOnBatteries,
+    KillIfGoingOnBatteries,
+    RunOnlyIfDocked,
+    Hidden,
+    RunIfConnectedToInternet,
+    RestartOnIdleResume,
+    SystemRequired,
+    RunOnlyIfLoggedOn,
+}
+
+impl TaskFlag {
+    /// Each flag with its bit in the flags field and its constant name in
+    /// the Task Scheduler SDK, in bit order.
+    const BITS: [(TaskFlag, u32, &'static str); 13] = [
+        (TaskFlag::Interactive, flags::INTERACTIVE, "TASK_FLAG_INTERACTIVE"),
+        (TaskFlag::DeleteWhenDone, flags::DELETE_WHEN_DONE, "TASK_FLAG_DELETE_WHEN_DONE"),
+        (TaskFlag::Disabled, flags::DISABLED, "TASK_FLAG_DISABLED"),
+        (TaskFlag::StartOnlyIfIdle, flags::START_ONLY_IF_IDLE, "TASK_FLAG_START_ONLY_IF_IDLE"),
+        (TaskFlag::KillOnIdleEnd, flags::KILL_ON_IDLE_END, "TASK_FLAG_KILL_ON_IDLE_END"),
+        (
+            TaskFlag::DontStartIfOnBatteries,
+            flags::DONT_START_IF_ON_BATTERIES,
+            "TASK_FLAG_DONT_START_IF_ON_BATTERIES",
+        ),
+        (
+            TaskFlag::KillIfGoingOnBatteries,
+            flags::KILL_IF_GOING_ON_BATTERIES,
+            "TASK_FLAG_KILL_IF_GOING_ON_BATTERIES",
+        ),
+        (TaskFlag::RunOnlyIfDocked, flags::RUN_ONLY_IF_DOCKED, "TASK_FLAG_RUN_ONLY_IF_DOCKED"),
+        (TaskFlag::Hidden, flags::HIDDEN, "TASK_FLAG_HIDDEN"),
+        (
+            TaskFlag::RunIfConnectedToInternet,
+            flags::RUN_IF_CONNECTED_TO_INTERNET,
+            "TASK_FLAG_RUN_IF_CONNECTED_TO_INTERNET",
+        ),
+        (
+            TaskFlag::RestartOnIdleResume,
+            flags::RESTART_ON_IDLE_RESUME,
+            "TASK_FLAG_RESTART_ON_IDLE_RESUME",
+        ),
+        (TaskFlag::SystemRequired, flags::SYSTEM_REQUIRED, "TASK_FLAG_SYSTEM_REQUIRED"),
+        (
+            TaskFlag::RunOnlyIfLoggedOn,
+            flags::RUN_ONLY_IF_LOGGED_ON,
+            "TASK_FLAG_RUN_ONLY_IF_LOGGED_ON",
+        ),
+    ];
+
+    /// The flags set in `flags`, in bit order.
+    pub fn from_bits(flags: u32) -> Vec<TaskFlag> {
+        TaskFlag::BITS
+            .iter()
+            .filter(|(_, bit, _)| flags & bit != 0)
+            .map(|(flag, _, _)| *flag)
+            .collect()
+    }
+
+    /// The SDK constant, such as `TASK_FLAG_HIDDEN`.
+    pub fn constant_name(self) -> &'static str {
+        TaskFlag::BITS
+            .iter()
+            .find(|(flag, _, _)| *flag == self)
+            .map_or("", |(_, _, name)| name)
+    }
+}
+
+/// The state bits Task Scheduler keeps in a job's flags field, apart from
+/// its `TASK_FLAG_*` bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum TaskStateBit {
+    ApplicationName,
+}
+
+impl TaskStateBit {
+    /// Each state bit with its bit in the flags field and its constant name
+    /// in the Task Scheduler SDK.
+    const BITS: [(TaskStateBit, u32, &'static str); 1] =
+        [(TaskStateBit::ApplicationName, state::APPLICATION_NAME, "TASK_APPLICATION_NAME")];
+
+    /// The state bits set in `flags`, in bit order.
+    pub fn from_bits(flags: u32) -> Vec<TaskStateBit> {
+        TaskStateBit::BITS
+            .iter()
+            .filter(|(_, bit, _)| flags & bit != 0)
+            .map(|(bit, _, _)| *bit)
+            .collect()
+    }
+
+    /// The SDK constant, such as `TASK_APPLICATION_NAME`.
+    pub fn constant_name(self) -> &'static str {
+        TaskStateBit::BITS
+            .iter()
+            .find(|(bit, _, _)| *bit == self)
+            .map_or("", |(_, _, name)| name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Job {
+    pub product_info: u16,
+    pub file_version: u16,
+    pub uuid: UUID,
+    pub priority: u32,
+    pub max_run_time: i32,
+    pub exit_code: i32,
+    pub status: i32,
+    pub flags: u32,
+    pub run_date: JobDate,
+    /// When the earliest enabled trigger first fires, from its begin date
+    /// and start time; never without one. See `first_start`.
+    pub scheduled_date: JobDate,
+    pub name: String,
+    pub parameters: String,
+    pub working_directory: String,
+    pub user: String,
+    pub comment: String,
+}
+
+/// The serialized form of a `Job`: its fields plus the decoded status and
+/// flags.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[cfg_attr(
+    feature = "schema",
+    derive(schemars::JsonSchema),
+    schemars(rename = "Job")
+)]
+struct JobRecord<'a> {
+    product_info: u16,
+    file_version: u16,
+    uuid: &'a UUID,
+    priority: u32,
+    max_run_time: i32,
+    exit_code: i32,
+    status: i32,
+    status_name: Option<TaskStatus>,
+    flags: u32,
+    flag_names: Vec<TaskFlag>,
+    run_date: &'a JobDate,
+    scheduled_date: &'a JobDate,
+    name: &'a str,
+    parameters: &'a str,
+    working_directory: &'a str,
+    user: &'a str,
+    comment: &'a str,
+    state_bits: Vec<TaskStateBit>,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Job {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JobRecord {
+            product_info: self.product_info,
+            file_version: self.file_version,
+            uuid: &self.uuid,
+            priority: self.priority,
+            max_run_time: self.max_run_time,
+            exit_code: self.exit_code,
+            status: self.status,
+            status_name: self.status_name(),
+            flags: self.flags,
+            flag_names: self.flag_names(),
+            run_date: &self.run_date,
+            scheduled_date: &self.scheduled_date,
+            name: &self.name,
+            parameters: &self.parameters,
+            working_directory: &self.working_directory,
+            user: &self.user,
+            comment: &self.comment,
+            state_bits: self.state_bits(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A `JobRecord` read back. The decoded status and flags are ignored in
+/// favour of the raw fields they were decoded from.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct JobFields {
+    product_info: u16,
+    file_version: u16,
+    uuid: UUID,
+    priority: u32,
+    max_run_time: i32,
+    exit_code: i32,
+    status: i32,
+    flags: u32,
+    run_date: JobDate,
+    scheduled_date: JobDate,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    parameters: String,
+    #[serde(default)]
+    working_directory: String,
+    #[serde(default)]
+    user: String,
+    #[serde(default)]
+    comment: String,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Job {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = JobFields::deserialize(deserializer)?;
+        let mut run_date = fields.run_date;
+        // Like `JobDate::new`, which reads a weekday for the run date only.
+        run_date.weekday = run_date.weekday.or(Some(0));
+        let mut scheduled_date = fields.scheduled_date;
+        scheduled_date.weekday = None;
+        Ok(Job {
+            product_info: fields.product_info,
+            file_version: fields.file_version,
+            uuid: fields.uuid,
+            priority: fields.priority,
+            max_run_time: fields.max_run_time,
+            exit_code: fields.exit_code,
+            status: fields.status,
+            flags: fields.flags,
+            run_date,
+            scheduled_date,
+            name: fields.name,
+            parameters: fields.parameters,
+            working_directory: fields.working_directory,
+            user: fields.user,
+            comment: fields.comment,
+        })
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Job {
+    fn schema_name() -> Cow<'static, str> {
+        JobRecord::schema_name()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        JobRecord::json_schema(generator)
+    }
+}
+
+fn bytes(data: &[u8], offset: usize, len: usize) -> Result<&[u8], Error> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(Error::TruncatedJob { offset, len })
+}
+
+fn field(data: &[u8], field: Field) -> Result<&[u8], Error> {
+    bytes(data, field.offset, field.size)
+}
+
+/// The number of triggers in `data`, a binary job, if its trigger section
+/// is where the header says. An offset into the fixed-length section, such
+/// as the 0 some writers leave, says nothing. A `Job` does not keep the
+/// count: the trigger section lies past the part of the file a `Job` is
+/// parsed from.
+pub fn trigger_count(data: &[u8]) -> Option<u16> {
+    let offset = read_u16(data, fixed::TRIGGER_OFFSET.offset).ok()? as usize;
+    if offset < variable::APPLICATION_NAME {
+        return None;
+    }
+    read_u16(data, offset).ok()
+}
+
+/// The triggers in `data`, a binary job, for `trigger_warnings`, named
+/// `trigger 1`, `trigger 2` and so on. Each is compared by its fields from
+/// the begin date to the trigger-specific words, with
+/// `trigger::FLAG_DISABLED` taken as its enabled state instead. Triggers
+/// that run past the end of the file, or do not give the trigger size as
+/// their own, are left out along with those after them.
+pub fn trigger_schedules(data: &[u8]) -> Vec<TriggerSchedule> {
+    trigger_records(data)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (offset, record))| {
+            let flags = read_u32(record, trigger::FLAGS.offset).unwrap_or(0);
+            let mut schedule = record.to_vec();
+            schedule[trigger::FLAGS.offset..][..4]
+                .copy_from_slice(&(flags & !trigger::FLAG_DISABLED).to_le_bytes());
+            TriggerSchedule {
+                name: format!("trigger {}", i + 1),
+                enabled: flags & trigger::FLAG_DISABLED == 0,
+                schedule: schedule[trigger::BEGIN_YEAR.offset..trigger::PADDING.offset].to_vec(),
+                offset: Some(offset),
+            }
+        })
+        .collect()
+}
+
+/// The enabled triggers in `data`, a binary job, as a `Schedule`, read
+/// like `trigger_schedules` reads them. A trigger with an invalid begin or
+/// end date, or an unknown type, is left out.
+pub fn schedule(data: &[u8]) -> Schedule {
+    let mut schedule = Schedule::default();
+    for (_, record) in trigger_records(data) {
+        let word = |field: Field| read_u16(record, field.offset).unwrap_or(0);
+        let long = |field: Field| read_u32(record, field.offset).unwrap_or(0);
+        let flags = long(trigger::FLAGS);
+        if flags & trigger::FLAG_DISABLED != 0 {
+            continue;
+        }
+        let recurrence = match TriggerType::from_value(long(trigger::TRIGGER_TYPE)) {
+            Some(trigger_type) => match recurrence(trigger_type, record) {
+                Some(recurrence) => recurrence,
+                None => {
+                    schedule.event_triggers += 1;
+                    continue;
+                }
+            },
+            None => continue,
+        };
+        let date = |year: Field, month: Field, day: Field| {
+            let (year, month, day) = (word(year), word(month), word(day));
+            let valid = (1..=12).contains(&month) && (1..=31).contains(&day);
+            valid.then(|| days_from_civil(year.into(), month.into(), day.into()) * 86400)
+        };
+        let Some(start) = date(trigger::BEGIN_YEAR, trigger::BEGIN_MONTH, trigger::BEGIN_DAY)
+        else {
+            continue;
+        };
+        let start = start + (i64::from(word(trigger::START_HOUR)) * 60
+            + i64::from(word(trigger::START_MINUTE)))
+            * 60;
+        let end = if flags & trigger::FLAG_HAS_END_DATE != 0 {
+            match date(trigger::END_YEAR, trigger::END_MONTH, trigger::END_DAY) {
+                // The end date is the last day the trigger fires.
+                Some(end) => Some(end + 86399),
+                None => continue,
+            }
+        } else {
+            None
+        };
+        let interval = long(trigger::MINUTES_INTERVAL);
+        let repetition = (interval > 0).then(|| Repetition {
+            interval: i64::from(interval) * 60,
+            duration: Some(i64::from(long(trigger::MINUTES_DURATION)) * 60),
+        });
+        schedule.time_triggers.push(TimeTrigger {
+            start,
+            end,
+            recurrence,
+            repetition,
+            random_delay: 0,
+        });
+    }
+    schedule
+}
+
+/// Every trigger in `data`, a binary job, disabled or not, read like
+/// `trigger_schedules` reads them. The start and end are left out when
+/// their dates are invalid, and the interval and duration when the
+/// trigger does not repeat; an unknown type is named by its value.
+pub fn trigger_details(data: &[u8]) -> Vec<TriggerDetail> {
+    trigger_records(data)
+        .into_iter()
+        .map(|(_, record)| {
+            let word = |field: Field| read_u16(record, field.offset).unwrap_or(0);
+            let long = |field: Field| read_u32(record, field.offset).unwrap_or(0);
+            let flags = long(trigger::FLAGS);
+            let date = |year: Field, month: Field, day: Field| {
+                let (year, month, day) = (word(year), word(month), word(day));
+                let valid = (1..=12).contains(&month) && (1..=31).contains(&day);
+                valid.then(|| format!("{:04}-{:02}-{:02}", year, month, day))
+            };
+            let start = date(trigger::BEGIN_YEAR, trigger::BEGIN_MONTH, trigger::BEGIN_DAY)
+                .map(|day| {
+                    let (hour, minute) = (word(trigger::START_HOUR), word(trigger::START_MINUTE));
+                    format!("{}T{:02}:{:02}:00", day, hour, minute)
+                });
+            let end = date(trigger::END_YEAR, trigger::END_MONTH, trigger::END_DAY)
+                .filter(|_| flags & trigger::FLAG_HAS_END_DATE != 0);
+            let value = long(trigger::TRIGGER_TYPE);
+            let (kind, summary) = match TriggerType::from_value(value) {
+                Some(trigger_type) => {
+                    let summary = match (trigger_type, recurrence(trigger_type, record)) {
+                        (_, Some(recurrence)) => recurrence.to_string(),
+                        (TriggerType::OnIdle, None) => "on idle".to_string(),
+                        (TriggerType::AtSystemStart, None) => "at system start".to_string(),
+                        _ => "at logon".to_string(),
+                    };
+                    (format!("{:?}", trigger_type), summary)
+                }
+                None => (value.to_string(), "unknown".to_string()),
+            };
+            let interval = long(trigger::MINUTES_INTERVAL);
+            let minutes = |minutes: u32| format!("PT{}M", minutes);
+            TriggerDetail {
+                kind,
+                start,
+                end,
+                enabled: flags & trigger::FLAG_DISABLED == 0,
+                summary,
+                interval: (interval > 0).then(|| minutes(interval)),
+                duration: (interval > 0).then(|| minutes(long(trigger::MINUTES_DURATION))),
+            }
+        })
+        .collect()
+}
+
+/// When the earliest enabled trigger in `data`, a binary job, first fires:
+/// its begin date and start time, read like `trigger_details` reads them.
+/// Never when no enabled trigger has a valid begin date. The format keeps
+/// no next run time of its own.
+pub fn first_start(data: &[u8]) -> JobDate {
+    trigger_records(data)
+        .into_iter()
+        .filter_map(|(_, record)| {
+            let word = |field: Field| read_u16(record, field.offset).unwrap_or(0);
+            let flags = read_u32(record, trigger::FLAGS.offset).unwrap_or(0);
+            let (month, day) = (word(trigger::BEGIN_MONTH), word(trigger::BEGIN_DAY));
+            let valid = (1..=12).contains(&month) && (1..=31).contains(&day);
+            (valid && flags & trigger::FLAG_DISABLED == 0).then(|| JobDate {
+                year: word(trigger::BEGIN_YEAR),
+                month,
+                weekday: None,
+                day,
+                hour: word(trigger::START_HOUR),
+                minute: word(trigger::START_MINUTE),
+                second: 0,
+            })
+        })
+        .min_by_key(|date| (date.year, date.month, date.day, date.hour, date.minute))
+        .unwrap_or(JobDate::never(true))
+}
+
+/// When a trigger of type `trigger_type` fires, from the trigger-specific
+/// words of its `record`; `None` for the types that fire on an event.
+fn recurrence(trigger_type: TriggerType, record: &[u8]) -> Option<Recurrence> {
+    let word = |field: Field| read_u16(record, field.offset).unwrap_or(0);
+    let (specific0, specific1, specific2) = (
+        word(trigger::TRIGGER_SPECIFIC0),
+        word(trigger::TRIGGER_SPECIFIC1),
+        word(trigger::TRIGGER_SPECIFIC2),
+    );
+    let recurrence = match trigger_type {
+        TriggerType::Once => Recurrence::Once,
+        TriggerType::Daily => Recurrence::Daily { every: specific0.into() },
+        TriggerType::Weekly => Recurrence::Weekly {
+            every: specific0.into(),
+            days: specific1 as u8,
+        },
+        TriggerType::MonthlyDate => Recurrence::MonthlyDate {
+            days: u32::from(specific0) | u32::from(specific1) << 16,
+            last_day: false,
+            months: specific2,
+        },
+        // Weeks are numbered from 1, with 5 for the last.
+        TriggerType::MonthlyDow => Recurrence::MonthlyDow {
+            weeks: if (1..=5).contains(&specific0) { 1 << (specific0 - 1) } else { 0 },
+            days: specific1 as u8,
+            months: specific2,
+        },
+        TriggerType::OnIdle | TriggerType::AtSystemStart | TriggerType::AtLogon => return None,
+    };
+    Some(recurrence)
+}
+
+/// The offset and bytes of each trigger in `data`, a binary job, as far as
+/// `trigger_schedules` reads them.
+fn trigger_records(data: &[u8]) -> Vec<(usize, &[u8])> {
+    let Some(count) = trigger_count(data) else {
+        return Vec::new();
+    };
+    // Checked by `trigger_count`.
+    let start = read_u16(data, fixed::TRIGGER_OFFSET.offset).unwrap_or(0) as usize + 2;
+    let mut records = Vec::new();
+    for i in 0..count as usize {
+        let offset = start + i * trigger::LENGTH;
+        let Ok(record) = bytes(data, offset, trigger::LENGTH) else {
+            break;
+        };
+        if read_u16(record, trigger::TRIGGER_SIZE.offset).ok() != Some(trigger::LENGTH as u16) {
+            break;
+        }
+        records.push((offset, record));
+    }
+    records
+}
+
+/// The `coverage` below which a job is warned about, unless asked otherwise.
+pub const DEFAULT_MIN_COVERAGE: f64 = 90.0;
+
+/// How much of `data`, a binary job, its `sections` account for, as a
+/// percentage of its size: 100 unless bytes follow the last section, such
+/// as data appended to the file, or the sections stop at one that makes no
+/// sense. 0 for an empty file.
+pub fn coverage(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let covered: usize = sections(data).iter().map(|section| section.len).sum();
+    covered as f64 * 100.0 / data.len() as f64
+}
+
+/// The user data of `data`, a binary job, without its size; empty if the
+/// file ends before it. Task Scheduler leaves it to the program that
+/// created the job.
+pub fn user_data(data: &[u8]) -> &[u8] {
+    let section = sections(data).into_iter().find(|section| section.name == "user data");
+    section.and_then(|section| data.get(section.offset + 2..section.end())).unwrap_or_default()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    let b = bytes(data, offset, 2)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    let b = bytes(data, offset, 4)?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Result<i32, Error> {
+    read_u32(data, offset).map(|v| v as i32)
+}
+
+/// Reads a length-prefixed UTF-16LE string at `*offset` and advances past it.
+/// The length counts characters, including the terminating NUL; a length of
+/// 0 means the string is absent, terminator and all. Characters after the
+/// first NUL are left over from whatever the writer's buffer held before and
+/// are dropped.
+///
+/// A length that runs past the end of `data` fails with
+/// `Error::StringLengthOutOfBounds`, unless `warnings` is given: then the
+/// string is cut to the characters there are, a missing length reads as an
+/// absent string, and the error is added to `warnings` instead.
+fn read_string(
+    data: &[u8],
+    offset: &mut usize,
+    field: &'static str,
+    warnings: Option<&mut Vec<Error>>,
+) -> Result<String, Error> {
+    let declared = match read_u16(data, *offset) {
+        Ok(length) => length as usize,
+        Err(e) => return warn(e, warnings).map(|()| String::new()),
+    };
+    let start = offset.checked_add(variable::STRING_LENGTH_SIZE).ok_or(overflow(*offset))?;
+    let available = data.len().saturating_sub(start) / 2;
+    let length = if declared <= available {
+        declared
+    } else {
+        warn(Error::StringLengthOutOfBounds { field, declared, available }, warnings)?;
+        available
+    };
+    let size = length.checked_mul(2).ok_or(overflow(start))?;
+    let raw = bytes(data, start, size)?;
+    *offset = start.checked_add(size).ok_or(overflow(start))?;
+    let units: Vec<u16> = raw
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    Ok(String::from_utf16_lossy(&units))
+}
+
+/// Adds `e` to `warnings`, or fails with it if there are none.
+fn warn(e: Error, warnings: Option<&mut Vec<Error>>) -> Result<(), Error> {
+    match warnings {
+        Some(warnings) => {
+            warnings.push(e);
+            Ok(())
+        }
+        None => Err(e),
+    }
+}
+
+/// The error for an offset past what `usize` holds, which only a file
+/// larger than the address space could lead to.
+fn overflow(offset: usize) -> Error {
+    Error::TruncatedJob { offset, len: usize::MAX }
+}
+
+/// How `Job::to_bytes_with` writes an empty string. Task Scheduler writes a
+/// lone NUL; some other tools leave the string out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyString {
+    /// A length of 1 followed by the terminating NUL.
+    #[default]
+    Terminated,
+    /// A length of 0 and nothing else.
+    Absent,
+}
+
+fn push_string(out: &mut Vec<u8>, value: &str, empty: EmptyString) {
+    let mut units: Vec<u16> = value.encode_utf16().collect();
+    if !units.is_empty() || empty == EmptyString::Terminated {
+        units.push(0);
+    }
+    out.extend_from_slice(&(units.len() as u16).to_le_bytes());
+    for unit in units {
+        out.extend_from_slice(&unit.to_le_bytes());
+    }
+}
+
+impl Job {
+    /// Parses a binary job file, failing with `Error::TruncatedJob` when a
+    /// field extends past the end of `data`, or
+    /// `Error::StringLengthOutOfBounds` when a string's length does.
+    pub fn parse(data: &[u8]) -> Result<Job, Error> {
+        Job::parse_prefix(data).map(|(job, _)| job)
+    }
+
+    /// Like `parse`, but strings that run past the end of `data` are cut to
+    /// the characters there are, and strings after the end are empty. The
+    /// errors `parse` would have failed with are returned as warnings. A
+    /// truncated fixed-length section still fails.
+    pub fn parse_lenient(data: &[u8]) -> Result<(Job, Vec<Error>), Error> {
+        let mut warnings = Vec::new();
+        let (job, _) = Job::parse_with(data, Some(&mut warnings))?;
+        Ok((job, warnings))
+    }
+
+    /// Like `parse`, but also returns the number of bytes up to the end of
+    /// the comment string, ignoring whatever follows.
+    pub(crate) fn parse_prefix(data: &[u8]) -> Result<(Job, usize), Error> {
+        Job::parse_with(data, None)
+    }
+
+    fn parse_with(
+        data: &[u8],
+        mut warnings: Option<&mut Vec<Error>>,
+    ) -> Result<(Job, usize), Error> {
+        let product_info = read_u16(data, fixed::PRODUCT_VERSION.offset)?;
+        let file_version = read_u16(data, fixed::FILE_VERSION.offset)?;
+        let uuid = UUID::new(field(data, fixed::UUID)?);
+        let priority = read_u32(data, fixed::PRIORITY.offset)?;
+        let max_run_time = read_i32(data, fixed::MAXIMUM_RUN_TIME.offset)?;
+        let exit_code = read_i32(data, fixed::EXIT_CODE.offset)?;
+        let status = read_i32(data, fixed::STATUS.offset)?;
+        let flags = read_u32(data, fixed::FLAGS.offset)?;
+        let run_date = JobDate::new(field(data, fixed::LAST_RUN_TIME)?, false);
+        let scheduled_date = first_start(data);
+
+        let mut offset = variable::APPLICATION_NAME;
+        let mut read = |field| read_string(data, &mut offset, field, warnings.as_deref_mut());
+        let name = read("application name")?;
+        let parameters = read("parameters")?;
+        let working_directory = read("working directory")?;
+        let user = read("author")?;
+        let comment = read("comment")?;
+
+        let job = Job {
+            product_info,
+            file_version,
+            uuid,
+            priority,
+            max_run_time,
+            exit_code,
+            status,
+            flags,
+            run_date,
+            scheduled_date,
+            name,
+            parameters,
+            working_directory,
+            user,
+            comment,
+        };
+        Ok((job, offset))
+    }
+
+    /// Encodes the job in the binary format: the fixed-length header, the
+    /// five strings, and empty user data, reserved data and trigger
+    /// sections. `scheduled_date` comes from the triggers, so it is dropped.
+    /// The strings must fit, as `JobBuilder::build` checks; longer ones
+    /// get lengths and offsets that wrap.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(EmptyString::Terminated)
+    }
+
+    /// `to_bytes` with empty strings written as `empty` says.
+    pub fn to_bytes_with(&self, empty: EmptyString) -> Vec<u8> {
+        let mut strings = Vec::new();
+        for value in [
+            &self.name,
+            &self.parameters,
+            &self.working_directory,
+            &self.user,
+            &self.comment,
+        ] {
+            push_string(&mut strings, value, empty);
+        }
+        let name_offset = variable::APPLICATION_NAME as u16;
+        // Running instance count, the strings, then user data and reserved
+        // data sizes precede the trigger count.
+        let trigger_offset = (name_offset as usize + strings.len() + 4) as u16;
+
+        let mut out = Vec::with_capacity(trigger_offset as usize + 2);
+        out.extend_from_slice(&self.product_info.to_le_bytes());
+        out.extend_from_slice(&self.file_version.to_le_bytes());
+        out.extend_from_slice(&self.uuid.to_bytes());
+        out.extend_from_slice(&name_offset.to_le_bytes());
+        out.extend_from_slice(&trigger_offset.to_le_bytes());
+        // Retry count and interval, idle deadline and wait.
+        out.resize(fixed::PRIORITY.offset, 0);
+        out.extend_from_slice(&self.priority.to_le_bytes());
+        out.extend_from_slice(&self.max_run_time.to_le_bytes());
+        out.extend_from_slice(&self.exit_code.to_le_bytes());
+        out.extend_from_slice(&self.status.to_le_bytes());
+        out.extend_from_slice(&self.flags.to_le_bytes());
+        out.extend_from_slice(&self.run_date.to_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // running instance count
+        out.extend_from_slice(&strings);
+        out.extend_from_slice(&0u16.to_le_bytes()); // user data size
+        out.extend_from_slice(&0u16.to_le_bytes()); // reserved data size
+        out.extend_from_slice(&0u16.to_le_bytes()); // trigger count
+        out
+    }
+
+    /// The recognized status code, if `status` is one.
+    pub fn status_name(&self) -> Option<TaskStatus> {
+        TaskStatus::from_code(self.status)
+    }
+
+    /// The flags set in `flags`.
+    pub fn flag_names(&self) -> Vec<TaskFlag> {
+        TaskFlag::from_bits(self.flags)
+    }
+
+    /// The state bits set in `flags`.
+    pub fn state_bits(&self) -> Vec<TaskStateBit> {
+        TaskStateBit::from_bits(self.flags)
+    }
+
+    /// The bits set in `flags` that no `TaskFlag` or `TaskStateBit` stands
+    /// for, the reserved ones.
+    pub fn unknown_flags(&self) -> u32 {
+        let known = TaskFlag::BITS.iter().map(|(_, bit, _)| bit);
+        let known = known.chain(TaskStateBit::BITS.iter().map(|(_, bit, _)| bit));
+        known.fold(self.flags, |rest, bit| rest & !bit)
+    }
+
+    /// Whether the job was due by `now` (`YYYY-MM-DDTHH:MM:SS`) yet has
+    /// never run: its run date is zero or its status says it has not run,
+    /// and its earliest enabled trigger, its `scheduled_date`, started by
+    /// then. Jobs whose triggers all start later, or that have no enabled
+    /// trigger, are not flagged. Disabled jobs are never
+    /// flagged. Such jobs were often staged for later, or their payload was
+    /// removed before they fired.
+    pub fn never_ran_but_scheduled(&self, now: &str) -> bool {
+        let disabled = self.flag_names().contains(&TaskFlag::Disabled)
+            || self.status_name() == Some(TaskStatus::Disabled);
+        let never_ran = self.run_date.year == 0 || self.status_name() == Some(TaskStatus::HasNotRun);
+        let due = self.scheduled_date.year != 0 && self.scheduled_date.format_iso().as_str() <= now;
+        !disabled && never_ran && due
+    }
+
+    /// Whether the job is live; see `enabled`. The triggers are not read,
+    /// so only the job's flag and status count.
+    pub fn effective_enabled(&self) -> Enablement {
+        let disabled = if self.flag_names().contains(&TaskFlag::Disabled) {
+            Some(enabled::FLAG_DISABLED)
+        } else if self.status_name() == Some(TaskStatus::Disabled) {
+            Some(enabled::STATUS_DISABLED)
+        } else {
+            None
+        };
+        enabled::judge(disabled, None, "")
+    }
+
+    pub fn format_job(&self) -> String {
+        self.format_job_with(DateStyle::Classic)
+    }
+
+    /// `format_job` with the run and scheduled dates in `style`.
+    pub fn format_job_with(&self, style: DateStyle) -> String {
+        self.format_job_as(style, UuidFormat::default())
+    }
+
+    /// `format_job_with` with the UUID in `uuid`.
+    pub fn format_job_as(&self, style: DateStyle, uuid: UuidFormat) -> String {
+        let products: HashMap<u16, &str> = consts::product::VERSIONS.into_iter().collect();
+
+        let task_status: HashMap<i32, &str> = vec![
+            (status::READY, "Task is ready to run"),
+            (status::RUNNING, "Task is running"),
+            (status::DISABLED, "Task is disabled"),
+            (status::HAS_NOT_RUN, "Task has not run"),
+            (status::NO_MORE_RUNS, "No more scheduled runs"),
+            (status::NOT_SCHEDULED, "Properties not set"),
+            (status::TERMINATED, "Last run terminated by user"),
+            (status::NO_VALID_TRIGGERS, "No triggers/triggers disabled"),
+            (status::EVENT_TRIGGER, "Triggers do not have set run times"),
+        ]
+        .into_iter()
+        .collect();
+
+        let priorities: HashMap<u32, &str> = vec![
+            (consts::priority::NORMAL, "NORMAL_PRIORITY_CLASS"),
+            (consts::priority::IDLE, "IDLE_PRIORITY_CLASS"),
+            (consts::priority::HIGH, "HIGH_PRIORITY_CLASS"),
+            (consts::priority::REALTIME, "REALTIME_PRIORITY_CLASS"),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut result = String::new();
+
+        match products.get(&self.product_info) {
+            Some(name) => result.push_str(&format!("Product Info: {}\n", name)),
+            // With the word, which is all there is to judge it by.
+            None => result.push_str(&format!(
+                "Product Info: Unknown Version ({:#06x})\n",
+                self.product_info
+            )),
+        }
+        result.push_str(&format!("File Version: {}\n", self.file_version));
+        result.push_str(&format!("UUID: {}\n", self.uuid.format(uuid)));
+
+        let mut priority_list = String::new();
+        for (key, value) in &priorities {
+            if self.priority & key == *key {
+                priority_list.push_str(value);
+                priority_list.push_str(", ");
+            }
+        }
+        if !priority_list.is_empty() {
+            result.push_str(&format!(
+                "Priorities: {}\n",
+                priority_list.trim_end_matches(", ")
+            ));
+        }
+
+        let hours = self.max_run_time / 3600000;
+        let ms = self.max_run_time % 3600000;
+        let minutes = ms / 60000;
+        let ms = ms % 60000;
+        let seconds = ms / 1000;
+        let ms = ms % 1000;
+        result.push_str(&format!(
+            "Maximum Run Time: {:02}:{:02}:{:02}.{} (HH:MM:SS.MS)\n",
+            hours, minutes, seconds, ms
+        ));
+        result.push_str(&format!("Exit Code: {}\n", self.exit_code));
+        result.push_str(&format!(
+            "Status: {}\n",
+            task_status.get(&self.status).unwrap_or(&"Unknown Status")
+        ));
+
+        let flag_list: Vec<&str> = self
+            .flag_names()
+            .into_iter()
+            .map(TaskFlag::constant_name)
+            .collect();
+        result.push_str(&format!("Flags: {}\n", flag_list.join(", ")));
+        let state_bits: Vec<&str> = self
+            .state_bits()
+            .into_iter()
+            .map(TaskStateBit::constant_name)
+            .collect();
+        result.push_str(&format!("Task State Bits: {}\n", state_bits.join(", ")));
+        result.push_str(&format!("Date Run: {}\n", self.run_date.format_with(style)));
+        result.push_str(&format!("Scheduled Date: {}\n", self.scheduled_date.format_with(style)));
+        result.push_str(&format!("Application: {}\n", self.name));
+        result.push_str(&format!("Parameters: {}\n", self.parameters));
+        result.push_str(&format!("Working Directory: {}\n", self.working_directory));
+        result.push_str(&format!("User: {}\n", self.user));
+        result.push_str(&format!("Comment: {}\n", self.comment));
+
+        result
+    }
+}
+
+/// Assembles a `Job` field by field, starting from the values Task Scheduler
+/// uses for a new task that has never run.
+///
+/// ```
+/// use jobfileparser::JobBuilder;
+///
+/// let job = JobBuilder::new("C:\\Windows\\notepad.exe")
+///     .parameters("notes.txt")
+///     .author("EXAMPLE\\admin")
+///     .build()?;
+/// assert_eq!(job.parameters, "notes.txt");
+/// # Ok::<(), jobfileparser::Error>(())
+/// ```
+///
+/// Empty strings are encoded the way Task Scheduler writes them unless
+/// `Job::to_bytes_with` is asked for `EmptyString::Absent`.
+#[derive(Debug)]
+pub struct JobBuilder {
+    job: Job,
+}
+
+impl JobBuilder {
+    pub fn new(application: &str) -> JobBuilder {
+        JobBuilder {
+            job: Job {
+                product_info: consts::product::WINDOWS_7,
+                file_version: consts::FILE_VERSION,
+                uuid: UUID::new(&[0; 16]),
+                priority: consts::priority::NORMAL,
+                max_run_time: 259_200_000,
+                exit_code: 0,
+                status: status::HAS_NOT_RUN,
+                flags: 0,
+                run_date: JobDate::never(false),
+                scheduled_date: JobDate::never(true),
+                name: application.to_string(),
+                parameters: String::new(),
+                working_directory: String::new(),
+                user: String::new(),
+                comment: String::new(),
+            },
+        }
+    }
+
+    pub fn product_info(mut self, product_info: u16) -> JobBuilder {
+        self.job.product_info = product_info;
+        self
+    }
+
+    /// Sets the UUID from its 16 bytes as stored on disk.
+    pub fn uuid(mut self, bytes: &[u8; 16]) -> JobBuilder {
+        self.job.uuid = UUID::new(bytes);
+        self
+    }
+
+    pub fn priority(mut self, priority: u32) -> JobBuilder {
+        self.job.priority = priority;
+        self
+    }
+
+    /// Sets the maximum run time in milliseconds.
+    pub fn max_run_time(mut self, max_run_time: i32) -> JobBuilder {
+        self.job.max_run_time = max_run_time;
+        self
+    }
+
+    pub fn exit_code(mut self, exit_code: i32) -> JobBuilder {
+        self.job.exit_code = exit_code;
+        self
+    }
+
+    pub fn status(mut self, status: i32) -> JobBuilder {
+        self.job.status = status;
+        self
+    }
+
+    pub fn flags(mut self, flags: u32) -> JobBuilder {
+        self.job.flags = flags;
+        self
+    }
+
+    pub fn run_date(mut self, run_date: JobDate) -> JobBuilder {
+        self.job.run_date = run_date;
+        self
+    }
+
+    pub fn parameters(mut self, parameters: &str) -> JobBuilder {
+        self.job.parameters = parameters.to_string();
+        self
+    }
+
+    pub fn working_directory(mut self, working_directory: &str) -> JobBuilder {
+        self.job.working_directory = working_directory.to_string();
+        self
+    }
+
+    pub fn author(mut self, author: &str) -> JobBuilder {
+        self.job.user = author.to_string();
+        self
+    }
+
+    pub fn comment(mut self, comment: &str) -> JobBuilder {
+        self.job.comment = comment.to_string();
+        self
+    }
+
+    /// Returns the job, or `Error::StringTooLong` naming the longest string
+    /// when the strings do not fit in a job file: the trigger offset that
+    /// follows them is 16 bits, so together, with their lengths and NULs,
+    /// they must end within 65535 bytes of the start of the file.
+    pub fn build(self) -> Result<Job, Error> {
+        let job = &self.job;
+        let lengths = [
+            ("application name", &job.name),
+            ("parameters", &job.parameters),
+            ("working directory", &job.working_directory),
+            ("author", &job.user),
+            ("comment", &job.comment),
+        ]
+        .map(|(field, value)| (field, value.encode_utf16().count()));
+        // The user data and reserved data sizes follow the strings, before
+        // the trigger offset.
+        let end = lengths.iter().fold(variable::APPLICATION_NAME + 4, |end, (_, len)| {
+            end + variable::STRING_LENGTH_SIZE + (len + 1) * 2
+        });
+        if end > usize::from(u16::MAX) {
+            // The first of the longest, as `max_by_key` would give the last.
+            let longest = lengths.iter().rev().max_by_key(|(_, len)| len);
+            let (field, len) = *longest.expect("there are five strings");
+            return Err(Error::StringTooLong { field, len });
+        }
+        Ok(self.job)
+    }
+}
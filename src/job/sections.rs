@@ -0,0 +1,61 @@
+//! Where the parts of a binary job lie in the file, for hex dumps and
+//! `coverage`. Public only with the `unstable` feature.
+
+use super::{read_u16, Job};
+use crate::consts::{self, fixed, trigger};
+
+/// A part of a binary job and where it lies in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    /// `header`, `strings`, `user data`, `reserved data`, `triggers` or
+    /// `signature`.
+    pub name: &'static str,
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Section {
+    /// The offset just past the section.
+    pub fn end(&self) -> usize {
+        self.offset + self.len
+    }
+}
+
+/// The sections of `data`, a binary job, in file order: the fixed-length
+/// header, the running instance count and strings, the user and reserved
+/// data with their sizes, the trigger count and triggers, and a signature
+/// if one follows. The triggers are taken to start where the header says,
+/// if it points past the reserved data. A section that runs past the end of
+/// the file is cut short, and those after it are left out, as is whatever
+/// bytes no section accounts for, such as data appended to the file.
+pub fn sections(data: &[u8]) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut add = |name, offset: usize, len: usize| {
+        let len = len.min(data.len().saturating_sub(offset));
+        if len > 0 {
+            sections.push(Section { name, offset, len });
+        }
+        offset + len
+    };
+    let header = add("header", 0, fixed::LENGTH);
+    let Ok((_, strings_end)) = Job::parse_with(data, Some(&mut Vec::new())) else {
+        return sections;
+    };
+    let mut end = add("strings", header, strings_end.saturating_sub(header));
+    for name in ["user data", "reserved data"] {
+        let Ok(size) = read_u16(data, end) else {
+            return sections;
+        };
+        end = add(name, end, 2 + size as usize);
+    }
+    let declared = read_u16(data, fixed::TRIGGER_OFFSET.offset).unwrap_or(0) as usize;
+    let start = declared.max(end);
+    let Ok(count) = read_u16(data, start) else {
+        return sections;
+    };
+    end = add("triggers", start, 2 + count as usize * trigger::LENGTH);
+    if read_u16(data, end + consts::signature::SIGNATURE_VERSION.offset).ok() == Some(1) {
+        add("signature", end, consts::signature::LENGTH);
+    }
+    sections
+}
@@ -0,0 +1,484 @@
+//! Parsers for Windows Task Scheduler artifacts: legacy binary `.job` files and
+//! the XML task definitions written by Task Scheduler 2.0.
+//!
+//! # Stability
+//!
+//! What the crate root exports and the public modules follow semver. The
+//! offset tables of `consts`, `job::sections` and `sniff` follow the
+//! parsers' internals and are public only with the `unstable` feature.
+//! `tests/public_api_test.rs` checks the public API against a snapshot.
+
+#[cfg(feature = "fs")]
+use std::fs;
+use std::path::Path;
+
+pub mod account;
+#[cfg(feature = "xml")]
+pub mod acl;
+#[cfg(feature = "async")]
+mod async_scan;
+pub mod cancel;
+#[cfg(any(feature = "binary", feature = "xml"))]
+pub mod carve;
+pub mod cmdline;
+pub mod consts;
+#[cfg(all(feature = "binary", feature = "xml"))]
+pub mod convert;
+pub mod date;
+pub mod defaults;
+pub mod detect;
+pub mod enabled;
+pub mod entropy;
+mod error;
+pub mod fold;
+#[cfg(feature = "hive")]
+pub mod hive;
+pub mod indicators;
+#[cfg(feature = "fs")]
+pub mod input;
+#[cfg(feature = "binary")]
+pub mod interpret;
+#[cfg(feature = "binary")]
+pub mod job;
+#[cfg(feature = "fs")]
+mod path;
+pub mod payload;
+pub mod record;
+#[cfg(feature = "fs")]
+pub mod scan;
+pub mod schedlog;
+pub mod schedule;
+#[cfg(feature = "xml")]
+pub mod sddl;
+#[cfg(feature = "unstable")]
+pub mod sniff;
+#[cfg(feature = "xml")]
+pub mod task;
+#[cfg(feature = "hive")]
+pub mod taskcache;
+pub mod warning;
+
+pub use account::UserKind;
+#[cfg(feature = "xml")]
+pub use acl::FileSecurity;
+#[cfg(feature = "async")]
+pub use async_scan::{parse_file_async, scan_dir_stream};
+pub use cancel::{CancellationToken, PartialScan};
+#[cfg(any(feature = "binary", feature = "xml"))]
+pub use carve::{carve, carve_cancellable, Carved};
+pub use cmdline::CommandLine;
+pub use date::{DateStyle, Locale};
+pub use defaults::{Classification, KnowledgeBase};
+pub use detect::{at_job_index, detect, DetectOptions, Finding, PathRules};
+pub use enabled::Enablement;
+pub use entropy::name_entropy;
+pub use error::Error;
+pub use fold::Case;
+pub use indicators::{extract_indicators, Indicator, IndicatorKind};
+#[cfg(feature = "fs")]
+pub use input::Input;
+#[cfg(feature = "binary")]
+pub use job::{
+    EmptyString, Job, JobBuilder, JobDate, TaskFlag, TaskStateBit, TaskStatus, UuidFormat, UUID,
+};
+pub use payload::{decode_comment, decode_user_data, DecodedArtifact};
+pub use record::Record;
+#[cfg(feature = "fs")]
+pub use scan::{list_dir, scan_dir, scan_dir_cancellable, ScanOptions, DEFAULT_MAX_FILE_SIZE};
+pub use schedlog::{ExecutionHistory, ExecutionLog};
+pub use schedule::Schedule;
+#[cfg(feature = "xml")]
+pub use task::Task;
+#[cfg(feature = "hive")]
+pub use taskcache::{read_task_cache, CachedTask, DynamicInfo};
+pub use warning::{Warning, WarningCode};
+
+/// A single parsed artifact, either a binary job or an XML task. Only the
+/// variants for the enabled format features exist.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "format", rename_all = "lowercase")
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ParsedArtifact {
+    #[cfg(feature = "binary")]
+    Job(Job),
+    #[cfg(feature = "xml")]
+    Task(Task),
+}
+
+impl ParsedArtifact {
+    pub fn format(&self) -> String {
+        self.format_with(DateStyle::Classic)
+    }
+
+    /// `format` with dates in `style`.
+    #[cfg_attr(not(any(feature = "binary", feature = "xml")), allow(unused_variables))]
+    pub fn format_with(&self, style: DateStyle) -> String {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => job.format_job_with(style),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task.format_task_with(style),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// The program the artifact runs followed by its arguments, if any.
+    pub fn command_line(&self) -> Option<String> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => Some(join_command(&job.name, &job.parameters)),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task.actions.exec.as_ref().map(|exec| {
+                join_command(&exec.command, exec.arguments.as_deref().unwrap_or(""))
+            }),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// A job's comment or a task's description, if not empty.
+    #[cfg_attr(
+        not(any(feature = "binary", feature = "xml")),
+        allow(unreachable_code, unused_variables)
+    )]
+    pub fn comment(&self) -> Option<&str> {
+        let comment: Option<&str> = match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => Some(job.comment.as_str()),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task.registration_info.description.as_deref(),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        };
+        comment.filter(|comment| !comment.is_empty())
+    }
+
+    /// The arguments passed to the program: a job's parameters or a task's
+    /// `Exec/Arguments`.
+    pub fn arguments(&self) -> Option<&str> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => Some(job.parameters.as_str()).filter(|p| !p.is_empty()),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task.actions.exec.as_ref()?.arguments.as_deref(),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// The command line split and looked through as `CommandLine` describes,
+    /// for artifacts that run a program.
+    #[cfg_attr(
+        not(any(feature = "binary", feature = "xml")),
+        allow(unreachable_code, unused_variables)
+    )]
+    pub fn command(&self) -> Option<CommandLine> {
+        let command: CommandLine = match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => CommandLine::from_parts(&job.name, &job.parameters),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => {
+                let exec = task.actions.exec.as_ref()?;
+                CommandLine::from_parts(&exec.command, exec.arguments.as_deref().unwrap_or(""))
+            }
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        };
+        Some(command).filter(|c| !c.executable.is_empty())
+    }
+
+    /// How many triggers the artifact has. `None` for jobs, whose trigger
+    /// section is not parsed; see `job::trigger_count`.
+    pub fn trigger_count(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(_) => None,
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => Some(task.trigger_count()),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// What kind of account a job's user field names; see
+    /// `account::classify_user`. `None` for task XML.
+    pub fn user_kind(&self) -> Option<UserKind> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => Some(account::classify_user(&job.user)),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(_) => None,
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// The kind of each action, such as `Exec` or `ComHandler`; see
+    /// `Task::action_kinds`. A job runs exactly one program, an `Exec`.
+    pub fn action_kinds(&self) -> Vec<&str> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(_) => vec!["Exec"],
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task.action_kinds(),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// The account recorded as the task's author.
+    pub fn author(&self) -> Option<&str> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => Some(job.user.as_str()).filter(|a| !a.is_empty()),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task.registration_info.author.as_deref(),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// The account the task belongs to: a job's user, or a task's principal
+    /// (its user, else its group), falling back to the task's author.
+    pub fn user(&self) -> Option<&str> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => Some(job.user.as_str()).filter(|u| !u.is_empty()),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task
+                .principal()
+                .and_then(|p| p.user_id.as_deref().or(p.group_id.as_deref()))
+                .or(task.registration_info.author.as_deref())
+                .filter(|u| !u.is_empty()),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// When a job last ran, as `YYYY-MM-DDTHH:MM:SS`. `None` for jobs that
+    /// have never run and for task XML, which does not record it.
+    pub fn last_run(&self) -> Option<String> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) if job.run_date.year == 0 => None,
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => Some(job.run_date.format_iso()),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(_) => None,
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Whether a job was due by `now` (`YYYY-MM-DDTHH:MM:SS`) but never ran;
+    /// see `Job::never_ran_but_scheduled`. Always false for task XML, which
+    /// keeps no run history.
+    #[cfg_attr(not(feature = "binary"), allow(unused_variables))]
+    pub fn never_ran_but_scheduled(&self, now: &str) -> bool {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => job.never_ran_but_scheduled(now),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(_) => false,
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// What notable combinations of a job's flags mean as of `now`
+    /// (`YYYY-MM-DDTHH:MM:SS`); see `interpret`. Task XML has no flags.
+    #[cfg_attr(not(feature = "binary"), allow(unused_variables))]
+    pub fn interpretations(&self, now: &str) -> Vec<String> {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => interpret::interpret(job, now),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(_) => Vec::new(),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Whether the task is live as of `now` (`YYYY-MM-DDTHH:MM:SS`), and
+    /// why; see `enabled`.
+    #[cfg_attr(not(feature = "xml"), allow(unused_variables))]
+    pub fn effective_enabled(&self, now: &str) -> Enablement {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => job.effective_enabled(),
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task.effective_enabled(now),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Whether the task is hidden from the Task Scheduler UI.
+    pub fn is_hidden(&self) -> bool {
+        match self {
+            #[cfg(feature = "binary")]
+            ParsedArtifact::Job(job) => job.flags & consts::flags::HIDDEN != 0,
+            #[cfg(feature = "xml")]
+            ParsedArtifact::Task(task) => task.settings.hidden == Some(true),
+            #[cfg(not(any(feature = "binary", feature = "xml")))]
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(any(feature = "binary", feature = "xml"))]
+fn join_command(command: &str, arguments: &str) -> String {
+    if arguments.is_empty() {
+        command.to_string()
+    } else {
+        format!("{} {}", command, arguments)
+    }
+}
+
+/// The two on-disk formats, named after the features that parse them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactFormat {
+    Binary,
+    Xml,
+}
+
+impl ArtifactFormat {
+    /// The format `parse_auto` uses for `path`: task XML for `.xml` files,
+    /// a binary job for everything else.
+    pub fn for_path(path: &Path) -> ArtifactFormat {
+        let is_xml = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+        if is_xml {
+            ArtifactFormat::Xml
+        } else {
+            ArtifactFormat::Binary
+        }
+    }
+
+    /// The format `data` holds, judged by its first bytes as in
+    /// `parse_bytes`.
+    pub fn sniff(data: &[u8]) -> ArtifactFormat {
+        if looks_like_xml(data) {
+            ArtifactFormat::Xml
+        } else {
+            ArtifactFormat::Binary
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ArtifactFormat::Binary => "binary",
+            ArtifactFormat::Xml => "xml",
+        }
+    }
+}
+
+/// Parses `data` as the artifact type implied by `path`: `.xml` files are
+/// decoded as task XML, everything else as a binary job. Formats whose
+/// feature is disabled yield `Error::Unsupported`.
+pub fn parse_auto(path: &Path, data: &[u8]) -> Result<ParsedArtifact, Error> {
+    match ArtifactFormat::for_path(path) {
+        ArtifactFormat::Xml => parse_xml(data),
+        ArtifactFormat::Binary => parse_binary(data),
+    }
+}
+
+/// Parses `data` without a file name to go by, telling task XML from a binary
+/// job by its first bytes: a byte-order mark or `<` (after any whitespace)
+/// means XML.
+pub fn parse_bytes(data: &[u8]) -> Result<ParsedArtifact, Error> {
+    match ArtifactFormat::sniff(data) {
+        ArtifactFormat::Xml => parse_xml(data),
+        ArtifactFormat::Binary => parse_binary(data),
+    }
+}
+
+fn looks_like_xml(data: &[u8]) -> bool {
+    const BOMS: [&[u8]; 3] = [&[0xEF, 0xBB, 0xBF], &[0xFF, 0xFE], &[0xFE, 0xFF]];
+    if BOMS.iter().any(|bom| data.starts_with(bom)) {
+        return true;
+    }
+    data.iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|&b| b == b'<')
+}
+
+#[cfg(feature = "xml")]
+fn parse_xml(data: &[u8]) -> Result<ParsedArtifact, Error> {
+    Ok(ParsedArtifact::Task(Task::from_bytes(data)?))
+}
+
+#[cfg(not(feature = "xml"))]
+fn parse_xml(_data: &[u8]) -> Result<ParsedArtifact, Error> {
+    Err(Error::Unsupported("xml"))
+}
+
+#[cfg(feature = "binary")]
+fn parse_binary(data: &[u8]) -> Result<ParsedArtifact, Error> {
+    Ok(ParsedArtifact::Job(Job::parse(data)?))
+}
+
+#[cfg(not(feature = "binary"))]
+fn parse_binary(_data: &[u8]) -> Result<ParsedArtifact, Error> {
+    Err(Error::Unsupported("binary"))
+}
+
+/// Reads and parses the file at `path`, going by its content rather than its
+/// extension (see `parse_bytes`), so a task XML renamed to `.job` still
+/// parses. Compare `ArtifactFormat::for_path` with `ArtifactFormat::sniff`
+/// to spot such files. An empty file is reported as `Error::EmptyFile`.
+#[cfg(feature = "fs")]
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<ParsedArtifact, Error> {
+    let data = Input::open(path.as_ref())?;
+    if data.is_empty() {
+        return Err(Error::EmptyFile);
+    }
+    parse_bytes(&data)
+}
+
+/// Like `parse_file`, but a file over `max_size` bytes is reported as
+/// `Error::TooLarge` without being read.
+#[cfg(feature = "fs")]
+pub fn parse_file_limited<P: AsRef<Path>>(
+    path: P,
+    max_size: u64,
+) -> Result<ParsedArtifact, Error> {
+    parse_bytes(&read_file_limited(path, max_size)?)
+}
+
+/// Reads the file at `path` for parsing, applying the same checks as
+/// `parse_file_limited`: empty files and files over `max_size` bytes are
+/// errors.
+#[cfg(feature = "fs")]
+pub fn read_file_limited<P: AsRef<Path>>(path: P, max_size: u64) -> Result<Input, Error> {
+    let path = path.as_ref();
+    check_size(fs::metadata(path::os_path(path))?.len(), max_size)?;
+    let data = Input::open(path)?;
+    if data.is_empty() {
+        return Err(Error::EmptyFile);
+    }
+    Ok(data)
+}
+
+/// Rejects empty files and files over `max_size` bytes from their size
+/// alone.
+#[cfg(feature = "fs")]
+pub(crate) fn check_size(size: u64, max_size: u64) -> Result<(), Error> {
+    if size == 0 {
+        Err(Error::EmptyFile)
+    } else if size > max_size {
+        Err(Error::TooLarge {
+            size,
+            limit: max_size,
+        })
+    } else {
+        Ok(())
+    }
+}
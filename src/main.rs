@@ -1,468 +1,1443 @@
-use getopts::Options;
-use std::collections::HashMap;
-use std::env;
-use std::fs;
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
-use encoding_rs::UTF_16LE;
-use encoding_rs_io::DecodeReaderBytesBuilder;
-use quick_xml::de::from_str;
-use serde::Deserialize;
-
-#[derive(Debug)]
-struct JobDate {
-    year: u16,
-    month: u16,
-    weekday: Option<u16>,
-    day: u16,
-    hour: u16,
-    minute: u16,
-    second: u16,
-}
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::io;
+use std::io::IsTerminal;
+#[cfg(feature = "parallel")]
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-impl JobDate {
-    fn new(data: &[u8], scheduled: bool) -> JobDate {
-        let year = u16::from_le_bytes([data[0], data[1]]);
-        let month = u16::from_le_bytes([data[2], data[3]]);
-        let weekday = if !scheduled {
-            Some(u16::from_le_bytes([data[4], data[5]]))
-        } else {
-            None
-        };
-        let day = u16::from_le_bytes([data[6], data[7]]);
-        let hour = u16::from_le_bytes([data[8], data[9]]);
-        let minute = u16::from_le_bytes([data[10], data[11]]);
-        let second = u16::from_le_bytes([data[12], data[13]]);
-        JobDate {
-            year,
-            month,
-            weekday,
-            day,
-            hour,
-            minute,
-            second,
-        }
-    }
+use cli::aggregate;
+use cli::check::HygieneCheck;
+use cli::commands::{self, CreateOptions, ScanControls, Settings};
+use cli::bundle;
+use cli::completions;
+use cli::config::{self, Defaults};
+use cli::csv::{self, CsvStyle, QuoteStyle};
+use cli::extension;
+use cli::inline;
+use cli::manifest;
+use cli::output::{self, Format};
+use cli::schedlog::SchedLogCheck;
+use cli::selftest;
+#[cfg(feature = "http")]
+use cli::serve::{self, ServeOptions};
+use cli::sink::Destination;
+use cli::terminal::Capabilities;
+use cli::timezone::Zone;
+use cli::truncate;
+use cli::{EXIT_FATAL, EXIT_SUCCESS};
+use jobfileparser::date::{format_unix, is_iso_timestamp};
+use jobfileparser::job;
+use jobfileparser::{
+    Case, DateStyle, DetectOptions, Locale, PathRules, ScanOptions, UuidFormat, UUID,
+    DEFAULT_MAX_FILE_SIZE,
+};
 
-    fn format_date(&self) -> String {
-        let weekdays = [
-            "Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
-        ];
-        let months = [
-            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
-        ];
-        if let Some(weekday) = self.weekday {
-            format!(
-                "{} {} {} {:02}:{:02}:{:02} {}",
-                weekdays[weekday as usize],
-                months[self.month as usize - 1],
-                self.day,
-                self.hour,
-                self.minute,
-                self.second,
-                self.year
-            )
-        } else {
-            format!(
-                "{} {} {:02}:{:02}:{:02} {}",
-                months[self.month as usize - 1],
-                self.day,
-                self.hour,
-                self.minute,
-                self.second,
-                self.year
-            )
-        }
-    }
+mod cli;
+
+/// Parser for Windows Task Scheduler `.job` files and task XML.
+#[derive(Parser)]
+#[command(name = "jobfileparser", version)]
+struct Cli {
+    /// Read option defaults from this TOML file.
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Print the JSON Schema of the json/jsonl output records and exit.
+    #[arg(long, exclusive = true)]
+    print_schema: bool,
+    #[command(flatten)]
+    legacy: LegacyArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug)]
-struct UUID {
-    uuid0: u32,
-    uuid1: u16,
-    uuid2: u16,
-    uuid3: u16,
-    uuid4: u16,
-    uuid5: u16,
-    uuid6: u16,
+/// The flat options from before subcommands existed, kept as hidden aliases
+/// of `parse` and `scan` for one release.
+#[derive(Args)]
+struct LegacyArgs {
+    #[arg(short, long, hide = true)]
+    file: Option<PathBuf>,
+    #[arg(short, long, hide = true)]
+    dir: Vec<PathBuf>,
+    #[arg(short, long, hide = true, value_parser = ["text", "json", "jsonl"])]
+    output_format: Option<String>,
+    #[arg(long, hide = true)]
+    detect: bool,
+    #[arg(long, hide = true)]
+    fail_on_findings: bool,
+    #[arg(long, hide = true)]
+    exit_zero_always: bool,
 }
 
-impl UUID {
-    fn new(data: &[u8]) -> UUID {
-        UUID {
-            uuid0: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
-            uuid1: u16::from_le_bytes([data[4], data[5]]),
-            uuid2: u16::from_le_bytes([data[6], data[7]]),
-            uuid3: u16::from_be_bytes([data[8], data[9]]),
-            uuid4: u16::from_be_bytes([data[10], data[11]]),
-            uuid5: u16::from_be_bytes([data[12], data[13]]),
-            uuid6: u16::from_be_bytes([data[14], data[15]]),
-        }
-    }
-
-    fn format_uuid(&self) -> String {
-        format!(
-            "{{{:08X}-{:04X}-{:04X}-{:04X}-{:02X}{:02X}{:02X}}}",
-            self.uuid0, self.uuid1, self.uuid2, self.uuid3, self.uuid4, self.uuid5, self.uuid6
-        )
+impl LegacyArgs {
+    fn is_used(&self) -> bool {
+        self.file.is_some()
+            || !self.dir.is_empty()
+            || self.output_format.is_some()
+            || self.detect
+            || self.fail_on_findings
+            || self.exit_zero_always
     }
 }
 
-#[derive(Debug)]
-struct Job {
-    product_info: u16,
-    file_version: u16,
-    uuid: UUID,
-    priority: u32,
-    max_run_time: i32,
-    exit_code: i32,
-    status: i32,
-    flags: u32,
-    run_date: JobDate,
-    scheduled_date: JobDate,
-    name: String,
-    parameters: String,
-    working_directory: String,
-    user: String,
-    comment: String,
+/// Options shared by the commands that print parsed records.
+#[derive(Args)]
+struct OutputArgs {
+    /// Output format [default: the one the extension of the -w file stands
+    /// for, or text]. For shell pipelines use `minimal`: one line per record
+    /// with the path, user, command line and last run separated by tabs, in
+    /// a column order that will not change.
+    #[arg(short, long, value_name = "FORMAT", value_parser = Format::NAMES)]
+    output_format: Option<String>,
+    /// The field delimiter of -o csv, a single ASCII character [default: ,].
+    #[arg(long, value_name = "CHAR", value_parser = csv::parse_delimiter)]
+    delimiter: Option<char>,
+    /// Which -o csv fields are quoted [default: necessary].
+    #[arg(long, value_enum, value_name = "STYLE")]
+    quote_style: Option<QuoteStyleArg>,
+    /// Also write a row per trigger to FILE (-o csv only): type, start, end,
+    /// enabled, schedule, interval and duration, keyed by the path and
+    /// record_index of its record's row.
+    #[arg(long, value_name = "FILE")]
+    csv_triggers: Option<PathBuf>,
+    /// Also write a row per action to FILE (-o csv only): type, command,
+    /// arguments and class ID, keyed like --csv-triggers.
+    #[arg(long, value_name = "FILE")]
+    csv_actions: Option<PathBuf>,
+    /// The time zone the clock of the machine the artifacts came from was
+    /// set to, such as Europe/Berlin, +05:30 or a POSIX TZ rule; the
+    /// timeline formats convert the local times inside jobs and tasks with
+    /// it.
+    #[arg(long, value_name = "ZONE", value_parser = Zone::parse)]
+    timezone: Option<Zone>,
+    /// Take the local times inside artifacts as UTC in the timeline
+    /// formats, instead of giving --timezone.
+    #[arg(long, conflicts_with = "timezone")]
+    assume_utc: bool,
+    /// Print a "# <path>" line in place of the asterisk rules and File:
+    /// line around each job in text output, and no group headers.
+    #[arg(long)]
+    no_banner: bool,
+    /// Leave out the header row of -o csv and of the --group-by-dir table.
+    #[arg(long)]
+    no_header: bool,
+    /// Wrap text output at COLUMNS characters; 0 never wraps [default: the
+    /// terminal's width, or no wrapping when stdout is not a terminal].
+    #[arg(long, value_name = "COLUMNS")]
+    width: Option<usize>,
+    /// Cut strings from the file, such as a job's comment, to their first
+    /// CHARS characters before printing them, marking each with its length
+    /// and SHA-256. -o openioc always prints them whole.
+    #[arg(long, value_name = "CHARS", default_value_t = truncate::DEFAULT_MAX_STRING_LENGTH)]
+    max_string_length: usize,
+    /// Print strings whole in -o json and -o jsonl, however long.
+    #[arg(long)]
+    no_truncate: bool,
+    /// Write the records to FILE instead of stdout; messages still go to
+    /// stderr.
+    #[arg(short = 'w', long, value_name = "FILE")]
+    write: Option<PathBuf>,
+    /// Once the -w file has grown past MB megabytes (fractions allowed),
+    /// rename it to FILE.<YYYYMMDDTHHMMSS> and start a new one. Files are
+    /// only rotated between records. Not for -o json, -o dot, -o openioc or
+    /// -o taskxml-bundle.
+    #[arg(long, value_name = "MB", requires = "write", value_parser = parse_megabytes)]
+    rotate_size: Option<u64>,
+    /// With --rotate-size, keep only the N newest rotated files.
+    #[arg(long, value_name = "N", requires = "rotate_size")]
+    rotate_count: Option<usize>,
+    /// Compress the -w file with gzip, adding .gz to its name unless it
+    /// ends in it. The file is flushed every few records, so a run that
+    /// dies still leaves most of them readable.
+    #[arg(long, requires = "write")]
+    gzip: bool,
+    /// Write -o's format to the -w file even if its extension stands for
+    /// another one.
+    #[arg(long, requires = "write")]
+    force_format: bool,
+    /// Overwrite the -w file even if it holds another format than the one
+    /// written.
+    #[arg(long, requires = "write")]
+    force: bool,
+    /// With -o taskxml-bundle, write each task to a file of its own in DIR,
+    /// named after the file it came from, instead of the bundle; a line
+    /// per file written takes the bundle's place. Files already in DIR are
+    /// not replaced.
+    #[arg(long, value_name = "DIR")]
+    split_out_dir: Option<PathBuf>,
+    /// Write a chain-of-custody manifest to FILE when the run ends, or is
+    /// stopped with Ctrl-C: every input with its size, SHA-1, SHA-256 and
+    /// parse status, the version and command line, the start and end
+    /// times, and the hashes of the -w file. See verify-manifest.
+    #[arg(long, value_name = "FILE", conflicts_with = "rotate_size")]
+    manifest: Option<PathBuf>,
+    /// Keep what each file parsed to in DIR, under the SHA-256 of its
+    /// bytes, and on later runs take unchanged files from there instead of
+    /// parsing them again. Filters and heuristics still run every time.
+    #[arg(long, value_name = "DIR")]
+    cache: Option<PathBuf>,
+    /// Run the built-in heuristics and report their findings.
+    #[arg(long, overrides_with = "no_detect")]
+    detect: bool,
+    /// Do not run the heuristics, even if the config file or environment
+    /// turns them on.
+    #[arg(long, overrides_with = "detect")]
+    no_detect: bool,
+    /// Exit with 1 instead of 3 when heuristics match.
+    #[arg(long, overrides_with = "no_fail_on_findings")]
+    fail_on_findings: bool,
+    /// Exit with 3 when heuristics match, even if the config file or
+    /// environment sets fail_on_findings.
+    #[arg(long, overrides_with = "fail_on_findings")]
+    no_fail_on_findings: bool,
+    /// Exit with 1 when any record has warnings, as if it had failed.
+    #[arg(long)]
+    fail_on_warnings: bool,
+    /// Exit with 0 unless the arguments are invalid.
+    #[arg(long, overrides_with = "no_exit_zero_always")]
+    exit_zero_always: bool,
+    /// Exit with the usual codes, even if the config file or environment
+    /// sets exit_zero_always.
+    #[arg(long, overrides_with = "exit_zero_always")]
+    no_exit_zero_always: bool,
+    /// Embed each file, base64-encoded, in its record with its size and
+    /// SHA-256 (-o json or jsonl only).
+    #[arg(long)]
+    embed_raw: bool,
+    /// Add these fields to each record (-o json or jsonl only):
+    /// absolute_path, the absolute path of the file whatever path the
+    /// record names it by.
+    #[arg(long, value_name = "LIST", value_enum, value_delimiter = ',')]
+    fields: Vec<FieldArg>,
+    /// Name task XML elements the parser does not recognize on stderr.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Only report jobs that were due to run by --now but never ran
+    /// (failures are still reported).
+    #[arg(long)]
+    never_ran: bool,
+    /// Only report tasks that are effectively enabled as of --now: not
+    /// disabled themselves, with a trigger that is enabled and not past
+    /// its end boundary (failures are still reported).
+    #[arg(long, conflicts_with = "disabled_only")]
+    enabled_only: bool,
+    /// Only report tasks that are effectively disabled as of --now.
+    #[arg(long)]
+    disabled_only: bool,
+    /// Only report tasks with no triggers, such as persistence left behind
+    /// when its triggers were removed (failures are still reported).
+    #[arg(long)]
+    no_triggers: bool,
+    /// Only report tasks with more than one action.
+    #[arg(long)]
+    multi_action: bool,
+    /// Only report records with findings, from --detect, --detect-paths,
+    /// --hive, --schedlog or --classify; failures still go to stderr. How many records
+    /// there were in all, and how many had findings, is printed at the end,
+    /// and -o jsonl ends with a summary record giving the same counts.
+    #[arg(long)]
+    only_findings: bool,
+    /// Print only the warnings, one "<path>: <code>: <message>" line each,
+    /// such as those about triggers that end before they start or empty
+    /// schedules; files without warnings print nothing.
+    #[arg(long, conflicts_with = "output_format")]
+    validate_only: bool,
+    /// Only report jobs with this UUID, in any of the --uuid-format forms
+    /// (failures are still reported).
+    #[arg(long, value_name = "UUID", value_parser = parse_uuid)]
+    find_uuid: Option<UUID>,
+    /// Skip artifacts identical to one already written, such as copies of
+    /// a job in several directories (the first path is kept).
+    #[arg(long)]
+    dedupe: bool,
+    /// Print how long reading, parsing and writing took on stderr at the
+    /// end; with -v, also each file's times as it is read.
+    #[arg(long)]
+    timings: bool,
+    /// Name each file that took longer than MS milliseconds to read, parse
+    /// and write on stderr as it is written, and list the ten slowest at
+    /// the end.
+    #[arg(long, value_name = "MS")]
+    slow_threshold: Option<u64>,
+    /// Print the script paths, URLs and UNC paths named by the most
+    /// records on stderr at the end, with how many records name each.
+    #[arg(long)]
+    stats: bool,
+    /// Count files done, out of how many, and failures on stderr as the
+    /// run goes: one line rewritten in place on a terminal, a line every
+    /// few seconds otherwise.
+    #[arg(long)]
+    progress: bool,
+    /// The time to judge "due" against, as YYYY-MM-DDTHH:MM:SS [default:
+    /// the current time in UTC].
+    #[arg(long, value_name = "TIMESTAMP", value_parser = parse_now)]
+    now: Option<String>,
+    /// Flag tasks running as SYSTEM or Administrators from user-writable or
+    /// unusual directories, independently of --detect.
+    #[arg(long)]
+    detect_paths: bool,
+    /// A TOML file tuning the heuristics, such as the directory lists of
+    /// --detect-paths.
+    #[arg(long, value_name = "FILE")]
+    rules: Option<PathBuf>,
+    /// Run the rules in the *.toml files of DIR, each a condition over
+    /// record fields with an ATT&CK technique and score, independently of
+    /// --detect. Every file is checked before anything is read.
+    #[arg(long, value_name = "DIR")]
+    rules_dir: Option<PathBuf>,
+    /// Take the --rules file and --rules-dir from a bundle written by
+    /// compile-rules, or from the files it was compiled from if they have
+    /// changed since.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["rules", "rules_dir"])]
+    rules_bundle: Option<PathBuf>,
+    /// Attach each task's runs, first and last logged time and exit codes
+    /// from this SchedLgU.txt or CSV export of the TaskScheduler/Operational
+    /// log, matched by task name, and list on stderr the artifacts the log
+    /// never mentions and the logged tasks with no artifact.
+    #[arg(long, value_name = "FILE")]
+    schedlog: Option<PathBuf>,
+    /// Label each task as a Microsoft default, a modified Microsoft default
+    /// or not a default, from a built-in list of what Microsoft's own tasks
+    /// run. Modified defaults are reported as modified-default-task; the
+    /// unmodified ones are not written unless they have other findings,
+    /// and are counted on stderr at the end. Files without an extension
+    /// are scanned too, as under System32\Tasks.
+    #[arg(long)]
+    classify: bool,
+    /// Windows only: record the owner SID of each file and whether groups
+    /// of accounts other than administrators, such as Users or Everyone,
+    /// may change it, and flag tasks running as SYSTEM whose file they may
+    /// as writable-system-task.
+    #[arg(long)]
+    acl: bool,
+    /// Audit schedule hygiene rather than look for attacks: add each
+    /// task's operational problems, such as no time limit, normal priority
+    /// or a program that is not there, to its record as issues, and end
+    /// with a summary of the estate that names the programs several tasks
+    /// start in the same minute.
+    #[arg(long)]
+    check: bool,
+    /// With --check, flag tasks whose UNC paths name a server listed in
+    /// FILE, one per line.
+    #[arg(long, value_name = "FILE", requires = "check")]
+    decommissioned: Option<PathBuf>,
+    /// With --check, look for the programs tasks run on the Windows volume
+    /// mounted at DIR [default: the running system, on Windows only].
+    #[arg(long, value_name = "DIR", requires = "check")]
+    check_root: Option<PathBuf>,
+    /// Tell apart accounts, task names and paths that differ only in case
+    /// when matching them: the --detect-paths lists, --by-user groups,
+    /// --schedlog and --hive names, and --stats counts.
+    #[arg(long)]
+    case_sensitive: bool,
+    /// The name_entropy score, from 0 to 1, from which --detect flags a
+    /// task name as random.
+    #[arg(long, value_name = "SCORE", value_parser = parse_score, default_value_t = 0.8)]
+    name_entropy_threshold: f64,
+    /// The percentage of a binary job's bytes that must belong to a section
+    /// the parser knows; below it, as when data was appended to the file,
+    /// the record gets a low_coverage warning. Every job's coverage is its
+    /// record's `coverage`.
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        value_parser = parse_percent,
+        default_value_t = job::DEFAULT_MIN_COVERAGE
+    )]
+    min_coverage: f64,
+    /// How many minutes a last run may be from the nearest time the
+    /// triggers could have started it before --detect reports it as
+    /// off-schedule-run.
+    #[arg(long, value_name = "MINUTES", default_value_t = 5)]
+    schedule_tolerance: u64,
+    /// How the text format prints dates; the JSON formats always use ISO
+    /// 8601. `locale` takes the language from LC_ALL, LC_TIME or LANG
+    /// (German, French and Spanish, else English).
+    #[arg(long, value_enum, default_value_t = DateStyleArg::Classic)]
+    date_style: DateStyleArg,
+    /// How the text and JSON formats write a job's UUID.
+    #[arg(long, value_enum, default_value_t = UuidFormatArg::BracedUpper)]
+    uuid_format: UuidFormatArg,
+    /// Browse the results interactively instead of printing them.
+    #[cfg(feature = "tui")]
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "output_format", "embed_raw", "fields", "delimiter", "quote_style",
+            "csv_triggers", "csv_actions", "write", "timezone", "assume_utc",
+        ]
+    )]
+    tui: bool,
 }
 
-impl Job {
-    fn new(data: &[u8]) -> Job {
-        let product_info = u16::from_le_bytes([data[0], data[1]]);
-        let file_version = u16::from_le_bytes([data[2], data[3]]);
-        let uuid = UUID::new(&data[4..20]);
-        let priority = u32::from_le_bytes([data[32], data[33], data[34], data[35]]);
-        let max_run_time = i32::from_le_bytes([data[36], data[37], data[38], data[39]]);
-        let exit_code = i32::from_le_bytes([data[40], data[41], data[42], data[43]]);
-        let status = i32::from_le_bytes([data[44], data[45], data[46], data[47]]);
-        let flags = u32::from_le_bytes([data[48], data[49], data[50], data[51]]);
-        let run_date = JobDate::new(&data[52..68], false);
-        let scheduled_date = JobDate::new(&data[68..88], true);
-        let name_length = u16::from_le_bytes([data[70], data[71]]);
-        let name = std::str::from_utf8(&data[72..72 + name_length as usize * 2])
-            .unwrap()
-            .replace('\x00', "");
-        let parameter_size = u16::from_le_bytes([data[72 + name_length as usize * 2], data[73 + name_length as usize * 2]]);
-        let parameters = std::str::from_utf8(&data[74 + name_length as usize * 2..74 + name_length as usize * 2 + parameter_size as usize * 2])
-            .unwrap()
-            .replace('\x00', "");
-        let working_directory_size = u16::from_le_bytes([data[74 + name_length as usize * 2 + parameter_size as usize * 2], data[75 + name_length as usize * 2 + parameter_size as usize * 2]]);
-        let working_directory = std::str::from_utf8(&data[76 + name_length as usize * 2 + parameter_size as usize * 2..76 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2])
-            .unwrap()
-            .replace('\x00', "");
-        let user_size = u16::from_le_bytes([data[76 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2], data[77 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2]]);
-        let user = std::str::from_utf8(&data[78 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2..78 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2 + user_size as usize * 2])
-            .unwrap()
-            .replace('\x00', "");
-        let comment_size = u16::from_le_bytes([data[78 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2 + user_size as usize * 2], data[79 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2 + user_size as usize * 2]]);
-        let comment = std::str::from_utf8(&data[80 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2 + user_size as usize * 2..80 + name_length as usize * 2 + parameter_size as usize * 2 + working_directory_size as usize * 2 + user_size as usize * 2 + comment_size as usize * 2])
-            .unwrap()
-            .replace('\x00', "");
-
-        Job {
-            product_info,
-            file_version,
-            uuid,
-            priority,
-            max_run_time,
-            exit_code,
-            status,
-            flags,
-            run_date,
-            scheduled_date,
-            name,
-            parameters,
-            working_directory,
-            user,
-            comment,
-        }
-    }
+#[derive(Clone, Copy, ValueEnum)]
+enum DateStyleArg {
+    /// Friday Mar 1 02:13:00 2024
+    Classic,
+    /// 2024-03-01T02:13:00
+    Iso,
+    /// Freitag, 1. März 2024 02:13:00
+    Locale,
+}
 
-    fn format_job(&self) -> String {
-        let products: HashMap<u16, &str> = vec![
-            (0x400, "Windows NT 4.0"),
-            (0x500, "Windows 2000"),
-            (0x501, "Windows XP"),
-            (0x600, "Windows Vista"),
-            (0x601, "Windows 7"),
-            (0x602, "Windows 8"),
-            (0x603, "Windows 8.1"),
-            (0xa00, "Windows 10"),
-        ]
-        .into_iter()
-        .collect();
+#[derive(Clone, Copy, ValueEnum)]
+enum UuidFormatArg {
+    /// {0A1B2C3D-0000-0000-0000-000000000000}
+    BracedUpper,
+    /// {0a1b2c3d-0000-0000-0000-000000000000}
+    BracedLower,
+    /// 0A1B2C3D-0000-0000-0000-000000000000
+    PlainUpper,
+    /// 0a1b2c3d-0000-0000-0000-000000000000
+    PlainLower,
+}
 
-        let task_status: HashMap<i32, &str> = vec![
-            (0x41300, "Task is ready to run"),
-            (0x41301, "Task is running"),
-            (0x41302, "Task is disabled"),
-            (0x41303, "Task has not run"),
-            (0x41304, "No more scheduled runs"),
-            (0x41305, "Properties not set"),
-            (0x41306, "Last run terminated by user"),
-            (0x41307, "No triggers/triggers disabled"),
-            (0x41308, "Triggers do not have set run times"),
-        ]
-        .into_iter()
-        .collect();
+#[derive(Clone, Copy, ValueEnum)]
+enum QuoteStyleArg {
+    /// Quote every field.
+    Always,
+    /// Quote fields holding the delimiter, a quote or a line break.
+    Necessary,
+    /// Never quote, even if that makes a row ambiguous.
+    Never,
+}
 
-        let flags: HashMap<u32, &str> = vec![
-            (0x1, "TASK_APPLICATION_NAME"),
-            (0x200000, "TASK_FLAG_RUN_ONLY_IF_LOGGED_ON"),
-            (0x100000, "TASK_FLAG_SYSTEM_REQUIRED"),
-            (0x80000, "TASK_FLAG_RESTART_ON_IDLE_RESUME"),
-            (0x40000, "TASK_FLAG_RUN_IF_CONNECTED_TO_INTERNET"),
-            (0x20000, "TASK_FLAG_HIDDEN"),
-            (0x10000, "TASK_FLAG_RUN_ONLY_IF_DOCKED"),
-            (0x80000000, "TASK_FLAG_KILL_IF_GOING_ON_BATTERIES"),
-            (0x40000000, "TASK_FLAG_DONT_START_IF_ON_BATTERIES"),
-            (0x20000000, "TASK_FLAG_KILL_ON_IDLE_END"),
-            (0x10000000, "TASK_FLAG_START_ONLY_IF_IDLE"),
-            (0x4000000, "TASK_FLAG_DISABLED"),
-            (0x2000000, "TASK_FLAG_DELETE_WHEN_DONE"),
-            (0x1000000, "TASK_FLAG_INTERACTIVE"),
-        ]
-        .into_iter()
-        .collect();
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum FieldArg {
+    /// The absolute path of the file the record was read from.
+    #[value(name = "absolute_path")]
+    AbsolutePath,
+}
 
-        let priorities: HashMap<u32, &str> = vec![
-            (0x20000000, "NORMAL_PRIORITY_CLASS"),
-            (0x40000000, "IDLE_PRIORITY_CLASS"),
-            (0x80000000, "HIGH_PRIORITY_CLASS"),
-            (0x100000, "REALTIME_PRIORITY_CLASS"),
-        ]
-        .into_iter()
-        .collect();
+#[derive(Clone, Copy, ValueEnum)]
+enum Target {
+    Job,
+    Xml,
+}
 
-        let mut result = String::new();
+#[derive(Subcommand)]
+enum Command {
+    /// Parse one or more job or task XML files.
+    Parse {
+        /// Files to parse.
+        #[arg(required_unless_present_any = ["files_from", "hex", "b64"], value_name = "PATH")]
+        paths: Vec<PathBuf>,
+        /// Also parse the paths listed in this file, one per line ("-" for
+        /// stdin). Blank lines and lines starting with '#' are skipped.
+        #[arg(long, value_name = "FILE")]
+        files_from: Option<PathBuf>,
+        /// Parse the job or task spelled by STRING in hex instead of files,
+        /// reported as <inline>. Whitespace and 0x prefixes are ignored.
+        #[arg(long, value_name = "STRING", conflicts_with_all = ["paths", "files_from", "b64"])]
+        hex: Option<String>,
+        /// Parse the job or task encoded by STRING in base64 instead of
+        /// files, reported as <inline>. Whitespace is ignored.
+        #[arg(long, value_name = "STRING", conflicts_with_all = ["paths", "files_from"])]
+        b64: Option<String>,
+        /// Resolve relative paths from --files-from against this directory.
+        #[arg(long, value_name = "DIR", requires = "files_from")]
+        base_dir: Option<PathBuf>,
+        /// Skip files larger than this many bytes.
+        #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_FILE_SIZE)]
+        max_file_size: u64,
+        /// Parse files as their extension says (.xml as task XML, anything
+        /// else as a binary job) instead of going by their content.
+        #[arg(long)]
+        strict_extension: bool,
+        /// Read a job whose strings run past the end of the file as far as
+        /// it goes, with a warning, instead of failing it.
+        #[arg(long)]
+        lenient: bool,
+        /// After the record, print the file itself: task XML indented, a
+        /// binary job as a hex dump with its sections marked (header,
+        /// strings, user data, reserved data, triggers, signature). Only
+        /// for a single file.
+        #[arg(long)]
+        show_source: bool,
+        /// Read each input as a fragment of task XML instead of a whole
+        /// job or task: a Triggers, Actions or Settings element, or a single
+        /// trigger or action such as <CalendarTrigger> or <Exec>, as quoted
+        /// in scripts and detection rules. Prints its section of the report.
+        #[arg(
+            long,
+            conflicts_with_all = ["output_format", "show_source", "strict_extension", "lenient"]
+        )]
+        fragment: bool,
+        /// Name files by their path below the deepest directory they all
+        /// share, with / between directories, instead of as given.
+        #[arg(long, conflicts_with = "fragment")]
+        relative_paths: bool,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Parse every job and task XML file in a directory.
+    Scan {
+        /// Directories to scan. Directories given twice, or with -r inside
+        /// another one given, are scanned once; with several, records name
+        /// the one they were found in and a table at the end counts them.
+        #[arg(required_unless_present_any = ["image_root", "live"], value_name = "DIR")]
+        dirs: Vec<PathBuf>,
+        /// Scan the task directories of the Windows volume mounted at DIR,
+        /// Windows\Tasks and Windows\System32\Tasks in any letter case,
+        /// and report files by their path on Windows (C:\Windows\...).
+        /// Implies --recursive.
+        #[arg(long, value_name = "DIR", conflicts_with = "dirs")]
+        image_root: Option<PathBuf>,
+        /// Descend into subdirectories.
+        #[arg(short, long)]
+        recursive: bool,
+        /// After the initial scan, keep reporting files as they are created,
+        /// modified or removed until interrupted.
+        #[cfg(feature = "watch")]
+        #[cfg_attr(
+            feature = "tui",
+            arg(
+                long,
+                conflicts_with_all = [
+                    "tui", "limit", "list_only", "by_user", "group_by_dir", "image_root",
+                    "relative_paths",
+                ]
+            )
+        )]
+        #[cfg_attr(
+            not(feature = "tui"),
+            arg(
+                long,
+                conflicts_with_all = [
+                    "limit", "list_only", "by_user", "group_by_dir", "image_root",
+                    "relative_paths",
+                ]
+            )
+        )]
+        watch: bool,
+        /// Read and parse files on N threads. Records are written whole,
+        /// one at a time, in the order their files finish.
+        #[cfg(feature = "parallel")]
+        #[cfg_attr(
+            feature = "watch",
+            arg(
+                long,
+                value_name = "N",
+                default_value = "1",
+                conflicts_with_all = ["watch", "list_only", "by_user", "group_by_dir"]
+            )
+        )]
+        #[cfg_attr(
+            not(feature = "watch"),
+            arg(
+                long,
+                value_name = "N",
+                default_value = "1",
+                conflicts_with_all = ["list_only", "by_user", "group_by_dir"]
+            )
+        )]
+        threads: NonZeroUsize,
+        /// Skip files larger than this many bytes.
+        #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_FILE_SIZE)]
+        max_file_size: u64,
+        /// Parse files as their extension says (.xml as task XML, anything
+        /// else as a binary job) instead of going by their content.
+        #[arg(long)]
+        strict_extension: bool,
+        /// Read a job whose strings run past the end of the file as far as
+        /// it goes, with a warning, instead of failing it.
+        #[arg(long)]
+        lenient: bool,
+        /// Stop after emitting N records.
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+        /// Print which files would be parsed, and the format their extension
+        /// names, without reading them.
+        #[cfg_attr(
+            feature = "tui",
+            arg(long, conflicts_with_all = ["tui", "detect", "embed_raw", "only_findings"])
+        )]
+        #[cfg_attr(
+            not(feature = "tui"),
+            arg(long, conflicts_with_all = ["detect", "embed_raw", "only_findings"])
+        )]
+        list_only: bool,
+        /// Print, per user, the number of tasks, their distinct commands and
+        /// the earliest and latest run dates instead of the records. Nothing
+        /// is printed until the scan ends.
+        #[cfg_attr(
+            feature = "tui",
+            arg(
+                long,
+                conflicts_with_all =
+                    ["tui", "detect", "list_only", "embed_raw", "image_root", "write"]
+            )
+        )]
+        #[cfg_attr(
+            not(feature = "tui"),
+            arg(
+                long,
+                conflicts_with_all = ["detect", "list_only", "embed_raw", "image_root", "write"]
+            )
+        )]
+        by_user: bool,
+        /// With --by-user, group DOMAIN\user and user@domain under user.
+        #[arg(long, requires = "by_user")]
+        strip_domain: bool,
+        /// Group records by the first DEPTH directories under DIR, with a
+        /// header per group and a summary table; JSON records get a group
+        /// field instead.
+        #[arg(long, value_name = "DEPTH", conflicts_with = "by_user")]
+        group_by_dir: Option<usize>,
+        /// Name files by their path below DIR, with / between directories,
+        /// instead of the path they were found at.
+        #[arg(long, conflicts_with = "image_root")]
+        relative_paths: bool,
+        /// Scan the task directories of the system running the tool,
+        /// %SystemRoot%\Tasks and %SystemRoot%\System32\Tasks. Implies
+        /// --recursive.
+        #[arg(long, conflicts_with_all = ["dirs", "image_root"])]
+        live: bool,
+        /// Scan directories that are the task directories of the system
+        /// running the tool, or hold them, instead of refusing to.
+        #[arg(long)]
+        i_know_this_is_live: bool,
+        /// Check the task XML found against the TaskCache of this SOFTWARE
+        /// hive: tasks it does not list are flagged as not-in-task-cache,
+        /// and registered tasks with no file are listed at the end.
+        #[cfg(feature = "hive")]
+        #[cfg_attr(
+            feature = "watch",
+            arg(
+                long,
+                value_name = "SOFTWARE",
+                conflicts_with_all = ["watch", "list_only", "by_user"]
+            )
+        )]
+        #[cfg_attr(
+            not(feature = "watch"),
+            arg(long, value_name = "SOFTWARE", conflicts_with_all = ["list_only", "by_user"])
+        )]
+        hive: Option<PathBuf>,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Convert a binary job to task XML or back. Only the action, author,
+    /// comment, and enabled and hidden state carry over.
+    Convert {
+        /// Job or task XML file to convert, or with --out-dir a directory.
+        input: PathBuf,
+        /// Format to convert to.
+        #[arg(long, value_enum)]
+        to: Target,
+        /// Where to write the result [default: the input with the new extension].
+        #[arg(short = 'O', long, value_name = "FILE", conflicts_with = "out_dir")]
+        out_file: Option<PathBuf>,
+        /// Convert every file of the other format below the input directory
+        /// into this one, keeping their relative paths, and report what each
+        /// conversion dropped.
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+        /// Overwrite the output file if it exists.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Compare the parsed fields of two artifacts; exits with 1 if they differ.
+    Diff { a: PathBuf, b: PathBuf },
+    /// Fold the records of -o jsonl exports from many hosts into one group
+    /// per distinct task (command line and schedule), with the hosts it is
+    /// on and the fields that differ between them. Flags tasks that share a
+    /// name but run different commands.
+    Aggregate {
+        /// Exports written by -o jsonl, gzipped or not. Records are
+        /// credited to the hostname of their sidecar, or else to the
+        /// export's file name up to its first dot.
+        #[arg(required = true, value_name = "EXPORT")]
+        exports: Vec<PathBuf>,
+        #[arg(short, long, value_parser = ["text", "json"], default_value = "text")]
+        output_format: String,
+        /// Tell apart commands and task names that differ only in case.
+        #[arg(long)]
+        case_sensitive: bool,
+    },
+    /// Say what each file holds from its first 4 KiB, without parsing it:
+    /// a JSON line per file with its detected_format (job, taskxml or
+    /// unknown), confidence, evidence and size. Exits with 1 if any file
+    /// cannot be read.
+    Detect {
+        /// Files to look at.
+        #[arg(required_unless_present = "files_from", value_name = "PATH")]
+        paths: Vec<PathBuf>,
+        /// Also look at the paths listed in this file, one per line ("-"
+        /// for stdin). Blank lines and lines starting with '#' are skipped.
+        #[arg(long, value_name = "FILE")]
+        files_from: Option<PathBuf>,
+        /// Resolve relative paths from --files-from against this directory.
+        #[arg(long, value_name = "DIR", requires = "files_from")]
+        base_dir: Option<PathBuf>,
+    },
+    /// Hash the files a --manifest lists again and print those that are
+    /// missing or changed; exits with 1 if any are.
+    VerifyManifest {
+        /// Manifest written by --manifest.
+        manifest: PathBuf,
+    },
+    /// Check a --rules file and the rules of a --rules-dir and write them
+    /// to one bundle for --rules-bundle, which loads faster than the files.
+    CompileRules {
+        /// The --rules file to compile.
+        #[arg(long, value_name = "FILE", required_unless_present = "rules_dir")]
+        rules: Option<PathBuf>,
+        /// The --rules-dir to compile.
+        #[arg(long, value_name = "DIR")]
+        rules_dir: Option<PathBuf>,
+        /// Where to write the bundle.
+        #[arg(short = 'O', long = "output", value_name = "FILE")]
+        out: PathBuf,
+    },
+    /// Write a new job (or task XML) that runs an application.
+    Create {
+        /// Path of the program to run.
+        #[arg(long)]
+        application: String,
+        #[arg(long, default_value = "")]
+        parameters: String,
+        #[arg(long, default_value = "")]
+        working_directory: String,
+        #[arg(long, default_value = "")]
+        author: String,
+        #[arg(long, default_value = "")]
+        comment: String,
+        /// Create the task disabled.
+        #[arg(long)]
+        disabled: bool,
+        /// Hide the task from the Task Scheduler UI.
+        #[arg(long)]
+        hidden: bool,
+        /// Format to write.
+        #[arg(long, value_enum, default_value = "job")]
+        to: Target,
+        #[arg(short = 'O', long, value_name = "FILE")]
+        out_file: PathBuf,
+        /// Overwrite the output file if it exists.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Recover jobs and task XML embedded in a disk image or other raw data.
+    Carve {
+        /// Image or other raw data to search ("-" for stdin).
+        image: PathBuf,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Serve `POST /parse` and `GET /healthz` over HTTP.
+    #[cfg(feature = "http")]
+    Serve {
+        /// Address to listen on, such as 127.0.0.1:8080.
+        #[arg(value_name = "ADDR:PORT")]
+        addr: String,
+        /// Largest request body accepted, in bytes.
+        #[arg(long, value_name = "BYTES", default_value_t = 16 * 1024 * 1024)]
+        max_body_size: u64,
+        /// Seconds a single parse may take before the request fails.
+        #[arg(long, value_name = "SECS", default_value_t = 10)]
+        timeout: u64,
+        /// Number of requests handled concurrently.
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+        workers: u16,
+        /// Include heuristic findings in every response.
+        #[arg(long, overrides_with = "no_detect")]
+        detect: bool,
+        /// Leave heuristic findings out, even if the config file or
+        /// environment turns them on.
+        #[arg(long, overrides_with = "detect")]
+        no_detect: bool,
+    },
+    /// Print a shell completion script to stdout.
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Parse a few built-in sample artifacts in every output format and
+    /// report PASS or FAIL per check; exits with 1 if any check fails.
+    Selftest,
+}
 
-        result.push_str(&format!(
-            "Product Info: {}\n",
-            products.get(&self.product_info).unwrap_or(&"Unknown Version")
-        ));
-        result.push_str(&format!("File Version: {}\n", self.file_version));
-        result.push_str(&format!("UUID: {}\n", self.uuid.format_uuid()));
+/// The value a flag and its `--no-` counterpart give an option, or `None`
+/// if neither was given.
+fn negatable(on: bool, off: bool) -> Option<bool> {
+    match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
 
-        let mut priority_list = String::new();
-        for (key, value) in &priorities {
-            if self.priority & key == *key {
-                priority_list.push_str(value);
-                priority_list.push_str(", ");
-            }
-        }
-        if !priority_list.is_empty() {
-            result.push_str(&format!(
-                "Priorities: {}\n",
-                priority_list.trim_end_matches(", ")
-            ));
+/// Merges flags, environment variables and the config file, in that order
+/// of precedence.
+fn resolve_defaults(config_path: Option<&Path>, flags: Defaults) -> Result<Defaults, String> {
+    let (env, mut warnings) = config::from_env();
+    let path = match config_path {
+        Some(path) => Some(path.to_path_buf()),
+        None => config::default_path().filter(|path| path.is_file()),
+    };
+    let file = match path {
+        Some(path) => {
+            let (file, file_warnings) = config::from_file(&path)?;
+            warnings.extend(file_warnings);
+            file
         }
+        None => Defaults::default(),
+    };
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    Ok(flags.or(env).or(file))
+}
 
-        let hours = self.max_run_time / 3600000;
-        let ms = self.max_run_time % 3600000;
-        let minutes = ms / 60000;
-        let ms = ms % 60000;
-        let seconds = ms / 1000;
-        let ms = ms % 1000;
-        result.push_str(&format!(
-            "Maximum Run Time: {:02}:{:02}:{:02}.{} (HH:MM:SS.MS)\n",
-            hours, minutes, seconds, ms
-        ));
-        result.push_str(&format!("Exit Code: {}\n", self.exit_code));
-        result.push_str(&format!(
-            "Status: {}\n",
-            task_status.get(&self.status).unwrap_or(&"Unknown Status")
-        ));
-
-        let mut flag_list = String::new();
-        for (key, value) in &flags {
-            if self.flags & key == *key {
-                flag_list.push_str(value);
-                flag_list.push_str(", ");
-            }
+fn settings(config_path: Option<&Path>, output: &OutputArgs) -> Result<Settings, String> {
+    let flags = Defaults {
+        output_format: output.output_format.clone(),
+        detect: negatable(output.detect, output.no_detect),
+        fail_on_findings: negatable(output.fail_on_findings, output.no_fail_on_findings),
+        exit_zero_always: negatable(output.exit_zero_always, output.no_exit_zero_always),
+        ..Defaults::default()
+    };
+    let defaults = resolve_defaults(config_path, flags)?;
+    let rules = match &output.rules_bundle {
+        Some(path) => bundle::load(path, case(output))?,
+        None => {
+            let (file, dir) = (output.rules.as_deref(), output.rules_dir.as_deref());
+            bundle::from_sources(file, dir, case(output))?
         }
-        result.push_str(&format!("Flags: {}\n", flag_list.trim_end_matches(", ")));
-        result.push_str(&format!("Date Run: {}\n", self.run_date.format_date()));
-        result.push_str(&format!("Scheduled Date: {}\n", self.scheduled_date.format_date()));
-        result.push_str(&format!("Application: {}\n", self.name));
-        result.push_str(&format!("Parameters: {}\n", self.parameters));
-        result.push_str(&format!("Working Directory: {}\n", self.working_directory));
-        result.push_str(&format!("User: {}\n", self.user));
-        result.push_str(&format!("Comment: {}\n", self.comment));
-
-        result
+    };
+    let path_rules = path_rules(output, rules.path_rules);
+    let custom_rules = rules.rule_set;
+    let schedlog = output.schedlog.as_deref();
+    let schedlog = schedlog.map(|path| SchedLogCheck::load(path, case(output))).transpose()?;
+    let check = output.check.then(|| {
+        let decommissioned = output.decommissioned.as_deref();
+        let root = output.check_root.as_deref();
+        HygieneCheck::load(decommissioned, root, &now(output), case(output))
+    });
+    let check = check.transpose()?;
+    if output.acl && !cfg!(windows) {
+        return Err("--acl only works on Windows".to_string());
+    }
+    #[cfg(feature = "tui")]
+    if output.tui && !io::stdout().is_terminal() {
+        return Err("--tui needs an interactive terminal on stdout".to_string());
+    }
+    let name = output_format(output, defaults.output_format.as_deref())?;
+    let format = match name {
+        #[cfg(feature = "tui")]
+        _ if output.tui => Format::Collect,
+        _ if output.validate_only => Format::Validate,
+        None => Format::Text,
+        Some(name) => Format::from_name(name).ok_or(format!("unknown output format {}", name))?,
+    };
+    let json = matches!(format, Format::Json | Format::JsonLines);
+    let fields = match &defaults.fields {
+        _ if !output.fields.is_empty() => output.fields.clone(),
+        Some(names) if json => names
+            .iter()
+            .map(|name| FieldArg::from_str(name, false).map_err(|_| unknown_field(name)))
+            .collect::<Result<_, _>>()?,
+        _ => Vec::new(),
+    };
+    if output.embed_raw && !json {
+        return Err("--embed-raw needs -o json or -o jsonl".to_string());
+    }
+    if !output.fields.is_empty() && !json {
+        return Err("--fields needs -o json or -o jsonl".to_string());
+    }
+    if format != Format::Csv && (output.delimiter.is_some() || output.quote_style.is_some()) {
+        return Err("--delimiter and --quote-style need -o csv".to_string());
+    }
+    if format != Format::Csv && (output.csv_triggers.is_some() || output.csv_actions.is_some()) {
+        return Err("--csv-triggers and --csv-actions need -o csv".to_string());
     }
+    let timeline = matches!(format, Format::Timeline(_));
+    if !timeline && (output.timezone.is_some() || output.assume_utc) {
+        return Err("--timezone and --assume-utc need -o bodyfile, -o tln or -o plaso".to_string());
+    }
+    // Configured defaults only apply to the formats that take them.
+    let configured_zone = defaults.timezone.as_deref().filter(|_| timeline);
+    let configured_zone = configured_zone.map(Zone::parse).transpose();
+    let configured_zone = configured_zone.map_err(|e| format!("configured timezone: {}", e))?;
+    let timezone = output.timezone.clone().or_else(|| output.assume_utc.then(Zone::utc));
+    let timezone = timezone.or(configured_zone);
+    if timeline && timezone.is_none() {
+        return Err("the timeline formats convert the local times inside artifacts to UTC; \
+                    give the zone they are in with --timezone, or --assume-utc"
+            .to_string());
+    }
+    if output.no_truncate && !json {
+        return Err("--no-truncate needs -o json or -o jsonl".to_string());
+    }
+    if output.split_out_dir.is_some() && format != Format::TaskXmlBundle {
+        return Err("--split-out-dir needs -o taskxml-bundle".to_string());
+    }
+    // A JSON array or a graph cannot be cut into pieces that stand alone.
+    let whole = matches!(
+        format,
+        Format::Json | Format::Dot | Format::OpenIoc | Format::TaskXmlBundle
+    );
+    if output.rotate_size.is_some() && whole {
+        return Err("--rotate-size does not work with -o json, -o dot, -o openioc or -o \
+                    taskxml-bundle"
+            .to_string());
+    }
+    let write = output.write.as_ref().map(|path| Destination {
+        path: if output.gzip { extension::gzip_path(path) } else { path.clone() },
+        rotate_size: output.rotate_size,
+        rotate_count: output.rotate_count,
+        gzip: output.gzip,
+    });
+    if let (Some(destination), false) = (&write, output.force) {
+        let name = if output.validate_only { "text" } else { name.unwrap_or("text") };
+        extension::check_overwrite(&destination.path, name, output.gzip)?;
+    }
+    let detect = defaults.detect.unwrap_or(false);
+    // The browser scores each record by its findings.
+    #[cfg(feature = "tui")]
+    let detect = detect || output.tui;
+    Ok(Settings {
+        format,
+        detect,
+        fail_on_findings: defaults.fail_on_findings.unwrap_or(false),
+        fail_on_warnings: output.fail_on_warnings,
+        exit_zero_always: defaults.exit_zero_always.unwrap_or(false),
+        embed_raw: output.embed_raw,
+        verbose: output.verbose,
+        date_style: date_style(output.date_style),
+        uuid_format: uuid_format(output.uuid_format),
+        now: now(output),
+        never_ran: output.never_ran,
+        enabled_filter: enabled_filter(output),
+        no_triggers: output.no_triggers,
+        multi_action: output.multi_action,
+        only_findings: output.only_findings,
+        find_uuid: output.find_uuid.clone(),
+        dedupe: output.dedupe,
+        timings: output.timings,
+        slow_threshold: output.slow_threshold.map(Duration::from_millis),
+        absolute_path: fields.contains(&FieldArg::AbsolutePath),
+        stats: output.stats,
+        progress: output.progress,
+        detect_options: detect_options(output),
+        min_coverage: output.min_coverage,
+        path_rules,
+        custom_rules,
+        case: case(output),
+        schedlog,
+        check,
+        classify: output.classify,
+        acl: output.acl,
+        csv: csv_style(output),
+        csv_triggers: output.csv_triggers.clone(),
+        csv_actions: output.csv_actions.clone(),
+        timezone,
+        no_banner: output.no_banner,
+        no_header: output.no_header,
+        terminal: Capabilities::detect(
+            output.write.is_none() && io::stdout().is_terminal(),
+            output.width,
+        ),
+        max_string_length: (!output.no_truncate).then_some(output.max_string_length),
+        write,
+        manifest: output.manifest.clone(),
+        cache: output.cache.clone(),
+        split_out_dir: output.split_out_dir.clone(),
+    })
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename = "Task")]
-struct Task {
-    #[serde(rename = "RegistrationInfo")]
-    registration_info: RegistrationInfo,
-    #[serde(rename = "Triggers")]
-    triggers: Triggers,
-    #[serde(rename = "Settings")]
-    settings: Settings,
-    #[serde(rename = "Actions")]
-    actions: Actions,
+/// The `--output-format` name to write: `-o`, or else the one the
+/// extension of the `-w` file stands for, or else the configured default.
+/// A default the extension also stands for is kept. An `-o` the extension
+/// does not stand for is an error without `--force-format`.
+fn output_format<'a>(
+    output: &'a OutputArgs,
+    default: Option<&'a str>,
+) -> Result<Option<&'a str>, String> {
+    let Some(path) = output.write.as_deref() else {
+        return Ok(default);
+    };
+    let Some(formats) = extension::formats_for(path) else {
+        return Ok(default);
+    };
+    match output.output_format.as_deref() {
+        Some(given) if !formats.contains(&given) && !output.force_format => Err(format!(
+            "{} is named like -o {} output, but -o {} was given; rename it, or pass \
+             --force-format to write it anyway",
+            path.display(),
+            formats[0],
+            given
+        )),
+        Some(given) => Ok(Some(given)),
+        None => Ok(Some(default.filter(|name| formats.contains(name)).unwrap_or(formats[0]))),
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename = "RegistrationInfo")]
-struct RegistrationInfo {
-    #[serde(rename = "Author")]
-    author: Option<String>,
-    #[serde(rename = "Date")]
-    date: Option<String>,
-    #[serde(rename = "Description")]
-    description: Option<String>,
+fn unknown_field(name: &str) -> String {
+    let known: Vec<_> = FieldArg::value_variants()
+        .iter()
+        .filter_map(|field| field.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    format!("unknown field {} in the configured fields; expected {}", name, known.join(", "))
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename = "Triggers")]
-struct Triggers {
-    #[serde(rename = "CalendarTrigger", default)]
-    calendar_trigger: Option<CalendarTrigger>,
+fn enabled_filter(output: &OutputArgs) -> Option<bool> {
+    if output.enabled_only {
+        Some(true)
+    } else if output.disabled_only {
+        Some(false)
+    } else {
+        None
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename = "CalendarTrigger")]
-struct CalendarTrigger {
-    #[serde(rename = "StartBoundary")]
-    start_boundary: String,
-    #[serde(rename = "EndBoundary")]
-    end_boundary: Option<String>,
-    #[serde(rename = "Enabled")]
-    enabled: Option<bool>,
+fn parse_now(value: &str) -> Result<String, String> {
+    if is_iso_timestamp(value) {
+        Ok(value.to_string())
+    } else {
+        Err("expected YYYY-MM-DDTHH:MM:SS".to_string())
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename = "Settings")]
-struct Settings {
-    #[serde(rename = "Enabled")]
-    enabled: Option<bool>,
-    #[serde(rename = "AllowStartIfOnBatteries")]
-    allow_start_if_on_batteries: Option<bool>,
+fn parse_score(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(score) if (0.0..=1.0).contains(&score) => Ok(score),
+        _ => Err("expected a number from 0 to 1".to_string()),
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename = "Actions")]
-struct Actions {
-    #[serde(rename = "Exec")]
-    exec: Option<Exec>,
+fn parse_percent(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err("expected a number from 0 to 100".to_string()),
+    }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename = "Exec")]
-struct Exec {
-    #[serde(rename = "Command")]
-    command: String,
-    #[serde(rename = "Arguments")]
-    arguments: Option<String>,
+/// A --find-uuid in any of the --uuid-format forms.
+fn parse_uuid(value: &str) -> Result<UUID, String> {
+    let expected = || "expected a GUID such as {0A1B2C3D-...}".to_string();
+    UUID::parse_uuid(value.trim()).ok_or_else(expected)
 }
 
-fn display_xml_job_info(task: &Task) {
-    println!("Author: {:?}", task.registration_info.author);
-    println!("Date: {:?}", task.registration_info.date);
-    println!("Description: {:?}", task.registration_info.description);
+/// A --rotate-size in bytes.
+fn parse_megabytes(value: &str) -> Result<u64, String> {
+    match value.parse::<f64>() {
+        Ok(megabytes) if megabytes > 0.0 => Ok((megabytes * 1024.0 * 1024.0).ceil() as u64),
+        _ => Err("expected a positive number of megabytes".to_string()),
+    }
+}
 
-    if let Some(trigger) = &task.triggers.calendar_trigger {
-        println!("StartBoundary: {}", trigger.start_boundary);
-        println!("EndBoundary: {:?}", trigger.end_boundary);
-        println!("Enabled: {:?}", trigger.enabled);
+fn detect_options(output: &OutputArgs) -> DetectOptions {
+    DetectOptions {
+        name_entropy_threshold: output.name_entropy_threshold,
+        schedule_tolerance: Duration::from_secs(output.schedule_tolerance * 60),
     }
+}
 
-    println!("Settings:");
-    println!("  Enabled: {:?}", task.settings.enabled);
-    println!("  AllowStartIfOnBatteries: {:?}", task.settings.allow_start_if_on_batteries);
+/// The directory lists for --detect-paths, `None` without it.
+fn path_rules(output: &OutputArgs, rules: PathRules) -> Option<PathRules> {
+    output.detect_paths.then_some(PathRules {
+        case: case(output),
+        ..rules
+    })
+}
 
-    if let Some(exec) = &task.actions.exec {
-        println!("Command: {}", exec.command);
-        println!("Arguments: {:?}", exec.arguments);
+fn case(output: &OutputArgs) -> Case {
+    if output.case_sensitive {
+        Case::Sensitive
+    } else {
+        Case::Insensitive
     }
 }
 
-fn decode_utf16_file<P: AsRef<Path>>(path: P) -> Result<Task, Box<dyn std::error::Error>> {
-    let file = File::open(path)?;
-    let transcoded = DecodeReaderBytesBuilder::new()
-        .encoding(Some(UTF_16LE))
-        .build(file);
-    let mut buffer = String::new();
-    let mut reader = transcoded.take(1 << 16);
-    reader.read_to_string(&mut buffer)?;
-    let task: Task = from_str(&buffer)?;
-    Ok(task)
+fn now(output: &OutputArgs) -> String {
+    output.now.clone().unwrap_or_else(|| {
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        format_unix(since_epoch.as_secs())
+    })
 }
 
-fn usage() {
-    println!("jobparser.rs:");
-    println!(" -f <job>");
-    println!(" -d <directory of job files>");
+fn date_style(arg: DateStyleArg) -> DateStyle {
+    match arg {
+        DateStyleArg::Classic => DateStyle::Classic,
+        DateStyleArg::Iso => DateStyle::Iso,
+        DateStyleArg::Locale => DateStyle::Locale(Locale::from_env()),
+    }
 }
 
-fn parse_file(file_path: &str) {
-    let path = Path::new(file_path);
+fn uuid_format(arg: UuidFormatArg) -> UuidFormat {
+    match arg {
+        UuidFormatArg::BracedUpper => UuidFormat::BracedUpper,
+        UuidFormatArg::BracedLower => UuidFormat::BracedLower,
+        UuidFormatArg::PlainUpper => UuidFormat::PlainUpper,
+        UuidFormatArg::PlainLower => UuidFormat::PlainLower,
+    }
+}
 
-    if path.extension().and_then(|s| s.to_str()) == Some("xml") {
-        // Try to parse as an XML job file
-        match decode_utf16_file(&path) {
-            Ok(task) => display_xml_job_info(&task),
-            Err(e) => eprintln!("Unable to process file {}: {}", file_path, e),
-        }
-    } else {
-        // Try to parse as a binary job file
-        let mut file = File::open(&path).expect("Unable to open file");
-        let mut data = Vec::new();
-        file.read_to_end(&mut data).expect("Unable to read file");
-        let job = Job::new(&data);
-        println!("************************************************************************");
-        println!("File: {}", path.display());
-        println!("{}", job.format_job());
-        println!("************************************************************************");
+fn csv_style(output: &OutputArgs) -> CsvStyle {
+    let default = CsvStyle::default();
+    CsvStyle {
+        delimiter: output.delimiter.unwrap_or(default.delimiter),
+        quote_style: match output.quote_style {
+            None => default.quote_style,
+            Some(QuoteStyleArg::Always) => QuoteStyle::Always,
+            Some(QuoteStyleArg::Necessary) => QuoteStyle::Necessary,
+            Some(QuoteStyleArg::Never) => QuoteStyle::Never,
+        },
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "print this help menu");
-    opts.optopt("f", "file", "set job file", "FILE");
-    opts.optopt("d", "dir", "set directory of job files", "DIR");
-
-    let matches = match opts.parse(&args[1..]) {
-        Ok(m) => m,
-        Err(f) => {
-            eprintln!("Error: {}", f);
-            usage();
-            return;
-        }
+/// Rewrites the hidden `-f`/`-d` invocation as the equivalent subcommand.
+fn legacy_command(legacy: LegacyArgs) -> Option<Command> {
+    let output = OutputArgs {
+        output_format: legacy.output_format,
+        delimiter: None,
+        quote_style: None,
+        csv_triggers: None,
+        csv_actions: None,
+        timezone: None,
+        assume_utc: false,
+        no_banner: false,
+        no_header: false,
+        width: None,
+        max_string_length: truncate::DEFAULT_MAX_STRING_LENGTH,
+        no_truncate: false,
+        write: None,
+        rotate_size: None,
+        rotate_count: None,
+        gzip: false,
+        force_format: false,
+        force: false,
+        split_out_dir: None,
+        manifest: None,
+        cache: None,
+        detect: legacy.detect,
+        no_detect: false,
+        fail_on_findings: legacy.fail_on_findings,
+        no_fail_on_findings: false,
+        fail_on_warnings: false,
+        exit_zero_always: legacy.exit_zero_always,
+        no_exit_zero_always: false,
+        embed_raw: false,
+        fields: Vec::new(),
+        verbose: false,
+        date_style: DateStyleArg::Classic,
+        uuid_format: UuidFormatArg::BracedUpper,
+        never_ran: false,
+        enabled_only: false,
+        disabled_only: false,
+        no_triggers: false,
+        multi_action: false,
+        only_findings: false,
+        validate_only: false,
+        find_uuid: None,
+        dedupe: false,
+        timings: false,
+        slow_threshold: None,
+        stats: false,
+        progress: false,
+        now: None,
+        name_entropy_threshold: DetectOptions::default().name_entropy_threshold,
+        schedule_tolerance: DetectOptions::default().schedule_tolerance.as_secs() / 60,
+        min_coverage: job::DEFAULT_MIN_COVERAGE,
+        detect_paths: false,
+        rules: None,
+        rules_dir: None,
+        rules_bundle: None,
+        schedlog: None,
+        classify: false,
+        acl: false,
+        check: false,
+        decommissioned: None,
+        check_root: None,
+        case_sensitive: false,
+        #[cfg(feature = "tui")]
+        tui: false,
     };
-
-    if matches.opt_present("h") {
-        usage();
-        return;
+    if !legacy.dir.is_empty() {
+        eprintln!(
+            "Warning: -d/--dir is deprecated and will be removed; use `jobfileparser scan <DIR>`"
+        );
+        Some(Command::Scan {
+            dirs: legacy.dir,
+            image_root: None,
+            recursive: false,
+            #[cfg(feature = "watch")]
+            watch: false,
+            #[cfg(feature = "parallel")]
+            threads: NonZeroUsize::MIN,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            strict_extension: false,
+            lenient: false,
+            limit: None,
+            list_only: false,
+            by_user: false,
+            strip_domain: false,
+            group_by_dir: None,
+            relative_paths: false,
+            live: false,
+            i_know_this_is_live: false,
+            #[cfg(feature = "hive")]
+            hive: None,
+            output,
+        })
+    } else if let Some(file) = legacy.file {
+        eprintln!("Warning: -f/--file is deprecated and will be removed; use `jobfileparser parse <FILE>`");
+        Some(Command::Parse {
+            paths: vec![file],
+            files_from: None,
+            hex: None,
+            b64: None,
+            base_dir: None,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            strict_extension: false,
+            lenient: false,
+            show_source: false,
+            fragment: false,
+            relative_paths: false,
+            output,
+        })
+    } else {
+        None
     }
+}
 
-    let file_path = matches.opt_str("f");
-    let dir_path = matches.opt_str("d");
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let config_path = cli.config.as_deref();
 
-    if file_path.is_none() && dir_path.is_none() {
-        usage();
-        return;
+    if cli.print_schema {
+        if cli.command.is_some() {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--print-schema cannot be combined with a subcommand",
+                )
+                .exit();
+        }
+        return match serde_json::to_string_pretty(&output::record_schema()) {
+            Ok(json) => {
+                println!("{}", json);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Unable to serialize the schema: {}", e);
+                ExitCode::from(EXIT_FATAL)
+            }
+        };
     }
 
-    if let Some(dir) = dir_path {
-        if Path::new(&dir).is_dir() {
-            for entry in fs::read_dir(dir).expect("Unable to read directory") {
-                let entry = entry.expect("Unable to get entry");
-                let path = entry.path();
-                if path.is_file() && (path.extension().and_then(|s| s.to_str()) == Some("job") || path.extension().and_then(|s| s.to_str()) == Some("xml")) {
-                    parse_file(path.to_str().unwrap());
+    if cli.command.is_some() && cli.legacy.is_used() {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "the deprecated top-level options cannot be combined with a subcommand",
+            )
+            .exit();
+    }
+    let Some(command) = cli.command.or_else(|| legacy_command(cli.legacy)) else {
+        // Printing help cannot usefully fail.
+        let _ = Cli::command().print_help();
+        return ExitCode::from(EXIT_FATAL);
+    };
+
+    let code = match command {
+        Command::Parse {
+            mut paths,
+            files_from,
+            hex,
+            b64,
+            base_dir,
+            max_file_size,
+            strict_extension,
+            lenient,
+            show_source,
+            fragment,
+            relative_paths,
+            output,
+        } => settings(config_path, &output).and_then(|settings| {
+            if let Some(list) = files_from {
+                let listed = commands::read_path_list(&list, base_dir.as_deref())
+                    .map_err(|e| format!("unable to read {}: {}", list.display(), e))?;
+                paths.extend(listed);
+            }
+            let inline = match (hex, b64) {
+                (Some(hex), _) => {
+                    Some(inline::decode_hex(&hex).map_err(|e| format!("--hex: {}", e))?)
                 }
+                (_, Some(b64)) => {
+                    Some(inline::decode_base64(&b64).map_err(|e| format!("--b64: {}", e))?)
+                }
+                (None, None) => None,
+            };
+            if show_source && paths.len() + usize::from(inline.is_some()) != 1 {
+                return Err("--show-source needs a single file".to_string());
+            }
+            if fragment {
+                return Ok(commands::parse_fragments(&paths, inline, max_file_size, &settings));
             }
+            let options = ScanOptions {
+                max_file_size,
+                strict_extension,
+                lenient,
+                ..ScanOptions::default()
+            };
+            Ok(commands::parse(&paths, inline, &options, &settings, show_source, relative_paths))
+        }),
+        Command::Scan {
+            mut dirs,
+            image_root,
+            recursive,
+            #[cfg(feature = "watch")]
+            watch,
+            #[cfg(feature = "parallel")]
+            threads,
+            max_file_size,
+            strict_extension,
+            lenient,
+            limit,
+            list_only,
+            by_user,
+            strip_domain,
+            group_by_dir,
+            relative_paths,
+            live,
+            i_know_this_is_live,
+            #[cfg(feature = "hive")]
+            hive,
+            output,
+        } => {
+            let controls = ScanControls {
+                recursive,
+                max_file_size,
+                strict_extension,
+                lenient,
+                #[cfg(feature = "watch")]
+                watch,
+                #[cfg(feature = "parallel")]
+                threads: threads.get(),
+                limit,
+                list_only,
+                by_user,
+                strip_domain,
+                group_by_dir,
+                relative_paths,
+                image_root: image_root.is_some(),
+                live,
+                live_acknowledged: i_know_this_is_live,
+                #[cfg(feature = "hive")]
+                hive,
+            };
+            // Clap requires directories or an image, not both.
+            dirs.extend(image_root);
+            settings(config_path, &output)
+                .map(|settings| commands::scan(&dirs, &controls, &settings))
+        }
+        Command::Carve { image, output } => {
+            settings(config_path, &output).map(|settings| commands::carve(&image, &settings))
+        }
+        Command::Convert {
+            input,
+            to,
+            out_file,
+            out_dir,
+            force,
+        } => {
+            let to_xml = matches!(to, Target::Xml);
+            Ok(match out_dir {
+                Some(out_dir) => commands::convert_tree(&input, to_xml, &out_dir, force),
+                None => commands::convert(&input, to_xml, out_file, force),
+            })
+        }
+        Command::Diff { a, b } => Ok(commands::diff(&a, &b)),
+        Command::Aggregate { exports, output_format, case_sensitive } => {
+            let case = if case_sensitive { Case::Sensitive } else { Case::Insensitive };
+            Ok(aggregate::run(&exports, output_format == "json", case))
+        }
+        Command::Detect { mut paths, files_from, base_dir } => {
+            let listed = match files_from {
+                Some(list) => commands::read_path_list(&list, base_dir.as_deref())
+                    .map_err(|e| format!("unable to read {}: {}", list.display(), e)),
+                None => Ok(Vec::new()),
+            };
+            listed.map(|listed| {
+                paths.extend(listed);
+                commands::detect(&paths)
+            })
+        }
+        Command::VerifyManifest { manifest } => Ok(manifest::verify(&manifest)),
+        Command::CompileRules { rules, rules_dir, out } => {
+            Ok(bundle::compile(rules.as_deref(), rules_dir.as_deref(), &out))
+        }
+        Command::Create {
+            application,
+            parameters,
+            working_directory,
+            author,
+            comment,
+            disabled,
+            hidden,
+            to,
+            out_file,
+            force,
+        } => Ok(commands::create(&CreateOptions {
+            application,
+            parameters,
+            working_directory,
+            author,
+            comment,
+            disabled,
+            hidden,
+            xml: matches!(to, Target::Xml),
+            out_file,
+            force,
+        })),
+        #[cfg(feature = "http")]
+        Command::Serve {
+            addr,
+            max_body_size,
+            timeout,
+            workers,
+            detect,
+            no_detect,
+        } => resolve_defaults(
+            config_path,
+            Defaults {
+                detect: negatable(detect, no_detect),
+                ..Defaults::default()
+            },
+        )
+        .and_then(|defaults| {
+            let options = ServeOptions {
+                max_body_size,
+                timeout: Duration::from_secs(timeout),
+                workers: workers.into(),
+                detect: defaults.detect.unwrap_or(false),
+            };
+            serve::serve(&addr, options)
+                .map(|()| EXIT_SUCCESS)
+                .map_err(|e| format!("unable to listen on {}: {}", addr, e))
+        }),
+        Command::Completions { shell } => {
+            match completions::generate(shell, &mut Cli::command(), &mut io::stdout()) {
+                Ok(()) => Ok(EXIT_SUCCESS),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        Command::Selftest => Ok(selftest::selftest()),
+    };
+
+    match code {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(EXIT_FATAL)
         }
-    } else if let Some(file_path) = file_path {
-        parse_file(&file_path);
     }
 }
@@ -0,0 +1,44 @@
+//! Paths as handed to the operating system.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+/// The form of `path` to open. On Windows this is the extended-length
+/// (`\\?\` or `\\?\UNC\`) form, which is not limited to `MAX_PATH`; elsewhere
+/// `path` is returned unchanged. Callers keep reporting the original path.
+#[cfg(windows)]
+pub(crate) fn os_path(path: &Path) -> Cow<'_, Path> {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    let Ok(absolute) = std::path::absolute(path) else {
+        return Cow::Borrowed(path);
+    };
+    let mut components = absolute.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return Cow::Borrowed(path);
+    };
+    let mut extended = match prefix.kind() {
+        Prefix::Disk(_) => {
+            let mut extended = OsString::from(r"\\?\");
+            extended.push(prefix.as_os_str());
+            extended
+        }
+        Prefix::UNC(server, share) => {
+            let mut extended = OsString::from(r"\\?\UNC\");
+            extended.push(server);
+            extended.push(r"\");
+            extended.push(share);
+            extended
+        }
+        // Already verbatim, or a device path that must not be rewritten.
+        _ => return Cow::Borrowed(path),
+    };
+    extended.push(components.as_path());
+    Cow::Owned(extended.into())
+}
+
+#[cfg(not(windows))]
+pub(crate) fn os_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
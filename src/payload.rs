@@ -0,0 +1,245 @@
+//! Payloads hidden in a job's comment or user data: base64 and hex runs,
+//! and UTF-16 text, as droppers leave configuration or a second command
+//! there. One level of base64 or hex is taken off, then what it decodes to
+//! is read as text: UTF-8, UTF-16LE, or the UTF-16LE strings inside binary
+//! data.
+//!
+//! A run only counts when it decodes and the result reads as text, with at
+//! least `PRINTABLE_RATIO` of its characters printable, so long words and
+//! paths in an ordinary comment, which decode to noise, are not reported.
+//! UTF-16 is recognized by its Latin text, most of whose code units have a
+//! zero high byte.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The shortest base64 run tried, in characters: 18 bytes decoded.
+pub const MIN_BASE64_LENGTH: usize = 24;
+
+/// The shortest hex run tried, in digits: 16 bytes decoded.
+pub const MIN_HEX_LENGTH: usize = 32;
+
+/// The fewest characters of UTF-16 text looked for in binary data.
+pub const MIN_UTF16_CHARS: usize = 8;
+
+/// The share of printable characters decoded text needs.
+pub const PRINTABLE_RATIO: f64 = 0.9;
+
+/// An encoding taken off a payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Encoding {
+    #[cfg_attr(feature = "serde", serde(rename = "base64"))]
+    Base64,
+    #[cfg_attr(feature = "serde", serde(rename = "hex"))]
+    Hex,
+    #[cfg_attr(feature = "serde", serde(rename = "utf-16le"))]
+    Utf16Le,
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Base64 => "base64",
+            Encoding::Hex => "hex",
+            Encoding::Utf16Le => "utf-16le",
+        }
+    }
+}
+
+/// A payload and the text it decodes to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DecodedArtifact {
+    /// `comment` or `user_data`.
+    pub source: String,
+    /// Where the payload starts: in characters of the comment, in bytes of
+    /// the user data.
+    pub offset: usize,
+    /// The encodings taken off, outermost first, such as `base64` and then
+    /// `utf-16le`.
+    pub encoding: Vec<Encoding>,
+    pub decoded: String,
+}
+
+/// The payloads in `comment`, a job's comment or a task's description.
+pub fn decode_comment(comment: &str) -> Vec<DecodedArtifact> {
+    let mut found = Vec::new();
+    decode_runs("comment", comment, 0, &[], &mut found);
+    found
+}
+
+/// The payloads in `data`, a job's user data: the encoded runs in the text
+/// it holds, and UTF-16 text that holds none.
+pub fn decode_user_data(data: &[u8]) -> Vec<DecodedArtifact> {
+    let mut found = Vec::new();
+    for (offset, encoding, text) in texts(data) {
+        let before = found.len();
+        let chain: Vec<Encoding> = encoding.into_iter().collect();
+        decode_runs("user_data", &text, offset, &chain, &mut found);
+        if found.len() == before && !chain.is_empty() {
+            found.push(DecodedArtifact {
+                source: "user_data".into(),
+                offset,
+                encoding: chain,
+                decoded: text,
+            });
+        }
+    }
+    found
+}
+
+/// Adds the payloads of the base64 and hex runs in `text`, found at
+/// `offset` of `source` under the encodings of `chain`.
+fn decode_runs(
+    source: &str,
+    text: &str,
+    offset: usize,
+    chain: &[Encoding],
+    found: &mut Vec<DecodedArtifact>,
+) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if !is_base64(chars[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && is_base64(chars[i]) {
+            i += 1;
+        }
+        let mut end = i;
+        while end < chars.len() && end - i < 2 && chars[end] == '=' {
+            end += 1;
+        }
+        let run: String = chars[start..end].iter().collect();
+        let hex = run.len() >= MIN_HEX_LENGTH && run.len().is_multiple_of(2);
+        let base64 = run.len() >= MIN_BASE64_LENGTH;
+        let decoded = [
+            (Encoding::Hex, hex.then(|| decode_hex(&run)).flatten()),
+            (Encoding::Base64, base64.then(|| decode_base64(&run)).flatten()),
+        ];
+        for (encoding, bytes) in decoded {
+            let texts = bytes.map(|bytes| texts(&bytes)).unwrap_or_default();
+            if texts.is_empty() {
+                continue;
+            }
+            for (_, inner, decoded) in texts {
+                let encoding = chain.iter().copied().chain([encoding]).chain(inner).collect();
+                found.push(DecodedArtifact {
+                    source: source.to_string(),
+                    offset: offset + start,
+                    encoding,
+                    decoded,
+                });
+            }
+            break;
+        }
+        i = end;
+    }
+}
+
+fn is_base64(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '-' | '_')
+}
+
+fn decode_hex(run: &str) -> Option<Vec<u8>> {
+    let digits = run.as_bytes();
+    digits
+        .chunks(2)
+        .map(|pair| Some((hex_value(pair[0])? << 4) | hex_value(pair[1])?))
+        .collect()
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+/// `run` decoded as base64, in the standard or the URL-safe alphabet, with
+/// or without padding.
+fn decode_base64(run: &str) -> Option<Vec<u8>> {
+    let symbols = run.trim_end_matches('=').as_bytes();
+    if symbols.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(symbols.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+    for &symbol in symbols {
+        let value = match symbol {
+            b'A'..=b'Z' => symbol - b'A',
+            b'a'..=b'z' => symbol - b'a' + 26,
+            b'0'..=b'9' => symbol - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | u32::from(value);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// The text in `bytes`, with where it starts and the encoding it is in if
+/// not UTF-8: all of them as UTF-8 or UTF-16LE text, or else the UTF-16LE
+/// strings among them. Empty for binary data without any.
+fn texts(bytes: &[u8]) -> Vec<(usize, Option<Encoding>, String)> {
+    if let Some(text) = utf16_text(bytes) {
+        return vec![(0, Some(Encoding::Utf16Le), text)];
+    }
+    if let Some(text) = std::str::from_utf8(bytes).ok().filter(|text| printable(text)) {
+        return vec![(0, None, text.to_string())];
+    }
+    let mut strings = Vec::new();
+    let mut start = 0;
+    while start + 1 < bytes.len() {
+        let mut end = start;
+        while end + 1 < bytes.len() && bytes[end + 1] == 0 && is_text_byte(bytes[end]) {
+            end += 2;
+        }
+        if (end - start) / 2 >= MIN_UTF16_CHARS {
+            let text = bytes[start..end].iter().step_by(2).map(|&b| b as char).collect();
+            strings.push((start, Some(Encoding::Utf16Le), text));
+            start = end;
+        } else {
+            start = end.max(start + 1);
+        }
+    }
+    strings
+}
+
+fn is_text_byte(byte: u8) -> bool {
+    matches!(byte, 0x20..=0x7e | b'\t' | b'\n' | b'\r')
+}
+
+/// `bytes` read as UTF-16LE if they are Latin text, without trailing NULs.
+fn utf16_text(bytes: &[u8]) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let mut units: Vec<u16> =
+        bytes.chunks(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+    while units.last() == Some(&0) {
+        units.pop();
+    }
+    let latin = units.iter().filter(|&&unit| unit < 0x100).count();
+    if units.len() < MIN_UTF16_CHARS || latin * 2 < units.len() {
+        return None;
+    }
+    String::from_utf16(&units).ok().filter(|text| printable(text))
+}
+
+/// Whether at least `PRINTABLE_RATIO` of the characters of `text` are
+/// printable, counting tabs and line breaks.
+fn printable(text: &str) -> bool {
+    let total = text.chars().count();
+    let printable =
+        text.chars().filter(|&c| !c.is_control() || matches!(c, '\t' | '\n' | '\r')).count();
+    total > 0 && printable as f64 >= total as f64 * PRINTABLE_RATIO
+}
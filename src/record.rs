@@ -0,0 +1,833 @@
+//! Per-file records for machine-readable output: one per artifact scanned,
+//! whether or not it parsed.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::path::Path;
+
+use crate::account::UserKind;
+#[cfg(feature = "xml")]
+use crate::acl::FileSecurity;
+use crate::defaults::{Classification, KnowledgeBase};
+use crate::detect::{
+    at_job_index, detect, detect_at_job, detect_off_schedule, detect_random_name,
+    detect_writable_path, DetectOptions, Finding, PathRules,
+};
+use crate::entropy::{name_entropy, task_name};
+use crate::indicators::{extract_indicators, Indicator};
+use crate::payload::{decode_comment, DecodedArtifact};
+use crate::schedlog::ExecutionHistory;
+use crate::schedule::Schedule;
+use crate::warning::{artifact_warnings, Warning};
+use crate::{Error, ParsedArtifact};
+
+/// The version of the record layout, carried in every record as
+/// `schema_version`. Bumped whenever a field is renamed, removed or changes
+/// type or meaning, or keys change places. Version 2 reads a job's `flags`
+/// by the bit layout of MS-TSCH, and moves the state bits among them out to
+/// `state_bits`.
+///
+/// Keys are written in the order the fields are declared, and no record
+/// holds a `HashMap`, so the same record always serializes to the same
+/// bytes. New fields may be added, but never move the keys already there
+/// relative to each other. Findings are sorted by rule, then
+/// description, and `flag_names` follow the flag bits from lowest to highest.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Records compare and hash field by field. `Eq` holds because
+/// `name_entropy` is always a finite score.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "parse_status", rename_all = "lowercase")
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+// Most records are parsed artifacts, so boxing them would only add an
+// allocation per file.
+#[allow(clippy::large_enum_variant)]
+pub enum Record {
+    Ok {
+        /// 0 for records read back from exports that predate it.
+        #[cfg_attr(feature = "serde", serde(default))]
+        schema_version: u32,
+        path: String,
+        /// Heuristic matches; `None` unless detection was run.
+        #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+        findings: Option<Vec<Finding>>,
+        /// Set when the file's extension names the other format; the
+        /// artifact was parsed as its content says.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "std::ops::Not::not"))]
+        extension_mismatch: bool,
+        /// What is odd about the file without keeping it from being read;
+        /// see `jobfileparser::warning`.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+        warnings: Vec<Warning>,
+        /// Set for files named `At<n>.job`, which `at.exe` creates.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "std::ops::Not::not"))]
+        at_job: bool,
+        /// The `<n>` of an `At<n>.job` file.
+        #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+        at_job_index: Option<u32>,
+        /// How random the task's name looks, from 0 to 1; see
+        /// `name_entropy`.
+        #[cfg_attr(feature = "serde", serde(default))]
+        name_entropy: f64,
+        /// What the task really runs when its command goes through an
+        /// interpreter such as `cmd /c`; see `CommandLine`.
+        #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+        effective_command: Option<String>,
+        /// Script paths, URLs and UNC paths named in the arguments or in
+        /// `effective_command`; see `extract_indicators`.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+        indicators: Vec<Indicator>,
+        /// Whether the job was due but never ran; `None` unless
+        /// `infer_history` was called.
+        #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+        never_ran_but_scheduled: Option<bool>,
+        /// Whether the task is live, combining the switches of the task and
+        /// its triggers, and why; see `jobfileparser::enabled`. `None`
+        /// unless `infer_history` was called.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        effective_enabled: Option<bool>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        enabled_reason: Option<String>,
+        /// What a scheduler log says about the task; `None` unless
+        /// `set_execution_history` was called.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        execution_history: Option<ExecutionHistory>,
+        /// How many triggers the task has. `None` for a job until
+        /// `read_job_triggers` reads them from its bytes.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        trigger_count: Option<usize>,
+        /// How much of a job's file the parser understood, as a percentage
+        /// of its size; see `job::coverage`. `None` for task XML, and for a
+        /// job until `read_coverage` reads its bytes.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        coverage: Option<f64>,
+        /// How many actions the task has, and whether any is an `Exec` or a
+        /// `ComHandler`; see `ParsedArtifact::action_kinds`.
+        #[cfg_attr(feature = "serde", serde(default))]
+        action_count: usize,
+        #[cfg_attr(feature = "serde", serde(default))]
+        has_exec: bool,
+        #[cfg_attr(feature = "serde", serde(default))]
+        has_comhandler: bool,
+        /// What kind of account a job's user field names; see
+        /// `classify_user`. `None` for task XML.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        user_kind: Option<UserKind>,
+        /// Whether the task is one Windows ships with, and still runs what
+        /// it ships with; `None` unless `classify` was called.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        classification: Option<Classification>,
+        /// The SID of the file's owner, from `apply_file_security`.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        owner: Option<String>,
+        /// Whether accounts other than administrators may change the file;
+        /// `None` unless `apply_file_security` could tell.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        writable_by_users: Option<bool>,
+        #[cfg_attr(feature = "serde", serde(flatten))]
+        origin: Origin,
+        #[cfg_attr(feature = "serde", serde(flatten))]
+        artifact: ParsedArtifact,
+        /// The file itself; `None` unless `embed_raw` was called.
+        #[cfg(feature = "raw")]
+        #[serde(flatten)]
+        raw: Option<RawContent>,
+        /// What notable combinations of a job's flags mean, as sentences;
+        /// see `jobfileparser::interpret`. Empty unless `infer_history` was
+        /// called.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+        interpretations: Vec<String>,
+        /// Operational problems, such as a task that may run forever, found
+        /// by the checks of a schedule hygiene audit; `None` unless one ran.
+        /// Sorted like `findings`.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+        issues: Option<Vec<Finding>>,
+        /// Base64, hex and UTF-16 payloads in the comment and, once
+        /// `read_user_data` reads it, a job's user data; see
+        /// `jobfileparser::payload`.
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+        decoded_comment_artifacts: Vec<DecodedArtifact>,
+    },
+    /// A file that failed to parse. `error_kind` is the `Error` variant name.
+    Error {
+        #[cfg_attr(feature = "serde", serde(default))]
+        schema_version: u32,
+        path: String,
+        error_kind: Cow<'static, str>,
+        detail: String,
+        offset: Option<usize>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "std::ops::Not::not"))]
+        extension_mismatch: bool,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+        warnings: Vec<Warning>,
+        #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "std::ops::Not::not"))]
+        at_job: bool,
+        #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+        at_job_index: Option<u32>,
+        #[cfg_attr(feature = "serde", serde(flatten))]
+        origin: Origin,
+        #[cfg(feature = "raw")]
+        #[serde(flatten)]
+        raw: Option<RawContent>,
+    },
+}
+
+/// Where an artifact came from, for records from several machines in one
+/// output. Each field is left out when not known.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Origin {
+    /// The machine the artifact was collected from.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub hostname: Option<String>,
+    /// The time zone the machine's clock was set to, such as
+    /// `Europe/Berlin`. Job run times are in local time.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub timezone: Option<String>,
+    /// The case or evidence item the artifact belongs to.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub evidence_id: Option<String>,
+}
+
+impl Origin {
+    /// `self` with the fields `other` sets replaced by its values.
+    pub fn overridden_by(mut self, other: &Origin) -> Origin {
+        for (field, value) in [
+            (&mut self.hostname, &other.hostname),
+            (&mut self.timezone, &other.timezone),
+            (&mut self.evidence_id, &other.evidence_id),
+        ] {
+            if value.is_some() {
+                field.clone_from(value);
+            }
+        }
+        self
+    }
+}
+
+/// The original bytes of an artifact, for records that have to stand on
+/// their own.
+#[cfg(feature = "raw")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RawContent {
+    /// The bytes, base64-encoded with padding.
+    pub raw_content: String,
+    pub raw_size: usize,
+    /// Lowercase hex SHA-256 of the bytes.
+    pub raw_sha256: String,
+}
+
+#[cfg(feature = "raw")]
+impl RawContent {
+    pub fn new(data: &[u8]) -> RawContent {
+        use base64::Engine;
+        use sha2::{Digest, Sha256};
+
+        let digest = Sha256::digest(data);
+        RawContent {
+            raw_content: base64::engine::general_purpose::STANDARD.encode(data),
+            raw_size: data.len(),
+            raw_sha256: digest.iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+}
+
+impl Eq for Record {}
+
+/// Adds `found` to `findings`. Findings are kept sorted, by rule and then
+/// description, so a record lists them the same way whichever check found
+/// them first.
+fn add_findings(findings: &mut Option<Vec<Finding>>, found: impl IntoIterator<Item = Finding>) {
+    let findings = findings.get_or_insert_with(Vec::new);
+    findings.extend(found);
+    findings.sort();
+}
+
+impl Hash for Record {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Record::Ok {
+                schema_version,
+                path,
+                findings,
+                issues,
+                extension_mismatch,
+                warnings,
+                at_job,
+                at_job_index,
+                name_entropy,
+                effective_command,
+                indicators,
+                decoded_comment_artifacts,
+                never_ran_but_scheduled,
+                effective_enabled,
+                enabled_reason,
+                interpretations,
+                execution_history,
+                trigger_count,
+                coverage,
+                action_count,
+                has_exec,
+                has_comhandler,
+                user_kind,
+                classification,
+                owner,
+                writable_by_users,
+                origin,
+                artifact,
+                #[cfg(feature = "raw")]
+                raw,
+            } => {
+                (schema_version, path, findings, extension_mismatch, at_job).hash(state);
+                (at_job_index, effective_command, never_ran_but_scheduled, artifact).hash(state);
+                (indicators, effective_enabled, enabled_reason, execution_history).hash(state);
+                (trigger_count, action_count, has_exec, has_comhandler, user_kind).hash(state);
+                (warnings, classification, owner, writable_by_users, origin).hash(state);
+                (interpretations, issues, decoded_comment_artifacts).hash(state);
+                // Adding 0.0 turns -0.0, which equals 0.0, into 0.0.
+                (name_entropy + 0.0).to_bits().hash(state);
+                coverage.map(|coverage| (coverage + 0.0).to_bits()).hash(state);
+                #[cfg(feature = "raw")]
+                raw.hash(state);
+            }
+            Record::Error {
+                schema_version,
+                path,
+                error_kind,
+                detail,
+                offset,
+                extension_mismatch,
+                warnings,
+                at_job,
+                at_job_index,
+                origin,
+                #[cfg(feature = "raw")]
+                raw,
+            } => {
+                (schema_version, path, error_kind, detail, offset).hash(state);
+                (extension_mismatch, warnings, at_job, at_job_index, origin).hash(state);
+                #[cfg(feature = "raw")]
+                raw.hash(state);
+            }
+        }
+    }
+}
+
+impl Record {
+    pub fn new(path: &Path, result: Result<ParsedArtifact, Error>) -> Record {
+        let at_job_index = at_job_index(path);
+        let path = path.display().to_string();
+        match result {
+            Ok(artifact) => {
+                let name_entropy = name_entropy(task_name(&path));
+                let effective_command = artifact.command().and_then(|c| c.effective_command);
+                let texts = artifact.arguments().into_iter().chain(effective_command.as_deref());
+                let indicators = extract_indicators(texts);
+                let decoded_comment_artifacts =
+                    artifact.comment().map(decode_comment).unwrap_or_default();
+                let warnings = artifact_warnings(&artifact);
+                let actions = artifact.action_kinds();
+                let (action_count, has_exec, has_comhandler) = (
+                    actions.len(),
+                    actions.contains(&"Exec"),
+                    actions.contains(&"ComHandler"),
+                );
+                Record::Ok {
+                    schema_version: SCHEMA_VERSION,
+                    path,
+                    findings: None,
+                    issues: None,
+                    extension_mismatch: false,
+                    warnings,
+                    at_job: at_job_index.is_some(),
+                    at_job_index,
+                    name_entropy,
+                    effective_command,
+                    indicators,
+                    decoded_comment_artifacts,
+                    never_ran_but_scheduled: None,
+                    effective_enabled: None,
+                    enabled_reason: None,
+                    interpretations: Vec::new(),
+                    execution_history: None,
+                    trigger_count: artifact.trigger_count(),
+                    coverage: None,
+                    action_count,
+                    has_exec,
+                    has_comhandler,
+                    user_kind: artifact.user_kind(),
+                    classification: None,
+                    owner: None,
+                    writable_by_users: None,
+                    origin: Origin::default(),
+                    artifact,
+                    #[cfg(feature = "raw")]
+                    raw: None,
+                }
+            }
+            Err(e) => Record::Error {
+                schema_version: SCHEMA_VERSION,
+                path,
+                error_kind: e.kind().into(),
+                detail: e.to_string(),
+                offset: e.offset(),
+                extension_mismatch: false,
+                warnings: Vec::new(),
+                at_job: at_job_index.is_some(),
+                at_job_index,
+                origin: Origin::default(),
+                #[cfg(feature = "raw")]
+                raw: None,
+            },
+        }
+    }
+
+    /// Runs the built-in heuristics and stores their findings on the record.
+    /// Error records are left untouched.
+    pub fn detect(&mut self) {
+        self.detect_with(&DetectOptions::default());
+    }
+
+    /// `detect` with the limits in `options`.
+    pub fn detect_with(&mut self, options: &DetectOptions) {
+        if let Record::Ok {
+            path,
+            findings,
+            artifact,
+            at_job_index,
+            name_entropy,
+            ..
+        } = self
+        {
+            let mut found = detect(artifact);
+            if let Some(index) = at_job_index {
+                found.push(detect_at_job(*index, artifact));
+            }
+            found.extend(detect_random_name(task_name(path), *name_entropy, options));
+            found.sort();
+            *findings = Some(found);
+        }
+    }
+
+    /// Runs `detect_writable_path` and adds its finding, if any, to those on
+    /// the record. Error records are left untouched.
+    pub fn detect_paths(&mut self, rules: &PathRules) {
+        if let Record::Ok {
+            findings, artifact, ..
+        } = self
+        {
+            add_findings(findings, detect_writable_path(artifact, rules));
+        }
+    }
+
+    /// Runs `detect_off_schedule` and adds its finding, if any, to those on
+    /// the record. A job is judged by its run date and the triggers read
+    /// from `data`, the bytes it was parsed from, and a task by the last
+    /// start in its `execution_history`. Jobs that never ran, tasks without
+    /// a logged start and error records are left untouched.
+    pub fn detect_schedule(&mut self, data: Option<&[u8]>, options: &DetectOptions) {
+        let Record::Ok {
+            findings,
+            artifact,
+            execution_history,
+            ..
+        } = self
+        else {
+            return;
+        };
+        let last_run = schedule_and_last_run(artifact, execution_history.as_ref(), data);
+        let Some((schedule, last_run)) = last_run else {
+            return;
+        };
+        if let Some(finding) = detect_off_schedule(&schedule, &last_run, options) {
+            add_findings(findings, [finding]);
+        }
+    }
+
+    /// Classifies the task against `knowledge`, storing where it stands and
+    /// adding the finding for a modified default to those on the record.
+    /// Error records are left untouched.
+    pub fn classify(&mut self, knowledge: &KnowledgeBase) {
+        let Record::Ok {
+            path,
+            findings,
+            artifact,
+            classification,
+            ..
+        } = self
+        else {
+            return;
+        };
+        let (class, finding) = knowledge.classify(path, artifact);
+        *classification = Some(class);
+        if let Some(finding) = finding {
+            add_findings(findings, [finding]);
+        }
+    }
+
+    /// Records who owns the file the artifact was read from and whether
+    /// accounts other than administrators may change it, as `security`
+    /// says, and flags an artifact running as SYSTEM that they may.
+    #[cfg(feature = "xml")]
+    pub fn apply_file_security(&mut self, security: &FileSecurity) {
+        let Record::Ok {
+            path,
+            findings,
+            artifact,
+            owner,
+            writable_by_users,
+            ..
+        } = self
+        else {
+            return;
+        };
+        owner.clone_from(&security.owner);
+        let writers = security.writers().ok();
+        *writable_by_users = writers.as_ref().map(|writers| !writers.is_empty());
+        let finding = writers.and_then(|writers| FileSecurity::finding(path, artifact, &writers));
+        if let Some(finding) = finding {
+            add_findings(findings, [finding]);
+        }
+    }
+
+    /// The SID of the file's owner, set by `apply_file_security`.
+    pub fn owner(&self) -> Option<&str> {
+        match self {
+            Record::Ok { owner, .. } => owner.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether accounts other than administrators may change the file, as
+    /// `apply_file_security` found.
+    pub fn writable_by_users(&self) -> Option<bool> {
+        match self {
+            Record::Ok { writable_by_users, .. } => *writable_by_users,
+            _ => None,
+        }
+    }
+
+    /// Where `classify` put the task; `None` if it has not run.
+    pub fn classification(&self) -> Option<Classification> {
+        match self {
+            Record::Ok { classification, .. } => *classification,
+            _ => None,
+        }
+    }
+
+    /// Adds `finding` to those on the record. Error records are left
+    /// untouched.
+    pub fn add_finding(&mut self, finding: Finding) {
+        if let Record::Ok { findings, .. } = self {
+            add_findings(findings, [finding]);
+        }
+    }
+
+    /// Adds `issue` to the operational problems on the record. Error
+    /// records are left untouched.
+    pub fn add_issue(&mut self, issue: Finding) {
+        if let Record::Ok { issues, .. } = self {
+            add_findings(issues, [issue]);
+        }
+    }
+
+    /// Marks the record as checked for operational problems, whether or not
+    /// any are found. Error records are left untouched.
+    pub fn mark_checked(&mut self) {
+        if let Record::Ok { issues, .. } = self {
+            issues.get_or_insert_with(Vec::new);
+        }
+    }
+
+    /// Attaches what a scheduler log says about the task. Error records are
+    /// left untouched.
+    pub fn set_execution_history(&mut self, history: ExecutionHistory) {
+        if let Record::Ok {
+            execution_history, ..
+        } = self
+        {
+            *execution_history = Some(history);
+        }
+    }
+
+    /// Works out `never_ran_but_scheduled`, `effective_enabled` and
+    /// `interpretations` as of `now` (`YYYY-MM-DDTHH:MM:SS`). Error records
+    /// are left untouched.
+    pub fn infer_history(&mut self, now: &str) {
+        if let Record::Ok {
+            artifact,
+            never_ran_but_scheduled,
+            effective_enabled,
+            enabled_reason,
+            interpretations,
+            ..
+        } = self
+        {
+            *never_ran_but_scheduled = Some(artifact.never_ran_but_scheduled(now));
+            let enablement = artifact.effective_enabled(now);
+            *effective_enabled = Some(enablement.enabled);
+            *enabled_reason = Some(enablement.reason.to_string());
+            *interpretations = artifact.interpretations(now);
+        }
+    }
+
+    /// What notable combinations of a job's flags mean; empty unless
+    /// `infer_history` was called.
+    pub fn interpretations(&self) -> &[String] {
+        match self {
+            Record::Ok { interpretations, .. } => interpretations,
+            _ => &[],
+        }
+    }
+
+    /// `effective_enabled`, for records `infer_history` was called on.
+    pub fn effective_enabled(&self) -> Option<bool> {
+        match self {
+            Record::Ok { effective_enabled, .. } => *effective_enabled,
+            _ => None,
+        }
+    }
+
+    /// The `SCHEMA_VERSION` the record was written with.
+    pub fn schema_version(&self) -> u32 {
+        let (Record::Ok { schema_version, .. } | Record::Error { schema_version, .. }) = self;
+        *schema_version
+    }
+
+    /// A warning for a record read back from JSON that another version
+    /// wrote with a different layout: fields renamed since then come back
+    /// empty, and fields added since are missing.
+    pub fn version_warning(&self) -> Option<String> {
+        let version = self.schema_version();
+        (version != SCHEMA_VERSION).then(|| {
+            format!(
+                "{}: written with record schema version {}, this build reads version {}",
+                self.path(),
+                version,
+                SCHEMA_VERSION
+            )
+        })
+    }
+
+    /// The path the record is for.
+    pub fn path(&self) -> &str {
+        let (Record::Ok { path, .. } | Record::Error { path, .. }) = self;
+        path
+    }
+
+    /// The `<n>` of a record for an `At<n>.job` file.
+    pub fn at_job_index(&self) -> Option<u32> {
+        let (Record::Ok { at_job_index, .. } | Record::Error { at_job_index, .. }) = self;
+        *at_job_index
+    }
+
+    /// Marks the record as read from a file whose extension names the other
+    /// format.
+    pub fn flag_extension_mismatch(&mut self) {
+        let (Record::Ok { extension_mismatch, .. } | Record::Error { extension_mismatch, .. }) = self;
+        *extension_mismatch = true;
+    }
+
+    /// Adds `warnings`, such as those about how the file was read, to the
+    /// record's own.
+    pub fn add_warnings(&mut self, new: impl IntoIterator<Item = Warning>) {
+        let (Record::Ok { warnings, .. } | Record::Error { warnings, .. }) = self;
+        warnings.extend(new);
+    }
+
+    /// The record's warnings.
+    pub fn warnings(&self) -> &[Warning] {
+        let (Record::Ok { warnings, .. } | Record::Error { warnings, .. }) = self;
+        warnings
+    }
+
+    /// Reads the triggers of a job record from `data`, the bytes it was
+    /// parsed from: sets `trigger_count` and adds the `trigger_warnings`
+    /// about them. Other records are left untouched.
+    #[cfg_attr(not(feature = "binary"), allow(unused_variables))]
+    pub fn read_job_triggers(&mut self, data: &[u8]) {
+        #[cfg(feature = "binary")]
+        if let Record::Ok {
+            trigger_count,
+            warnings,
+            artifact: ParsedArtifact::Job(_),
+            ..
+        } = self
+        {
+            *trigger_count = crate::job::trigger_count(data).map(usize::from);
+            let schedules = crate::job::trigger_schedules(data);
+            warnings.extend(crate::warning::trigger_warnings(&schedules));
+        }
+    }
+
+    /// Adds the payloads in the user data of a job record to its
+    /// `decoded_comment_artifacts`, from `data`, the bytes it was parsed
+    /// from. Other records are left untouched.
+    #[cfg_attr(not(feature = "binary"), allow(unused_variables))]
+    pub fn read_user_data(&mut self, data: &[u8]) {
+        #[cfg(feature = "binary")]
+        if let Record::Ok {
+            decoded_comment_artifacts,
+            artifact: ParsedArtifact::Job(_),
+            ..
+        } = self
+        {
+            let user_data = crate::job::user_data(data);
+            decoded_comment_artifacts.extend(crate::payload::decode_user_data(user_data));
+        }
+    }
+
+    /// Sets the `coverage` of a job from `data`, the bytes it was parsed
+    /// from, with a `LowCoverage` warning if it is below `min`, a
+    /// percentage. Other records are left untouched.
+    #[cfg_attr(not(feature = "binary"), allow(unused_variables))]
+    pub fn read_coverage(&mut self, data: &[u8], min: f64) {
+        #[cfg(feature = "binary")]
+        if let Record::Ok {
+            coverage,
+            warnings,
+            artifact: ParsedArtifact::Job(_),
+            ..
+        } = self
+        {
+            let percent = crate::job::coverage(data);
+            *coverage = Some(percent);
+            if percent < min {
+                let sections = crate::job::sections(data);
+                let covered: usize = sections.iter().map(|section| section.len).sum();
+                let message = format!(
+                    "only {:.1}% of the file belongs to a known section: {} of its {} bytes do not",
+                    percent,
+                    data.len() - covered,
+                    data.len()
+                );
+                let code = crate::warning::WarningCode::LowCoverage;
+                let end = sections.last().map_or(0, |section| section.end());
+                warnings.push(Warning::new(code, message).at(end));
+            }
+        }
+    }
+
+    /// The coverage set by `read_coverage`, if any.
+    pub fn coverage(&self) -> Option<f64> {
+        match self {
+            Record::Ok { coverage, .. } => *coverage,
+            _ => None,
+        }
+    }
+
+    /// How many triggers a parsed artifact has, when known.
+    pub fn trigger_count(&self) -> Option<usize> {
+        match self {
+            Record::Ok { trigger_count, .. } => *trigger_count,
+            Record::Error { .. } => None,
+        }
+    }
+
+    /// How many actions a parsed artifact has; 0 for a failure.
+    pub fn action_count(&self) -> usize {
+        match self {
+            Record::Ok { action_count, .. } => *action_count,
+            Record::Error { .. } => 0,
+        }
+    }
+
+    /// Sets where the artifact came from.
+    pub fn set_origin(&mut self, new: Origin) {
+        let (Record::Ok { origin, .. } | Record::Error { origin, .. }) = self;
+        *origin = new;
+    }
+
+    pub fn origin(&self) -> &Origin {
+        let (Record::Ok { origin, .. } | Record::Error { origin, .. }) = self;
+        origin
+    }
+
+    /// Embeds `data`, the bytes the record was parsed from, in the record.
+    #[cfg(feature = "raw")]
+    pub fn embed_raw(&mut self, data: &[u8]) {
+        let (Record::Ok { raw, .. } | Record::Error { raw, .. }) = self;
+        *raw = Some(RawContent::new(data));
+    }
+
+    /// The operational problems stored by `add_issue`.
+    pub fn issues(&self) -> &[Finding] {
+        match self {
+            Record::Ok { issues: Some(issues), .. } => issues,
+            _ => &[],
+        }
+    }
+
+    /// The findings stored by `detect`, empty if it has not run.
+    pub fn findings(&self) -> &[Finding] {
+        match self {
+            Record::Ok {
+                findings: Some(findings),
+                ..
+            } => findings,
+            _ => &[],
+        }
+    }
+
+    /// The record's indicators; none for error records.
+    pub fn indicators(&self) -> &[Indicator] {
+        match self {
+            Record::Ok { indicators, .. } => indicators,
+            _ => &[],
+        }
+    }
+
+    /// The payloads found by `Record::new` and `read_user_data`.
+    pub fn decoded_comment_artifacts(&self) -> &[DecodedArtifact] {
+        match self {
+            Record::Ok { decoded_comment_artifacts, .. } => decoded_comment_artifacts,
+            Record::Error { .. } => &[],
+        }
+    }
+}
+
+/// The schedule of `artifact` and when it last ran, for
+/// `Record::detect_schedule`.
+#[cfg_attr(not(all(feature = "binary", feature = "xml")), allow(unused_variables))]
+fn schedule_and_last_run(
+    artifact: &ParsedArtifact,
+    history: Option<&ExecutionHistory>,
+    data: Option<&[u8]>,
+) -> Option<(Schedule, String)> {
+    match artifact {
+        #[cfg(feature = "binary")]
+        ParsedArtifact::Job(job) => {
+            let data = data.filter(|_| job.run_date.year != 0)?;
+            Some((crate::job::schedule(data), job.run_date.format_iso()))
+        }
+        #[cfg(feature = "xml")]
+        ParsedArtifact::Task(task) => Some((task.schedule(), history?.last_started.clone()?)),
+        #[cfg(not(any(feature = "binary", feature = "xml")))]
+        _ => None,
+    }
+}
+
+/// A JSON Schema (draft 2020-12) describing a serialized `Record`. The
+/// top-level `version` matches `SCHEMA_VERSION`.
+#[cfg(feature = "schema")]
+pub fn record_schema() -> serde_json::Value {
+    let mut schema = schemars::generate::SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<Record>()
+        .to_value();
+    if let Some(object) = schema.as_object_mut() {
+        object.insert("version".to_string(), SCHEMA_VERSION.into());
+    }
+    schema
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+use crate::cancel::{CancellationToken, PartialScan};
+use crate::path::os_path;
+#[cfg(feature = "binary")]
+use crate::{ArtifactFormat, Job};
+use crate::{parse_auto, parse_bytes, read_file_limited, Error, ParsedArtifact};
+
+/// The default `ScanOptions::max_file_size`. Real jobs and task XML are a
+/// few KiB at most.
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Controls which files `scan_dir` visits.
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Descend into subdirectories. Links to directories are not followed,
+    /// so a link back up the tree cannot make the walk go round forever;
+    /// links to files are read like the files.
+    pub recursive: bool,
+    /// File extensions (without the dot, compared case-insensitively) to parse.
+    /// An empty one stands for files without an extension.
+    pub extensions: Vec<String>,
+    /// Files larger than this are reported as `Error::TooLarge` unread.
+    pub max_file_size: u64,
+    /// Parse files as their extension says (`parse_auto`) rather than as
+    /// their content says (`parse_bytes`).
+    pub strict_extension: bool,
+    /// Parse binary jobs with `Job::parse_lenient`, cutting strings that run
+    /// past the end of the file instead of failing.
+    pub lenient: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> ScanOptions {
+        ScanOptions {
+            recursive: false,
+            extensions: vec!["job".to_string(), "xml".to_string()],
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            strict_extension: false,
+            lenient: false,
+        }
+    }
+}
+
+impl ScanOptions {
+    /// Whether `path` has one of the configured extensions.
+    pub fn wants(&self, path: &Path) -> bool {
+        match path.extension().and_then(|s| s.to_str()) {
+            Some(ext) => self.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)),
+            None => self.extensions.iter().any(String::is_empty),
+        }
+    }
+
+    /// Parses `data`, read from `path`, by content or, with
+    /// `strict_extension`, by extension.
+    pub fn parse(&self, path: &Path, data: &[u8]) -> Result<ParsedArtifact, Error> {
+        self.parse_with_warnings(path, data).0
+    }
+
+    /// Like `parse`, with what a `lenient` parse let through.
+    pub fn parse_with_warnings(
+        &self,
+        path: &Path,
+        data: &[u8],
+    ) -> (Result<ParsedArtifact, Error>, Vec<Error>) {
+        #[cfg(feature = "binary")]
+        if self.lenient {
+            let format = if self.strict_extension {
+                ArtifactFormat::for_path(path)
+            } else {
+                ArtifactFormat::sniff(data)
+            };
+            if format == ArtifactFormat::Binary {
+                return match Job::parse_lenient(data) {
+                    Ok((job, warnings)) => (Ok(ParsedArtifact::Job(job)), warnings),
+                    Err(e) => (Err(e), Vec::new()),
+                };
+            }
+        }
+        let result = if self.strict_extension {
+            parse_auto(path, data)
+        } else {
+            parse_bytes(data)
+        };
+        (result, Vec::new())
+    }
+}
+
+/// Walks `path` and hands each artifact to `callback` as soon as it is parsed,
+/// without collecting results. Entries are visited in path order. Returning
+/// `ControlFlow::Break` from the callback stops the scan before the next file
+/// is read.
+///
+/// Only an unreadable root directory is reported as an `Err`; per-file
+/// failures, including unreadable subdirectories, go to the callback.
+pub fn scan_dir<P, F>(path: P, options: &ScanOptions, mut callback: F) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(&Path, Result<ParsedArtifact, Error>) -> ControlFlow<()> + Send,
+{
+    list_dir(path, options, |path, result| callback(path, read(path, result, options)))
+}
+
+/// `scan_dir`, stopping before the next file once `token` is cancelled.
+/// What the callback got by then stands; the `PartialScan` counts the
+/// files it got and says whether the scan was cut short.
+pub fn scan_dir_cancellable<P, F>(
+    path: P,
+    options: &ScanOptions,
+    token: &CancellationToken,
+    mut callback: F,
+) -> Result<PartialScan, Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(&Path, Result<ParsedArtifact, Error>) -> ControlFlow<()> + Send,
+{
+    let mut partial = PartialScan::default();
+    list_dir(path, options, |path, result| {
+        if token.is_cancelled() {
+            partial.cancelled = true;
+            return ControlFlow::Break(());
+        }
+        partial.completed += 1;
+        callback(path, read(path, result, options))
+    })?;
+    Ok(partial)
+}
+
+/// Reads and parses the file `list_dir` found at `path`.
+fn read(
+    path: &Path,
+    listed: Result<(), Error>,
+    options: &ScanOptions,
+) -> Result<ParsedArtifact, Error> {
+    listed
+        .and_then(|()| read_file_limited(path, options.max_file_size))
+        .and_then(|data| options.parse(path, &data))
+}
+
+/// Walks `path` like `scan_dir` but without reading any file: `callback`
+/// gets `Ok(())` for each file `scan_dir` would parse and an `Err` for each
+/// unreadable subdirectory.
+pub fn list_dir<P, F>(path: P, options: &ScanOptions, mut callback: F) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    F: FnMut(&Path, Result<(), Error>) -> ControlFlow<()>,
+{
+    let entries = sorted_entries(path.as_ref())?;
+    let _ = walk(entries, options, &mut callback);
+    Ok(())
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut entries = Vec::new();
+    // Children are joined onto `dir` rather than taken from the entries so
+    // that they keep the form the caller used, not the extended-length one.
+    for entry in fs::read_dir(os_path(dir))? {
+        entries.push(dir.join(entry?.file_name()));
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+fn walk<F>(entries: Vec<PathBuf>, options: &ScanOptions, callback: &mut F) -> ControlFlow<()>
+where
+    F: FnMut(&Path, Result<(), Error>) -> ControlFlow<()>,
+{
+    for path in entries {
+        let metadata = fs::symlink_metadata(os_path(&path));
+        let link = metadata.as_ref().is_ok_and(|m| m.file_type().is_symlink());
+        let metadata = if link { fs::metadata(os_path(&path)) } else { metadata };
+        if link && metadata.as_ref().is_ok_and(|m| m.is_dir()) {
+            continue;
+        }
+        if metadata.as_ref().is_ok_and(|m| m.is_dir()) {
+            if options.recursive {
+                match sorted_entries(&path) {
+                    Ok(children) => walk(children, options, callback)?,
+                    Err(e) => callback(&path, Err(e))?,
+                }
+            }
+        } else if metadata.is_ok_and(|m| m.is_file()) && options.wants(&path) {
+            callback(&path, Ok(()))?;
+        }
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,397 @@
+//! Task execution history from scheduler logs: the legacy `SchedLgU.txt`
+//! and CSV exports of the `Microsoft-Windows-TaskScheduler/Operational`
+//! event log. Entries are summarized per task name so that they can be set
+//! beside the artifacts for the same tasks.
+//!
+//! Both sources write dates in the system's locale; month-first dates
+//! (`3/4/2024 2:13:00 PM`, as on US English systems) and ISO 8601 are read.
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::entropy::task_name;
+use crate::fold::{fold, Case};
+
+/// The line the Task Scheduler service writes below the newest entry of
+/// `SchedLgU.txt`. What follows it is the oldest part of the log.
+const WRAP_MARKER: &str = "[ ***** Most recent entry is above this line ***** ]";
+
+/// What a log entry says happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Started,
+    Finished,
+    /// The task could not be started or did not complete.
+    Failed,
+}
+
+/// One event for one task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// The task as logged: `Name.job` in `SchedLgU.txt`, `\Folder\Name` in
+    /// the operational log.
+    pub task: String,
+    pub kind: EntryKind,
+    /// ISO 8601 local time; `None` when the entry was cut short.
+    pub time: Option<String>,
+    pub exit_code: Option<i64>,
+}
+
+/// What the log says about one task.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExecutionHistory {
+    /// Logged starts.
+    pub runs: usize,
+    /// Logged failures to start or complete.
+    pub failures: usize,
+    /// The earliest and latest time any entry for the task was logged.
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+    /// The latest logged start.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub last_started: Option<String>,
+    /// The distinct exit codes logged, in increasing order.
+    pub exit_codes: Vec<i64>,
+}
+
+impl ExecutionHistory {
+    fn add(&mut self, entry: &LogEntry) {
+        match entry.kind {
+            EntryKind::Started => self.runs += 1,
+            EntryKind::Failed => self.failures += 1,
+            EntryKind::Finished => {}
+        }
+        if let Some(time) = &entry.time {
+            if self.first_seen.as_ref().is_none_or(|first| time < first) {
+                self.first_seen = Some(time.clone());
+            }
+            if self.last_seen.as_ref().is_none_or(|last| time > last) {
+                self.last_seen = Some(time.clone());
+            }
+            let started = entry.kind == EntryKind::Started;
+            if started && self.last_started.as_ref().is_none_or(|last| time > last) {
+                self.last_started = Some(time.clone());
+            }
+        }
+        if let Some(code) = entry.exit_code {
+            if let Err(at) = self.exit_codes.binary_search(&code) {
+                self.exit_codes.insert(at, code);
+            }
+        }
+    }
+}
+
+/// The histories of every task in a log, by task name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionLog {
+    /// Keyed by `key`, with the name as first logged.
+    tasks: BTreeMap<String, (String, ExecutionHistory)>,
+    case: Case,
+}
+
+impl ExecutionLog {
+    /// Reads `data` as a CSV export of the operational log if its first line
+    /// is a header naming an event ID column, and as `SchedLgU.txt`
+    /// otherwise.
+    pub fn parse(data: &[u8]) -> ExecutionLog {
+        ExecutionLog::parse_with(data, Case::Insensitive)
+    }
+
+    /// `parse`, with task names matched as `case` says.
+    pub fn parse_with(data: &[u8], case: Case) -> ExecutionLog {
+        let text = decode(data);
+        let header = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+        let csv = header.contains(',') && csv_fields(header).iter().any(|f| is_id_column(f));
+        let entries = if csv {
+            parse_operational_csv(&text)
+        } else {
+            parse_schedlgu(&text)
+        };
+        ExecutionLog::from_entries_with(&entries, case)
+    }
+
+    pub fn from_entries(entries: &[LogEntry]) -> ExecutionLog {
+        ExecutionLog::from_entries_with(entries, Case::Insensitive)
+    }
+
+    pub fn from_entries_with(entries: &[LogEntry], case: Case) -> ExecutionLog {
+        let mut log = ExecutionLog {
+            case,
+            ..ExecutionLog::default()
+        };
+        for entry in entries {
+            let key = log.key(&entry.task);
+            let (_, history) = log
+                .tasks
+                .entry(key)
+                .or_insert_with(|| (entry.task.clone(), ExecutionHistory::default()));
+            history.add(entry);
+        }
+        log
+    }
+
+    /// The history of the task an artifact at `path` defines, matched by
+    /// its name without folders or the `.job` or `.xml` extension.
+    pub fn history(&self, path: &str) -> Option<&ExecutionHistory> {
+        self.tasks.get(&self.key(path)).map(|(_, history)| history)
+    }
+
+    /// The name tasks are matched by: the last component of `name`, without
+    /// `.job` or `.xml`, with its case folded unless the log was read with
+    /// `Case::Sensitive`.
+    pub fn key(&self, name: &str) -> String {
+        self.case.key(task_name(name)).into_owned()
+    }
+
+    /// Every task in the log, as first logged, in name order.
+    pub fn tasks(&self) -> impl Iterator<Item = (&str, &ExecutionHistory)> {
+        self.tasks.values().map(|(name, history)| (name.as_str(), history))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+/// `ExecutionLog::key` of a log matched without regard to case.
+pub fn log_key(name: &str) -> String {
+    fold(task_name(name)).into_owned()
+}
+
+/// Decodes UTF-16LE, which `SchedLgU.txt` and Unicode CSV exports use, by
+/// its byte-order mark or its zero high bytes, and anything else as UTF-8.
+/// NULs, which pad a preallocated `SchedLgU.txt`, are dropped.
+fn decode(data: &[u8]) -> String {
+    let utf16 = data.starts_with(&[0xFF, 0xFE])
+        || (data.len() >= 4 && data[1] == 0 && data[3] == 0 && data[0] != 0);
+    let text = if utf16 {
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(data).into_owned()
+    };
+    text.trim_start_matches('\u{feff}').replace('\0', "")
+}
+
+/// Reads `SchedLgU.txt`: a `"Name.job" (program)` line per event, followed
+/// by indented `Started`, `Finished` and `Result:` lines, or with the time
+/// and `** ERROR **` on the line itself.
+///
+/// The log is a ring: the text after `WRAP_MARKER` is older than the text
+/// before it, and the oldest entry was partly overwritten, so it may start
+/// mid-line. The two parts are put back in order, joined without a break so
+/// that a line the wrap split in two is read whole, and lines that do not
+/// follow a task line are skipped.
+pub fn parse_schedlgu(text: &str) -> Vec<LogEntry> {
+    let text = match text.split_once(WRAP_MARKER) {
+        Some((newer, older)) => format!("{}{}", older.trim_start_matches(['\r', '\n']), newer),
+        None => text.to_string(),
+    };
+    let mut entries = Vec::new();
+    let mut task: Option<String> = None;
+    for line in text.lines() {
+        if !line.starts_with([' ', '\t']) {
+            task = None;
+            let Some((name, rest)) = line.strip_prefix('"').and_then(|l| l.split_once('"')) else {
+                continue;
+            };
+            if !name.to_ascii_lowercase().ends_with(".job") {
+                continue;
+            }
+            task = Some(name.to_string());
+            if let Some(rest) = rest.trim_end().strip_suffix("** ERROR **") {
+                // `"Name.job" (program) 3/4/2024 2:13:00 AM ** ERROR **`
+                let time = rest.rsplit_once(')').map_or(rest, |(_, time)| time);
+                entries.push(LogEntry {
+                    task: name.to_string(),
+                    kind: EntryKind::Failed,
+                    time: parse_log_time(time),
+                    exit_code: None,
+                });
+            }
+            continue;
+        }
+        let Some(name) = &task else {
+            continue;
+        };
+        let line = line.trim();
+        let (kind, time) = if let Some(time) = line.strip_prefix("Started ") {
+            (EntryKind::Started, time)
+        } else if let Some(time) = line.strip_prefix("Finished ") {
+            (EntryKind::Finished, time)
+        } else {
+            if line.starts_with("Result:") {
+                if let (Some(code), Some(last)) = (exit_code(line), entries.last_mut()) {
+                    if last.task == *name && last.kind == EntryKind::Finished {
+                        last.exit_code = Some(code);
+                    }
+                }
+            }
+            continue;
+        };
+        entries.push(LogEntry {
+            task: name.clone(),
+            kind,
+            time: parse_log_time(time),
+            exit_code: None,
+        });
+    }
+    entries
+}
+
+/// The code in `Result: The task completed with an exit code of (1).`,
+/// which is hexadecimal when it has letters in it.
+fn exit_code(line: &str) -> Option<i64> {
+    let (_, rest) = line.rsplit_once('(')?;
+    let (code, _) = rest.split_once(')')?;
+    code.parse()
+        .ok()
+        .or_else(|| i64::from_str_radix(code.trim_start_matches("0x"), 16).ok())
+}
+
+/// Reads a CSV export of the operational log, as Event Viewer saves it
+/// (`Level,Date and Time,Source,Event ID,Task Category` and the message in
+/// an unnamed last column) or as `Export-Csv` writes it (`TimeCreated`,
+/// `Id` and `Message` among other columns). Events are matched to their
+/// task by the first quoted `\` path in the message.
+pub fn parse_operational_csv(text: &str) -> Vec<LogEntry> {
+    let mut records = csv_records(text).into_iter();
+    let Some(header) = records.next() else {
+        return Vec::new();
+    };
+    let column = |names: &[&str]| {
+        header
+            .iter()
+            .position(|h| names.iter().any(|name| h.trim().eq_ignore_ascii_case(name)))
+    };
+    let (Some(id_column), Some(time_column)) =
+        (column(&["Event ID", "Id"]), column(&["Date and Time", "TimeCreated"]))
+    else {
+        return Vec::new();
+    };
+    let message_column = column(&["Message"]).unwrap_or(header.len());
+    let mut entries = Vec::new();
+    for record in records {
+        let field = |index: usize| record.get(index).map(String::as_str).unwrap_or_default();
+        let kind = match field(id_column).trim() {
+            "100" => EntryKind::Started,
+            "102" | "201" => EntryKind::Finished,
+            "101" | "103" | "203" => EntryKind::Failed,
+            _ => continue,
+        };
+        let message = field(message_column);
+        let Some(task) = quoted_task(message) else {
+            continue;
+        };
+        let exit_code = message
+            .split_once("return code ")
+            .and_then(|(_, rest)| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .and_then(|code| code.parse().ok());
+        entries.push(LogEntry {
+            task: task.to_string(),
+            kind,
+            time: parse_log_time(field(time_column)),
+            exit_code,
+        });
+    }
+    entries
+}
+
+fn is_id_column(name: &str) -> bool {
+    let name = name.trim();
+    name.eq_ignore_ascii_case("Event ID") || name.eq_ignore_ascii_case("Id")
+}
+
+/// The first `"\..."` in `message`.
+fn quoted_task(message: &str) -> Option<&str> {
+    message.split('"').skip(1).step_by(2).find(|quoted| quoted.starts_with('\\'))
+}
+
+/// The fields of one CSV line, for sniffing the header.
+fn csv_fields(line: &str) -> Vec<String> {
+    csv_records(line).into_iter().next().unwrap_or_default()
+}
+
+/// Splits CSV text into records of fields. Quoted fields may hold commas,
+/// line breaks and doubled quotes.
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.is_empty()) {
+        records.push(record);
+    }
+    records
+}
+
+/// Reads `3/4/2024 2:13:00 PM` (month first, optional seconds and AM/PM)
+/// or `2024-03-04T14:13:00` (or with a space, fractional seconds and a
+/// zone) as `2024-03-04T14:13:00`.
+pub fn parse_log_time(text: &str) -> Option<String> {
+    let text = text.trim();
+    let (date, rest) = text.split_once(['T', ' '])?;
+    let (year, month, day) = if date.contains('/') {
+        let parts: Vec<u32> = date.split('/').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+        let [month, day, year] = parts[..] else {
+            return None;
+        };
+        (year, month, day)
+    } else {
+        let parts: Vec<u32> = date.split('-').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+        let [year, month, day] = parts[..] else {
+            return None;
+        };
+        (year, month, day)
+    };
+    let mut words = rest.split_whitespace();
+    let time = words.next()?;
+    let time = time.split(['.', 'Z', '+', '-']).next()?;
+    let parts: Vec<u32> = time.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let (mut hour, minute, second) = match parts[..] {
+        [hour, minute] => (hour, minute, 0),
+        [hour, minute, second] => (hour, minute, second),
+        _ => return None,
+    };
+    match words.next().map(str::to_ascii_uppercase).as_deref() {
+        Some("AM") if hour == 12 => hour = 0,
+        Some("PM") if hour < 12 => hour += 12,
+        _ => {}
+    }
+    let valid = (1000..=9999).contains(&year)
+        && (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && hour < 24
+        && minute < 60
+        && second < 60;
+    valid.then(|| {
+        format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", year, month, day, hour, minute, second)
+    })
+}
@@ -0,0 +1,346 @@
+//! When the triggers of a job or task could have started it. The
+//! time-based triggers of both formats are read into a `Schedule`, which
+//! finds the run nearest to a given time, for telling a scheduled run from
+//! one started by hand.
+//!
+//! Times are seconds since 1970 in the local time of the machine, as
+//! `date::local_seconds` reads them: job triggers have no zone, and a zone
+//! on a task's boundaries is ignored, as Task Scheduler does unless told to
+//! synchronize across time zones. Daylight saving time is not accounted
+//! for.
+
+use std::fmt;
+
+use crate::date::{civil_from_days, days_from_civil};
+
+const DAY: i64 = 86400;
+
+/// How far `Schedule::nearest_run` looks back and ahead for the closest
+/// start of a recurring trigger: far enough for one that only fires on
+/// 29 February.
+const SCAN_DAYS: i64 = 5 * 366;
+
+/// The triggers of a job or task, as far as they say when it runs.
+/// Disabled triggers are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+    /// The triggers that start the task at set times.
+    pub time_triggers: Vec<TimeTrigger>,
+    /// How many triggers start it on an event instead, such as a logon, the
+    /// system starting or going idle.
+    pub event_triggers: usize,
+    /// Whether a missed run starts as soon as it can (a task's
+    /// `StartWhenAvailable`), so that a run may come at any time after one
+    /// was due.
+    pub catches_up: bool,
+}
+
+/// A trigger that starts the task at set times: first at `start`, then as
+/// `recurrence` says, until `end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeTrigger {
+    pub start: i64,
+    /// The last time the trigger fires, if it stops.
+    pub end: Option<i64>,
+    pub recurrence: Recurrence,
+    /// How often the task runs again after each start, and for how long.
+    pub repetition: Option<Repetition>,
+    /// The longest the scheduler may wait after each start, in seconds.
+    pub random_delay: i64,
+}
+
+/// Which days a `TimeTrigger` fires on, at the time of day of its start.
+/// In masks of days of the week, bit 0 is Sunday; in masks of months, bit
+/// 0 is January.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    /// Only at the start.
+    Once,
+    /// Every `every` days.
+    Daily { every: u32 },
+    /// On `days` of every `every` weeks, counted from the week of the start.
+    Weekly { every: u32, days: u8 },
+    /// On the `days` of `months`, bit 0 for the 1st, and on their last day
+    /// if `last_day`.
+    MonthlyDate { days: u32, last_day: bool, months: u16 },
+    /// On `days` of the `weeks` of `months`: bits 0 to 3 for the first to
+    /// the fourth of each day in the month, bit 4 for the last.
+    MonthlyDow { weeks: u8, days: u8, months: u16 },
+}
+
+/// A task that runs every `interval` seconds after each start, for
+/// `duration` seconds or, when that is `None`, until the trigger fires
+/// again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repetition {
+    pub interval: i64,
+    pub duration: Option<i64>,
+}
+
+/// The run of a schedule closest to a given time, and how far from it that
+/// time is, in seconds. A time within the random delay after a run is 0
+/// away from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedRun {
+    pub time: i64,
+    pub distance: i64,
+}
+
+/// One trigger as written, enabled or not, for listing a task's triggers
+/// one per row. Boundaries and durations are kept as text, since a job
+/// writes its own and a task's may not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerDetail {
+    /// The job trigger type, such as `Daily` or `AtLogon`, or the task
+    /// trigger element, such as `CalendarTrigger` or `LogonTrigger`.
+    pub kind: String,
+    /// When it first fires, `YYYY-MM-DDTHH:MM:SS` for a job.
+    pub start: Option<String>,
+    /// When it stops: the last day, `YYYY-MM-DD`, for a job.
+    pub end: Option<String>,
+    pub enabled: bool,
+    /// When it fires, in words: the `Recurrence` of a time trigger, such as
+    /// `weekly on Mon, Fri`, or the event of the others, such as `at logon`.
+    /// `unknown` for a time trigger whose days cannot be read, or a kind of
+    /// trigger this crate does not know.
+    pub summary: String,
+    /// How often the task runs again after each start, as an `xs:duration`
+    /// such as `PT15M`.
+    pub interval: Option<String>,
+    /// For how long it runs again, as an `xs:duration`.
+    pub duration: Option<String>,
+}
+
+impl Schedule {
+    /// The run closest to `at` of any time trigger, the earlier of two
+    /// equally close; `None` without time triggers, or when the recurring
+    /// ones start neither in the five years before `at` nor in the five
+    /// after.
+    pub fn nearest_run(&self, at: i64) -> Option<ExpectedRun> {
+        self.time_triggers
+            .iter()
+            .flat_map(|trigger| {
+                let delay = trigger.random_delay;
+                trigger.runs_near(at).into_iter().map(move |time| ExpectedRun {
+                    time,
+                    distance: distance(time, at, delay),
+                })
+            })
+            .min_by_key(|run| (run.distance, run.time))
+    }
+
+    /// The times from `from` to `to`, both included, that any time trigger
+    /// is due to start the task, repetitions included, in order and each
+    /// once; at most the first `limit`. A random delay is not added.
+    pub fn runs_between(&self, from: i64, to: i64, limit: usize) -> Vec<i64> {
+        let mut runs: Vec<i64> = self
+            .time_triggers
+            .iter()
+            .flat_map(|trigger| trigger.runs_between(from, to, limit))
+            .collect();
+        runs.sort_unstable();
+        runs.dedup();
+        runs.truncate(limit);
+        runs
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] =
+    ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+const WEEKS: [&str; 5] = ["first", "second", "third", "fourth", "last"];
+
+/// The recurrence in words, such as `every 2 weeks on Mon, Fri` or
+/// `monthly on the last Fri in Mar`.
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = |mask: u32, names: &[&str]| -> String {
+            let set = names.iter().enumerate().filter(|&(bit, _)| has_bit(mask, bit as i64));
+            set.map(|(_, name)| *name).collect::<Vec<_>>().join(", ")
+        };
+        let in_months = |months: u16| match months & 0xFFF {
+            0xFFF => String::new(),
+            months => format!(" in {}", names(months.into(), &MONTHS)),
+        };
+        match *self {
+            Recurrence::Once => write!(f, "once"),
+            Recurrence::Daily { every: 1 } => write!(f, "daily"),
+            Recurrence::Daily { every } => write!(f, "every {} days", every),
+            Recurrence::Weekly { every, days } => {
+                let days = names(days.into(), &WEEKDAYS);
+                match every {
+                    1 => write!(f, "weekly on {}", days),
+                    every => write!(f, "every {} weeks on {}", every, days),
+                }
+            }
+            Recurrence::MonthlyDate { days, last_day, months } => {
+                let mut dates: Vec<String> = (0..31)
+                    .filter(|&day| has_bit(days, day))
+                    .map(|day| (day + 1).to_string())
+                    .collect();
+                if last_day {
+                    dates.push("last".to_string());
+                }
+                write!(f, "monthly on day {}{}", dates.join(", "), in_months(months))
+            }
+            Recurrence::MonthlyDow { weeks, days, months } => {
+                let (weeks, days) = (names(weeks.into(), &WEEKS), names(days.into(), &WEEKDAYS));
+                write!(f, "monthly on the {} {}{}", weeks, days, in_months(months))
+            }
+        }
+    }
+}
+
+/// How far `at` is from a run started at `time` and delayed by up to
+/// `delay` seconds.
+fn distance(time: i64, at: i64, delay: i64) -> i64 {
+    if at < time {
+        time - at
+    } else {
+        (at - time - delay).max(0)
+    }
+}
+
+impl TimeTrigger {
+    /// The runs closest to `at` on either side: the last start at or before
+    /// it and the first after it, and the repetitions around `at` of the
+    /// starts whose repetition may still be going then.
+    fn runs_near(&self, at: i64) -> Vec<i64> {
+        let today = at.div_euclid(DAY);
+        let start_day = self.start.div_euclid(DAY);
+        let (first, last) = match self.recurrence {
+            Recurrence::Once => (start_day, start_day),
+            _ => (start_day.max(today - SCAN_DAYS), today.max(start_day) + SCAN_DAYS),
+        };
+        let last = self.end.map_or(last, |end| last.min(end.div_euclid(DAY)));
+        let previous = (first..=today.min(last))
+            .rev()
+            .find_map(|day| self.start_on(day).filter(|&start| start <= at));
+        let next = (today.max(first)..=last)
+            .find_map(|day| self.start_on(day).filter(|&start| start > at));
+        let mut runs: Vec<i64> = previous.into_iter().chain(next).collect();
+        let Some(repetition) = self.repetition.filter(|repetition| repetition.interval > 0) else {
+            return runs;
+        };
+        let starts: Vec<i64> = match repetition.duration {
+            // Repetitions may overlap the next start, though starts more
+            // than `SCAN_DAYS` back are not looked for.
+            Some(duration) => {
+                let first = at.saturating_sub(duration).saturating_sub(self.random_delay);
+                let first = first.div_euclid(DAY).max(start_day).max(today - SCAN_DAYS);
+                (first..=today)
+                    .filter_map(|day| self.start_on(day))
+                    .filter(|&start| start <= at)
+                    .collect()
+            }
+            None => previous.into_iter().collect(),
+        };
+        for start in starts {
+            // The last repetition, before the next start when repeating
+            // indefinitely.
+            let last = match repetition.duration {
+                Some(duration) => start.saturating_add(duration),
+                None => next.map_or(i64::MAX, |next| next - 1),
+            };
+            let last = self.end.map_or(last, |end| last.min(end));
+            let steps = last.saturating_sub(start).div_euclid(repetition.interval);
+            let latest = (at - start).div_euclid(repetition.interval);
+            let earliest = (at - start - self.random_delay).div_euclid(repetition.interval);
+            for k in [earliest, latest, latest + 1] {
+                runs.push(start + k.clamp(0, steps.max(0)) * repetition.interval);
+            }
+        }
+        runs
+    }
+
+    /// The first `limit` runs from `from` to `to`; see
+    /// `Schedule::runs_between`.
+    fn runs_between(&self, from: i64, to: i64, limit: usize) -> Vec<i64> {
+        let repetition = self.repetition.filter(|repetition| repetition.interval > 0);
+        // Starts before `from` whose repetitions may still be going then.
+        let lookback = match repetition {
+            None => 0,
+            Some(Repetition { duration: Some(duration), .. }) => {
+                (duration.div_euclid(DAY) + 1).min(SCAN_DAYS)
+            }
+            Some(Repetition { duration: None, .. }) => SCAN_DAYS,
+        };
+        let (first, last) = (from.div_euclid(DAY) - lookback, to.div_euclid(DAY));
+        let last = match self.recurrence {
+            Recurrence::Once => last.min(self.start.div_euclid(DAY)),
+            _ => last,
+        };
+        let starts: Vec<i64> = (first.max(self.start.div_euclid(DAY))..=last)
+            .filter_map(|day| self.start_on(day))
+            .collect();
+        let mut runs = Vec::new();
+        for (i, &start) in starts.iter().enumerate() {
+            let Some(repetition) = repetition else {
+                runs.push(start);
+                continue;
+            };
+            let until = match repetition.duration {
+                Some(duration) => start.saturating_add(duration),
+                None => starts.get(i + 1).map_or(to, |next| next - 1),
+            };
+            let until = self.end.map_or(until, |end| until.min(end)).min(to);
+            // The first repetition at or after `from`.
+            let skip = (from - start).max(0).saturating_add(repetition.interval - 1);
+            let mut run = start + skip.div_euclid(repetition.interval) * repetition.interval;
+            while run <= until && runs.len() < limit {
+                runs.push(run);
+                run = run.saturating_add(repetition.interval);
+            }
+        }
+        runs.retain(|run| (from..=to).contains(run));
+        runs.truncate(limit);
+        runs
+    }
+
+    /// When the trigger fires on `day`, days since 1970, if it does.
+    fn start_on(&self, day: i64) -> Option<i64> {
+        let start_day = self.start.div_euclid(DAY);
+        let time = day * DAY + self.start.rem_euclid(DAY);
+        if day < start_day || self.end.is_some_and(|end| time > end) {
+            return None;
+        }
+        let fires = match self.recurrence {
+            Recurrence::Once => day == start_day,
+            Recurrence::Daily { every } => (day - start_day) % i64::from(every.max(1)) == 0,
+            Recurrence::Weekly { every, days } => {
+                // Weeks run from Sunday.
+                let week = (day - start_day + weekday(start_day)).div_euclid(7);
+                has_bit(days.into(), weekday(day)) && week % i64::from(every.max(1)) == 0
+            }
+            Recurrence::MonthlyDate { days, last_day, months } => {
+                let (year, month, day_of_month) = civil_from_days(day);
+                has_bit(months.into(), month - 1)
+                    && (has_bit(days, day_of_month - 1)
+                        || (last_day && day_of_month == days_in_month(year, month)))
+            }
+            Recurrence::MonthlyDow { weeks, days, months } => {
+                let (year, month, day_of_month) = civil_from_days(day);
+                let last = day_of_month + 7 > days_in_month(year, month);
+                has_bit(months.into(), month - 1)
+                    && has_bit(days.into(), weekday(day))
+                    && (has_bit(weeks.into(), (day_of_month - 1) / 7)
+                        || (last && has_bit(weeks.into(), 4)))
+            }
+        };
+        fires.then_some(time)
+    }
+}
+
+/// The day of the week, 0 for Sunday, of `day` days since 1970, a Thursday.
+fn weekday(day: i64) -> i64 {
+    (day + 4).rem_euclid(7)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1)
+}
+
+fn has_bit(mask: u32, bit: i64) -> bool {
+    (0..32).contains(&bit) && mask & (1 << bit) != 0
+}
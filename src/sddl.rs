@@ -0,0 +1,561 @@
+//! Decoding of SDDL security descriptor strings, such as the one in a task's
+//! `RegistrationInfo/SecurityDescriptor`, into owner, group and ACEs, and
+//! naming of the well-known SIDs they and task principals refer to.
+
+use std::fmt;
+
+/// Account abbreviations SDDL uses in place of a SID.
+const WELL_KNOWN_SIDS: &[(&str, &str)] = &[
+    ("AA", "Access Control Assistance Operators"),
+    ("AC", "ALL APPLICATION PACKAGES"),
+    ("AN", "ANONYMOUS LOGON"),
+    ("AO", "Account Operators"),
+    ("AU", "Authenticated Users"),
+    ("BA", "BUILTIN\\Administrators"),
+    ("BG", "BUILTIN\\Guests"),
+    ("BO", "Backup Operators"),
+    ("BU", "BUILTIN\\Users"),
+    ("CA", "Cert Publishers"),
+    ("CD", "Certificate Service DCOM Access"),
+    ("CG", "CREATOR GROUP"),
+    ("CO", "CREATOR OWNER"),
+    ("CY", "Cryptographic Operators"),
+    ("DA", "Domain Admins"),
+    ("DC", "Domain Computers"),
+    ("DD", "Domain Controllers"),
+    ("DG", "Domain Guests"),
+    ("DU", "Domain Users"),
+    ("EA", "Enterprise Admins"),
+    ("ED", "ENTERPRISE DOMAIN CONTROLLERS"),
+    ("ER", "Event Log Readers"),
+    ("HI", "High Mandatory Level"),
+    ("IS", "IIS_IUSRS"),
+    ("IU", "INTERACTIVE"),
+    ("LA", "Administrator"),
+    ("LG", "Guest"),
+    ("LS", "LOCAL SERVICE"),
+    ("LU", "Performance Log Users"),
+    ("LW", "Low Mandatory Level"),
+    ("ME", "Medium Mandatory Level"),
+    ("MU", "Performance Monitor Users"),
+    ("NO", "Network Configuration Operators"),
+    ("NS", "NETWORK SERVICE"),
+    ("NU", "NETWORK"),
+    ("OW", "OWNER RIGHTS"),
+    ("PA", "Group Policy Creator Owners"),
+    ("PO", "Print Operators"),
+    ("PS", "SELF"),
+    ("PU", "Power Users"),
+    ("RC", "RESTRICTED"),
+    ("RD", "Remote Desktop Users"),
+    ("RE", "Replicator"),
+    ("RM", "Remote Management Users"),
+    ("RO", "Enterprise Read-only Domain Controllers"),
+    ("RS", "RAS and IAS Servers"),
+    ("RU", "Pre-Windows 2000 Compatible Access"),
+    ("SA", "Schema Admins"),
+    ("SI", "System Mandatory Level"),
+    ("SO", "Server Operators"),
+    ("SS", "Service asserted identity"),
+    ("SU", "SERVICE"),
+    ("SY", "NT AUTHORITY\\SYSTEM"),
+    ("WD", "Everyone"),
+    ("WR", "WRITE RESTRICTED"),
+];
+
+/// Well-known SIDs that appear spelled out rather than abbreviated, as in a
+/// principal's `UserId`.
+const WELL_KNOWN_SID_STRINGS: &[(&str, &str)] = &[
+    ("S-1-0-0", "NULL SID"),
+    ("S-1-1-0", "Everyone"),
+    ("S-1-2-0", "LOCAL"),
+    ("S-1-3-0", "CREATOR OWNER"),
+    ("S-1-3-1", "CREATOR GROUP"),
+    ("S-1-5-2", "NETWORK"),
+    ("S-1-5-3", "BATCH"),
+    ("S-1-5-4", "INTERACTIVE"),
+    ("S-1-5-6", "SERVICE"),
+    ("S-1-5-7", "ANONYMOUS LOGON"),
+    ("S-1-5-9", "ENTERPRISE DOMAIN CONTROLLERS"),
+    ("S-1-5-10", "SELF"),
+    ("S-1-5-11", "Authenticated Users"),
+    ("S-1-5-12", "RESTRICTED"),
+    ("S-1-5-18", "NT AUTHORITY\\SYSTEM"),
+    ("S-1-5-19", "LOCAL SERVICE"),
+    ("S-1-5-20", "NETWORK SERVICE"),
+    ("S-1-5-32-544", "BUILTIN\\Administrators"),
+    ("S-1-5-32-545", "BUILTIN\\Users"),
+    ("S-1-5-32-546", "BUILTIN\\Guests"),
+    ("S-1-5-32-547", "Power Users"),
+    ("S-1-5-32-551", "Backup Operators"),
+    ("S-1-5-32-555", "Remote Desktop Users"),
+    ("S-1-16-4096", "Low Mandatory Level"),
+    ("S-1-16-8192", "Medium Mandatory Level"),
+    ("S-1-16-12288", "High Mandatory Level"),
+    ("S-1-16-16384", "System Mandatory Level"),
+];
+
+/// Groups that take in accounts other than administrators, by SDDL
+/// abbreviation and by SID: Everyone, Authenticated Users, INTERACTIVE,
+/// ANONYMOUS LOGON, Users, Guests, Power Users, Domain Users and Domain
+/// Guests.
+const NON_ADMIN_GROUPS: &[&str] = &[
+    "WD", "S-1-1-0", "AU", "S-1-5-11", "IU", "S-1-5-4", "AN", "S-1-5-7", "BU", "S-1-5-32-545",
+    "BG", "S-1-5-32-546", "PU", "S-1-5-32-547", "DU", "DG",
+];
+
+/// Rights that let a file be changed, whether by writing to it or by
+/// taking it over.
+const WRITE_RIGHTS: &[&str] = &[
+    "GENERIC_ALL",
+    "GENERIC_WRITE",
+    "FILE_ALL_ACCESS",
+    "FILE_GENERIC_WRITE",
+    "WRITE_DAC",
+    "WRITE_OWNER",
+];
+
+/// The same as bits: `FILE_WRITE_DATA`, `FILE_APPEND_DATA`, `WRITE_DAC`,
+/// `WRITE_OWNER`, `GENERIC_ALL` and `GENERIC_WRITE`.
+const WRITE_MASK: u32 = 0x2 | 0x4 | 0x40000 | 0x80000 | 0x1000_0000 | 0x4000_0000;
+
+/// Access right abbreviations and the rights they stand for.
+const RIGHTS: &[(&str, &str)] = &[
+    ("GA", "GENERIC_ALL"),
+    ("GR", "GENERIC_READ"),
+    ("GW", "GENERIC_WRITE"),
+    ("GX", "GENERIC_EXECUTE"),
+    ("RC", "READ_CONTROL"),
+    ("SD", "DELETE"),
+    ("WD", "WRITE_DAC"),
+    ("WO", "WRITE_OWNER"),
+    ("RP", "READ_PROPERTY"),
+    ("WP", "WRITE_PROPERTY"),
+    ("CC", "CREATE_CHILD"),
+    ("DC", "DELETE_CHILD"),
+    ("LC", "LIST_CHILDREN"),
+    ("SW", "SELF_WRITE"),
+    ("LO", "LIST_OBJECT"),
+    ("DT", "DELETE_TREE"),
+    ("CR", "CONTROL_ACCESS"),
+    ("FA", "FILE_ALL_ACCESS"),
+    ("FR", "FILE_GENERIC_READ"),
+    ("FW", "FILE_GENERIC_WRITE"),
+    ("FX", "FILE_GENERIC_EXECUTE"),
+    ("KA", "KEY_ALL_ACCESS"),
+    ("KR", "KEY_READ"),
+    ("KW", "KEY_WRITE"),
+    ("KX", "KEY_EXECUTE"),
+    ("NR", "NO_READ_UP"),
+    ("NW", "NO_WRITE_UP"),
+    ("NX", "NO_EXECUTE_UP"),
+];
+
+const ACE_TYPES: &[(&str, &str)] = &[
+    ("A", "Allow"),
+    ("D", "Deny"),
+    ("OA", "Object allow"),
+    ("OD", "Object deny"),
+    ("AU", "Audit"),
+    ("AL", "Alarm"),
+    ("OU", "Object audit"),
+    ("OL", "Object alarm"),
+    ("ML", "Mandatory label"),
+    ("XA", "Callback allow"),
+    ("XD", "Callback deny"),
+    ("XU", "Callback audit"),
+    ("ZA", "Callback object allow"),
+    ("RA", "Resource attribute"),
+    ("SP", "Scoped policy"),
+];
+
+const ACE_FLAGS: &[(&str, &str)] = &[
+    ("CI", "container inherit"),
+    ("OI", "object inherit"),
+    ("NP", "no propagate"),
+    ("IO", "inherit only"),
+    ("ID", "inherited"),
+    ("SA", "audit success"),
+    ("FA", "audit failure"),
+    ("TP", "trust protected"),
+    ("CR", "critical"),
+];
+
+const ACL_FLAGS: &[(&str, &str)] = &[
+    ("NO_ACCESS_CONTROL", "no access control"),
+    ("P", "protected"),
+    ("AR", "auto-inherit requested"),
+    ("AI", "auto-inherited"),
+];
+
+/// A decoded security descriptor. Absent parts were not in the string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityDescriptor {
+    pub owner: Option<Trustee>,
+    pub group: Option<Trustee>,
+    pub dacl: Option<Acl>,
+    pub sacl: Option<Acl>,
+}
+
+/// A SID as written in the SDDL, with the account name when it is a
+/// well-known one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trustee {
+    pub sid: String,
+    pub name: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Acl {
+    /// Expanded ACL flags, such as `protected`.
+    pub flags: Vec<&'static str>,
+    pub aces: Vec<Ace>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ace {
+    /// The expanded ACE type, such as `Allow`.
+    pub ace_type: &'static str,
+    /// Expanded ACE flags, such as `object inherit`.
+    pub flags: Vec<&'static str>,
+    /// Rights by name; a numeric mask is kept in hex, and an unknown
+    /// abbreviation as written.
+    pub rights: Vec<String>,
+    pub object_guid: Option<String>,
+    pub inherit_object_guid: Option<String>,
+    pub trustee: Trustee,
+    /// The condition or attribute of callback and resource ACEs, as written.
+    pub extra: Option<String>,
+}
+
+/// Why an SDDL string could not be decoded. `offset` is in bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SddlError {
+    pub offset: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for SddlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid SDDL at offset {}: {}", self.offset, self.reason)
+    }
+}
+
+impl std::error::Error for SddlError {}
+
+fn error<T>(offset: usize, reason: impl Into<String>) -> Result<T, SddlError> {
+    Err(SddlError {
+        offset,
+        reason: reason.into(),
+    })
+}
+
+fn lookup(table: &[(&str, &'static str)], key: &str) -> Option<&'static str> {
+    table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+/// The account name of a well-known SID such as `S-1-5-18`. Per-service
+/// SIDs (`S-1-5-80-...`) are named `NT SERVICE`, since only the service name
+/// they were hashed from tells them apart.
+pub fn sid_name(sid: &str) -> Option<&'static str> {
+    if sid.starts_with("S-1-5-80-") {
+        return Some("NT SERVICE");
+    }
+    lookup(WELL_KNOWN_SID_STRINGS, sid)
+}
+
+/// Whether `sid` is a domain or machine account (`S-1-5-21-...`), which
+/// only that domain's controllers can name.
+pub fn is_domain_sid(sid: &str) -> bool {
+    sid.starts_with("S-1-5-21-")
+}
+
+/// An account as it should read in reports: a SID followed by its short
+/// name, such as `S-1-5-18 (SYSTEM)`, with domain SIDs marked as
+/// unresolved. Account names and unknown SIDs are returned as given.
+pub fn describe_account(account: &str) -> String {
+    match sid_name(account) {
+        Some(name) => {
+            format!("{} ({})", account, name.strip_prefix("NT AUTHORITY\\").unwrap_or(name))
+        }
+        None if is_domain_sid(account) => {
+            format!("{} (domain SID, resolve against the domain)", account)
+        }
+        None => account.to_string(),
+    }
+}
+
+/// Whether `account` names the local SYSTEM account, by SID or by name.
+pub fn is_system_account(account: &str) -> bool {
+    let account = account.trim();
+    account == "S-1-5-18"
+        || account.eq_ignore_ascii_case("SYSTEM")
+        || account.eq_ignore_ascii_case("NT AUTHORITY\\SYSTEM")
+}
+
+impl Trustee {
+    fn new(sid: &str) -> Trustee {
+        let name = lookup(WELL_KNOWN_SIDS, sid).or_else(|| sid_name(sid));
+        Trustee {
+            sid: sid.to_string(),
+            name,
+        }
+    }
+}
+
+impl fmt::Display for Trustee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{} ({})", name, self.sid),
+            None => write!(f, "{}", self.sid),
+        }
+    }
+}
+
+impl SecurityDescriptor {
+    /// Decodes an SDDL string like `O:BAG:SYD:(A;;FA;;;BA)S:(AU;FA;FA;;;WD)`.
+    pub fn parse(sddl: &str) -> Result<SecurityDescriptor, SddlError> {
+        let mut descriptor = SecurityDescriptor {
+            owner: None,
+            group: None,
+            dacl: None,
+            sacl: None,
+        };
+        let mut offset = skip_whitespace(sddl, 0);
+        while offset < sddl.len() {
+            let rest = &sddl[offset..];
+            let component = match rest.as_bytes() {
+                [c @ (b'O' | b'G' | b'D' | b'S'), b':', ..] => *c,
+                _ => return error(offset, "expected O:, G:, D: or S:"),
+            };
+            let start = offset + 2;
+            let end = component_end(sddl, start);
+            let value = &sddl[start..end];
+            let slot_taken = match component {
+                b'O' => descriptor.owner.replace(parse_sid(value, start)?).is_some(),
+                b'G' => descriptor.group.replace(parse_sid(value, start)?).is_some(),
+                b'D' => descriptor.dacl.replace(parse_acl(value, start)?).is_some(),
+                _ => descriptor.sacl.replace(parse_acl(value, start)?).is_some(),
+            };
+            if slot_taken {
+                return error(offset, format!("{}: appears twice", component as char));
+            }
+            offset = skip_whitespace(sddl, end);
+        }
+        Ok(descriptor)
+    }
+
+    /// The groups of accounts other than administrators that the DACL lets
+    /// change the object, by name, in the order of their ACEs. Only allow
+    /// ACEs that apply to the object itself count, and deny ACEs are not
+    /// weighed against them. A DACL marked as no access control lets
+    /// `Everyone` in; a descriptor without a DACL says nothing.
+    pub fn non_admin_writers(&self) -> Vec<String> {
+        let Some(dacl) = &self.dacl else {
+            return Vec::new();
+        };
+        if dacl.flags.contains(&"no access control") {
+            return vec!["Everyone".to_string()];
+        }
+        let mut writers = Vec::new();
+        for ace in &dacl.aces {
+            let non_admin = NON_ADMIN_GROUPS.contains(&ace.trustee.sid.as_str());
+            let writes = ace.rights.iter().any(|right| match right.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16).is_ok_and(|mask| mask & WRITE_MASK != 0),
+                None => WRITE_RIGHTS.contains(&right.as_str()),
+            });
+            let name = ace.trustee.name.unwrap_or(&ace.trustee.sid).to_string();
+            if ace.ace_type == "Allow"
+                && !ace.flags.contains(&"inherit only")
+                && non_admin
+                && writes
+                && !writers.contains(&name)
+            {
+                writers.push(name);
+            }
+        }
+        writers
+    }
+
+    /// One line per part and ACE, indented by `indent` spaces.
+    pub fn render(&self, indent: usize) -> String {
+        let mut out = String::new();
+        let pad = " ".repeat(indent);
+        if let Some(owner) = &self.owner {
+            out.push_str(&format!("{}Owner: {}\n", pad, owner));
+        }
+        if let Some(group) = &self.group {
+            out.push_str(&format!("{}Group: {}\n", pad, group));
+        }
+        for (name, acl) in [("DACL", &self.dacl), ("SACL", &self.sacl)] {
+            let Some(acl) = acl else {
+                continue;
+            };
+            if acl.flags.is_empty() {
+                out.push_str(&format!("{}{}:\n", pad, name));
+            } else {
+                out.push_str(&format!("{}{} ({}):\n", pad, name, acl.flags.join(", ")));
+            }
+            if acl.aces.is_empty() {
+                out.push_str(&format!("{}  (no entries)\n", pad));
+            }
+            for ace in &acl.aces {
+                out.push_str(&format!("{}  {}\n", pad, ace));
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for Ace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.ace_type, self.trustee, self.rights.join(", "))?;
+        if !self.flags.is_empty() {
+            write!(f, " [{}]", self.flags.join(", "))?;
+        }
+        if let Some(guid) = &self.object_guid {
+            write!(f, " object {}", guid)?;
+        }
+        if let Some(guid) = &self.inherit_object_guid {
+            write!(f, " inherited by {}", guid)?;
+        }
+        if let Some(extra) = &self.extra {
+            write!(f, " {}", extra)?;
+        }
+        Ok(())
+    }
+}
+
+fn skip_whitespace(sddl: &str, offset: usize) -> usize {
+    offset + sddl[offset..].len() - sddl[offset..].trim_start().len()
+}
+
+/// Where the component starting at `start` ends: at the next `X:` marker
+/// outside parentheses, or at the end of the string.
+fn component_end(sddl: &str, start: usize) -> usize {
+    let bytes = sddl.as_bytes();
+    let mut depth = 0usize;
+    for i in start..bytes.len() {
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' => depth = depth.saturating_sub(1),
+            b'O' | b'G' | b'D' | b'S' if depth == 0 && bytes.get(i + 1) == Some(&b':') => {
+                return i;
+            }
+            _ => {}
+        }
+    }
+    bytes.len()
+}
+
+fn parse_sid(value: &str, offset: usize) -> Result<Trustee, SddlError> {
+    let value = value.trim();
+    let valid = (value.len() == 2 && value.bytes().all(|b| b.is_ascii_uppercase()))
+        || (value.starts_with("S-1-")
+            && value[4..]
+                .split('-')
+                .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit())));
+    if !valid {
+        return error(offset, format!("{:?} is not a SID", value));
+    }
+    Ok(Trustee::new(value))
+}
+
+fn parse_acl(value: &str, offset: usize) -> Result<Acl, SddlError> {
+    let ace_start = value.find('(').unwrap_or(value.len());
+    let mut flags = Vec::new();
+    let mut rest = value[..ace_start].trim();
+    while !rest.is_empty() {
+        let Some((token, name)) = ACL_FLAGS.iter().find(|(token, _)| rest.starts_with(token)) else {
+            return error(offset, format!("unknown ACL flags {:?}", rest));
+        };
+        flags.push(*name);
+        rest = &rest[token.len()..];
+    }
+
+    let mut aces = Vec::new();
+    let bytes = value.as_bytes();
+    let mut i = ace_start;
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if bytes[i] != b'(' {
+            return error(offset + i, "expected ( to start an ACE");
+        }
+        let mut depth = 0;
+        let mut end = None;
+        for (j, &b) in bytes.iter().enumerate().skip(i) {
+            match b {
+                b'(' => depth += 1,
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(j);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let Some(end) = end else {
+            return error(offset + i, "unterminated ACE");
+        };
+        aces.push(parse_ace(&value[i + 1..end], offset + i + 1)?);
+        i = end + 1;
+    }
+    Ok(Acl { flags, aces })
+}
+
+fn parse_ace(ace: &str, offset: usize) -> Result<Ace, SddlError> {
+    let fields: Vec<&str> = ace.splitn(7, ';').collect();
+    if fields.len() < 6 {
+        return error(offset, format!("ACE {:?} has fewer than 6 fields", ace));
+    }
+    let Some(ace_type) = lookup(ACE_TYPES, fields[0]) else {
+        return error(offset, format!("unknown ACE type {:?}", fields[0]));
+    };
+    let mut flags = Vec::new();
+    for token in pairs(fields[1]) {
+        match lookup(ACE_FLAGS, token) {
+            Some(name) => flags.push(name),
+            None => return error(offset, format!("unknown ACE flag {:?}", token)),
+        }
+    }
+    let rights = parse_rights(fields[2]);
+    let optional = |field: &str| (!field.is_empty()).then(|| field.to_string());
+    Ok(Ace {
+        ace_type,
+        flags,
+        rights,
+        object_guid: optional(fields[3]),
+        inherit_object_guid: optional(fields[4]),
+        trustee: parse_sid(fields[5], offset)?,
+        extra: fields.get(6).and_then(|field| optional(field)),
+    })
+}
+
+/// Expands an access mask: hex or decimal numbers are kept in hex, and
+/// two-letter abbreviations are replaced by the rights they name.
+fn parse_rights(rights: &str) -> Vec<String> {
+    let number = match rights.strip_prefix("0x").or_else(|| rights.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => rights.parse::<u32>().ok(),
+    };
+    if let Some(mask) = number {
+        return vec![format!("{:#x}", mask)];
+    }
+    pairs(rights)
+        .map(|token| lookup(RIGHTS, token).map_or(token.to_string(), str::to_string))
+        .collect()
+}
+
+/// Splits a run of two-letter tokens like `CCLCSW`. An odd trailing letter
+/// is returned on its own.
+fn pairs(s: &str) -> impl Iterator<Item = &str> {
+    (0..s.len())
+        .step_by(2)
+        .map(move |i| s.get(i..(i + 2).min(s.len())).unwrap_or(""))
+        .filter(|token| !token.is_empty())
+}
@@ -0,0 +1,149 @@
+//! What a file holds, judged from its first few KiB without parsing it, for
+//! routing files before handing them to a parser. `ArtifactFormat::sniff`
+//! only picks which parser to try; `sniff` also says how sure it is and
+//! why, and tells apart XML that is not a task and bytes that are not a
+//! job.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::consts::{self, fixed, product};
+
+/// How much of a file `sniff` needs; it looks at nothing past this.
+pub const SNIFF_LENGTH: usize = 4096;
+
+/// The format a file appears to hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+pub enum DetectedFormat {
+    Job,
+    TaskXml,
+    Unknown,
+}
+
+/// How sure `sniff` is of a `DetectedFormat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+pub enum Confidence {
+    /// Everything looked at agrees.
+    High,
+    /// Some of it does.
+    Medium,
+    /// A guess.
+    Low,
+}
+
+/// What `sniff` went by; fields it did not look at, or found nothing for,
+/// are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Evidence {
+    /// The byte-order mark the file starts with: `utf-8`, `utf-16le` or
+    /// `utf-16be`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub bom: Option<&'static str>,
+    /// The first word of a job, the Windows version that wrote it.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub product_version: Option<u16>,
+    /// The second word of a job, always `consts::FILE_VERSION`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub file_version: Option<u16>,
+    /// The name of the first element of an XML file, without a namespace
+    /// prefix.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub root_element: Option<String>,
+}
+
+/// What `sniff` made of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Sniff {
+    pub detected_format: DetectedFormat,
+    pub confidence: Confidence,
+    pub evidence: Evidence,
+    /// The size of the whole file, in bytes.
+    pub size: u64,
+}
+
+/// Judges a file of `size` bytes by `head`, its first bytes; anything past
+/// `SNIFF_LENGTH` is ignored. Files that start like XML, with a byte-order
+/// mark or `<`, are task XML if their root element is `Task` and unknown
+/// otherwise. Anything else is a job if its first two words are a product
+/// version and the file version Task Scheduler writes; with only one of
+/// them, or a file shorter than the fixed section, it is a job with less
+/// confidence.
+pub fn sniff(head: &[u8], size: u64) -> Sniff {
+    let head = &head[..head.len().min(SNIFF_LENGTH)];
+    let mut evidence = Evidence::default();
+    let (detected_format, confidence) = if head.is_empty() {
+        (DetectedFormat::Unknown, Confidence::High)
+    } else if crate::looks_like_xml(head) {
+        let (bom, text) = decode(head);
+        evidence.bom = bom;
+        evidence.root_element = root_element(&text);
+        match evidence.root_element.as_deref() {
+            Some("Task") => (DetectedFormat::TaskXml, Confidence::High),
+            Some(_) => (DetectedFormat::Unknown, Confidence::High),
+            // Cut off before the root element, or not XML after all.
+            None => (DetectedFormat::Unknown, Confidence::Low),
+        }
+    } else if head.len() < 4 {
+        (DetectedFormat::Unknown, Confidence::Medium)
+    } else {
+        let word = |offset: usize| u16::from_le_bytes([head[offset], head[offset + 1]]);
+        let (version, file_version) = (word(0), word(2));
+        evidence.product_version = Some(version);
+        evidence.file_version = Some(file_version);
+        let known = product::VERSIONS.iter().any(|(known, _)| *known == version);
+        match (known, file_version == consts::FILE_VERSION) {
+            (true, true) if size >= fixed::LENGTH as u64 => (DetectedFormat::Job, Confidence::High),
+            (true, true) => (DetectedFormat::Job, Confidence::Medium),
+            (true, false) | (false, true) => (DetectedFormat::Job, Confidence::Low),
+            (false, false) => (DetectedFormat::Unknown, Confidence::Medium),
+        }
+    };
+    Sniff { detected_format, confidence, evidence, size }
+}
+
+/// `head` as text, and the name of its byte-order mark. Text without one
+/// is taken as UTF-8, as XML without a declaration of its own is.
+fn decode(head: &[u8]) -> (Option<&'static str>, String) {
+    let utf16 = |bytes: &[u8], word: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| word([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    };
+    if let Some(rest) = head.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        (Some("utf-8"), String::from_utf8_lossy(rest).into_owned())
+    } else if let Some(rest) = head.strip_prefix(&[0xFF, 0xFE]) {
+        (Some("utf-16le"), utf16(rest, u16::from_le_bytes))
+    } else if let Some(rest) = head.strip_prefix(&[0xFE, 0xFF]) {
+        (Some("utf-16be"), utf16(rest, u16::from_be_bytes))
+    } else {
+        (None, String::from_utf8_lossy(head).into_owned())
+    }
+}
+
+/// The local name of the first element of `text`, after any declaration,
+/// comments, processing instructions and doctype.
+fn root_element(text: &str) -> Option<String> {
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start();
+        let skip_to = if rest.starts_with("<?") {
+            "?>"
+        } else if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<!") {
+            ">"
+        } else {
+            break;
+        };
+        let end = rest.find(skip_to)?;
+        rest = &rest[end + skip_to.len()..];
+    }
+    let name = rest.strip_prefix('<')?;
+    let end = name.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+    let name = &name[..end];
+    let local = name.rsplit(':').next().unwrap_or(name);
+    Some(local.to_string()).filter(|local| !local.is_empty())
+}
@@ -0,0 +1,1237 @@
+use encoding_rs::{Encoding, UTF_16LE, UTF_8};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use quick_xml::de::from_str;
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+use serde::de::{IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::fmt;
+use std::io::Read;
+use std::time::Duration;
+
+use crate::date::{self, format_timestamp, DateStyle};
+use crate::enabled::{self, Enablement, TriggerState};
+use crate::schedule::{Recurrence, Repetition, Schedule, TimeTrigger, TriggerDetail};
+use crate::sddl::{describe_account, SecurityDescriptor};
+use crate::warning::{TriggerSchedule, Warning, WarningCode};
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename = "Task")]
+pub struct Task {
+    #[serde(rename(deserialize = "RegistrationInfo"), alias = "registration_info")]
+    pub registration_info: RegistrationInfo,
+    #[serde(rename(deserialize = "Triggers"), alias = "triggers")]
+    pub triggers: Triggers,
+    #[serde(rename(deserialize = "Settings"), alias = "settings")]
+    pub settings: Settings,
+    #[serde(rename(deserialize = "Actions"), alias = "actions")]
+    pub actions: Actions,
+    #[serde(rename(deserialize = "Principals"), alias = "principals", default)]
+    pub principals: Option<Principals>,
+    /// Elements the model has no field for, keyed by their path below
+    /// `Task` (`Settings/WakeToRun`), with their content as written. A
+    /// repeated element gets an index: `Triggers/LogonTrigger[2]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub unknown_elements: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename = "RegistrationInfo")]
+pub struct RegistrationInfo {
+    #[serde(rename(deserialize = "Author"), alias = "author")]
+    pub author: Option<String>,
+    #[serde(rename(deserialize = "Date"), alias = "date")]
+    pub date: Option<String>,
+    #[serde(rename(deserialize = "Description"), alias = "description")]
+    pub description: Option<String>,
+    /// The task's access control list as an SDDL string; see `sddl`.
+    #[serde(rename(deserialize = "SecurityDescriptor"), alias = "security_descriptor")]
+    pub security_descriptor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename = "Triggers"))]
+pub struct Triggers {
+    pub calendar_trigger: Option<CalendarTrigger>,
+}
+
+// By hand, as the derived impl fails on a second `CalendarTrigger`.
+impl<'de> Deserialize<'de> for Triggers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TriggersVisitor;
+
+        impl<'de> Visitor<'de> for TriggersVisitor {
+            type Value = Triggers;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a Triggers element")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Triggers, A::Error> {
+                let mut calendar_trigger = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "CalendarTrigger" | "calendar_trigger" if calendar_trigger.is_none() => {
+                            calendar_trigger = map.next_value()?
+                        }
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(Triggers { calendar_trigger })
+            }
+        }
+
+        deserializer.deserialize_struct("Triggers", &["CalendarTrigger"], TriggersVisitor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename = "CalendarTrigger")]
+pub struct CalendarTrigger {
+    #[serde(rename(deserialize = "StartBoundary"), alias = "start_boundary")]
+    pub start_boundary: String,
+    #[serde(rename(deserialize = "EndBoundary"), alias = "end_boundary")]
+    pub end_boundary: Option<String>,
+    #[serde(rename(deserialize = "Enabled"), alias = "enabled")]
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename = "Settings")]
+pub struct Settings {
+    #[serde(rename(deserialize = "Enabled"), alias = "enabled")]
+    pub enabled: Option<bool>,
+    #[serde(rename(deserialize = "AllowStartIfOnBatteries"), alias = "allow_start_if_on_batteries")]
+    pub allow_start_if_on_batteries: Option<bool>,
+    #[serde(rename(deserialize = "Hidden"), alias = "hidden")]
+    pub hidden: Option<bool>,
+}
+
+/// The model holds the first `Exec` action; any after it are kept in
+/// `Task::unknown_elements`, as `Actions/Exec[2]` and on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", serde(rename = "Actions"))]
+pub struct Actions {
+    pub exec: Option<Exec>,
+}
+
+// By hand, as the derived impl fails on a second `Exec`.
+impl<'de> Deserialize<'de> for Actions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ActionsVisitor;
+
+        impl<'de> Visitor<'de> for ActionsVisitor {
+            type Value = Actions;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an Actions element")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Actions, A::Error> {
+                let mut exec = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "Exec" | "exec" if exec.is_none() => exec = map.next_value()?,
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(Actions { exec })
+            }
+        }
+
+        deserializer.deserialize_struct("Actions", &["Exec"], ActionsVisitor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename = "Exec")]
+pub struct Exec {
+    #[serde(rename(deserialize = "Command"), alias = "command")]
+    pub command: String,
+    #[serde(rename(deserialize = "Arguments"), alias = "arguments")]
+    pub arguments: Option<String>,
+    #[serde(rename(deserialize = "WorkingDirectory"), alias = "working_directory")]
+    pub working_directory: Option<String>,
+}
+
+/// What one action of a task runs; see `Task::action_targets`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionTarget {
+    /// A program and its arguments, as written.
+    Exec { command: String, arguments: Option<String> },
+    /// A COM class, by its `ClassId`.
+    ComHandler { class_id: String },
+    /// Any other kind of action, such as `SendEmail`, by element name.
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename = "Principals")]
+pub struct Principals {
+    #[serde(rename(deserialize = "Principal"), alias = "principal")]
+    pub principal: Option<Principal>,
+}
+
+/// The account the task runs as.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename = "Principal")]
+pub struct Principal {
+    #[serde(rename(deserialize = "UserId"), alias = "user_id")]
+    pub user_id: Option<String>,
+    #[serde(rename(deserialize = "GroupId"), alias = "group_id")]
+    pub group_id: Option<String>,
+    #[serde(rename(deserialize = "RunLevel"), alias = "run_level")]
+    pub run_level: Option<String>,
+}
+
+/// The tasks in one XML file; see `Task::all_from_bytes`.
+#[derive(Debug)]
+pub struct TaskList {
+    /// One entry per `<Task>` element, in document order.
+    pub tasks: Vec<Result<Task, Error>>,
+    /// Why the rest of the file was skipped, if it was not well formed.
+    pub trailing_error: Option<Error>,
+}
+
+/// The part of a task a fragment belongs in; see `Task::from_fragment`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Triggers,
+    Settings,
+    Actions,
+}
+
+impl Section {
+    /// The element name, such as `Triggers`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::Triggers => "Triggers",
+            Section::Settings => "Settings",
+            Section::Actions => "Actions",
+        }
+    }
+}
+
+/// A piece of task XML parsed on its own; see `Task::from_fragment`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    /// The root element as written, such as `Triggers` or `CalendarTrigger`.
+    pub root: String,
+    pub section: Section,
+    /// A task holding the fragment in its section, and nothing else.
+    pub task: Task,
+}
+
+/// The trigger elements `Task::from_fragment` reads as a root.
+const TRIGGER_ELEMENTS: &[&str] = &[
+    "BootTrigger",
+    "CalendarTrigger",
+    "EventTrigger",
+    "IdleTrigger",
+    "LogonTrigger",
+    "RegistrationTrigger",
+    "SessionStateChangeTrigger",
+    "TimeTrigger",
+];
+
+/// The action elements `Task::from_fragment` reads as a root.
+const ACTION_ELEMENTS: &[&str] = &["ComHandler", "Exec", "SendEmail", "ShowMessage"];
+
+impl Task {
+    /// Decodes a task XML document. A byte order mark selects the encoding;
+    /// input without one is treated as UTF-16LE, which is how Task Scheduler
+    /// writes these files, unless it starts with an XML declaration in
+    /// ASCII, when the encoding it declares is used.
+    pub fn from_bytes(data: &[u8]) -> Result<Task, Error> {
+        Task::from_xml(&decode(data)?)
+    }
+
+    /// Decodes a file that may hold several tasks: concatenated `<Task>`
+    /// documents, or a wrapping element around them. Each `<Task>` element
+    /// is parsed on its own, in document order. Content that is not well
+    /// formed ends the search; the tasks before it are kept and the error is
+    /// returned as `TaskList::trailing_error`.
+    ///
+    /// A file without any `<Task>` element is parsed as a whole, exactly
+    /// like `from_bytes`.
+    pub fn all_from_bytes(data: &[u8]) -> Result<TaskList, Error> {
+        let xml = decode(data)?;
+        let mut reader = Reader::from_str(&xml);
+        let mut tasks = Vec::new();
+        let mut trailing_error = None;
+        loop {
+            let start = reader.buffer_position() as usize;
+            let event = match reader.read_event() {
+                Ok(event) => event,
+                Err(e) => {
+                    trailing_error = Some(Error::Xml(e.into()));
+                    break;
+                }
+            };
+            match event {
+                Event::Start(element) if element.local_name().as_ref() == b"Task" => {
+                    if let Err(e) = reader.read_to_end(element.name()) {
+                        trailing_error = Some(Error::Xml(e.into()));
+                        break;
+                    }
+                }
+                Event::Empty(element) if element.local_name().as_ref() == b"Task" => {}
+                Event::Eof => break,
+                _ => continue,
+            }
+            let end = reader.buffer_position() as usize;
+            tasks.push(Task::from_xml(&xml[start..end]));
+        }
+        if tasks.is_empty() {
+            return Task::from_xml(&xml).map(|task| TaskList {
+                tasks: vec![Ok(task)],
+                trailing_error: None,
+            });
+        }
+        Ok(TaskList {
+            tasks,
+            trailing_error,
+        })
+    }
+
+    /// Parses an already decoded task XML document.
+    pub fn from_xml(xml: &str) -> Result<Task, Error> {
+        let mut task: Task = from_str(xml)?;
+        task.unknown_elements = unknown_elements(xml);
+        Ok(task)
+    }
+
+    /// Parses a fragment of task XML, as quoted in scripts and detection
+    /// rules: a `Triggers`, `Settings` or `Actions` element, or a single
+    /// trigger such as `CalendarTrigger` or action such as `Exec`. The
+    /// fragment is put in an otherwise empty task, which is parsed like any
+    /// other, so children the model has no field for are kept in
+    /// `unknown_elements`. Any other root fails with
+    /// `Error::UnknownFragment`.
+    pub fn from_fragment(xml: &str) -> Result<Fragment, Error> {
+        let mut reader = Reader::from_str(xml);
+        let (start, root) = loop {
+            let start = reader.buffer_position() as usize;
+            match reader.read_event() {
+                Ok(Event::Start(element)) | Ok(Event::Empty(element)) => {
+                    let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                    break (start, name);
+                }
+                Ok(Event::Eof) => return Err(Error::UnknownFragment(String::new())),
+                Ok(_) => continue,
+                Err(e) => return Err(Error::Xml(e.into())),
+            }
+        };
+        let section = match root.as_str() {
+            "Triggers" => Section::Triggers,
+            "Settings" => Section::Settings,
+            "Actions" => Section::Actions,
+            name if TRIGGER_ELEMENTS.contains(&name) => Section::Triggers,
+            name if ACTION_ELEMENTS.contains(&name) => Section::Actions,
+            _ => return Err(Error::UnknownFragment(root)),
+        };
+        let fragment = &xml[start..];
+        let content = if root == section.name() {
+            fragment.to_string()
+        } else {
+            format!("<{0}>{1}</{0}>", section.name(), fragment)
+        };
+        let part = |part: Section| {
+            if part == section {
+                content.clone()
+            } else {
+                format!("<{}/>", part.name())
+            }
+        };
+        let xml = format!(
+            "<Task><RegistrationInfo/>{}{}{}</Task>",
+            part(Section::Triggers),
+            part(Section::Settings),
+            part(Section::Actions)
+        );
+        let task = Task::from_xml(&xml)?;
+        Ok(Fragment { root, section, task })
+    }
+
+    /// `from_fragment` for undecoded bytes. A byte order mark selects the
+    /// encoding; without one, input with NUL bytes is read as UTF-16LE and
+    /// anything else as UTF-8, as fragments are mostly copied from text
+    /// files rather than from Task Scheduler.
+    pub fn fragment_from_bytes(data: &[u8]) -> Result<Fragment, Error> {
+        let bom = data.starts_with(&[0xFF, 0xFE])
+            || data.starts_with(&[0xFE, 0xFF])
+            || data.starts_with(&[0xEF, 0xBB, 0xBF]);
+        let xml = if bom || data.contains(&0) {
+            decode(data)?
+        } else {
+            String::from_utf8_lossy(data).into_owned()
+        };
+        Task::from_fragment(&xml)
+    }
+
+    /// Writes the task as a Task Scheduler XML document. The declaration
+    /// names UTF-16, the encoding Task Scheduler expects on disk; encode the
+    /// string accordingly (see `to_utf16_bytes`).
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-16\"?>\n");
+        xml.push_str("<Task version=\"1.2\" xmlns=\"http://schemas.microsoft.com/windows/2004/02/mit/task\">\n");
+
+        let info = &self.registration_info;
+        xml.push_str("  <RegistrationInfo>\n");
+        push_element(&mut xml, 4, "Date", info.date.as_deref());
+        push_element(&mut xml, 4, "Author", info.author.as_deref());
+        push_element(&mut xml, 4, "Description", info.description.as_deref());
+        push_element(&mut xml, 4, "SecurityDescriptor", info.security_descriptor.as_deref());
+        xml.push_str("  </RegistrationInfo>\n");
+
+        xml.push_str("  <Triggers>\n");
+        if let Some(trigger) = &self.triggers.calendar_trigger {
+            xml.push_str("    <CalendarTrigger>\n");
+            push_element(&mut xml, 6, "StartBoundary", Some(&trigger.start_boundary));
+            push_element(&mut xml, 6, "EndBoundary", trigger.end_boundary.as_deref());
+            push_element(&mut xml, 6, "Enabled", trigger.enabled.map(bool_str));
+            xml.push_str("    </CalendarTrigger>\n");
+        }
+        xml.push_str("  </Triggers>\n");
+
+        xml.push_str("  <Settings>\n");
+        push_element(&mut xml, 4, "Enabled", self.settings.enabled.map(bool_str));
+        push_element(
+            &mut xml,
+            4,
+            "AllowStartIfOnBatteries",
+            self.settings.allow_start_if_on_batteries.map(bool_str),
+        );
+        push_element(&mut xml, 4, "Hidden", self.settings.hidden.map(bool_str));
+        xml.push_str("  </Settings>\n");
+
+        if let Some(principal) = self.principal() {
+            xml.push_str("  <Principals>\n");
+            xml.push_str("    <Principal id=\"Author\">\n");
+            push_element(&mut xml, 6, "UserId", principal.user_id.as_deref());
+            push_element(&mut xml, 6, "GroupId", principal.group_id.as_deref());
+            push_element(&mut xml, 6, "RunLevel", principal.run_level.as_deref());
+            xml.push_str("    </Principal>\n");
+            xml.push_str("  </Principals>\n");
+        }
+
+        xml.push_str("  <Actions Context=\"Author\">\n");
+        if let Some(exec) = &self.actions.exec {
+            xml.push_str("    <Exec>\n");
+            push_element(&mut xml, 6, "Command", Some(&exec.command));
+            push_element(&mut xml, 6, "Arguments", exec.arguments.as_deref());
+            push_element(&mut xml, 6, "WorkingDirectory", exec.working_directory.as_deref());
+            xml.push_str("    </Exec>\n");
+        }
+        xml.push_str("  </Actions>\n");
+        xml.push_str("</Task>\n");
+        xml
+    }
+
+    /// The task's principal, if it names one.
+    pub fn principal(&self) -> Option<&Principal> {
+        self.principals.as_ref()?.principal.as_ref()
+    }
+
+    /// `to_xml` encoded as UTF-16LE with a byte order mark.
+    pub fn to_utf16_bytes(&self) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xFE];
+        for unit in self.to_xml().encode_utf16() {
+            out.extend_from_slice(&unit.to_le_bytes());
+        }
+        out
+    }
+
+    /// Whether the task is live as of `now` (`YYYY-MM-DDTHH:MM:SS`); see
+    /// `enabled`.
+    pub fn effective_enabled(&self, now: &str) -> Enablement {
+        let disabled = (self.settings.enabled == Some(false)).then_some(enabled::SETTINGS_DISABLED);
+        enabled::judge(disabled, Some(&self.trigger_states()), now)
+    }
+
+    /// Every trigger's `Enabled` and `EndBoundary`. Triggers other than the
+    /// calendar trigger are only kept whole in `unknown_elements`, as
+    /// `Triggers/LogonTrigger` or `Triggers/LogonTrigger[2]`, and are read
+    /// from there.
+    pub fn trigger_states(&self) -> Vec<TriggerState> {
+        let mut states: Vec<TriggerState> = self
+            .triggers
+            .calendar_trigger
+            .iter()
+            .map(|trigger| TriggerState {
+                enabled: trigger.enabled != Some(false),
+                end_boundary: trigger.end_boundary.clone(),
+            })
+            .collect();
+        for (path, content) in &self.unknown_elements {
+            let Some(element) = path.strip_prefix("Triggers/") else {
+                continue;
+            };
+            if element.contains('/') {
+                continue;
+            }
+            states.push(TriggerState {
+                enabled: child_text(content, "Enabled").as_deref().and_then(parse_bool) != Some(false),
+                end_boundary: child_text(content, "EndBoundary"),
+            });
+        }
+        states
+    }
+
+    /// Every trigger, for `trigger_warnings`, named by its path in the XML
+    /// such as `Triggers/CalendarTrigger[2]`. Triggers are compared by kind
+    /// and by every child but `Enabled` and `Id`, whitespace between
+    /// elements aside.
+    pub fn trigger_schedules(&self) -> Vec<TriggerSchedule> {
+        self.trigger_elements()
+            .into_iter()
+            .map(|(path, children)| {
+                let enabled = children.iter().find(|(name, _)| name == "Enabled");
+                let enabled = enabled.and_then(|(_, text)| parse_bool(text.trim()));
+                trigger_schedule(&path, enabled != Some(false), children)
+            })
+            .collect()
+    }
+
+    /// The enabled triggers as a `Schedule`. `TimeTrigger` and
+    /// `CalendarTrigger` elements are time triggers and the others count as
+    /// event triggers. A time trigger without a valid `StartBoundary`, or
+    /// whose `ScheduleBy...` element cannot be read, is left out. Zones on
+    /// the boundaries are ignored.
+    pub fn schedule(&self) -> Schedule {
+        let mut schedule = Schedule {
+            catches_up: self
+                .unknown_elements
+                .get("Settings/StartWhenAvailable")
+                .and_then(|text| parse_bool(text.trim()))
+                == Some(true),
+            ..Schedule::default()
+        };
+        for (path, children) in self.trigger_elements() {
+            let child = |name: &str| {
+                children.iter().find(|(child, _)| child == name).map(|(_, text)| text.trim())
+            };
+            if child("Enabled").and_then(parse_bool) == Some(false) {
+                continue;
+            }
+            let kind = path.strip_prefix("Triggers/").unwrap_or(&path);
+            let recurrence = match kind.split('[').next().unwrap_or(kind) {
+                "TimeTrigger" => Some(Recurrence::Once),
+                "CalendarTrigger" => calendar_recurrence(&children),
+                _ => {
+                    schedule.event_triggers += 1;
+                    continue;
+                }
+            };
+            let start = child("StartBoundary").and_then(date::local_seconds);
+            let (Some(recurrence), Some(start)) = (recurrence, start) else {
+                continue;
+            };
+            let seconds = |text: &str| {
+                date::parse_duration(text).map(|d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+            };
+            let repetition = child("Repetition").and_then(|xml| {
+                Some(Repetition {
+                    interval: seconds(&child_text(xml, "Interval")?)?,
+                    duration: child_text(xml, "Duration").and_then(|text| seconds(&text)),
+                })
+            });
+            schedule.time_triggers.push(TimeTrigger {
+                start,
+                end: child("EndBoundary").and_then(date::local_seconds),
+                recurrence,
+                repetition,
+                random_delay: child("RandomDelay").and_then(seconds).unwrap_or(0),
+            });
+        }
+        schedule
+    }
+
+    /// Every trigger, enabled or not, with its boundaries and repetition as
+    /// written. `TimeTrigger` and `CalendarTrigger` elements are summarized
+    /// by their `Recurrence`, and the others by the event they fire on.
+    pub fn trigger_details(&self) -> Vec<TriggerDetail> {
+        self.trigger_elements()
+            .into_iter()
+            .map(|(path, children)| {
+                let child = |name: &str| {
+                    let child = children.iter().find(|(child, _)| child == name);
+                    child.map(|(_, text)| text.trim().to_string())
+                };
+                let path = path.strip_prefix("Triggers/").unwrap_or(&path);
+                let kind = path.split('[').next().unwrap_or(path).to_string();
+                let summary = match kind.as_str() {
+                    "TimeTrigger" => Some(Recurrence::Once.to_string()),
+                    "CalendarTrigger" => calendar_recurrence(&children).map(|r| r.to_string()),
+                    "BootTrigger" => Some("at system start".to_string()),
+                    "LogonTrigger" => Some("at logon".to_string()),
+                    "IdleTrigger" => Some("on idle".to_string()),
+                    "RegistrationTrigger" => Some("at registration".to_string()),
+                    "EventTrigger" => Some("on an event log entry".to_string()),
+                    "SessionStateChangeTrigger" => Some("on a session change".to_string()),
+                    _ => None,
+                };
+                let repetition = child("Repetition");
+                let repetition =
+                    |name: &str| repetition.as_deref().and_then(|xml| child_text(xml, name));
+                TriggerDetail {
+                    start: child("StartBoundary"),
+                    end: child("EndBoundary"),
+                    enabled: child("Enabled").as_deref().and_then(parse_bool) != Some(false),
+                    summary: summary.unwrap_or_else(|| "unknown".to_string()),
+                    interval: repetition("Interval").map(|text| text.trim().to_string()),
+                    duration: repetition("Duration").map(|text| text.trim().to_string()),
+                    kind,
+                }
+            })
+            .collect()
+    }
+
+    /// Warnings for settings that are valid XML but make little sense, and
+    /// are more often hand-written than set in Task Scheduler: a trigger
+    /// that ends before it starts, a schedule with an empty list of days,
+    /// weeks or months, a repetition interval longer than the repetition
+    /// lasts, and an `ExecutionTimeLimit` of zero, which turns the limit
+    /// off.
+    pub fn schedule_warnings(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        for (path, children) in self.trigger_elements() {
+            let child = |name: &str| {
+                children.iter().find(|(child, _)| child == name).map(|(_, text)| text.as_str())
+            };
+            if let (Some(start), Some(end)) = (child("StartBoundary"), child("EndBoundary")) {
+                if date::compare_timestamps(end, start) == Some(Ordering::Less) {
+                    let (start, end) = (start.trim(), end.trim());
+                    let message = format!("{} ends at {} before it starts at {}", path, end, start);
+                    let warning = Warning::new(WarningCode::EndBeforeStart, message);
+                    warnings.push(warning.field(format!("{}/EndBoundary", path)));
+                }
+            }
+            for (schedule, lists) in SCHEDULE_LISTS {
+                for (list, content) in child(schedule).map(children_of).unwrap_or_default() {
+                    if lists.contains(&list.as_str()) && children_of(&content).is_empty() {
+                        let field = format!("{}/{}/{}", path, schedule, list);
+                        let message = format!("{} is empty", field);
+                        let warning = Warning::new(WarningCode::EmptyScheduleList, message);
+                        warnings.push(warning.field(field));
+                    }
+                }
+            }
+            if let Some(repetition) = child("Repetition") {
+                warnings.extend(repetition_warning(&path, repetition));
+            }
+            if let Some(limit) = child("ExecutionTimeLimit") {
+                warnings.extend(time_limit_warning(&format!("{}/ExecutionTimeLimit", path), limit));
+            }
+        }
+        if let Some(limit) = self.unknown_elements.get("Settings/ExecutionTimeLimit") {
+            warnings.extend(time_limit_warning("Settings/ExecutionTimeLimit", limit));
+        }
+        warnings
+    }
+
+    /// The path and children, names and contents, of every trigger: the
+    /// modeled calendar trigger with its unknown children, then those kept
+    /// in `unknown_elements`.
+    fn trigger_elements(&self) -> Vec<(String, Vec<(String, String)>)> {
+        let mut triggers = Vec::new();
+        if let Some(trigger) = &self.triggers.calendar_trigger {
+            let path = "Triggers/CalendarTrigger";
+            let modeled = [
+                ("StartBoundary", Some(trigger.start_boundary.clone())),
+                ("EndBoundary", trigger.end_boundary.clone()),
+                ("Enabled", trigger.enabled.map(|enabled| bool_str(enabled).to_string())),
+            ];
+            let mut children: Vec<(String, String)> = modeled
+                .into_iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value?)))
+                .collect();
+            for (child, content) in &self.unknown_elements {
+                let name = child.strip_prefix(path).and_then(|name| name.strip_prefix('/'));
+                if let Some(name) = name.filter(|name| !name.contains('/')) {
+                    children.push((name.to_string(), content.clone()));
+                }
+            }
+            triggers.push((path.to_string(), children));
+        }
+        for (path, content) in &self.unknown_elements {
+            let Some(element) = path.strip_prefix("Triggers/") else {
+                continue;
+            };
+            if !element.contains('/') {
+                triggers.push((path.clone(), children_of(content)));
+            }
+        }
+        triggers
+    }
+
+    /// How many triggers the task has, modeled or not.
+    pub fn trigger_count(&self) -> usize {
+        self.trigger_states().len()
+    }
+
+    /// The element name of each action, such as `Exec` or `ComHandler`: the
+    /// modeled `Exec` first, then those kept in `unknown_elements`, as
+    /// `Actions/ComHandler` or `Actions/Exec[2]`.
+    pub fn action_kinds(&self) -> Vec<&str> {
+        let mut kinds: Vec<&str> = self.actions.exec.iter().map(|_| "Exec").collect();
+        for path in self.unknown_elements.keys() {
+            let Some(element) = path.strip_prefix("Actions/") else {
+                continue;
+            };
+            if !element.contains('/') {
+                kinds.push(element.split('[').next().unwrap_or(element));
+            }
+        }
+        kinds
+    }
+
+    /// What each action runs, in the order of `action_kinds`.
+    pub fn action_targets(&self) -> Vec<ActionTarget> {
+        let mut targets: Vec<ActionTarget> = self
+            .actions
+            .exec
+            .iter()
+            .map(|exec| ActionTarget::Exec {
+                command: exec.command.clone(),
+                arguments: exec.arguments.clone(),
+            })
+            .collect();
+        for (path, content) in &self.unknown_elements {
+            let Some(element) = path.strip_prefix("Actions/") else {
+                continue;
+            };
+            if element.contains('/') {
+                continue;
+            }
+            targets.push(match element.split('[').next().unwrap_or(element) {
+                "Exec" => ActionTarget::Exec {
+                    command: child_text(content, "Command").unwrap_or_default(),
+                    arguments: child_text(content, "Arguments"),
+                },
+                "ComHandler" => ActionTarget::ComHandler {
+                    class_id: child_text(content, "ClassId").unwrap_or_default(),
+                },
+                kind => ActionTarget::Other(kind.to_string()),
+            });
+        }
+        targets
+    }
+
+    pub fn format_task(&self) -> String {
+        self.format_task_with(DateStyle::Classic)
+    }
+
+    /// `format_task` with the registration date and trigger boundaries in
+    /// `style`.
+    pub fn format_task_with(&self, style: DateStyle) -> String {
+        let mut result = String::new();
+
+        result.push_str(&format!("Author: {:?}\n", self.registration_info.author));
+        let date = self.registration_info.date.as_deref();
+        result.push_str(&format!("Date: {:?}\n", date.map(|d| format_timestamp(d, style))));
+        result.push_str(&format!("Description: {:?}\n", self.registration_info.description));
+        if let Some(sddl) = &self.registration_info.security_descriptor {
+            match SecurityDescriptor::parse(sddl) {
+                Ok(descriptor) => {
+                    result.push_str("SecurityDescriptor:\n");
+                    result.push_str(&descriptor.render(2));
+                }
+                Err(e) => {
+                    result.push_str(&format!("SecurityDescriptor: {} (could not decode: {})\n", sddl, e));
+                }
+            }
+        }
+
+        self.push_triggers(&mut result, style);
+        self.push_settings(&mut result);
+
+        if let Some(principal) = self.principal() {
+            result.push_str(&format!("UserId: {:?}\n", principal.user_id.as_deref().map(describe_account)));
+            result.push_str(&format!("RunLevel: {:?}\n", principal.run_level));
+        }
+
+        self.push_actions(&mut result);
+
+        result
+    }
+
+    fn push_triggers(&self, result: &mut String, style: DateStyle) {
+        if let Some(trigger) = &self.triggers.calendar_trigger {
+            let end = trigger.end_boundary.as_deref();
+            result.push_str(&format!("StartBoundary: {}\n", format_timestamp(&trigger.start_boundary, style)));
+            result.push_str(&format!("EndBoundary: {:?}\n", end.map(|e| format_timestamp(e, style))));
+            result.push_str(&format!("Enabled: {:?}\n", trigger.enabled));
+        }
+    }
+
+    fn push_settings(&self, result: &mut String) {
+        result.push_str("Settings:\n");
+        result.push_str(&format!("  Enabled: {:?}\n", self.settings.enabled));
+        result.push_str(&format!("  AllowStartIfOnBatteries: {:?}\n", self.settings.allow_start_if_on_batteries));
+        if let Some(hidden) = self.settings.hidden {
+            result.push_str(&format!("  Hidden: {}\n", hidden));
+        }
+    }
+
+    fn push_actions(&self, result: &mut String) {
+        if let Some(exec) = &self.actions.exec {
+            result.push_str(&format!("Command: {}\n", exec.command));
+            result.push_str(&format!("Arguments: {:?}\n", exec.arguments));
+        }
+    }
+}
+
+impl Fragment {
+    /// The lines `Task::format_task_with` prints for the fragment's section,
+    /// followed by every trigger or action in it, modeled or not, or by the
+    /// settings the model has no field for.
+    pub fn format_with(&self, style: DateStyle) -> String {
+        let task = &self.task;
+        let mut result = format!("Fragment: {}\n", self.root);
+        match self.section {
+            Section::Triggers => {
+                task.push_triggers(&mut result, style);
+                for (i, trigger) in task.trigger_details().iter().enumerate() {
+                    let mut line =
+                        format!("Trigger {}: {}, {}", i + 1, trigger.kind, trigger.summary);
+                    if let Some(start) = &trigger.start {
+                        line.push_str(&format!(", from {}", format_timestamp(start, style)));
+                    }
+                    if let Some(end) = &trigger.end {
+                        line.push_str(&format!(" until {}", format_timestamp(end, style)));
+                    }
+                    if let Some(interval) = &trigger.interval {
+                        line.push_str(&format!(", repeating every {}", interval));
+                        if let Some(duration) = &trigger.duration {
+                            line.push_str(&format!(" for {}", duration));
+                        }
+                    }
+                    if !trigger.enabled {
+                        line.push_str(", disabled");
+                    }
+                    result.push_str(&line);
+                    result.push('\n');
+                }
+            }
+            Section::Settings => {
+                task.push_settings(&mut result);
+                for (path, content) in &task.unknown_elements {
+                    let Some(name) = path.strip_prefix("Settings/") else {
+                        continue;
+                    };
+                    // Nested settings, such as `IdleSettings`, by their children.
+                    let children = children_of(content);
+                    let value = if children.is_empty() {
+                        content.trim().to_string()
+                    } else {
+                        let children = children.iter().map(|(child, text)| {
+                            format!("{}={}", child, text.trim())
+                        });
+                        children.collect::<Vec<_>>().join(", ")
+                    };
+                    result.push_str(&format!("  {}: {}\n", name, value));
+                }
+            }
+            Section::Actions => {
+                task.push_actions(&mut result);
+                for (i, action) in task.action_targets().iter().enumerate() {
+                    let action = match action {
+                        ActionTarget::Exec { command, arguments: Some(arguments) } => {
+                            format!("Exec {} {}", command, arguments)
+                        }
+                        ActionTarget::Exec { command, arguments: None } => {
+                            format!("Exec {}", command)
+                        }
+                        ActionTarget::ComHandler { class_id } => format!("ComHandler {}", class_id),
+                        ActionTarget::Other(kind) => kind.clone(),
+                    };
+                    result.push_str(&format!("Action {}: {}\n", i + 1, action));
+                }
+            }
+        }
+        result
+    }
+}
+
+/// The children each modeled element is parsed for, by path below `Task`.
+const KNOWN_ELEMENTS: &[(&str, &[&str])] = &[
+    ("", &["RegistrationInfo", "Triggers", "Settings", "Actions", "Principals"]),
+    ("RegistrationInfo", &["Author", "Date", "Description", "SecurityDescriptor"]),
+    ("Triggers", &["CalendarTrigger"]),
+    ("Triggers/CalendarTrigger", &["StartBoundary", "EndBoundary", "Enabled"]),
+    ("Settings", &["Enabled", "AllowStartIfOnBatteries", "Hidden"]),
+    ("Actions", &["Exec"]),
+    ("Actions/Exec", &["Command", "Arguments", "WorkingDirectory"]),
+    ("Principals", &["Principal"]),
+    ("Principals/Principal", &["UserId", "GroupId", "RunLevel"]),
+];
+
+/// Collects the children of modeled elements that the model does not know,
+/// for `Task::unknown_elements`. The first `<Task>` in `xml` is examined;
+/// anything after it, or after content that is not well formed, is not.
+fn unknown_elements(xml: &str) -> BTreeMap<String, String> {
+    let mut unknown = BTreeMap::new();
+    let mut modeled = HashSet::new();
+    let mut reader = Reader::from_str(xml);
+    // Element names below `Task`; `None` until the `Task` element starts.
+    let mut stack: Option<Vec<String>> = None;
+    loop {
+        let (element, empty) = match reader.read_event() {
+            Ok(Event::Start(element)) => (element, false),
+            Ok(Event::Empty(element)) => (element, true),
+            Ok(Event::End(_)) => match stack.as_mut().map(Vec::pop) {
+                Some(None) => break,
+                _ => continue,
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+        let Some(stack) = stack.as_mut() else {
+            if name == "Task" && !empty {
+                stack = Some(Vec::new());
+            }
+            continue;
+        };
+        let parent = stack.join("/");
+        let known = KNOWN_ELEMENTS
+            .iter()
+            .find(|(path, _)| *path == parent)
+            .map(|(_, children)| children.contains(&name.as_str()));
+        let path = if parent.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", parent, name)
+        };
+        // The model holds one of each known element, the first; a second
+        // `Exec` is kept like an unknown element.
+        let repeated = known == Some(true) && !modeled.insert(path.clone());
+        if known == Some(false) || repeated {
+            let content = if empty {
+                String::new()
+            } else {
+                match reader.read_text(element.name()) {
+                    Ok(content) => content.into_owned(),
+                    Err(_) => break,
+                }
+            };
+            let mut index = 1 + usize::from(repeated);
+            let mut key = if repeated { format!("{}[{}]", path, index) } else { path.clone() };
+            while unknown.contains_key(&key) {
+                index += 1;
+                key = format!("{}[{}]", path, index);
+            }
+            unknown.insert(key, content);
+        } else if !empty {
+            stack.push(name);
+        }
+    }
+    unknown
+}
+
+/// The text of the first `name` element at the top level of `xml`, a
+/// fragment that may hold several elements.
+fn child_text(xml: &str, name: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut depth = 0;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) if depth == 0 && element.local_name().as_ref() == name.as_bytes() => {
+                return reader.read_text(element.name()).ok().map(|text| text.trim().to_string());
+            }
+            Ok(Event::Start(_)) => depth += 1,
+            Ok(Event::End(_)) => depth -= 1,
+            Ok(Event::Eof) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    }
+}
+
+/// The days a `CalendarTrigger` with `children` fires on, from its
+/// `ScheduleByDay`, `ScheduleByWeek`, `ScheduleByMonth` or
+/// `ScheduleByMonthDayOfWeek`. Intervals default to 1, and a list that is
+/// left out to every day or month.
+fn calendar_recurrence(children: &[(String, String)]) -> Option<Recurrence> {
+    const DAYS: &[&str] =
+        &["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+    const MONTHS: &[&str] = &[
+        "January", "February", "March", "April", "May", "June", "July", "August", "September",
+        "October", "November", "December",
+    ];
+    let (name, xml) = children.iter().find(|(name, _)| name.starts_with("ScheduleBy"))?;
+    let interval = |name: &str| match child_text(xml, name) {
+        Some(text) => text.parse::<u32>().ok(),
+        None => Some(1),
+    };
+    // The names of the children of `name` as bits, every bit when absent.
+    let mask = |name: &str, names: &[&str]| match child_text(xml, name) {
+        Some(list) => children_of(&list)
+            .iter()
+            .filter_map(|(child, _)| names.iter().position(|name| name == child))
+            .fold(0, |mask, bit| mask | 1 << bit),
+        None => (1 << names.len()) - 1,
+    };
+    // The texts of the children of `name`.
+    let values = |name: &str| -> Vec<String> {
+        child_text(xml, name).map_or_else(Vec::new, |list| {
+            children_of(&list).into_iter().map(|(_, text)| text.trim().to_string()).collect()
+        })
+    };
+    let recurrence = match name.as_str() {
+        "ScheduleByDay" => Recurrence::Daily { every: interval("DaysInterval")? },
+        "ScheduleByWeek" => Recurrence::Weekly {
+            every: interval("WeeksInterval")?,
+            days: mask("DaysOfWeek", DAYS) as u8,
+        },
+        "ScheduleByMonth" => {
+            let days = values("DaysOfMonth");
+            Recurrence::MonthlyDate {
+                days: days
+                    .iter()
+                    .filter_map(|day| day.parse::<u32>().ok().filter(|day| (1..=31).contains(day)))
+                    .fold(0, |mask, day| mask | 1 << (day - 1)),
+                last_day: days.iter().any(|day| day == "Last"),
+                months: mask("Months", MONTHS) as u16,
+            }
+        }
+        "ScheduleByMonthDayOfWeek" => Recurrence::MonthlyDow {
+            // Weeks are numbered from 1, and `Last` comes fifth.
+            weeks: values("Weeks")
+                .iter()
+                .filter_map(|week| match week.as_str() {
+                    "Last" => Some(5),
+                    week => week.parse::<u32>().ok().filter(|week| (1..=4).contains(week)),
+                })
+                .fold(0, |mask, week| mask | 1 << (week - 1)),
+            days: mask("DaysOfWeek", DAYS) as u8,
+            months: mask("Months", MONTHS) as u16,
+        },
+        _ => return None,
+    };
+    Some(recurrence)
+}
+
+/// The `TriggerSchedule` for the trigger at `path` with `children`, the
+/// names and contents of its child elements.
+fn trigger_schedule(
+    path: &str,
+    enabled: bool,
+    mut children: Vec<(String, String)>,
+) -> TriggerSchedule {
+    let kind = path.strip_prefix("Triggers/").unwrap_or(path);
+    let kind = kind.split('[').next().unwrap_or(kind);
+    children.retain(|(name, _)| name != "Enabled" && name != "Id");
+    children.sort();
+    let mut schedule = kind.to_string();
+    for (name, content) in children {
+        let content = content.split_whitespace().collect::<Vec<_>>().join(" ").replace("> <", "><");
+        schedule.push_str(&format!("\n{}={}", name, content));
+    }
+    TriggerSchedule {
+        name: path.to_string(),
+        enabled,
+        schedule: schedule.into_bytes(),
+        offset: None,
+    }
+}
+
+/// The lists below each kind of schedule that name the days, weeks or
+/// months it runs on.
+const SCHEDULE_LISTS: &[(&str, &[&str])] = &[
+    ("ScheduleByWeek", &["DaysOfWeek"]),
+    ("ScheduleByMonth", &["DaysOfMonth", "Months"]),
+    ("ScheduleByMonthDayOfWeek", &["Weeks", "DaysOfWeek", "Months"]),
+];
+
+/// A `RepetitionExceedsDuration` warning for the trigger at `path` if the
+/// `Interval` of its `repetition` is longer than its `Duration`.
+fn repetition_warning(path: &str, repetition: &str) -> Option<Warning> {
+    let children = children_of(repetition);
+    let value = |name: &str| {
+        let text = children.iter().find(|(child, _)| child == name)?.1.trim();
+        Some((text, date::parse_duration(text)?))
+    };
+    let ((interval, every), (duration, lasting)) = (value("Interval")?, value("Duration")?);
+    if every <= lasting {
+        return None;
+    }
+    let message = format!(
+        "{} repeats every {}, longer than the {} the repetition lasts",
+        path, interval, duration
+    );
+    let warning = Warning::new(WarningCode::RepetitionExceedsDuration, message);
+    Some(warning.field(format!("{}/Repetition/Interval", path)))
+}
+
+/// A `NoTimeLimit` warning for the `ExecutionTimeLimit` at `field` if
+/// `limit` is zero.
+fn time_limit_warning(field: &str, limit: &str) -> Option<Warning> {
+    if date::parse_duration(limit)? != Duration::ZERO {
+        return None;
+    }
+    let message = format!("{} of {} turns the limit off", field, limit.trim());
+    Some(Warning::new(WarningCode::NoTimeLimit, message).field(field))
+}
+
+/// The name and content of each element at the top level of `xml`, a
+/// fragment that may hold several elements.
+fn children_of(xml: &str) -> Vec<(String, String)> {
+    let mut reader = Reader::from_str(xml);
+    let mut children = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                match reader.read_text(element.name()) {
+                    Ok(content) => children.push((name, content.into_owned())),
+                    Err(_) => break,
+                }
+            }
+            Ok(Event::Empty(element)) => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                children.push((name, String::new()));
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+    children
+}
+
+/// An `xs:boolean`.
+fn parse_bool(text: &str) -> Option<bool> {
+    match text {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// `data`, task XML in any encoding `Task::from_bytes` reads, decoded and
+/// re-indented by two spaces per level, for reading rather than parsing:
+/// whitespace around elements is dropped, and everything else is kept as
+/// written, comments included. Content that is not well formed fails with
+/// `Error::Xml`.
+pub fn indent_xml(data: &[u8]) -> Result<String, Error> {
+    let xml = decode(data)?;
+    let mut reader = Reader::from_str(&xml);
+    reader.config_mut().trim_text(true);
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(event) => writer.write_event(event).map_err(|e| Error::Xml(e.into()))?,
+            Err(e) => return Err(Error::Xml(e.into())),
+        }
+    }
+    let mut indented = String::from_utf8_lossy(&writer.into_inner()).into_owned();
+    indented.push('\n');
+    Ok(indented)
+}
+
+/// Decodes task XML to a string. A byte order mark selects the encoding;
+/// input without one is treated as UTF-16LE, unless it starts with an XML
+/// declaration in ASCII, as a UTF-8 document written without a byte order
+/// mark does.
+fn decode(data: &[u8]) -> Result<String, Error> {
+    let encoding = if data.starts_with(b"<?xml") { declared_encoding(data) } else { UTF_16LE };
+    let transcoded = DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .bom_override(true)
+        .build(data);
+    let mut buffer = String::new();
+    let mut reader = transcoded.take(1 << 16);
+    reader.read_to_string(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// The encoding the XML declaration at the start of `data` names, or UTF-8
+/// when it names none, or one ASCII text cannot be in.
+fn declared_encoding(data: &[u8]) -> &'static Encoding {
+    let declaration = &data[..data.len().min(256)];
+    let declaration = match declaration.windows(2).position(|pair| pair == b"?>") {
+        Some(end) => &declaration[..end],
+        None => declaration,
+    };
+    let label = declaration
+        .windows(9)
+        .position(|window| window == b"encoding=")
+        .and_then(|start| {
+            let rest = &declaration[start + 9..];
+            let quote = *rest.first()?;
+            let rest = rest.get(1..)?;
+            Some(&rest[..rest.iter().position(|&b| b == quote)?])
+        });
+    label
+        .and_then(Encoding::for_label)
+        .filter(|encoding| encoding.is_ascii_compatible())
+        .unwrap_or(UTF_8)
+}
+
+fn bool_str(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+fn push_element(xml: &mut String, indent: usize, name: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        xml.push_str(&format!(
+            "{:indent$}<{name}>{}</{name}>\n",
+            "",
+            escape(value),
+            indent = indent,
+            name = name
+        ));
+    }
+}
+
+/// `value` as XML character data. Characters XML 1.0 does not allow at all,
+/// such as the control characters a job's comment may hold, become U+FFFD.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}' => escaped.push('\u{FFFD}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,161 @@
+//! The Task Scheduler cache in the SOFTWARE hive, under
+//! `Microsoft\Windows NT\CurrentVersion\Schedule\TaskCache`. `Tasks` holds a
+//! key per task GUID with its path and a `DynamicInfo` blob of run times;
+//! `Tree` mirrors the folder layout of `System32\Tasks`, each task key naming
+//! its GUID in `Id`. Scheduled tasks are registered in both and written to
+//! disk, so a task missing from one of the three has been tampered with.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::date::format_unix;
+use crate::hive::{Hive, Key, REG_BINARY};
+use crate::Error;
+
+/// Where `TaskCache` sits in the SOFTWARE hive.
+pub const TASK_CACHE_KEY: &str = "Microsoft\\Windows NT\\CurrentVersion\\Schedule\\TaskCache";
+
+/// Seconds from 1601-01-01, where FILETIMEs count from, to the Unix epoch.
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+/// How deep `Tree` folders may nest before the hive is taken as corrupt.
+const MAX_TREE_DEPTH: usize = 64;
+
+/// A task as the registry knows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedTask {
+    /// The GUID, braces included, as `Tasks` and `Id` spell it.
+    pub id: String,
+    /// The task path, such as `\Microsoft\Windows\Defrag\ScheduledDefrag`:
+    /// the `Path` value under `Tasks`, or where the task sits under `Tree`
+    /// when `Tasks` has no entry for it.
+    pub path: String,
+    /// Has a key under `Tasks`.
+    pub in_tasks: bool,
+    /// Has a key under `Tree`.
+    pub in_tree: bool,
+    pub dynamic_info: Option<DynamicInfo>,
+}
+
+/// The `DynamicInfo` value under a `Tasks` key. Times are ISO 8601 in UTC,
+/// `None` when never set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicInfo {
+    pub registered: Option<String>,
+    pub last_run: Option<String>,
+    pub task_state: u32,
+    /// The `HRESULT` or exit code of the last run.
+    pub last_result: u32,
+    /// Only written from Windows 8 on, in the 36-byte form of the value.
+    pub last_success: Option<String>,
+}
+
+impl DynamicInfo {
+    /// Decodes the 28-byte (Windows 7) or 36-byte form; `None` when shorter.
+    pub fn parse(data: &[u8]) -> Option<DynamicInfo> {
+        let dword = |at: usize| {
+            let b = data.get(at..at + 4)?;
+            Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let time = |at: usize| {
+            let b = data.get(at..at + 8)?;
+            filetime(u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+        };
+        Some(DynamicInfo {
+            task_state: dword(20)?,
+            last_result: dword(24)?,
+            registered: time(4),
+            last_run: time(12),
+            last_success: time(28),
+        })
+    }
+}
+
+/// A FILETIME as ISO 8601 in UTC; `None` for zero or a time before 1970.
+pub fn filetime(value: u64) -> Option<String> {
+    (value / 10_000_000).checked_sub(FILETIME_UNIX_OFFSET).filter(|_| value != 0).map(format_unix)
+}
+
+/// Reads the tasks under `TaskCache` in `data`, a SOFTWARE hive, ordered by
+/// path. Fails if the hive is malformed or has no `TaskCache` key.
+pub fn read_task_cache(data: &[u8]) -> Result<Vec<CachedTask>, Error> {
+    let root = Hive::new(data)?.root()?;
+    let cache = root.descend(TASK_CACHE_KEY)?.ok_or(Error::InvalidHive {
+        offset: 0,
+        reason: "no TaskCache key",
+    })?;
+
+    let mut tasks: BTreeMap<String, CachedTask> = BTreeMap::new();
+    if let Some(list) = cache.subkey("Tasks")? {
+        for key in list.subkeys()? {
+            let id = key.name();
+            let path = key.value("Path")?.and_then(|v| v.string()).unwrap_or_default();
+            let dynamic_info = key
+                .value("DynamicInfo")?
+                .filter(|v| v.kind == REG_BINARY)
+                .and_then(|v| DynamicInfo::parse(&v.data));
+            tasks.insert(
+                id.to_ascii_uppercase(),
+                CachedTask {
+                    id,
+                    path,
+                    in_tasks: true,
+                    in_tree: false,
+                    dynamic_info,
+                },
+            );
+        }
+    }
+    if let Some(tree) = cache.subkey("Tree")? {
+        let mut entries = Vec::new();
+        walk_tree(&tree, "", 0, &mut HashSet::new(), &mut entries)?;
+        for (path, id) in entries {
+            let task = tasks.entry(id.to_ascii_uppercase()).or_insert_with(|| CachedTask {
+                id,
+                path: path.clone(),
+                in_tasks: false,
+                in_tree: false,
+                dynamic_info: None,
+            });
+            task.in_tree = true;
+            if task.path.is_empty() {
+                task.path = path;
+            }
+        }
+    }
+    let mut tasks: Vec<CachedTask> = tasks.into_values().collect();
+    tasks.sort_by_key(|task| task.path.to_lowercase());
+    Ok(tasks)
+}
+
+/// Collects the `(path, id)` of every key below `key` that has an `Id`
+/// value. Folders have none. `visited` holds the offsets of the keys already
+/// walked, so a key listed under two folders, or under itself, is an error
+/// rather than walked again.
+fn walk_tree(
+    key: &Key,
+    path: &str,
+    depth: usize,
+    visited: &mut HashSet<u32>,
+    out: &mut Vec<(String, String)>,
+) -> Result<(), Error> {
+    if depth > MAX_TREE_DEPTH {
+        return Err(Error::InvalidHive {
+            offset: 0,
+            reason: "TaskCache tree nested too deep",
+        });
+    }
+    for child in key.subkeys()? {
+        if !visited.insert(child.offset()) {
+            return Err(Error::InvalidHive {
+                offset: 0,
+                reason: "TaskCache tree key listed twice",
+            });
+        }
+        let path = format!("{}\\{}", path, child.name());
+        if let Some(id) = child.value("Id")?.and_then(|v| v.string()) {
+            out.push((path.clone(), id));
+        }
+        walk_tree(&child, &path, depth + 1, visited, out)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,267 @@
+//! Advisory notes on an artifact that parsed: things worth knowing about
+//! the file that do not keep it from being read. Records carry them as
+//! `warnings`.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "binary")]
+use crate::consts::{fixed, product, systemtime};
+#[cfg(any(feature = "binary", feature = "xml"))]
+use crate::date;
+use crate::{Error, ParsedArtifact};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum WarningCode {
+    /// The file's extension names the other format.
+    ExtensionMismatch,
+    /// A lenient parse got past what a strict one fails on.
+    RecoveredError,
+    /// Content after the last well-formed task of an XML file was skipped.
+    TrailingContent,
+    /// Less of a binary job than asked for belongs to a section the parser
+    /// knows, as when data was appended to it; see `job::coverage`.
+    LowCoverage,
+    /// A job's flags have bits no known flag uses.
+    UnknownFlagBits,
+    /// A date that is not a valid one, such as month 13 or 30 February.
+    InvalidDate,
+    /// A job's last run time stores a weekday other than the one its date
+    /// falls on.
+    WeekdayMismatch,
+    /// A task XML element the model has no field for.
+    UnknownElement,
+    /// Two triggers of a task are identical.
+    DuplicateTrigger,
+    /// Two triggers of a task fire on the same schedule, but one of them is
+    /// disabled.
+    ConflictingTrigger,
+    /// A trigger's `EndBoundary` is before its `StartBoundary`.
+    EndBeforeStart,
+    /// A trigger's schedule has an empty list of days, weeks or months,
+    /// such as `ScheduleByMonth` with no `Months`.
+    EmptyScheduleList,
+    /// A trigger's repetition interval is longer than the repetition
+    /// lasts, so it never repeats.
+    RepetitionExceedsDuration,
+    /// An `ExecutionTimeLimit` of zero, which lets the task run forever.
+    NoTimeLimit,
+    /// A job's product and file versions are not a pair Task Scheduler
+    /// writes; see `consts::product::FILE_VERSIONS`.
+    ImplausibleVersion,
+}
+
+impl WarningCode {
+    pub fn name(self) -> &'static str {
+        match self {
+            WarningCode::ExtensionMismatch => "extension_mismatch",
+            WarningCode::RecoveredError => "recovered_error",
+            WarningCode::TrailingContent => "trailing_content",
+            WarningCode::LowCoverage => "low_coverage",
+            WarningCode::UnknownFlagBits => "unknown_flag_bits",
+            WarningCode::InvalidDate => "invalid_date",
+            WarningCode::WeekdayMismatch => "weekday_mismatch",
+            WarningCode::UnknownElement => "unknown_element",
+            WarningCode::DuplicateTrigger => "duplicate_trigger",
+            WarningCode::ConflictingTrigger => "conflicting_trigger",
+            WarningCode::EndBeforeStart => "end_before_start",
+            WarningCode::EmptyScheduleList => "empty_schedule_list",
+            WarningCode::RepetitionExceedsDuration => "repetition_exceeds_duration",
+            WarningCode::NoTimeLimit => "no_time_limit",
+            WarningCode::ImplausibleVersion => "implausible_version",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Warning {
+    pub code: WarningCode,
+    pub message: String,
+    /// The byte offset in the file the warning is about, when known.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub offset: Option<usize>,
+    /// The field or element the warning is about, such as
+    /// `Settings/WakeToRun`.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub field: Option<String>,
+}
+
+impl Warning {
+    pub fn new(code: WarningCode, message: impl Into<String>) -> Warning {
+        Warning {
+            code,
+            message: message.into(),
+            offset: None,
+            field: None,
+        }
+    }
+
+    pub fn at(mut self, offset: usize) -> Warning {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn field(mut self, field: impl Into<String>) -> Warning {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// A `RecoveredError` for what a lenient parse read past.
+    pub fn recovered(error: &Error) -> Warning {
+        let mut warning = Warning::new(WarningCode::RecoveredError, error.to_string());
+        warning.offset = error.offset();
+        if let Error::StringLengthOutOfBounds { field, .. } = error {
+            warning.field = Some(field.to_string());
+        }
+        warning
+    }
+}
+
+/// What `trigger_warnings` compares of a trigger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriggerSchedule {
+    /// How warnings name the trigger, such as `trigger 2` for a job or
+    /// `Triggers/CalendarTrigger[2]` for task XML.
+    pub name: String,
+    pub enabled: bool,
+    /// Everything about when the trigger fires, only ever compared.
+    pub schedule: Vec<u8>,
+    /// The byte offset of the trigger in the file, when known.
+    pub offset: Option<usize>,
+}
+
+/// A `DuplicateTrigger` warning for each trigger identical to an earlier
+/// one, and a `ConflictingTrigger` warning for each with an earlier one's
+/// schedule but the other enabled state. Task Scheduler runs such tasks
+/// without complaint, but they are rarely written by hand and more often
+/// left behind by something that edited the task.
+pub fn trigger_warnings(triggers: &[TriggerSchedule]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    for (i, trigger) in triggers.iter().enumerate() {
+        let Some(first) = triggers[..i].iter().find(|t| t.schedule == trigger.schedule) else {
+            continue;
+        };
+        let warning = if first.enabled == trigger.enabled {
+            let message = format!("{} and {} are identical", first.name, trigger.name);
+            Warning::new(WarningCode::DuplicateTrigger, message)
+        } else {
+            let (on, off) = if first.enabled { (first, trigger) } else { (trigger, first) };
+            let message = format!(
+                "{} is enabled and {}, on the same schedule, is disabled",
+                on.name, off.name
+            );
+            Warning::new(WarningCode::ConflictingTrigger, message)
+        };
+        let mut warning = warning.field(trigger.name.as_str());
+        warning.offset = trigger.offset;
+        warnings.push(warning);
+    }
+    warnings
+}
+
+/// The warnings `artifact` raises by itself: implausible versions, unknown
+/// flag bits, invalid dates and wrong weekdays in a job, unknown elements,
+/// invalid dates, repeated triggers and the odd schedules of
+/// `Task::schedule_warnings` in task XML. Those about how the file was read,
+/// such as `ExtensionMismatch`, are up to the reader, and a job's triggers
+/// are only read from its bytes; see `Record::read_job_triggers`.
+#[cfg_attr(
+    not(any(feature = "binary", feature = "xml")),
+    allow(unused_mut, unused_variables, unreachable_code)
+)]
+pub fn artifact_warnings(artifact: &ParsedArtifact) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    match artifact {
+        #[cfg(feature = "binary")]
+        ParsedArtifact::Job(job) => {
+            if !product::is_plausible(job.product_info, job.file_version) {
+                let message = format!(
+                    "{} with file version {} is not a pair Task Scheduler writes",
+                    describe_product(job.product_info),
+                    job.file_version
+                );
+                let warning = Warning::new(WarningCode::ImplausibleVersion, message);
+                warnings.push(warning.at(fixed::PRODUCT_VERSION.offset).field("product_info"));
+            }
+            let unknown = job.unknown_flags();
+            if unknown != 0 {
+                let message = format!("unknown flag bits {:#x}", unknown);
+                let warning = Warning::new(WarningCode::UnknownFlagBits, message);
+                warnings.push(warning.at(fixed::FLAGS.offset).field("flags"));
+            }
+            let dates = [
+                ("last run time", &job.run_date, Some(fixed::LAST_RUN_TIME.offset)),
+                ("scheduled date", &job.scheduled_date, None),
+            ];
+            for (field, date, offset) in dates {
+                let text = date.format_iso();
+                if date.year != 0 && !date::is_iso_timestamp(&text) {
+                    let message = format!("invalid {} {}", field, text);
+                    let mut warning = Warning::new(WarningCode::InvalidDate, message).field(field);
+                    warning.offset = offset;
+                    warnings.push(warning);
+                }
+            }
+            if let Some((stored, actual)) = job.run_date.weekday_mismatch() {
+                let message = format!(
+                    "last run time {} was a {}, but its weekday field says {}",
+                    job.run_date.format_iso(),
+                    describe_weekday(actual),
+                    describe_weekday(stored)
+                );
+                let offset = fixed::LAST_RUN_TIME.offset + systemtime::DAY_OF_WEEK.offset;
+                let warning = Warning::new(WarningCode::WeekdayMismatch, message);
+                warnings.push(warning.at(offset).field("last run time"));
+            }
+        }
+        #[cfg(feature = "xml")]
+        ParsedArtifact::Task(task) => {
+            let trigger = task.triggers.calendar_trigger.as_ref();
+            let dates = [
+                ("RegistrationInfo/Date", task.registration_info.date.as_deref()),
+                (
+                    "Triggers/CalendarTrigger/StartBoundary",
+                    trigger.map(|t| t.start_boundary.as_str()),
+                ),
+                (
+                    "Triggers/CalendarTrigger/EndBoundary",
+                    trigger.and_then(|t| t.end_boundary.as_deref()),
+                ),
+            ];
+            for (field, text) in dates {
+                if let Some(text) = text.filter(|text| !date::is_timestamp(text)) {
+                    let message = format!("invalid {} {}", field, text);
+                    warnings.push(Warning::new(WarningCode::InvalidDate, message).field(field));
+                }
+            }
+            for path in task.unknown_elements.keys() {
+                let message = format!("unrecognized element {}", path);
+                warnings.push(Warning::new(WarningCode::UnknownElement, message).field(path.as_str()));
+            }
+            warnings.extend(trigger_warnings(&task.trigger_schedules()));
+            warnings.extend(task.schedule_warnings());
+        }
+        #[cfg(not(any(feature = "binary", feature = "xml")))]
+        _ => unreachable!(),
+    }
+    warnings
+}
+
+/// A stored weekday by name, 0 for Sunday, or by number past Saturday:
+/// `Friday`, `day 9`.
+#[cfg(feature = "binary")]
+pub fn describe_weekday(weekday: u16) -> String {
+    date::weekday_name(weekday).map_or_else(|| format!("day {}", weekday), str::to_string)
+}
+
+/// `version` as a product word and, if it is one, the release it names:
+/// `product version 0x0a00 (Windows 10)`.
+#[cfg(feature = "binary")]
+pub fn describe_product(version: u16) -> String {
+    let name = product::name(version).unwrap_or("unknown");
+    format!("product version {:#06x} ({})", version, name)
+}
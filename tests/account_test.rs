@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::account::{classify_user, localized_administrator};
+    use jobfileparser::UserKind;
+
+    fn assert_kind(users: &[&str], kind: UserKind) {
+        for user in users {
+            assert_eq!(classify_user(user), kind, "{:?}", user);
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_kind(&["", "  "], UserKind::Empty);
+    }
+
+    #[test]
+    fn test_well_known() {
+        assert_kind(
+            &[
+                "SYSTEM",
+                "NT AUTHORITY\\SYSTEM",
+                "nt authority\\network service",
+                "NT-AUTORITÄT\\SYSTEM",
+                "BUILTIN\\Administrators",
+                "Administrator",
+                ".\\administrator",
+                "CORP\\Administrator",
+                "Administrateur",
+                "PC\\ADMINISTRADOR",
+                "Администратор",
+                "Διαχειριστής",
+                "S-1-5-18",
+                "S-1-5-32-544",
+                "S-1-1-0",
+                "S-1-5-21-1004336348-1177238915-682003330-500",
+            ],
+            UserKind::WellKnown,
+        );
+    }
+
+    #[test]
+    fn test_local() {
+        assert_kind(&["alice", ".\\alice", "\\alice", "Ädmin"], UserKind::Local);
+    }
+
+    #[test]
+    fn test_domain() {
+        assert_kind(
+            &["CORP\\alice", "WORKSTATION7\\svc_backup", "S-1-5-21-1004336348-1177238915-682003330-1104"],
+            UserKind::Domain,
+        );
+    }
+
+    #[test]
+    fn test_upn() {
+        assert_kind(&["alice@corp.example.com", ".\\alice@corp.example.com"], UserKind::Upn);
+    }
+
+    #[test]
+    fn test_localized_administrator() {
+        assert_eq!(localized_administrator("Administrateur"), Some("French"));
+        assert_eq!(localized_administrator("PC01\\ADMINISTRATÖR"), Some("Swedish"));
+        assert_eq!(localized_administrator(" .\\администратор "), Some("Russian"));
+        assert_eq!(localized_administrator("ΔΙΑΧΕΙΡΙΣΤΉΣ"), Some("Greek"));
+        assert_eq!(localized_administrator("Administrator"), None);
+        assert_eq!(localized_administrator("Administrateurs"), None);
+        assert_eq!(localized_administrator(""), None);
+    }
+
+    #[cfg(all(feature = "binary", feature = "serde"))]
+    #[test]
+    fn test_record_and_finding() {
+        use jobfileparser::{detect, JobBuilder, ParsedArtifact, Record};
+        use std::path::Path;
+
+        let job = JobBuilder::new("a.exe").author("SERVEUR\\Administrateur").build().unwrap();
+        let findings = detect(&ParsedArtifact::Job(job.clone()));
+        assert_eq!(findings[0].rule, "localized-admin");
+        assert_eq!(
+            findings[0].description,
+            "created by the built-in administrator of a French system (SERVEUR\\Administrateur)"
+        );
+        let record = Record::new(Path::new("a.job"), Ok(ParsedArtifact::Job(job)));
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["user_kind"], "well_known");
+
+        let job = JobBuilder::new("a.exe").build().unwrap();
+        assert!(detect(&ParsedArtifact::Job(job.clone())).is_empty());
+        let record = Record::new(Path::new("a.job"), Ok(ParsedArtifact::Job(job)));
+        assert_eq!(serde_json::to_value(&record).unwrap()["user_kind"], "empty");
+    }
+}
@@ -0,0 +1,70 @@
+#![cfg(all(feature = "binary", feature = "xml"))]
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::{FileSecurity, JobBuilder, ParsedArtifact, Record};
+    use std::path::Path;
+
+    /// What `FileSecurity::read` gives for a file in `C:\Windows\Tasks`
+    /// that Users were given write access to.
+    fn writable() -> FileSecurity {
+        FileSecurity {
+            owner: Some("S-1-5-32-544".to_string()),
+            dacl: "D:AI(A;ID;FA;;;SY)(A;ID;FA;;;BA)(A;;0x1301bf;;;BU)".to_string(),
+        }
+    }
+
+    fn record(user: &str) -> Record {
+        let job = JobBuilder::new("C:\\Tools\\backup.exe").author(user).build().unwrap();
+        Record::new(Path::new("C:\\Windows\\Tasks\\Backup.job"), Ok(ParsedArtifact::Job(job)))
+    }
+
+    #[test]
+    fn test_writable_system_task() {
+        let mut record = record("NT AUTHORITY\\SYSTEM");
+        record.apply_file_security(&writable());
+        assert_eq!(record.owner(), Some("S-1-5-32-544"));
+        assert_eq!(record.writable_by_users(), Some(true));
+        assert_eq!(record.findings()[0].rule, "writable-system-task");
+        assert_eq!(
+            record.findings()[0].description,
+            "C:\\Windows\\Tasks\\Backup.job runs as SYSTEM, but BUILTIN\\Users can modify it"
+        );
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_value(&record).unwrap();
+            assert_eq!((&json["owner"], &json["writable_by_users"]), (&"S-1-5-32-544".into(), &true.into()));
+        }
+    }
+
+    #[test]
+    fn test_writable_user_task() {
+        // Users may change a task that runs as one of them anyway.
+        let mut record = record("CONTOSO\\alice");
+        record.apply_file_security(&writable());
+        assert_eq!(record.writable_by_users(), Some(true));
+        assert!(record.findings().is_empty());
+    }
+
+    #[test]
+    fn test_protected_file() {
+        let mut record = record("S-1-5-18");
+        let security = FileSecurity {
+            owner: Some("S-1-5-18".to_string()),
+            dacl: "D:P(A;;FA;;;SY)(A;;FA;;;BA)(A;;0x1200a9;;;BU)".to_string(),
+        };
+        record.apply_file_security(&security);
+        assert_eq!((record.owner(), record.writable_by_users()), (Some("S-1-5-18"), Some(false)));
+        assert!(record.findings().is_empty());
+    }
+
+    #[test]
+    fn test_undecodable_dacl() {
+        let mut record = record("SYSTEM");
+        record.apply_file_security(&FileSecurity { owner: None, dacl: "D:(A;;FA".to_string() });
+        assert_eq!((record.owner(), record.writable_by_users()), (None, None));
+        assert!(record.findings().is_empty());
+        #[cfg(feature = "serde")]
+        assert!(serde_json::to_value(&record).unwrap().get("writable_by_users").is_none());
+    }
+}
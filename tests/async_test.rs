@@ -0,0 +1,62 @@
+#![cfg(all(feature = "async", feature = "binary"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use futures::StreamExt;
+    use jobfileparser::{parse_file_async, scan_dir_stream, ParsedArtifact, ScanOptions};
+    use std::fs;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_parse_file_async() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.job");
+        fs::write(&path, job_bytes("a.exe")).unwrap();
+
+        let artifact = block_on(parse_file_async(&path)).unwrap();
+        assert!(matches!(artifact, ParsedArtifact::Job(ref job) if job.name == "a.exe"));
+    }
+
+    #[test]
+    fn test_scan_dir_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(dir.path().join("a.job"), job_bytes("a.exe")).unwrap();
+        fs::write(dir.path().join("b.xml"), b"not a task").unwrap();
+        fs::write(nested.join("c.job"), job_bytes("c.exe")).unwrap();
+
+        let options = ScanOptions { recursive: true, ..ScanOptions::default() };
+        let results: Vec<_> = block_on(scan_dir_stream(dir.path(), &options).collect());
+
+        let names: Vec<_> = results
+            .iter()
+            .map(|(path, _)| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["a.job", "b.xml", "c.job"]);
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_err());
+        assert!(results[2].1.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_dir_stream_does_not_follow_directory_links() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.job"), job_bytes("a.exe")).unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("loop")).unwrap();
+
+        let options = ScanOptions { recursive: true, ..ScanOptions::default() };
+        let results: Vec<_> = block_on(scan_dir_stream(dir.path(), &options).collect());
+        assert_eq!(results.len(), 1);
+    }
+}
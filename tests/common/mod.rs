@@ -0,0 +1,212 @@
+#![allow(dead_code)]
+
+/// Builds a minimal binary job whose layout matches what `Job::parse` reads.
+pub fn job_bytes(name: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&0x0601u16.to_le_bytes()); // product info
+    data.extend_from_slice(&1u16.to_le_bytes()); // file version
+    data.extend_from_slice(&[0x11; 16]); // uuid
+    data.extend_from_slice(&[0; 12]); // offsets, retry and idle settings
+    data.extend_from_slice(&0x20u32.to_le_bytes()); // priority
+    data.extend_from_slice(&3_600_000i32.to_le_bytes()); // max run time
+    data.extend_from_slice(&0i32.to_le_bytes()); // exit code
+    data.extend_from_slice(&0x41300i32.to_le_bytes()); // status
+    data.extend_from_slice(&0u32.to_le_bytes()); // flags
+    for word in [2024u16, 3, 1, 4, 2, 13, 0, 0] {
+        data.extend_from_slice(&word.to_le_bytes()); // last run time
+    }
+    data.extend_from_slice(&0u16.to_le_bytes()); // running instance count
+    for value in [name, "", "", "", ""] {
+        push_string(&mut data, value);
+    }
+    data
+}
+
+fn push_string(data: &mut Vec<u8>, value: &str) {
+    let units: Vec<u16> = value.encode_utf16().chain(std::iter::once(0)).collect();
+    data.extend_from_slice(&(units.len() as u16).to_le_bytes());
+    for unit in units {
+        data.extend_from_slice(&unit.to_le_bytes());
+    }
+}
+
+/// The 48 bytes of a job trigger with `fields` set, and its size, the rest
+/// zero.
+pub fn trigger_bytes(fields: &[(jobfileparser::consts::Field, u32)]) -> Vec<u8> {
+    use jobfileparser::consts::trigger;
+
+    let mut data = vec![0; trigger::LENGTH];
+    for &(field, value) in [(trigger::TRIGGER_SIZE, trigger::LENGTH as u32)].iter().chain(fields) {
+        data[field.offset..][..field.size].copy_from_slice(&value.to_le_bytes()[..field.size]);
+    }
+    data
+}
+
+/// `job`, as `Job::to_bytes` writes it, with `triggers` in place of its
+/// empty trigger section.
+pub fn with_triggers(mut job: Vec<u8>, triggers: &[Vec<u8>]) -> Vec<u8> {
+    use jobfileparser::consts::fixed;
+
+    let at = u16::from_le_bytes([job[fixed::TRIGGER_OFFSET.offset], job[fixed::TRIGGER_OFFSET.offset + 1]]) as usize;
+    job[at..at + 2].copy_from_slice(&(triggers.len() as u16).to_le_bytes());
+    job.extend(triggers.concat());
+    job
+}
+
+/// A registry key for `hive_bytes`: its name, its values as name, type and
+/// data, and its subkeys.
+#[derive(Default)]
+pub struct HiveKey {
+    pub name: String,
+    pub values: Vec<(String, u32, Vec<u8>)>,
+    pub subkeys: Vec<HiveKey>,
+}
+
+impl HiveKey {
+    pub fn new(name: &str) -> HiveKey {
+        HiveKey {
+            name: name.to_string(),
+            ..HiveKey::default()
+        }
+    }
+
+    /// The subkey called `name`, added if missing.
+    pub fn child(&mut self, name: &str) -> &mut HiveKey {
+        let index = match self.subkeys.iter().position(|key| key.name == name) {
+            Some(index) => index,
+            None => {
+                self.subkeys.push(HiveKey::new(name));
+                self.subkeys.len() - 1
+            }
+        };
+        &mut self.subkeys[index]
+    }
+
+    /// Follows or creates `path`, a `\`-separated list of subkey names.
+    pub fn path(&mut self, path: &str) -> &mut HiveKey {
+        path.split('\\').filter(|name| !name.is_empty()).fold(self, |key, name| key.child(name))
+    }
+
+    pub fn value(&mut self, name: &str, kind: u32, data: Vec<u8>) -> &mut HiveKey {
+        self.values.push((name.to_string(), kind, data));
+        self
+    }
+}
+
+/// `text` as `REG_SZ` data: UTF-16LE with a terminating NUL.
+pub fn reg_sz(text: &str) -> Vec<u8> {
+    text.encode_utf16().chain(std::iter::once(0)).flat_map(u16::to_le_bytes).collect()
+}
+
+/// Lays out `root` as a `regf` hive with a single bin, subkeys in `lh`
+/// lists and names stored as Latin-1.
+pub fn hive_bytes(root: &HiveKey) -> Vec<u8> {
+    let mut bins = b"hbin".to_vec();
+    bins.resize(0x20, 0);
+    let root_offset = push_key(&mut bins, root);
+    let size = bins.len().next_multiple_of(0x1000);
+    bins.resize(size, 0);
+    bins[8..12].copy_from_slice(&(size as u32).to_le_bytes());
+
+    let mut data = b"regf".to_vec();
+    data.resize(0x1000, 0);
+    data[0x24..0x28].copy_from_slice(&root_offset.to_le_bytes());
+    data[0x28..0x2C].copy_from_slice(&(size as u32).to_le_bytes());
+    data.extend_from_slice(&bins);
+    data
+}
+
+fn push_cell(bins: &mut Vec<u8>, data: &[u8]) -> u32 {
+    let offset = bins.len();
+    let size = (4 + data.len()).next_multiple_of(8);
+    bins.extend_from_slice(&(-(size as i32)).to_le_bytes());
+    bins.extend_from_slice(data);
+    bins.resize(offset + size, 0);
+    offset as u32
+}
+
+fn push_key(bins: &mut Vec<u8>, key: &HiveKey) -> u32 {
+    let subkeys: Vec<u32> = key.subkeys.iter().map(|subkey| push_key(bins, subkey)).collect();
+    let mut list = b"lh".to_vec();
+    list.extend_from_slice(&(subkeys.len() as u16).to_le_bytes());
+    for offset in &subkeys {
+        list.extend_from_slice(&offset.to_le_bytes());
+        list.extend_from_slice(&0u32.to_le_bytes()); // name hash, unchecked
+    }
+    let subkey_list = if subkeys.is_empty() { u32::MAX } else { push_cell(bins, &list) };
+
+    let values: Vec<u32> = key.values.iter().map(|value| push_value(bins, value)).collect();
+    let value_list = if values.is_empty() {
+        u32::MAX
+    } else {
+        push_cell(bins, &values.iter().flat_map(|offset| offset.to_le_bytes()).collect::<Vec<u8>>())
+    };
+
+    let mut nk = vec![0; 0x4C];
+    nk[0..2].copy_from_slice(b"nk");
+    nk[2..4].copy_from_slice(&0x20u16.to_le_bytes()); // KEY_COMP_NAME
+    nk[0x14..0x18].copy_from_slice(&(subkeys.len() as u32).to_le_bytes());
+    nk[0x1C..0x20].copy_from_slice(&subkey_list.to_le_bytes());
+    nk[0x24..0x28].copy_from_slice(&(values.len() as u32).to_le_bytes());
+    nk[0x28..0x2C].copy_from_slice(&value_list.to_le_bytes());
+    nk[0x48..0x4A].copy_from_slice(&(key.name.len() as u16).to_le_bytes());
+    nk.extend_from_slice(key.name.as_bytes());
+    push_cell(bins, &nk)
+}
+
+fn push_value(bins: &mut Vec<u8>, (name, kind, data): &(String, u32, Vec<u8>)) -> u32 {
+    let mut vk = vec![0; 0x14];
+    vk[0..2].copy_from_slice(b"vk");
+    vk[2..4].copy_from_slice(&(name.len() as u16).to_le_bytes());
+    if data.len() <= 4 {
+        // Small data is kept in the offset field.
+        vk[4..8].copy_from_slice(&(data.len() as u32 | 0x8000_0000).to_le_bytes());
+        vk[8..8 + data.len()].copy_from_slice(data);
+    } else {
+        let cell = push_cell(bins, data);
+        vk[4..8].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        vk[8..12].copy_from_slice(&cell.to_le_bytes());
+    }
+    vk[12..16].copy_from_slice(&kind.to_le_bytes());
+    vk[16..18].copy_from_slice(&1u16.to_le_bytes()); // VALUE_COMP_NAME
+    vk.extend_from_slice(name.as_bytes());
+    push_cell(bins, &vk)
+}
+
+/// A task for `task_cache_hive`: its GUID, the path under `Tasks` (if
+/// listed there), the path under `Tree` (if listed there) and its
+/// `DynamicInfo`.
+pub type CacheEntry<'a> = (&'a str, Option<&'a str>, Option<&'a str>, Option<Vec<u8>>);
+
+/// A SOFTWARE hive whose TaskCache lists `tasks`.
+pub fn task_cache_hive(tasks: &[CacheEntry]) -> Vec<u8> {
+    let mut root = HiveKey::new("ROOT");
+    let cache = root.path("Microsoft\\Windows NT\\CurrentVersion\\Schedule\\TaskCache");
+    cache.child("Tasks");
+    cache.child("Tree");
+    for (id, path, tree, dynamic_info) in tasks {
+        if let Some(path) = path {
+            let key = cache.child("Tasks").child(id);
+            key.value("Path", 1, reg_sz(path));
+            if let Some(info) = dynamic_info {
+                key.value("DynamicInfo", 3, info.clone());
+            }
+        }
+        if let Some(tree) = tree {
+            let key = cache.child("Tree").path(tree);
+            key.value("Id", 1, reg_sz(id)).value("Index", 4, vec![3, 0, 0, 0]);
+        }
+    }
+    hive_bytes(&root)
+}
+
+/// A 36-byte `DynamicInfo` with the given FILETIMEs.
+pub fn dynamic_info(registered: u64, last_run: u64, last_success: u64) -> Vec<u8> {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&registered.to_le_bytes());
+    data.extend_from_slice(&last_run.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes()); // task state
+    data.extend_from_slice(&0x41303u32.to_le_bytes()); // last result
+    data.extend_from_slice(&last_success.to_le_bytes());
+    data
+}
@@ -0,0 +1,42 @@
+#![cfg(all(feature = "binary", feature = "xml", feature = "fs", feature = "cli"))]
+
+#[cfg(test)]
+mod tests {
+    use assert_cmd::Command;
+
+    fn completions(shell: &str) -> String {
+        let output = Command::cargo_bin("jobfileparser")
+            .unwrap()
+            .args(["completions", shell])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_every_shell_lists_output_formats() {
+        for shell in ["bash", "zsh", "fish", "powershell"] {
+            let script = completions(shell);
+            assert!(script.contains("output-format"), "{}", shell);
+            assert!(script.contains("jsonl"), "{}", shell);
+        }
+    }
+
+    #[test]
+    fn test_powershell_completes_option_values() {
+        let script = completions("powershell");
+        assert!(script.contains("'--output-format' = @('text', 'json', 'jsonl')"));
+        assert!(script.contains("'-o' = @('text', 'json', 'jsonl')"));
+        assert!(script.contains("Register-ArgumentCompleter -Native -CommandName 'jobfileparser'"));
+    }
+
+    #[test]
+    fn test_unknown_shell_is_rejected() {
+        Command::cargo_bin("jobfileparser")
+            .unwrap()
+            .args(["completions", "tcsh"])
+            .assert()
+            .code(2);
+    }
+}
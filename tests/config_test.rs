@@ -0,0 +1,143 @@
+#![cfg(all(feature = "binary", feature = "xml", feature = "fs", feature = "cli"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use assert_cmd::Command;
+    use std::fs;
+    use tempfile::{tempdir, TempDir};
+
+    struct Setup {
+        jobs: TempDir,
+        config_home: TempDir,
+    }
+
+    impl Setup {
+        /// A directory holding one job that `--detect` flags, and an XDG
+        /// config home whose config file contains `config`.
+        fn new(config: &str) -> Setup {
+            let jobs = tempdir().unwrap();
+            fs::write(jobs.path().join("a.job"), job_bytes("cmd.exe")).unwrap();
+            let config_home = tempdir().unwrap();
+            let dir = config_home.path().join("jobfileparser");
+            fs::create_dir(&dir).unwrap();
+            fs::write(dir.join("config.toml"), config).unwrap();
+            Setup { jobs, config_home }
+        }
+
+        fn cmd(&self) -> Command {
+            let mut cmd = Command::cargo_bin("jobfileparser").unwrap();
+            cmd.env("XDG_CONFIG_HOME", self.config_home.path());
+            for (key, _) in std::env::vars() {
+                if key.starts_with("JOBPARSER_") {
+                    cmd.env_remove(key);
+                }
+            }
+            cmd.args(["scan", self.jobs.path().to_str().unwrap()]);
+            cmd
+        }
+    }
+
+    fn stdout_of(cmd: &mut Command) -> String {
+        String::from_utf8(cmd.output().unwrap().stdout).unwrap()
+    }
+
+    #[test]
+    fn test_built_in_default() {
+        let setup = Setup::new("");
+        assert!(stdout_of(&mut setup.cmd()).starts_with("****"));
+        setup.cmd().assert().code(0);
+    }
+
+    #[test]
+    fn test_file_beats_default() {
+        let setup = Setup::new("output_format = \"jsonl\"\ndetect = true\n");
+        assert!(stdout_of(&mut setup.cmd()).starts_with("{"));
+        setup.cmd().assert().code(3);
+    }
+
+    #[test]
+    fn test_env_beats_file() {
+        let setup = Setup::new("output_format = \"jsonl\"\ndetect = true\n");
+        let mut cmd = setup.cmd();
+        cmd.env("JOBPARSER_OUTPUT_FORMAT", "json");
+        assert!(stdout_of(&mut cmd).starts_with("["));
+
+        setup.cmd().env("JOBPARSER_DETECT", "false").assert().code(0);
+    }
+
+    #[test]
+    fn test_flag_beats_env() {
+        let setup = Setup::new("output_format = \"jsonl\"\n");
+        let mut cmd = setup.cmd();
+        cmd.env("JOBPARSER_OUTPUT_FORMAT", "json").args(["-o", "text"]);
+        assert!(stdout_of(&mut cmd).starts_with("****"));
+
+        setup
+            .cmd()
+            .env("JOBPARSER_DETECT", "0")
+            .arg("--detect")
+            .assert()
+            .code(3);
+    }
+
+    #[test]
+    fn test_negated_flag_beats_file() {
+        let setup = Setup::new("detect = true\nfail_on_findings = true\n");
+        setup.cmd().assert().code(1);
+        setup.cmd().arg("--no-fail-on-findings").assert().code(3);
+        setup.cmd().arg("--no-detect").assert().code(0);
+        setup.cmd().args(["--detect", "--no-detect"]).assert().code(0);
+        setup.cmd().args(["--no-detect", "--detect"]).assert().code(1);
+
+        let setup = Setup::new("detect = true\nexit_zero_always = true\n");
+        setup.cmd().assert().code(0);
+        setup.cmd().arg("--no-exit-zero-always").assert().code(3);
+    }
+
+    #[test]
+    fn test_timezone_and_fields_from_file() {
+        let setup = Setup::new("timezone = \"+02:00\"\nfields = [\"absolute_path\"]\n");
+        setup.cmd().args(["-o", "tln"]).assert().code(0);
+        let jsonl = stdout_of(setup.cmd().args(["-o", "jsonl"]));
+        assert!(jsonl.contains("\"absolute_path\":"));
+        // Formats that take neither setting ignore them.
+        setup.cmd().assert().code(0);
+
+        let setup = Setup::new("");
+        setup.cmd().args(["-o", "tln"]).assert().code(2);
+        let mut cmd = setup.cmd();
+        cmd.env("JOBPARSER_TIMEZONE", "UTC").env("JOBPARSER_FIELDS", "absolute_path");
+        assert!(stdout_of(cmd.args(["-o", "tln"])).contains('|'));
+        let mut cmd = setup.cmd();
+        cmd.env("JOBPARSER_FIELDS", "absolute_path");
+        assert!(stdout_of(cmd.args(["-o", "jsonl"])).contains("\"absolute_path\":"));
+
+        let setup = Setup::new("fields = [\"size\"]\n");
+        setup.cmd().args(["-o", "jsonl"]).assert().code(2);
+    }
+
+    #[test]
+    fn test_explicit_config_path() {
+        let setup = Setup::new("");
+        let other = tempdir().unwrap();
+        let path = other.path().join("engagement.toml");
+        fs::write(&path, "exit_zero_always = true\ndetect = true\n").unwrap();
+        setup.cmd().args(["--config", path.to_str().unwrap()]).assert().code(0);
+
+        let missing = other.path().join("missing.toml");
+        setup.cmd().args(["--config", missing.to_str().unwrap()]).assert().code(2);
+    }
+
+    #[test]
+    fn test_unknown_and_mistyped_keys_warn() {
+        let setup = Setup::new("colour = \"always\"\ndetect = \"yes\"\n");
+        let output = setup.cmd().output().unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("unknown config key `colour`"));
+        assert!(stderr.contains("config key `detect` has the wrong type"));
+        assert_eq!(output.status.code(), Some(0));
+    }
+}
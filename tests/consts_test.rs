@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::consts::{self, fixed, signature, systemtime, trigger, variable, Field};
+
+    /// Checks that `fields` are contiguous from offset 0 and end at `length`.
+    fn assert_packed(fields: &[(&str, Field)], length: usize) {
+        let mut end = 0;
+        for (name, field) in fields {
+            assert_eq!(field.offset, end, "{} does not follow the previous field", name);
+            end = field.end();
+        }
+        assert_eq!(end, length);
+        assert_eq!(fields.iter().map(|(_, f)| f.size).sum::<usize>(), length);
+    }
+
+    #[test]
+    fn test_sections_are_packed() {
+        assert_packed(&fixed::FIELDS, fixed::LENGTH);
+        assert_packed(&systemtime::FIELDS, systemtime::LENGTH);
+        assert_packed(&trigger::FIELDS, trigger::LENGTH);
+        assert_packed(&signature::FIELDS, signature::LENGTH);
+        assert_eq!(fixed::LAST_RUN_TIME.size, systemtime::LENGTH);
+    }
+
+    #[test]
+    fn test_variable_section_follows_fixed() {
+        assert_eq!(variable::RUNNING_INSTANCE_COUNT.offset, fixed::LENGTH);
+        assert_eq!(variable::APPLICATION_NAME, variable::RUNNING_INSTANCE_COUNT.end());
+    }
+
+    #[test]
+    fn test_coded_values() {
+        assert_eq!(consts::status::EVENT_TRIGGER - consts::status::READY, 8);
+        assert_eq!(consts::product::VERSIONS.len(), 8);
+        assert_eq!(trigger::TriggerType::from_value(7), Some(trigger::TriggerType::AtLogon));
+        assert_eq!(trigger::TriggerType::from_value(8), None);
+        assert_eq!(trigger::TriggerType::AtSystemStart as u32, 6);
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_builder_writes_the_layout() {
+        let data = jobfileparser::JobBuilder::new("a.exe").build().unwrap().to_bytes();
+        let word = |field: Field| u16::from_le_bytes([data[field.offset], data[field.offset + 1]]);
+        assert_eq!(word(fixed::PRODUCT_VERSION), consts::product::WINDOWS_7);
+        assert_eq!(word(fixed::FILE_VERSION), consts::FILE_VERSION);
+        assert_eq!(word(fixed::APP_NAME_LEN_OFFSET) as usize, variable::APPLICATION_NAME);
+    }
+}
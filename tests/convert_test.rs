@@ -0,0 +1,168 @@
+#![cfg(all(feature = "binary", feature = "xml"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use jobfileparser::consts::{fixed, flags, priority};
+    use jobfileparser::convert::{
+        job_to_task, job_to_task_losses, job_trigger_count, task_to_job, task_to_job_losses,
+    };
+    use jobfileparser::{carve, Job, JobBuilder, JobDate, ParsedArtifact, Task};
+
+    #[test]
+    fn test_builder_round_trips_through_bytes() {
+        let built = JobBuilder::new("C:\\Tools\\sync.exe")
+            .parameters("--all")
+            .working_directory("C:\\Tools")
+            .author("EXAMPLE\\ops")
+            .comment("Nächtlicher Abgleich")
+            .flags(0x4)
+            .uuid(&[7; 16])
+            .build().unwrap();
+
+        let parsed = Job::parse(&built.to_bytes()).unwrap();
+        assert_eq!(parsed.name, built.name);
+        assert_eq!(parsed.parameters, "--all");
+        assert_eq!(parsed.working_directory, "C:\\Tools");
+        assert_eq!(parsed.user, "EXAMPLE\\ops");
+        assert_eq!(parsed.comment, "Nächtlicher Abgleich");
+        assert_eq!(parsed.flags, 0x4);
+        assert_eq!(parsed.status, 0x41303);
+        assert_eq!(parsed.uuid.format_uuid(), built.uuid.format_uuid());
+    }
+
+    #[test]
+    fn test_xml_round_trip_escapes_text() {
+        let job = JobBuilder::new("cmd.exe")
+            .parameters("/c \"a & b\" > <out>")
+            .build().unwrap();
+        let task = job_to_task(&job);
+
+        let reparsed = Task::from_bytes(&task.to_utf16_bytes()).unwrap();
+        let exec = reparsed.actions.exec.unwrap();
+        assert_eq!(exec.command, "cmd.exe");
+        assert_eq!(exec.arguments.as_deref(), Some("/c \"a & b\" > <out>"));
+        assert_eq!(reparsed.settings.enabled, Some(true));
+    }
+
+    #[test]
+    fn test_xml_replaces_characters_xml_forbids() {
+        let job = JobBuilder::new("a.exe")
+            .comment("a\u{1}b\u{8}c\td\u{FFFF}")
+            .parameters("x\u{0}y")
+            .build().unwrap();
+        let xml = job_to_task(&job).to_xml();
+        let forbidden = |c: char| c.is_control() && !matches!(c, '\t' | '\n' | '\r');
+        assert!(!xml.contains(forbidden) && !xml.contains('\u{FFFF}'), "{:?}", xml);
+
+        let reparsed = Task::from_xml(&xml).unwrap();
+        let description = reparsed.registration_info.description.as_deref();
+        assert_eq!(description, Some("a\u{FFFD}b\u{FFFD}c\td\u{FFFD}"));
+        let arguments = reparsed.actions.exec.unwrap().arguments;
+        assert_eq!(arguments.as_deref(), Some("x\u{FFFD}y"));
+    }
+
+    #[test]
+    fn test_task_to_job_maps_disabled_state() {
+        let task = Task::from_xml(
+            "<Task><RegistrationInfo><Author>me</Author></RegistrationInfo><Triggers/>\
+             <Settings><Enabled>false</Enabled></Settings>\
+             <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+        )
+        .unwrap();
+        let job = task_to_job(&task).unwrap();
+        assert_eq!(job.name, "a.exe");
+        assert_eq!(job.user, "me");
+        assert_eq!(job.flags & 0x4, 0x4);
+    }
+
+    #[test]
+    fn test_job_to_task_losses() {
+        let plain = JobBuilder::new("a.exe").author("me").flags(flags::HIDDEN).build().unwrap();
+        assert_eq!(job_trigger_count(&plain.to_bytes()), Some(0));
+        assert!(job_to_task_losses(&plain, &plain.to_bytes()).is_empty());
+
+        let mut last_run = [0; 16];
+        last_run[..2].copy_from_slice(&2024u16.to_le_bytes());
+        let job = JobBuilder::new("a.exe")
+            .priority(priority::HIGH)
+            .exit_code(1)
+            .run_date(JobDate::new(&last_run, false))
+            .flags(flags::DISABLED | flags::INTERACTIVE | flags::RUN_ONLY_IF_LOGGED_ON)
+            .build().unwrap();
+        let mut data = job.to_bytes();
+        let count_at = u16::from_le_bytes([data[fixed::TRIGGER_OFFSET.offset], data[fixed::TRIGGER_OFFSET.offset + 1]]);
+        data[count_at as usize] = 2;
+        assert_eq!(job_trigger_count(&data), Some(2));
+        assert_eq!(
+            job_to_task_losses(&job, &data),
+            [
+                "2 triggers not converted",
+                "priority dropped",
+                "exit code dropped",
+                "last run time dropped",
+                "flag TASK_FLAG_INTERACTIVE dropped",
+                "flag TASK_FLAG_RUN_ONLY_IF_LOGGED_ON dropped",
+            ]
+        );
+        assert_eq!(job_to_task_losses(&job, &data[..20])[0], "trigger section unreadable");
+    }
+
+    #[test]
+    fn test_task_to_job_losses() {
+        let plain = Task::from_xml(
+            "<Task><RegistrationInfo><Author>me</Author></RegistrationInfo><Triggers/><Settings/>\
+             <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+        )
+        .unwrap();
+        assert!(task_to_job_losses(&plain).is_empty());
+
+        let task = Task::from_xml(
+            "<Task><RegistrationInfo><Date>2024-01-01T00:00:00</Date>\
+             <SecurityDescriptor>D:(A;;FA;;;SY)</SecurityDescriptor></RegistrationInfo>\
+             <Triggers><CalendarTrigger><StartBoundary>2024-01-01T00:00:00</StartBoundary></CalendarTrigger>\
+             <LogonTrigger/><BootTrigger/></Triggers><Settings><WakeToRun>true</WakeToRun></Settings>\
+             <Actions><Exec><Command>a.exe</Command></Exec></Actions>\
+             <Principals><Principal><UserId>S-1-5-18</UserId></Principal></Principals></Task>",
+        )
+        .unwrap();
+        assert_eq!(
+            task_to_job_losses(&task),
+            [
+                "3 triggers not converted",
+                "principal dropped",
+                "registration date dropped",
+                "security descriptor dropped",
+                "Settings/WakeToRun dropped",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_carve_reports_offsets_and_lengths() {
+        let job = job_bytes("a.exe");
+        let mut data = vec![0; 17];
+        data.extend_from_slice(&job);
+        let xml: Vec<u8> = "<Task><RegistrationInfo/><Triggers/><Settings/><Actions/></Task>"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        data.extend_from_slice(&[0xFF; 9]);
+        data.extend_from_slice(&xml);
+
+        let found = carve(&data);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].offset, found[0].len), (17, job.len()));
+        assert!(matches!(found[0].artifact, ParsedArtifact::Job(ref job) if job.name == "a.exe"));
+        assert_eq!((found[1].offset, found[1].len), (17 + job.len() + 9, xml.len()));
+        assert!(matches!(found[1].artifact, ParsedArtifact::Task(_)));
+    }
+
+    #[test]
+    fn test_carve_ignores_noise() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        assert!(carve(&data).is_empty());
+    }
+}
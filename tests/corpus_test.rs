@@ -0,0 +1,104 @@
+#![cfg(all(feature = "corpus", feature = "binary", feature = "xml", feature = "fs"))]
+
+mod common;
+// Not part of `common`, whose other users build without `binary` or `xml`.
+#[path = "common/corpus.rs"]
+mod corpus;
+
+#[cfg(test)]
+mod tests {
+    use super::corpus::{corpus, Set};
+    use jobfileparser::detect::PathRules;
+    use jobfileparser::{parse_bytes, Record};
+    use std::collections::BTreeSet;
+    use std::env;
+    use std::fs;
+    use std::panic;
+    use std::path::{Path, PathBuf};
+
+    fn root() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+    }
+
+    /// Every file below `dir`, relative to it and with `/` between the
+    /// components.
+    fn files(dir: &Path) -> BTreeSet<String> {
+        let mut found = BTreeSet::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            if path.is_dir() {
+                found.extend(files(&path).into_iter().map(|file| format!("{}/{}", name, file)));
+            } else {
+                found.insert(name);
+            }
+        }
+        found
+    }
+
+    /// The record of a corpus file, run through the built-in heuristics the
+    /// way `scan` runs them.
+    fn record(path: &Path) -> Record {
+        let data = fs::read(path).unwrap();
+        let mut record = Record::new(path, parse_bytes(&data));
+        record.read_job_triggers(&data);
+        record.detect();
+        record.detect_paths(&PathRules::default());
+        record
+    }
+
+    /// The files in `tests/corpus` are what the generator makes. Run this
+    /// test alone with `UPDATE_CORPUS=1` to rewrite them after changing a
+    /// template.
+    #[test]
+    fn test_corpus_is_current() {
+        let root = root();
+        let entries = corpus();
+        if env::var_os("UPDATE_CORPUS").is_some() {
+            if root.exists() {
+                fs::remove_dir_all(&root).unwrap();
+            }
+            for entry in &entries {
+                let path = root.join(entry.path());
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, &entry.data).unwrap();
+            }
+        }
+        let expected: BTreeSet<String> = entries.iter().map(|entry| entry.path()).collect();
+        assert_eq!(files(&root), expected, "rerun with UPDATE_CORPUS=1 to regenerate tests/corpus");
+        for entry in &entries {
+            let committed = fs::read(root.join(entry.path())).unwrap();
+            assert!(committed == entry.data, "tests/corpus/{} is stale; rerun with UPDATE_CORPUS=1", entry.path());
+        }
+    }
+
+    #[test]
+    fn test_corpus_parses_without_panics() {
+        let mut panicked = Vec::new();
+        for file in files(&root()) {
+            let path = root().join(&file);
+            if panic::catch_unwind(|| record(&path)).is_err() {
+                panicked.push(file);
+            }
+        }
+        assert!(panicked.is_empty(), "panicked on {:?}", panicked);
+    }
+
+    #[test]
+    fn test_benign_corpus_raises_no_warnings() {
+        for entry in corpus().iter().filter(|entry| entry.set == Set::Benign) {
+            let record = record(&root().join(entry.path()));
+            assert!(matches!(record, Record::Ok { .. }), "{}: {:?}", entry.path(), record);
+            assert_eq!(record.warnings(), [], "{}", entry.path());
+        }
+    }
+
+    #[test]
+    fn test_corpus_findings() {
+        for entry in corpus() {
+            let record = record(&root().join(entry.path()));
+            let rules: Vec<&str> = record.findings().iter().map(|finding| finding.rule.as_ref()).collect();
+            assert_eq!(rules, entry.findings, "{}", entry.path());
+        }
+    }
+}
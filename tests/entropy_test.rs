@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::entropy::task_name;
+    use jobfileparser::name_entropy;
+
+    #[test]
+    fn test_random_names_score_high() {
+        assert_eq!(name_entropy("GxkQpd"), 1.0);
+        assert!(name_entropy("qwhfzxv") > 0.8);
+        assert!(name_entropy("{6E8F1C23-9A4B-4D2E-B1F0-3C7A5D9E8B21}") > 0.9);
+    }
+
+    #[test]
+    fn test_vendor_names_score_low() {
+        for name in [
+            "Adobe Acrobat Update Task",
+            "GoogleUpdateTaskMachineCore",
+            "MicrosoftEdgeUpdateTaskMachineUA",
+            "ZoomUpdateTaskUser",
+            "npcapwatchdog",
+            "script",
+            "MyNightlyJob",
+            "OneDrive Standalone Update Task-S-1-5-21-3623811015-3361044348-30300820-1013",
+        ] {
+            assert!(name_entropy(name) < 0.5, "{}: {}", name, name_entropy(name));
+        }
+    }
+
+    #[test]
+    fn test_repetition_and_case() {
+        assert_eq!(name_entropy("aaaaaaaa"), 0.0);
+        assert!(name_entropy("abababab") < name_entropy("abcdefgh"));
+        assert_eq!(name_entropy("GXKQPD"), name_entropy("gxkqpd"));
+    }
+
+    #[test]
+    fn test_short_names_are_not_scored() {
+        assert_eq!(name_entropy("At1"), 0.0);
+        assert_eq!(name_entropy("xq-z"), 0.0);
+        assert_eq!(name_entropy(""), 0.0);
+    }
+
+    #[test]
+    fn test_task_name() {
+        assert_eq!(task_name("C:\\Windows\\Tasks\\GxkQpd.job"), "GxkQpd");
+        assert_eq!(task_name("tasks/Backup.XML"), "Backup");
+        assert_eq!(task_name("System32/Tasks/Microsoft Edge Update"), "Microsoft Edge Update");
+        assert_eq!(task_name("tasks/app.v2"), "app.v2");
+        assert_eq!(task_name(".job"), ".job");
+    }
+}
@@ -0,0 +1,63 @@
+#![cfg(all(feature = "binary", feature = "xml"))]
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::{parse_bytes, JobBuilder, ParsedArtifact, Record};
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+    use std::path::Path;
+
+    const FIXTURES: [&[u8]; 3] = [
+        include_bytes!("fixtures/future_settings.xml"),
+        include_bytes!("fixtures/renamed_job.xml"),
+        include_bytes!("fixtures/renamed_task.job"),
+    ];
+
+    fn hash<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_independent_parses_are_equal() {
+        for data in FIXTURES {
+            let (a, b) = (parse_bytes(data).unwrap(), parse_bytes(data).unwrap());
+            assert_eq!(a, b);
+            assert_eq!(hash(&a), hash(&b));
+            assert_eq!(a.clone(), b);
+
+            let path = Path::new("C:\\Windows\\Tasks\\a.job");
+            let (mut a, mut b) = (Record::new(path, Ok(a)), Record::new(path, Ok(b)));
+            a.detect();
+            b.detect();
+            assert_eq!(a, b);
+            assert_eq!(hash(&a), hash(&b));
+        }
+    }
+
+    #[test]
+    fn test_equality_is_structural() {
+        let artifacts: HashSet<ParsedArtifact> = FIXTURES
+            .iter()
+            .chain(FIXTURES.iter())
+            .map(|data| parse_bytes(data).unwrap())
+            .collect();
+        assert_eq!(artifacts.len(), 3);
+
+        let a = JobBuilder::new("a.exe").build().unwrap();
+        let mut b = a.clone();
+        assert_eq!(a, b);
+        b.run_date.second = 1;
+        assert_ne!(a, b);
+        // Same formatting, different bytes.
+        let mut c = a.clone();
+        c.flags = 0x8;
+        assert_eq!(a.format_job(), c.format_job());
+        assert_ne!(a, c);
+
+        let record = |path: &str| Record::new(Path::new(path), Ok(ParsedArtifact::Job(a.clone())));
+        assert_ne!(record("a.job"), record("b.job"));
+    }
+}
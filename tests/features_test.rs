@@ -0,0 +1,52 @@
+mod common;
+
+#[cfg(all(feature = "binary", not(feature = "xml")))]
+mod binary_only {
+    use super::common::job_bytes;
+    use jobfileparser::{parse_auto, parse_bytes, Error, ParsedArtifact};
+    use std::path::Path;
+
+    #[test]
+    fn test_binary_parses() {
+        let artifact = parse_auto(Path::new("a.job"), &job_bytes("a.exe")).unwrap();
+        assert!(matches!(artifact, ParsedArtifact::Job(_)));
+    }
+
+    #[test]
+    fn test_xml_is_unsupported() {
+        let result = parse_auto(Path::new("a.xml"), b"<Task/>");
+        assert!(matches!(result, Err(Error::Unsupported("xml"))));
+    }
+
+    #[test]
+    fn test_parse_bytes_sniffs_content() {
+        let artifact = parse_bytes(&job_bytes("a.exe")).unwrap();
+        assert!(matches!(artifact, ParsedArtifact::Job(_)));
+        let result = parse_bytes(b"  \r\n<Task/>");
+        assert!(matches!(result, Err(Error::Unsupported("xml"))));
+    }
+}
+
+#[cfg(all(feature = "xml", not(feature = "binary")))]
+mod xml_only {
+    use jobfileparser::{parse_auto, Error};
+    use std::path::Path;
+
+    #[test]
+    fn test_binary_is_unsupported() {
+        let result = parse_auto(Path::new("a.job"), &[0; 128]);
+        assert!(matches!(result, Err(Error::Unsupported("binary"))));
+    }
+}
+
+#[cfg(not(any(feature = "binary", feature = "xml")))]
+mod no_formats {
+    use jobfileparser::{parse_auto, Error};
+    use std::path::Path;
+
+    #[test]
+    fn test_everything_is_unsupported() {
+        assert!(matches!(parse_auto(Path::new("a.job"), &[]), Err(Error::Unsupported(_))));
+        assert!(matches!(parse_auto(Path::new("a.xml"), &[]), Err(Error::Unsupported(_))));
+    }
+}
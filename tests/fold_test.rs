@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::fold::fold;
+    use jobfileparser::Case;
+
+    #[test]
+    fn test_fold() {
+        let alike = [
+            ("POWERSHELL.EXE", "powershell.exe"),
+            ("C:\\Users\\Émilie\\ÉTÉ.ps1", "c:\\users\\émilie\\été.ps1"),
+            // Turkish: `İ` and `ı` are the other case of `i` and `I`.
+            ("YÖNETİCİ", "yönetici"),
+            ("Yönetıcı", "YÖNETICI"),
+            ("STRASSE", "Straße"),
+            // Greek final sigma.
+            ("ΟΔΥΣΣΕΥΣ", "Οδυσσευς"),
+            ("АДМИНИСТРАТОР", "Администратор"),
+        ];
+        for (a, b) in alike {
+            assert_eq!(fold(a), fold(b), "{} {}", a, b);
+        }
+        assert_ne!(fold("Système"), fold("Systeme"));
+        assert_ne!(fold("Administrateur"), fold("Administrator"));
+        // Text that is already folded is not copied.
+        assert!(matches!(fold("c:\\tools\\a.exe"), std::borrow::Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_case_modes() {
+        assert!(Case::default().eq("\\Microsoft\\Windows\\Defrag", "\\MICROSOFT\\windows\\DEFRAG"));
+        assert!(Case::Insensitive.eq("İşlem", "iŞLEM"));
+        assert!(!Case::Sensitive.eq("İşlem", "iŞLEM"));
+        assert!(Case::Sensitive.eq("İşlem", "İşlem"));
+        assert_eq!(Case::Sensitive.key("MiXeD"), "MiXeD");
+        assert_eq!(Case::Insensitive.key("MiXeD"), "mixed");
+    }
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "binary")]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::{trigger_bytes, with_triggers};
+    use jobfileparser::consts::trigger;
+    use jobfileparser::{Job, JobBuilder, JobDate};
+
+    const NOW: &str = "2024-06-01T12:00:00";
+
+    fn ran(month: u16) -> JobDate {
+        JobDate {
+            year: 2024,
+            month,
+            weekday: Some(0),
+            day: 1,
+            hour: 8,
+            minute: 0,
+            second: 0,
+        }
+    }
+
+    /// A one-time trigger starting at 08:00 on the first of `month` 2024.
+    fn trigger(month: u32, disabled: bool) -> Vec<u8> {
+        let flags = if disabled { trigger::FLAG_DISABLED } else { 0 };
+        trigger_bytes(&[
+            (trigger::BEGIN_YEAR, 2024),
+            (trigger::BEGIN_MONTH, month),
+            (trigger::BEGIN_DAY, 1),
+            (trigger::START_HOUR, 8),
+            (trigger::FLAGS, flags),
+        ])
+    }
+
+    fn past() -> Vec<u8> {
+        trigger(5, false)
+    }
+
+    fn future() -> Vec<u8> {
+        trigger(7, false)
+    }
+
+    /// A job with `triggers` and the given status and flags that last ran
+    /// on `run_date`, parsed from its bytes.
+    fn job(run_date: JobDate, triggers: &[Vec<u8>], status: i32, flags: u32) -> Job {
+        let job = JobBuilder::new("a.exe").run_date(run_date).status(status).flags(flags).build().unwrap();
+        Job::parse(&with_triggers(job.to_bytes(), triggers)).unwrap()
+    }
+
+    fn never() -> JobDate {
+        JobDate::never(false)
+    }
+
+    #[test]
+    fn test_scheduled_date_is_the_earliest_enabled_trigger() {
+        let triggers = [future(), trigger(4, true), past()];
+        let scheduled = job(never(), &triggers, 0x41303, 0).scheduled_date;
+        assert_eq!(scheduled.format_iso(), "2024-05-01T08:00:00");
+        assert_eq!(scheduled.weekday, None);
+        assert_eq!(job(never(), &[], 0x41303, 0).scheduled_date, JobDate::never(true));
+        let disabled = job(never(), &[trigger(4, true)], 0x41303, 0);
+        assert_eq!(disabled.scheduled_date, JobDate::never(true));
+    }
+
+    #[test]
+    fn test_due_and_never_ran() {
+        assert!(job(never(), &[past()], 0x41303, 0).never_ran_but_scheduled(NOW));
+        // Either signal of "never ran" is enough on its own.
+        assert!(job(never(), &[past()], 0x41300, 0).never_ran_but_scheduled(NOW));
+        assert!(job(ran(4), &[past()], 0x41303, 0).never_ran_but_scheduled(NOW));
+        // One trigger that has started is enough.
+        assert!(job(never(), &[future(), past()], 0x41303, 0).never_ran_but_scheduled(NOW));
+        // Due at exactly `now`.
+        let due_now = job(never(), &[trigger(6, false)], 0x41303, 0);
+        assert!(due_now.never_ran_but_scheduled("2024-06-01T08:00:00"));
+    }
+
+    #[test]
+    fn test_ran_jobs_are_not_flagged() {
+        assert!(!job(ran(5), &[past()], 0x41300, 0).never_ran_but_scheduled(NOW));
+    }
+
+    #[test]
+    fn test_future_or_unscheduled_jobs_are_not_flagged() {
+        assert!(!job(never(), &[future()], 0x41303, 0).never_ran_but_scheduled(NOW));
+        assert!(!job(never(), &[], 0x41303, 0).never_ran_but_scheduled(NOW));
+        // A disabled trigger that has started does not make the job due.
+        let triggers = [trigger(5, true), future()];
+        assert!(!job(never(), &triggers, 0x41303, 0).never_ran_but_scheduled(NOW));
+    }
+
+    #[test]
+    fn test_disabled_jobs_are_not_flagged() {
+        assert!(!job(never(), &[past()], 0x41303, 0x4).never_ran_but_scheduled(NOW));
+        assert!(!job(never(), &[past()], 0x41302, 0).never_ran_but_scheduled(NOW));
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_tasks_are_not_flagged() {
+        use jobfileparser::{ParsedArtifact, Task};
+
+        let task = Task::from_xml(
+            "<Task><RegistrationInfo/><Triggers><CalendarTrigger>\
+             <StartBoundary>2020-01-01T00:00:00</StartBoundary></CalendarTrigger></Triggers>\
+             <Settings/><Actions/></Task>",
+        )
+        .unwrap();
+        assert!(!ParsedArtifact::Task(task).never_ran_but_scheduled(NOW));
+    }
+}
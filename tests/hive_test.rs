@@ -0,0 +1,153 @@
+#![cfg(feature = "hive")]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::{dynamic_info, hive_bytes, reg_sz, task_cache_hive, HiveKey};
+    use jobfileparser::hive::{Hive, REG_BINARY, REG_DWORD, REG_SZ};
+    use jobfileparser::taskcache::filetime;
+    use jobfileparser::{read_task_cache, DynamicInfo, Error};
+
+    const REGISTERED: u64 = 133182432000000000; // 2023-01-15T08:00:00
+    const LAST_RUN: u64 = 133539919800000000; // 2024-03-04T02:13:00
+    const LAST_SUCCESS: u64 = 133539055800000000; // 2024-03-03T02:13:00
+
+    #[test]
+    fn test_keys_and_values() {
+        let mut root = HiveKey::new("ROOT");
+        root.path("Software\\Vendor")
+            .value("Name", REG_SZ, reg_sz("Agent"))
+            .value("Count", REG_DWORD, 7u32.to_le_bytes().to_vec())
+            .value("Blob", REG_BINARY, vec![1, 2, 3, 4, 5, 6]);
+        root.path("Software\\Other");
+        let data = hive_bytes(&root);
+
+        let root = Hive::new(&data).unwrap().root().unwrap();
+        assert_eq!(root.name(), "ROOT");
+        let software = root.subkey("software").unwrap().unwrap();
+        let names: Vec<String> = software.subkeys().unwrap().iter().map(|k| k.name()).collect();
+        assert_eq!(names, ["Vendor", "Other"]);
+        assert!(root.descend("Software\\Missing").unwrap().is_none());
+
+        let vendor = root.descend("Software\\Vendor").unwrap().unwrap();
+        assert_eq!(vendor.values().unwrap().len(), 3);
+        assert_eq!(vendor.value("name").unwrap().unwrap().string().as_deref(), Some("Agent"));
+        assert_eq!(vendor.value("Count").unwrap().unwrap().dword(), Some(7));
+        let blob = vendor.value("Blob").unwrap().unwrap();
+        assert_eq!(&blob.data[..], [1, 2, 3, 4, 5, 6]);
+        assert_eq!(blob.string(), None);
+        assert!(vendor.value("Missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_malformed_hives_are_errors() {
+        assert!(matches!(Hive::new(b"not a hive"), Err(Error::InvalidHive { offset: 0, .. })));
+        let mut data = hive_bytes(&HiveKey::new("ROOT"));
+        assert!(matches!(Hive::new(&data[..0x800]), Err(Error::InvalidHive { .. })));
+        // Point the root at the middle of the bin header.
+        data[0x24..0x28].copy_from_slice(&4u32.to_le_bytes());
+        let error = Hive::new(&data).unwrap().root().unwrap_err();
+        assert_eq!(error.kind(), "InvalidHive");
+        assert_eq!(error.offset(), Some(0x1004));
+    }
+
+    /// Sets the `u32` at `offset`, relative to the bins, in `data`.
+    fn patch_u32(data: &mut [u8], offset: u32, value: u32) {
+        let at = 0x1000 + offset as usize;
+        data[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn test_looping_subkey_index_is_an_error() {
+        let mut root = HiveKey::new("ROOT");
+        root.child("A");
+        let mut data = hive_bytes(&root);
+        // An `ri` list whose entries are itself, past the end of the bin.
+        let ri = (data.len() - 0x1000) as u32;
+        data.extend_from_slice(&(-16i32).to_le_bytes());
+        data.extend_from_slice(b"ri\x02\x00");
+        data.extend_from_slice(&ri.to_le_bytes());
+        data.extend_from_slice(&ri.to_le_bytes());
+        let root_offset = Hive::new(&data).unwrap().root().unwrap().offset();
+        patch_u32(&mut data, root_offset + 4 + 0x1C, ri);
+        let error = Hive::new(&data).unwrap().root().unwrap().subkeys().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("invalid registry hive: subkey list referenced twice at offset {}", 0x1000 + ri)
+        );
+    }
+
+    #[test]
+    fn test_task_cache_tree_cycle_is_an_error() {
+        let id = "{AAAAAAAA-0000-0000-0000-000000000001}";
+        let mut data = task_cache_hive(&[(id, None, Some("\\Folder\\Task"), None)]);
+        let root = Hive::new(&data).unwrap().root().unwrap();
+        let cache = root.descend("Microsoft\\Windows NT\\CurrentVersion\\Schedule\\TaskCache");
+        let tree = cache.unwrap().unwrap().subkey("Tree").unwrap().unwrap();
+        let folder = tree.subkey("Folder").unwrap().unwrap();
+        let (tree, folder) = (tree.offset(), folder.offset());
+        // List `Tree` under `Folder`, in place of `Task`.
+        let at = 0x1000 + folder as usize + 4 + 0x1C;
+        let list = u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        patch_u32(&mut data, list + 4 + 4, tree);
+        let error = read_task_cache(&data).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid registry hive: TaskCache tree key listed twice at offset 0"
+        );
+    }
+
+    #[test]
+    fn test_dynamic_info() {
+        let info = DynamicInfo::parse(&dynamic_info(REGISTERED, LAST_RUN, LAST_SUCCESS)).unwrap();
+        assert_eq!(info.registered.as_deref(), Some("2023-01-15T08:00:00"));
+        assert_eq!(info.last_run.as_deref(), Some("2024-03-04T02:13:00"));
+        assert_eq!(info.last_success.as_deref(), Some("2024-03-03T02:13:00"));
+        assert_eq!(info.last_result, 0x41303);
+
+        // The Windows 7 form has no last successful run.
+        let info = DynamicInfo::parse(&dynamic_info(REGISTERED, 0, 0)[..28]).unwrap();
+        assert_eq!(info.last_run, None);
+        assert_eq!(info.last_success, None);
+        assert_eq!(DynamicInfo::parse(&[0; 20]), None);
+        assert_eq!(filetime(0), None);
+        assert_eq!(filetime(116444736000000000).as_deref(), Some("1970-01-01T00:00:00"));
+    }
+
+    #[test]
+    fn test_task_cache_joins_tasks_and_tree() {
+        let data = task_cache_hive(&[
+            (
+                "{AAAAAAAA-0000-0000-0000-000000000001}",
+                Some("\\Microsoft\\Windows\\Defrag\\ScheduledDefrag"),
+                Some("\\Microsoft\\Windows\\Defrag\\ScheduledDefrag"),
+                Some(dynamic_info(REGISTERED, LAST_RUN, LAST_SUCCESS)),
+            ),
+            ("{AAAAAAAA-0000-0000-0000-000000000002}", Some("\\Updater"), None, None),
+            ("{aaaaaaaa-0000-0000-0000-000000000003}", None, Some("\\Hidden\\Beacon"), None),
+        ]);
+        let tasks = read_task_cache(&data).unwrap();
+        let summary: Vec<(&str, bool, bool)> =
+            tasks.iter().map(|t| (t.path.as_str(), t.in_tasks, t.in_tree)).collect();
+        assert_eq!(
+            summary,
+            [
+                ("\\Hidden\\Beacon", false, true),
+                ("\\Microsoft\\Windows\\Defrag\\ScheduledDefrag", true, true),
+                ("\\Updater", true, false),
+            ]
+        );
+        assert_eq!(tasks[0].id, "{aaaaaaaa-0000-0000-0000-000000000003}");
+        let info = tasks[1].dynamic_info.as_ref().unwrap();
+        assert_eq!(info.last_run.as_deref(), Some("2024-03-04T02:13:00"));
+        assert_eq!(tasks[2].dynamic_info, None);
+    }
+
+    #[test]
+    fn test_hive_without_task_cache() {
+        let data = hive_bytes(&HiveKey::new("ROOT"));
+        let error = read_task_cache(&data).unwrap_err();
+        assert_eq!(error.to_string(), "invalid registry hive: no TaskCache key at offset 0");
+    }
+}
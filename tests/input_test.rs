@@ -0,0 +1,45 @@
+#![cfg(feature = "fs")]
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::input::MMAP_THRESHOLD;
+    use jobfileparser::Input;
+    use std::fs;
+
+    #[test]
+    fn test_small_files_are_read_and_large_ones_mapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = dir.path().join("small.bin");
+        let large = dir.path().join("large.bin");
+        fs::write(&small, b"abc").unwrap();
+        fs::write(&large, vec![7; MMAP_THRESHOLD as usize]).unwrap();
+
+        let input = Input::open(&small).unwrap();
+        assert!(!input.is_mapped());
+        assert_eq!(&*input, b"abc");
+
+        let input = Input::open(&large).unwrap();
+        assert!(input.is_mapped());
+        assert_eq!(input.len(), MMAP_THRESHOLD as usize);
+
+        assert!(Input::map(&small).unwrap().is_mapped());
+    }
+
+    #[test]
+    fn test_empty_files_are_not_mapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty.bin");
+        fs::write(&empty, b"").unwrap();
+
+        let input = Input::map(&empty).unwrap();
+        assert!(!input.is_mapped());
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_streams_are_buffered() {
+        let input = Input::read(&b"data"[..]).unwrap();
+        assert!(!input.is_mapped());
+        assert_eq!(&*input, b"data");
+    }
+}
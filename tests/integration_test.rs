@@ -3,6 +3,7 @@ mod tests {
     use serde::Deserialize;
     use quick_xml::de::from_str;
 
+    #[allow(dead_code)]
     #[derive(Debug, Deserialize)]
     struct Task {
         #[serde(rename = "RegistrationInfo")]
@@ -31,6 +32,7 @@ mod tests {
         calendar_triggers: Vec<CalendarTrigger>,
     }
 
+    #[allow(dead_code)]
     #[derive(Debug, Deserialize)]
     struct CalendarTrigger {
         #[serde(rename = "StartBoundary")]
@@ -41,6 +43,7 @@ mod tests {
         enabled: Option<bool>,
     }
 
+    #[allow(dead_code)]
     #[derive(Debug, Deserialize)]
     struct Settings {
         #[serde(rename = "Enabled")]
@@ -0,0 +1,64 @@
+#![cfg(feature = "binary")]
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::consts::{flags, status};
+    use jobfileparser::interpret::interpret;
+    use jobfileparser::{Job, JobBuilder, JobDate, ParsedArtifact, Record};
+    use std::path::Path;
+
+    const NOW: &str = "2024-06-01T12:00:00";
+
+    fn ran(year: u16, month: u16, day: u16) -> JobDate {
+        JobDate { year, month, weekday: Some(0), day, hour: 8, minute: 0, second: 0 }
+    }
+
+    fn job(flags: u32, status: i32, run_date: JobDate) -> Job {
+        JobBuilder::new("a.exe").flags(flags).status(status).run_date(run_date).build().unwrap()
+    }
+
+    #[test]
+    fn test_delete_when_done() {
+        let pending = interpret(&job(flags::DELETE_WHEN_DONE, status::READY, JobDate::never(false)), NOW);
+        assert_eq!(pending.len(), 1);
+        assert!(pending[0].starts_with("TASK_FLAG_DELETE_WHEN_DONE is set: Task Scheduler deletes this job"));
+        // With no runs left it should already have been deleted.
+        let due = interpret(&job(flags::DELETE_WHEN_DONE, status::NO_MORE_RUNS, ran(2024, 5, 1)), NOW);
+        assert_eq!(due.len(), 1);
+        assert!(due[0].contains("no more runs are scheduled"), "{}", due[0]);
+    }
+
+    #[test]
+    fn test_disabled_but_ran() {
+        let recent = interpret(&job(flags::DISABLED, status::DISABLED, ran(2024, 5, 20)), NOW);
+        assert_eq!(
+            recent,
+            ["TASK_FLAG_DISABLED is set, yet the job last ran at 2024-05-20T08:00:00: it was disabled since, \
+              or the flag was set to make it look inactive"]
+        );
+        // Long ago, never, or after `now`, there is nothing odd about it.
+        for run_date in [ran(2023, 1, 1), JobDate::never(false), ran(2024, 6, 2)] {
+            assert!(interpret(&job(flags::DISABLED, status::DISABLED, run_date), NOW).is_empty());
+        }
+        // Nor about a recent run of an enabled job.
+        assert!(interpret(&job(0, status::READY, ran(2024, 5, 20)), NOW).is_empty());
+    }
+
+    #[test]
+    fn test_hidden_and_interactive() {
+        let both = job(flags::HIDDEN | flags::INTERACTIVE, status::READY, JobDate::never(false));
+        assert_eq!(interpret(&both, NOW).len(), 1);
+        assert!(interpret(&job(flags::HIDDEN, status::READY, JobDate::never(false)), NOW).is_empty());
+    }
+
+    #[test]
+    fn test_record_interpretations() {
+        let artifact = ParsedArtifact::Job(job(flags::DISABLED, status::DISABLED, ran(2024, 5, 20)));
+        let mut record = Record::new(Path::new("a.job"), Ok(artifact));
+        assert!(record.interpretations().is_empty());
+        record.infer_history(NOW);
+        assert_eq!(record.interpretations().len(), 1);
+        #[cfg(feature = "serde")]
+        assert_eq!(serde_json::to_value(&record).unwrap()["interpretations"][0], record.interpretations()[0]);
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(all(unix, feature = "cli", feature = "binary", feature = "xml", feature = "fs"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use std::fs;
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    const FILES: usize = 3000;
+
+    #[test]
+    fn test_ctrl_c_keeps_written_records_and_summarizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = job_bytes("a.exe");
+        for i in 0..FILES {
+            fs::write(dir.path().join(format!("{:04}.job", i)), &data).unwrap();
+        }
+
+        // Nothing reads stdout until the signal is sent, so the scan blocks
+        // once the pipe is full, well before the last file.
+        let mut child = Command::new(assert_cmd::cargo::cargo_bin("jobfileparser"))
+            .args(["scan", dir.path().to_str().unwrap(), "-o", "jsonl"])
+            .env("XDG_CONFIG_HOME", "/nonexistent")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        thread::sleep(Duration::from_millis(500));
+        let status = Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let mut stdout = String::new();
+        child.stdout.take().unwrap().read_to_string(&mut stdout).unwrap();
+        let code = child.wait().unwrap();
+        assert_eq!(code.code(), Some(130));
+
+        let records: Vec<serde_json::Value> =
+            stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert!(!records.is_empty() && records.len() < FILES, "{}", records.len());
+        assert!(stdout.ends_with('\n'));
+        let mut stderr = String::new();
+        child.stderr.take().unwrap().read_to_string(&mut stderr).unwrap();
+        let summary = format!("Interrupted after {} records", records.len());
+        assert!(stderr.contains(&summary), "{}", stderr);
+    }
+
+    #[test]
+    fn test_ctrl_c_still_writes_the_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = job_bytes("a.exe");
+        for i in 0..FILES {
+            fs::write(dir.path().join(format!("{:04}.job", i)), &data).unwrap();
+        }
+        let manifest = dir.path().join("manifest.json");
+
+        let mut child = Command::new(assert_cmd::cargo::cargo_bin("jobfileparser"))
+            .args(["scan", dir.path().to_str().unwrap(), "-o", "jsonl", "--manifest"])
+            .arg(&manifest)
+            .env("XDG_CONFIG_HOME", "/nonexistent")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        thread::sleep(Duration::from_millis(500));
+        let status = Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let mut stdout = String::new();
+        child.stdout.take().unwrap().read_to_string(&mut stdout).unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(130));
+
+        let manifest: serde_json::Value = serde_json::from_slice(&fs::read(&manifest).unwrap()).unwrap();
+        assert_eq!(manifest["interrupted"], true);
+        let inputs = manifest["inputs"].as_array().unwrap();
+        assert_eq!(inputs.len(), stdout.lines().count());
+        assert!(inputs.len() < FILES);
+        assert!(manifest["output"].is_null());
+    }
+}
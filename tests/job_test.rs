@@ -0,0 +1,287 @@
+#![cfg(feature = "binary")]
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::consts::{fixed, variable};
+    use jobfileparser::{EmptyString, Error, Job, JobBuilder};
+
+    const FIELDS: [&str; 5] = ["a.exe", "/x", "C:\\w", "DOM\\u", "note"];
+    const NAMES: [&str; 5] = ["application name", "parameters", "working directory", "author", "comment"];
+
+    /// The fixed section of a job, to put hand-written strings after.
+    fn header() -> Vec<u8> {
+        JobBuilder::new("").build().unwrap().to_bytes()[..variable::APPLICATION_NAME].to_vec()
+    }
+
+    /// A string as the count word then the UTF-16LE units, as given.
+    fn string(count: u16, units: &[u16]) -> Vec<u8> {
+        let mut out = count.to_le_bytes().to_vec();
+        for unit in units {
+            out.extend_from_slice(&unit.to_le_bytes());
+        }
+        out
+    }
+
+    fn terminated(text: &str) -> Vec<u8> {
+        let units: Vec<u16> = text.encode_utf16().chain([0]).collect();
+        string(units.len() as u16, &units)
+    }
+
+    fn job_data(strings: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = header();
+        for s in strings {
+            data.extend_from_slice(s);
+        }
+        // User data and reserved data sizes, and the trigger count.
+        data.extend_from_slice(&[0; 6]);
+        data
+    }
+
+    fn parse(strings: &[Vec<u8>]) -> Job {
+        Job::parse(&job_data(strings)).unwrap()
+    }
+
+    fn fields(job: &Job) -> [&str; 5] {
+        [&job.name, &job.parameters, &job.working_directory, &job.user, &job.comment]
+    }
+
+    #[test]
+    fn test_each_string_empty() {
+        for empty in 0..FIELDS.len() {
+            let mut expected = FIELDS;
+            expected[empty] = "";
+            // A count of 0 and no units, then a count of 1 and a lone NUL.
+            for encoded in [string(0, &[]), string(1, &[0])] {
+                let strings: Vec<Vec<u8>> = (0..FIELDS.len())
+                    .map(|i| if i == empty { encoded.clone() } else { terminated(FIELDS[i]) })
+                    .collect();
+                let job = parse(&strings);
+                assert_eq!(fields(&job), expected, "field {} as {:?}", empty, encoded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_strings_absent() {
+        let job = parse(&vec![string(0, &[]); 5]);
+        assert_eq!(fields(&job), [""; 5]);
+        assert!(job.format_job().contains("Application: \nParameters: \nWorking Directory: \n"));
+    }
+
+    #[test]
+    fn test_stale_units_after_the_terminator() {
+        // A writer that reused its buffer: "ab", the NUL, then what was
+        // there before.
+        let stale = string(5, &[b'a' as u16, b'b' as u16, 0, b'z' as u16, b'z' as u16]);
+        let job = parse(&[terminated("a.exe"), stale, terminated("C:\\w"), terminated(""), terminated("")]);
+        assert_eq!(job.parameters, "ab");
+        assert_eq!(job.working_directory, "C:\\w");
+    }
+
+    #[test]
+    fn test_builder_writes_both_empty_encodings() {
+        let job = JobBuilder::new("a.exe").comment("note").build().unwrap();
+        let start = variable::APPLICATION_NAME + terminated("a.exe").len();
+        let terminated_bytes = job.to_bytes();
+        let absent_bytes = job.to_bytes_with(EmptyString::Absent);
+        assert_eq!(terminated_bytes, job.to_bytes_with(EmptyString::Terminated));
+        assert_eq!(&terminated_bytes[start..start + 12], [1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(&absent_bytes[start..start + 6], [0, 0, 0, 0, 0, 0]);
+        assert_eq!(absent_bytes.len() + 6, terminated_bytes.len());
+        for data in [terminated_bytes, absent_bytes] {
+            assert_eq!(fields(&Job::parse(&data).unwrap()), fields(&job));
+        }
+    }
+
+    #[test]
+    fn test_builder_rejects_strings_past_the_trigger_offset() {
+        // With the other four strings empty, the application name ends the
+        // strings at 94 bytes plus two a character.
+        let longest = "a".repeat(32720);
+        let job = JobBuilder::new(&longest).build().unwrap();
+        let data = job.to_bytes();
+        let offset = &data[fixed::TRIGGER_OFFSET.offset..];
+        assert_eq!(u16::from_le_bytes([offset[0], offset[1]]), 0xFFFE);
+        assert_eq!(Job::parse(&data).unwrap().name, longest);
+        let error = JobBuilder::new(&format!("{}a", longest)).build().unwrap_err();
+        assert!(matches!(error, Error::StringTooLong { field: "application name", len: 32721 }));
+        assert_eq!(error.kind(), "StringTooLong");
+        // The longest string is the one named.
+        let error = JobBuilder::new("a.exe")
+            .parameters(&"b".repeat(20000))
+            .comment(&"c".repeat(20001))
+            .build()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "job comment is 20001 characters long, more than a job file holds");
+    }
+
+    #[test]
+    fn test_string_lengths_out_of_bounds() {
+        let abc: Vec<u16> = "abc".encode_utf16().collect();
+        for huge in 0..FIELDS.len() {
+            let strings: Vec<Vec<u8>> = (0..FIELDS.len())
+                .map(|i| if i == huge { string(0xFFFF, &abc) } else { terminated(FIELDS[i]) })
+                .collect();
+            let data = job_data(&strings);
+            let start = data.len() - strings[huge..].iter().map(Vec::len).sum::<usize>() - 6 + 2;
+            let available = (data.len() - start) / 2;
+            match Job::parse(&data) {
+                Err(Error::StringLengthOutOfBounds { field, declared: 0xFFFF, available: left }) => {
+                    assert_eq!((field, left), (NAMES[huge], available));
+                }
+                other => panic!("{}: {:?}", NAMES[huge], other),
+            }
+            assert_eq!(Job::parse(&data).unwrap_err().kind(), "StringLengthOutOfBounds");
+
+            // Lenient: the string runs to the end, the ones after it are
+            // empty, and each is a warning.
+            let (job, warnings) = Job::parse_lenient(&data).unwrap();
+            assert_eq!(&fields(&job)[..huge], &FIELDS[..huge]);
+            assert!(fields(&job)[huge].starts_with("abc"), "{:?}", job);
+            assert!(fields(&job)[huge + 1..].iter().all(|field| field.is_empty()));
+            assert_eq!(warnings.len(), FIELDS.len() - huge);
+            assert!(matches!(warnings[0], Error::StringLengthOutOfBounds { declared: 0xFFFF, .. }));
+            assert!(warnings[1..].iter().all(|w| matches!(w, Error::TruncatedJob { len: 2, .. })));
+        }
+    }
+
+    #[test]
+    fn test_lenient_keeps_strict_failures() {
+        let data = JobBuilder::new("a.exe").build().unwrap().to_bytes();
+        // The fixed section cut short still fails.
+        assert!(matches!(Job::parse_lenient(&data[..40]), Err(Error::TruncatedJob { .. })));
+        let (job, warnings) = Job::parse_lenient(&data).unwrap();
+        assert_eq!(job.name, "a.exe");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_trigger_count() {
+        use jobfileparser::consts::fixed;
+        use jobfileparser::job::trigger_count;
+        use jobfileparser::{ParsedArtifact, Record};
+        use std::path::Path;
+
+        let job = JobBuilder::new("a.exe").build().unwrap();
+        let mut data = job.to_bytes();
+        assert_eq!(trigger_count(&data), Some(0));
+        let mut record = Record::new(Path::new("a.job"), Ok(ParsedArtifact::Job(job)));
+        assert_eq!((record.trigger_count(), record.action_count()), (None, 1));
+        record.read_job_triggers(&data);
+        assert_eq!(record.trigger_count(), Some(0));
+
+        // An offset into the fixed-length section, and one past the end.
+        data[fixed::TRIGGER_OFFSET.offset..][..2].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(trigger_count(&data), None);
+        data[fixed::TRIGGER_OFFSET.offset..][..2].copy_from_slice(&0xFFF0u16.to_le_bytes());
+        assert_eq!(trigger_count(&data), None);
+    }
+
+    #[test]
+    fn test_sections() {
+        use jobfileparser::consts::{fixed, trigger};
+        use jobfileparser::job::{sections, Section};
+
+        let extents = |data: &[u8]| {
+            sections(data).iter().map(|s| (s.name, s.offset, s.len)).collect::<Vec<_>>()
+        };
+        let mut data = job_data(&FIELDS.map(terminated));
+        let strings = data.len() - 6;
+        let expected = [
+            ("header", 0, fixed::LENGTH),
+            ("strings", fixed::LENGTH, strings - fixed::LENGTH),
+            ("user data", strings, 2),
+            ("reserved data", strings + 2, 2),
+            ("triggers", strings + 4, 2),
+        ];
+        // Without a trigger offset in the header, the triggers follow the
+        // reserved data.
+        assert_eq!(extents(&data), expected);
+
+        // Three bytes of user data, and a trigger the count says is there.
+        data.truncate(strings);
+        data.extend([3, 0, 0xAA, 0xBB, 0xCC, 0, 0, 1, 0]);
+        data.extend([0; trigger::LENGTH]);
+        let triggers = data.len() - 2 - trigger::LENGTH;
+        data[fixed::TRIGGER_OFFSET.offset..][..2].copy_from_slice(&(triggers as u16).to_le_bytes());
+        let all = sections(&data);
+        assert_eq!(all[2], Section { name: "user data", offset: strings, len: 5 });
+        assert_eq!(all[4], Section { name: "triggers", offset: triggers, len: 2 + trigger::LENGTH });
+        assert_eq!(all.last().unwrap().end(), data.len());
+
+        // A signature, and junk no section accounts for.
+        data.extend([1, 0, 1, 0]);
+        data.extend([0; 64]);
+        data.extend(b"junk");
+        let all = sections(&data);
+        assert_eq!(all[5], Section { name: "signature", offset: triggers + 2 + trigger::LENGTH, len: 68 });
+        assert_eq!(all[5].end(), data.len() - 4);
+
+        // A cut trigger section is as long as what is left, and the header
+        // alone is all a truncated header has.
+        assert_eq!(sections(&data[..triggers + 10]).last().unwrap().len, 10);
+        assert_eq!(extents(&data[..40]), [("header", 0, 40)]);
+        assert!(sections(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_coverage() {
+        use jobfileparser::job::coverage;
+
+        let data = JobBuilder::new("a.exe").comment("note").build().unwrap().to_bytes();
+        assert_eq!(coverage(&data), 100.0);
+        let mut padded = data.clone();
+        padded.extend([0; 1024]);
+        assert_eq!(coverage(&padded), data.len() as f64 * 100.0 / padded.len() as f64);
+        assert_eq!(coverage(&[]), 0.0);
+    }
+
+    /// Flag words with the lines printed for them: what the Scheduled Task
+    /// Wizard of Windows XP writes for a new task, what `at` writes for a
+    /// one-off and an `/interactive` job, and a hidden, disabled job without
+    /// an application name. They are synthetic, put together from MS-TSCH
+    /// section 2.4.1 and patched into built jobs, not captured from real
+    /// systems; there are no captured job files in this repository.
+    const FLAG_WORDS: [([u8; 4], &str, &str, u32); 5] = [
+        (
+            [0xC0, 0x00, 0x00, 0x01],
+            "TASK_FLAG_DONT_START_IF_ON_BATTERIES, TASK_FLAG_KILL_IF_GOING_ON_BATTERIES",
+            "TASK_APPLICATION_NAME",
+            0,
+        ),
+        ([0x02, 0x00, 0x00, 0x01], "TASK_FLAG_DELETE_WHEN_DONE", "TASK_APPLICATION_NAME", 0),
+        (
+            [0x03, 0x00, 0x00, 0x01],
+            "TASK_FLAG_INTERACTIVE, TASK_FLAG_DELETE_WHEN_DONE",
+            "TASK_APPLICATION_NAME",
+            0,
+        ),
+        ([0x04, 0x02, 0x00, 0x00], "TASK_FLAG_DISABLED, TASK_FLAG_HIDDEN", "", 0),
+        // Reserved bits are neither.
+        ([0x08, 0x20, 0x40, 0x80], "TASK_FLAG_RUN_ONLY_IF_LOGGED_ON", "", 0x80400008),
+    ];
+
+    #[test]
+    fn test_flag_words() {
+        use jobfileparser::{TaskFlag, TaskStateBit};
+
+        for (word, flags, state_bits, unknown) in FLAG_WORDS {
+            let mut data = JobBuilder::new("a.exe").build().unwrap().to_bytes();
+            data[48..52].copy_from_slice(&word);
+            let job = Job::parse(&data).unwrap();
+            let text = job.format_job();
+            let line = |label: &str| {
+                let line = text.lines().find_map(|line| line.strip_prefix(label));
+                line.unwrap().to_string()
+            };
+            assert_eq!(line("Flags: "), flags, "{:02x?}", word);
+            assert_eq!(line("Task State Bits: "), state_bits, "{:02x?}", word);
+            assert_eq!(job.unknown_flags(), unknown, "{:02x?}", word);
+        }
+
+        let job = JobBuilder::new("a.exe").flags(0x01000200).build().unwrap();
+        assert_eq!(job.flag_names(), [TaskFlag::Hidden]);
+        assert_eq!(job.state_bits(), [TaskStateBit::ApplicationName]);
+        assert_eq!(TaskStateBit::ApplicationName.constant_name(), "TASK_APPLICATION_NAME");
+    }
+}
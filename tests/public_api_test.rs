@@ -0,0 +1,180 @@
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Where the snapshot of the public API is kept.
+    const SNAPSHOT: &str = "tests/public-api.txt";
+
+    /// Sources behind the `unstable` feature, which may change in any
+    /// release and so are left out of the snapshot.
+    const UNSTABLE: &[&str] = &["src/consts/layout.rs", "src/job/sections.rs", "src/sniff.rs"];
+
+    fn root() -> &'static Path {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+    }
+
+    /// The library's source files under `dir`, in order, without the
+    /// command-line tool's.
+    fn sources(dir: &Path, files: &mut Vec<PathBuf>) {
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().path()).collect();
+        entries.sort();
+        for path in entries {
+            let relative = path.strip_prefix(root()).unwrap().to_string_lossy().replace('\\', "/");
+            if relative == "src/cli" || relative == "src/main.rs" || UNSTABLE.contains(&relative.as_str()) {
+                continue;
+            }
+            if path.is_dir() {
+                sources(&path, files);
+            } else if relative.ends_with(".rs") {
+                files.push(path);
+            }
+        }
+    }
+
+    /// Each `pub` declaration of `source`, on one line with the `cfg`
+    /// attributes right above it: a signature without its body, a constant
+    /// without its value, a field with its type.
+    fn declarations(source: &str) -> Vec<String> {
+        let mut declarations = Vec::new();
+        let mut cfgs: Vec<&str> = Vec::new();
+        let mut lines = source.lines().map(str::trim);
+        while let Some(line) = lines.next() {
+            if line.starts_with("#[cfg") {
+                cfgs.push(line);
+                continue;
+            }
+            if !line.starts_with("pub ") {
+                if !line.starts_with("#[") && !line.starts_with("///") {
+                    cfgs.clear();
+                }
+                continue;
+            }
+            // A field ends its line; a signature runs on to its body, and a
+            // `use` to its semicolon.
+            let mut declaration = line.to_string();
+            let is_use = line.starts_with("pub use ");
+            let complete = |d: &str| {
+                d.ends_with(';') || (!is_use && (d.ends_with('{') || d.contains(" = ")))
+            };
+            let mut done = complete(&declaration) || declaration.ends_with(',');
+            while !done {
+                let Some(next) = lines.next() else { break };
+                declaration.push(' ');
+                declaration.push_str(next);
+                done = complete(&declaration);
+            }
+            let declaration = match declaration.find(" = ") {
+                Some(value) if declaration.starts_with("pub const") || declaration.starts_with("pub static") => &declaration[..value],
+                _ => declaration.trim_end_matches('{').trim_end_matches(';').trim_end(),
+            };
+            let declaration = declaration.replace("{ ", "{").replace(", }", "}");
+            let mut entry = cfgs.join(" ");
+            if !entry.is_empty() {
+                entry.push(' ');
+            }
+            entry.push_str(&declaration);
+            declarations.push(entry);
+            cfgs.clear();
+        }
+        declarations
+    }
+
+    /// The public API as the snapshot lists it: a line per declaration,
+    /// under a `# <file>` line for each file.
+    fn public_api() -> String {
+        let mut files = Vec::new();
+        sources(&root().join("src"), &mut files);
+        let mut api = String::new();
+        for file in files {
+            let declarations = declarations(&fs::read_to_string(&file).unwrap());
+            if declarations.is_empty() {
+                continue;
+            }
+            let relative = file.strip_prefix(root()).unwrap().to_string_lossy().replace('\\', "/");
+            api.push_str(&format!("# {}\n", relative));
+            for declaration in declarations {
+                api.push_str(&declaration);
+                api.push('\n');
+            }
+        }
+        api
+    }
+
+    /// Fails when anything public was added, removed or changed, listing
+    /// what. Run with `UPDATE_PUBLIC_API=1` to accept the change, which
+    /// then shows up in review as a change to the snapshot.
+    #[test]
+    fn test_public_api_snapshot() {
+        let current = public_api();
+        let path = root().join(SNAPSHOT);
+        if env::var_os("UPDATE_PUBLIC_API").is_some() {
+            fs::write(&path, &current).unwrap();
+            return;
+        }
+        let snapshot = fs::read_to_string(&path).unwrap_or_default().replace("\r\n", "\n");
+        let missing = |from: &str, other: &str| -> Vec<String> {
+            from.lines().filter(|line| !line.starts_with('#') && !other.lines().any(|o| o == *line)).map(str::to_string).collect()
+        };
+        let (added, removed) = (missing(&current, &snapshot), missing(&snapshot, &current));
+        assert!(
+            added.is_empty() && removed.is_empty(),
+            "the public API changed; if that is intended, run `UPDATE_PUBLIC_API=1 cargo test --test public_api_test` \
+             and note the change in review\nadded:\n  {}\nremoved:\n  {}",
+            added.join("\n  "),
+            removed.join("\n  ")
+        );
+    }
+
+    #[test]
+    fn test_declarations() {
+        let source = "\
+            /// Docs.\n\
+            #[cfg(feature = \"fs\")]\n\
+            #[inline]\n\
+            pub fn scan<P>(path: P) -> Result<(), Error>\n\
+            where\n    P: AsRef<Path>,\n\
+            {\n}\n\
+            pub fn stream<P: AsRef<Path>>(\n    path: P,\n) -> Stream {\n}\n\
+            pub(crate) fn hidden() {}\n\
+            pub const LIMIT: usize = 4096;\n\
+            pub struct Options {\n    pub depth: usize,\n    private: bool,\n}\n";
+        assert_eq!(
+            declarations(source),
+            [
+                "#[cfg(feature = \"fs\")] pub fn scan<P>(path: P) -> Result<(), Error> where P: AsRef<Path>,",
+                "pub fn stream<P: AsRef<Path>>( path: P, ) -> Stream",
+                "pub const LIMIT: usize",
+                "pub struct Options",
+                "pub depth: usize,",
+            ]
+        );
+    }
+
+    /// The intended API is reachable from the crate root with the
+    /// signatures it is documented with.
+    #[cfg(all(feature = "binary", feature = "xml", feature = "fs"))]
+    #[test]
+    fn test_root_exports() {
+        use jobfileparser::{
+            parse_auto, parse_bytes, parse_file, scan_dir, Error, Job, JobBuilder, ParsedArtifact, Record,
+            ScanOptions, Task, TaskFlag, TaskStatus,
+        };
+        use std::ops::ControlFlow;
+
+        let _: fn(&Path, &[u8]) -> Result<ParsedArtifact, Error> = parse_auto;
+        let _: fn(&[u8]) -> Result<ParsedArtifact, Error> = parse_bytes;
+        let _: fn(PathBuf) -> Result<ParsedArtifact, Error> = parse_file::<PathBuf>;
+        let callback = |_: &Path, _: Result<ParsedArtifact, Error>| ControlFlow::Continue(());
+        let _ = scan_dir(root().join("tests").join("fixtures"), &ScanOptions::default(), callback);
+
+        let job: Job = JobBuilder::new("a.exe").build().unwrap();
+        let artifact = parse_bytes(&job.to_bytes()).unwrap();
+        assert!(matches!(&artifact, ParsedArtifact::Job(parsed) if parsed.name == job.name));
+        let record = Record::new(Path::new("a.job"), Ok(artifact));
+        assert_eq!(record.path(), "a.job");
+        let _: Option<&Task> = None;
+        let _: Option<(TaskFlag, TaskStatus)> = None;
+    }
+}
@@ -0,0 +1,199 @@
+#![cfg(all(feature = "binary", feature = "xml", feature = "serde"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use jobfileparser::record::{Origin, SCHEMA_VERSION};
+    use jobfileparser::{parse_bytes, JobBuilder, Record, UUID};
+    use std::path::Path;
+
+    const FIXTURES: [(&str, &[u8]); 3] = [
+        ("future_settings.xml", include_bytes!("fixtures/future_settings.xml")),
+        ("renamed_job.xml", include_bytes!("fixtures/renamed_job.xml")),
+        ("renamed_task.job", include_bytes!("fixtures/renamed_task.job")),
+    ];
+
+    fn records() -> Vec<Record> {
+        let hidden = JobBuilder::new("C:\\Windows\\System32\\wscript.exe")
+            .parameters("//B payload.vbs")
+            .uuid(&[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0, 1, 0, 2, 0, 3, 0, 4])
+            .flags(0x200)
+            .build().unwrap()
+            .to_bytes();
+        let mut inputs: Vec<(&str, Vec<u8>)> =
+            FIXTURES.iter().map(|(name, data)| (*name, data.to_vec())).collect();
+        inputs.push(("At3.job", job_bytes("\\\\server\\share\\x.exe")));
+        inputs.push(("hidden.job", hidden));
+        inputs.push(("truncated.job", job_bytes("a.exe")[..40].to_vec()));
+        inputs
+            .into_iter()
+            .map(|(name, data)| {
+                let mut record = Record::new(Path::new(name), parse_bytes(&data));
+                record.detect();
+                record.infer_history("2024-06-01T12:00:00");
+                #[cfg(feature = "raw")]
+                record.embed_raw(&data);
+                if name != "At3.job" && name.ends_with(".job") {
+                    let origin = Origin { hostname: Some("WS042".into()), evidence_id: Some("E03".into()), ..Origin::default() };
+                    record.set_origin(origin);
+                }
+                record
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_records_round_trip() {
+        for record in records() {
+            let json = serde_json::to_string(&record).unwrap();
+            let read: Record = serde_json::from_str(&json).unwrap();
+            assert_eq!(read, record, "{}", json);
+            assert_eq!(serde_json::to_string(&read).unwrap(), json);
+            assert_eq!(read.version_warning(), None);
+        }
+    }
+
+    #[test]
+    fn test_unknown_and_missing_fields() {
+        let record = &records()[3];
+        let mut json = serde_json::to_value(record).unwrap();
+        let object = json.as_object_mut().unwrap();
+        // Written by `scan --watch` and `--group-by-dir`.
+        object.insert("event".into(), "created".into());
+        object.insert("group".into(), "Tasks".into());
+        object.insert("added_later".into(), serde_json::json!({ "x": 1 }));
+        for optional in ["findings", "effective_command", "never_ran_but_scheduled", "comment"] {
+            object.remove(optional);
+        }
+        let read: Record = serde_json::from_value(json).unwrap();
+        assert!(read.findings().is_empty());
+        assert_eq!(read.at_job_index(), Some(3));
+    }
+
+    #[test]
+    fn test_version_warning() {
+        let mut json = serde_json::to_value(&records()[0]).unwrap();
+        json["schema_version"] = (SCHEMA_VERSION + 1).into();
+        let read: Record = serde_json::from_value(json.clone()).unwrap();
+        let warning = read.version_warning().unwrap();
+        assert!(warning.starts_with("future_settings.xml: written with record schema version"));
+
+        json.as_object_mut().unwrap().remove("schema_version");
+        let read: Record = serde_json::from_value(json).unwrap();
+        assert_eq!(read.schema_version(), 0);
+        assert!(read.version_warning().is_some());
+    }
+
+    /// The keys of the top-level object `json`, in the order written.
+    fn keys(json: &str) -> Vec<String> {
+        let (mut keys, mut depth, mut string, mut escaped) = (Vec::new(), 0, None, false);
+        for (i, c) in json.char_indices() {
+            match (string, c) {
+                (Some(_), _) if escaped => escaped = false,
+                (Some(_), '\\') => escaped = true,
+                (Some(start), '"') => {
+                    if depth == 1 && json[i + 1..].starts_with(':') {
+                        keys.push(json[start + 1..i].to_string());
+                    }
+                    string = None;
+                }
+                (Some(_), _) => {}
+                (None, '"') => string = Some(i),
+                (None, '{' | '[') => depth += 1,
+                (None, '}' | ']') => depth -= 1,
+                (None, _) => {}
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn test_schema_version_key_order() {
+        // The keys of schema version 2, in order. New fields may be added,
+        // but these must never change places; anything that would move them
+        // needs a new SCHEMA_VERSION, and a new list here.
+        assert_eq!(SCHEMA_VERSION, 2);
+        let job = [
+            "parse_status", "schema_version", "path", "findings", "name_entropy", "effective_command",
+            "indicators", "never_ran_but_scheduled", "effective_enabled", "enabled_reason",
+            "action_count", "has_exec", "has_comhandler", "user_kind", "hostname", "evidence_id",
+            "format", "product_info", "file_version", "uuid", "priority", "max_run_time", "exit_code",
+            "status", "status_name", "flags", "flag_names", "run_date", "scheduled_date", "name",
+            "parameters", "working_directory", "user", "comment", "state_bits",
+        ];
+        let task = [
+            "parse_status", "schema_version", "path", "findings", "warnings", "name_entropy",
+            "never_ran_but_scheduled", "effective_enabled", "enabled_reason", "trigger_count",
+            "action_count", "has_exec", "has_comhandler", "format", "registration_info",
+            "triggers", "settings", "actions", "principals", "unknown_elements",
+        ];
+        let records = records();
+        for (record, expected) in [(&records[4], &job[..]), (&records[0], &task[..])] {
+            let keys = keys(&serde_json::to_string(record).unwrap());
+            let mut found = keys.iter().filter(|key| expected.contains(&key.as_str()));
+            assert!(expected.iter().all(|key| found.next().is_some_and(|found| found == key)), "{:?}", keys);
+        }
+    }
+
+    #[test]
+    fn test_serialization_is_stable() {
+        // The same file parsed twice serializes to the same bytes, and so
+        // does what was read back from it.
+        let data = include_bytes!("fixtures/future_settings.xml");
+        let json: Vec<String> = (0..2)
+            .map(|_| {
+                let mut record = Record::new(Path::new("future_settings.xml"), parse_bytes(data));
+                record.detect();
+                serde_json::to_string(&record).unwrap()
+            })
+            .collect();
+        assert_eq!(json[0], json[1]);
+        let read: Record = serde_json::from_str(&json[0]).unwrap();
+        assert_eq!(serde_json::to_string(&read).unwrap(), json[0]);
+    }
+
+    #[test]
+    fn test_uuid_text() {
+        let uuid = UUID::new(&[0x67, 0x45, 0x23, 0x01, 0xab, 0x89, 0xef, 0xcd, 0, 1, 0, 2, 0, 3, 0, 4]);
+        assert_eq!(uuid.format_uuid(), "{01234567-89AB-CDEF-0001-000200030004}");
+        assert_eq!(UUID::parse_uuid("01234567-89ab-cdef-0001-000200030004"), Some(uuid));
+        assert_eq!(UUID::parse_uuid("{01234567-89AB-CDEF-0001-00020003}"), None);
+    }
+
+    #[test]
+    fn test_uuid_formats() {
+        use jobfileparser::UuidFormat;
+
+        let uuid = UUID::new(&[0x67, 0x45, 0x23, 0x01, 0xab, 0x89, 0xef, 0xcd, 0, 1, 0, 2, 0, 3, 0, 4]);
+        let forms = [
+            (UuidFormat::BracedUpper, "{01234567-89AB-CDEF-0001-000200030004}"),
+            (UuidFormat::BracedLower, "{01234567-89ab-cdef-0001-000200030004}"),
+            (UuidFormat::PlainUpper, "01234567-89AB-CDEF-0001-000200030004"),
+            (UuidFormat::PlainLower, "01234567-89ab-cdef-0001-000200030004"),
+        ];
+        for (format, text) in forms {
+            assert_eq!(uuid.format(format), text);
+            assert_eq!(UUID::parse_uuid(text).as_ref(), Some(&uuid), "{}", text);
+        }
+        assert_eq!(uuid.format(UuidFormat::default()), uuid.format_uuid());
+    }
+
+    #[test]
+    fn test_uuid_format_scope() {
+        use jobfileparser::UuidFormat;
+
+        let uuid = UUID::new(&[0x67, 0x45, 0x23, 0x01, 0xab, 0x89, 0xef, 0xcd, 0, 1, 0, 2, 0, 3, 0, 4]);
+        let json = || serde_json::to_string(&uuid).unwrap();
+        assert_eq!(json(), "\"{01234567-89AB-CDEF-0001-000200030004}\"");
+        let (outer, inner) = UuidFormat::PlainLower.scope(|| (json(), UuidFormat::BracedLower.scope(json)));
+        assert_eq!(outer, "\"01234567-89ab-cdef-0001-000200030004\"");
+        assert_eq!(inner, "\"{01234567-89ab-cdef-0001-000200030004}\"");
+        // The braced uppercase form again once the scope ends, even by a panic.
+        assert_eq!(json(), "\"{01234567-89AB-CDEF-0001-000200030004}\"");
+        let panicked = std::panic::catch_unwind(|| UuidFormat::PlainUpper.scope(|| panic!("in scope")));
+        assert!(panicked.is_err());
+        assert_eq!(json(), "\"{01234567-89AB-CDEF-0001-000200030004}\"");
+    }
+}
@@ -0,0 +1,160 @@
+#![cfg(all(feature = "binary", feature = "fs"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use jobfileparser::{list_dir, parse_file, scan_dir, ArtifactFormat, Error, ParsedArtifact, ScanOptions};
+    use std::fs;
+    use std::ops::ControlFlow;
+
+    #[test]
+    fn test_scan_dir_visits_jobs_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..3 {
+            fs::write(dir.path().join(format!("t{}.job", i)), job_bytes("a.exe")).unwrap();
+        }
+        fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+
+        let mut seen = Vec::new();
+        scan_dir(dir.path(), &ScanOptions::default(), |path, result| {
+            assert!(matches!(result, Ok(ParsedArtifact::Job(_))));
+            seen.push(path.file_name().unwrap().to_str().unwrap().to_string());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        assert_eq!(seen, ["t0.job", "t1.job", "t2.job"]);
+    }
+
+    #[test]
+    fn test_scan_dir_break_stops_reading() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..10 {
+            fs::write(dir.path().join(format!("t{}.job", i)), job_bytes("a.exe")).unwrap();
+        }
+
+        let mut calls = 0;
+        scan_dir(dir.path(), &ScanOptions::default(), |_, _| {
+            calls += 1;
+            if calls == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_scan_dir_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(dir.path().join("top.job"), job_bytes("a.exe")).unwrap();
+        fs::write(nested.join("inner.job"), job_bytes("b.exe")).unwrap();
+
+        let count = |recursive| {
+            let options = ScanOptions { recursive, ..ScanOptions::default() };
+            let mut count = 0;
+            scan_dir(dir.path(), &options, |_, _| {
+                count += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+            count
+        };
+
+        assert_eq!(count(false), 1);
+        assert_eq!(count(true), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_dir_does_not_follow_directory_links() {
+        use std::os::unix::fs::symlink;
+        use std::path::Path;
+
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("a.job"), job_bytes("a.exe")).unwrap();
+        // A loop back up the tree, and a link to a file.
+        symlink(dir.path(), nested.join("loop")).unwrap();
+        symlink(nested.join("a.job"), dir.path().join("b.job")).unwrap();
+
+        let options = ScanOptions { recursive: true, ..ScanOptions::default() };
+        let mut seen = Vec::new();
+        scan_dir(dir.path(), &options, |path, result| {
+            assert!(result.is_ok(), "{}", path.display());
+            seen.push(path.strip_prefix(dir.path()).unwrap().to_path_buf());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(seen, [Path::new("b.job"), &Path::new("nested").join("a.job")]);
+    }
+
+    #[test]
+    fn test_list_dir_does_not_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.job"), b"garbage").unwrap();
+        fs::write(dir.path().join("b.xml"), b"garbage").unwrap();
+
+        let mut seen = Vec::new();
+        list_dir(dir.path(), &ScanOptions::default(), |path, result| {
+            assert!(result.is_ok());
+            seen.push(ArtifactFormat::for_path(path));
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        assert_eq!(seen, [ArtifactFormat::Binary, ArtifactFormat::Xml]);
+    }
+
+    #[test]
+    fn test_scan_dir_reports_empty_and_oversized_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.job"), b"").unwrap();
+        fs::write(dir.path().join("b.job"), vec![0; 100]).unwrap();
+
+        let options = ScanOptions {
+            max_file_size: 99,
+            ..ScanOptions::default()
+        };
+        let mut errors = Vec::new();
+        scan_dir(dir.path(), &options, |_, result| {
+            errors.push(result.unwrap_err());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        assert!(matches!(errors[0], Error::EmptyFile));
+        assert!(matches!(errors[1], Error::TooLarge { size: 100, limit: 99 }));
+        assert!(matches!(parse_file(dir.path().join("a.job")), Err(Error::EmptyFile)));
+    }
+
+    #[test]
+    fn test_content_decides_format_unless_strict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("renamed.xml");
+        fs::write(&path, job_bytes("a.exe")).unwrap();
+        assert_eq!(ArtifactFormat::for_path(&path), ArtifactFormat::Xml);
+        assert_eq!(ArtifactFormat::sniff(&fs::read(&path).unwrap()), ArtifactFormat::Binary);
+        assert!(matches!(parse_file(&path), Ok(ParsedArtifact::Job(_))));
+
+        let strict = ScanOptions {
+            strict_extension: true,
+            ..ScanOptions::default()
+        };
+        for (options, parses) in [(ScanOptions::default(), true), (strict, false)] {
+            scan_dir(dir.path(), &options, |_, result| {
+                assert_eq!(result.is_ok(), parses);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::schedlog::{
+        parse_log_time, parse_operational_csv, parse_schedlgu, EntryKind, LogEntry,
+    };
+    use jobfileparser::{Case, ExecutionHistory, ExecutionLog};
+
+    fn utf16(text: &str) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xFE];
+        for unit in text.encode_utf16() {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_schedlgu_entries() {
+        let text = "\"Task Scheduler Service\"\r\n\
+                    \tStarted at 3/1/2024 8:00:00 AM\r\n\
+                    \"Backup.job\" (backup.exe) \r\n\
+                    \tStarted 3/4/2024 2:13:00 AM\r\n\
+                    \"Backup.job\" (backup.exe) \r\n\
+                    \tFinished 3/4/2024 2:15:10 AM\r\n\
+                    \tResult: The task completed with an exit code of (1).\r\n\
+                    \"Sync.job\" (sync.exe) 3/4/2024 1:00:00 PM ** ERROR **\r\n\
+                    \tThe attempt to log on to the account associated with the task failed.\r\n";
+        let entries = parse_schedlgu(text);
+        assert_eq!(
+            entries,
+            [
+                LogEntry {
+                    task: "Backup.job".to_string(),
+                    kind: EntryKind::Started,
+                    time: Some("2024-03-04T02:13:00".to_string()),
+                    exit_code: None,
+                },
+                LogEntry {
+                    task: "Backup.job".to_string(),
+                    kind: EntryKind::Finished,
+                    time: Some("2024-03-04T02:15:10".to_string()),
+                    exit_code: Some(1),
+                },
+                LogEntry {
+                    task: "Sync.job".to_string(),
+                    kind: EntryKind::Failed,
+                    time: Some("2024-03-04T13:00:00".to_string()),
+                    exit_code: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedlgu_wraps_around() {
+        // The newest entries come first, then the marker, then what is left
+        // of the oldest ones: a fragment, and an entry whose last line the
+        // wrap carried over to the start of the file.
+        let text = "ted 3/5/2024 9:00:00 AM\r\n\
+                    \"New.job\" (new.exe) \r\n\
+                    \tStarted 3/6/2024 9:00:00 AM\r\n\
+                    [ ***** Most recent entry is above this line ***** ]\r\n\
+                    ed 3/1/2024 9:00:00 AM\r\n\
+                    \tResult: The task completed with an exit code of (0).\r\n\
+                    \"Old.job\" (old.exe) \r\n\
+                    \tStar";
+        let log = ExecutionLog::parse(&utf16(text));
+        let names: Vec<&str> = log.tasks().map(|(name, _)| name).collect();
+        assert_eq!(names, ["New.job", "Old.job"]);
+        let old = log.history("C:\\Windows\\Tasks\\old.job").unwrap();
+        assert_eq!(old.runs, 1);
+        assert_eq!(old.first_seen.as_deref(), Some("2024-03-05T09:00:00"));
+        let new = log.history("New.job").unwrap();
+        assert_eq!(new.last_seen.as_deref(), Some("2024-03-06T09:00:00"));
+        assert!(log.history("Missing.job").is_none());
+    }
+
+    #[test]
+    fn test_operational_csv() {
+        let csv = "Level,Date and Time,Source,Event ID,Task Category\r\n\
+                   Information,3/4/2024 2:13:00 AM,Microsoft-Windows-TaskScheduler,100,Task Started,\
+                   \"Task Scheduler started \"\"{1}\"\" instance of the \"\"\\Microsoft\\Updater\"\" task \
+                   for user \"\"CORP\\admin\"\".\"\r\n\
+                   Information,3/4/2024 2:13:05 AM,Microsoft-Windows-TaskScheduler,201,Action completed,\
+                   \"Task Scheduler successfully completed task \"\"\\Microsoft\\Updater\"\" , instance \
+                   \"\"{1}\"\" , action \"\"C:\\Tools\\updater.exe\"\" with return code 2147942402.\r\n\
+                   Second line.\"\r\n\
+                   Information,3/4/2024 2:14:00 AM,Microsoft-Windows-TaskScheduler,140,Task updated,\
+                   \"User \"\"CORP\\admin\"\" updated Task Scheduler task \"\"\\Microsoft\\Updater\"\"\"\r\n\
+                   Error,3/5/2024 2:13:00 AM,Microsoft-Windows-TaskScheduler,101,Task Start Failed,\
+                   \"Task Scheduler failed to start \"\"\\Ghost\"\" task for user \"\"CORP\\admin\"\".\"\r\n";
+        let entries = parse_operational_csv(csv);
+        let kinds: Vec<(&str, EntryKind)> = entries.iter().map(|e| (e.task.as_str(), e.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("\\Microsoft\\Updater", EntryKind::Started),
+                ("\\Microsoft\\Updater", EntryKind::Finished),
+                ("\\Ghost", EntryKind::Failed),
+            ]
+        );
+        assert_eq!(entries[1].exit_code, Some(2147942402));
+
+        let log = ExecutionLog::parse(csv.as_bytes());
+        assert_eq!(
+            log.history("C:\\Windows\\System32\\Tasks\\Microsoft\\Updater"),
+            Some(&ExecutionHistory {
+                runs: 1,
+                failures: 0,
+                first_seen: Some("2024-03-04T02:13:00".to_string()),
+                last_seen: Some("2024-03-04T02:13:05".to_string()),
+                last_started: Some("2024-03-04T02:13:00".to_string()),
+                exit_codes: vec![2147942402],
+            })
+        );
+        assert_eq!(log.history("Ghost").unwrap().failures, 1);
+    }
+
+    #[test]
+    fn test_export_csv_columns() {
+        let csv = "\"TimeCreated\",\"Id\",\"Message\"\n\
+                   \"2024-03-04 02:13:00\",\"100\",\"Task Scheduler started \"\"{1}\"\" instance of the \
+                   \"\"\\Agent\"\" task.\"\n";
+        let log = ExecutionLog::parse(csv.as_bytes());
+        assert_eq!(log.history("Agent").unwrap().last_seen.as_deref(), Some("2024-03-04T02:13:00"));
+    }
+
+    #[test]
+    fn test_log_times() {
+        assert_eq!(parse_log_time("12/31/2023 12:05:00 AM").as_deref(), Some("2023-12-31T00:05:00"));
+        assert_eq!(parse_log_time("1/2/2024 12:05 PM").as_deref(), Some("2024-01-02T12:05:00"));
+        assert_eq!(parse_log_time("2024-01-02T03:04:05.123Z").as_deref(), Some("2024-01-02T03:04:05"));
+        assert_eq!(parse_log_time("2024-01-02T03:04:05-05:00").as_deref(), Some("2024-01-02T03:04:05"));
+        assert_eq!(parse_log_time("13/2/2024 1:00:00"), None);
+        assert_eq!(parse_log_time("3/4/2024"), None);
+    }
+
+    #[test]
+    fn test_task_names_ignore_case() {
+        let text = "\"Sauvegarde Données.job\" (a.exe) \r\n\tStarted 3/4/2024 2:13:00 AM\r\n\
+                    \"İşlem.job\" (b.exe) \r\n\tStarted 3/4/2024 3:00:00 AM\r\n";
+        let log = ExecutionLog::parse(&utf16(text));
+        assert_eq!(log.history("C:\\Windows\\Tasks\\SAUVEGARDE DONNÉES.JOB").unwrap().runs, 1);
+        assert_eq!(log.history("iŞLEM.job").unwrap().runs, 1);
+        // Names are kept as logged.
+        let names: Vec<&str> = log.tasks().map(|(name, _)| name).collect();
+        assert_eq!(names, ["İşlem.job", "Sauvegarde Données.job"]);
+
+        let log = ExecutionLog::parse_with(&utf16(text), Case::Sensitive);
+        assert!(log.history("SAUVEGARDE DONNÉES.job").is_none());
+        assert!(log.history("iŞLEM.job").is_none());
+        assert_eq!(log.history("Tasks\\İşlem.xml").unwrap().runs, 1);
+    }
+}
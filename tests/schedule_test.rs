@@ -0,0 +1,375 @@
+#[cfg(feature = "binary")]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::date::{format_unix, local_seconds};
+    use jobfileparser::schedule::{ExpectedRun, Recurrence, Repetition, Schedule, TimeTrigger};
+
+    fn t(text: &str) -> i64 {
+        local_seconds(text).unwrap()
+    }
+
+    fn trigger(start: &str, recurrence: Recurrence) -> TimeTrigger {
+        TimeTrigger { start: t(start), end: None, recurrence, repetition: None, random_delay: 0 }
+    }
+
+    fn schedule(triggers: Vec<TimeTrigger>) -> Schedule {
+        Schedule { time_triggers: triggers, ..Schedule::default() }
+    }
+
+    /// The nearest run to `at`, written out, and how far it is in seconds.
+    fn nearest(schedule: &Schedule, at: &str) -> (String, i64) {
+        let ExpectedRun { time, distance } = schedule.nearest_run(t(at)).unwrap();
+        (format_unix(time as u64), distance)
+    }
+
+    #[test]
+    fn test_daily() {
+        let daily = schedule(vec![trigger("2024-03-01T03:00:00", Recurrence::Daily { every: 1 })]);
+        assert_eq!(nearest(&daily, "2024-03-04T03:02:00"), ("2024-03-04T03:00:00".to_string(), 120));
+        assert_eq!(nearest(&daily, "2024-03-04T02:59:30"), ("2024-03-04T03:00:00".to_string(), 30));
+        assert_eq!(nearest(&daily, "2024-03-04T14:37:00"), ("2024-03-04T03:00:00".to_string(), 41820));
+        // Before the first run, and just before midnight.
+        assert_eq!(nearest(&daily, "2024-02-20T03:00:00").0, "2024-03-01T03:00:00");
+        assert_eq!(nearest(&daily, "2024-03-04T23:59:00").0, "2024-03-05T03:00:00");
+
+        // Every other day from the 1st; equally close runs give the earlier.
+        let every_other = schedule(vec![trigger("2024-03-01T03:00:00", Recurrence::Daily { every: 2 })]);
+        assert_eq!(nearest(&every_other, "2024-03-04T03:00:00"), ("2024-03-03T03:00:00".to_string(), 86400));
+        assert_eq!(nearest(&every_other, "2024-03-05T03:00:00").1, 0);
+    }
+
+    #[test]
+    fn test_weekly() {
+        // Mondays and Wednesdays every other week from Friday 1 March, whose
+        // week has neither.
+        let weekly = schedule(vec![trigger(
+            "2024-03-01T09:00:00",
+            Recurrence::Weekly { every: 2, days: 0b1010 },
+        )]);
+        assert_eq!(nearest(&weekly, "2024-03-04T09:00:00").0, "2024-03-11T09:00:00");
+        assert_eq!(nearest(&weekly, "2024-03-13T09:00:00").1, 0);
+        assert_eq!(nearest(&weekly, "2024-03-18T09:00:00").0, "2024-03-13T09:00:00");
+        assert_eq!(nearest(&weekly, "2024-03-25T09:00:00").1, 0);
+    }
+
+    #[test]
+    fn test_monthly_dates() {
+        // The 15th and the last day of every month.
+        let monthly = schedule(vec![trigger(
+            "2023-01-01T10:00:00",
+            Recurrence::MonthlyDate { days: 1 << 14, last_day: true, months: 0xFFF },
+        )]);
+        for day in ["2024-02-29", "2023-02-28", "2024-04-30", "2024-12-31", "2024-06-15"] {
+            assert_eq!(nearest(&monthly, &format!("{}T10:00:00", day)).1, 0, "{}", day);
+        }
+        assert_eq!(nearest(&monthly, "2024-02-28T10:00:00").0, "2024-02-29T10:00:00");
+        assert_eq!(nearest(&monthly, "2024-04-29T10:00:00").0, "2024-04-30T10:00:00");
+
+        // 29 February only, years apart.
+        let leap = schedule(vec![trigger(
+            "2020-01-01T10:00:00",
+            Recurrence::MonthlyDate { days: 1 << 28, last_day: false, months: 1 << 1 },
+        )]);
+        assert_eq!(nearest(&leap, "2023-06-01T10:00:00").0, "2024-02-29T10:00:00");
+        assert_eq!(nearest(&leap, "2021-06-01T10:00:00").0, "2020-02-29T10:00:00");
+    }
+
+    #[test]
+    fn test_monthly_days_of_week() {
+        // The last Friday and the second Tuesday of March.
+        let last_friday = trigger("2024-01-01T08:00:00", Recurrence::MonthlyDow { weeks: 1 << 4, days: 1 << 5, months: 1 << 2 });
+        let second_tuesday = trigger("2024-01-01T08:00:00", Recurrence::MonthlyDow { weeks: 1 << 1, days: 1 << 2, months: 1 << 2 });
+        let both = schedule(vec![last_friday, second_tuesday]);
+        assert_eq!(nearest(&both, "2024-03-29T08:00:00").1, 0);
+        assert_eq!(nearest(&both, "2024-03-12T08:00:00").1, 0);
+        assert_eq!(nearest(&both, "2024-03-22T08:00:00").0, "2024-03-29T08:00:00");
+        assert_eq!(nearest(&both, "2024-03-05T08:00:00").0, "2024-03-12T08:00:00");
+        // In 2025 the last Friday of March is the 28th.
+        assert_eq!(nearest(&both, "2025-03-28T08:00:00").1, 0);
+    }
+
+    #[test]
+    fn test_repetition() {
+        // Every 15 minutes for an hour from 09:00 every day.
+        let mut repeated = trigger("2024-03-01T09:00:00", Recurrence::Daily { every: 1 });
+        repeated.repetition = Some(Repetition { interval: 15 * 60, duration: Some(3600) });
+        let repeated = schedule(vec![repeated]);
+        assert_eq!(nearest(&repeated, "2024-03-04T09:45:00").1, 0);
+        assert_eq!(nearest(&repeated, "2024-03-04T10:00:00").1, 0);
+        assert_eq!(nearest(&repeated, "2024-03-04T09:50:00"), ("2024-03-04T09:45:00".to_string(), 300));
+        assert_eq!(nearest(&repeated, "2024-03-04T10:20:00"), ("2024-03-04T10:00:00".to_string(), 1200));
+
+        // Every 15 minutes from 09:00 on 1 March, indefinitely.
+        let mut forever = trigger("2024-03-01T09:00:00", Recurrence::Once);
+        forever.repetition = Some(Repetition { interval: 15 * 60, duration: None });
+        let forever = schedule(vec![forever]);
+        assert_eq!(nearest(&forever, "2024-06-04T17:07:00"), ("2024-06-04T17:00:00".to_string(), 420));
+        assert_eq!(nearest(&forever, "2024-06-04T17:15:00").1, 0);
+        assert_eq!(nearest(&forever, "2024-03-01T08:00:00").0, "2024-03-01T09:00:00");
+
+        // Repeating until the next start: every 7 minutes from each 09:00,
+        // which 24 hours are not a multiple of.
+        let mut until_next = trigger("2024-03-01T09:00:00", Recurrence::Daily { every: 1 });
+        until_next.repetition = Some(Repetition { interval: 7 * 60, duration: None });
+        let until_next = schedule(vec![until_next]);
+        assert_eq!(nearest(&until_next, "2024-03-02T08:55:00").1, 0);
+        assert_eq!(nearest(&until_next, "2024-03-02T08:57:00"), ("2024-03-02T08:55:00".to_string(), 120));
+        assert_eq!(nearest(&until_next, "2024-03-02T09:07:00").1, 0);
+        assert_eq!(nearest(&until_next, "2024-03-02T09:03:00").1, 180);
+    }
+
+    #[test]
+    fn test_runs_between() {
+        let runs = |schedule: &Schedule, from: &str, to: &str, limit: usize| -> Vec<String> {
+            let runs = schedule.runs_between(t(from), t(to), limit);
+            runs.into_iter().map(|run| format_unix(run as u64)).collect()
+        };
+        let daily = schedule(vec![trigger("2024-03-01T03:00:00", Recurrence::Daily { every: 1 })]);
+        assert_eq!(
+            runs(&daily, "2024-03-04T03:00:00", "2024-03-06T03:00:00", 10),
+            ["2024-03-04T03:00:00", "2024-03-05T03:00:00", "2024-03-06T03:00:00"]
+        );
+        assert_eq!(runs(&daily, "2024-02-01T00:00:00", "2024-03-02T00:00:00", 10), ["2024-03-01T03:00:00"]);
+        assert_eq!(runs(&daily, "2024-03-04T03:00:00", "2024-03-30T00:00:00", 2).len(), 2);
+
+        // Repetitions started the day before are still going at midnight,
+        // and a second trigger at the same time is one run.
+        let mut repeated = trigger("2024-03-01T23:00:00", Recurrence::Daily { every: 1 });
+        repeated.repetition = Some(Repetition { interval: 30 * 60, duration: Some(2 * 3600) });
+        let both = schedule(vec![repeated, trigger("2024-03-01T00:30:00", Recurrence::Daily { every: 1 })]);
+        assert_eq!(
+            runs(&both, "2024-03-04T00:00:00", "2024-03-04T01:00:00", 10),
+            ["2024-03-04T00:00:00", "2024-03-04T00:30:00", "2024-03-04T01:00:00"]
+        );
+        assert!(runs(&schedule(Vec::new()), "2024-03-04T00:00:00", "2024-03-05T00:00:00", 10).is_empty());
+    }
+
+    #[test]
+    fn test_end_and_random_delay() {
+        let mut ending = trigger("2024-03-01T03:00:00", Recurrence::Daily { every: 1 });
+        ending.end = Some(t("2024-03-05T23:59:59"));
+        let ending = schedule(vec![ending]);
+        assert_eq!(nearest(&ending, "2024-03-10T03:00:00").0, "2024-03-05T03:00:00");
+        assert_eq!(nearest(&ending, "2024-03-05T03:00:00").1, 0);
+
+        // Up to an hour late is on time.
+        let mut delayed = trigger("2024-03-01T09:00:00", Recurrence::Once);
+        delayed.random_delay = 3600;
+        let delayed = schedule(vec![delayed]);
+        assert_eq!(nearest(&delayed, "2024-03-01T09:40:00").1, 0);
+        assert_eq!(nearest(&delayed, "2024-03-01T10:10:00").1, 600);
+        assert_eq!(nearest(&delayed, "2024-03-01T08:50:00").1, 600);
+    }
+
+    #[test]
+    fn test_nearest_of_several_triggers() {
+        let triggers = schedule(vec![
+            trigger("2024-03-01T03:00:00", Recurrence::Daily { every: 1 }),
+            // Sundays.
+            trigger("2024-03-01T14:30:00", Recurrence::Weekly { every: 1, days: 1 }),
+        ]);
+        assert_eq!(nearest(&triggers, "2024-03-03T14:32:00"), ("2024-03-03T14:30:00".to_string(), 120));
+        assert_eq!(nearest(&triggers, "2024-03-04T14:32:00").0, "2024-03-04T03:00:00");
+        assert_eq!(nearest(&triggers, "2024-03-04T15:32:00").0, "2024-03-05T03:00:00");
+        assert_eq!(Schedule::default().nearest_run(t("2024-03-04T14:32:00")), None);
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_job_schedule() {
+        use super::common::{trigger_bytes, with_triggers};
+        use jobfileparser::consts::trigger::{self, TriggerType};
+        use jobfileparser::job::schedule;
+        use jobfileparser::JobBuilder;
+
+        let start = [(trigger::BEGIN_YEAR, 2024), (trigger::BEGIN_MONTH, 3), (trigger::BEGIN_DAY, 1)];
+        let with = |fields: &[(jobfileparser::consts::Field, u32)]| {
+            trigger_bytes(&start.iter().chain(fields).copied().collect::<Vec<_>>())
+        };
+        let data = with_triggers(
+            JobBuilder::new("a.exe").build().unwrap().to_bytes(),
+            &[
+                // Daily at 03:30 to 10 March, every 10 minutes for an hour.
+                with(&[
+                    (trigger::TRIGGER_TYPE, TriggerType::Daily as u32),
+                    (trigger::TRIGGER_SPECIFIC0, 1),
+                    (trigger::START_HOUR, 3),
+                    (trigger::START_MINUTE, 30),
+                    (trigger::FLAGS, trigger::FLAG_HAS_END_DATE),
+                    (trigger::END_YEAR, 2024),
+                    (trigger::END_MONTH, 3),
+                    (trigger::END_DAY, 10),
+                    (trigger::MINUTES_INTERVAL, 10),
+                    (trigger::MINUTES_DURATION, 60),
+                ]),
+                // Disabled.
+                with(&[(trigger::TRIGGER_TYPE, TriggerType::Once as u32), (trigger::FLAGS, trigger::FLAG_DISABLED)]),
+                // The last Sunday of every month.
+                with(&[
+                    (trigger::TRIGGER_TYPE, TriggerType::MonthlyDow as u32),
+                    (trigger::TRIGGER_SPECIFIC0, 5),
+                    (trigger::TRIGGER_SPECIFIC1, 1),
+                    (trigger::TRIGGER_SPECIFIC2, 0xFFF),
+                ]),
+                // The 1st and 31st of January.
+                with(&[
+                    (trigger::TRIGGER_TYPE, TriggerType::MonthlyDate as u32),
+                    (trigger::TRIGGER_SPECIFIC0, 1),
+                    (trigger::TRIGGER_SPECIFIC1, 1 << 14),
+                    (trigger::TRIGGER_SPECIFIC2, 1),
+                ]),
+                with(&[(trigger::TRIGGER_TYPE, TriggerType::AtLogon as u32)]),
+                // Month 13.
+                with(&[(trigger::TRIGGER_TYPE, TriggerType::Once as u32), (trigger::BEGIN_MONTH, 13)]),
+            ],
+        );
+        let found = schedule(&data);
+        assert_eq!(found.event_triggers, 1);
+        assert!(!found.catches_up);
+        assert_eq!(
+            found.time_triggers,
+            [
+                TimeTrigger {
+                    start: t("2024-03-01T03:30:00"),
+                    end: Some(t("2024-03-10T23:59:59")),
+                    recurrence: Recurrence::Daily { every: 1 },
+                    repetition: Some(Repetition { interval: 600, duration: Some(3600) }),
+                    random_delay: 0,
+                },
+                trigger("2024-03-01T00:00:00", Recurrence::MonthlyDow { weeks: 1 << 4, days: 1, months: 0xFFF }),
+                trigger(
+                    "2024-03-01T00:00:00",
+                    Recurrence::MonthlyDate { days: 1 | 1 << 30, last_day: false, months: 1 },
+                ),
+            ]
+        );
+        assert_eq!(schedule(&JobBuilder::new("a.exe").build().unwrap().to_bytes()), Schedule::default());
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_task_schedule() {
+        use jobfileparser::Task;
+
+        let task = |triggers: &str, settings: &str| {
+            let xml = format!(
+                "\u{FEFF}<Task><RegistrationInfo/><Triggers>{}</Triggers><Settings>{}</Settings>\
+                 <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+                triggers, settings
+            );
+            Task::from_bytes(xml.as_bytes()).unwrap().schedule()
+        };
+        let found = task(
+            "<CalendarTrigger>\
+               <StartBoundary>2024-03-01T09:00:00+01:00</StartBoundary>\
+               <EndBoundary>2025-03-01T00:00:00</EndBoundary>\
+               <Repetition><Interval>PT30M</Interval></Repetition>\
+               <RandomDelay>PT5M</RandomDelay>\
+               <ScheduleByWeek><WeeksInterval>2</WeeksInterval>\
+                 <DaysOfWeek><Monday /><Friday /></DaysOfWeek></ScheduleByWeek>\
+             </CalendarTrigger>\
+             <CalendarTrigger><StartBoundary>2024-03-01T10:00:00</StartBoundary>\
+               <ScheduleByMonth><DaysOfMonth><Day>1</Day><Day>Last</Day></DaysOfMonth>\
+                 <Months><June /><December /></Months></ScheduleByMonth>\
+             </CalendarTrigger>\
+             <CalendarTrigger><StartBoundary>2024-03-01T11:00:00</StartBoundary>\
+               <ScheduleByMonthDayOfWeek><Weeks><Week>2</Week><Week>Last</Week></Weeks>\
+                 <DaysOfWeek><Sunday /></DaysOfWeek></ScheduleByMonthDayOfWeek>\
+             </CalendarTrigger>\
+             <TimeTrigger><StartBoundary>2024-03-01T12:00:00</StartBoundary></TimeTrigger>\
+             <TimeTrigger><StartBoundary>2024-03-01T13:00:00</StartBoundary><Enabled>false</Enabled></TimeTrigger>\
+             <CalendarTrigger><StartBoundary>2024-03-01T14:00:00</StartBoundary>\
+               <ScheduleByDay /></CalendarTrigger>\
+             <LogonTrigger />\
+             <TimeTrigger><StartBoundary>soon</StartBoundary></TimeTrigger>",
+            "",
+        );
+        assert_eq!(found.event_triggers, 1);
+        assert!(!found.catches_up);
+        assert_eq!(
+            found.time_triggers,
+            [
+                TimeTrigger {
+                    start: t("2024-03-01T09:00:00"),
+                    end: Some(t("2025-03-01T00:00:00")),
+                    recurrence: Recurrence::Weekly { every: 2, days: 0b100010 },
+                    repetition: Some(Repetition { interval: 1800, duration: None }),
+                    random_delay: 300,
+                },
+                trigger(
+                    "2024-03-01T10:00:00",
+                    Recurrence::MonthlyDate { days: 1, last_day: true, months: 1 << 5 | 1 << 11 },
+                ),
+                trigger(
+                    "2024-03-01T11:00:00",
+                    Recurrence::MonthlyDow { weeks: 1 << 1 | 1 << 4, days: 1, months: 0xFFF },
+                ),
+                trigger("2024-03-01T14:00:00", Recurrence::Daily { every: 1 }),
+                trigger("2024-03-01T12:00:00", Recurrence::Once),
+            ]
+        );
+        let catching_up = task(
+            "<TimeTrigger><StartBoundary>2024-03-01T12:00:00</StartBoundary></TimeTrigger>",
+            "<StartWhenAvailable>true</StartWhenAvailable>",
+        );
+        assert!(catching_up.catches_up);
+    }
+
+    #[test]
+    fn test_recurrence_in_words() {
+        assert_eq!(Recurrence::Once.to_string(), "once");
+        assert_eq!(Recurrence::Daily { every: 1 }.to_string(), "daily");
+        assert_eq!(Recurrence::Daily { every: 3 }.to_string(), "every 3 days");
+        assert_eq!(Recurrence::Weekly { every: 1, days: 0b1000001 }.to_string(), "weekly on Sun, Sat");
+        assert_eq!(Recurrence::Weekly { every: 2, days: 0b100010 }.to_string(), "every 2 weeks on Mon, Fri");
+        assert_eq!(
+            Recurrence::MonthlyDate { days: 1 | 1 << 14, last_day: true, months: 0xFFF }.to_string(),
+            "monthly on day 1, 15, last"
+        );
+        assert_eq!(
+            Recurrence::MonthlyDow { weeks: 1 << 1 | 1 << 4, days: 1 << 2, months: 1 << 2 | 1 << 11 }.to_string(),
+            "monthly on the second, last Tue in Mar, Dec"
+        );
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_task_trigger_details() {
+        use jobfileparser::schedule::TriggerDetail;
+        use jobfileparser::Task;
+
+        let xml = "\u{FEFF}<Task><RegistrationInfo/><Settings/><Triggers>\
+                   <CalendarTrigger><StartBoundary>2024-03-01T09:00:00</StartBoundary>\
+                     <EndBoundary>2025-03-01T00:00:00</EndBoundary><Enabled>false</Enabled>\
+                     <Repetition><Interval>PT30M</Interval><Duration>PT2H</Duration></Repetition>\
+                     <ScheduleByDay><DaysInterval>2</DaysInterval></ScheduleByDay></CalendarTrigger>\
+                   <BootTrigger/><WnfStateChangeTrigger/></Triggers>\
+                   <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>";
+        let detail = |kind: &str, summary: &str| TriggerDetail {
+            kind: kind.into(),
+            start: None,
+            end: None,
+            enabled: true,
+            summary: summary.into(),
+            interval: None,
+            duration: None,
+        };
+        assert_eq!(
+            Task::from_bytes(xml.as_bytes()).unwrap().trigger_details(),
+            [
+                TriggerDetail {
+                    start: Some("2024-03-01T09:00:00".into()),
+                    end: Some("2025-03-01T00:00:00".into()),
+                    enabled: false,
+                    interval: Some("PT30M".into()),
+                    duration: Some("PT2H".into()),
+                    ..detail("CalendarTrigger", "every 2 days")
+                },
+                detail("BootTrigger", "at system start"),
+                detail("WnfStateChangeTrigger", "unknown"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "xml")]
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::sddl::{describe_account, SecurityDescriptor};
+    use jobfileparser::Task;
+
+    #[test]
+    fn test_task_folder_default() {
+        // The descriptor schtasks gives a task registered by an administrator.
+        let sd = SecurityDescriptor::parse("D:(A;;FA;;;BA)(A;;FA;;;SY)(A;;FR;;;AU)").unwrap();
+        assert_eq!(sd.owner, None);
+        assert_eq!(sd.sacl, None);
+        let dacl = sd.dacl.unwrap();
+        assert!(dacl.flags.is_empty());
+        let lines: Vec<String> = dacl.aces.iter().map(|ace| ace.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "Allow BUILTIN\\Administrators (BA): FILE_ALL_ACCESS",
+                "Allow NT AUTHORITY\\SYSTEM (SY): FILE_ALL_ACCESS",
+                "Allow Authenticated Users (AU): FILE_GENERIC_READ",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_owner_group_and_inheritance() {
+        let sd = SecurityDescriptor::parse("O:BAG:SYD:PAI(A;OICI;FA;;;SY)(A;OICI;FA;;;BA)(A;OICIIO;GA;;;CO)").unwrap();
+        assert_eq!(sd.owner.unwrap().to_string(), "BUILTIN\\Administrators (BA)");
+        assert_eq!(sd.group.unwrap().name, Some("NT AUTHORITY\\SYSTEM"));
+        let dacl = sd.dacl.unwrap();
+        assert_eq!(dacl.flags, ["protected", "auto-inherited"]);
+        let creator = &dacl.aces[2];
+        assert_eq!(creator.flags, ["object inherit", "container inherit", "inherit only"]);
+        assert_eq!(creator.rights, ["GENERIC_ALL"]);
+        assert_eq!(creator.trustee.name, Some("CREATOR OWNER"));
+    }
+
+    #[test]
+    fn test_sacl_audit_and_label() {
+        let sd = SecurityDescriptor::parse(
+            "O:SYG:SYD:(A;;0x1200a9;;;BU)(D;;WDWO;;;WD)S:AI(AU;SAFA;FA;;;WD)(ML;;NRNWNX;;;HI)",
+        )
+        .unwrap();
+        let dacl = sd.dacl.unwrap();
+        assert_eq!(dacl.aces[0].rights, ["0x1200a9"]);
+        assert_eq!(dacl.aces[1].ace_type, "Deny");
+        assert_eq!(dacl.aces[1].rights, ["WRITE_DAC", "WRITE_OWNER"]);
+        let sacl = sd.sacl.unwrap();
+        assert_eq!(sacl.flags, ["auto-inherited"]);
+        assert_eq!(sacl.aces[0].ace_type, "Audit");
+        assert_eq!(sacl.aces[0].flags, ["audit success", "audit failure"]);
+        assert_eq!(sacl.aces[1].ace_type, "Mandatory label");
+        assert_eq!(sacl.aces[1].rights, ["NO_READ_UP", "NO_WRITE_UP", "NO_EXECUTE_UP"]);
+        assert_eq!(sacl.aces[1].trustee.name, Some("High Mandatory Level"));
+    }
+
+    #[test]
+    fn test_domain_sids_and_conditional_aces() {
+        let sd = SecurityDescriptor::parse(
+            "O:S-1-5-21-3623811015-3361044348-30300820-1013G:S-1-5-32-544\
+             D:(XA;;FX;;;S-1-1-0;(@User.Title==\"PM\"))",
+        )
+        .unwrap();
+        let owner = sd.owner.unwrap();
+        assert_eq!(owner.sid, "S-1-5-21-3623811015-3361044348-30300820-1013");
+        assert_eq!(owner.name, None);
+        assert_eq!(sd.group.unwrap().name, Some("BUILTIN\\Administrators"));
+        let ace = &sd.dacl.unwrap().aces[0];
+        assert_eq!(ace.to_string(), "Callback allow Everyone (S-1-1-0): FILE_GENERIC_EXECUTE (@User.Title==\"PM\")");
+    }
+
+    #[test]
+    fn test_malformed() {
+        for sddl in [
+            "X:BA",
+            "O:not a sid",
+            "D:(A;;FA;;;BA",
+            "D:(Q;;FA;;;BA)",
+            "D:(A;ZZ;FA;;;BA)",
+            "D:(A;;FA;;BA)",
+            "D:(A;;FA;;;BA)D:(A;;FA;;;SY)",
+            "D:XYZ(A;;FA;;;BA)",
+        ] {
+            assert!(SecurityDescriptor::parse(sddl).is_err(), "{}", sddl);
+        }
+        let err = SecurityDescriptor::parse("D:(A;;FA;;;BA)junk").unwrap_err();
+        assert_eq!(err.offset, 14);
+    }
+
+    #[test]
+    fn test_describe_account() {
+        assert_eq!(describe_account("S-1-5-18"), "S-1-5-18 (SYSTEM)");
+        assert_eq!(describe_account("S-1-5-20"), "S-1-5-20 (NETWORK SERVICE)");
+        assert_eq!(describe_account("S-1-5-32-544"), "S-1-5-32-544 (BUILTIN\\Administrators)");
+        assert_eq!(
+            describe_account("S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478464"),
+            "S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478464 (NT SERVICE)"
+        );
+        assert_eq!(
+            describe_account("S-1-5-21-3623811015-3361044348-30300820-1013"),
+            "S-1-5-21-3623811015-3361044348-30300820-1013 (domain SID, resolve against the domain)"
+        );
+        assert_eq!(describe_account("CORP\\alice"), "CORP\\alice");
+        assert_eq!(describe_account("S-1-5-99"), "S-1-5-99");
+    }
+
+    #[test]
+    fn test_format_task_shows_aces() {
+        let xml = "<Task><RegistrationInfo><SecurityDescriptor>O:BAD:(A;;FA;;;SY)</SecurityDescriptor>\
+                   </RegistrationInfo><Triggers/><Settings/><Actions/></Task>";
+        let text = Task::from_xml(xml).unwrap().format_task();
+        assert!(text.contains(
+            "SecurityDescriptor:\n  Owner: BUILTIN\\Administrators (BA)\n  DACL:\n    Allow NT AUTHORITY\\SYSTEM (SY): FILE_ALL_ACCESS\n"
+        ));
+
+        let xml = xml.replace("(A;;FA;;;SY)", "(A;;FA");
+        let text = Task::from_xml(&xml).unwrap().format_task();
+        assert!(text.contains("SecurityDescriptor: O:BAD:(A;;FA (could not decode: invalid SDDL at offset 6: unterminated ACE)"));
+    }
+
+    #[test]
+    fn test_non_admin_writers() {
+        let writers = |sddl: &str| SecurityDescriptor::parse(sddl).unwrap().non_admin_writers();
+        // What System32\Tasks files inherit: read and execute for Users.
+        assert!(writers("D:AI(A;ID;FA;;;SY)(A;ID;FA;;;BA)(A;ID;0x1200a9;;;BU)").is_empty());
+        assert_eq!(writers("D:(A;;FA;;;SY)(A;;0x1301bf;;;BU)(A;;FW;;;S-1-1-0)"), ["BUILTIN\\Users", "Everyone"]);
+        assert_eq!(writers("D:(A;;WD;;;AU)(A;;GA;;;AU)"), ["Authenticated Users"]);
+        // Deny entries, entries for the children of a folder and entries for
+        // administrators do not count.
+        assert!(writers("D:(D;;FA;;;WD)(A;OICIIO;FA;;;BU)(A;;FA;;;BA)(A;;FA;;;S-1-5-21-1-2-3-500)").is_empty());
+        assert_eq!(writers("D:NO_ACCESS_CONTROL"), ["Everyone"]);
+        assert!(writers("O:BA").is_empty());
+    }
+}
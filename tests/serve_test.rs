@@ -0,0 +1,126 @@
+#![cfg(all(feature = "http", feature = "binary", feature = "xml"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpStream;
+    use std::process::{Child, Command, Stdio};
+    use std::thread;
+
+    /// Kills the server when a test finishes, pass or fail.
+    struct Server {
+        child: Child,
+        addr: String,
+    }
+
+    impl Drop for Server {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    fn start(extra: &[&str]) -> Server {
+        let mut child = Command::new(assert_cmd::cargo::cargo_bin("jobfileparser"))
+            .args(["serve", "127.0.0.1:0"])
+            .args(extra)
+            .env("XDG_CONFIG_HOME", "/nonexistent")
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stderr = BufReader::new(child.stderr.take().unwrap());
+        let mut line = String::new();
+        stderr.read_line(&mut line).unwrap();
+        let addr = line
+            .trim()
+            .strip_prefix("Listening on http://")
+            .unwrap_or_else(|| panic!("unexpected banner {:?}", line))
+            .to_string();
+        Server { child, addr }
+    }
+
+    /// Sends one request and returns the status code and body.
+    fn request(server: &Server, method: &str, path: &str, body: &[u8]) -> (u16, String) {
+        let mut stream = TcpStream::connect(&server.addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            method,
+            path,
+            server.addr,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+        (status, body)
+    }
+
+    #[test]
+    fn test_serve_parses_posted_artifacts() {
+        let server = start(&["--detect"]);
+
+        assert_eq!(request(&server, "GET", "/healthz", b""), (200, "ok\n".to_string()));
+
+        let (status, body) = request(&server, "POST", "/parse", &job_bytes("cmd.exe"));
+        assert_eq!(status, 200);
+        let record: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(record["parse_status"], "ok");
+        assert_eq!(record["name"], "cmd.exe");
+        assert_eq!(record["findings"][0]["rule"], "script-host");
+
+        // Task Scheduler writes UTF-16 with a byte-order mark.
+        let xml = "<Task><RegistrationInfo><Author>me</Author></RegistrationInfo>\
+                   <Triggers/><Settings/><Actions/></Task>";
+        let mut data = vec![0xFF, 0xFE];
+        data.extend(xml.encode_utf16().flat_map(u16::to_le_bytes));
+        let (status, body) = request(&server, "POST", "/parse", &data);
+        assert_eq!(status, 200, "{}", body);
+        let record: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(record["parse_status"], "ok");
+        assert_eq!(record["registration_info"]["author"], "me");
+
+        let (status, body) = request(&server, "POST", "/parse", &job_bytes("a.exe")[..60]);
+        assert_eq!(status, 422);
+        let record: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(record["error_kind"], "TruncatedJob");
+
+        assert_eq!(request(&server, "GET", "/parse", b"").0, 405);
+        assert_eq!(request(&server, "GET", "/nope", b"").0, 404);
+    }
+
+    #[test]
+    fn test_serve_caps_body_size() {
+        let server = start(&["--max-body-size", "64"]);
+        let (status, body) = request(&server, "POST", "/parse", &[0; 65]);
+        assert_eq!(status, 413);
+        assert!(body.contains("too large"));
+    }
+
+    #[test]
+    fn test_serve_handles_concurrent_requests() {
+        let server = start(&["--workers", "4"]);
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let server = &server;
+                    scope.spawn(move || {
+                        let name = format!("job{}.exe", i);
+                        let (status, body) = request(server, "POST", "/parse", &job_bytes(&name));
+                        assert_eq!(status, 200);
+                        assert!(body.contains(&name));
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        });
+    }
+}
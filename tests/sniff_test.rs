@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::sniff::{sniff, Confidence, DetectedFormat, SNIFF_LENGTH};
+
+    /// A fixed section of `len` bytes starting with `product` and
+    /// `file_version`.
+    fn job_head(product: u16, file_version: u16, len: usize) -> Vec<u8> {
+        let mut data = [product.to_le_bytes(), file_version.to_le_bytes()].concat();
+        data.resize(len, 0);
+        data
+    }
+
+    fn outcome(data: &[u8]) -> (DetectedFormat, Confidence) {
+        let sniffed = sniff(data, data.len() as u64);
+        (sniffed.detected_format, sniffed.confidence)
+    }
+
+    #[test]
+    fn test_job() {
+        let sniffed = sniff(&job_head(0x601, 1, 68), 500);
+        assert_eq!((sniffed.detected_format, sniffed.confidence), (DetectedFormat::Job, Confidence::High));
+        assert_eq!((sniffed.evidence.product_version, sniffed.evidence.file_version), (Some(0x601), Some(1)));
+        assert_eq!(sniffed.size, 500);
+        // Shorter than the fixed section.
+        assert_eq!(outcome(&job_head(0x601, 1, 20)), (DetectedFormat::Job, Confidence::Medium));
+        assert_eq!(outcome(&job_head(0x1234, 1, 68)), (DetectedFormat::Job, Confidence::Low));
+        assert_eq!(outcome(&job_head(0xa00, 7, 68)), (DetectedFormat::Job, Confidence::Low));
+    }
+
+    #[test]
+    fn test_unknown_binary() {
+        assert_eq!(outcome(b"MZ\x90\x00\x03\x00\x00\x00"), (DetectedFormat::Unknown, Confidence::Medium));
+        assert_eq!(outcome(b"MZ"), (DetectedFormat::Unknown, Confidence::Medium));
+        let empty = sniff(&[], 0);
+        assert_eq!((empty.detected_format, empty.confidence), (DetectedFormat::Unknown, Confidence::High));
+        assert_eq!(empty.evidence, Default::default());
+    }
+
+    #[test]
+    fn test_task_xml() {
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-16\"?>\n<!-- exported -->\n\
+                   <Task version=\"1.2\" xmlns=\"http://schemas.microsoft.com/windows/2004/02/mit/task\">";
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain(xml.encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        let sniffed = sniff(&utf16, 9000);
+        assert_eq!((sniffed.detected_format, sniffed.confidence), (DetectedFormat::TaskXml, Confidence::High));
+        assert_eq!(sniffed.evidence.bom, Some("utf-16le"));
+        assert_eq!(sniffed.evidence.root_element.as_deref(), Some("Task"));
+        assert_eq!(outcome(b"\xEF\xBB\xBF<task:Task/>"), (DetectedFormat::TaskXml, Confidence::High));
+        assert_eq!(outcome(b"  <Task>"), (DetectedFormat::TaskXml, Confidence::High));
+    }
+
+    #[test]
+    fn test_xml_that_is_not_a_task() {
+        let sniffed = sniff(b"<?xml version=\"1.0\"?><!DOCTYPE html><html><body/></html>", 60);
+        assert_eq!((sniffed.detected_format, sniffed.confidence), (DetectedFormat::Unknown, Confidence::High));
+        assert_eq!(sniffed.evidence.root_element.as_deref(), Some("html"));
+        // The root element past the part looked at.
+        let mut padded = format!("<!--{}-->", " ".repeat(SNIFF_LENGTH)).into_bytes();
+        padded.extend(b"<Task/>");
+        assert_eq!(outcome(&padded), (DetectedFormat::Unknown, Confidence::Low));
+        assert_eq!(outcome(b"<<<<"), (DetectedFormat::Unknown, Confidence::Low));
+    }
+}
@@ -0,0 +1,215 @@
+#![cfg(feature = "xml")]
+
+#[cfg(test)]
+mod tests {
+    use jobfileparser::Task;
+
+    fn task(command: &str) -> String {
+        format!(
+            "<Task><RegistrationInfo/><Triggers/><Settings/>\
+             <Actions><Exec><Command>{}</Command></Exec></Actions></Task>",
+            command
+        )
+    }
+
+    fn commands(data: &str) -> Vec<String> {
+        let list = Task::all_from_bytes(format!("\u{FEFF}{}", data).as_bytes()).unwrap();
+        list.tasks
+            .into_iter()
+            .map(|task| task.unwrap().actions.exec.unwrap().command)
+            .collect()
+    }
+
+    #[test]
+    fn test_concatenated_documents() {
+        let data = format!(
+            "<?xml version=\"1.0\"?>\n{}\n<?xml version=\"1.0\"?>\n{}\n",
+            task("a.exe"),
+            task("b.exe")
+        );
+        assert_eq!(commands(&data), ["a.exe", "b.exe"]);
+    }
+
+    #[test]
+    fn test_wrapped_tasks() {
+        let data = format!("<Tasks>\n  {}\n  {}\n</Tasks>", task("a.exe"), task("b.exe"));
+        assert_eq!(commands(&data), ["a.exe", "b.exe"]);
+        assert_eq!(commands(&task("single.exe")), ["single.exe"]);
+    }
+
+    #[test]
+    fn test_trailing_garbage_keeps_parsed_tasks() {
+        let data = format!("\u{FEFF}{}<Task><Actions></Oops>", task("a.exe"));
+        let list = Task::all_from_bytes(data.as_bytes()).unwrap();
+        assert_eq!(list.tasks.len(), 1);
+        assert!(list.tasks[0].is_ok());
+        assert!(list.trailing_error.is_some());
+
+        let data = format!("\u{FEFF}<Tasks>{}<Task><Settings/></Task></Tasks>", task("a.exe"));
+        let list = Task::all_from_bytes(data.as_bytes()).unwrap();
+        assert_eq!(list.tasks.len(), 2);
+        assert!(list.tasks[1].is_err());
+        assert!(list.trailing_error.is_none());
+
+        assert!(Task::all_from_bytes("\u{FEFF}<Task><Oops".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_declaration_selects_encoding_without_bom() {
+        let read = |data: &[u8]| Task::from_bytes(data).unwrap().actions.exec.unwrap().command;
+        let utf8 = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}", task("Größe.exe"));
+        assert_eq!(read(utf8.as_bytes()), "Größe.exe");
+        let latin1 = b"<?xml version='1.0' encoding='ISO-8859-1'?><Task><RegistrationInfo/>\
+                       <Triggers/><Settings/><Actions><Exec><Command>Gr\xF6\xDFe.exe</Command>\
+                       </Exec></Actions></Task>";
+        assert_eq!(read(latin1), "Größe.exe");
+        // No encoding, or one ASCII text cannot be in, is UTF-8.
+        let undeclared = format!("<?xml version=\"1.0\"?>{}", task("a.exe"));
+        assert_eq!(read(undeclared.as_bytes()), "a.exe");
+        let utf16 = format!("<?xml version=\"1.0\" encoding=\"UTF-16\"?>{}", task("a.exe"));
+        assert_eq!(read(utf16.as_bytes()), "a.exe");
+        // A byte order mark still wins.
+        let bom: Vec<u8> = "\u{FEFF}<?xml version=\"1.0\" encoding=\"UTF-8\"?>"
+            .encode_utf16()
+            .chain(task("a.exe").encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(read(&bom), "a.exe");
+    }
+
+    #[test]
+    fn test_unknown_elements_are_recorded() {
+        let task = Task::from_bytes(include_bytes!("fixtures/future_settings.xml")).unwrap();
+        assert_eq!(task.settings.allow_start_if_on_batteries, Some(false));
+        assert_eq!(task.actions.exec.unwrap().arguments.as_deref(), Some("--all"));
+
+        let names: Vec<&str> = task.unknown_elements.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            ["RegistrationInfo/URI", "Settings/EfficiencyClass", "Triggers/CalendarTrigger/ScheduleByDay"]
+        );
+        assert_eq!(task.unknown_elements["RegistrationInfo/URI"], "\\Maintenance\\Cleanup");
+        assert!(task.unknown_elements["Settings/EfficiencyClass"].contains("<PreferredCores>2</PreferredCores>"));
+    }
+
+    #[test]
+    fn test_repeated_unknown_elements_are_numbered() {
+        let task = Task::from_xml(
+            "<Task><RegistrationInfo/><Triggers><LogonTrigger/><LogonTrigger><Delay>PT1M</Delay></LogonTrigger></Triggers>\
+             <Settings/><Actions/></Task>",
+        )
+        .unwrap();
+        assert_eq!(task.unknown_elements["Triggers/LogonTrigger"], "");
+        assert_eq!(task.unknown_elements["Triggers/LogonTrigger[2]"], "<Delay>PT1M</Delay>");
+    }
+
+    #[test]
+    fn test_actions_and_triggers_are_counted() {
+        let task = Task::from_xml(
+            "<Task><RegistrationInfo/><Triggers><LogonTrigger/><CalendarTrigger><StartBoundary>2024-01-01T00:00:00</StartBoundary>\
+             <ScheduleByDay><DaysInterval>1</DaysInterval></ScheduleByDay></CalendarTrigger></Triggers><Settings/>\
+             <Actions Context=\"Author\"><Exec><Command>a.exe</Command></Exec><ComHandler><ClassId>{0}</ClassId></ComHandler>\
+             <Exec><Command>b.exe</Command><Arguments>/x</Arguments></Exec></Actions></Task>",
+        )
+        .unwrap();
+        // The first `Exec` is the model's; the second is kept whole.
+        assert_eq!(task.actions.exec.as_ref().unwrap().command, "a.exe");
+        assert_eq!(task.unknown_elements["Actions/Exec[2]"], "<Command>b.exe</Command><Arguments>/x</Arguments>");
+        assert_eq!(task.action_kinds(), ["Exec", "ComHandler", "Exec"]);
+        // `ScheduleByDay` belongs to the calendar trigger.
+        assert_eq!(task.trigger_count(), 2);
+
+        let empty = Task::from_xml("<Task><RegistrationInfo/><Triggers/><Settings/><Actions/></Task>").unwrap();
+        assert_eq!((empty.trigger_count(), empty.action_kinds().len()), (0, 0));
+    }
+
+    #[test]
+    fn test_indent_xml() {
+        use jobfileparser::task::indent_xml;
+
+        // UTF-16LE without a byte order mark, as Task Scheduler writes it.
+        let utf16: Vec<u8> = task("a.exe").encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(
+            indent_xml(&utf16).unwrap(),
+            "<Task>\n  <RegistrationInfo/>\n  <Triggers/>\n  <Settings/>\n  <Actions>\n    <Exec>\n      \
+             <Command>a.exe</Command>\n    </Exec>\n  </Actions>\n</Task>\n"
+        );
+        assert!(indent_xml("\u{FEFF}<Task><Actions></Task>".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_fragments() {
+        use jobfileparser::task::Section;
+        use jobfileparser::{DateStyle, Error};
+
+        // A bare trigger, as UTF-8 without a byte order mark.
+        let fragment = Task::fragment_from_bytes(
+            b"<CalendarTrigger><StartBoundary>2024-03-01T08:00:00</StartBoundary>\
+              <ScheduleByWeek><DaysOfWeek><Monday/></DaysOfWeek><WeeksInterval>1</WeeksInterval></ScheduleByWeek>\
+              </CalendarTrigger>",
+        )
+        .unwrap();
+        assert_eq!((fragment.root.as_str(), fragment.section), ("CalendarTrigger", Section::Triggers));
+        assert_eq!(fragment.task.triggers.calendar_trigger.as_ref().unwrap().start_boundary, "2024-03-01T08:00:00");
+        assert_eq!(
+            fragment.format_with(DateStyle::Classic),
+            "Fragment: CalendarTrigger\nStartBoundary: 2024-03-01T08:00:00\nEndBoundary: None\nEnabled: None\n\
+             Trigger 1: CalendarTrigger, weekly on Mon, from 2024-03-01T08:00:00\n"
+        );
+
+        // A trigger the model has no field for, with a declaration.
+        let fragment = Task::from_fragment(
+            "<?xml version=\"1.0\"?>\n<LogonTrigger><Enabled>false</Enabled><UserId>bob</UserId></LogonTrigger>",
+        )
+        .unwrap();
+        assert_eq!(fragment.task.trigger_details()[0].kind, "LogonTrigger");
+        assert_eq!(fragment.format_with(DateStyle::Classic), "Fragment: LogonTrigger\nTrigger 1: LogonTrigger, at logon, disabled\n");
+
+        let fragment = Task::from_fragment(
+            "<Triggers><BootTrigger/><TimeTrigger><StartBoundary>2024-03-01T08:00:00</StartBoundary></TimeTrigger></Triggers>",
+        )
+        .unwrap();
+        assert_eq!((fragment.root.as_str(), fragment.task.trigger_count()), ("Triggers", 2));
+
+        // Actions, whole and one at a time, in UTF-16LE.
+        let actions = "<Actions><Exec><Command>cmd.exe</Command><Arguments>/c x</Arguments></Exec>\
+                       <ComHandler><ClassId>{1}</ClassId></ComHandler></Actions>";
+        let utf16: Vec<u8> = actions.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let fragment = Task::fragment_from_bytes(&utf16).unwrap();
+        assert_eq!(fragment.section, Section::Actions);
+        assert_eq!(
+            fragment.format_with(DateStyle::Classic),
+            "Fragment: Actions\nCommand: cmd.exe\nArguments: Some(\"/c x\")\n\
+             Action 1: Exec cmd.exe /c x\nAction 2: ComHandler {1}\n"
+        );
+        let fragment = Task::from_fragment("<Exec><Command>a.exe</Command></Exec>").unwrap();
+        assert_eq!(fragment.task.actions.exec.unwrap().command, "a.exe");
+        let fragment = Task::from_fragment("<ShowMessage><Title>hi</Title></ShowMessage>").unwrap();
+        assert_eq!(fragment.task.action_kinds(), ["ShowMessage"]);
+
+        // Settings, with those the model has no field for.
+        let fragment = Task::from_fragment(
+            "<Settings xmlns=\"http://schemas.microsoft.com/windows/2004/02/mit/task\"><Hidden>true</Hidden>\
+             <IdleSettings><Duration>PT10M</Duration><StopOnIdleEnd>true</StopOnIdleEnd></IdleSettings>\
+             <ExecutionTimeLimit>PT0S</ExecutionTimeLimit></Settings>",
+        )
+        .unwrap();
+        assert_eq!((fragment.section, fragment.task.settings.hidden), (Section::Settings, Some(true)));
+        assert_eq!(
+            fragment.format_with(DateStyle::Classic),
+            "Fragment: Settings\nSettings:\n  Enabled: None\n  AllowStartIfOnBatteries: None\n  Hidden: true\n  \
+             ExecutionTimeLimit: PT0S\n  IdleSettings: Duration=PT10M, StopOnIdleEnd=true\n"
+        );
+        assert_eq!(fragment.task.schedule_warnings().len(), 1);
+
+        // Anything else is refused, naming what is read.
+        for xml in ["<Principals/>", "<Task/>", "", "<!-- nothing -->"] {
+            let error = Task::from_fragment(xml).unwrap_err();
+            assert!(matches!(error, Error::UnknownFragment(_)), "{}", xml);
+            assert_eq!(error.kind(), "UnknownFragment");
+            assert!(error.to_string().contains("expected Triggers, Actions, Settings"), "{}", error);
+        }
+        assert!(Task::from_fragment("<Principals/>").unwrap_err().to_string().starts_with("<Principals> is not"));
+        assert!(matches!(Task::from_fragment("<Exec><Command>a</Exec>"), Err(Error::Xml(_))));
+    }
+}
@@ -0,0 +1,400 @@
+#[cfg(test)]
+mod tests {
+    use jobfileparser::{Error, Warning, WarningCode};
+
+    #[cfg(any(feature = "binary", feature = "xml"))]
+    fn codes(warnings: &[Warning]) -> Vec<WarningCode> {
+        warnings.iter().map(|w| w.code).collect()
+    }
+
+    #[test]
+    fn test_recovered_errors_keep_field_and_offset() {
+        let error = Error::StringLengthOutOfBounds { field: "comment", declared: 9, available: 2 };
+        let warning = Warning::recovered(&error);
+        assert_eq!(warning.code, WarningCode::RecoveredError);
+        assert_eq!(warning.field.as_deref(), Some("comment"));
+        assert_eq!(warning.message, error.to_string());
+        let warning = Warning::recovered(&Error::TruncatedJob { offset: 70, len: 2 });
+        assert_eq!((warning.offset, warning.field), (Some(70), None));
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_implausible_version() {
+        use jobfileparser::consts::{fixed, product};
+        use jobfileparser::warning::artifact_warnings;
+        use jobfileparser::{JobBuilder, ParsedArtifact};
+
+        for (version, file_version, message) in [
+            (product::WINDOWS_10, 1, None),
+            (product::WINDOWS_NT_4_0, 1, None),
+            (product::WINDOWS_10, 2, Some("product version 0x0a00 (Windows 10) with file version 2")),
+            (product::WINDOWS_7, 0, Some("product version 0x0601 (Windows 7) with file version 0")),
+            (0x0000, 1, Some("product version 0x0000 (unknown) with file version 1")),
+            (0xFFFF, 1, Some("product version 0xffff (unknown) with file version 1")),
+            (0xFFFF, 0xFFFF, Some("product version 0xffff (unknown) with file version 65535")),
+        ] {
+            assert_eq!(product::is_plausible(version, file_version), message.is_none());
+            let mut job = JobBuilder::new("a.exe").product_info(version).build().unwrap();
+            job.file_version = file_version;
+            // The text report shows both words whatever they are.
+            let text = job.format_job();
+            let name = product::name(version).map_or(format!("Unknown Version ({:#06x})", version), str::to_string);
+            assert!(text.contains(&format!("Product Info: {}\nFile Version: {}\n", name, file_version)), "{}", text);
+            let warnings = artifact_warnings(&ParsedArtifact::Job(job));
+            match message {
+                None => assert!(warnings.is_empty(), "{:#x} {}", version, file_version),
+                Some(message) => {
+                    assert_eq!(codes(&warnings), [WarningCode::ImplausibleVersion]);
+                    assert_eq!(warnings[0].message, format!("{} is not a pair Task Scheduler writes", message));
+                    assert_eq!(warnings[0].offset, Some(fixed::PRODUCT_VERSION.offset));
+                    assert_eq!(warnings[0].field.as_deref(), Some("product_info"));
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_job_warnings() {
+        use jobfileparser::consts::{fixed, flags};
+        use jobfileparser::warning::artifact_warnings;
+        use jobfileparser::{JobBuilder, JobDate, ParsedArtifact};
+
+        let job = |builder: JobBuilder| artifact_warnings(&ParsedArtifact::Job(builder.build().unwrap()));
+        assert!(job(JobBuilder::new("a.exe").flags(flags::HIDDEN | flags::DISABLED)).is_empty());
+
+        let warnings = job(JobBuilder::new("a.exe").flags(flags::HIDDEN | 0x8 | 0x4000));
+        assert_eq!(codes(&warnings), [WarningCode::UnknownFlagBits]);
+        assert_eq!(warnings[0].message, "unknown flag bits 0x4008");
+        assert_eq!(warnings[0].offset, Some(fixed::FLAGS.offset));
+
+        let mut month_13 = [0; 16];
+        month_13[..4].copy_from_slice(&[0xE8, 0x07, 13, 0]);
+        let warnings = job(JobBuilder::new("a.exe").run_date(JobDate::new(&month_13, false)));
+        assert_eq!(codes(&warnings), [WarningCode::InvalidDate]);
+        assert_eq!(warnings[0].field.as_deref(), Some("last run time"));
+        assert_eq!(warnings[0].offset, Some(fixed::LAST_RUN_TIME.offset));
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_weekday_mismatch() {
+        use jobfileparser::consts::{fixed, systemtime};
+        use jobfileparser::warning::artifact_warnings;
+        use jobfileparser::{JobBuilder, JobDate, ParsedArtifact};
+
+        let ran = |year: u16, month: u16, day: u16, weekday: u16| {
+            let run_date = JobDate { year, month, weekday: Some(weekday), day, hour: 2, minute: 13, second: 0 };
+            JobBuilder::new("a.exe").run_date(run_date).build().unwrap()
+        };
+
+        // 4 March 2024 was a Monday, and 29 February a Thursday.
+        for job in [ran(2024, 3, 4, 1), ran(2024, 2, 29, 4)] {
+            assert_eq!(job.run_date.weekday_mismatch(), None);
+            assert!(artifact_warnings(&ParsedArtifact::Job(job)).is_empty());
+        }
+        assert!(artifact_warnings(&ParsedArtifact::Job(JobBuilder::new("a.exe").build().unwrap())).is_empty());
+
+        let job = ran(2024, 3, 4, 5);
+        assert_eq!(job.run_date.calendar_weekday(), Some(1));
+        assert_eq!(job.run_date.weekday_mismatch(), Some((5, 1)));
+        // What the writing system stored is what is shown.
+        assert_eq!(job.run_date.format_date(), "Friday Mar 4 02:13:00 2024");
+        let warnings = artifact_warnings(&ParsedArtifact::Job(job));
+        assert_eq!(codes(&warnings), [WarningCode::WeekdayMismatch]);
+        assert_eq!(warnings[0].message, "last run time 2024-03-04T02:13:00 was a Monday, but its weekday field says Friday");
+        assert_eq!(warnings[0].offset, Some(fixed::LAST_RUN_TIME.offset + systemtime::DAY_OF_WEEK.offset));
+        assert_eq!(warnings[0].field.as_deref(), Some("last run time"));
+        let warnings = artifact_warnings(&ParsedArtifact::Job(ran(2024, 3, 4, 9)));
+        assert!(warnings[0].message.ends_with("its weekday field says day 9"), "{}", warnings[0].message);
+
+        // A date that does not exist has no weekday to compare.
+        for job in [ran(2024, 2, 30, 5), ran(2023, 2, 29, 3), ran(2024, 4, 31, 0)] {
+            assert_eq!(job.run_date.calendar_weekday(), None);
+            assert_eq!(job.run_date.weekday_mismatch(), None);
+            let warnings = artifact_warnings(&ParsedArtifact::Job(job));
+            assert_eq!(codes(&warnings), [WarningCode::InvalidDate]);
+            assert!(warnings[0].message.starts_with("invalid last run time "), "{}", warnings[0].message);
+        }
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_task_warnings() {
+        use jobfileparser::warning::artifact_warnings;
+        use jobfileparser::{ParsedArtifact, Task};
+
+        let task = |registration: &str, settings: &str| {
+            let xml = format!(
+                "\u{FEFF}<Task><RegistrationInfo>{}</RegistrationInfo><Triggers/><Settings>{}</Settings>\
+                 <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+                registration, settings
+            );
+            artifact_warnings(&ParsedArtifact::Task(Task::from_bytes(xml.as_bytes()).unwrap()))
+        };
+        assert!(task("<Date>2024-03-01T02:13:00.1234567+01:00</Date>", "").is_empty());
+
+        let warnings = task("<Date>2024-13-01T00:00:00</Date>", "<WakeToRun>true</WakeToRun>");
+        assert_eq!(codes(&warnings), [WarningCode::InvalidDate, WarningCode::UnknownElement]);
+        assert_eq!(warnings[0].field.as_deref(), Some("RegistrationInfo/Date"));
+        assert_eq!(warnings[1].field.as_deref(), Some("Settings/WakeToRun"));
+        assert_eq!(warnings[1].message, "unrecognized element Settings/WakeToRun");
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_repeated_calendar_triggers() {
+        use jobfileparser::warning::artifact_warnings;
+        use jobfileparser::{ParsedArtifact, Task};
+
+        let daily = |enabled: &str, days: u32| {
+            format!(
+                "<CalendarTrigger>\n  <StartBoundary>2024-01-01T09:00:00</StartBoundary>{}\n  \
+                 <ScheduleByDay>\n    <DaysInterval>{}</DaysInterval>\n  </ScheduleByDay>\n</CalendarTrigger>",
+                enabled, days
+            )
+        };
+        let warnings = |triggers: &[String]| {
+            let xml = format!(
+                "\u{FEFF}<Task><RegistrationInfo/><Triggers>{}</Triggers><Settings/>\
+                 <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+                triggers.concat()
+            );
+            let task = Task::from_bytes(xml.as_bytes()).unwrap();
+            let warnings = artifact_warnings(&ParsedArtifact::Task(task));
+            warnings.into_iter().filter(|w| w.code != WarningCode::UnknownElement).collect::<Vec<_>>()
+        };
+        let on = "<Enabled>true</Enabled>";
+        let off = "<Enabled>false</Enabled>";
+        let logon = "<LogonTrigger><Enabled>false</Enabled></LogonTrigger>".to_string();
+
+        // Whitespace and a default Enabled written out make no difference.
+        let found = warnings(&[daily("", 1), logon.clone(), daily(on, 1).replace("\n  ", "")]);
+        assert_eq!(codes(&found), [WarningCode::DuplicateTrigger]);
+        assert_eq!(found[0].message, "Triggers/CalendarTrigger and Triggers/CalendarTrigger[2] are identical");
+        assert_eq!(found[0].field.as_deref(), Some("Triggers/CalendarTrigger[2]"));
+
+        let found = warnings(&[daily(off, 1), daily(on, 1), daily("", 1)]);
+        assert_eq!(codes(&found), [WarningCode::ConflictingTrigger, WarningCode::ConflictingTrigger]);
+        assert_eq!(
+            found[0].message,
+            "Triggers/CalendarTrigger[2] is enabled and Triggers/CalendarTrigger, on the same schedule, is disabled"
+        );
+
+        assert!(warnings(&[daily("", 1), daily("", 2), logon.clone()]).is_empty());
+        assert_eq!(codes(&warnings(&[logon.clone(), logon.clone()])), [WarningCode::DuplicateTrigger]);
+        assert_eq!(
+            codes(&warnings(&[logon.clone(), logon.replace("false", "true")])),
+            [WarningCode::ConflictingTrigger]
+        );
+    }
+
+    /// The warnings of a task with `triggers` and `settings`, but for
+    /// those about unknown elements.
+    #[cfg(feature = "xml")]
+    fn schedule_warnings(triggers: &str, settings: &str) -> Vec<Warning> {
+        use jobfileparser::warning::artifact_warnings;
+        use jobfileparser::{ParsedArtifact, Task};
+
+        let xml = format!(
+            "\u{FEFF}<Task><RegistrationInfo/><Triggers>{}</Triggers><Settings>{}</Settings>\
+             <Actions><Exec><Command>a.exe</Command></Exec></Actions></Task>",
+            triggers, settings
+        );
+        let task = Task::from_bytes(xml.as_bytes()).unwrap();
+        let warnings = artifact_warnings(&ParsedArtifact::Task(task));
+        warnings.into_iter().filter(|w| w.code != WarningCode::UnknownElement).collect()
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_end_before_start() {
+        let calendar = |start: &str, end: &str| {
+            format!(
+                "<CalendarTrigger><StartBoundary>{}</StartBoundary><EndBoundary>{}</EndBoundary>\
+                 <ScheduleByDay><DaysInterval>1</DaysInterval></ScheduleByDay></CalendarTrigger>",
+                start, end
+            )
+        };
+        let found = schedule_warnings(&calendar("2024-03-01T09:00:00", "2024-02-01T09:00:00"), "");
+        assert_eq!(codes(&found), [WarningCode::EndBeforeStart]);
+        assert_eq!(
+            found[0].message,
+            "Triggers/CalendarTrigger ends at 2024-02-01T09:00:00 before it starts at 2024-03-01T09:00:00"
+        );
+        assert_eq!(found[0].field.as_deref(), Some("Triggers/CalendarTrigger/EndBoundary"));
+
+        let time = "<TimeTrigger><StartBoundary>2024-01-01T09:00:00Z</StartBoundary>\
+                    <EndBoundary>2024-01-01T10:00:00+02:00</EndBoundary></TimeTrigger>";
+        let found = schedule_warnings(time, "");
+        assert_eq!(codes(&found), [WarningCode::EndBeforeStart]);
+        assert_eq!(found[0].field.as_deref(), Some("Triggers/TimeTrigger/EndBoundary"));
+
+        assert!(schedule_warnings(&calendar("2024-03-01T09:00:00", "2024-03-01T09:00:00"), "").is_empty());
+        assert!(schedule_warnings(&calendar("2024-03-01T09:00:00", "2025-01-01T00:00:00"), "").is_empty());
+        // Only one of them names a zone, so they cannot be compared.
+        assert!(schedule_warnings(&calendar("2024-03-01T09:00:00Z", "2024-02-01T09:00:00"), "").is_empty());
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_empty_schedule_lists() {
+        let calendar = |schedule: &str| {
+            format!(
+                "<CalendarTrigger><StartBoundary>2024-01-01T09:00:00</StartBoundary>{}</CalendarTrigger>",
+                schedule
+            )
+        };
+        let by_month = "<ScheduleByMonth><DaysOfMonth><Day>1</Day></DaysOfMonth><Months/></ScheduleByMonth>";
+        let found = schedule_warnings(&calendar(by_month), "");
+        assert_eq!(codes(&found), [WarningCode::EmptyScheduleList]);
+        assert_eq!(found[0].message, "Triggers/CalendarTrigger/ScheduleByMonth/Months is empty");
+        assert_eq!(found[0].field.as_deref(), Some("Triggers/CalendarTrigger/ScheduleByMonth/Months"));
+
+        let by_week = "<ScheduleByWeek><WeeksInterval>1</WeeksInterval><DaysOfWeek>\n  </DaysOfWeek></ScheduleByWeek>";
+        let found = schedule_warnings(&calendar(by_week), "");
+        assert_eq!(found[0].field.as_deref(), Some("Triggers/CalendarTrigger/ScheduleByWeek/DaysOfWeek"));
+
+        // Below a second calendar trigger, kept whole, too.
+        let by_weekday = "<ScheduleByMonthDayOfWeek><Weeks></Weeks><DaysOfWeek><Monday/></DaysOfWeek>\
+                          <Months></Months></ScheduleByMonthDayOfWeek>";
+        let found = schedule_warnings(&(calendar("") + &calendar(by_weekday)), "");
+        let fields: Vec<_> = found.iter().map(|w| w.field.as_deref().unwrap()).collect();
+        assert_eq!(
+            fields,
+            [
+                "Triggers/CalendarTrigger[2]/ScheduleByMonthDayOfWeek/Weeks",
+                "Triggers/CalendarTrigger[2]/ScheduleByMonthDayOfWeek/Months",
+            ]
+        );
+
+        let full = "<ScheduleByMonth><DaysOfMonth><Day>1</Day></DaysOfMonth><Months><March/></Months></ScheduleByMonth>";
+        assert!(schedule_warnings(&calendar(full), "").is_empty());
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_repetition_exceeds_duration() {
+        let repeating = |interval: &str, duration: &str| {
+            format!(
+                "<TimeTrigger><StartBoundary>2024-01-01T09:00:00</StartBoundary><Repetition>\
+                 <Interval>{}</Interval><Duration>{}</Duration></Repetition></TimeTrigger>",
+                interval, duration
+            )
+        };
+        let found = schedule_warnings(&repeating("PT2H", "PT30M"), "");
+        assert_eq!(codes(&found), [WarningCode::RepetitionExceedsDuration]);
+        assert_eq!(found[0].message, "Triggers/TimeTrigger repeats every PT2H, longer than the PT30M the repetition lasts");
+        assert_eq!(found[0].field.as_deref(), Some("Triggers/TimeTrigger/Repetition/Interval"));
+
+        let calendar = "<CalendarTrigger><StartBoundary>2024-01-01T09:00:00</StartBoundary>\
+                        <Repetition><Interval>P1D</Interval><Duration>PT12H</Duration></Repetition></CalendarTrigger>";
+        let found = schedule_warnings(calendar, "");
+        assert_eq!(found[0].field.as_deref(), Some("Triggers/CalendarTrigger/Repetition/Interval"));
+
+        assert!(schedule_warnings(&repeating("PT1H", "PT1H"), "").is_empty());
+        assert!(schedule_warnings(&repeating("PT5M", "P1D"), "").is_empty());
+        // Without a duration, the repetition goes on indefinitely.
+        let indefinitely = "<TimeTrigger><Repetition><Interval>PT5M</Interval></Repetition></TimeTrigger>";
+        assert!(schedule_warnings(indefinitely, "").is_empty());
+    }
+
+    #[cfg(feature = "xml")]
+    #[test]
+    fn test_no_time_limit() {
+        let found = schedule_warnings("", "<ExecutionTimeLimit>PT0S</ExecutionTimeLimit>");
+        assert_eq!(codes(&found), [WarningCode::NoTimeLimit]);
+        assert_eq!(found[0].message, "Settings/ExecutionTimeLimit of PT0S turns the limit off");
+        assert_eq!(found[0].field.as_deref(), Some("Settings/ExecutionTimeLimit"));
+
+        let trigger = "<BootTrigger><ExecutionTimeLimit>P0D</ExecutionTimeLimit></BootTrigger>";
+        let found = schedule_warnings(trigger, "<ExecutionTimeLimit>PT72H</ExecutionTimeLimit>");
+        assert_eq!(codes(&found), [WarningCode::NoTimeLimit]);
+        assert_eq!(found[0].field.as_deref(), Some("Triggers/BootTrigger/ExecutionTimeLimit"));
+
+        assert!(schedule_warnings("", "<ExecutionTimeLimit>PT1S</ExecutionTimeLimit>").is_empty());
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_repeated_job_triggers() {
+        use jobfileparser::consts::{fixed, trigger};
+        use jobfileparser::{JobBuilder, ParsedArtifact, Record};
+        use std::path::Path;
+
+        /// A daily trigger at `hour`:00, disabled or not.
+        fn daily(hour: u16, disabled: bool) -> Vec<u8> {
+            let mut data = vec![0; trigger::LENGTH];
+            let mut put = |field: jobfileparser::consts::Field, value: u32| {
+                data[field.offset..field.offset + field.size].copy_from_slice(&value.to_le_bytes()[..field.size]);
+            };
+            put(trigger::TRIGGER_SIZE, trigger::LENGTH as u32);
+            put(trigger::BEGIN_YEAR, 2024);
+            put(trigger::BEGIN_MONTH, 1);
+            put(trigger::BEGIN_DAY, 1);
+            put(trigger::START_HOUR, u32::from(hour));
+            put(trigger::FLAGS, if disabled { trigger::FLAG_DISABLED } else { 0 });
+            put(trigger::TRIGGER_TYPE, trigger::TriggerType::Daily as u32);
+            put(trigger::TRIGGER_SPECIFIC0, 1);
+            data
+        }
+        let warnings = |triggers: &[Vec<u8>]| {
+            let job = JobBuilder::new("a.exe").build().unwrap();
+            let mut data = job.to_bytes();
+            let count_at = u16::from_le_bytes([data[fixed::TRIGGER_OFFSET.offset], data[fixed::TRIGGER_OFFSET.offset + 1]]) as usize;
+            data[count_at..count_at + 2].copy_from_slice(&(triggers.len() as u16).to_le_bytes());
+            data.extend(triggers.concat());
+            let mut record = Record::new(Path::new("a.job"), Ok(ParsedArtifact::Job(job)));
+            record.read_job_triggers(&data);
+            assert_eq!(record.trigger_count(), Some(triggers.len()));
+            (record.warnings().to_vec(), count_at + 2)
+        };
+
+        let (found, start) = warnings(&[daily(9, false), daily(12, false), daily(9, false)]);
+        assert_eq!(codes(&found), [WarningCode::DuplicateTrigger]);
+        assert_eq!(found[0].message, "trigger 1 and trigger 3 are identical");
+        assert_eq!(found[0].offset, Some(start + 2 * trigger::LENGTH));
+
+        let (found, _) = warnings(&[daily(9, true), daily(9, false)]);
+        assert_eq!(codes(&found), [WarningCode::ConflictingTrigger]);
+        assert_eq!(found[0].message, "trigger 2 is enabled and trigger 1, on the same schedule, is disabled");
+
+        assert!(warnings(&[daily(9, false), daily(10, false)]).0.is_empty());
+        // A trigger that does not give its size is not read, nor those after it.
+        let mut unsized_trigger = daily(9, false);
+        unsized_trigger[0] = 0;
+        assert!(warnings(&[daily(9, false), unsized_trigger, daily(9, false)]).0.is_empty());
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_low_coverage() {
+        use jobfileparser::{JobBuilder, ParsedArtifact, Record};
+        use std::path::Path;
+
+        let job = JobBuilder::new("a.exe").comment("note").build().unwrap();
+        let data = job.to_bytes();
+        let read = |data: &[u8]| {
+            let mut record = Record::new(Path::new("a.job"), Ok(ParsedArtifact::Job(job.clone())));
+            record.read_coverage(data, 90.0);
+            record
+        };
+        let record = read(&data);
+        assert_eq!(record.coverage(), Some(100.0));
+        assert!(record.warnings().is_empty());
+
+        let mut padded = data.clone();
+        padded.extend([0xCC; 1024]);
+        let record = read(&padded);
+        let coverage = record.coverage().unwrap();
+        assert!(coverage < 50.0, "{}", coverage);
+        assert_eq!(codes(record.warnings()), [WarningCode::LowCoverage]);
+        assert_eq!(record.warnings()[0].offset, Some(data.len()));
+        assert!(record.warnings()[0].message.ends_with(&format!("1024 of its {} bytes do not", padded.len())));
+
+        let mut failed = Record::new(Path::new("b.job"), Err(Error::TruncatedJob { offset: 0, len: 2 }));
+        failed.read_coverage(&[0, 0], 90.0);
+        assert_eq!(failed.coverage(), None);
+    }
+}
@@ -0,0 +1,81 @@
+#![cfg(all(unix, feature = "watch", feature = "binary", feature = "xml"))]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::common::job_bytes;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Read};
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn test_watch_reports_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.job"), job_bytes("a.exe")).unwrap();
+
+        let mut child = Command::new(assert_cmd::cargo::cargo_bin("jobfileparser"))
+            .args(["scan", dir.path().to_str().unwrap(), "--watch", "-o", "jsonl"])
+            .env("XDG_CONFIG_HOME", "/nonexistent")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let stdout = child.stdout.take().unwrap();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let record: serde_json::Value = serde_json::from_str(&line.unwrap()).unwrap();
+                if tx.send(record).is_err() {
+                    break;
+                }
+            }
+        });
+        let next = || rx.recv_timeout(TIMEOUT).expect("no watch record in time");
+
+        let record = next();
+        assert_eq!(record["event"], "initial");
+        assert_eq!(record["name"], "a.exe");
+
+        // Several quick writes collapse into one record.
+        let b = dir.path().join("b.job");
+        for _ in 0..3 {
+            fs::write(&b, job_bytes("b.exe")).unwrap();
+        }
+        let record = next();
+        assert_eq!(record["event"], "created");
+        assert_eq!(record["name"], "b.exe");
+
+        thread::sleep(Duration::from_millis(100));
+        fs::write(&b, job_bytes("c.exe")).unwrap();
+        let record = next();
+        assert_eq!(record["event"], "modified");
+        assert_eq!(record["name"], "c.exe");
+
+        // Files the scan would skip are not reported.
+        fs::write(dir.path().join("notes.txt"), "x").unwrap();
+        fs::remove_file(&b).unwrap();
+        let record = next();
+        assert_eq!(record["event"], "removed");
+        assert_eq!(record["path"], b.to_str().unwrap());
+        assert!(record.get("parse_status").is_none());
+
+        let status = Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let code = child.wait().unwrap();
+        assert_eq!(code.code(), Some(0));
+        let mut stderr = String::new();
+        child.stderr.take().unwrap().read_to_string(&mut stderr).unwrap();
+        assert!(stderr.contains("1 created, 1 modified, 1 removed"), "{}", stderr);
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+}
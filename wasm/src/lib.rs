@@ -0,0 +1,28 @@
+//! WebAssembly exports for parsing artifacts in the browser. Build with
+//! `wasm-pack build wasm --target web`; see `examples/web` for a page that
+//! uses the package.
+
+use jobfileparser::{Job, ParsedArtifact, Task};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+fn to_js(artifact: &ParsedArtifact) -> Result<JsValue, JsError> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    artifact.serialize(&serializer).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Parses the bytes of a binary `.job` file into the same structure the
+/// JSON serializers produce.
+#[wasm_bindgen]
+pub fn parse_job_bytes(data: &[u8]) -> Result<JsValue, JsError> {
+    let job = Job::parse(data).map_err(|e| JsError::new(&e.to_string()))?;
+    to_js(&ParsedArtifact::Job(job))
+}
+
+/// Parses a task XML document into the same structure the JSON serializers
+/// produce.
+#[wasm_bindgen]
+pub fn parse_task_xml(xml: String) -> Result<JsValue, JsError> {
+    let task = Task::from_xml(&xml).map_err(|e| JsError::new(&e.to_string()))?;
+    to_js(&ParsedArtifact::Task(task))
+}
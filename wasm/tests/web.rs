@@ -0,0 +1,43 @@
+//! Run with `wasm-pack test --node wasm` (or `cargo test --target
+//! wasm32-unknown-unknown` with wasm-bindgen-test-runner configured).
+#![cfg(target_arch = "wasm32")]
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+use common::job_bytes;
+use jobfileparser_wasm::{parse_job_bytes, parse_task_xml};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn get(value: &JsValue, key: &str) -> JsValue {
+    js_sys::Reflect::get(value, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_parse_job_bytes() {
+    let job = parse_job_bytes(&job_bytes("a.exe")).unwrap();
+    assert_eq!(get(&job, "format").as_string().unwrap(), "job");
+    assert_eq!(get(&job, "name").as_string().unwrap(), "a.exe");
+    assert_eq!(get(&job, "run_date").as_string().unwrap(), "2024-03-04T02:13:00");
+    assert_eq!(get(&job, "status").as_f64().unwrap(), 0x41300 as f64);
+}
+
+#[wasm_bindgen_test]
+fn test_parse_task_xml() {
+    let xml = "<Task>\
+        <RegistrationInfo><Author>Test Author</Author></RegistrationInfo>\
+        <Triggers/>\
+        <Settings><Enabled>true</Enabled></Settings>\
+        <Actions><Exec><Command>notepad.exe</Command></Exec></Actions>\
+        </Task>";
+    let task = parse_task_xml(xml.to_string()).unwrap();
+    assert_eq!(get(&task, "format").as_string().unwrap(), "task");
+    let exec = get(&get(&task, "actions"), "exec");
+    assert_eq!(get(&exec, "command").as_string().unwrap(), "notepad.exe");
+}
+
+#[wasm_bindgen_test]
+fn test_parse_task_xml_error() {
+    assert!(parse_task_xml("<NotATask/>".to_string()).is_err());
+}